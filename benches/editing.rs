@@ -5,6 +5,7 @@ use criterion::measurement::WallTime;
 use criterion::{
     criterion_group,
     criterion_main,
+    BatchSize,
     Bencher,
     BenchmarkGroup,
     Criterion,
@@ -149,6 +150,32 @@ fn delete_char_with_clone_around(c: &mut Criterion) {
     });
 }
 
+/// Deletes a range spanning a growing percentage of a large `Rope`, whose
+/// nodes are shared with the original so that dropping the deleted ones isn't
+/// measured. The subtrees fully covered by the range are detached without
+/// being visited, so the time should stay about the same for every size.
+fn delete_percentage(c: &mut Criterion) {
+    let mut group = c.benchmark_group("delete_percentage");
+
+    let orig = Rope::from(LARGE);
+
+    for percent in [1, 10, 50, 90] {
+        let len = orig.byte_len() * percent / 100;
+        let start = (orig.byte_len() - len) / 2;
+
+        group.bench_function(format!("{percent}%"), |bench| {
+            bench.iter_batched(
+                || orig.clone(),
+                |mut r| {
+                    r.delete(start..start + len);
+                    r
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
 fn bench_replace(group: &mut BenchmarkGroup<WallTime>, replace: &str) {
     #[inline(always)]
     fn bench(bench: &mut Bencher, s: &str, replace: &str) {
@@ -198,6 +225,7 @@ criterion_group!(
     delete_sentence,
     delete_large,
     delete_char_with_clone_around,
+    delete_percentage,
     replace_char,
     replace_sentence,
     replace_large,
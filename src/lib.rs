@@ -7,15 +7,75 @@
 //! complexity of inserting, deleting or replacing a piece of text is always
 //! logarithmic in the size of the `Rope`.
 //!
-//! The crate has a relatively straightforward API. There are 3 structs to be
-//! aware of:
+//! The core of the API is made of 3 structs:
 //!
 //! - [`Rope`]: the star of the crate;
 //! - [`RopeSlice`]: an immutable slice of a `Rope`;
 //! - [`RopeBuilder`]: an incremental `Rope` builder.
 //!
 //! plus the [`iter`] module which contains iterators over `Rope`s and
-//! `RopeSlice`s. That's it.
+//! `RopeSlice`s. Most applications won't need anything else, but the crate
+//! also provides a number of building blocks for editors and other
+//! text-heavy programs, which are grouped below.
+//!
+//! ### Editing
+//!
+//! - [`EditSession`], [`RopeCursor`] and [`IncrementalEdit`]: a burst of
+//!   edits at a single position, a cursor caching its position in the tree,
+//!   and a replacement carried out in bounded steps;
+//! - [`transaction`]: a [`Transaction`](transaction::Transaction) built from
+//!   retain, delete and insert operations;
+//! - [`Delta`], [`EditInfo`] and [`EditRegion`]: descriptions of the edits
+//!   performed on a `Rope`;
+//! - [`Limit`]: a cap on the length of a `Rope`, which turns it into a ring
+//!   buffer.
+//!
+//! ### Tracking changes
+//!
+//! - [`history`]: an undo/redo [`History`](history::History) with branches
+//!   and named checkpoints;
+//! - [`op_log`]: a [`Replica`](op_log::Replica) recording every edit as an
+//!   operation that can be applied on other replicas;
+//! - [`Snapshot`]: a frozen copy of a `Rope` that can be diffed with it;
+//! - [`delta_sync`]: content-defined signatures used to sync a `Rope` with
+//!   a remote copy.
+//!
+//! ### Positions and layers
+//!
+//! - [`marks`], [`selections`] and [`interval_tree`]: offsets, selections
+//!   and ranges that follow the edits of a `Rope`;
+//! - [`annotated`]: an [`AnnotatedRope`](annotated::AnnotatedRope) pairing a
+//!   `Rope` with spans of annotations;
+//! - `LineCache`, `line_data` and `filtered_lines` (with the `line-metric`
+//!   feature): a cache for repeated line conversions, per-line metadata and a
+//!   view over the lines matching a predicate;
+//! - [`record_index`]: records delimited by a custom separator.
+//!
+//! ### Views
+//!
+//! - [`RopeRef`]: a read-only view over a borrowed `&str`;
+//! - [`multi_rope`], [`edit_overlay`] and [`folded_view`]: a concatenation of
+//!   several `Rope`s, pending edits layered on top of a `Rope`, and a `Rope`
+//!   with some of its ranges folded away.
+//!
+//! ### Memory and storage
+//!
+//! - [`FrozenRope`], [`ChunkInterner`] and [`MemStats`]: a packed immutable
+//!   `Rope`, chunks shared between `Rope`s with the same text, and a
+//!   breakdown of the memory used by a `Rope`;
+//! - `RopeLoader` and `store` (with the `std` feature): a loader reading a
+//!   `Rope` in bounded steps, and an on-disk store sharing the unchanged
+//!   subtrees of the saved `Rope`s;
+//! - `paging`, `chunk_pool`, `chunked`, `editing_trace` and `ArchivedRope`:
+//!   see the feature flags below;
+//! - [`Bom`], [`LineEnding`] and [`TooLongError`]: the byte order marks,
+//!   the line endings and the length limit of the loaded text.
+//!
+//! ### Building your own
+//!
+//! - [`tree`]: the B-tree backing `Rope`s, generic over its leaves;
+//! - [`sum_rope`]: a [`SumRope`](sum_rope::SumRope) of arbitrary items built
+//!   on it.
 //!
 //! # Example usage
 //!
@@ -87,7 +147,7 @@
 //!         BufWriter::new(File::create("my_little_poem.txt").unwrap());
 //!
 //!     // The text content is stored in the leaves of the B-tree, where each
-//!     // chunk can store up to 1KB of data.
+//!     // chunk can store up to 2 KiB of data by default.
 //!     //
 //!     // We can iterate over the leaves using the `Chunks` iterator which
 //!     // yields the chunks of the `Rope` as string slices.
@@ -123,21 +183,176 @@
 //! to use a byte offset of 2. The maximum value for an offset is **equal to**
 //! the length of the string.
 //!
+//! # Tuning the node sizes
+//!
+//! By default every leaf of a `Rope`'s B-tree stores up to 2 KiB of text, and
+//! every internal node has up to 16 children, which works well for most
+//! documents. Workloads at either extreme can change both with the last two
+//! generic parameters of [`Rope`], `ARITY` and `MAX_BYTES`:
+//!
+//! - `MAX_BYTES` is the maximum number of bytes stored in a leaf, between 4
+//!   and 65535. Smaller chunks make edits cheaper and waste less memory on
+//!   many tiny documents, while larger ones make building and iterating over
+//!   very large texts faster;
+//!
+//! - `ARITY` is the maximum number of children of an internal node, at least
+//!   4. A larger fanout gives shallower trees.
+//!
+//! Values out of range fail the build. The usual constructors, like
+//! [`Rope::new()`] or `From<&str>`, only build `Rope`s with the default
+//! sizes, so `Rope`s with other sizes are created with
+//! [`Rope::from_text()`], [`Rope::empty()`] or [`RopeBuilder::empty()`]. For
+//! example, a project storing append-only logs could use
+//!
+//! ```
+//! # use crop::{tree::AtomicCount, Rope, RopeBuilder};
+//! #
+//! type LogRope = Rope<AtomicCount, 32, 16384>;
+//!
+//! let mut builder = RopeBuilder::<AtomicCount, 32, 16384>::empty();
+//! builder.append("GET /index.html 200\n");
+//!
+//! let mut log: LogRope = builder.build();
+//! log.insert(log.byte_len(), "GET /favicon.ico 404\n");
+//!
+//! assert_eq!(log.line(1), "GET /favicon.ico 404");
+//! ```
+//!
+//! # WebAssembly
+//!
+//! crop builds on `wasm32-unknown-unknown` and the WASI targets with its
+//! default features, none of which depend on the operating system. To
+//! hand the text of a `Rope` to JavaScript without collecting it into a
+//! `String` first, either pass each of its [`chunks()`](Rope::chunks()),
+//! which are `&str`s, to `JsString::from`, or feed the UTF-16 code units
+//! yielded by [`encode_utf16()`](Rope::encode_utf16()) to a JavaScript
+//! string. Enabling the `utf16-metric` feature lets you convert the UTF-16
+//! offsets used by JavaScript to and from byte offsets in logarithmic time.
+//!
 //! # Feature flags
 //!
 //! The following feature flags can be used to tweak crop's behavior and
 //! enable additional APIs:
 //!
-//! - `simd` (enabled by default): enables SIMD on supported platforms;
+//! - `line-metric` (enabled by default): makes the `Rope` and `RopeSlice`
+//!   track the line breaks they contain, which is needed by all the
+//!   line-based APIs. Disabling it saves counting the line breaks of every
+//!   edited chunk, and is useful when the text is only ever accessed by byte
+//!   offsets;
+//!
+//! - `simd` (enabled by default): enables SIMD on supported platforms. On
+//!   `x86_64` the line breaks are counted with AVX2 if the CPU supports it
+//!   (which is detected at runtime if `std` is also enabled) and SSE2
+//!   otherwise, on `aarch64` with NEON, and on `wasm32` with SIMD128 if the
+//!   `simd128` target feature is enabled. The SIMD counter only looks for
+//!   line feeds, so it's not used if `unicode-lines` is enabled;
+//!
+//! - `chunk-pool` (disabled by default): adds the `chunk_pool` module, whose
+//!   `set_allocator()` sets the allocator used for the buffers backing the
+//!   chunks of `Rope`s and for the nodes of their trees, and a
+//!   `ThreadLocalPool` allocator which recycles the blocks of dropped chunks
+//!   and nodes through a per-thread pool. Implies `std`;
+//!
+//! - `compact-summaries` (disabled by default): stores the byte, line and
+//!   UTF-16 counts summarizing the text of every chunk and internal node as
+//!   `u32`s instead of `usize`s, which roughly halves their size on 64-bit
+//!   targets. This is useful when keeping many medium-sized documents in
+//!   memory, but limits every `Rope` to 4 GiB of text: any operation which
+//!   would make it longer panics before modifying the `Rope`, while the
+//!   `try_` variants of the methods of `Rope` and `RopeBuilder` adding text
+//!   to it return a `TooLongError` instead;
+//!
+//! - `compression` (disabled by default): adds `Rope::compress()`, which
+//!   compresses the chunks of a `Rope` with LZ4 and decompresses them the
+//!   first time they're accessed, trading some CPU time for a smaller memory
+//!   footprint;
+//!
+//! - `content-hash` (disabled by default): keeps a hash of the text of every
+//!   chunk and internal node in its summary, which makes
+//!   `Rope::content_hash()` take `O(1)` time even right after an edit;
+//!
+//! - `digest` (disabled by default): adds a `digest()` method to `Rope`s
+//!   and `RopeSlice`s which computes the digest of their text with any hash
+//!   function implementing the [`Digest`] trait, without collecting it into
+//!   a `String` first;
+//!
+//! - `editing-traces` (disabled by default): adds the `editing_trace`
+//!   module, which can deserialize recorded editing traces and replay them on
+//!   a `Rope`. Implies `serde`;
+//!
+//! - `encoding` (disabled by default): adds
+//!   `Rope::from_reader_with_encoding()` and `Rope::write_with_encoding()`,
+//!   which read and write text in any of the encodings supported by
+//!   [`encoding_rs`], e.g. UTF-16, Latin-1 or Shift-JIS. Implies `std`;
 //!
 //! - `graphemes` (disabled by default): enables a few grapheme-oriented APIs
 //!   on `Rope`s and `RopeSlice`s such as the
 //!   [`Graphemes`](crate::iter::Graphemes) iterator and others;
 //!
+//! - `mmap` (disabled by default): adds `Rope::from_mmap()`, which creates a
+//!   `Rope` whose chunks borrow their text from a [`memmap2::Mmap`] (or any
+//!   other byte buffer implementing `StableBytes`) and only copy it when
+//!   they're edited, like `Rope::from_static()` does with a `&'static str`.
+//!   Implies `std`;
+//!
+//! - `node-hashes` (disabled by default): makes the internal nodes of a
+//!   `Rope` cache the hash of their contents, so that
+//!   `Rope::changes_since()` can skip the subtrees storing the same text as
+//!   the snapshot even when they're not shared with it, e.g. because the
+//!   snapshot was serialized and deserialized. Unshared subtrees with the
+//!   same hash are compared byte by byte before being skipped;
+//!
+//! - `paging` (disabled by default): adds `Rope::page_out()`, which writes
+//!   the cold chunks of a `Rope` to a spill file and memory-maps them,
+//!   letting the operating system page their text in and out of memory while
+//!   the tree stays resident. Implies `mmap` and `std`;
+//!
+//! - `parallel` (disabled by default): adds `Rope::from_str_parallel()` and
+//!   `Rope::from_reader_parallel()`, which split large texts into chunks on
+//!   multiple threads using [rayon], and `RopeSlice::par_chunks()` and
+//!   `RopeSlice::par_find_all()`, which iterate over and search the chunks of
+//!   a `RopeSlice` in parallel, and `Rope::par_changes_since()`. Implies
+//!   `std`;
+//!
+//! - `rkyv` (disabled by default): implements rkyv's `Archive`,
+//!   `Serialize` and `Deserialize` traits for `Rope`s. The archived form of a
+//!   `Rope`, an `ArchivedRope`, can be read directly from the archive's bytes
+//!   without deserializing it, and stores the byte and line offsets of its
+//!   chunks to access its bytes, lines and byte ranges in logarithmic time;
+//!
+//! - `serde` (disabled by default): implements serde's `Serialize` and
+//!   `Deserialize` traits for `Rope`s, and `Serialize` for `RopeSlice`s.
+//!   Both are serialized as strings, while the `chunked` module can be
+//!   used to serialize a `Rope` as a sequence of its chunks;
+//!
+//! - `simdutf8` (disabled by default): validates the UTF-8 of byte inputs,
+//!   e.g. in `Rope::from_mmap()`, `Rope::from_reader_parallel()`,
+//!   `RopeBuilder::push_bytes()` and the `TryFrom<&[u8]>` impl, with
+//!   [`simdutf8`], which is several times faster than the standard library's
+//!   validator on large inputs;
+//!
+//! - `unicode-lines` (disabled by default): makes the line metric also
+//!   break lines on vertical tabs, form feeds, next lines (U+0085), line
+//!   separators (U+2028) and paragraph separators (U+2029), in addition to
+//!   line feeds. A CRLF is still a single line break, but a lone carriage
+//!   return isn't one, since the two halves of a CRLF can end up in
+//!   different chunks: use `Rope::normalize_eols()` first if the text could
+//!   contain them. Implies `line-metric`;
+//!
 //! - `utf16-metric` (disabled by default): makes the `Rope` and `RopeSlice`
 //!   track the UTF-16 code units they'd have if their content was stored as
 //!   UTF-16 instead of UTF-8, allowing them to efficiently convert UTF-16
-//!   code unit offsets to and from byte offsets in logarithmic time.
+//!   code unit offsets to and from byte offsets in logarithmic time;
+//!
+//! - `word-metric` (disabled by default): makes every chunk of the `Rope`
+//!   keep track of the number of words it contains, allowing
+//!   `Rope::word_len()` and `RopeSlice::word_len()` to run in constant time.
+//!
+//! [`Digest`]: https://docs.rs/digest/0.10/digest/trait.Digest.html
+//! [`encoding_rs`]: https://docs.rs/encoding_rs/0.8
+//! [`memmap2::Mmap`]: https://docs.rs/memmap2/0.9/memmap2/struct.Mmap.html
+//! [rayon]: https://docs.rs/rayon/1
+//! [`simdutf8`]: https://docs.rs/simdutf8/0.1
 
 #![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![allow(clippy::explicit_auto_deref)]
@@ -152,6 +367,123 @@
 
 extern crate alloc;
 
+pub mod annotated {
+    //! An [`AnnotatedRope`] pairing a [`Rope`](crate::Rope) with a layer of
+    //! annotations that follow its edits.
+
+    pub use crate::rope::annotated::*;
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "chunk-pool")))]
+#[cfg(feature = "chunk-pool")]
+pub mod chunk_pool {
+    //! An allocator hook for the buffers backing the chunks of
+    //! [`Rope`](crate::Rope)s and the nodes of their trees, used when the
+    //! `chunk-pool` feature is enabled.
+    //!
+    //! Every chunk of a `Rope` is backed by a fixed-size buffer, and every
+    //! node of its tree by a fixed-size block, which are allocated with the
+    //! global allocator by default. Embedders can route these allocations to
+    //! an arena or a pool of their own by passing any
+    //! [`GlobalAlloc`](core::alloc::GlobalAlloc) to [`set_allocator()`]
+    //! before creating the first `Rope`.
+    //!
+    //! The [`ThreadLocalPool`] is one such allocator, which keeps the blocks
+    //! of the chunks and nodes that are dropped in a pool owned by the thread
+    //! that dropped them, and reuses them for the ones created afterwards on
+    //! the same thread.
+    //!
+    //! # Examples
+    //!
+    //! ```
+    //! use crop::chunk_pool::{self, ThreadLocalPool};
+    //! use crop::Rope;
+    //!
+    //! chunk_pool::set_allocator(&ThreadLocalPool).unwrap();
+    //!
+    //! drop(Rope::from("Hello, world!\n".repeat(1000)));
+    //! assert!(chunk_pool::pooled() > 0);
+    //! ```
+
+    pub use crate::rope::chunk_pool::{
+        chunk_layout,
+        clear,
+        pooled,
+        set_allocator,
+        set_max_pooled,
+        SetAllocatorError,
+        ThreadLocalPool,
+    };
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+#[cfg(feature = "serde")]
+pub mod chunked {
+    //! Serialization of a [`Rope`](crate::Rope) that preserves its chunk
+    //! boundaries, meant to be used with serde's `with` attribute.
+    //!
+    //! Unlike the default `Serialize` impl, which writes out the `Rope` as a
+    //! single string, this writes it out as a sequence of strings, one for
+    //! each of its chunks. Deserializing it then uses the chunks as they are
+    //! instead of splitting the whole text into chunks again, which is faster
+    //! for large documents.
+
+    pub use crate::rope::chunked::{deserialize, serialize};
+}
+
+pub mod delta_sync {
+    //! Content-defined [`Signature`]s of a [`Rope`](crate::Rope), used to
+    //! sync it with a remote copy by only sending the parts that differ.
+
+    pub use crate::rope::delta_sync::*;
+}
+
+pub mod edit_overlay {
+    //! An [`EditOverlay`] layering a set of pending edits on top of a
+    //! [`Rope`](crate::Rope).
+
+    pub use crate::rope::edit_overlay::EditOverlay;
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "editing-traces")))]
+#[cfg(feature = "editing-traces")]
+pub mod editing_trace {
+    //! An [`EditingTrace`] recording the edits performed on a document, which
+    //! can be deserialized from the common JSON trace formats and replayed on
+    //! a [`Rope`](crate::Rope).
+
+    pub use crate::rope::editing_trace::{EditingTrace, TracePatch};
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+#[cfg(feature = "line-metric")]
+pub mod filtered_lines {
+    //! A [`FilteredLines`] view over the lines of a [`Rope`](crate::Rope)
+    //! matching a predicate.
+
+    pub use crate::rope::filtered_lines::FilteredLines;
+}
+
+pub mod folded_view {
+    //! A [`FoldedView`] hiding folded byte ranges of a [`Rope`](crate::Rope)
+    //! and presenting the rest of its text as a single document.
+
+    pub use crate::rope::folded_view::FoldedView;
+}
+
+pub mod history {
+    //! An undo/redo [`History`] wrapping a [`Rope`](crate::Rope).
+
+    pub use crate::rope::history::*;
+}
+
+pub mod interval_tree {
+    //! An [`IntervalTree`] storing byte ranges with a payload that follow
+    //! the edits performed on a [`Rope`](crate::Rope).
+
+    pub use crate::rope::interval_tree::{IntervalId, IntervalTree};
+}
+
 pub mod iter {
     //! Iterators over [`Rope`](crate::Rope)s and
     //! [`RopeSlice`](crate::RopeSlice)s.
@@ -159,9 +491,90 @@ pub mod iter {
     pub use crate::rope::iterators::*;
 }
 
+#[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+#[cfg(feature = "line-metric")]
+pub mod line_data {
+    //! A [`LineDataRope`] storing some optional metadata for each line of a
+    //! [`Rope`](crate::Rope), which follows the lines through its edits.
+
+    pub use crate::rope::line_data::{LineData, LineDataRope};
+}
+
+pub mod marks {
+    //! Marks, i.e. positions in a [`Rope`](crate::Rope) that follow the
+    //! edits performed on it.
+
+    pub use crate::rope::marks::{Gravity, MarkId, MarkSet, Tail};
+}
+
+pub mod multi_rope {
+    //! A [`MultiRope`] view presenting several
+    //! [`RopeSlice`](crate::RopeSlice)s as a single document.
+
+    pub use crate::rope::multi_rope::MultiRope;
+}
+
+pub mod op_log {
+    //! A [`Replica`] wrapping a [`Rope`](crate::Rope) that records its edits
+    //! as a stream of uniquely identified operations, which can be exported
+    //! and re-applied on other replicas.
+
+    pub use crate::rope::op_log::*;
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "paging")))]
+#[cfg(feature = "paging")]
+pub mod paging {
+    //! A [`SpillFile`] the chunks of a [`Rope`](crate::Rope) can be paged out
+    //! to with [`Rope::page_out()`](crate::Rope::page_out()).
+
+    pub use crate::rope::paging::SpillFile;
+}
+
+pub mod record_index {
+    //! A [`RecordIndex`] over the records of a [`Rope`](crate::Rope)
+    //! delimited by a custom separator byte.
+
+    pub use crate::rope::record_index::RecordIndex;
+}
+
 mod rope;
 
-#[doc(hidden)]
+pub mod selections {
+    //! A set of [`Selections`] that can be moved through the edits described
+    //! by a [`Delta`](crate::Delta).
+
+    pub use crate::rope::selections::Selections;
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+#[cfg(feature = "std")]
+pub mod store {
+    //! A [`Store`] persisting the versions of a [`Rope`](crate::Rope) in an
+    //! append-only file, storing the subtrees they share only once.
+
+    pub use crate::rope::store::Store;
+}
+
+pub mod sum_rope {
+    //! A [`SumRope`] storing a sequence of arbitrary items together with the
+    //! sum of their summaries.
+
+    pub use crate::rope::sum_rope::{SumRope, SumSlice};
+}
+
+pub mod transaction {
+    //! A [`Transaction`] describing a compound edit of a
+    //! [`Rope`](crate::Rope) as a sequence of retain, delete and insert
+    //! operations.
+
+    pub use crate::rope::transaction::{
+        Operation,
+        Transaction,
+        TransactionError,
+    };
+}
+
 pub mod tree;
 
 // These are not part of the public API, we only export them to be able to run
@@ -172,7 +585,41 @@ pub use rope::{
     gap_slice::GapSlice,
     metrics::ChunkSummary,
 };
-pub use rope::{Rope, RopeBuilder, RopeSlice};
+#[cfg_attr(docsrs, doc(cfg(feature = "rkyv")))]
+#[cfg(feature = "rkyv")]
+pub use rope::{ArchivedRope, ArchivedRopeSlice, RopeResolver};
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+#[cfg(feature = "std")]
+pub use rope::RopeLoader;
+#[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+#[cfg(feature = "mmap")]
+pub use rope::StableBytes;
+#[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+#[cfg(feature = "line-metric")]
+pub use rope::LineCache;
+pub use rope::{
+    Bom,
+    ChunkInterner,
+    Delta,
+    EditInfo,
+    EditRegion,
+    EditSession,
+    FrozenRope,
+    IncrementalEdit,
+    Limit,
+    LineEnding,
+    MemStats,
+    Revision,
+    Rope,
+    RopeBuilder,
+    RopeCursor,
+    RopeRef,
+    RopeSlice,
+    Snapshot,
+    SubscriptionId,
+    TooLongError,
+    TrailingLineBreak,
+};
 
 #[inline]
 pub(crate) fn range_bounds_to_start_end<T, B>(
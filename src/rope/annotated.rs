@@ -0,0 +1,248 @@
+use core::ops::{Range, RangeBounds};
+
+use super::interval_tree::{IntervalId, IntervalTree};
+use super::utils::panic_messages as panic;
+use super::{EditInfo, Rope};
+use crate::range_bounds_to_start_end;
+
+/// A [`Rope`] with a layer of annotations, i.e. byte ranges mapped to some
+/// user data like highlight styles or diagnostics.
+///
+/// The annotations are stored in an [`IntervalTree`] and are kept in sync
+/// with the text through every edit performed on the `AnnotatedRope`. Text
+/// inserted at the start of an annotation is placed before it, text inserted
+/// at its end extends it, and text replacing its contents stays in it.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::annotated::AnnotatedRope;
+/// # use crop::Rope;
+/// #
+/// let mut r = AnnotatedRope::from(Rope::from("let foo = 42;\nfoo += 1;\n"));
+///
+/// r.annotate(0..3, "keyword");
+/// r.annotate(10..12, "number");
+/// r.annotate(21..22, "number");
+///
+/// r.replace(10..12, "1337");
+///
+/// assert_eq!(
+///     r.line_annotations(0).collect::<Vec<_>>(),
+///     [(0..3, &"keyword"), (10..14, &"number")]
+/// );
+///
+/// assert_eq!(r.line_annotations(1).collect::<Vec<_>>(), [(7..8, &"number")]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AnnotatedRope<T: Clone + 'static> {
+    rope: Rope,
+    annotations: IntervalTree<T>,
+}
+
+impl<T: Clone + 'static> AnnotatedRope<T> {
+    /// Annotates the given byte range with some data, returning the id of
+    /// the annotation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than its end or if its
+    /// end is out of bounds (i.e. greater than
+    /// [`byte_len()`](Rope::byte_len())).
+    #[track_caller]
+    #[inline]
+    pub fn annotate<R>(&mut self, byte_range: R, value: T) -> IntervalId
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.rope.byte_len());
+
+        // Slicing takes care of checking the range.
+        let _ = self.rope.byte_slice(start..end);
+
+        self.annotations.insert(start..end, value)
+    }
+
+    /// Returns the annotation layer.
+    #[inline]
+    pub fn annotations(&self) -> &IntervalTree<T> {
+        &self.annotations
+    }
+
+    /// Returns an iterator over the annotations overlapping the given byte
+    /// range, sorted by their start and clipped to the range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than its end or if its
+    /// end is out of bounds (i.e. greater than
+    /// [`byte_len()`](Rope::byte_len())).
+    #[track_caller]
+    #[inline]
+    pub fn annotations_in<R>(
+        &self,
+        byte_range: R,
+    ) -> impl Iterator<Item = (Range<usize>, &T)> + '_
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.rope.byte_len());
+
+        let _ = self.rope.byte_slice(start..end);
+
+        self.annotations.overlapping(start..end).map(
+            move |(_, range, value)| {
+                (range.start.max(start)..range.end.min(end), value)
+            },
+        )
+    }
+
+    /// Deletes the text within the specified byte range, updating the
+    /// annotations.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::delete()`].
+    #[track_caller]
+    #[inline]
+    pub fn delete<R>(&mut self, byte_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.replace(byte_range, "");
+    }
+
+    /// Creates a new `AnnotatedRope` from a `Rope` and an existing annotation
+    /// layer, which is assumed to refer to the text of the `Rope`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rope` has a [`limit()`](Rope::limit()).
+    #[track_caller]
+    #[inline]
+    pub fn from_parts(rope: Rope, annotations: IntervalTree<T>) -> Self {
+        if rope.limit().is_some() {
+            panic::rope_has_limit("AnnotatedRope");
+        }
+
+        Self { rope, annotations }
+    }
+
+    /// Inserts `text` at the given byte offset, updating the annotations.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::insert()`].
+    #[track_caller]
+    #[inline]
+    pub fn insert<S>(&mut self, byte_offset: usize, text: S)
+    where
+        S: AsRef<str>,
+    {
+        self.replace(byte_offset..byte_offset, text);
+    }
+
+    /// Consumes the `AnnotatedRope`, returning its `Rope` and its annotation
+    /// layer.
+    #[inline]
+    pub fn into_parts(self) -> (Rope, IntervalTree<T>) {
+        (self.rope, self.annotations)
+    }
+
+    /// Consumes the `AnnotatedRope`, returning its `Rope`.
+    #[inline]
+    pub fn into_rope(self) -> Rope {
+        self.rope
+    }
+
+    /// Returns an iterator over the annotations overlapping the line at the
+    /// given index, sorted by their start and clipped to the line.
+    ///
+    /// The ranges are relative to the start of the line and don't include
+    /// its line break, so they can be used to directly slice the line
+    /// returned by [`Rope::line()`], or the text yielded by
+    /// [`Rope::lines()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line index is out of bounds (i.e. greater than or equal
+    /// to [`line_len()`](Rope::line_len())).
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn line_annotations(
+        &self,
+        line_index: usize,
+    ) -> impl Iterator<Item = (Range<usize>, &T)> + '_ {
+        let line_len = self.rope.line(line_index).byte_len();
+        let start = self.rope.byte_of_line(line_index);
+
+        self.annotations_in(start..start + line_len).map(
+            move |(range, value)| {
+                (range.start - start..range.end - start, value)
+            },
+        )
+    }
+
+    /// Removes an annotation, returning its byte range and its data if it
+    /// hadn't already been removed.
+    #[inline]
+    pub fn remove_annotation(
+        &mut self,
+        id: IntervalId,
+    ) -> Option<(Range<usize>, T)> {
+        self.annotations.remove(id)
+    }
+
+    /// Replaces the text within the specified byte range with `text`,
+    /// updating the annotations.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::replace()`].
+    #[track_caller]
+    #[inline]
+    pub fn replace<R, S>(&mut self, byte_range: R, text: S)
+    where
+        R: RangeBounds<usize>,
+        S: AsRef<str>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.rope.byte_len());
+
+        let text = text.as_ref();
+
+        self.rope.replace(start..end, text);
+
+        self.annotations.edit(&EditInfo {
+            byte_range: start..end,
+            inserted_len: text.len(),
+        });
+    }
+
+    /// Returns the annotated `Rope`.
+    #[inline]
+    pub fn rope(&self) -> &Rope {
+        &self.rope
+    }
+}
+
+impl<T: Clone + 'static> core::ops::Deref for AnnotatedRope<T> {
+    type Target = Rope;
+
+    #[inline]
+    fn deref(&self) -> &Rope {
+        &self.rope
+    }
+}
+
+impl<T: Clone + 'static> From<Rope> for AnnotatedRope<T> {
+    #[track_caller]
+    #[inline]
+    fn from(rope: Rope) -> Self {
+        Self::from_parts(rope, IntervalTree::new())
+    }
+}
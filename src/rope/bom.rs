@@ -0,0 +1,93 @@
+/// A byte order mark, i.e. the encoding of the `U+FEFF` character some
+/// programs write at the start of a file to signal its encoding.
+///
+/// The constructors that strip the BOM from the text, like
+/// [`Rope::from_utf8_with_bom()`](crate::Rope::from_utf8_with_bom()), return
+/// the one they found so that it can be written back when saving the text,
+/// without it ending up in the contents of the first line.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Bom {
+    /// The UTF-8 BOM, `EF BB BF`.
+    Utf8,
+
+    /// The UTF-16 big-endian BOM, `FE FF`.
+    Utf16Be,
+
+    /// The UTF-16 little-endian BOM, `FF FE`.
+    Utf16Le,
+}
+
+impl Bom {
+    /// Returns the bytes of the BOM.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Bom;
+    /// #
+    /// assert_eq!(Bom::Utf8.as_bytes(), b"\xEF\xBB\xBF");
+    /// assert_eq!(Bom::Utf16Le.as_bytes(), b"\xFF\xFE");
+    /// ```
+    #[inline]
+    pub const fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => b"\xEF\xBB\xBF",
+            Self::Utf16Be => b"\xFE\xFF",
+            Self::Utf16Le => b"\xFF\xFE",
+        }
+    }
+
+    /// Returns the BOM at the start of `bytes`, if there is one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Bom;
+    /// #
+    /// assert_eq!(Bom::detect(b"\xEF\xBB\xBFHello"), Some(Bom::Utf8));
+    /// assert_eq!(Bom::detect(b"\xFE\xFF\x00H"), Some(Bom::Utf16Be));
+    /// assert_eq!(Bom::detect(b"Hello"), None);
+    /// ```
+    #[inline]
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        [Self::Utf8, Self::Utf16Be, Self::Utf16Le]
+            .into_iter()
+            .find(|bom| bytes.starts_with(bom.as_bytes()))
+    }
+
+    /// Returns the encoding signaled by the BOM.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Bom;
+    /// #
+    /// assert_eq!(Bom::Utf16Le.encoding(), encoding_rs::UTF_16LE);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+    #[cfg(feature = "encoding")]
+    #[inline]
+    pub fn encoding(&self) -> &'static encoding_rs::Encoding {
+        match self {
+            Self::Utf8 => encoding_rs::UTF_8,
+            Self::Utf16Be => encoding_rs::UTF_16BE,
+            Self::Utf16Le => encoding_rs::UTF_16LE,
+        }
+    }
+
+    /// Returns the byte length of the BOM.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Bom;
+    /// #
+    /// assert_eq!(Bom::Utf8.len(), 3);
+    /// assert_eq!(Bom::Utf16Be.len(), 2);
+    /// ```
+    #[allow(clippy::len_without_is_empty)]
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+}
@@ -0,0 +1,500 @@
+//! This module contains the [`ChunkBytes`] backing a
+//! [`GapBuffer`](super::gap_buffer::GapBuffer).
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+#[cfg(feature = "compression")]
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "compression")]
+use core::sync::atomic::{self, AtomicUsize};
+#[cfg(feature = "mmap")]
+use core::ptr::NonNull;
+
+/// The buffer backing owned chunks, which is allocated with the allocator
+/// set with `chunk_pool::set_allocator()` with the `chunk-pool` feature.
+#[cfg(not(feature = "chunk-pool"))]
+pub(super) type OwnedBytes<const MAX_BYTES: usize> = Box<[u8; MAX_BYTES]>;
+
+#[cfg(feature = "chunk-pool")]
+pub(super) use super::chunk_pool::PooledBytes as OwnedBytes;
+
+/// Returns a new buffer for an owned chunk.
+#[inline]
+pub(super) fn new_owned<const MAX_BYTES: usize>() -> OwnedBytes<MAX_BYTES> {
+    #[cfg(not(feature = "chunk-pool"))]
+    {
+        Box::new([0; MAX_BYTES])
+    }
+
+    #[cfg(feature = "chunk-pool")]
+    {
+        OwnedBytes::new()
+    }
+}
+
+/// The bytes of a `GapBuffer`.
+///
+/// They're normally owned by the buffer, but they can also be borrowed from a
+/// `&'static str` or, with the `mmap` feature, from a shared source, e.g. a
+/// memory-mapped file, until the first time the buffer is mutated. With the
+/// `compression` feature they can also be stored compressed, and be
+/// decompressed when they're accessed. Borrowed and compressed bytes are
+/// always the left chunk of the buffer, with an empty right chunk.
+///
+/// Owned bytes always have room for `MAX_BYTES` bytes, unless they've been
+/// shrunk to the length of the text by
+/// [`Rope::shrink_to_fit()`](crate::Rope::shrink_to_fit()). They can also be
+/// shared with other buffers holding the same text by
+/// [`Rope::intern_chunks()`](crate::Rope::intern_chunks()). Shrunk and
+/// interned bytes are also the left chunk of the buffer, and get their full
+/// capacity back the first time the buffer is mutated.
+#[derive(Clone)]
+pub(super) enum ChunkBytes<const MAX_BYTES: usize> {
+    Owned(OwnedBytes<MAX_BYTES>),
+
+    Shrunk(Box<[u8]>),
+
+    Interned(Arc<[u8]>),
+
+    Static(&'static [u8]),
+
+    #[cfg(feature = "mmap")]
+    Mapped(MappedBytes),
+
+    #[cfg(feature = "compression")]
+    Compressed(CompressedBytes),
+}
+
+impl<const MAX_BYTES: usize> From<OwnedBytes<MAX_BYTES>>
+    for ChunkBytes<MAX_BYTES>
+{
+    #[inline]
+    fn from(bytes: OwnedBytes<MAX_BYTES>) -> Self {
+        Self::Owned(bytes)
+    }
+}
+
+#[cfg(feature = "chunk-pool")]
+impl<const MAX_BYTES: usize> From<Box<[u8; MAX_BYTES]>>
+    for ChunkBytes<MAX_BYTES>
+{
+    #[inline]
+    fn from(bytes: Box<[u8; MAX_BYTES]>) -> Self {
+        Self::Owned(bytes.into())
+    }
+}
+
+impl<const MAX_BYTES: usize> ChunkBytes<MAX_BYTES> {
+    /// Compresses `bytes`, returning `None` if that wouldn't make them any
+    /// smaller.
+    #[cfg(feature = "compression")]
+    #[inline]
+    pub(super) fn compress(bytes: &[u8]) -> Option<Self> {
+        CompressedBytes::new(bytes).map(Self::Compressed)
+    }
+
+    /// Returns a copy of the bytes without their decompressed copy if
+    /// they're compressed and have been accessed since they were compressed,
+    /// or `None` otherwise.
+    #[cfg(feature = "compression")]
+    #[inline]
+    pub(super) fn evict(&self) -> Option<Self> {
+        match self {
+            Self::Compressed(compressed) => {
+                compressed.evict().map(Self::Compressed)
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the LRU clock the last time the bytes were read,
+    /// if they're compressed and currently have a decompressed copy.
+    #[cfg(feature = "compression")]
+    #[inline]
+    pub(super) fn last_read_decompressed(&self) -> Option<usize> {
+        match self {
+            Self::Compressed(compressed) => compressed
+                .decompressed
+                .get()
+                .map(|_| compressed.last_read()),
+            _ => None,
+        }
+    }
+
+    /// Calls `f` with the bytes without keeping a decompressed copy of them
+    /// around if they're compressed and don't already have one.
+    #[inline]
+    pub(super) fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        match self {
+            #[cfg(feature = "compression")]
+            Self::Compressed(compressed) => compressed.with_bytes(f),
+            _ => f(self),
+        }
+    }
+
+    /// Returns the number of bytes allocated on the heap to store the bytes,
+    /// which is zero if they're borrowed.
+    #[inline]
+    pub(super) fn heap_size(&self) -> usize {
+        match self {
+            Self::Owned(_) => MAX_BYTES,
+            Self::Shrunk(bytes) => bytes.len(),
+            Self::Interned(bytes) => bytes.len(),
+            Self::Static(_) => 0,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(_) => 0,
+            #[cfg(feature = "compression")]
+            Self::Compressed(compressed) => compressed.heap_size(),
+        }
+    }
+
+    /// Returns `true` if the bytes are stored uncompressed in memory, i.e.
+    /// if they're neither borrowed nor compressed.
+    #[inline]
+    pub(super) fn is_resident(&self) -> bool {
+        matches!(
+            self,
+            Self::Owned(_) | Self::Shrunk(_) | Self::Interned(_)
+        )
+    }
+
+    /// Returns `true` if the bytes have room for `MAX_BYTES` bytes.
+    #[inline]
+    pub(super) fn is_full_capacity(&self) -> bool {
+        matches!(self, Self::Owned(_))
+    }
+
+    /// Returns `true` if the bytes are borrowed from a shared source or from
+    /// a `&'static str`.
+    #[cfg(feature = "paging")]
+    #[inline]
+    pub(super) fn is_mapped(&self) -> bool {
+        matches!(self, Self::Mapped(_) | Self::Static(_))
+    }
+}
+
+impl<const MAX_BYTES: usize> Deref for ChunkBytes<MAX_BYTES> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Owned(bytes) => &bytes[..],
+
+            Self::Shrunk(bytes) => bytes,
+
+            Self::Interned(bytes) => bytes,
+
+            Self::Static(bytes) => bytes,
+
+            #[cfg(feature = "mmap")]
+            Self::Mapped(mapped) => mapped.as_bytes(),
+
+            #[cfg(feature = "compression")]
+            Self::Compressed(compressed) => compressed.as_bytes(),
+        }
+    }
+}
+
+impl<const MAX_BYTES: usize> DerefMut for ChunkBytes<MAX_BYTES> {
+    /// Copies the bytes into an owned buffer with their full capacity if
+    /// they're shrunk, interned, static, borrowed or compressed.
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        let unsized_bytes = match self {
+            Self::Shrunk(shrunk) => Some(&shrunk[..]),
+            Self::Interned(interned) => Some(&interned[..]),
+            Self::Static(bytes) => Some(*bytes),
+            _ => None,
+        };
+
+        if let Some(src) = unsized_bytes {
+            let mut bytes = new_owned();
+            bytes[..src.len()].copy_from_slice(src);
+            *self = Self::Owned(bytes);
+        }
+
+        #[cfg(feature = "compression")]
+        if let Self::Compressed(compressed) = self {
+            let mut bytes = new_owned();
+            compressed.decompress_into(&mut bytes[..]);
+            *self = Self::Owned(bytes);
+        }
+
+        #[cfg(feature = "mmap")]
+        if let Self::Mapped(mapped) = self {
+            let src = mapped.as_bytes();
+            let mut bytes = new_owned();
+            bytes[..src.len()].copy_from_slice(src);
+            *self = Self::Owned(bytes);
+        }
+
+        match self {
+            Self::Owned(bytes) => &mut bytes[..],
+
+            Self::Shrunk(_) | Self::Interned(_) | Self::Static(_) => {
+                unreachable!()
+            },
+
+            #[cfg(feature = "mmap")]
+            Self::Mapped(_) => unreachable!(),
+
+            #[cfg(feature = "compression")]
+            Self::Compressed(_) => unreachable!(),
+        }
+    }
+}
+
+/// Byte buffers that always return the same bytes from
+/// [`as_ref()`](AsRef::as_ref()), which lets
+/// [`Rope::from_mmap()`](crate::Rope::from_mmap()) borrow them.
+///
+/// # Safety
+///
+/// As long as the buffer isn't mutated through a mutable reference or
+/// dropped, every call to `as_ref()` must return a slice with the same
+/// address, length and contents as the first one, even if the buffer is
+/// moved in between. Chunks keep pointing to the slice returned by the first
+/// call, and they treat its bytes as valid UTF-8 once they've been checked.
+#[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+#[cfg(feature = "mmap")]
+pub unsafe trait StableBytes: AsRef<[u8]> {}
+
+// SAFETY: the bytes live on the heap, and they can't be changed or
+// reallocated through a shared reference.
+#[cfg(feature = "mmap")]
+unsafe impl StableBytes for alloc::vec::Vec<u8> {}
+
+// SAFETY: same as above.
+#[cfg(feature = "mmap")]
+unsafe impl StableBytes for alloc::string::String {}
+
+// SAFETY: same as above.
+#[cfg(feature = "mmap")]
+unsafe impl StableBytes for Box<[u8]> {}
+
+// SAFETY: same as above.
+#[cfg(feature = "mmap")]
+unsafe impl StableBytes for Arc<[u8]> {}
+
+// SAFETY: the bytes are borrowed for the whole program, and they can't be
+// changed through a shared reference.
+#[cfg(feature = "mmap")]
+unsafe impl StableBytes for &'static [u8] {}
+
+// SAFETY: same as above.
+#[cfg(feature = "mmap")]
+unsafe impl StableBytes for &'static str {}
+
+// SAFETY: the mapping is never moved or resized, and the caller of
+// `Mmap::map()` guarantees that the file isn't modified while it's mapped.
+#[cfg(feature = "mmap")]
+unsafe impl StableBytes for memmap2::Mmap {}
+
+/// The source of the bytes borrowed by [`MappedBytes`].
+#[cfg(feature = "mmap")]
+pub(super) type ByteSource = Arc<dyn StableBytes + Send + Sync>;
+
+/// A range of bytes borrowed from a [`ByteSource`].
+#[cfg(feature = "mmap")]
+#[derive(Clone)]
+pub(super) struct MappedBytes {
+    /// Keeps the memory pointed to by `ptr` alive, which `StableBytes`
+    /// guarantees is where the bytes are for as long as it is.
+    _source: ByteSource,
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: the bytes are immutable and the source they're borrowed from is
+// `Send + Sync`.
+#[cfg(feature = "mmap")]
+unsafe impl Send for MappedBytes {}
+
+// SAFETY: same as above.
+#[cfg(feature = "mmap")]
+unsafe impl Sync for MappedBytes {}
+
+#[cfg(feature = "mmap")]
+impl MappedBytes {
+    #[inline]
+    fn as_bytes(&self) -> &[u8] {
+        // SAFETY: `ptr` and `len` were taken from a slice returned by the
+        // source, which `StableBytes` guarantees stays valid and unchanged
+        // until `_source` is dropped.
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Borrows `bytes`, which must be a slice of the bytes returned by
+    /// `source`.
+    #[inline]
+    pub(super) fn new(source: &ByteSource, bytes: &[u8]) -> Self {
+        Self {
+            _source: Arc::clone(source),
+            ptr: NonNull::from(bytes).cast(),
+            len: bytes.len(),
+        }
+    }
+}
+
+/// The maximum number of chunks a [`Rope`](crate::Rope) keeps decompressed
+/// after an edit, see [`Rope::compress()`](crate::Rope::compress()).
+#[cfg(feature = "compression")]
+pub(super) const MAX_DECOMPRESSED_CHUNKS: usize = 64;
+
+/// The number of [`CompressedBytes`] that currently have a decompressed copy.
+#[cfg(feature = "compression")]
+static DECOMPRESSED: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of times any [`CompressedBytes`] has been decompressed, which
+/// is used as the clock of the LRU deciding which decompressed copies to
+/// drop.
+#[cfg(feature = "compression")]
+static DECOMPRESSIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of compressed chunks that currently have a
+/// decompressed copy, across all the `Rope`s.
+#[cfg(feature = "compression")]
+#[inline]
+pub(super) fn decompressed() -> usize {
+    DECOMPRESSED.load(atomic::Ordering::Relaxed)
+}
+
+/// Returns the number of times a compressed chunk has been decompressed.
+#[cfg(feature = "compression")]
+#[inline]
+pub(super) fn decompressions() -> usize {
+    DECOMPRESSIONS.load(atomic::Ordering::Relaxed)
+}
+
+/// Bytes compressed with LZ4, which are decompressed the first time they're
+/// accessed.
+///
+/// The decompressed copy is then kept around so that reading the same chunk
+/// over and over doesn't decompress it every time, until it's dropped by
+/// [`evict()`](Self::evict()). It can't be dropped while the bytes are only
+/// borrowed, since the slices returned by [`as_bytes()`](Self::as_bytes())
+/// point into it.
+#[cfg(feature = "compression")]
+pub(super) struct CompressedBytes {
+    compressed: Arc<[u8]>,
+    decompressed: once_cell::race::OnceBox<Vec<u8>>,
+
+    /// The value of [`DECOMPRESSIONS`] the last time the decompressed copy
+    /// was read.
+    last_read: AtomicUsize,
+
+    len: usize,
+}
+
+#[cfg(feature = "compression")]
+impl Clone for CompressedBytes {
+    /// The decompressed copy is not cloned, since the clone is usually only
+    /// made to be edited, which decompresses it into an owned buffer anyway.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            compressed: Arc::clone(&self.compressed),
+            decompressed: once_cell::race::OnceBox::new(),
+            last_read: AtomicUsize::new(self.last_read()),
+            len: self.len,
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+impl Drop for CompressedBytes {
+    #[inline]
+    fn drop(&mut self) {
+        if self.decompressed.get().is_some() {
+            DECOMPRESSED.fetch_sub(1, atomic::Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+impl CompressedBytes {
+    #[inline]
+    fn as_bytes(&self) -> &[u8] {
+        let now = decompressions();
+
+        if self.last_read() != now {
+            self.last_read.store(now, atomic::Ordering::Relaxed);
+        }
+
+        if let Some(bytes) = self.decompressed.get() {
+            return bytes;
+        }
+
+        let mut bytes = alloc::vec![0; self.len];
+        self.decompress_into(&mut bytes);
+
+        // Only count the copy if another thread didn't set one first.
+        if self.decompressed.set(Box::new(bytes)).is_ok() {
+            DECOMPRESSED.fetch_add(1, atomic::Ordering::Relaxed);
+            DECOMPRESSIONS.fetch_add(1, atomic::Ordering::Relaxed);
+        }
+
+        self.decompressed.get().unwrap()
+    }
+
+    /// Writes the decompressed bytes at the start of `buf`, which must be at
+    /// least `self.len` bytes long.
+    #[inline]
+    fn decompress_into(&self, buf: &mut [u8]) {
+        if let Some(decompressed) = self.decompressed.get() {
+            buf[..self.len].copy_from_slice(decompressed);
+            return;
+        }
+
+        let len = lz4_flex::block::decompress_into(&self.compressed, buf)
+            .expect("the bytes were compressed by `CompressedBytes::new()`");
+
+        debug_assert_eq!(len, self.len);
+    }
+
+    #[inline]
+    fn evict(&self) -> Option<Self> {
+        self.decompressed.get()?;
+        Some(self.clone())
+    }
+
+    /// Returns the size of the compressed bytes plus the one of their
+    /// decompressed copy, if they have one.
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.compressed.len()
+            + self.decompressed.get().map_or(0, |bytes| bytes.capacity())
+    }
+
+    /// Calls `f` with the decompressed bytes, decompressing them into a
+    /// temporary buffer instead of keeping a copy if they don't have one.
+    #[inline]
+    fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        if let Some(bytes) = self.decompressed.get() {
+            return f(bytes);
+        }
+
+        let mut bytes = alloc::vec![0; self.len];
+        self.decompress_into(&mut bytes);
+        f(&bytes)
+    }
+
+    #[inline]
+    fn last_read(&self) -> usize {
+        self.last_read.load(atomic::Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn new(bytes: &[u8]) -> Option<Self> {
+        let compressed = lz4_flex::block::compress(bytes);
+
+        (compressed.len() < bytes.len()).then(|| Self {
+            compressed: compressed.into(),
+            decompressed: once_cell::race::OnceBox::new(),
+            last_read: AtomicUsize::new(decompressions()),
+            len: bytes.len(),
+        })
+    }
+}
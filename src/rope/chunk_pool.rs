@@ -0,0 +1,339 @@
+//! This module contains the allocator hook used to allocate the buffers
+//! backing the chunks of [`Rope`](crate::Rope)s and the nodes of their trees,
+//! and a per-thread pool that can be installed with it, which are used when
+//! the `chunk-pool` feature is enabled.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::{RefCell, UnsafeCell};
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use std::sync::Once;
+
+/// An allocator for the chunk buffers and the tree nodes, as stored by
+/// [`set_allocator()`].
+pub(crate) type Allocator = &'static (dyn GlobalAlloc + Sync);
+
+/// The allocator used for every chunk and node, which is set either by
+/// [`set_allocator()`] or to the global allocator by the first allocation,
+/// whichever comes first, and never changes afterwards.
+static ALLOCATOR: AllocatorCell = AllocatorCell {
+    once: Once::new(),
+    allocator: UnsafeCell::new(MaybeUninit::uninit()),
+};
+
+/// A cell which is written to once, like a `OnceLock` which isn't available
+/// in our MSRV.
+struct AllocatorCell {
+    once: Once,
+    allocator: UnsafeCell<MaybeUninit<Allocator>>,
+}
+
+// SAFETY: the allocator is only written to once, while `Once` guarantees
+// that nothing is reading it.
+unsafe impl Sync for AllocatorCell {}
+
+impl AllocatorCell {
+    /// Returns the allocator, setting it to the one returned by `init` if
+    /// it's not set yet.
+    #[inline]
+    fn get_or_init(&self, init: impl FnOnce() -> Allocator) -> Allocator {
+        self.once.call_once(|| {
+            // SAFETY: `Once` guarantees that nothing else is accessing the
+            // cell while this runs.
+            unsafe { (*self.allocator.get()).write(init()) };
+        });
+
+        // SAFETY: the `Once` has completed, so the allocator was written to
+        // and will never be again.
+        unsafe { (*self.allocator.get()).assume_init() }
+    }
+}
+
+/// The error returned by [`set_allocator()`] when the allocator has already
+/// been set, either by a previous call or by the allocation of a chunk or
+/// node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SetAllocatorError;
+
+impl core::fmt::Display for SetAllocatorError {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("the chunk allocator has already been set")
+    }
+}
+
+impl std::error::Error for SetAllocatorError {}
+
+/// Sets the allocator used for the buffers of the chunks and for the nodes
+/// of the trees of every `Rope`, on every thread. By default they're
+/// allocated with the global allocator.
+///
+/// The allocator can only be set once, before any `Rope` has been created,
+/// since the first chunk or node to be allocated sets it to the global
+/// allocator. Calling this afterwards returns a [`SetAllocatorError`] and
+/// leaves the allocator unchanged, so this is meant to be called at startup.
+///
+/// The allocator is asked for blocks of [`chunk_layout()`](chunk_layout) for
+/// the chunks, one for every chunk size the `Rope`s are instantiated with,
+/// and of a few other fixed layouts for the nodes, so it can be a simple
+/// arena or pool of fixed-size blocks, like the [`ThreadLocalPool`].
+#[inline]
+pub fn set_allocator<A: GlobalAlloc + Sync>(
+    allocator: &'static A,
+) -> Result<(), SetAllocatorError> {
+    let mut set = false;
+
+    ALLOCATOR.get_or_init(|| {
+        set = true;
+        allocator
+    });
+
+    if set {
+        Ok(())
+    } else {
+        Err(SetAllocatorError)
+    }
+}
+
+/// Returns the layout of the blocks requested to the allocator set with
+/// [`set_allocator()`] for the chunk buffers of the `Rope`s whose chunks
+/// store up to `MAX_BYTES` bytes, i.e. `chunk_layout::<2048>()` for the
+/// default [`Rope`](crate::Rope).
+#[inline]
+pub fn chunk_layout<const MAX_BYTES: usize>() -> Layout {
+    Layout::new::<[u8; MAX_BYTES]>()
+}
+
+/// Returns the allocator set with [`set_allocator()`], or the global one.
+#[inline]
+pub(crate) fn allocator() -> Allocator {
+    ALLOCATOR.get_or_init(|| &Global)
+}
+
+/// The global allocator, as seen from the `alloc` crate.
+struct Global;
+
+unsafe impl GlobalAlloc for Global {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        alloc::alloc::alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        alloc::alloc::dealloc(ptr, layout)
+    }
+}
+
+/// The maximum number of buffers kept in the pool of a thread if it's not
+/// changed with [`set_max_pooled()`].
+const DEFAULT_MAX_POOLED: usize = 64;
+
+/// An allocator which keeps the blocks given back to it in a pool owned by
+/// the thread that freed them, and hands them out again to the allocations
+/// of the same layout made afterwards on that thread, instead of asking the
+/// global allocator for new ones.
+///
+/// This reduces the cost of allocating and the fragmentation of the heap for
+/// workloads that create and drop many `Rope`s, or edit them heavily. It's
+/// used for the chunks and nodes of every `Rope` after calling
+/// `set_allocator(&ThreadLocalPool)`, and its pools can be configured with
+/// [`clear()`], [`pooled()`] and [`set_max_pooled()`].
+pub struct ThreadLocalPool;
+
+struct Pool {
+    blocks: Vec<(NonNull<u8>, Layout)>,
+    max_pooled: usize,
+}
+
+impl Pool {
+    /// Gives the blocks in excess of `max_len` back to the global allocator.
+    #[inline]
+    fn truncate(&mut self, max_len: usize) {
+        for (ptr, layout) in
+            self.blocks.drain(max_len.min(self.blocks.len())..)
+        {
+            // SAFETY: the blocks in the pool were allocated by the global
+            // allocator with their layout.
+            unsafe { Global.dealloc(ptr.as_ptr(), layout) };
+        }
+    }
+}
+
+impl Drop for Pool {
+    #[inline]
+    fn drop(&mut self) {
+        self.truncate(0);
+    }
+}
+
+std::thread_local! {
+    static POOL: RefCell<Pool> = const {
+        RefCell::new(Pool { blocks: Vec::new(), max_pooled: DEFAULT_MAX_POOLED })
+    };
+}
+
+unsafe impl GlobalAlloc for ThreadLocalPool {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // The pool holds blocks of a few different layouts, i.e. the ones of
+        // the chunks and of the nodes, so we look for the last one freed
+        // with the same layout.
+        let pooled = POOL
+            .try_with(|pool| {
+                let mut pool = pool.borrow_mut();
+                let idx = pool.blocks.iter().rposition(|b| b.1 == layout)?;
+                Some(pool.blocks.swap_remove(idx))
+            })
+            .ok()
+            .flatten();
+
+        match pooled {
+            Some((ptr, _)) => ptr.as_ptr(),
+            None => Global.alloc(layout),
+        }
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // The pool may have already been destroyed if this is called while
+        // the thread is exiting, in which case the block is simply freed.
+        let recycled = POOL
+            .try_with(|pool| {
+                let mut pool = pool.borrow_mut();
+                if pool.blocks.len() >= pool.max_pooled {
+                    return false;
+                }
+                // SAFETY: the caller guarantees that the pointer was
+                // returned by `alloc()`, which never returns null.
+                let ptr = NonNull::new_unchecked(ptr);
+                pool.blocks.push((ptr, layout));
+                true
+            })
+            .unwrap_or(false);
+
+        if !recycled {
+            Global.dealloc(ptr, layout);
+        }
+    }
+}
+
+/// Gives all the blocks in the pool of the current thread back to the global
+/// allocator.
+#[inline]
+pub fn clear() {
+    POOL.with(|pool| pool.borrow_mut().truncate(0));
+}
+
+/// Returns the number of blocks in the pool of the current thread.
+#[inline]
+pub fn pooled() -> usize {
+    POOL.with(|pool| pool.borrow().blocks.len())
+}
+
+/// Sets the maximum number of blocks kept in the pool of the current thread,
+/// giving the ones in excess back to the global allocator. The default is
+/// 64.
+///
+/// Setting it to zero disables the pool for the current thread.
+#[inline]
+pub fn set_max_pooled(max_pooled: usize) {
+    POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        pool.max_pooled = max_pooled;
+        pool.truncate(max_pooled);
+    });
+}
+
+/// A chunk buffer allocated with the allocator set with [`set_allocator()`],
+/// which is given back to the same allocator when it's dropped.
+pub(super) struct PooledBytes<const N: usize> {
+    ptr: NonNull<[u8; N]>,
+    allocator: Allocator,
+}
+
+// SAFETY: the buffer is uniquely owned, and the allocator is `Sync`.
+unsafe impl<const N: usize> Send for PooledBytes<N> {}
+
+// SAFETY: the buffer is only mutated through a mutable reference.
+unsafe impl<const N: usize> Sync for PooledBytes<N> {}
+
+impl<const N: usize> PooledBytes<N> {
+    /// Returns the layout of the buffer.
+    #[inline]
+    fn layout() -> Layout {
+        chunk_layout::<N>()
+    }
+
+    #[inline]
+    pub(super) fn new() -> Self {
+        let allocator = allocator();
+
+        // SAFETY: the layout has a non-zero size since chunks can store at
+        // least one byte.
+        let ptr = unsafe { allocator.alloc(Self::layout()) };
+
+        let Some(ptr) = NonNull::new(ptr.cast::<[u8; N]>()) else {
+            alloc::alloc::handle_alloc_error(Self::layout());
+        };
+
+        // SAFETY: the block is valid for writes of `N` bytes, and zeroing it
+        // makes it valid for reads, whatever the allocator left in it.
+        unsafe { ptr.as_ptr().write_bytes(0, 1) };
+
+        Self { ptr, allocator }
+    }
+}
+
+impl<const N: usize> Clone for PooledBytes<N> {
+    #[inline]
+    fn clone(&self) -> Self {
+        let mut new = Self::new();
+        new.copy_from_slice(&self[..]);
+        new
+    }
+}
+
+impl<const N: usize> Deref for PooledBytes<N> {
+    type Target = [u8; N];
+
+    #[inline]
+    fn deref(&self) -> &[u8; N] {
+        // SAFETY: the buffer is valid and initialized until it's dropped.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<const N: usize> DerefMut for PooledBytes<N> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8; N] {
+        // SAFETY: the buffer is valid and initialized until it's dropped,
+        // and it's uniquely owned.
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<const N: usize> Drop for PooledBytes<N> {
+    #[inline]
+    fn drop(&mut self) {
+        // SAFETY: the buffer was allocated by this allocator with this
+        // layout, and it's never accessed again after this.
+        unsafe {
+            self.allocator.dealloc(self.ptr.as_ptr().cast(), Self::layout())
+        };
+    }
+}
+
+impl<const N: usize> From<Box<[u8; N]>> for PooledBytes<N> {
+    /// Takes ownership of a buffer allocated with the global allocator,
+    /// which it's given back to when it's dropped.
+    #[inline]
+    fn from(buffer: Box<[u8; N]>) -> Self {
+        // SAFETY: `Box::into_raw()` never returns null.
+        let ptr = unsafe { NonNull::new_unchecked(Box::into_raw(buffer)) };
+        Self { ptr, allocator: &Global }
+    }
+}
@@ -0,0 +1,161 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserializer, Serialize};
+
+use super::gap_slice::GapSlice;
+use super::rope::RopeChunk;
+use super::utils::ends_with_line_break;
+use super::Rope;
+use crate::tree::Tree;
+
+/// Serializes the `Rope` as a sequence of strings, one for each of its
+/// chunks.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::Rope;
+/// #
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Document {
+///     #[serde(with = "crop::chunked")]
+///     text: Rope,
+/// }
+///
+/// let doc = Document { text: Rope::from("Hello, world!") };
+///
+/// let json = serde_json::to_string(&doc).unwrap();
+/// let de: Document = serde_json::from_str(&json).unwrap();
+///
+/// assert_eq!(de.text, doc.text);
+/// ```
+#[inline]
+pub fn serialize<S: Serializer>(
+    rope: &Rope,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        rope.tree.leaves().filter(|leaf| leaf.len() > 0).map(Chunk),
+    )
+}
+
+/// Deserializes a `Rope` from the sequence of chunks written by
+/// [`serialize()`].
+///
+/// The chunks become the leaves of the `Rope` as they are, so the text isn't
+/// split into chunks again. This fails if they don't respect the size limits
+/// of this build of crop, e.g. because they were written by a different
+/// version of it.
+#[inline]
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Rope, D::Error> {
+    deserializer.deserialize_seq(ChunksVisitor)
+}
+
+/// A leaf of the `Rope` serialized as a single string, even if its text is
+/// split in two by the gap.
+struct Chunk<'a>(GapSlice<'a>);
+
+impl fmt::Display for Chunk<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0.left_chunk())?;
+        f.write_str(self.0.right_chunk())
+    }
+}
+
+impl Serialize for Chunk<'_> {
+    #[inline]
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if self.0.len_right() == 0 {
+            serializer.serialize_str(self.0.left_chunk())
+        } else {
+            serializer.collect_str(self)
+        }
+    }
+}
+
+struct ChunksVisitor;
+
+impl<'de> Visitor<'de> for ChunksVisitor {
+    type Value = Rope;
+
+    #[inline]
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of chunks")
+    }
+
+    #[inline]
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> Result<Rope, A::Error> {
+        let mut chunks = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+        while let Some(chunk) = seq.next_element_seed(ChunkSeed)? {
+            chunks.push(chunk);
+        }
+
+        if chunks.len() > 1 {
+            if let Some(chunk) = chunks.iter().find(|chunk| {
+                chunk.is_empty() || chunk.len() < RopeChunk::chunk_min()
+            }) {
+                return Err(de::Error::invalid_length(
+                    chunk.len(),
+                    &ChunkSeed,
+                ));
+            }
+        }
+
+        let has_trailing_newline = chunks
+            .last()
+            .map_or(false, |chunk| ends_with_line_break(chunk.last_chunk()));
+
+        Ok(Rope::from_tree(Tree::from_leaves(chunks), has_trailing_newline))
+    }
+}
+
+/// Deserializes a single chunk, checking that it fits in a leaf.
+struct ChunkSeed;
+
+impl<'de> DeserializeSeed<'de> for ChunkSeed {
+    type Value = RopeChunk;
+
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<RopeChunk, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ChunkSeed {
+    type Value = RopeChunk;
+
+    #[inline]
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a chunk between {} and {} bytes long",
+            RopeChunk::chunk_min(),
+            RopeChunk::max_bytes()
+        )
+    }
+
+    #[inline]
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<RopeChunk, E> {
+        if s.len() > RopeChunk::max_bytes() {
+            return Err(E::invalid_length(s.len(), &self));
+        }
+
+        Ok(RopeChunk::from(s))
+    }
+}
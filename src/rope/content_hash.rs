@@ -0,0 +1,262 @@
+//! The content hash returned by [`Rope::content_hash()`].
+//!
+//! The hash is a polynomial hash of the bytes of the text, which can be
+//! computed piece by piece: if every piece of text is summarized by its hash
+//! `h` together with `B^len`, the pair of the concatenation of two pieces is
+//! `(h1 * B^len2 + h2, B^len1 * B^len2)`. The pairs can't be combined in any
+//! order, but the summaries of a `Rope` are only ever added up in the order
+//! of the text they summarize, so with the `content-hash` feature enabled
+//! every `ChunkSummary` stores one and the hash of a `Rope` can be read from
+//! the summary of its root.
+//!
+//! Summaries are also subtracted from each other, e.g. to update the summary
+//! of an internal node when one of its children changes, and the pair of the
+//! difference can't be computed without knowing where the subtracted text
+//! was. The difference is marked as stale instead, and after every edit the
+//! `Rope` recomputes the stale pairs from the chunks and the children that
+//! changed, which only touches the nodes on the paths to the edited chunks.
+//!
+//! Without the feature, or if the root's pair is stale, the hash is computed
+//! with a single pass over the chunks the first time it's asked for, and then
+//! cached until the next edit.
+//!
+//! [`Rope::content_hash()`]: super::Rope::content_hash()
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// The base of the polynomial hash, which has to be odd for its powers to
+/// never be zero.
+const BASE: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// `BASE^i` for every `i` in `0..=8`, used to hash 8 bytes at a time.
+const BASE_POWERS: [u64; 9] = {
+    let mut powers = [1u64; 9];
+    let mut i = 1;
+    while i < 9 {
+        powers[i] = powers[i - 1].wrapping_mul(BASE);
+        i += 1;
+    }
+    powers
+};
+
+/// Returns the 64-bit FNV-1a hash of the text made by concatenating the
+/// chunks, which doesn't depend on how the text is split into chunks.
+///
+/// The hash is never zero.
+#[inline]
+pub(super) fn fnv_hash_chunks<'a, C>(chunks: C) -> u64
+where
+    C: IntoIterator<Item = &'a str>,
+{
+    let hash = chunks
+        .into_iter()
+        .flat_map(str::bytes)
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+
+    hash.max(1)
+}
+
+/// Returns the content hash of the text made by concatenating the chunks,
+/// which doesn't depend on how the text is split into chunks.
+///
+/// The hash is never zero, which is used by the [`ContentHashCache`] to
+/// mean that there's no cached hash.
+#[inline]
+pub(super) fn hash_text_chunks<'a, C>(chunks: C) -> u64
+where
+    C: IntoIterator<Item = &'a str>,
+{
+    chunks
+        .into_iter()
+        .fold(PolyHash::default(), |hash, chunk| {
+            hash.concat(PolyHash::from(chunk))
+        })
+        .finish()
+}
+
+/// The polynomial hash of a piece of text, together with `BASE` raised to
+/// its length.
+///
+/// A stale hash compares equal to any other hash, so that summaries storing
+/// them can still be compared by their counts.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct PolyHash {
+    /// The sum of `(byte + 1) * BASE^i` over the bytes of the text, where
+    /// `i` is the number of bytes after the byte. Adding one to the bytes
+    /// makes leading zero bytes change the hash.
+    hash: u64,
+
+    /// `BASE^len`, which is always odd, or zero if the hash is stale.
+    power: u64,
+}
+
+impl Default for PolyHash {
+    #[inline]
+    fn default() -> Self {
+        Self { hash: 0, power: 1 }
+    }
+}
+
+impl From<&str> for PolyHash {
+    #[inline]
+    fn from(s: &str) -> Self {
+        let mut chunks = s.as_bytes().chunks_exact(8);
+
+        let mut hash = 0u64;
+
+        // Unrolling the loop lets the multiplications of the 8 bytes run in
+        // parallel instead of waiting for each other.
+        for chunk in &mut chunks {
+            let mut sum = 0u64;
+            for (i, &byte) in chunk.iter().enumerate() {
+                sum = sum.wrapping_add(
+                    (byte as u64 + 1).wrapping_mul(BASE_POWERS[7 - i]),
+                );
+            }
+            hash = hash.wrapping_mul(BASE_POWERS[8]).wrapping_add(sum);
+        }
+
+        for &byte in chunks.remainder() {
+            hash = hash.wrapping_mul(BASE).wrapping_add(byte as u64 + 1);
+        }
+
+        Self { hash, power: power_of_base(s.len()) }
+    }
+}
+
+impl PartialEq for PolyHash {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.is_stale()
+            || other.is_stale()
+            || (self.hash == other.hash && self.power == other.power)
+    }
+}
+
+impl PolyHash {
+    /// A hash that has to be recomputed before it can be used.
+    pub(super) const STALE: Self = Self { hash: 0, power: 0 };
+
+    /// Returns the hash of the text made by appending `rhs`'s text to
+    /// `self`'s, which is stale if either of them is.
+    #[inline]
+    pub(super) fn concat(self, rhs: Self) -> Self {
+        if self.is_stale() || rhs.is_stale() {
+            return Self::STALE;
+        }
+
+        Self {
+            hash: self.hash.wrapping_mul(rhs.power).wrapping_add(rhs.hash),
+            power: self.power.wrapping_mul(rhs.power),
+        }
+    }
+
+    /// Returns the final 64-bit hash, which is never zero. The hash must not
+    /// be stale.
+    #[inline]
+    pub(super) fn finish(self) -> u64 {
+        debug_assert!(!self.is_stale());
+
+        // The finalizer of MurmurHash3, which spreads the entropy of the low
+        // bytes of the polynomial hash across all of its bits.
+        let mut hash = self.hash;
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        hash ^= hash >> 33;
+        hash.max(1)
+    }
+
+    #[inline]
+    pub(super) fn is_stale(&self) -> bool {
+        self.power == 0
+    }
+}
+
+/// Returns `BASE^exp`.
+#[inline]
+fn power_of_base(mut exp: usize) -> u64 {
+    let mut base = BASE;
+    let mut power = 1u64;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            power = power.wrapping_mul(base);
+        }
+        base = base.wrapping_mul(base);
+        exp >>= 1;
+    }
+
+    power
+}
+
+/// The cached content hash of a `Rope`, or nothing if it hasn't been
+/// computed since the last edit.
+///
+/// The cache uses an atomic so that it can be filled in from a shared
+/// reference without making the `Rope` `!Sync`. On targets without 64-bit
+/// atomics nothing is cached, and the hash is recomputed every time.
+#[derive(Debug, Default)]
+pub(super) struct ContentHashCache {
+    /// The cached hash, where zero means that there's no cached hash.
+    #[cfg(target_has_atomic = "64")]
+    hash: core::sync::atomic::AtomicU64,
+}
+
+impl ContentHashCache {
+    #[inline]
+    pub(super) fn clear(&mut self) {
+        #[cfg(target_has_atomic = "64")]
+        {
+            *self.hash.get_mut() = 0;
+        }
+    }
+
+    /// Returns the cached hash, calling `compute` to fill in the cache if
+    /// it's empty.
+    #[inline]
+    pub(super) fn get_or_compute(&self, compute: impl FnOnce() -> u64) -> u64 {
+        #[cfg(target_has_atomic = "64")]
+        {
+            use core::sync::atomic::Ordering;
+
+            let cached = self.hash.load(Ordering::Relaxed);
+
+            if cached != 0 {
+                return cached;
+            }
+
+            let hash = compute();
+            debug_assert_ne!(hash, 0);
+            self.hash.store(hash, Ordering::Relaxed);
+            hash
+        }
+
+        #[cfg(not(target_has_atomic = "64"))]
+        {
+            compute()
+        }
+    }
+}
+
+impl Clone for ContentHashCache {
+    #[inline]
+    fn clone(&self) -> Self {
+        #[cfg(target_has_atomic = "64")]
+        {
+            use core::sync::atomic::{AtomicU64, Ordering};
+
+            Self { hash: AtomicU64::new(self.hash.load(Ordering::Relaxed)) }
+        }
+
+        #[cfg(not(target_has_atomic = "64"))]
+        {
+            Self {}
+        }
+    }
+}
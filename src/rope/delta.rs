@@ -0,0 +1,120 @@
+use alloc::vec::Vec;
+
+use super::EditInfo;
+
+/// A sequence of edits, each one relative to the text as it is after the
+/// edits before it.
+///
+/// A `Delta` describes how the byte offsets of a text move when it's edited,
+/// and can be used to map positions computed before the edits to their
+/// positions after them, e.g. with
+/// [`Selections::transform()`](crate::selections::Selections::transform()).
+/// It can be built by hand or by collecting the edits passed to the callbacks
+/// registered with [`Rope::subscribe()`](crate::Rope::subscribe()).
+///
+/// # Examples
+///
+/// ```
+/// # use crop::{Delta, EditInfo};
+/// #
+/// let mut delta = Delta::new();
+///
+/// // Insert 3 bytes at offset 0, then delete the 2 bytes after them.
+/// delta.push(EditInfo { byte_range: 0..0, inserted_len: 3 });
+/// delta.push(EditInfo { byte_range: 3..5, inserted_len: 0 });
+///
+/// assert_eq!(delta.len(), 2);
+/// assert_eq!(delta.map_offset(10), 11);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Delta {
+    edits: Vec<EditInfo>,
+}
+
+impl Delta {
+    /// Returns `true` if the delta doesn't contain any edits.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Returns an iterator over the edits of the delta, in the order in
+    /// which they're applied.
+    #[inline]
+    pub fn iter(&self) -> core::slice::Iter<'_, EditInfo> {
+        self.edits.iter()
+    }
+
+    /// Returns the number of edits in the delta.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    /// Maps a byte offset in the text before the edits to the corresponding
+    /// offset in the text after them.
+    ///
+    /// Offsets within a replaced range are moved past the inserted text.
+    #[inline]
+    pub fn map_offset(&self, byte_offset: usize) -> usize {
+        self.edits.iter().fold(byte_offset, map)
+    }
+
+    /// Creates a new, empty `Delta`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an edit to the delta. Its byte range is relative to the text
+    /// as it is after all the edits already in the delta.
+    #[inline]
+    pub fn push(&mut self, edit: EditInfo) {
+        self.edits.push(edit);
+    }
+}
+
+/// Moves a byte offset to its position after the given edit.
+#[inline]
+pub(super) fn map(offset: usize, edit: &EditInfo) -> usize {
+    let EditInfo { byte_range, inserted_len } = edit;
+
+    if offset < byte_range.start {
+        offset
+    } else if offset <= byte_range.end {
+        byte_range.start + inserted_len
+    } else {
+        offset - byte_range.len() + inserted_len
+    }
+}
+
+impl From<EditInfo> for Delta {
+    #[inline]
+    fn from(edit: EditInfo) -> Self {
+        Self { edits: alloc::vec![edit] }
+    }
+}
+
+impl FromIterator<EditInfo> for Delta {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = EditInfo>>(iter: I) -> Self {
+        Self { edits: iter.into_iter().collect() }
+    }
+}
+
+impl Extend<EditInfo> for Delta {
+    #[inline]
+    fn extend<I: IntoIterator<Item = EditInfo>>(&mut self, iter: I) {
+        self.edits.extend(iter);
+    }
+}
+
+impl<'a> IntoIterator for &'a Delta {
+    type Item = &'a EditInfo;
+    type IntoIter = core::slice::Iter<'a, EditInfo>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
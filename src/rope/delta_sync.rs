@@ -0,0 +1,339 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use super::content_hash::fnv_hash_chunks;
+use super::{Rope, RopeBuilder, RopeSlice};
+
+/// Blocks are never cut before this many bytes.
+const MIN_BLOCK_LEN: usize = 256;
+
+/// Blocks are always cut after this many bytes, or at the first code point
+/// boundary after it.
+const MAX_BLOCK_LEN: usize = 8192;
+
+/// The number of high bits of the gear hash that have to be zero for a block
+/// to end, which makes blocks `2^BOUNDARY_BITS` bytes long on average.
+const BOUNDARY_BITS: u32 = 10;
+
+/// The random values added to the gear hash for each byte.
+const GEAR: [u64; 256] = {
+    // SplitMix64, seeded with an arbitrary constant.
+    let mut state = 0x6372_6f70_6765_6172_u64;
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+};
+
+/// The signature of a single block of a [`Signature`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockSignature {
+    /// The byte length of the block.
+    pub len: usize,
+
+    /// The rsync rolling checksum of the block.
+    pub weak: u32,
+
+    /// The 64-bit FNV-1a hash of the block.
+    pub strong: u64,
+}
+
+/// The signature of a text, used to compute a [`SyncDelta`] turning it into
+/// another text without having access to it.
+///
+/// The text is split into content-defined blocks, i.e. blocks whose
+/// boundaries are chosen by a rolling hash over the last few bytes instead of
+/// being at fixed offsets, so that an edit only changes the blocks around it
+/// instead of shifting all the ones after it. Each block is then identified
+/// by its length and by a weak and a strong hash of its contents.
+///
+/// Blocks always start and end on code point boundaries.
+///
+/// This follows the usual rsync-style protocol:
+///
+/// 1. the side with the old text computes its signature with
+///    [`Signature::new()`] and sends it to the side with the new text;
+///
+/// 2. the side with the new text computes a delta against the signature with
+///    [`Signature::delta()`] and sends it back. The delta only contains the
+///    text of the blocks that aren't in the old text;
+///
+/// 3. the side with the old text applies the delta with
+///    [`SyncDelta::apply()`] to get the new text.
+///
+/// Neither hash is cryptographically secure, so this shouldn't be used when
+/// the two texts could be crafted to make different blocks collide.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::delta_sync::Signature;
+/// # use crop::Rope;
+/// #
+/// let text = (0..1000).map(|i| format!("Line {i}\n")).collect::<String>();
+/// let old = Rope::from(text);
+///
+/// let mut new = old.clone();
+/// new.insert(new.byte_of_line(500), "Hi there!\n");
+///
+/// let signature = Signature::new(old.byte_slice(..));
+/// let delta = signature.delta(new.byte_slice(..));
+/// assert!(delta.inserted_len() < new.byte_len() / 2);
+///
+/// let synced = delta.apply(old.byte_slice(..)).unwrap();
+/// assert_eq!(synced, new);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Signature {
+    blocks: Vec<BlockSignature>,
+}
+
+impl Signature {
+    /// Returns the signatures of the blocks, in the order in which they
+    /// appear in the text.
+    #[inline]
+    pub fn blocks(&self) -> &[BlockSignature] {
+        &self.blocks
+    }
+
+    /// Computes the delta turning the text this signature was computed from
+    /// into `new`.
+    #[inline]
+    pub fn delta(&self, new: RopeSlice<'_>) -> SyncDelta {
+        let mut starts = Vec::with_capacity(self.blocks.len());
+
+        let mut offsets = BTreeMap::new();
+
+        let mut offset = 0;
+
+        for block in &self.blocks {
+            starts.push(offset);
+            offsets.entry(*block).or_insert(offset);
+            offset += block.len;
+        }
+
+        let mut delta = SyncDelta::default();
+
+        let mut offset = 0;
+
+        for block in blocks(new) {
+            let range = offset..offset + block.len;
+
+            offset += block.len;
+
+            // If the same block appears more than once in the old text we
+            // prefer the one right after the last copied range, so that the
+            // two copies can be merged.
+            let continued = match delta.ops.last() {
+                Some(SyncOp::Copy(last)) => starts
+                    .binary_search(&last.end)
+                    .ok()
+                    .filter(|&idx| self.blocks[idx] == block)
+                    .map(|_| last.end),
+                _ => None,
+            };
+
+            let op = match continued.or_else(|| offsets.get(&block).copied()) {
+                Some(start) => SyncOp::Copy(start..start + block.len),
+                None => SyncOp::Insert(new.byte_slice(range).to_string()),
+            };
+
+            delta.push(op);
+        }
+
+        delta
+    }
+
+    /// Creates a signature from the signatures of its blocks, e.g. after
+    /// receiving them from the network.
+    #[inline]
+    pub fn from_blocks(blocks: Vec<BlockSignature>) -> Self {
+        Self { blocks }
+    }
+
+    /// Computes the signature of the given text.
+    #[inline]
+    pub fn new(text: RopeSlice<'_>) -> Self {
+        Self { blocks: blocks(text) }
+    }
+}
+
+/// A single operation of a [`SyncDelta`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SyncOp {
+    /// Copies a byte range of the old text.
+    Copy(Range<usize>),
+
+    /// Inserts some text that isn't in the old text.
+    Insert(String),
+}
+
+/// The error returned by [`SyncDelta::apply()`] when the delta doesn't match
+/// the text it's applied to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyncError {
+    /// A [`SyncOp::Copy`]'s range is out of bounds or doesn't lie on code
+    /// point boundaries of the text.
+    InvalidRange(Range<usize>),
+}
+
+impl core::fmt::Display for SyncError {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::InvalidRange(range) => {
+                write!(f, "the byte range {range:?} is not valid")
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SyncError {}
+
+/// The operations turning an old text into a new one, computed by
+/// [`Signature::delta()`].
+///
+/// Consecutive copies of adjacent ranges and consecutive insertions are
+/// always merged into a single operation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SyncDelta {
+    ops: Vec<SyncOp>,
+}
+
+impl SyncDelta {
+    /// Builds the new text by applying the delta to the old one.
+    #[inline]
+    pub fn apply(&self, old: RopeSlice<'_>) -> Result<Rope, SyncError> {
+        let mut builder = RopeBuilder::new();
+
+        for op in &self.ops {
+            match op {
+                SyncOp::Copy(range) => {
+                    if range.start > range.end
+                        || range.end > old.byte_len()
+                        || !old.is_char_boundary(range.start)
+                        || !old.is_char_boundary(range.end)
+                    {
+                        return Err(SyncError::InvalidRange(range.clone()));
+                    }
+
+                    for chunk in old.byte_slice(range.clone()).chunks() {
+                        builder.append(chunk);
+                    }
+                },
+
+                SyncOp::Insert(text) => {
+                    builder.append(text);
+                },
+            }
+        }
+
+        Ok(builder.build())
+    }
+
+    /// Creates a delta from its operations, e.g. after receiving them from
+    /// the network.
+    #[inline]
+    pub fn from_ops(ops: Vec<SyncOp>) -> Self {
+        let mut delta = Self::default();
+        ops.into_iter().for_each(|op| delta.push(op));
+        delta
+    }
+
+    /// Returns the combined byte length of the text inserted by the delta,
+    /// i.e. the text that had to be sent because it isn't in the old text.
+    #[inline]
+    pub fn inserted_len(&self) -> usize {
+        self.ops
+            .iter()
+            .map(|op| match op {
+                SyncOp::Copy(_) => 0,
+                SyncOp::Insert(text) => text.len(),
+            })
+            .sum()
+    }
+
+    /// Returns the operations of the delta, in the order in which they're
+    /// applied.
+    #[inline]
+    pub fn ops(&self) -> &[SyncOp] {
+        &self.ops
+    }
+
+    #[inline]
+    fn push(&mut self, op: SyncOp) {
+        match (self.ops.last_mut(), op) {
+            (Some(SyncOp::Copy(last)), SyncOp::Copy(range))
+                if last.end == range.start =>
+            {
+                last.end = range.end;
+            },
+
+            (Some(SyncOp::Insert(last)), SyncOp::Insert(text)) => {
+                last.push_str(&text);
+            },
+
+            (_, op) => self.ops.push(op),
+        }
+    }
+}
+
+/// Splits the text into content-defined blocks and returns their signatures.
+#[inline]
+fn blocks(text: RopeSlice<'_>) -> Vec<BlockSignature> {
+    let mut blocks = Vec::new();
+
+    let mut start = 0;
+    let mut len = 0;
+    let mut gear = 0u64;
+    let mut s1 = 0u32;
+    let mut s2 = 0u32;
+    let mut should_cut = false;
+
+    let mut push_block = |start: usize, len: usize, s1: u32, s2: u32| {
+        blocks.push(BlockSignature {
+            len,
+            weak: (s1 & 0xffff) | (s2 << 16),
+            strong: fnv_hash_chunks(
+                text.byte_slice(start..start + len).chunks(),
+            ),
+        });
+    };
+
+    for byte in text.chunks().flat_map(str::bytes) {
+        // Only cut before the first byte of a code point.
+        if should_cut && (byte & 0xc0) != 0x80 {
+            push_block(start, len, s1, s2);
+            start += len;
+            len = 0;
+            gear = 0;
+            s1 = 0;
+            s2 = 0;
+            should_cut = false;
+        }
+
+        len += 1;
+        gear = (gear << 1).wrapping_add(GEAR[byte as usize]);
+        s1 = s1.wrapping_add(byte as u32);
+        s2 = s2.wrapping_add(s1);
+
+        should_cut |= len >= MAX_BLOCK_LEN
+            || (len >= MIN_BLOCK_LEN && gear >> (64 - BOUNDARY_BITS) == 0);
+    }
+
+    if len > 0 {
+        push_block(start, len, s1, s2);
+    }
+
+    blocks
+}
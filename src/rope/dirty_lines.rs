@@ -0,0 +1,58 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// The set of lines touched by the edits performed on a
+/// [`Rope`](crate::Rope) since the last call to
+/// [`take_dirty_lines()`](crate::Rope::take_dirty_lines()), stored as a
+/// sorted list of disjoint, non-adjacent line ranges.
+#[derive(Clone, Debug, Default)]
+pub(super) struct DirtyLines {
+    ranges: Vec<Range<usize>>,
+}
+
+impl DirtyLines {
+    /// Records an edit which replaced the lines in `start..=old_end` with the
+    /// lines in `start..=new_end`.
+    ///
+    /// The ranges after the edit are shifted by the number of lines it added
+    /// or removed, and the ones overlapping it are merged with it.
+    #[inline]
+    pub(super) fn record(
+        &mut self,
+        start: usize,
+        old_end: usize,
+        new_end: usize,
+    ) {
+        let mut edited = start..new_end + 1;
+
+        // The index of the first range touching the edited lines.
+        let first = self.ranges.partition_point(|r| r.end < start);
+
+        // One past the index of the last range touching the edited lines.
+        let last = first
+            + self.ranges[first..].partition_point(|r| r.start <= old_end + 1);
+
+        if let Some(r) = self.ranges.get(first).filter(|_| first < last) {
+            edited.start = edited.start.min(r.start);
+        }
+
+        if let Some(r) = last.checked_sub(1).map(|i| &self.ranges[i]) {
+            if first < last && r.end > old_end + 1 {
+                edited.end = r.end + new_end - old_end;
+            }
+        }
+
+        for range in &mut self.ranges[last..] {
+            range.start = range.start + new_end - old_end;
+            range.end = range.end + new_end - old_end;
+        }
+
+        self.ranges.splice(first..last, core::iter::once(edited));
+    }
+
+    /// Returns the dirty line ranges, leaving the set empty.
+    #[inline]
+    pub(super) fn take(&mut self) -> Vec<Range<usize>> {
+        core::mem::take(&mut self.ranges)
+    }
+}
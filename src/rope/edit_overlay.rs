@@ -0,0 +1,215 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::{Range, RangeBounds};
+
+use super::multi_rope::MultiRope;
+use super::utils::panic_messages as panic;
+use super::{Delta, Rope, RopeSlice};
+use crate::range_bounds_to_start_end;
+
+/// A set of pending edits layered on top of a base [`Rope`], which can be
+/// read through as if they had been applied and then either committed or
+/// discarded.
+///
+/// The overlay holds a cheap clone of the base `Rope` and the text inserted
+/// by every edit, and the combined document is presented as a [`MultiRope`]
+/// made of slices of the two, like in a piece table. The byte ranges of the
+/// edits always refer to the base `Rope`, and they can be added in any order
+/// as long as they don't overlap.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::edit_overlay::EditOverlay;
+/// # use crop::Rope;
+/// #
+/// let mut r = Rope::from("let foo = foo + 1;");
+///
+/// // Preview renaming every `foo` to `bar`.
+/// let mut overlay = EditOverlay::new(&r);
+/// overlay.replace(10..13, "bar");
+/// overlay.replace(4..7, "bar");
+///
+/// assert_eq!(overlay.view(), "let bar = bar + 1;");
+/// assert_eq!(r, "let foo = foo + 1;");
+///
+/// overlay.commit(&mut r);
+/// assert_eq!(r, "let bar = bar + 1;");
+/// ```
+#[derive(Clone, Debug)]
+pub struct EditOverlay {
+    base: Rope,
+
+    /// The pending edits, sorted by their byte range in the base `Rope`.
+    edits: Vec<(Range<usize>, Rope)>,
+}
+
+impl EditOverlay {
+    /// Returns the `Rope` the edits are layered on.
+    #[inline]
+    pub fn base(&self) -> &Rope {
+        &self.base
+    }
+
+    /// Applies the pending edits to the given `Rope` with
+    /// [`Rope::apply_edits()`], returning the [`Delta`] describing them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [`revision()`](Rope::revision()) of the `Rope` differs
+    /// from the one of the base, e.g. because the `Rope` has been edited
+    /// since the overlay was created from it.
+    #[track_caller]
+    #[inline]
+    pub fn commit(self, rope: &mut Rope) -> Delta {
+        assert!(
+            rope.revision() == self.base.revision(),
+            "can't commit an edit overlay to a rope that's different from \
+             its base"
+        );
+
+        let edits = self
+            .edits
+            .iter()
+            .map(|(byte_range, text)| (byte_range.clone(), text.to_string()))
+            .collect::<Vec<(Range<usize>, String)>>();
+
+        rope.apply_edits(&edits)
+    }
+
+    /// Adds a pending edit deleting the text in the given byte range of the
+    /// base `Rope`.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`replace()`](Self::replace()).
+    #[track_caller]
+    #[inline]
+    pub fn delete<R>(&mut self, byte_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.replace(byte_range, "");
+    }
+
+    /// Returns an iterator over the pending edits, sorted by their byte
+    /// range in the base `Rope`, and the text they insert.
+    #[inline]
+    pub fn edits(
+        &self,
+    ) -> impl ExactSizeIterator<Item = (Range<usize>, RopeSlice<'_>)> {
+        self.edits.iter().map(|(byte_range, text)| {
+            (byte_range.clone(), text.byte_slice(..))
+        })
+    }
+
+    /// Adds a pending edit inserting `text` at the given byte offset of the
+    /// base `Rope`.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`replace()`](Self::replace()).
+    #[track_caller]
+    #[inline]
+    pub fn insert<T>(&mut self, byte_offset: usize, text: T)
+    where
+        T: AsRef<str>,
+    {
+        self.replace(byte_offset..byte_offset, text);
+    }
+
+    /// Returns a new `Rope` containing the base `Rope` with all the pending
+    /// edits applied, sharing the nodes of both.
+    #[inline]
+    pub fn into_rope(self) -> Rope {
+        let mut rope = Rope::new();
+
+        for piece in self.view().pieces() {
+            rope.append(Rope::from(*piece));
+        }
+
+        rope
+    }
+
+    /// Returns `true` if there are no pending edits.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Returns the number of pending edits.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    /// Creates a new `EditOverlay` with no pending edits on top of the given
+    /// `Rope`.
+    #[inline]
+    pub fn new(base: &Rope) -> Self {
+        Self { base: base.clone(), edits: Vec::new() }
+    }
+
+    /// Adds a pending edit replacing the text in the given byte range of the
+    /// base `Rope` with `text`.
+    ///
+    /// Edits are allowed to touch but not to overlap, and insertions at the
+    /// same offset end up in the order in which they're added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte range would cause [`Rope::replace()`] to panic on
+    /// the base `Rope`, or if it overlaps the range of another pending edit.
+    #[track_caller]
+    #[inline]
+    pub fn replace<R, T>(&mut self, byte_range: R, text: T)
+    where
+        R: RangeBounds<usize>,
+        T: AsRef<str>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.base.byte_len());
+
+        // Slicing takes care of checking the range.
+        let _ = self.base.byte_slice(start..end);
+
+        let idx = self.edits.partition_point(|(byte_range, _)| {
+            (byte_range.start, byte_range.end) <= (start, end)
+                || byte_range.end <= start
+        });
+
+        if let Some((previous, _)) = idx.checked_sub(1).map(|i| &self.edits[i])
+        {
+            if previous.end > start {
+                panic::byte_ranges_overlap(previous.end, start);
+            }
+        }
+
+        if let Some((next, _)) = self.edits.get(idx) {
+            if next.start < end {
+                panic::byte_ranges_overlap(end, next.start);
+            }
+        }
+
+        self.edits.insert(idx, (start..end, Rope::from(text.as_ref())));
+    }
+
+    /// Returns the combined document, i.e. the base `Rope` with all the
+    /// pending edits applied.
+    #[inline]
+    pub fn view(&self) -> MultiRope<'_> {
+        let mut view = MultiRope::new();
+
+        let mut offset = 0;
+
+        for (byte_range, text) in &self.edits {
+            view.push(self.base.byte_slice(offset..byte_range.start));
+            view.push(text);
+            offset = byte_range.end;
+        }
+
+        view.push(self.base.byte_slice(offset..));
+
+        view
+    }
+}
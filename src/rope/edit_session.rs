@@ -0,0 +1,216 @@
+use alloc::string::String;
+
+use super::utils::panic_messages as panic;
+use super::Rope;
+
+/// A burst of edits performed at a single position of a [`Rope`], like the
+/// ones made by a user typing.
+///
+/// Inserting text in a `Rope` descends its tree from the root to the leaf
+/// containing the insertion point, copying the nodes on the way down if
+/// they're shared with a clone, and then updates the summaries of all of
+/// them on the way back up. Doing that for every keystroke adds up, so an
+/// `EditSession` instead collects the text inserted at its cursor in a
+/// buffer, and only inserts it in the `Rope` once the session is flushed,
+/// which happens when:
+///
+/// - the cursor is moved with [`move_to()`](Self::move_to());
+///
+/// - the `Rope` is accessed with [`rope()`](Self::rope());
+///
+/// - the session is dropped.
+///
+/// This makes every insertion and backspace within the pending text take
+/// `O(1)` amortized time. Since the pending text is inserted with a single
+/// edit, the marks, subscribers and other state of the `Rope` see a single
+/// edit per flush instead of one per keystroke.
+///
+/// This struct is created by [`Rope::edit_session()`]. See its documentation
+/// for more.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::Rope;
+/// #
+/// let mut r = Rope::from("Hello !");
+///
+/// let mut session = r.edit_session(6);
+///
+/// for ch in "Wordl".chars() {
+///     session.insert_char(ch);
+/// }
+///
+/// session.backspace();
+/// session.backspace();
+/// session.insert("ld");
+///
+/// assert_eq!(session.rope(), "Hello World!");
+///
+/// drop(session);
+///
+/// assert_eq!(r, "Hello World!");
+/// ```
+pub struct EditSession<'a> {
+    rope: &'a mut Rope,
+
+    /// The byte offset in the `Rope` where the pending text will be
+    /// inserted.
+    start: usize,
+
+    /// The text inserted since the session was last flushed.
+    pending: String,
+}
+
+impl<'a> EditSession<'a> {
+    /// Deletes the [`char`] before the cursor, returning it, or returns
+    /// `None` if the cursor is at the start of the `Rope`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("bär");
+    ///
+    /// let mut session = r.edit_session(3);
+    ///
+    /// session.insert("z");
+    /// assert_eq!(session.backspace(), Some('z'));
+    /// assert_eq!(session.backspace(), Some('ä'));
+    /// assert_eq!(session.offset(), 1);
+    ///
+    /// drop(session);
+    ///
+    /// assert_eq!(r, "br");
+    /// ```
+    #[inline]
+    pub fn backspace(&mut self) -> Option<char> {
+        if let Some(ch) = self.pending.pop() {
+            return Some(ch);
+        }
+
+        let ch = self.rope.byte_slice(..self.start).chars().next_back()?;
+        self.start -= ch.len_utf8();
+        self.rope.delete(self.start..self.start + ch.len_utf8());
+        Some(ch)
+    }
+
+    /// Inserts the pending text in the `Rope`.
+    #[inline]
+    fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            let byte_len = self.rope.byte_len() + self.pending.len();
+
+            self.rope.insert(self.start, &self.pending);
+            self.start += self.pending.len();
+            self.pending.clear();
+
+            // If the insertion made the `Rope` exceed its limit, the excess
+            // text was deleted from its start, which moves the cursor back
+            // by the same amount.
+            let trimmed = byte_len - self.rope.byte_len();
+            self.start = self.start.saturating_sub(trimmed);
+        }
+    }
+
+    /// Inserts the given text at the cursor, moving the cursor after it.
+    #[inline]
+    pub fn insert<T>(&mut self, text: T)
+    where
+        T: AsRef<str>,
+    {
+        self.pending.push_str(text.as_ref());
+    }
+
+    /// Inserts a single character at the cursor, moving the cursor after
+    /// it.
+    #[inline]
+    pub fn insert_char(&mut self, ch: char) {
+        self.pending.push(ch);
+    }
+
+    /// Moves the cursor to the given byte offset, flushing the pending text
+    /// first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than the
+    /// byte length of the `Rope` after the pending text is inserted) or if
+    /// it's not a char boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("foo");
+    ///
+    /// let mut session = r.edit_session(0);
+    ///
+    /// session.insert("bar");
+    /// session.move_to(6);
+    /// session.insert("baz");
+    ///
+    /// drop(session);
+    ///
+    /// assert_eq!(r, "barfoobaz");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn move_to(&mut self, byte_offset: usize) {
+        self.flush();
+        assert_char_boundary(self.rope, byte_offset);
+        self.start = byte_offset;
+    }
+
+    #[track_caller]
+    #[inline]
+    pub(super) fn new(rope: &'a mut Rope, byte_offset: usize) -> Self {
+        assert_char_boundary(rope, byte_offset);
+        Self { rope, start: byte_offset, pending: String::new() }
+    }
+
+    /// Returns the byte offset of the cursor, which includes the length of
+    /// the pending text.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.start + self.pending.len()
+    }
+
+    /// Flushes the pending text and returns the `Rope` being edited.
+    #[inline]
+    pub fn rope(&mut self) -> &Rope {
+        self.flush();
+        self.rope
+    }
+}
+
+impl Drop for EditSession<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl core::fmt::Debug for EditSession<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("EditSession")
+            .field("offset", &self.offset())
+            .field("pending", &self.pending)
+            .finish_non_exhaustive()
+    }
+}
+
+#[track_caller]
+#[inline]
+fn assert_char_boundary(rope: &Rope, byte_offset: usize) {
+    if byte_offset > rope.byte_len() {
+        panic::byte_offset_out_of_bounds(byte_offset, rope.byte_len());
+    }
+
+    // Slicing takes care of panicking with a nice message if the offset
+    // isn't a char boundary.
+    let _ = rope.byte_slice(byte_offset..);
+}
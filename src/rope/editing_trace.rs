@@ -0,0 +1,328 @@
+//! This module contains the [`EditingTrace`] type, a recording of the edits
+//! performed on a document that can be replayed on a [`Rope`].
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use super::Rope;
+
+/// A single edit of an [`EditingTrace`], deleting `del` characters starting
+/// at `pos` and then inserting `ins` there.
+///
+/// Both `pos` and `del` are measured in Unicode scalar values (i.e.
+/// [`char`]s), and not in bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TracePatch {
+    /// The char offset where the edit happens.
+    pub pos: usize,
+
+    /// The number of chars deleted.
+    pub del: usize,
+
+    /// The text inserted.
+    pub ins: String,
+}
+
+/// A recording of the edits performed on a document, e.g. by someone typing
+/// in an editor, which can be replayed on a [`Rope`].
+///
+/// Traces are usually deserialized from JSON. Both the format used by the
+/// [editing-traces] and [crdt-benchmarks] repositories, i.e.
+///
+/// ```json
+/// {
+///     "startContent": "",
+///     "endContent": "Hi!",
+///     "txns": [{ "patches": [[0, 0, "Hi"], [2, 0, "!"]] }]
+/// }
+/// ```
+///
+/// and the older [automerge-perf] format, i.e.
+///
+/// ```json
+/// { "edits": [[0, 0, "H"], [1, 0, "i"], [2, 0, "!"]], "finalText": "Hi!" }
+/// ```
+///
+/// are supported. Any other field is ignored, and the patches of all the
+/// transactions are flattened into a single list.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::editing_trace::EditingTrace;
+/// #
+/// let json = r#"{
+///     "startContent": "Hello",
+///     "endContent": "Hello, wörld!",
+///     "txns": [{ "patches": [[5, 0, ", world"], [8, 1, "ö"], [12, 0, "!"]] }]
+/// }"#;
+///
+/// let trace: EditingTrace = serde_json::from_str(json).unwrap();
+///
+/// assert_eq!(trace.patches.len(), 3);
+/// assert_eq!(trace.replay(), trace.end_content);
+/// ```
+///
+/// [editing-traces]: https://github.com/josephg/editing-traces
+/// [crdt-benchmarks]: https://github.com/dmonad/crdt-benchmarks
+/// [automerge-perf]: https://github.com/automerge/automerge-perf
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EditingTrace {
+    /// The text of the document before the first edit.
+    pub start_content: String,
+
+    /// The text of the document after the last edit.
+    pub end_content: String,
+
+    /// The edits, in the order in which they were performed.
+    pub patches: Vec<TracePatch>,
+}
+
+impl EditingTrace {
+    /// Returns the edits of the trace with their positions converted to byte
+    /// offsets, as `(deleted byte range, inserted text)` pairs.
+    ///
+    /// The edits can then be applied with [`Rope::replace()`] on a `Rope`
+    /// created from the [`start_content`](Self::start_content), which is
+    /// useful when benchmarking since it doesn't include the cost of
+    /// converting the positions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::editing_trace::{EditingTrace, TracePatch};
+    /// # use crop::Rope;
+    /// #
+    /// let trace = EditingTrace {
+    ///     start_content: "ñ".into(),
+    ///     end_content: "ña".into(),
+    ///     patches: vec![TracePatch { pos: 1, del: 0, ins: "a".into() }],
+    /// };
+    ///
+    /// let edits = trace.byte_edits();
+    /// assert_eq!(edits, [(2..2, "a")]);
+    ///
+    /// let mut r = Rope::from(&*trace.start_content);
+    ///
+    /// for (range, text) in edits {
+    ///     r.replace(range, text);
+    /// }
+    ///
+    /// assert_eq!(r, trace.end_content);
+    /// ```
+    #[inline]
+    pub fn byte_edits(&self) -> Vec<(Range<usize>, &str)> {
+        let mut edits = Vec::with_capacity(self.patches.len());
+        self.replay_with(|range, text| edits.push((range, text)));
+        edits
+    }
+
+    /// Replays the trace on a `Rope` created from the
+    /// [`start_content`](Self::start_content), and returns it.
+    ///
+    /// If the trace is valid the returned `Rope` is equal to the
+    /// [`end_content`](Self::end_content).
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the edits is out of bounds.
+    #[inline]
+    pub fn replay(&self) -> Rope {
+        self.replay_with(|_, _| {})
+    }
+
+    /// Replays the trace, calling `on_edit` with the byte range and the text
+    /// of every edit before applying it.
+    #[track_caller]
+    #[inline]
+    fn replay_with<'a>(
+        &'a self,
+        mut on_edit: impl FnMut(Range<usize>, &'a str),
+    ) -> Rope {
+        let mut rope = Rope::from(&*self.start_content);
+
+        // A char offset and the corresponding byte offset in the current
+        // text, which stays valid after an edit at or after it. Edits tend to
+        // be close to each other, so converting the position of an edit only
+        // has to walk the chars between it and the previous one.
+        let mut cursor = (0, 0);
+
+        for TracePatch { pos, del, ins } in &self.patches {
+            let start = char_to_byte(&rope, cursor, *pos);
+            let end = char_to_byte(&rope, (*pos, start), pos + del);
+            on_edit(start..end, ins);
+            rope.replace(start..end, ins);
+            cursor = (*pos, start);
+        }
+
+        rope
+    }
+}
+
+/// Converts the char offset `char_offset` of the `Rope` into a byte offset,
+/// starting from a known `(char offset, byte offset)` pair.
+#[track_caller]
+#[inline]
+fn char_to_byte(
+    rope: &Rope,
+    (from_char, from_byte): (usize, usize),
+    char_offset: usize,
+) -> usize {
+    if rope.is_ascii() {
+        return char_offset;
+    }
+
+    if char_offset >= from_char {
+        let mut chars = rope.byte_slice(from_byte..).chars();
+
+        (from_char..char_offset).fold(from_byte, |offset, _| {
+            offset
+                + chars
+                    .next()
+                    .expect("the char offset is out of bounds")
+                    .len_utf8()
+        })
+    } else {
+        let mut chars = rope.byte_slice(..from_byte).chars().rev();
+
+        (char_offset..from_char).fold(from_byte, |offset, _| {
+            offset - chars.next().unwrap().len_utf8()
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for EditingTrace {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(EditingTraceVisitor)
+    }
+}
+
+struct EditingTraceVisitor;
+
+impl<'de> Visitor<'de> for EditingTraceVisitor {
+    type Value = EditingTrace;
+
+    #[inline]
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an editing trace")
+    }
+
+    #[inline]
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> Result<EditingTrace, A::Error> {
+        let mut trace = EditingTrace::default();
+
+        while let Some(key) = map.next_key::<String>()? {
+            match &*key {
+                "startContent" => trace.start_content = map.next_value()?,
+
+                "endContent" | "finalText" => {
+                    trace.end_content = map.next_value()?
+                },
+
+                "txns" => {
+                    let txns = map.next_value::<Vec<Transaction>>()?;
+                    trace.patches.extend(txns.into_iter().flat_map(|t| t.0));
+                },
+
+                "edits" => trace.patches.extend(map.next_value::<Vec<_>>()?),
+
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                },
+            }
+        }
+
+        Ok(trace)
+    }
+}
+
+/// A transaction of the editing-traces format, of which only the patches are
+/// kept.
+struct Transaction(Vec<TracePatch>);
+
+impl<'de> Deserialize<'de> for Transaction {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(TransactionVisitor)
+    }
+}
+
+struct TransactionVisitor;
+
+impl<'de> Visitor<'de> for TransactionVisitor {
+    type Value = Transaction;
+
+    #[inline]
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a transaction")
+    }
+
+    #[inline]
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> Result<Transaction, A::Error> {
+        let mut patches = Vec::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "patches" {
+                patches = map.next_value()?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        Ok(Transaction(patches))
+    }
+}
+
+impl<'de> Deserialize<'de> for TracePatch {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(TracePatchVisitor)
+    }
+}
+
+struct TracePatchVisitor;
+
+impl<'de> Visitor<'de> for TracePatchVisitor {
+    type Value = TracePatch;
+
+    #[inline]
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a [pos, del, ins] tuple, where ins is optional")
+    }
+
+    #[inline]
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> Result<TracePatch, A::Error> {
+        let pos = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+        let del = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        let ins = seq.next_element()?.unwrap_or_default();
+
+        Ok(TracePatch { pos, del, ins })
+    }
+}
@@ -0,0 +1,133 @@
+use alloc::vec::Vec;
+
+use super::utils::panic_messages as panic;
+use super::{Rope, RopeSlice};
+
+/// A view over the lines of a [`Rope`] matching a predicate, created by
+/// [`Rope::filter_lines()`].
+///
+/// The view only stores the indices of the matching lines, so the text is
+/// never copied. Its lines are indexed from zero like the ones of the `Rope`,
+/// and can be mapped back and forth to the lines of the `Rope` with
+/// [`original_line()`](Self::original_line()) and
+/// [`filtered_line()`](Self::filtered_line()).
+///
+/// # Examples
+///
+/// ```
+/// # use crop::Rope;
+/// #
+/// let r = Rope::from("INFO foo\nERROR bar\nINFO baz\nERROR qux\n");
+///
+/// let errors = r.filter_lines(|line| line.strip_prefix("ERROR").is_some());
+///
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors.line(1), "ERROR qux");
+/// assert_eq!(errors.original_line(1), 3);
+/// assert_eq!(errors.filtered_line(1), Some(0));
+/// assert_eq!(errors.filtered_line(2), None);
+/// ```
+#[derive(Clone)]
+pub struct FilteredLines<'a> {
+    rope: &'a Rope,
+
+    /// The sorted indices of the lines of the `Rope` in the view.
+    line_indices: Vec<usize>,
+}
+
+impl<'a> FilteredLines<'a> {
+    /// Returns the index in the view of the given line of the `Rope`, or
+    /// `None` if that line isn't in the view.
+    #[inline]
+    pub fn filtered_line(&self, original_line: usize) -> Option<usize> {
+        self.line_indices.binary_search(&original_line).ok()
+    }
+
+    /// Returns `true` if none of the lines of the `Rope` are in the view.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.line_indices.is_empty()
+    }
+
+    /// Returns an iterator over the lines in the view, each one paired with
+    /// its index in the `Rope`.
+    #[inline]
+    pub fn iter(
+        &self,
+    ) -> impl ExactSizeIterator<Item = (usize, RopeSlice<'a>)> + '_ {
+        let rope = self.rope;
+
+        self.line_indices
+            .iter()
+            .map(move |&line_index| (line_index, rope.line(line_index)))
+    }
+
+    /// Returns the line at the given index in the view, without its line
+    /// break.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds (i.e. greater than or equal to
+    /// [`len()`](Self::len())).
+    #[track_caller]
+    #[inline]
+    pub fn line(&self, line_index: usize) -> RopeSlice<'a> {
+        self.rope.line(self.original_line(line_index))
+    }
+
+    /// Returns the number of lines in the view.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.line_indices.len()
+    }
+
+    #[inline]
+    pub(super) fn new<F>(rope: &'a Rope, mut predicate: F) -> Self
+    where
+        F: FnMut(RopeSlice<'_>) -> bool,
+    {
+        let line_indices = rope
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| predicate(line).then_some(idx))
+            .collect();
+
+        Self { rope, line_indices }
+    }
+
+    /// Returns the index in the `Rope` of the line at the given index in the
+    /// view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds (i.e. greater than or equal to
+    /// [`len()`](Self::len())).
+    #[track_caller]
+    #[inline]
+    pub fn original_line(&self, line_index: usize) -> usize {
+        match self.line_indices.get(line_index) {
+            Some(&original_line) => original_line,
+            None => panic::line_index_out_of_bounds(line_index, self.len()),
+        }
+    }
+
+    /// Returns the indices in the `Rope` of all the lines in the view, in
+    /// increasing order.
+    #[inline]
+    pub fn original_lines(&self) -> &[usize] {
+        &self.line_indices
+    }
+
+    /// Returns the `Rope` the view is over.
+    #[inline]
+    pub fn rope(&self) -> &'a Rope {
+        self.rope
+    }
+}
+
+impl core::fmt::Debug for FilteredLines<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,264 @@
+use alloc::vec::Vec;
+use core::ops::{Range, RangeBounds};
+
+use super::multi_rope::MultiRope;
+use super::utils::panic_messages as panic;
+use super::Rope;
+use crate::range_bounds_to_start_end;
+
+/// A read-only projection of a [`Rope`] hiding a set of folded byte ranges,
+/// which presents the remaining text as a single contiguous document.
+///
+/// The visible text is exposed as a [`MultiRope`] by
+/// [`view()`](Self::view()), so it can be sliced, iterated over and searched
+/// without knowing about the folds, and offsets can be mapped between the
+/// `Rope` and the view with [`to_view_offset()`](Self::to_view_offset()) and
+/// [`to_rope_offset()`](Self::to_rope_offset()).
+///
+/// Overlapping or touching folds are merged together, so the folds returned
+/// by [`folds()`](Self::folds()) are always sorted and disjoint.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::folded_view::FoldedView;
+/// # use crop::Rope;
+/// #
+/// let r = Rope::from("fn foo() {\n    bar();\n}\n");
+///
+/// let mut folded = FoldedView::new(&r);
+/// folded.fold(10..22);
+///
+/// assert_eq!(folded.view(), "fn foo() {}\n");
+/// assert_eq!(folded.to_view_offset(24), 12);
+/// assert_eq!(folded.to_view_offset(15), 10);
+/// assert_eq!(folded.to_rope_offset(10), 10);
+/// assert_eq!(folded.to_rope_offset(11), 23);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FoldedView<'a> {
+    rope: &'a Rope,
+
+    /// The sorted, disjoint and non-touching folded byte ranges.
+    folds: Vec<Range<usize>>,
+
+    /// The byte offset in the view at which every fold is hidden.
+    view_offsets: Vec<usize>,
+
+    /// The visible text.
+    view: MultiRope<'a>,
+}
+
+impl<'a> FoldedView<'a> {
+    /// Hides the text in the given byte range of the `Rope`, merging the new
+    /// fold with any existing fold it overlaps or touches. Folding an empty
+    /// range has no effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start is greater than the end or if the end is out of
+    /// bounds (i.e. greater than the byte length of the `Rope`), or if either
+    /// of them doesn't lie on a code point boundary.
+    #[track_caller]
+    #[inline]
+    pub fn fold<R>(&mut self, byte_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        let (mut start, mut end) =
+            range_bounds_to_start_end(byte_range, 0, self.rope.byte_len());
+
+        // Slicing takes care of checking the range.
+        let _ = self.rope.byte_slice(start..end);
+
+        if start == end {
+            return;
+        }
+
+        let first = self.folds.partition_point(|fold| fold.end < start);
+        let last = self.folds.partition_point(|fold| fold.start <= end);
+
+        if first < last {
+            start = start.min(self.folds[first].start);
+            end = end.max(self.folds[last - 1].end);
+        }
+
+        self.folds.splice(first..last, core::iter::once(start..end));
+
+        self.update();
+    }
+
+    /// Returns the fold hiding the byte at the given index, or `None` if
+    /// that byte is visible.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte index is out of bounds (i.e. greater than or equal
+    /// to the byte length of the `Rope`).
+    #[track_caller]
+    #[inline]
+    pub fn fold_of_byte(&self, byte_index: usize) -> Option<Range<usize>> {
+        if byte_index >= self.rope.byte_len() {
+            panic::byte_index_out_of_bounds(byte_index, self.rope.byte_len());
+        }
+
+        let idx = self.folds.partition_point(|fold| fold.end <= byte_index);
+
+        self.folds.get(idx).filter(|fold| fold.start <= byte_index).cloned()
+    }
+
+    /// Returns the folded byte ranges of the `Rope`, sorted and disjoint.
+    #[inline]
+    pub fn folds(&self) -> &[Range<usize>] {
+        &self.folds
+    }
+
+    /// Returns the number of bytes hidden by the folds before the one at the
+    /// given index, or by all the folds if the index is out of bounds.
+    #[inline]
+    fn hidden_before(&self, fold_idx: usize) -> usize {
+        match self.folds.get(fold_idx) {
+            Some(fold) => fold.start - self.view_offsets[fold_idx],
+            None => self.rope.byte_len() - self.view.byte_len(),
+        }
+    }
+
+    /// Creates a new `FoldedView` over the given `Rope`, with no folds.
+    #[inline]
+    pub fn new(rope: &'a Rope) -> Self {
+        let mut folded = Self {
+            rope,
+            folds: Vec::new(),
+            view_offsets: Vec::new(),
+            view: MultiRope::new(),
+        };
+        folded.update();
+        folded
+    }
+
+    /// Returns the `Rope` the view is over.
+    #[inline]
+    pub fn rope(&self) -> &'a Rope {
+        self.rope
+    }
+
+    /// Maps a byte offset in the view to the corresponding byte offset in
+    /// the `Rope`.
+    ///
+    /// An offset at the position of a fold is mapped to the start of the
+    /// fold, i.e. before the hidden text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset is out of bounds (i.e. greater than the byte
+    /// length of the view).
+    #[track_caller]
+    #[inline]
+    pub fn to_rope_offset(&self, view_offset: usize) -> usize {
+        if view_offset > self.view.byte_len() {
+            panic::byte_offset_out_of_bounds(
+                view_offset,
+                self.view.byte_len(),
+            );
+        }
+
+        let idx =
+            self.view_offsets.partition_point(|&offset| offset < view_offset);
+
+        view_offset + self.hidden_before(idx)
+    }
+
+    /// Maps a byte offset in the `Rope` to the corresponding byte offset in
+    /// the view.
+    ///
+    /// An offset inside a fold is mapped to the position of the fold in the
+    /// view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset is out of bounds (i.e. greater than the byte
+    /// length of the `Rope`).
+    #[track_caller]
+    #[inline]
+    pub fn to_view_offset(&self, byte_offset: usize) -> usize {
+        if byte_offset > self.rope.byte_len() {
+            panic::byte_offset_out_of_bounds(
+                byte_offset,
+                self.rope.byte_len(),
+            );
+        }
+
+        let idx = self.folds.partition_point(|fold| fold.end <= byte_offset);
+
+        match self.folds.get(idx) {
+            Some(fold) if fold.start < byte_offset => self.view_offsets[idx],
+            _ => byte_offset - self.hidden_before(idx),
+        }
+    }
+
+    /// Makes the text in the given byte range of the `Rope` visible again,
+    /// shrinking or splitting the folds it overlaps. Unfolding an empty range
+    /// has no effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`fold()`](Self::fold()).
+    #[track_caller]
+    #[inline]
+    pub fn unfold<R>(&mut self, byte_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.rope.byte_len());
+
+        // Slicing takes care of checking the range.
+        let _ = self.rope.byte_slice(start..end);
+
+        if start == end {
+            return;
+        }
+
+        let first = self.folds.partition_point(|fold| fold.end <= start);
+        let last = self.folds.partition_point(|fold| fold.start < end);
+
+        if first == last {
+            return;
+        }
+
+        let before = self.folds[first].start..start;
+        let after = end..self.folds[last - 1].end;
+
+        self.folds.splice(
+            first..last,
+            [before, after].into_iter().filter(|fold| fold.start < fold.end),
+        );
+
+        self.update();
+    }
+
+    /// Returns the visible text, i.e. the text of the `Rope` outside of the
+    /// folds.
+    #[inline]
+    pub fn view(&self) -> &MultiRope<'a> {
+        &self.view
+    }
+
+    /// Recomputes the visible text and the view offsets of the folds after
+    /// the folds have changed.
+    #[inline]
+    fn update(&mut self) {
+        self.view_offsets.clear();
+        self.view = MultiRope::new();
+
+        let mut offset = 0;
+
+        for fold in &self.folds {
+            self.view.push(self.rope.byte_slice(offset..fold.start));
+            self.view_offsets.push(self.view.byte_len());
+            offset = fold.end;
+        }
+
+        self.view.push(self.rope.byte_slice(offset..));
+    }
+}
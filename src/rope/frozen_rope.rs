@@ -0,0 +1,168 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use super::metrics::ChunkSummary;
+use super::{Rope, RopeRef};
+
+/// An immutable, packed copy of a [`Rope`]'s text, meant to be shared
+/// between threads or serialized.
+///
+/// A `FrozenRope` stores its text in a single contiguous block, next to a
+/// flat index with the summaries of the text before every few kilobytes of
+/// it, which takes a fraction of the memory of a `Rope`'s tree. Both are
+/// reference-counted, so cloning a `FrozenRope` is cheap, and it can be
+/// queried for byte, line and UTF-16 offsets in logarithmic time through
+/// the [`RopeRef`] returned by [`as_rope_ref()`](Self::as_rope_ref()).
+///
+/// This makes it a good fit for the copies of a document that will never be
+/// edited, e.g. the text being saved or analyzed on a background thread.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::{FrozenRope, Rope};
+/// #
+/// let r = Rope::from("foo\nbär\r\nbaz");
+///
+/// let frozen = FrozenRope::from(&r);
+///
+/// let handle = std::thread::spawn({
+///     let frozen = frozen.clone();
+///     move || frozen.as_rope_ref().line(1).to_owned()
+/// });
+///
+/// assert_eq!(handle.join().unwrap(), "bär");
+/// assert_eq!(frozen, "foo\nbär\r\nbaz");
+/// ```
+#[derive(Clone)]
+pub struct FrozenRope {
+    text: Arc<str>,
+
+    /// See the field with the same name in [`RopeRef`].
+    prefixes: Arc<[ChunkSummary]>,
+}
+
+impl FrozenRope {
+    /// Returns a [`RopeRef`] over the text, which can be used to query it
+    /// without copying it or rebuilding its index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::FrozenRope;
+    /// #
+    /// let frozen = FrozenRope::from("foo\nbar\r\nbaz\n");
+    ///
+    /// let r = frozen.as_rope_ref();
+    ///
+    /// assert_eq!(r.line_len(), 3);
+    /// assert_eq!(r.byte_of_line(2), 9);
+    /// ```
+    #[inline]
+    pub fn as_rope_ref(&self) -> RopeRef<'_> {
+        RopeRef::from_parts(&self.text, &self.prefixes)
+    }
+
+    /// Returns the text as a string slice.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the length of the text in bytes.
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Returns `true` if the text is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    #[inline]
+    fn new(text: Arc<str>) -> Self {
+        let (_, prefixes) = RopeRef::new(&text).into_parts();
+        let prefixes = Arc::from(prefixes);
+        Self { text, prefixes }
+    }
+}
+
+impl From<&Rope> for FrozenRope {
+    #[inline]
+    fn from(rope: &Rope) -> Self {
+        let mut text = String::with_capacity(rope.byte_len());
+
+        for chunk in rope.chunks() {
+            text.push_str(chunk);
+        }
+
+        Self::new(text.into())
+    }
+}
+
+impl From<&str> for FrozenRope {
+    #[inline]
+    fn from(text: &str) -> Self {
+        Self::new(text.into())
+    }
+}
+
+impl From<String> for FrozenRope {
+    #[inline]
+    fn from(text: String) -> Self {
+        Self::new(text.into())
+    }
+}
+
+impl From<&FrozenRope> for Rope {
+    #[inline]
+    fn from(frozen: &FrozenRope) -> Self {
+        Rope::from(frozen.as_str())
+    }
+}
+
+impl core::fmt::Debug for FrozenRope {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_tuple("FrozenRope").field(&self.as_str()).finish()
+    }
+}
+
+impl core::fmt::Display for FrozenRope {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq for FrozenRope {
+    #[inline]
+    fn eq(&self, rhs: &Self) -> bool {
+        Arc::ptr_eq(&self.text, &rhs.text) || self.text == rhs.text
+    }
+}
+
+impl Eq for FrozenRope {}
+
+impl PartialEq<str> for FrozenRope {
+    #[inline]
+    fn eq(&self, rhs: &str) -> bool {
+        self.as_str() == rhs
+    }
+}
+
+impl PartialEq<&str> for FrozenRope {
+    #[inline]
+    fn eq(&self, rhs: &&str) -> bool {
+        self.as_str() == *rhs
+    }
+}
+
+impl PartialEq<FrozenRope> for Rope {
+    #[inline]
+    fn eq(&self, rhs: &FrozenRope) -> bool {
+        *self == rhs.as_str()
+    }
+}
@@ -4,17 +4,29 @@
 //! module on it to be able to use it as the leaf of our [`Rope`](crate::Rope).
 
 use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::ops::{Range, RangeBounds};
 
+use super::chunk_bytes::{new_owned, ChunkBytes};
+#[cfg(feature = "mmap")]
+use super::chunk_bytes::{ByteSource, MappedBytes};
+use super::content_hash::fnv_hash_chunks;
+#[cfg(feature = "content-hash")]
+use super::content_hash::PolyHash;
 use super::gap_slice::GapSlice;
+use super::interner::ChunkInterner;
 use super::metrics::{ByteMetric, ChunkSummary};
 use super::utils::{panic_messages as panic, *};
+#[cfg(feature = "word-metric")]
+use super::word_count::WordCount;
 use crate::range_bounds_to_start_end;
 use crate::tree::{
     AsSlice,
     BalancedLeaf,
     BaseMeasured,
+    HashedLeaf,
     ReplaceableLeaf,
     Summarize,
 };
@@ -37,7 +49,7 @@ use crate::tree::{
 /// [gap buffer]: https://en.wikipedia.org/wiki/Gap_buffer
 #[derive(Clone)]
 pub struct GapBuffer<const MAX_BYTES: usize> {
-    pub(super) bytes: Box<[u8; MAX_BYTES]>,
+    pub(super) bytes: ChunkBytes<MAX_BYTES>,
     pub(super) left_summary: ChunkSummary,
     pub(super) len_right: u16,
 }
@@ -57,7 +69,7 @@ impl<const MAX_BYTES: usize> Default for GapBuffer<MAX_BYTES> {
     #[inline]
     fn default() -> Self {
         Self {
-            bytes: Box::new([0u8; MAX_BYTES]),
+            bytes: new_owned().into(),
             left_summary: ChunkSummary::default(),
             len_right: 0,
         }
@@ -309,6 +321,40 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
         Self::min_bytes().saturating_sub(3)
     }
 
+    /// Returns a compressed copy of the buffer, or `None` if it's empty,
+    /// borrowed, or if compressing it wouldn't make it any smaller.
+    ///
+    /// If the buffer is already compressed its decompressed copy is evicted
+    /// instead, returning `None` if it doesn't have one.
+    #[cfg(feature = "compression")]
+    #[inline]
+    pub(super) fn compressed(&self) -> Option<Self> {
+        let bytes = if !self.bytes.is_resident() {
+            self.bytes.evict()?
+        } else if self.is_empty() {
+            return None;
+        } else if self.len_right() == 0 {
+            ChunkBytes::compress(self.left_chunk().as_bytes())?
+        } else {
+            let mut text = Vec::with_capacity(self.len());
+            text.extend_from_slice(self.left_chunk().as_bytes());
+            text.extend_from_slice(self.right_chunk().as_bytes());
+            ChunkBytes::compress(&text)?
+        };
+
+        Some(Self { bytes, left_summary: self.summarize(), len_right: 0 })
+    }
+
+    /// Drops the decompressed copy of the buffer if it's compressed and has
+    /// one, which doesn't change its summary.
+    #[cfg(feature = "compression")]
+    #[inline]
+    pub(super) fn evict_decompressed(&mut self) {
+        if let Some(bytes) = self.bytes.evict() {
+            self.bytes = bytes;
+        }
+    }
+
     /// Creates a new `GapBuffer` from a slice of `&str`s.
     ///
     /// # Panics
@@ -336,7 +382,7 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
 
         let to_left = total_len / 2;
 
-        let mut bytes = Box::new([0u8; MAX_BYTES]);
+        let mut bytes = new_owned::<MAX_BYTES>();
 
         let mut summary_left = ChunkSummary::new();
 
@@ -394,7 +440,7 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
                 }
 
                 return Self {
-                    bytes,
+                    bytes: bytes.into(),
                     left_summary: summary_left,
                     len_right: len_right as u16,
                 };
@@ -404,10 +450,70 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
         unreachable!("This can only be reached if the total length is zero");
     }
 
-    /// Returns `true` if the buffer ends with a newline ('\n') character.
+    /// Creates a new `GapBuffer` whose left chunk is `s`, reusing its
+    /// allocation.
+    ///
+    /// The allocation is resized to `MAX_BYTES` if it has a different
+    /// capacity, which the allocator can usually do in place.
+    ///
+    /// Note that this doesn't compute the summary of the left chunk, which is
+    /// left empty and has to be set by the caller.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` is longer than `MAX_BYTES`.
+    #[inline]
+    pub(super) fn from_string_unsummarized(s: String) -> Self {
+        assert!(s.len() <= MAX_BYTES);
+
+        let mut bytes = s.into_bytes();
+
+        bytes.resize(MAX_BYTES, 0);
+
+        let bytes: Box<[u8; MAX_BYTES]> =
+            bytes.into_boxed_slice().try_into().unwrap();
+
+        Self {
+            bytes: bytes.into(),
+            left_summary: ChunkSummary::new(),
+            len_right: 0,
+        }
+    }
+
+    /// Creates a new buffer borrowing `s` instead of copying it.
+    ///
+    /// The bytes are copied into an owned buffer the first time the buffer
+    /// is mutated.
+    #[inline]
+    pub(super) fn from_static(s: &'static str) -> Self {
+        debug_assert!(s.len() <= MAX_BYTES);
+
+        Self {
+            bytes: ChunkBytes::Static(s.as_bytes()),
+            left_summary: ChunkSummary::from(s),
+            len_right: 0,
+        }
+    }
+
+    /// Returns `true` if the buffer ends with a line break.
     #[inline]
     pub(super) fn has_trailing_newline(&self) -> bool {
-        self.last_chunk().ends_with('\n')
+        ends_with_line_break(self.last_chunk())
+    }
+
+    /// Returns the number of bytes allocated on the heap for the text of the
+    /// buffer, and how many of them are not used by the text.
+    #[inline]
+    pub(super) fn heap_size(&self) -> (usize, usize) {
+        let heap_size = self.bytes.heap_size();
+
+        let slack = if self.bytes.is_full_capacity() {
+            MAX_BYTES - self.len()
+        } else {
+            0
+        };
+
+        (heap_size, slack)
     }
 
     /// Inserts the string at the given byte offset, moving the gap to the new
@@ -433,6 +539,8 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
 
         debug_assert_eq!(insert_at, self.len_left());
 
+        let right_summary = self.right_summary(summary);
+
         let insert_range = {
             let start = self.len_left();
             let end = start + s.len();
@@ -445,7 +553,44 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
 
         self.left_summary += inserted_summary;
 
-        summary + inserted_summary
+        // Summaries have to be added in the order of the text they
+        // summarize, so we can't just add the inserted summary to the old
+        // one.
+        self.left_summary + right_summary
+    }
+
+    /// Returns a copy of the buffer whose bytes are shared with every other
+    /// buffer storing the same text interned by `interner`, or `None` if it
+    /// already shares them, or if its bytes are borrowed or compressed.
+    #[inline]
+    pub(super) fn interned(
+        &self,
+        interner: &mut ChunkInterner,
+    ) -> Option<Self> {
+        if !self.bytes.is_resident() {
+            return None;
+        }
+
+        let interned = if self.len_right() == 0 {
+            interner.intern(self.left_chunk().as_bytes())
+        } else {
+            let mut bytes = Vec::with_capacity(self.len());
+            bytes.extend_from_slice(self.left_chunk().as_bytes());
+            bytes.extend_from_slice(self.right_chunk().as_bytes());
+            interner.intern(&bytes)
+        };
+
+        if let ChunkBytes::Interned(bytes) = &self.bytes {
+            if Arc::ptr_eq(bytes, &interned) {
+                return None;
+            }
+        }
+
+        Some(Self {
+            bytes: ChunkBytes::Interned(interned),
+            left_summary: self.summarize(),
+            len_right: 0,
+        })
     }
 
     #[inline]
@@ -475,6 +620,41 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
         }
     }
 
+    /// Returns `true` if the buffer borrows its text from a shared source
+    /// instead of owning it.
+    #[cfg(feature = "paging")]
+    #[inline]
+    pub(super) fn is_mapped(&self) -> bool {
+        self.bytes.is_mapped()
+    }
+
+    /// Returns the value of the LRU clock the last time the buffer was read,
+    /// if it's compressed and currently has a decompressed copy.
+    #[cfg(feature = "compression")]
+    #[inline]
+    pub(super) fn last_read_decompressed(&self) -> Option<usize> {
+        self.bytes.last_read_decompressed()
+    }
+
+    /// Calls `f` with the left and right chunks of this buffer, without
+    /// keeping a decompressed copy of its bytes if they're compressed.
+    #[inline]
+    pub(super) fn with_chunks<R>(&self, f: impl FnOnce(&str, &str) -> R) -> R {
+        let (len_left, len_right) = (self.len_left(), self.len_right());
+
+        self.bytes.with_bytes(|bytes| {
+            // SAFETY: same as in `left_chunk()` and `right_chunk()`.
+            unsafe {
+                f(
+                    core::str::from_utf8_unchecked(&bytes[..len_left]),
+                    core::str::from_utf8_unchecked(
+                        &bytes[bytes.len() - len_right..],
+                    ),
+                )
+            }
+        })
+    }
+
     /// Returns the left chunk of this buffer as a string slice.
     #[inline]
     pub fn left_chunk(&self) -> &str {
@@ -506,6 +686,28 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
         self.len_right as _
     }
 
+    /// Creates a new buffer borrowing `s`, which must be a slice of the bytes
+    /// of `source`, instead of copying it.
+    ///
+    /// The bytes are copied into an owned buffer the first time the buffer
+    /// is mutated.
+    #[cfg(feature = "mmap")]
+    #[inline]
+    pub(super) fn mapped(source: &ByteSource, s: &str) -> Self {
+        debug_assert!(s.len() <= MAX_BYTES);
+
+        Self {
+            bytes: ChunkBytes::Mapped(MappedBytes::new(source, s.as_bytes())),
+            left_summary: ChunkSummary::from(s),
+            len_right: 0,
+        }
+    }
+
+    /// The maximum number of bytes this buffer can hold.
+    pub(super) const fn max_bytes() -> usize {
+        MAX_BYTES
+    }
+
     /// The minimum number of bytes this buffer should have to not be
     /// considered underfilled.
     pub(super) const fn min_bytes() -> usize {
@@ -696,7 +898,7 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
         // Prepend the string.
         self.bytes[..s.len()].copy_from_slice(s.as_bytes());
 
-        self.left_summary += prepended_summary;
+        self.left_summary = prepended_summary + self.left_summary;
     }
 
     /// Exactly the same as [`prepend`](Self::prepend()), except it
@@ -748,7 +950,7 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
         // Prepend the second string.
         self.bytes[a.len()..a.len() + b.len()].copy_from_slice(b.as_bytes());
 
-        self.left_summary += prepended_summary;
+        self.left_summary = prepended_summary + self.left_summary;
     }
 
     /// Removes the first `byte_offset` bytes from this buffer.
@@ -840,6 +1042,8 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
 
         let removed_summary = self.summarize_range(start..end, summary);
 
+        let right_summary = self.right_summary(summary);
+
         let added_summary = ChunkSummary::from(s);
 
         self.bytes[start..start + s.len()].copy_from_slice(s.as_bytes());
@@ -848,7 +1052,8 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
 
         self.left_summary += added_summary;
 
-        summary - removed_summary + added_summary
+        // Add the summaries in the order of their text.
+        self.left_summary + right_summary
     }
 
     /// Replaces the text in `byte_range` with the string `s`, where the
@@ -1011,17 +1216,85 @@ impl<const MAX_BYTES: usize> GapBuffer<MAX_BYTES> {
         // `len_right()` bytes valid UTF-8.
         unsafe {
             core::str::from_utf8_unchecked(
-                &self.bytes[MAX_BYTES - self.len_right()..],
+                &self.bytes[self.bytes.len() - self.len_right()..],
             )
         }
     }
 
+    /// Recomputes the content hashes and the word counts of the left chunk
+    /// and of the whole buffer if they're stale, where `summary` is the
+    /// buffer's summary.
+    #[cfg(any(feature = "content-hash", feature = "word-metric"))]
+    #[inline]
+    pub(super) fn refresh_summary(&mut self, summary: &mut ChunkSummary) {
+        #[cfg(feature = "content-hash")]
+        {
+            if self.left_summary.hash.is_stale() {
+                self.left_summary.hash = PolyHash::from(self.left_chunk());
+            }
+
+            summary.hash = self
+                .left_summary
+                .hash
+                .concat(PolyHash::from(self.right_chunk()));
+        }
+
+        #[cfg(feature = "word-metric")]
+        {
+            if self.left_summary.words.is_stale() {
+                self.left_summary.words = WordCount::from(self.left_chunk());
+            }
+
+            summary.words = self
+                .left_summary
+                .words
+                .concat(WordCount::from(self.right_chunk()));
+        }
+    }
+
     /// Returns the summary of the right chunk by subtracting the summary of
     /// the left chunk from the total.
     #[inline]
     fn right_summary(&self, summary: ChunkSummary) -> ChunkSummary {
         debug_assert_eq!(summary, self.summarize());
-        summary - self.left_summary
+
+        #[cfg_attr(not(feature = "word-metric"), allow(unused_mut))]
+        let mut right_summary = summary - self.left_summary;
+
+        #[cfg(feature = "word-metric")]
+        {
+            right_summary.words = summary.words.strip_prefix(
+                self.left_summary.words,
+                self.right_chunk().chars().next(),
+            );
+        }
+
+        right_summary
+    }
+
+    /// Returns a copy of the buffer whose bytes are exactly as long as its
+    /// text, or `None` if it's empty or its bytes aren't owned with their
+    /// full capacity.
+    #[inline]
+    pub(super) fn shrunk(&self) -> Option<Self> {
+        if self.is_empty() || !self.bytes.is_full_capacity() {
+            return None;
+        }
+
+        let bytes = if self.len_right() == 0 {
+            self.left_chunk().as_bytes().into()
+        } else {
+            let mut bytes = Vec::with_capacity(self.len());
+            bytes.extend_from_slice(self.left_chunk().as_bytes());
+            bytes.extend_from_slice(self.right_chunk().as_bytes());
+            bytes.into_boxed_slice()
+        };
+
+        Some(Self {
+            bytes: ChunkBytes::Shrunk(bytes),
+            left_summary: self.summarize(),
+            len_right: 0,
+        })
     }
 
     #[inline]
@@ -1217,7 +1490,7 @@ impl<const MAX_BYTES: usize> BaseMeasured for GapBuffer<MAX_BYTES> {
 impl<const MAX_BYTES: usize> From<GapSlice<'_>> for GapBuffer<MAX_BYTES> {
     #[inline]
     fn from(slice: GapSlice<'_>) -> Self {
-        let mut bytes = Box::new([0u8; MAX_BYTES]);
+        let mut bytes = new_owned::<MAX_BYTES>();
 
         bytes[..slice.len_left()]
             .copy_from_slice(slice.left_chunk().as_bytes());
@@ -1226,7 +1499,7 @@ impl<const MAX_BYTES: usize> From<GapSlice<'_>> for GapBuffer<MAX_BYTES> {
             .copy_from_slice(slice.right_chunk().as_bytes());
 
         Self {
-            bytes,
+            bytes: bytes.into(),
             left_summary: slice.left_summary,
             len_right: slice.len_right,
         }
@@ -1292,7 +1565,7 @@ impl<const MAX_BYTES: usize> BalancedLeaf for GapBuffer<MAX_BYTES> {
             let moved_right =
                 left.move_to_right(missing_right, right, *left_summary);
             *left_summary -= moved_right;
-            *right_summary += moved_right;
+            *right_summary = moved_right + *right_summary;
 
             debug_assert!(left.len() >= Self::chunk_min());
             debug_assert!(right.len() >= Self::chunk_min());
@@ -1303,6 +1576,22 @@ impl<const MAX_BYTES: usize> BalancedLeaf for GapBuffer<MAX_BYTES> {
     }
 }
 
+impl<const MAX_BYTES: usize> HashedLeaf for GapBuffer<MAX_BYTES> {
+    #[inline]
+    fn hash_slice(slice: GapSlice<'_>) -> u64 {
+        fnv_hash_chunks([slice.left_chunk(), slice.right_chunk()])
+    }
+
+    #[inline]
+    fn slices_eq(lhs: GapSlice<'_>, rhs: GapSlice<'_>) -> bool {
+        let lhs_bytes =
+            lhs.left_chunk().bytes().chain(lhs.right_chunk().bytes());
+        let rhs_bytes =
+            rhs.left_chunk().bytes().chain(rhs.right_chunk().bytes());
+        lhs.len() == rhs.len() && lhs_bytes.eq(rhs_bytes)
+    }
+}
+
 impl<const MAX_BYTES: usize> ReplaceableLeaf<ByteMetric>
     for GapBuffer<MAX_BYTES>
 {
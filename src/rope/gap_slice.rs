@@ -1,5 +1,9 @@
 use super::metrics::{ChunkSummary, SummaryUpTo, ToByteOffset};
-use super::utils::{debug_no_quotes, panic_messages as panic};
+use super::utils::{
+    debug_no_quotes,
+    ends_with_line_break,
+    panic_messages as panic,
+};
 use crate::tree::{Metric, Summarize};
 
 /// A slice of a [`GapBuffer`](super::gap_buffer::GapBuffer).
@@ -88,6 +92,7 @@ impl<'a> GapSlice<'a> {
         M::measure(&self.left_summary)
     }
 
+    #[cfg(feature = "line-metric")]
     #[inline]
     pub(super) fn truncate_last_char(
         &mut self,
@@ -137,6 +142,7 @@ impl<'a> GapSlice<'a> {
 
     /// Removes the trailing line break (if it has one), returning the new
     /// summary.
+    #[cfg(feature = "line-metric")]
     #[inline]
     pub(super) fn truncate_trailing_line_break(
         &mut self,
@@ -148,24 +154,27 @@ impl<'a> GapSlice<'a> {
             return summary;
         }
 
+        let is_lf = self.last_chunk().ends_with('\n');
+
         let mut new_summary = self.truncate_last_char(summary);
 
-        if self.last_chunk().ends_with('\r') {
+        if is_lf && self.last_chunk().ends_with('\r') {
             new_summary = self.truncate_last_char(new_summary)
         }
 
         new_summary
     }
 
+    #[cfg(feature = "line-metric")]
     #[inline]
     pub(super) fn empty() -> Self {
         Self::default()
     }
 
-    /// Returns `true` if it ends with a newline.
+    /// Returns `true` if it ends with a line break.
     #[inline]
     pub(super) fn has_trailing_newline(&self) -> bool {
-        self.last_chunk().ends_with('\n')
+        ends_with_line_break(self.last_chunk())
     }
 
     #[inline]
@@ -230,7 +239,19 @@ impl<'a> GapSlice<'a> {
     #[inline]
     fn right_summary(&self, summary: ChunkSummary) -> ChunkSummary {
         debug_assert_eq!(summary, self.summarize());
-        summary - self.left_summary
+
+        #[cfg_attr(not(feature = "word-metric"), allow(unused_mut))]
+        let mut right_summary = summary - self.left_summary;
+
+        #[cfg(feature = "word-metric")]
+        {
+            right_summary.words = summary.words.strip_prefix(
+                self.left_summary.words,
+                self.right_chunk().chars().next(),
+            );
+        }
+
+        right_summary
     }
 
     /// Splits the slice at the given offset, returning the left and right
@@ -286,13 +307,34 @@ impl<'a> GapSlice<'a> {
                 len_right: 0,
             };
 
-            let right = Self {
+            #[cfg_attr(not(feature = "word-metric"), allow(unused_mut))]
+            let mut right = Self {
                 bytes: bytes_right,
                 left_summary: self.left_summary - left_left_summary,
                 len_right: self.len_right,
             };
 
-            ((left, left.left_summary), (right, summary - left.left_summary))
+            #[cfg_attr(not(feature = "word-metric"), allow(unused_mut))]
+            let mut right_summary = summary - left.left_summary;
+
+            // Subtracting the summaries doesn't know the character right
+            // after the split, but we do.
+            #[cfg(feature = "word-metric")]
+            {
+                let next = right.left_chunk().chars().next();
+
+                right.left_summary.words = self
+                    .left_summary
+                    .words
+                    .strip_prefix(left_left_summary.words, next);
+
+                right_summary.words = summary.words.strip_prefix(
+                    left_left_summary.words,
+                    next.or_else(|| right.right_chunk().chars().next()),
+                );
+            }
+
+            ((left, left.left_summary), (right, right_summary))
         } else {
             offset -= self.left_measure::<M>();
 
@@ -314,13 +356,33 @@ impl<'a> GapSlice<'a> {
                 len_right: right_left_summary.bytes() as u16,
             };
 
-            let right = Self {
+            let right_right_summary = self.right_summary(summary);
+
+            #[cfg_attr(not(feature = "word-metric"), allow(unused_mut))]
+            let mut right = Self {
                 bytes: bytes_right,
-                left_summary: self.right_summary(summary) - right_left_summary,
+                left_summary: right_right_summary - right_left_summary,
                 len_right: 0,
             };
 
-            ((left, summary - right.left_summary), (right, right.left_summary))
+            #[cfg_attr(not(feature = "word-metric"), allow(unused_mut))]
+            let mut left_summary = summary - right.left_summary;
+
+            // Subtracting the summaries doesn't know the character right
+            // after the split, but we do.
+            #[cfg(feature = "word-metric")]
+            {
+                right.left_summary.words =
+                    right_right_summary.words.strip_prefix(
+                        right_left_summary.words,
+                        right.left_chunk().chars().next(),
+                    );
+
+                left_summary.words =
+                    self.left_summary.words.concat(right_left_summary.words);
+            }
+
+            ((left, left_summary), (right, right.left_summary))
         }
     }
 
@@ -0,0 +1,1132 @@
+use alloc::collections::BTreeMap;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::{Range, RangeBounds};
+use core::time::Duration;
+
+use super::utils::panic_messages as panic;
+use super::Rope;
+use crate::range_bounds_to_start_end;
+
+/// A single edit recorded by a [`History`], storing both the text that was
+/// removed and the text that replaced it so that the edit can be undone and
+/// redone.
+///
+/// Both texts are stored as plain strings, since a `Rope` would allocate at
+/// least a whole chunk even for the single characters most edits consist
+/// of.
+#[derive(Clone, Debug)]
+struct Edit {
+    /// The byte offset at which the edit happened.
+    start: usize,
+
+    /// The text that was removed by the edit.
+    removed: Box<str>,
+
+    /// The text that was inserted by the edit.
+    inserted: Box<str>,
+}
+
+impl Edit {
+    /// Returns `true` if `next` looks like it was produced by continuing to
+    /// type (or to delete text) right after `self`, without crossing a word
+    /// boundary.
+    #[inline]
+    fn is_continued_by(&self, next: &Edit) -> bool {
+        let is_insertion =
+            |edit: &Edit| edit.removed.is_empty() && !edit.inserted.is_empty();
+
+        let is_deletion =
+            |edit: &Edit| edit.inserted.is_empty() && !edit.removed.is_empty();
+
+        if is_insertion(self) && is_insertion(next) {
+            let ends_word = self
+                .inserted
+                .chars()
+                .next_back()
+                .map_or(false, |ch| !ch.is_whitespace());
+
+            let starts_space = next
+                .inserted
+                .chars()
+                .next()
+                .map_or(false, char::is_whitespace);
+
+            next.start == self.start + self.inserted.len()
+                && !(ends_word && starts_space)
+        } else if is_deletion(self) && is_deletion(next) {
+            // Either backspacing or deleting forward.
+            next.start + next.removed.len() == self.start
+                || next.start == self.start
+        } else {
+            false
+        }
+    }
+
+    /// Reverts the edit, assuming it is the last edit applied to `rope`.
+    #[inline]
+    fn undo(&self, rope: &mut Rope) {
+        let end = self.start + self.inserted.len();
+        rope.replace(self.start..end, &self.removed);
+    }
+
+    /// Re-applies the edit, assuming it was the last edit undone on `rope`.
+    #[inline]
+    fn redo(&self, rope: &mut Rope) {
+        let end = self.start + self.removed.len();
+        rope.replace(self.start..end, &self.inserted);
+    }
+
+    /// Returns the number of bytes of text stored by the edit.
+    #[inline]
+    fn size(&self) -> usize {
+        self.removed.len() + self.inserted.len()
+    }
+}
+
+/// The identifier of a state of the document recorded by a [`History`].
+///
+/// Ids are assigned in increasing order as new states are created, so
+/// comparing two ids tells which state was created first.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StateId(usize);
+
+/// A named handle to a state of the document, created by
+/// [`History::checkpoint()`].
+///
+/// A checkpoint also stores a snapshot of the document, which is cheap to
+/// keep around thanks to structural sharing, so it stays valid even if the
+/// state it refers to is forgotten by the history.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    label: String,
+    state: StateId,
+    rope: Rope,
+}
+
+impl Checkpoint {
+    /// Returns the label given to the checkpoint.
+    #[inline]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the document as it was when the checkpoint was created.
+    #[inline]
+    pub fn rope(&self) -> &Rope {
+        &self.rope
+    }
+
+    /// Returns the state the checkpoint refers to.
+    #[inline]
+    pub fn state(&self) -> StateId {
+        self.state
+    }
+}
+
+/// A region of the document that differs between a [`Checkpoint`] and the
+/// current state, returned by [`History::diff_since()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Change {
+    /// The byte range of the region in the document stored by the
+    /// checkpoint.
+    pub old: Range<usize>,
+
+    /// The byte range of the region in the current document.
+    pub new: Range<usize>,
+}
+
+/// Updates a sorted list of non-overlapping changes to account for an edit
+/// replacing the `replaced` byte range of the new document with
+/// `inserted_len` bytes.
+#[inline]
+fn compose_change(
+    changes: &mut Vec<Change>,
+    replaced: Range<usize>,
+    inserted_len: usize,
+) {
+    let Range { start, end } = replaced;
+
+    // The index of the first change touching the replaced range.
+    let first = changes.partition_point(|c| c.new.end < start);
+
+    // One past the index of the last change touching the replaced range.
+    let last =
+        first + changes[first..].partition_point(|c| c.new.start <= end);
+
+    // The difference between new and old offsets right before the first
+    // change touching the replaced range.
+    let delta_before = changes[..first]
+        .iter()
+        .map(|c| c.new.len() as isize - c.old.len() as isize)
+        .sum::<isize>();
+
+    let delta_after = delta_before
+        + changes[first..last]
+            .iter()
+            .map(|c| c.new.len() as isize - c.old.len() as isize)
+            .sum::<isize>();
+
+    let (new_start, old_start) = match changes.get(first) {
+        Some(c) if first < last && c.new.start <= start => {
+            (c.new.start, c.old.start)
+        },
+        _ => (start, (start as isize - delta_before) as usize),
+    };
+
+    let (new_end, old_end) = match last.checked_sub(1).map(|i| &changes[i]) {
+        Some(c) if first < last && c.new.end >= end => (c.new.end, c.old.end),
+        _ => (end, (end as isize - delta_after) as usize),
+    };
+
+    let shift = inserted_len as isize - (end - start) as isize;
+
+    let merged = Change {
+        old: old_start..old_end,
+        new: new_start..(new_end as isize + shift) as usize,
+    };
+
+    for change in &mut changes[last..] {
+        change.new.start = (change.new.start as isize + shift) as usize;
+        change.new.end = (change.new.end as isize + shift) as usize;
+    }
+
+    changes.splice(first..last, core::iter::once(merged));
+}
+
+/// The rules used by a [`History`] to decide whether consecutive edits
+/// should be coalesced into a single undo step.
+///
+/// By default no rules are enabled and every edit is undone on its own,
+/// unless it's part of an explicit group started with
+/// [`History::begin_group()`]. When more than one rule is enabled an edit is
+/// coalesced with the previous one only if all of them are satisfied.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::history::{Grouping, History};
+/// # use crop::Rope;
+/// #
+/// let mut h = History::new(Rope::new());
+///
+/// h.set_grouping(Grouping::new().coalesce_typing(true));
+///
+/// for (offset, ch) in "foo bar".char_indices() {
+///     h.insert(offset, ch.encode_utf8(&mut [0; 4]));
+/// }
+///
+/// assert!(h.undo());
+/// assert_eq!(*h, "foo");
+///
+/// assert!(h.undo());
+/// assert_eq!(*h, "");
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Grouping {
+    coalesce_typing: bool,
+    max_gap: Option<(Duration, fn() -> Duration)>,
+}
+
+impl Grouping {
+    /// Coalesces an edit with the previous one if it continues it, i.e. if
+    /// it inserts text right after the text inserted by the previous edit,
+    /// or if it deletes text right before or after the text deleted by the
+    /// previous edit.
+    ///
+    /// Typing whitespace after a non-whitespace character starts a new
+    /// group, so that typed text is undone one word at a time.
+    #[inline]
+    pub fn coalesce_typing(mut self, coalesce: bool) -> Self {
+        self.coalesce_typing = coalesce;
+        self
+    }
+
+    /// Coalesces an edit with the previous one if no more than `gap` has
+    /// elapsed between them, according to the given clock.
+    ///
+    /// The clock can return the time elapsed since any fixed point in the
+    /// past, only the difference between its readings matters.
+    #[inline]
+    pub fn max_gap(mut self, gap: Duration, clock: fn() -> Duration) -> Self {
+        self.max_gap = Some((gap, clock));
+        self
+    }
+
+    /// Returns a `Grouping` with no rules enabled.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if at least one rule is enabled.
+    #[inline]
+    fn is_enabled(&self) -> bool {
+        self.coalesce_typing || self.max_gap.is_some()
+    }
+}
+
+/// A node of the undo tree.
+#[derive(Clone, Debug, Default)]
+struct State {
+    /// The state this one was derived from, or `None` if this is the root of
+    /// the tree.
+    parent: Option<StateId>,
+
+    /// The states derived from this one, from oldest to newest.
+    children: Vec<StateId>,
+
+    /// The child that [`History::redo()`] moves to, which is the child that
+    /// was most recently created or undone from.
+    redo_child: Option<StateId>,
+
+    /// The edits transforming the parent state into this one. This is empty
+    /// for the root.
+    edits: Vec<Edit>,
+}
+
+impl State {
+    #[inline]
+    fn size(&self) -> usize {
+        self.edits.iter().map(Edit::size).sum()
+    }
+}
+
+/// A [`Rope`] wrapper that records every edit performed on it, allowing it to
+/// be undone and redone.
+///
+/// Every edit is stored as an inverse delta containing the removed and the
+/// inserted text. Edits made after undoing don't discard the states that
+/// could have been redone: instead they start a new branch, turning the
+/// history into a tree of states similar to Vim's undo tree. Any state in the
+/// tree can be restored with [`jump_to()`](Self::jump_to()).
+///
+/// The number of edits that can be undone and the memory they use can be
+/// capped with [`set_max_depth()`](Self::set_max_depth()) and
+/// [`set_memory_budget()`](Self::set_memory_budget()), in which case the
+/// oldest states are forgotten first.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::history::History;
+/// # use crop::Rope;
+/// #
+/// let mut h = History::new(Rope::from("Hello"));
+///
+/// h.insert(5, " World");
+/// h.replace(6..11, "Earth");
+/// assert_eq!(*h, "Hello Earth");
+///
+/// assert!(h.undo());
+/// assert_eq!(*h, "Hello World");
+///
+/// assert!(h.undo());
+/// assert_eq!(*h, "Hello");
+///
+/// assert!(!h.undo());
+///
+/// assert!(h.redo());
+/// assert_eq!(*h, "Hello World");
+/// ```
+#[derive(Clone, Debug)]
+pub struct History {
+    rope: Rope,
+
+    /// All the states in the tree, indexed by their ids.
+    states: BTreeMap<StateId, State>,
+
+    /// The root of the undo tree.
+    root: StateId,
+
+    /// The state the document is currently in.
+    current: StateId,
+
+    /// The id that'll be given to the next state.
+    next_id: usize,
+
+    /// The sum of the sizes of the edits of all the states.
+    memory_used: usize,
+
+    max_depth: Option<usize>,
+
+    memory_budget: Option<usize>,
+
+    grouping: Grouping,
+
+    /// The number of calls to `begin_group()` that haven't been matched by
+    /// a call to `end_group()` yet.
+    open_groups: usize,
+
+    /// Whether the next edit can be added to the edits of the current state
+    /// instead of creating a new one. This is only true if the current state
+    /// was created by the last edit and we haven't moved away from it since.
+    can_coalesce: bool,
+
+    /// The time of the last edit, if the grouping uses a clock.
+    last_edit_time: Option<Duration>,
+}
+
+impl Default for History {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Rope::new())
+    }
+}
+
+impl History {
+    /// Starts an explicit undo group: all the edits performed until the
+    /// matching call to [`end_group()`](Self::end_group()) are undone and
+    /// redone as a single step.
+    ///
+    /// Groups can be nested, in which case only the outermost group is
+    /// taken into account.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::history::History;
+    /// # use crop::Rope;
+    /// #
+    /// let mut h = History::new(Rope::from("foo"));
+    ///
+    /// h.begin_group();
+    /// h.insert(0, "(");
+    /// h.insert(4, ")");
+    /// h.end_group();
+    ///
+    /// assert_eq!(*h, "(foo)");
+    ///
+    /// assert!(h.undo());
+    /// assert_eq!(*h, "foo");
+    /// ```
+    #[inline]
+    pub fn begin_group(&mut self) {
+        if self.open_groups == 0 {
+            self.can_coalesce = false;
+        }
+        self.open_groups += 1;
+    }
+
+    /// Returns the states at the tip of every branch of the undo tree, from
+    /// oldest to newest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::history::History;
+    /// # use crop::Rope;
+    /// #
+    /// let mut h = History::new(Rope::new());
+    ///
+    /// h.insert(0, "foo");
+    /// let foo = h.current();
+    ///
+    /// h.undo();
+    /// h.insert(0, "bar");
+    /// let bar = h.current();
+    ///
+    /// assert_eq!(h.branches().collect::<Vec<_>>(), [foo, bar]);
+    /// ```
+    #[inline]
+    pub fn branches(&self) -> impl Iterator<Item = StateId> + '_ {
+        self.states
+            .iter()
+            .filter(|(_, state)| state.children.is_empty())
+            .map(|(&id, _)| id)
+    }
+
+    /// Returns `true` if there's at least one state that can be redone.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        self.state(self.current).redo_child.is_some()
+    }
+
+    /// Returns `true` if there's at least one state that can be undone.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        self.current != self.root
+    }
+
+    /// Creates a named checkpoint referring to the current state.
+    ///
+    /// The next edit will always create a new state, even if it would
+    /// otherwise be coalesced with the previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::history::History;
+    /// # use crop::Rope;
+    /// #
+    /// let mut h = History::new(Rope::from("foo"));
+    ///
+    /// let saved = h.checkpoint("saved");
+    ///
+    /// h.insert(3, "bar");
+    /// h.delete(..1);
+    /// assert_eq!(*h, "oobar");
+    ///
+    /// h.revert_to(&saved);
+    /// assert_eq!(*h, "foo");
+    ///
+    /// // Reverting can be undone.
+    /// assert!(h.undo());
+    /// assert_eq!(*h, "oobar");
+    /// ```
+    #[inline]
+    pub fn checkpoint<L>(&mut self, label: L) -> Checkpoint
+    where
+        L: Into<String>,
+    {
+        self.can_coalesce = false;
+
+        Checkpoint {
+            label: label.into(),
+            state: self.current,
+            rope: self.rope.clone(),
+        }
+    }
+
+    /// Returns the states directly derived from the given one, from oldest
+    /// to newest.
+    ///
+    /// Returns an empty slice if the state has been forgotten.
+    #[inline]
+    pub fn children(&self, state: StateId) -> &[StateId] {
+        self.states.get(&state).map(|s| &*s.children).unwrap_or(&[])
+    }
+
+    /// Forgets all the recorded states without modifying the document.
+    #[inline]
+    pub fn clear(&mut self) {
+        let root = self.new_state_id();
+        self.states.clear();
+        self.states.insert(root, State::default());
+        self.root = root;
+        self.current = root;
+        self.memory_used = 0;
+        self.can_coalesce = false;
+    }
+
+    /// Returns the state the document is currently in.
+    #[inline]
+    pub fn current(&self) -> StateId {
+        self.current
+    }
+
+    /// Deletes the contents of the document within the specified byte range,
+    /// recording the edit.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::delete()`].
+    #[track_caller]
+    #[inline]
+    pub fn delete<R>(&mut self, byte_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.replace(byte_range, "");
+    }
+
+    /// Returns the number of undo steps separating the given state from the
+    /// root of the tree.
+    #[inline]
+    fn depth(&self, mut state: StateId) -> usize {
+        let mut depth = 0;
+        while let Some(parent) = self.state(state).parent {
+            state = parent;
+            depth += 1;
+        }
+        depth
+    }
+
+    /// Returns the regions of the document that changed since the given
+    /// checkpoint was created, sorted by their position in the document.
+    ///
+    /// The changes are computed by composing the edits on the path between
+    /// the checkpoint's state and the current state, so their cost only
+    /// depends on the number of edits and not on the size of the document.
+    /// Regions that were edited but ended up with the same content are not
+    /// reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::history::{Change, History};
+    /// # use crop::Rope;
+    /// #
+    /// let mut h = History::new(Rope::from("foo\nbar\nbaz\n"));
+    ///
+    /// let saved = h.checkpoint("saved");
+    ///
+    /// h.insert(0, "// ");
+    /// h.replace(11..14, "BAZ");
+    ///
+    /// assert_eq!(
+    ///     h.diff_since(&saved),
+    ///     [Change { old: 0..0, new: 0..3 }, Change { old: 8..11, new: 11..14 }]
+    /// );
+    /// ```
+    #[inline]
+    pub fn diff_since(&self, checkpoint: &Checkpoint) -> Vec<Change> {
+        let mut changes = Vec::new();
+
+        if !self.states.contains_key(&checkpoint.state) {
+            // The path between the two states has been forgotten, so all we
+            // can do is consider the whole document as changed.
+            changes.push(Change {
+                old: 0..checkpoint.rope.byte_len(),
+                new: 0..self.rope.byte_len(),
+            });
+        } else {
+            let path_from_checkpoint = self.path_to_root(checkpoint.state);
+            let path_from_current = self.path_to_root(self.current);
+
+            let ancestor = *path_from_checkpoint
+                .iter()
+                .find(|state| path_from_current.contains(state))
+                .unwrap();
+
+            for &state in path_from_checkpoint
+                .iter()
+                .take_while(|&&state| state != ancestor)
+            {
+                for edit in self.state(state).edits.iter().rev() {
+                    let end = edit.start + edit.inserted.len();
+                    let len = edit.removed.len();
+                    compose_change(&mut changes, edit.start..end, len);
+                }
+            }
+
+            for &state in path_from_current
+                .iter()
+                .take_while(|&&state| state != ancestor)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+            {
+                for edit in &self.state(state).edits {
+                    let end = edit.start + edit.removed.len();
+                    let len = edit.inserted.len();
+                    compose_change(&mut changes, edit.start..end, len);
+                }
+            }
+        }
+
+        changes.retain(|change| {
+            change.old.len() != change.new.len()
+                || checkpoint.rope.byte_slice(change.old.clone())
+                    != self.rope.byte_slice(change.new.clone())
+        });
+
+        changes
+    }
+
+    /// Ends the explicit undo group started by the last call to
+    /// [`begin_group()`](Self::begin_group()).
+    ///
+    /// Calling this when no group is open does nothing.
+    #[inline]
+    pub fn end_group(&mut self) {
+        self.open_groups = self.open_groups.saturating_sub(1);
+        if self.open_groups == 0 {
+            self.can_coalesce = false;
+        }
+    }
+
+    /// Forgets the oldest states until both the depth and the memory limits
+    /// are satisfied.
+    #[inline]
+    fn enforce_limits(&mut self) {
+        if let Some(max_depth) = self.max_depth {
+            let mut depth = self.depth(self.current);
+            while depth > max_depth {
+                self.pop_root();
+                depth -= 1;
+            }
+        }
+
+        if let Some(budget) = self.memory_budget {
+            while self.memory_used > budget && self.forget_oldest() {}
+        }
+    }
+
+    /// Forgets the oldest state that isn't the current state or one of its
+    /// ancestors, or the root if it's older. Returns `false` if there was
+    /// nothing to forget.
+    #[inline]
+    fn forget_oldest(&mut self) -> bool {
+        let oldest_leaf =
+            self.branches().find(|&branch| branch != self.current);
+
+        // Popping the root forgets the edits of the child on the path to
+        // the current state, so that's the id we need to compare.
+        let root_child = (self.root != self.current).then(|| {
+            let mut state = self.current;
+            while self.state(state).parent != Some(self.root) {
+                state = self.state(state).parent.unwrap();
+            }
+            state
+        });
+
+        match (oldest_leaf, root_child) {
+            (Some(leaf), Some(child)) if leaf < child => {
+                self.remove_leaf(leaf)
+            },
+            (Some(leaf), None) => self.remove_leaf(leaf),
+            (_, Some(_)) => self.pop_root(),
+            (None, None) => return false,
+        }
+
+        true
+    }
+
+    /// Inserts `text` at the given byte offset, recording the edit.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::insert()`].
+    #[track_caller]
+    #[inline]
+    pub fn insert<T>(&mut self, byte_offset: usize, text: T)
+    where
+        T: AsRef<str>,
+    {
+        self.replace(byte_offset..byte_offset, text);
+    }
+
+    /// Consumes the `History`, returning the document.
+    #[inline]
+    pub fn into_rope(self) -> Rope {
+        self.rope
+    }
+
+    /// Restores the document to the given state by undoing and redoing the
+    /// edits on the path between the current state and the target, returning
+    /// `false` if the state has been forgotten.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::history::History;
+    /// # use crop::Rope;
+    /// #
+    /// let mut h = History::new(Rope::new());
+    ///
+    /// h.insert(0, "foo");
+    /// let foo = h.current();
+    ///
+    /// h.undo();
+    /// h.insert(0, "bar");
+    /// assert_eq!(*h, "bar");
+    ///
+    /// assert!(h.jump_to(foo));
+    /// assert_eq!(*h, "foo");
+    /// ```
+    #[inline]
+    pub fn jump_to(&mut self, target: StateId) -> bool {
+        if !self.states.contains_key(&target) {
+            return false;
+        }
+
+        let path_to_target = self.path_to_root(target);
+
+        // Undo until we reach a common ancestor of the current state and the
+        // target.
+        while !path_to_target.contains(&self.current) {
+            self.undo();
+        }
+
+        let ancestor = path_to_target.iter().position(|&s| s == self.current);
+
+        for &state in path_to_target[..ancestor.unwrap()].iter().rev() {
+            self.redo_to(state);
+        }
+
+        true
+    }
+
+    /// Returns the maximum number of edits that can be undone, if any.
+    #[inline]
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Returns the maximum number of bytes the recorded edits can use, if
+    /// any.
+    #[inline]
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.memory_budget
+    }
+
+    /// Returns the number of bytes of text stored by the recorded edits.
+    #[inline]
+    pub fn memory_used(&self) -> usize {
+        self.memory_used
+    }
+
+    /// Creates a new `History` with no recorded edits and no limits on its
+    /// depth or memory usage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rope` has a [`limit()`](Rope::limit()).
+    #[track_caller]
+    #[inline]
+    pub fn new(rope: Rope) -> Self {
+        if rope.limit().is_some() {
+            panic::rope_has_limit("History");
+        }
+
+        let root = StateId(0);
+        let mut states = BTreeMap::new();
+        states.insert(root, State::default());
+
+        Self {
+            rope,
+            states,
+            root,
+            current: root,
+            next_id: 1,
+            memory_used: 0,
+            max_depth: None,
+            memory_budget: None,
+            grouping: Grouping::new(),
+            open_groups: 0,
+            can_coalesce: false,
+            last_edit_time: None,
+        }
+    }
+
+    #[inline]
+    fn new_state_id(&mut self) -> StateId {
+        let id = StateId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// Returns the state the given one was derived from, or `None` if it's
+    /// the root of the tree or if it has been forgotten.
+    #[inline]
+    pub fn parent(&self, state: StateId) -> Option<StateId> {
+        self.states.get(&state).and_then(|s| s.parent)
+    }
+
+    /// Returns the given state followed by all of its ancestors, up to and
+    /// including the root.
+    #[inline]
+    fn path_to_root(&self, mut state: StateId) -> Vec<StateId> {
+        let mut path = alloc::vec![state];
+        while let Some(parent) = self.state(state).parent {
+            path.push(parent);
+            state = parent;
+        }
+        path
+    }
+
+    /// Forgets the root of the tree together with all of its branches except
+    /// the one leading to the current state.
+    #[inline]
+    fn pop_root(&mut self) {
+        debug_assert_ne!(self.root, self.current);
+
+        let mut new_root = self.current;
+        while self.state(new_root).parent != Some(self.root) {
+            new_root = self.state(new_root).parent.unwrap();
+        }
+
+        let old_root = self.states.remove(&self.root).unwrap();
+
+        for child in old_root.children {
+            if child != new_root {
+                self.remove_subtree(child);
+            }
+        }
+
+        let root = self.states.get_mut(&new_root).unwrap();
+        root.parent = None;
+        let edits = core::mem::take(&mut root.edits);
+        self.memory_used -= edits.iter().map(Edit::size).sum::<usize>();
+
+        self.root = new_root;
+    }
+
+    /// Re-applies the edits leading to the most recently visited child of the
+    /// current state, returning `false` if there was nothing to redo.
+    #[inline]
+    pub fn redo(&mut self) -> bool {
+        let Some(child) = self.state(self.current).redo_child else {
+            return false;
+        };
+        self.redo_to(child);
+        true
+    }
+
+    /// Re-applies the edits leading to the given child of the current state.
+    #[inline]
+    fn redo_to(&mut self, child: StateId) {
+        debug_assert_eq!(self.state(child).parent, Some(self.current));
+
+        for edit in &self.states[&child].edits {
+            edit.redo(&mut self.rope);
+        }
+
+        self.states.get_mut(&self.current).unwrap().redo_child = Some(child);
+        self.current = child;
+        self.can_coalesce = false;
+    }
+
+    /// Removes a state with no children from the tree.
+    #[inline]
+    fn remove_leaf(&mut self, leaf: StateId) {
+        debug_assert!(self.state(leaf).children.is_empty());
+        debug_assert_ne!(leaf, self.current);
+
+        let state = self.states.remove(&leaf).unwrap();
+        self.memory_used -= state.size();
+
+        let parent = self.states.get_mut(&state.parent.unwrap()).unwrap();
+        parent.children.retain(|&child| child != leaf);
+
+        if parent.redo_child == Some(leaf) {
+            parent.redo_child = parent.children.last().copied();
+        }
+    }
+
+    /// Removes the given state and all of its descendants from the tree.
+    #[inline]
+    fn remove_subtree(&mut self, state: StateId) {
+        let state = self.states.remove(&state).unwrap();
+        self.memory_used -= state.size();
+        for child in state.children {
+            self.remove_subtree(child);
+        }
+    }
+
+    /// Replaces the contents of the document within the specified byte range
+    /// with the given text, recording the edit.
+    ///
+    /// The edit creates a new state which becomes a child of the current
+    /// one.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::replace()`].
+    #[track_caller]
+    #[inline]
+    pub fn replace<R, T>(&mut self, byte_range: R, text: T)
+    where
+        R: RangeBounds<usize>,
+        T: AsRef<str>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.rope.byte_len());
+
+        let text = text.as_ref();
+
+        let removed = self.rope.byte_slice(start..end).to_string();
+
+        self.rope.replace(start..end, text);
+
+        self.record(Edit {
+            start,
+            removed: removed.into_boxed_str(),
+            inserted: text.into(),
+        });
+    }
+
+    /// Records an edit that was just performed on the document, either by
+    /// adding it to the current state or by creating a new one.
+    #[inline]
+    fn record(&mut self, edit: Edit) {
+        self.memory_used += edit.size();
+
+        let now = self.grouping.max_gap.map(|(_, clock)| clock());
+
+        if self.should_coalesce(&edit, now) {
+            self.states.get_mut(&self.current).unwrap().edits.push(edit);
+        } else {
+            let id = self.new_state_id();
+
+            let state = State {
+                parent: Some(self.current),
+                children: Vec::new(),
+                redo_child: None,
+                edits: alloc::vec![edit],
+            };
+
+            self.states.insert(id, state);
+
+            let parent = self.states.get_mut(&self.current).unwrap();
+            parent.children.push(id);
+            parent.redo_child = Some(id);
+
+            self.current = id;
+        }
+
+        self.can_coalesce = true;
+        self.last_edit_time = now;
+
+        self.enforce_limits();
+    }
+
+    /// Restores the contents of the document to the ones it had when the
+    /// given checkpoint was created.
+    ///
+    /// Unlike [`jump_to()`](Self::jump_to()), this records the changes as a
+    /// new undo step, so the revert itself can be undone.
+    ///
+    /// Every region returned by [`diff_since()`](Self::diff_since()) is
+    /// reverted by a single edit of the document, which splices in the text
+    /// of the checkpoint instead of copying it.
+    #[inline]
+    pub fn revert_to(&mut self, checkpoint: &Checkpoint) {
+        let changes = self.diff_since(checkpoint);
+
+        if changes.is_empty() {
+            return;
+        }
+
+        self.begin_group();
+
+        // Going from the last change to the first means we don't have to
+        // adjust the offsets of the changes we haven't reverted yet.
+        for Change { old, new } in changes.into_iter().rev() {
+            let old = checkpoint.rope.byte_slice(old);
+            let removed = self.rope.byte_slice(new.clone()).to_string();
+            let start = new.start;
+            self.rope.replace_with_slice(new, old);
+            self.record(Edit {
+                start,
+                removed: removed.into_boxed_str(),
+                inserted: old.to_string().into_boxed_str(),
+            });
+        }
+
+        self.end_group();
+    }
+
+    /// Returns the document.
+    #[inline]
+    pub fn rope(&self) -> &Rope {
+        &self.rope
+    }
+
+    /// Returns the root of the undo tree, i.e. the oldest state that hasn't
+    /// been forgotten.
+    #[inline]
+    pub fn root(&self) -> StateId {
+        self.root
+    }
+
+    /// Sets the rules used to coalesce consecutive edits into a single undo
+    /// step. See [`Grouping`] for more infos.
+    #[inline]
+    pub fn set_grouping(&mut self, grouping: Grouping) {
+        self.grouping = grouping;
+        self.can_coalesce = false;
+    }
+
+    /// Sets the maximum number of edits that can be undone. Passing `None`
+    /// removes the limit.
+    ///
+    /// If more edits than the new limit have already been recorded the
+    /// oldest ones are forgotten.
+    #[inline]
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+        self.enforce_limits();
+    }
+
+    /// Sets the maximum number of bytes the recorded edits can use. Passing
+    /// `None` removes the limit.
+    ///
+    /// Memory usage is measured as the number of bytes removed and inserted
+    /// by each edit, which are stored as strings of the same length. When the
+    /// budget is exceeded the oldest states are forgotten first.
+    #[inline]
+    pub fn set_memory_budget(&mut self, memory_budget: Option<usize>) {
+        self.memory_budget = memory_budget;
+        self.enforce_limits();
+    }
+
+    /// Returns `true` if the given edit, which was just performed at time
+    /// `now`, should be added to the edits of the current state instead of
+    /// creating a new state.
+    #[inline]
+    fn should_coalesce(&self, edit: &Edit, now: Option<Duration>) -> bool {
+        // The root can't hold any edits, which could happen if it was
+        // forgotten while a group was open.
+        if !self.can_coalesce || self.current == self.root {
+            return false;
+        }
+
+        if self.open_groups > 0 {
+            return true;
+        }
+
+        if !self.grouping.is_enabled() {
+            return false;
+        }
+
+        if self.grouping.coalesce_typing {
+            let last = self.state(self.current).edits.last().unwrap();
+            if !last.is_continued_by(edit) {
+                return false;
+            }
+        }
+
+        if let (Some((gap, _)), Some(now), Some(last)) =
+            (self.grouping.max_gap, now, self.last_edit_time)
+        {
+            if now.saturating_sub(last) > gap {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    #[inline]
+    fn state(&self, id: StateId) -> &State {
+        &self.states[&id]
+    }
+
+    /// Reverts the edits leading to the current state, moving to its parent
+    /// and returning `false` if there was nothing to undo.
+    #[inline]
+    pub fn undo(&mut self) -> bool {
+        let Some(parent) = self.state(self.current).parent else {
+            return false;
+        };
+
+        for edit in self.states[&self.current].edits.iter().rev() {
+            edit.undo(&mut self.rope);
+        }
+
+        self.states.get_mut(&parent).unwrap().redo_child = Some(self.current);
+        self.current = parent;
+        self.can_coalesce = false;
+        true
+    }
+}
+
+impl core::ops::Deref for History {
+    type Target = Rope;
+
+    #[inline]
+    fn deref(&self) -> &Rope {
+        &self.rope
+    }
+}
+
+impl From<Rope> for History {
+    #[track_caller]
+    #[inline]
+    fn from(rope: Rope) -> Self {
+        Self::new(rope)
+    }
+}
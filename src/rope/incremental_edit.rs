@@ -0,0 +1,187 @@
+use core::ops::Range;
+
+use super::rope::RopeChunk;
+use super::Rope;
+use crate::tree::Tree;
+
+/// A replacement of a range of a [`Rope`] carried out in bounded steps.
+///
+/// Most of the time spent replacing a range of a `Rope` with a very long
+/// text goes into copying the text into chunks and building a tree out of
+/// them. An `IncrementalEdit` does that a bounded number of bytes at a time
+/// with every call to [`step()`](Self::step()), so that the thread driving
+/// it can do other work, like painting the UI, between the steps. Once the
+/// whole text has been turned into a tree, the last step splices it into
+/// the `Rope` and applies the edit, which takes `O(log n)` time apart from
+/// the time needed to free the deleted text.
+///
+/// The `Rope` isn't modified until the last step, and it can be read through
+/// [`rope()`](Self::rope()) in the meantime. If the edit is dropped before
+/// it's done, the remaining steps are carried out at once.
+///
+/// This struct is created by [`Rope::begin_edit()`]. See its documentation
+/// for more.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::Rope;
+/// #
+/// let mut r = Rope::from("foo\nbaz\n");
+///
+/// let text = "bar\n".repeat(10_000);
+///
+/// let mut edit = r.begin_edit(4..4, &text);
+///
+/// let mut steps = 0;
+///
+/// while !edit.step(4096) {
+///     assert_eq!(edit.rope(), "foo\nbaz\n");
+///     steps += 1;
+/// }
+///
+/// assert!(steps > 1);
+/// assert!(edit.is_done());
+/// assert_eq!(edit.rope().line_len(), 10_002);
+/// ```
+pub struct IncrementalEdit<'a, T: AsRef<str>> {
+    rope: &'a mut Rope,
+
+    /// The byte range of the `Rope` replaced by the edit.
+    byte_range: Range<usize>,
+
+    text: T,
+
+    /// The byte length of the prefix of `text` that's already been turned
+    /// into chunks of `inserted`.
+    built: usize,
+
+    /// The tree that'll be spliced into the `Rope` once it contains all of
+    /// `text`.
+    inserted: Tree<{ Rope::arity() }, RopeChunk>,
+
+    is_done: bool,
+}
+
+impl<'a, T: AsRef<str>> IncrementalEdit<'a, T> {
+    /// Carries out all the remaining steps of the edit at once.
+    #[inline]
+    pub fn finish(mut self) {
+        self.step(usize::MAX);
+    }
+
+    /// Returns `true` if the edit has been applied to the `Rope`.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.is_done
+    }
+
+    #[inline]
+    pub(super) fn new(
+        rope: &'a mut Rope,
+        byte_range: Range<usize>,
+        text: T,
+    ) -> Self {
+        Self {
+            rope,
+            byte_range,
+            text,
+            built: 0,
+            inserted: Tree::default(),
+            is_done: false,
+        }
+    }
+
+    /// Returns the number of bytes of the inserted text that are yet to be
+    /// processed.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.text.as_ref().len() - self.built
+    }
+
+    /// Returns the `Rope` being edited, which is unchanged until the edit is
+    /// done.
+    #[inline]
+    pub fn rope(&self) -> &Rope {
+        self.rope
+    }
+
+    /// Processes roughly `max_bytes` of the inserted text, applying the edit
+    /// to the `Rope` if that was the last of it. Returns whether the edit is
+    /// done.
+    ///
+    /// The text is processed a chunk at a time, so a step can go past
+    /// `max_bytes` by less than a chunk and always processes at least one
+    /// chunk.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello World!");
+    ///
+    /// let mut edit = r.begin_edit(5..11, "");
+    ///
+    /// assert!(edit.step(1));
+    /// assert!(edit.step(1));
+    /// assert_eq!(edit.rope(), "Hello!");
+    /// ```
+    #[inline]
+    pub fn step(&mut self, max_bytes: usize) -> bool {
+        if self.is_done {
+            return true;
+        }
+
+        let text = self.text.as_ref();
+
+        let mut segmenter = RopeChunk::segmenter(&text[self.built..]);
+
+        let mut stepped = 0;
+
+        let chunks = core::iter::from_fn(|| {
+            if stepped >= max_bytes && stepped > 0 {
+                return None;
+            }
+            let chunk = segmenter.next()?;
+            stepped += chunk.len();
+            Some(RopeChunk::from(chunk))
+        });
+
+        self.inserted.append(Tree::from_leaves(chunks));
+
+        self.built += stepped;
+
+        if self.built < text.len() {
+            return false;
+        }
+
+        self.rope.replace_with(
+            self.byte_range.clone(),
+            text,
+            Some(core::mem::take(&mut self.inserted)),
+        );
+
+        self.is_done = true;
+
+        true
+    }
+}
+
+impl<T: AsRef<str>> Drop for IncrementalEdit<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.step(usize::MAX);
+    }
+}
+
+impl<T: AsRef<str>> core::fmt::Debug for IncrementalEdit<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("IncrementalEdit")
+            .field("byte_range", &self.byte_range)
+            .field("remaining", &self.remaining())
+            .field("is_done", &self.is_done)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,86 @@
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+
+/// A set of chunks that can be shared by several [`Rope`](crate::Rope)s,
+/// used by [`Rope::intern_chunks()`](crate::Rope::intern_chunks()).
+///
+/// Every chunk interned with the same `ChunkInterner` whose text is equal to
+/// one interned before it points to the same allocation, which is only
+/// copied when the chunk is edited. This can save a lot of memory for very
+/// repetitive texts, e.g. logs or generated files.
+///
+/// The interner keeps every chunk it has seen alive, even after the `Rope`s
+/// using it have been edited or dropped. Those can be released with
+/// [`purge()`](Self::purge()).
+#[derive(Clone, Default)]
+pub struct ChunkInterner {
+    chunks: BTreeSet<Arc<[u8]>>,
+}
+
+impl core::fmt::Debug for ChunkInterner {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("ChunkInterner")
+            .field("chunks", &self.chunks.len())
+            .finish()
+    }
+}
+
+impl ChunkInterner {
+    /// Returns the allocation storing `bytes`, inserting them if they
+    /// haven't been interned yet.
+    #[inline]
+    pub(super) fn intern(&mut self, bytes: &[u8]) -> Arc<[u8]> {
+        if let Some(interned) = self.chunks.get(bytes) {
+            return Arc::clone(interned);
+        }
+
+        let interned = Arc::<[u8]>::from(bytes);
+        self.chunks.insert(Arc::clone(&interned));
+        interned
+    }
+
+    /// Returns `true` if no chunks have been interned.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns the number of distinct chunks that have been interned.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Creates a new, empty `ChunkInterner`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Releases the chunks that are no longer used by any `Rope`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{ChunkInterner, Rope};
+    /// #
+    /// let mut interner = ChunkInterner::new();
+    ///
+    /// let mut r = Rope::from("Hello, world!\n".repeat(2));
+    /// r.intern_chunks(&mut interner);
+    /// let interned = interner.len();
+    ///
+    /// // The chunks are still used by `r`, so they're kept.
+    /// interner.purge();
+    /// assert_eq!(interner.len(), interned);
+    ///
+    /// drop(r);
+    /// interner.purge();
+    /// assert!(interner.is_empty());
+    /// ```
+    #[inline]
+    pub fn purge(&mut self) {
+        self.chunks.retain(|chunk| Arc::strong_count(chunk) > 1);
+    }
+}
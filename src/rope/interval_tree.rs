@@ -0,0 +1,814 @@
+use alloc::vec::Vec;
+use core::ops::{Add, AddAssign, Range, RangeBounds, Sub, SubAssign};
+
+use super::{EditInfo, Rope};
+use crate::tree::{
+    AsSlice,
+    BalancedLeaf,
+    BaseMeasured,
+    Metric,
+    ReplaceableLeaf,
+    SlicingMetric,
+    Summarize,
+    Tree,
+};
+
+#[cfg(any(test, feature = "small_chunks"))]
+const CHUNK_MAX_INTERVALS: usize = 4;
+
+#[cfg(not(any(test, feature = "small_chunks")))]
+const CHUNK_MAX_INTERVALS: usize = 32;
+
+const CHUNK_MIN_INTERVALS: usize = CHUNK_MAX_INTERVALS / 2;
+
+/// The identifier of an interval inserted in an [`IntervalTree`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IntervalId(u64);
+
+/// A collection of byte ranges with an associated payload, built on the same
+/// B-tree used by [`Rope`].
+///
+/// The intervals are kept in sync with the text they annotate by passing
+/// every edit to [`edit()`](Self::edit()), which can be done automatically
+/// by registering a callback with [`Rope::subscribe()`]. Text inserted at the
+/// start of an interval is placed before it, while text inserted at its end
+/// extends it. Replacing text that starts within an interval keeps the new
+/// text in it, and deleting all the text within an interval leaves it empty.
+///
+/// Queries skip the subtrees whose intervals either all start after the
+/// queried range or are too short to reach it, so when the intervals have
+/// similar lengths the ones containing a byte offset or overlapping a byte
+/// range are found in `O(log n + k)` time, where `k` is the number of
+/// matches. Every edit takes `O((k + 1) log n)` time, where `k` is the number
+/// of intervals touching the edited range.
+///
+/// Payloads have to be `'static` since the tree's leaves must be sliceable
+/// for any lifetime.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::interval_tree::IntervalTree;
+/// # use crop::EditInfo;
+/// #
+/// let mut folds = IntervalTree::new();
+///
+/// let fold = folds.insert(10..20, "fn main");
+/// folds.insert(30..45, "impl Foo");
+///
+/// // Insert 5 bytes at offset 0.
+/// folds.edit(&EditInfo { byte_range: 0..0, inserted_len: 5 });
+///
+/// assert_eq!(
+///     folds.containing(17).collect::<Vec<_>>(),
+///     [(fold, 15..25, &"fn main")]
+/// );
+/// ```
+#[derive(Clone)]
+pub struct IntervalTree<T> {
+    tree: Tree<{ Rope::arity() }, IntervalChunk<T>>,
+    next_id: u64,
+}
+
+impl<T: Clone + 'static> IntervalTree<T> {
+    #[doc(hidden)]
+    pub fn assert_invariants(&self) {
+        self.tree.assert_invariants();
+    }
+
+    /// Returns the number of intervals whose start is before `byte_offset`.
+    #[inline]
+    fn count_starting_before(&self, byte_offset: usize) -> usize {
+        if byte_offset > self.tree.measure::<IntervalSpan>().0 {
+            return self.len();
+        }
+
+        let IntervalCount(count) = self
+            .tree
+            .convert_measure::<_, IntervalCount>(IntervalSpan(byte_offset));
+
+        count
+    }
+
+    /// Returns an iterator over the intervals containing the given byte
+    /// offset, sorted by their start.
+    ///
+    /// Empty intervals don't contain any offset.
+    #[inline]
+    pub fn containing(
+        &self,
+        byte_offset: usize,
+    ) -> impl Iterator<Item = (IntervalId, Range<usize>, &T)> + '_ {
+        self.overlapping(byte_offset..byte_offset + 1)
+    }
+
+    /// Moves the intervals to their positions after the given edit.
+    ///
+    /// Interval ends within the replaced range are moved past the inserted
+    /// text, while interval starts are moved to the start of the range,
+    /// unless the edit is a pure insertion. The boundaries after the range
+    /// are shifted by the difference between the inserted and the deleted
+    /// lengths.
+    #[inline]
+    pub fn edit(&mut self, edit: &EditInfo) {
+        let Range { start, end } = edit.byte_range.clone();
+
+        let move_end = |offset: usize| {
+            if offset < start {
+                offset
+            } else if offset <= end {
+                start + edit.inserted_len
+            } else {
+                offset - (end - start) + edit.inserted_len
+            }
+        };
+
+        // Starts only move past the inserted text if nothing was deleted,
+        // so that replacing the text of an interval keeps the new text in
+        // it.
+        let move_start = |offset: usize| {
+            if start < end && (start..=end).contains(&offset) {
+                start
+            } else {
+                move_end(offset)
+            }
+        };
+
+        let first_moved = self.count_starting_before(start);
+
+        // Update the ends of the intervals starting before the edit but
+        // ending within or after it. These are updated in place since their
+        // start doesn't move.
+        let ending_after = self
+            .entries_where(|offset, summary| {
+                offset.count < first_moved
+                    && summary.max_end_from(offset) >= start
+            })
+            .filter(|&(idx, entry_start, entry)| {
+                idx < first_moved && entry_start + entry.len >= start
+            })
+            .map(|(idx, entry_start, entry)| {
+                let new_end = move_end(entry_start + entry.len);
+                (idx, Entry { len: new_end - entry_start, ..entry.clone() })
+            })
+            .collect::<Vec<_>>();
+
+        for (idx, entry) in ending_after {
+            self.replace_entries(idx..idx + 1, alloc::vec![entry]);
+        }
+
+        // The intervals starting within the edit are moved together with the
+        // first one after it, whose gap from the previous interval changes.
+        let moved = (self.count_starting_before(end + 1) + 1).min(self.len());
+
+        if first_moved == moved {
+            return;
+        }
+
+        let mut previous_start = self.start_of(first_moved);
+
+        let entries = self
+            .entries_in(first_moved..moved)
+            .map(|(_, entry_start, entry)| {
+                let new_start = move_start(entry_start);
+                let new_end = move_end(entry_start + entry.len);
+                let gap = new_start - previous_start;
+                previous_start = new_start;
+                Entry { gap, len: new_end - new_start, ..entry.clone() }
+            })
+            .collect::<Vec<_>>();
+
+        self.replace_entries(first_moved..moved, entries);
+    }
+
+    /// Returns an iterator over the `(index, start, entry)` triples of the
+    /// intervals with an index in the given range.
+    #[inline]
+    fn entries_in(
+        &self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = (usize, usize, &Entry<T>)> + '_ {
+        self.entries_where(move |offset, summary| {
+            offset.count < range.end
+                && offset.count + summary.count > range.start
+        })
+        .filter(move |(idx, _, _)| range.contains(idx))
+    }
+
+    /// Returns an iterator over the `(index, start, entry)` triples of the
+    /// intervals contained in the subtrees accepted by `keep`, which is
+    /// called with the summary of everything before a subtree and with the
+    /// summary of the subtree itself.
+    #[inline]
+    fn entries_where<F>(
+        &self,
+        mut keep: F,
+    ) -> impl Iterator<Item = (usize, usize, &Entry<T>)> + '_
+    where
+        F: FnMut(&IntervalSummary, &IntervalSummary) -> bool,
+    {
+        self.tree
+            .filter_leaves(|offset, summary| {
+                summary.count > 0 && keep(offset, summary)
+            })
+            .into_iter()
+            .flat_map(|(offset, slice)| {
+                let mut start = offset.span;
+                slice.entries.iter().enumerate().map(move |(idx, entry)| {
+                    start += entry.gap;
+                    (offset.count + idx, start, entry)
+                })
+            })
+    }
+
+    /// Inserts a new interval, returning its id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than its end.
+    #[track_caller]
+    #[inline]
+    pub fn insert<R>(&mut self, byte_range: R, value: T) -> IntervalId
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            crate::range_bounds_to_start_end(byte_range, 0, usize::MAX);
+
+        assert!(
+            start <= end,
+            "byte start after end: {start} > {end} in interval range"
+        );
+
+        let id = IntervalId(self.next_id);
+        self.next_id += 1;
+
+        let idx = self.count_starting_before(start + 1);
+
+        let gap = start - self.start_of(idx);
+
+        let mut entries =
+            alloc::vec![Entry { id, gap, len: end - start, value }];
+
+        let next = self
+            .entries_in(idx..idx + 1)
+            .next()
+            .map(|(_, _, next)| Entry { gap: next.gap - gap, ..next.clone() });
+
+        let replaced = if let Some(next) = next {
+            entries.push(next);
+            idx..idx + 1
+        } else {
+            idx..idx
+        };
+
+        self.replace_entries(replaced, entries);
+
+        id
+    }
+
+    /// Returns `true` if the tree doesn't contain any intervals.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over all the intervals, sorted by their start.
+    #[inline]
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (IntervalId, Range<usize>, &T)> + '_ {
+        self.entries_where(|_, _| true).map(to_item)
+    }
+
+    /// Returns the number of intervals in the tree.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.summary().count
+    }
+
+    /// Creates a new, empty `IntervalTree`.
+    #[inline]
+    pub fn new() -> Self {
+        Self { tree: Tree::default(), next_id: 0 }
+    }
+
+    /// Returns an iterator over the intervals sharing at least one byte with
+    /// the given byte range, sorted by their start.
+    ///
+    /// Empty intervals don't overlap any range.
+    #[inline]
+    pub fn overlapping<R>(
+        &self,
+        byte_range: R,
+    ) -> impl Iterator<Item = (IntervalId, Range<usize>, &T)> + '_
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            crate::range_bounds_to_start_end(byte_range, 0, usize::MAX);
+
+        self.entries_where(move |offset, summary| {
+            offset.span < end && summary.max_end_from(offset) > start
+        })
+        .filter(move |&(_, entry_start, entry)| {
+            entry_start < end && entry_start + entry.len > start
+        })
+        .map(to_item)
+    }
+
+    /// Removes the interval with the given id, returning its range and value
+    /// if it hadn't already been removed.
+    ///
+    /// Unlike the other operations this takes `O(n)` time, since the
+    /// interval has to be looked up by its id.
+    #[inline]
+    pub fn remove(&mut self, id: IntervalId) -> Option<(Range<usize>, T)> {
+        let (idx, start, removed) = self
+            .entries_where(|_, _| true)
+            .find(|(_, _, entry)| entry.id == id)
+            .map(|(idx, start, entry)| (idx, start, entry.clone()))?;
+
+        let next =
+            self.entries_in(idx + 1..idx + 2).next().map(|(_, _, next)| {
+                Entry { gap: removed.gap + next.gap, ..next.clone() }
+            });
+
+        match next {
+            Some(next) => {
+                self.replace_entries(idx..idx + 2, alloc::vec![next])
+            },
+            None => self.replace_entries(idx..idx + 1, Vec::new()),
+        }
+
+        Some((start..start + removed.len, removed.value))
+    }
+
+    /// Replaces the intervals with an index in the given range with the
+    /// given entries.
+    #[inline]
+    fn replace_entries(
+        &mut self,
+        range: Range<usize>,
+        entries: Vec<Entry<T>>,
+    ) {
+        self.tree.replace(
+            IntervalCount(range.start)..IntervalCount(range.end),
+            entries,
+        );
+    }
+
+    /// Returns the start of the interval right before the one at the given
+    /// index, or zero if there isn't one.
+    #[inline]
+    fn start_of(&self, idx: usize) -> usize {
+        let IntervalSpan(start) =
+            self.tree.convert_measure::<_, IntervalSpan>(IntervalCount(idx));
+        start
+    }
+}
+
+impl<T: Clone + 'static> Default for IntervalTree<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + core::fmt::Debug + 'static> core::fmt::Debug
+    for IntervalTree<T>
+{
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(|(_, range, value)| (range, value)))
+            .finish()
+    }
+}
+
+#[inline]
+fn to_item<T>(
+    (_, start, entry): (usize, usize, &Entry<T>),
+) -> (IntervalId, Range<usize>, &T) {
+    (entry.id, start..start + entry.len, &entry.value)
+}
+
+/// An interval stored in a leaf of the tree.
+///
+/// The start of an interval is stored as the distance from the start of the
+/// interval before it, so that shifting all the intervals after an edit only
+/// requires updating the first one.
+#[derive(Clone)]
+struct Entry<T> {
+    id: IntervalId,
+    gap: usize,
+    len: usize,
+    value: T,
+}
+
+impl<T> Entry<T> {
+    #[inline]
+    fn summarize(&self) -> IntervalSummary {
+        IntervalSummary { count: 1, span: self.gap, max_len: self.len }
+    }
+}
+
+impl<T> core::fmt::Debug for Entry<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:?}: +{} ({})", self.id, self.gap, self.len)
+    }
+}
+
+/// The leaves of an [`IntervalTree`], storing the intervals sorted by their
+/// start.
+#[derive(Clone)]
+struct IntervalChunk<T> {
+    entries: Vec<Entry<T>>,
+}
+
+impl<T> Default for IntervalChunk<T> {
+    #[inline]
+    fn default() -> Self {
+        Self { entries: Vec::new() }
+    }
+}
+
+impl<T> core::fmt::Debug for IntervalChunk<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list().entries(&self.entries).finish()
+    }
+}
+
+impl<T> Summarize for IntervalChunk<T> {
+    type Summary = IntervalSummary;
+
+    #[inline]
+    fn summarize(&self) -> IntervalSummary {
+        self.as_slice().summarize()
+    }
+}
+
+impl<T> BaseMeasured for IntervalChunk<T> {
+    type BaseMetric = IntervalCount;
+}
+
+impl<T> AsSlice for IntervalChunk<T> {
+    type Slice<'a>
+        = IntervalSlice<'a, T>
+    where
+        T: 'a;
+
+    #[inline]
+    fn as_slice(&self) -> IntervalSlice<'_, T> {
+        IntervalSlice { entries: &self.entries }
+    }
+}
+
+impl<T: Clone + 'static> BalancedLeaf for IntervalChunk<T> {
+    #[inline]
+    fn is_underfilled(&self, summary: &IntervalSummary) -> bool {
+        summary.count < CHUNK_MIN_INTERVALS
+    }
+
+    #[inline]
+    fn balance_leaves(
+        (left, left_summary): (&mut Self, &mut IntervalSummary),
+        (right, right_summary): (&mut Self, &mut IntervalSummary),
+    ) {
+        let total = left.entries.len() + right.entries.len();
+
+        if total <= CHUNK_MAX_INTERVALS {
+            left.entries.append(&mut right.entries);
+        } else if left.entries.len() < right.entries.len() {
+            let moved = right.entries.len() - total / 2;
+            left.entries.extend(right.entries.drain(..moved));
+        } else {
+            let moved = left.entries.len() - total / 2;
+            let split = left.entries.len() - moved;
+            right.entries.splice(..0, left.entries.drain(split..));
+        }
+
+        *left_summary = left.summarize();
+        *right_summary = right.summarize();
+    }
+}
+
+impl<T: Clone + 'static> From<IntervalSlice<'_, T>> for IntervalChunk<T> {
+    #[inline]
+    fn from(slice: IntervalSlice<'_, T>) -> Self {
+        Self { entries: slice.entries.to_vec() }
+    }
+}
+
+impl<T: Clone + 'static> ReplaceableLeaf<IntervalCount> for IntervalChunk<T> {
+    type Replacement<'a> = Vec<Entry<T>>;
+
+    type ExtraLeaves = alloc::vec::IntoIter<Self>;
+
+    #[inline]
+    fn replace<R>(
+        &mut self,
+        summary: &mut IntervalSummary,
+        range: R,
+        replacement: Vec<Entry<T>>,
+    ) -> Option<Self::ExtraLeaves>
+    where
+        R: RangeBounds<IntervalCount>,
+    {
+        let (start, end) =
+            crate::range_bounds_to_start_end(range, 0, self.entries.len());
+
+        self.entries.splice(start..end, replacement);
+
+        let total = self.entries.len();
+
+        if total <= CHUNK_MAX_INTERVALS {
+            *summary = self.summarize();
+            return None;
+        }
+
+        // Split the entries in chunks of roughly the same size, all of which
+        // are guaranteed to be at least half full.
+        let chunks = (total + CHUNK_MAX_INTERVALS - 1) / CHUNK_MAX_INTERVALS;
+
+        let mut extras = Vec::with_capacity(chunks - 1);
+
+        for idx in (1..chunks).rev() {
+            let split = idx * total / chunks;
+            let entries = self.entries.split_off(split);
+            extras.push(Self { entries });
+        }
+
+        extras.reverse();
+
+        *summary = self.summarize();
+
+        Some(extras.into_iter())
+    }
+
+    #[inline]
+    fn remove_up_to(
+        &mut self,
+        summary: &mut IntervalSummary,
+        up_to: IntervalCount,
+    ) {
+        self.entries.drain(..up_to.0);
+        *summary = self.summarize();
+    }
+}
+
+struct IntervalSlice<'a, T> {
+    entries: &'a [Entry<T>],
+}
+
+impl<T> Clone for IntervalSlice<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for IntervalSlice<'_, T> {}
+
+impl<T> Default for IntervalSlice<'_, T> {
+    #[inline]
+    fn default() -> Self {
+        Self { entries: &[] }
+    }
+}
+
+impl<T> core::fmt::Debug for IntervalSlice<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list().entries(self.entries).finish()
+    }
+}
+
+impl<T> Summarize for IntervalSlice<'_, T> {
+    type Summary = IntervalSummary;
+
+    #[inline]
+    fn summarize(&self) -> IntervalSummary {
+        self.entries
+            .iter()
+            .fold(IntervalSummary::default(), |summary, entry| {
+                summary + &entry.summarize()
+            })
+    }
+}
+
+/// The summary of a sequence of intervals. All the offsets are relative to
+/// the start of the interval right before the first one in the sequence.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct IntervalSummary {
+    /// The number of intervals.
+    count: usize,
+
+    /// The offset of the start of the last interval.
+    span: usize,
+
+    /// An upper bound on the length of the intervals.
+    ///
+    /// This is exact when the summary is computed from the intervals or by
+    /// adding other summaries, but subtracting a summary leaves it unchanged
+    /// since the maximum can't be undone, so the bound stored in the
+    /// internal nodes of the tree can become loose after an edit. This only
+    /// means that queries may visit some subtrees that don't contain any
+    /// matches.
+    max_len: usize,
+}
+
+impl IntervalSummary {
+    /// Returns an upper bound on the end of the intervals, given the summary
+    /// of everything before them.
+    #[inline]
+    fn max_end_from(&self, offset: &Self) -> usize {
+        offset.span + self.span + self.max_len
+    }
+}
+
+impl Add<&Self> for IntervalSummary {
+    type Output = Self;
+
+    #[inline]
+    fn add(mut self, rhs: &Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+impl Sub<&Self> for IntervalSummary {
+    type Output = Self;
+
+    #[inline]
+    fn sub(mut self, rhs: &Self) -> Self {
+        self -= rhs;
+        self
+    }
+}
+
+impl AddAssign<&Self> for IntervalSummary {
+    #[inline]
+    fn add_assign(&mut self, rhs: &Self) {
+        self.max_len = self.max_len.max(rhs.max_len);
+        self.count += rhs.count;
+        self.span += rhs.span;
+    }
+}
+
+impl SubAssign<&Self> for IntervalSummary {
+    #[inline]
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.count -= rhs.count;
+        self.span -= rhs.span;
+    }
+}
+
+/// A metric counting the number of intervals.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct IntervalCount(usize);
+
+/// A metric measuring the offset of the start of the last interval.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct IntervalSpan(usize);
+
+macro_rules! impl_metric {
+    ($metric:ident, $field:ident, $is_before:expr) => {
+        impl Add for $metric {
+            type Output = Self;
+
+            #[inline]
+            fn add(self, other: Self) -> Self {
+                Self(self.0 + other.0)
+            }
+        }
+
+        impl Sub for $metric {
+            type Output = Self;
+
+            #[inline]
+            fn sub(self, other: Self) -> Self {
+                Self(self.0 - other.0)
+            }
+        }
+
+        impl AddAssign for $metric {
+            #[inline]
+            fn add_assign(&mut self, other: Self) {
+                self.0 += other.0
+            }
+        }
+
+        impl SubAssign for $metric {
+            #[inline]
+            fn sub_assign(&mut self, other: Self) {
+                self.0 -= other.0
+            }
+        }
+
+        impl Metric<IntervalSummary> for $metric {
+            #[inline]
+            fn zero() -> Self {
+                Self(0)
+            }
+
+            #[inline]
+            fn one() -> Self {
+                Self(1)
+            }
+
+            #[inline]
+            fn measure(summary: &IntervalSummary) -> Self {
+                Self(summary.$field)
+            }
+        }
+
+        impl<T: 'static> SlicingMetric<IntervalChunk<T>> for $metric {
+            #[inline]
+            fn slice_up_to<'a>(
+                slice: <IntervalChunk<T> as AsSlice>::Slice<'a>,
+                up_to: Self,
+                _: &IntervalSummary,
+            ) -> (IntervalSlice<'a, T>, IntervalSummary) {
+                let (left, _) = split_slice(slice, |before, including| {
+                    $is_before(
+                        Self::measure(before),
+                        Self::measure(including),
+                        up_to,
+                    )
+                });
+                (left, left.summarize())
+            }
+
+            #[inline]
+            fn slice_from<'a>(
+                slice: <IntervalChunk<T> as AsSlice>::Slice<'a>,
+                from: Self,
+                _: &IntervalSummary,
+            ) -> (IntervalSlice<'a, T>, IntervalSummary) {
+                let (_, right) = split_slice(slice, |before, including| {
+                    $is_before(
+                        Self::measure(before),
+                        Self::measure(including),
+                        from,
+                    )
+                });
+                (right, right.summarize())
+            }
+        }
+    };
+}
+
+impl Add<usize> for IntervalCount {
+    type Output = usize;
+
+    #[inline]
+    fn add(self, other: usize) -> usize {
+        self.0 + other
+    }
+}
+
+impl From<IntervalCount> for usize {
+    #[inline]
+    fn from(IntervalCount(count): IntervalCount) -> usize {
+        count
+    }
+}
+
+// An interval is before the `n`-th one if less than `n` intervals precede it.
+impl_metric!(IntervalCount, count, |before: Self, _, n: Self| before < n);
+
+// An interval is before an offset if it starts before it.
+impl_metric!(IntervalSpan, span, |_, including: Self, offset: Self| {
+    including < offset
+});
+
+/// Splits the slice right before the first interval for which the predicate
+/// returns `false`. The predicate is called with the summaries of the slice
+/// up to the interval, both excluding and including it.
+#[inline]
+fn split_slice<T, P>(
+    slice: IntervalSlice<'_, T>,
+    mut predicate: P,
+) -> (IntervalSlice<'_, T>, IntervalSlice<'_, T>)
+where
+    P: FnMut(&IntervalSummary, &IntervalSummary) -> bool,
+{
+    let mut summary = IntervalSummary::default();
+
+    let split = slice
+        .entries
+        .iter()
+        .take_while(|entry| {
+            let before = summary;
+            summary += &entry.summarize();
+            predicate(&before, &summary)
+        })
+        .count();
+
+    let (left, right) = slice.entries.split_at(split);
+
+    (IntervalSlice { entries: left }, IntervalSlice { entries: right })
+}
@@ -1,22 +1,29 @@
-use super::metrics::{ByteMetric, LineMetric, RawLineMetric};
-use super::rope::RopeChunk;
+use super::gap_buffer::GapBuffer;
+use super::rope::{DEFAULT_ARITY, DEFAULT_CHUNK_MAX_BYTES};
 use super::{Rope, RopeSlice};
-use crate::tree::{Leaves, Units};
+use crate::tree::{AtomicCount, Leaves, RefCount};
 
 /// An iterator over the `&str` chunks of `Rope`s and `RopeSlice`s.
 ///
 /// This struct is created by the `chunks` method on [`Rope`](Rope::chunks())
 /// and [`RopeSlice`](RopeSlice::chunks()). See their documentation for more.
 #[derive(Clone)]
-pub struct Chunks<'a> {
-    leaves: Leaves<'a, { Rope::arity() }, RopeChunk>,
+pub struct Chunks<
+    'a,
+    C: RefCount = AtomicCount,
+    const ARITY: usize = DEFAULT_ARITY,
+    const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+> {
+    leaves: Leaves<'a, ARITY, GapBuffer<MAX_BYTES>, C>,
     forward_extra_right: Option<&'a str>,
     backward_extra_left: Option<&'a str>,
 }
 
-impl<'a> From<&'a Rope> for Chunks<'a> {
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<&'a Rope<C, ARITY, MAX_BYTES>> for Chunks<'a, C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn from(rope: &'a Rope) -> Self {
+    fn from(rope: &'a Rope<C, ARITY, MAX_BYTES>) -> Self {
         let mut leaves = rope.tree.leaves();
         if rope.is_empty() {
             let _ = leaves.next();
@@ -25,9 +32,12 @@ impl<'a> From<&'a Rope> for Chunks<'a> {
     }
 }
 
-impl<'a> From<&RopeSlice<'a>> for Chunks<'a> {
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<&RopeSlice<'a, C, ARITY, MAX_BYTES>>
+    for Chunks<'a, C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn from(slice: &RopeSlice<'a>) -> Self {
+    fn from(slice: &RopeSlice<'a, C, ARITY, MAX_BYTES>) -> Self {
         let mut leaves = slice.tree_slice.leaves();
         if slice.is_empty() {
             let _ = leaves.next();
@@ -36,7 +46,9 @@ impl<'a> From<&RopeSlice<'a>> for Chunks<'a> {
     }
 }
 
-impl<'a> Iterator for Chunks<'a> {
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize> Iterator
+    for Chunks<'a, C, ARITY, MAX_BYTES>
+{
     type Item = &'a str;
 
     #[inline]
@@ -73,7 +85,9 @@ impl<'a> Iterator for Chunks<'a> {
     }
 }
 
-impl DoubleEndedIterator for Chunks<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    DoubleEndedIterator for Chunks<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn next_back(&mut self) -> Option<Self::Item> {
         if let Some(extra) = self.backward_extra_left.take() {
@@ -102,15 +116,23 @@ impl DoubleEndedIterator for Chunks<'_> {
     }
 }
 
-impl core::iter::FusedIterator for Chunks<'_> {}
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::iter::FusedIterator for Chunks<'_, C, ARITY, MAX_BYTES>
+{
+}
 
 /// An iterator over the bytes of `Rope`s and `RopeSlice`s.
 ///
 /// This struct is created by the `bytes` method on [`Rope`](Rope::bytes())
 /// and [`RopeSlice`](RopeSlice::bytes()). See their documentation for more.
 #[derive(Clone)]
-pub struct Bytes<'a> {
-    chunks: Chunks<'a>,
+pub struct Bytes<
+    'a,
+    C: RefCount = AtomicCount,
+    const ARITY: usize = DEFAULT_ARITY,
+    const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+> {
+    chunks: Chunks<'a, C, ARITY, MAX_BYTES>,
 
     /// The chunk used when calling [`Bytes::next()`].
     forward_chunk: &'a [u8],
@@ -131,9 +153,11 @@ pub struct Bytes<'a> {
     bytes_total: usize,
 }
 
-impl<'a> From<&'a Rope> for Bytes<'a> {
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<&'a Rope<C, ARITY, MAX_BYTES>> for Bytes<'a, C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn from(rope: &'a Rope) -> Self {
+    fn from(rope: &'a Rope<C, ARITY, MAX_BYTES>) -> Self {
         Self {
             chunks: rope.chunks(),
             forward_chunk: &[],
@@ -146,9 +170,12 @@ impl<'a> From<&'a Rope> for Bytes<'a> {
     }
 }
 
-impl<'a> From<&RopeSlice<'a>> for Bytes<'a> {
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<&RopeSlice<'a, C, ARITY, MAX_BYTES>>
+    for Bytes<'a, C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn from(slice: &RopeSlice<'a>) -> Self {
+    fn from(slice: &RopeSlice<'a, C, ARITY, MAX_BYTES>) -> Self {
         Self {
             chunks: slice.chunks(),
             forward_chunk: &[],
@@ -161,7 +188,9 @@ impl<'a> From<&RopeSlice<'a>> for Bytes<'a> {
     }
 }
 
-impl Iterator for Bytes<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> Iterator
+    for Bytes<'_, C, ARITY, MAX_BYTES>
+{
     type Item = u8;
 
     #[inline]
@@ -194,7 +223,9 @@ impl Iterator for Bytes<'_> {
     }
 }
 
-impl DoubleEndedIterator for Bytes<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    DoubleEndedIterator for Bytes<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn next_back(&mut self) -> Option<Self::Item> {
         if self.backward_byte_idx == 0 {
@@ -219,14 +250,19 @@ impl DoubleEndedIterator for Bytes<'_> {
     }
 }
 
-impl ExactSizeIterator for Bytes<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> ExactSizeIterator
+    for Bytes<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn len(&self) -> usize {
         self.bytes_total - self.bytes_yielded
     }
 }
 
-impl core::iter::FusedIterator for Bytes<'_> {}
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::iter::FusedIterator for Bytes<'_, C, ARITY, MAX_BYTES>
+{
+}
 
 /// An iterator over the code points (i.e. [`char`]s) of `Rope`s and
 /// `RopeSlice`s.
@@ -234,8 +270,13 @@ impl core::iter::FusedIterator for Bytes<'_> {}
 /// This struct is created by the `chars` method on [`Rope`](Rope::chars())
 /// and [`RopeSlice`](RopeSlice::chars()). See their documentation for more.
 #[derive(Clone)]
-pub struct Chars<'a> {
-    chunks: Chunks<'a>,
+pub struct Chars<
+    'a,
+    C: RefCount = AtomicCount,
+    const ARITY: usize = DEFAULT_ARITY,
+    const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+> {
+    chunks: Chunks<'a, C, ARITY, MAX_BYTES>,
 
     /// The chunk used when calling [`Chars::next()`].
     forward_chunk: &'a str,
@@ -251,9 +292,11 @@ pub struct Chars<'a> {
     backward_byte_idx: usize,
 }
 
-impl<'a> From<&'a Rope> for Chars<'a> {
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<&'a Rope<C, ARITY, MAX_BYTES>> for Chars<'a, C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn from(rope: &'a Rope) -> Self {
+    fn from(rope: &'a Rope<C, ARITY, MAX_BYTES>) -> Self {
         Self {
             chunks: rope.chunks(),
             forward_chunk: "",
@@ -264,9 +307,12 @@ impl<'a> From<&'a Rope> for Chars<'a> {
     }
 }
 
-impl<'a> From<&RopeSlice<'a>> for Chars<'a> {
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<&RopeSlice<'a, C, ARITY, MAX_BYTES>>
+    for Chars<'a, C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn from(slice: &RopeSlice<'a>) -> Self {
+    fn from(slice: &RopeSlice<'a, C, ARITY, MAX_BYTES>) -> Self {
         Self {
             chunks: slice.chunks(),
             forward_chunk: "",
@@ -277,7 +323,9 @@ impl<'a> From<&RopeSlice<'a>> for Chars<'a> {
     }
 }
 
-impl Iterator for Chars<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> Iterator
+    for Chars<'_, C, ARITY, MAX_BYTES>
+{
     type Item = char;
 
     #[inline]
@@ -321,7 +369,9 @@ impl Iterator for Chars<'_> {
     }
 }
 
-impl DoubleEndedIterator for Chars<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    DoubleEndedIterator for Chars<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn next_back(&mut self) -> Option<Self::Item> {
         if self.backward_byte_idx == 0 {
@@ -363,55 +413,171 @@ impl DoubleEndedIterator for Chars<'_> {
     }
 }
 
-impl core::iter::FusedIterator for Chars<'_> {}
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::iter::FusedIterator for Chars<'_, C, ARITY, MAX_BYTES>
+{
+}
 
-/// An iterator over the lines of `Rope`s and `RopeSlice`s, including the line
-/// terminators (`\n` or `\r\n`).
+/// An iterator over the UTF-16 code units of the text of `Rope`s and
+/// `RopeSlice`s, like the one returned by [`str::encode_utf16()`].
 ///
-/// This struct is created by the `raw_lines` method on
-/// [`Rope`](Rope::raw_lines()) and [`RopeSlice`](RopeSlice::raw_lines()). See
-/// their documentation for more.
+/// This struct is created by the `encode_utf16` method on
+/// [`Rope`](Rope::encode_utf16()) and
+/// [`RopeSlice`](RopeSlice::encode_utf16()). See their documentation for
+/// more.
 #[derive(Clone)]
-pub struct RawLines<'a> {
-    units: Units<'a, { Rope::arity() }, RopeChunk, RawLineMetric>,
+pub struct EncodeUtf16<
+    'a,
+    C: RefCount = AtomicCount,
+    const ARITY: usize = DEFAULT_ARITY,
+    const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+> {
+    chunks: Chunks<'a, C, ARITY, MAX_BYTES>,
+
+    /// The code units of the chunk currently being encoded.
+    current: core::str::EncodeUtf16<'a>,
+}
 
-    /// The number of lines that have been yielded so far.
-    lines_yielded: usize,
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<&'a Rope<C, ARITY, MAX_BYTES>>
+    for EncodeUtf16<'a, C, ARITY, MAX_BYTES>
+{
+    #[inline]
+    fn from(rope: &'a Rope<C, ARITY, MAX_BYTES>) -> Self {
+        Self { chunks: rope.chunks(), current: "".encode_utf16() }
+    }
+}
 
-    /// The total number of bytes this iterator will yield.
-    lines_total: usize,
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<&RopeSlice<'a, C, ARITY, MAX_BYTES>>
+    for EncodeUtf16<'a, C, ARITY, MAX_BYTES>
+{
+    #[inline]
+    fn from(slice: &RopeSlice<'a, C, ARITY, MAX_BYTES>) -> Self {
+        Self { chunks: slice.chunks(), current: "".encode_utf16() }
+    }
 }
 
-impl<'a> From<&'a Rope> for RawLines<'a> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> Iterator
+    for EncodeUtf16<'_, C, ARITY, MAX_BYTES>
+{
+    type Item = u16;
+
     #[inline]
-    fn from(rope: &'a Rope) -> Self {
-        Self {
-            units: rope.tree.units::<RawLineMetric>(),
-            lines_yielded: 0,
-            lines_total: rope.line_len(),
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(code_unit) = self.current.next() {
+                return Some(code_unit);
+            }
+
+            self.current = self.chunks.next()?.encode_utf16();
         }
     }
 }
 
-impl<'a> From<&RopeSlice<'a>> for RawLines<'a> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::iter::FusedIterator for EncodeUtf16<'_, C, ARITY, MAX_BYTES>
+{
+}
+
+/// An iterator over the hashes of every window of `window_size` consecutive
+/// bytes of `Rope`s and `RopeSlice`s.
+///
+/// This struct is created by the `rolling_hash` method on
+/// [`Rope`](Rope::rolling_hash()) and
+/// [`RopeSlice`](RopeSlice::rolling_hash()). See their documentation for
+/// more.
+#[derive(Clone)]
+pub struct RollingHash<
+    'a,
+    C: RefCount = AtomicCount,
+    const ARITY: usize = DEFAULT_ARITY,
+    const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+> {
+    /// The bytes entering the window.
+    head: Bytes<'a, C, ARITY, MAX_BYTES>,
+
+    /// The bytes leaving the window, which are `window_size` bytes behind
+    /// `head` after the first window has been hashed.
+    tail: Bytes<'a, C, ARITY, MAX_BYTES>,
+
+    /// The hash of the current window.
+    hash: u64,
+
+    /// `ROLLING_HASH_BASE` raised to `window_size - 1`, i.e. the factor of
+    /// the byte leaving the window.
+    leaving_factor: u64,
+
+    /// Whether the first window has been hashed.
+    has_started: bool,
+
+    window_size: usize,
+}
+
+/// The base of the polynomial hash computed by [`RollingHash`].
+const ROLLING_HASH_BASE: u64 = 0x0000_0100_0000_01b3;
+
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    RollingHash<'a, C, ARITY, MAX_BYTES>
+{
+    #[track_caller]
     #[inline]
-    fn from(slice: &RopeSlice<'a>) -> Self {
+    pub(super) fn new(
+        bytes: Bytes<'a, C, ARITY, MAX_BYTES>,
+        window_size: usize,
+    ) -> Self {
+        assert!(window_size > 0, "the window size must be positive");
+
+        let leaving_factor = (1..window_size)
+            .fold(1u64, |factor, _| factor.wrapping_mul(ROLLING_HASH_BASE));
+
         Self {
-            units: slice.tree_slice.units::<RawLineMetric>(),
-            lines_yielded: 0,
-            lines_total: slice.line_len(),
+            head: bytes.clone(),
+            tail: bytes,
+            hash: 0,
+            leaving_factor,
+            has_started: false,
+            window_size,
         }
     }
 }
 
-impl<'a> Iterator for RawLines<'a> {
-    type Item = RopeSlice<'a>;
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> Iterator
+    for RollingHash<'_, C, ARITY, MAX_BYTES>
+{
+    type Item = u64;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let (tree_slice, _) = self.units.next()?;
-        self.lines_yielded += 1;
-        Some(RopeSlice::from(tree_slice))
+        if !self.has_started {
+            if self.head.len() < self.window_size {
+                return None;
+            }
+
+            self.hash = self.head.by_ref().take(self.window_size).fold(
+                0,
+                |hash, byte| {
+                    hash.wrapping_mul(ROLLING_HASH_BASE)
+                        .wrapping_add(byte as u64)
+                },
+            );
+
+            self.has_started = true;
+
+            return Some(self.hash);
+        }
+
+        let entering = self.head.next()?;
+
+        let leaving = self.tail.next().expect("tail is behind head");
+
+        self.hash = self
+            .hash
+            .wrapping_sub((leaving as u64).wrapping_mul(self.leaving_factor))
+            .wrapping_mul(ROLLING_HASH_BASE)
+            .wrapping_add(entering as u64);
+
+        Some(self.hash)
     }
 
     #[inline]
@@ -421,119 +587,310 @@ impl<'a> Iterator for RawLines<'a> {
     }
 }
 
-impl DoubleEndedIterator for RawLines<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> ExactSizeIterator
+    for RollingHash<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn next_back(&mut self) -> Option<Self::Item> {
-        let (tree_slice, _) = self.units.next_back()?;
-        self.lines_yielded += 1;
-        Some(RopeSlice::from(tree_slice))
+    fn len(&self) -> usize {
+        if self.has_started {
+            self.head.len()
+        } else {
+            (self.head.len() + 1).saturating_sub(self.window_size)
+        }
     }
 }
 
-impl ExactSizeIterator for RawLines<'_> {
-    #[inline]
-    fn len(&self) -> usize {
-        self.lines_total - self.lines_yielded
-    }
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::iter::FusedIterator for RollingHash<'_, C, ARITY, MAX_BYTES>
+{
 }
 
-impl core::iter::FusedIterator for RawLines<'_> {}
+#[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+#[cfg(feature = "line-metric")]
+pub use lines::{Lines, RawLines};
 
-/// An iterator over the lines of `Rope`s and `RopeSlice`s, not including the
-/// line terminators (`\n` or `\r\n`).
-///
-/// This struct is created by the `lines` method on [`Rope`](Rope::lines()) and
-/// [`RopeSlice`](RopeSlice::lines()). See their documentation for more.
-#[derive(Clone)]
-pub struct Lines<'a> {
-    units: Units<'a, { Rope::arity() }, RopeChunk, LineMetric>,
+#[cfg(feature = "line-metric")]
+mod lines {
+    use super::*;
+    use crate::rope::metrics::{ByteMetric, LineMetric, RawLineMetric};
+    use crate::tree::Units;
 
-    /// The number of lines that have been yielded so far.
-    lines_yielded: usize,
+    /// An iterator over the lines of `Rope`s and `RopeSlice`s, including the line
+    /// terminators (`\n` or `\r\n`).
+    ///
+    /// This struct is created by the `raw_lines` method on
+    /// [`Rope`](Rope::raw_lines()) and [`RopeSlice`](RopeSlice::raw_lines()). See
+    /// their documentation for more.
+    #[derive(Clone)]
+    pub struct RawLines<
+        'a,
+        C: RefCount = AtomicCount,
+        const ARITY: usize = DEFAULT_ARITY,
+        const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+    > {
+        units: Units<'a, ARITY, GapBuffer<MAX_BYTES>, RawLineMetric, C>,
+
+        /// The number of lines that have been yielded so far.
+        lines_yielded: usize,
+
+        /// The total number of bytes this iterator will yield.
+        lines_total: usize,
+    }
 
-    /// The total number of bytes this iterator will yield.
-    lines_total: usize,
-}
+    impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        From<&'a Rope<C, ARITY, MAX_BYTES>>
+        for RawLines<'a, C, ARITY, MAX_BYTES>
+    {
+        #[inline]
+        fn from(rope: &'a Rope<C, ARITY, MAX_BYTES>) -> Self {
+            Self {
+                units: rope.tree.units::<RawLineMetric>(),
+                lines_yielded: 0,
+                lines_total: rope.line_len(),
+            }
+        }
+    }
 
-impl<'a> From<&'a Rope> for Lines<'a> {
-    #[inline]
-    fn from(rope: &'a Rope) -> Self {
-        Self {
-            units: rope.tree.units::<LineMetric>(),
-            lines_yielded: 0,
-            lines_total: rope.line_len(),
+    impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        From<&RopeSlice<'a, C, ARITY, MAX_BYTES>>
+        for RawLines<'a, C, ARITY, MAX_BYTES>
+    {
+        #[inline]
+        fn from(slice: &RopeSlice<'a, C, ARITY, MAX_BYTES>) -> Self {
+            Self {
+                units: slice.tree_slice.units::<RawLineMetric>(),
+                lines_yielded: 0,
+                lines_total: slice.line_len(),
+            }
         }
     }
-}
 
-impl<'a> From<&RopeSlice<'a>> for Lines<'a> {
-    #[inline]
-    fn from(slice: &RopeSlice<'a>) -> Self {
-        Self {
-            units: slice.tree_slice.units::<LineMetric>(),
-            lines_yielded: 0,
-            lines_total: slice.line_len(),
+    impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize> Iterator
+        for RawLines<'a, C, ARITY, MAX_BYTES>
+    {
+        type Item = RopeSlice<'a, C, ARITY, MAX_BYTES>;
+
+        #[inline]
+        fn next(&mut self) -> Option<Self::Item> {
+            let (tree_slice, _) = self.units.next()?;
+            self.lines_yielded += 1;
+            Some(RopeSlice::from(tree_slice))
+        }
+
+        #[inline]
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let exact = self.len();
+            (exact, Some(exact))
         }
     }
-}
 
-impl<'a> Iterator for Lines<'a> {
-    type Item = RopeSlice<'a>;
+    impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        DoubleEndedIterator for RawLines<'_, C, ARITY, MAX_BYTES>
+    {
+        #[inline]
+        fn next_back(&mut self) -> Option<Self::Item> {
+            let (tree_slice, _) = self.units.next_back()?;
+            self.lines_yielded += 1;
+            Some(RopeSlice::from(tree_slice))
+        }
+    }
 
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        let (tree_slice, ByteMetric(advance)) = self.units.next()?;
-        self.lines_yielded += 1;
+    impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        ExactSizeIterator for RawLines<'_, C, ARITY, MAX_BYTES>
+    {
+        #[inline]
+        fn len(&self) -> usize {
+            self.lines_total - self.lines_yielded
+        }
+    }
 
-        let mut slice = RopeSlice { tree_slice, has_trailing_newline: false };
+    impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        core::iter::FusedIterator for RawLines<'_, C, ARITY, MAX_BYTES>
+    {
+    }
 
-        // This handles CRLF pairs that have been split across chunks. For
-        // example, if we have "aaa\r" and "\nbbb" we should yield "aaa", but
-        // the tree slice currently contains "aaa\r", so we need to remove
-        // the trailing "\r".
-        if slice.tree_slice.end_slice().last_chunk().ends_with('\r')
-            && advance - slice.byte_len() == 1
-        {
-            slice.truncate_last_char();
+    /// An iterator over the lines of `Rope`s and `RopeSlice`s, not including the
+    /// line terminators (`\n` or `\r\n`, or the other line breaks counted with
+    /// the `unicode-lines` feature).
+    ///
+    /// This struct is created by the `lines` method on [`Rope`](Rope::lines()) and
+    /// [`RopeSlice`](RopeSlice::lines()). See their documentation for more.
+    #[derive(Clone)]
+    pub struct Lines<
+        'a,
+        C: RefCount = AtomicCount,
+        const ARITY: usize = DEFAULT_ARITY,
+        const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+    > {
+        units: Units<'a, ARITY, GapBuffer<MAX_BYTES>, LineMetric, C>,
+
+        /// The number of lines that have been yielded so far.
+        lines_yielded: usize,
+
+        /// The total number of bytes this iterator will yield.
+        lines_total: usize,
+
+        /// An empty line after the final line break, yielded after all the other
+        /// lines when the trailing line break starts a new line.
+        trailing_line: Option<RopeSlice<'a, C, ARITY, MAX_BYTES>>,
+
+        /// The text the lines are in, used to check whether a one byte line
+        /// break following a carriage return is a line feed.
+        text: RopeSlice<'a, C, ARITY, MAX_BYTES>,
+
+        /// The byte offset in `text` of the start of the next line yielded by
+        /// `next()`.
+        front_offset: usize,
+
+        /// The byte offset in `text` of the end of the next line yielded by
+        /// `next_back()`, including its line break.
+        back_offset: usize,
+    }
+
+    impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        From<&'a Rope<C, ARITY, MAX_BYTES>>
+        for Lines<'a, C, ARITY, MAX_BYTES>
+    {
+        #[inline]
+        fn from(rope: &'a Rope<C, ARITY, MAX_BYTES>) -> Self {
+            Self {
+                units: rope.tree.units::<LineMetric>(),
+                lines_yielded: 0,
+                lines_total: rope.line_len(),
+                trailing_line: None,
+                text: rope.byte_slice(..),
+                front_offset: 0,
+                back_offset: rope.byte_len(),
+            }
         }
+    }
 
-        Some(slice)
+    impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        From<&RopeSlice<'a, C, ARITY, MAX_BYTES>>
+        for Lines<'a, C, ARITY, MAX_BYTES>
+    {
+        #[inline]
+        fn from(slice: &RopeSlice<'a, C, ARITY, MAX_BYTES>) -> Self {
+            Self {
+                units: slice.tree_slice.units::<LineMetric>(),
+                lines_yielded: 0,
+                lines_total: slice.line_len(),
+                trailing_line: None,
+                text: *slice,
+                front_offset: 0,
+                back_offset: slice.byte_len(),
+            }
+        }
     }
 
-    #[inline]
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        let exact = self.len();
-        (exact, Some(exact))
+    impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        Lines<'a, C, ARITY, MAX_BYTES>
+    {
+        /// Makes the iterator yield the given empty slice as its last line.
+        #[inline]
+        pub(crate) fn with_trailing_line(
+            mut self,
+            empty: RopeSlice<'a, C, ARITY, MAX_BYTES>,
+        ) -> Self {
+            debug_assert!(empty.is_empty());
+            self.lines_total += 1;
+            self.trailing_line = Some(empty);
+            self
+        }
     }
-}
 
-impl DoubleEndedIterator for Lines<'_> {
-    #[inline]
-    fn next_back(&mut self) -> Option<Self::Item> {
-        let (tree_slice, ByteMetric(advance)) = self.units.next_back()?;
-        self.lines_yielded += 1;
+    impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize> Iterator
+        for Lines<'a, C, ARITY, MAX_BYTES>
+    {
+        type Item = RopeSlice<'a, C, ARITY, MAX_BYTES>;
 
-        let mut slice = RopeSlice { tree_slice, has_trailing_newline: false };
+        #[inline]
+        fn next(&mut self) -> Option<Self::Item> {
+            let Some((tree_slice, ByteMetric(advance))) = self.units.next()
+            else {
+                let trailing_line = self.trailing_line.take()?;
+                self.lines_yielded += 1;
+                return Some(trailing_line);
+            };
 
-        // Same as above.
-        if slice.tree_slice.end_slice().last_chunk().ends_with('\r')
-            && advance - slice.byte_len() == 1
-        {
-            slice.truncate_last_char();
+            self.lines_yielded += 1;
+
+            let mut slice =
+                RopeSlice { tree_slice, has_trailing_newline: false };
+
+            let line_end = self.front_offset + slice.byte_len();
+
+            self.front_offset += advance;
+
+            // This handles CRLF pairs that have been split across chunks. For
+            // example, if we have "aaa\r" and "\nbbb" we should yield "aaa", but
+            // the tree slice currently contains "aaa\r", so we need to remove
+            // the trailing "\r". With the `unicode-lines` feature the line break
+            // could also be a vertical tab or a form feed, which don't make a
+            // pair with the "\r", so we check that it's a line feed.
+            if slice.tree_slice.end_slice().last_chunk().ends_with('\r')
+                && advance - slice.byte_len() == 1
+                && self.text.byte(line_end) == b'\n'
+            {
+                slice.truncate_last_char();
+            }
+
+            Some(slice)
         }
 
-        Some(slice)
+        #[inline]
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let exact = self.len();
+            (exact, Some(exact))
+        }
     }
-}
 
-impl ExactSizeIterator for Lines<'_> {
-    #[inline]
-    fn len(&self) -> usize {
-        self.lines_total - self.lines_yielded
+    impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        DoubleEndedIterator for Lines<'_, C, ARITY, MAX_BYTES>
+    {
+        #[inline]
+        fn next_back(&mut self) -> Option<Self::Item> {
+            if let Some(trailing_line) = self.trailing_line.take() {
+                self.lines_yielded += 1;
+                return Some(trailing_line);
+            }
+
+            let (tree_slice, ByteMetric(advance)) = self.units.next_back()?;
+            self.lines_yielded += 1;
+
+            let mut slice =
+                RopeSlice { tree_slice, has_trailing_newline: false };
+
+            self.back_offset -= advance;
+
+            let line_end = self.back_offset + slice.byte_len();
+
+            // Same as above.
+            if slice.tree_slice.end_slice().last_chunk().ends_with('\r')
+                && advance - slice.byte_len() == 1
+                && self.text.byte(line_end) == b'\n'
+            {
+                slice.truncate_last_char();
+            }
+
+            Some(slice)
+        }
     }
-}
 
-impl core::iter::FusedIterator for Lines<'_> {}
+    impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        ExactSizeIterator for Lines<'_, C, ARITY, MAX_BYTES>
+    {
+        #[inline]
+        fn len(&self) -> usize {
+            self.lines_total - self.lines_yielded
+        }
+    }
+
+    impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        core::iter::FusedIterator for Lines<'_, C, ARITY, MAX_BYTES>
+    {
+    }
+}
 
 #[cfg_attr(docsrs, doc(cfg(feature = "graphemes")))]
 #[cfg(feature = "graphemes")]
@@ -554,12 +911,17 @@ mod graphemes {
     /// [`Rope`](Rope::graphemes()) and [`RopeSlice`](RopeSlice::graphemes()).
     /// See their documentation for more.
     #[derive(Clone)]
-    pub struct Graphemes<'a> {
-        chunks: Chunks<'a>,
+    pub struct Graphemes<
+        'a,
+        C: RefCount = AtomicCount,
+        const ARITY: usize = DEFAULT_ARITY,
+        const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+    > {
+        chunks: Chunks<'a, C, ARITY, MAX_BYTES>,
 
         /// The slice we're iterating over, used to provide precontext to the
         /// `GraphemeCursor`s.
-        slice: RopeSlice<'a>,
+        slice: RopeSlice<'a, C, ARITY, MAX_BYTES>,
 
         /// The cursor used when calling [`Graphemes::next()`].
         forward_cursor: GraphemeCursor,
@@ -586,9 +948,12 @@ mod graphemes {
         backward_offset: usize,
     }
 
-    impl<'a> From<&'a Rope> for Graphemes<'a> {
+    impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        From<&'a Rope<C, ARITY, MAX_BYTES>>
+        for Graphemes<'a, C, ARITY, MAX_BYTES>
+    {
         #[inline]
-        fn from(rope: &'a Rope) -> Self {
+        fn from(rope: &'a Rope<C, ARITY, MAX_BYTES>) -> Self {
             let len = rope.byte_len();
 
             Self {
@@ -604,9 +969,12 @@ mod graphemes {
         }
     }
 
-    impl<'a> From<&RopeSlice<'a>> for Graphemes<'a> {
+    impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        From<&RopeSlice<'a, C, ARITY, MAX_BYTES>>
+        for Graphemes<'a, C, ARITY, MAX_BYTES>
+    {
         #[inline]
-        fn from(slice: &RopeSlice<'a>) -> Self {
+        fn from(slice: &RopeSlice<'a, C, ARITY, MAX_BYTES>) -> Self {
             let len = slice.byte_len();
 
             Self {
@@ -622,7 +990,9 @@ mod graphemes {
         }
     }
 
-    impl<'a> Iterator for Graphemes<'a> {
+    impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize> Iterator
+        for Graphemes<'a, C, ARITY, MAX_BYTES>
+    {
         type Item = Cow<'a, str>;
 
         #[inline]
@@ -755,7 +1125,9 @@ mod graphemes {
         }
     }
 
-    impl DoubleEndedIterator for Graphemes<'_> {
+    impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        DoubleEndedIterator for Graphemes<'_, C, ARITY, MAX_BYTES>
+    {
         #[inline]
         fn next_back(&mut self) -> Option<Self::Item> {
             debug_assert_eq!(
@@ -906,5 +1278,83 @@ mod graphemes {
         }
     }
 
-    impl core::iter::FusedIterator for Graphemes<'_> {}
+    impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+        core::iter::FusedIterator for Graphemes<'_, C, ARITY, MAX_BYTES>
+    {
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+#[cfg(feature = "parallel")]
+pub use par_chunks::ParChunks;
+
+#[cfg(feature = "parallel")]
+mod par_chunks {
+    use rayon::iter::plumbing::{
+        bridge_unindexed,
+        Folder,
+        UnindexedConsumer,
+        UnindexedProducer,
+    };
+    use rayon::iter::ParallelIterator;
+
+    use super::*;
+    use crate::rope::parallel::split_point;
+
+    /// A parallel iterator over the `&str` chunks of `Rope`s and
+    /// `RopeSlice`s.
+    ///
+    /// This struct is created by the `par_chunks` method on
+    /// [`Rope`](Rope::par_chunks()) and
+    /// [`RopeSlice`](RopeSlice::par_chunks()). See their documentation for
+    /// more.
+    #[derive(Clone)]
+    pub struct ParChunks<'a> {
+        slice: RopeSlice<'a>,
+    }
+
+    impl<'a> From<RopeSlice<'a>> for ParChunks<'a> {
+        #[inline]
+        fn from(slice: RopeSlice<'a>) -> Self {
+            Self { slice }
+        }
+    }
+
+    impl<'a> ParallelIterator for ParChunks<'a> {
+        type Item = &'a str;
+
+        #[inline]
+        fn drive_unindexed<C>(self, consumer: C) -> C::Result
+        where
+            C: UnindexedConsumer<Self::Item>,
+        {
+            bridge_unindexed(self, consumer)
+        }
+    }
+
+    impl<'a> UnindexedProducer for ParChunks<'a> {
+        type Item = &'a str;
+
+        /// Splits the slice at the chunk boundary closest to its middle, so
+        /// that the two halves yield the same chunks as the whole slice.
+        #[inline]
+        fn split(self) -> (Self, Option<Self>) {
+            match split_point(&self.slice) {
+                Some(split_at) => (
+                    Self::from(self.slice.byte_slice(..split_at)),
+                    Some(Self::from(self.slice.byte_slice(split_at..))),
+                ),
+
+                None => (self, None),
+            }
+        }
+
+        #[inline]
+        fn fold_with<F>(self, folder: F) -> F
+        where
+            F: Folder<Self::Item>,
+        {
+            folder.consume_iter(self.slice.chunks())
+        }
+    }
 }
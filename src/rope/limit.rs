@@ -0,0 +1,59 @@
+use super::Rope;
+use crate::tree::RefCount;
+
+/// A cap on the length of a [`Rope`], set with
+/// [`Rope::set_limit()`](crate::Rope::set_limit()).
+///
+/// Once a `Rope` has a limit, every edit making it longer than that deletes
+/// just enough text from its start to bring it back within the limit, like
+/// the scrollback of a terminal or of a log console.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// The `Rope` can be at most this many bytes long. The text deleted from
+    /// its start always ends on a code point boundary, so the `Rope` can be
+    /// up to 3 bytes shorter than the limit.
+    Bytes(usize),
+
+    /// The `Rope` can contain at most this many lines, as counted by
+    /// [`Rope::line_len()`](crate::Rope::line_len()).
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    Lines(usize),
+}
+
+impl Limit {
+    /// Returns the byte length of the text at the start of `rope` that has
+    /// to be deleted to bring it within the limit.
+    #[inline]
+    pub(super) fn excess<
+        C: RefCount,
+        const ARITY: usize,
+        const MAX_BYTES: usize,
+    >(
+        &self,
+        rope: &Rope<C, ARITY, MAX_BYTES>,
+    ) -> usize {
+        match *self {
+            Self::Bytes(max_bytes) => {
+                let Some(mut excess) = rope.byte_len().checked_sub(max_bytes)
+                else {
+                    return 0;
+                };
+
+                while !rope.is_char_boundary(excess) {
+                    excess += 1;
+                }
+
+                excess
+            },
+
+            #[cfg(feature = "line-metric")]
+            Self::Lines(max_lines) => {
+                match rope.line_len().checked_sub(max_lines) {
+                    Some(excess) if excess > 0 => rope.byte_of_line(excess),
+                    _ => 0,
+                }
+            },
+        }
+    }
+}
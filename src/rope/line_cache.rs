@@ -0,0 +1,319 @@
+use super::gap_slice::GapSlice;
+use super::metrics::{
+    convert, count, ByteMetric, ChunkSummary, RawLineMetric,
+};
+#[cfg(feature = "unicode-lines")]
+use super::utils::is_line_break;
+use super::utils::panic_messages as panic;
+use super::{Revision, Rope};
+
+/// The number of lines remembered by a [`LineCache`].
+const CACHED_LINES: usize = 4;
+
+/// A cache for bursts of line conversions around the same position of a
+/// [`Rope`], e.g. the cursor's.
+///
+/// Converting offsets with [`Rope::line_of_byte()`] and
+/// [`Rope::byte_of_line()`] descends the tree from its root every time. A
+/// `LineCache` instead remembers the byte range and the line offset of the
+/// last few lines its conversions landed in, so that the following
+/// conversions within those lines are answered without touching the tree.
+///
+/// The lines are only valid for the [`revision()`](Rope::revision()) of the
+/// `Rope` they were resolved in, and they're forgotten as soon as the cache
+/// is used with a different one. Since revisions are unique across all
+/// `Rope`s, the same cache can be used with any number of them, although
+/// switching between them forgets the cached lines every time.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::{LineCache, Rope};
+/// #
+/// let mut r = Rope::from("foo\nbar\r\nbaz");
+///
+/// let mut cache = LineCache::new();
+///
+/// assert_eq!(cache.line_of_byte(&r, 5), 1);
+///
+/// // This is answered by the line resolved by the previous conversion.
+/// assert_eq!(cache.byte_of_line(&r, 1), 4);
+///
+/// r.insert(0, "\n");
+/// assert_eq!(cache.line_of_byte(&r, 5), 2);
+/// ```
+#[derive(Clone, Default)]
+pub struct LineCache {
+    lines: CachedLines,
+
+    /// The revision of the `Rope` the lines were resolved in, or `None` if
+    /// the cache hasn't been used yet.
+    resolved_in: Option<Revision>,
+}
+
+#[derive(Clone, Default)]
+struct CachedLines {
+    lines: [CachedLine; CACHED_LINES],
+
+    /// The number of valid lines at the start of `lines`.
+    len: usize,
+
+    /// The index of the line to evict when a new one is inserted.
+    next: usize,
+}
+
+/// A byte range of the `Rope` contained in a single line.
+#[derive(Clone, Copy, Default)]
+struct CachedLine {
+    /// The line offset of the line.
+    line: usize,
+
+    /// The byte offset of the start of the range.
+    start: usize,
+
+    /// The byte offset of the end of the range, which is right after the
+    /// line's line break if `ends_line` is `true`.
+    end: usize,
+
+    /// Whether the range starts at the start of the line.
+    starts_line: bool,
+
+    /// Whether the range ends with the line's line break.
+    ends_line: bool,
+}
+
+impl CachedLine {
+    #[inline]
+    fn contains(&self, byte_offset: usize) -> bool {
+        self.start <= byte_offset
+            && (byte_offset < self.end
+                || (byte_offset == self.end && !self.ends_line))
+    }
+
+    /// Returns the line containing the byte at `byte_offset` in `leaf`, where
+    /// `offset` is the summary of everything before the leaf.
+    ///
+    /// The range doesn't extend past the boundaries of the leaf.
+    #[inline]
+    fn in_leaf(
+        leaf: GapSlice<'_>,
+        offset: &ChunkSummary,
+        byte_offset: usize,
+    ) -> Self {
+        let (left, right) = (leaf.left_chunk(), leaf.right_chunk());
+
+        // Line breaks are single code points which are part of the line they
+        // end, so moving the offset back to the start of the code point it's
+        // in doesn't change the line it's on.
+        let mut byte_offset = byte_offset;
+
+        while !leaf.is_char_boundary(byte_offset) {
+            byte_offset -= 1;
+        }
+
+        let (before, after) = if byte_offset <= left.len() {
+            ((&left[..byte_offset], ""), (&left[byte_offset..], right))
+        } else {
+            let mid = byte_offset - left.len();
+            ((left, &right[..mid]), ("", &right[mid..]))
+        };
+
+        let line_breaks =
+            count::line_breaks(before.0) + count::line_breaks(before.1);
+
+        let start = match end_of_last_line_break(before.1) {
+            Some(end) => Some(before.0.len() + end),
+            None => end_of_last_line_break(before.0),
+        };
+
+        let end = match end_of_first_line_break(after.0) {
+            Some(end) => Some(byte_offset + end),
+            None => end_of_first_line_break(after.1)
+                .map(|end| byte_offset + after.0.len() + end),
+        };
+
+        let leaf_start = offset.bytes();
+
+        Self {
+            line: offset.line_breaks() + line_breaks,
+            start: leaf_start + start.unwrap_or(0),
+            end: leaf_start + end.unwrap_or(leaf.len()),
+            starts_line: start.is_some() || leaf_start == 0,
+            ends_line: end.is_some(),
+        }
+    }
+}
+
+impl CachedLines {
+    #[inline]
+    fn insert(&mut self, line: CachedLine) {
+        self.lines[self.next] = line;
+        self.len = (self.len + 1).min(CACHED_LINES);
+        self.next = (self.next + 1) % CACHED_LINES;
+    }
+
+    #[inline]
+    fn iter(&self) -> impl Iterator<Item = &CachedLine> {
+        self.lines[..self.len].iter()
+    }
+}
+
+impl LineCache {
+    /// Returns the byte offset of the start of the given line of the `Rope`,
+    /// like [`Rope::byte_of_line()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line offset is out of bounds (i.e. greater than
+    /// [`line_len()`](Rope::line_len())).
+    #[track_caller]
+    #[inline]
+    pub fn byte_of_line(&mut self, rope: &Rope, line_offset: usize) -> usize {
+        if line_offset > rope.line_len() {
+            panic::line_offset_out_of_bounds(line_offset, rope.line_len());
+        }
+
+        if line_offset > rope.tree.summary().line_breaks() {
+            return rope.byte_len();
+        }
+
+        if line_offset == 0 {
+            return 0;
+        }
+
+        let lines = self.lines_of(rope);
+
+        let cached = lines.iter().find_map(|cached| {
+            if cached.line == line_offset && cached.starts_line {
+                Some(cached.start)
+            } else if cached.line + 1 == line_offset && cached.ends_line {
+                Some(cached.end)
+            } else {
+                None
+            }
+        });
+
+        if let Some(byte_offset) = cached {
+            return byte_offset;
+        }
+
+        let (leaf, offset) =
+            rope.tree.leaf_and_offset_at_measure(RawLineMetric(line_offset));
+
+        // The leaf contains the line break ending the previous line.
+        let line_in_leaf = line_offset - offset.line_breaks();
+
+        let (left, right) = (leaf.left_chunk(), leaf.right_chunk());
+
+        let left_breaks = count::line_breaks(left);
+
+        let byte_in_leaf = if line_in_leaf <= left_breaks {
+            convert::byte_of_line(left, line_in_leaf)
+        } else {
+            left.len()
+                + convert::byte_of_line(right, line_in_leaf - left_breaks)
+        };
+
+        let cached = CachedLine::in_leaf(leaf, &offset, byte_in_leaf);
+
+        debug_assert_eq!(cached.line, line_offset);
+        debug_assert!(cached.starts_line);
+
+        lines.insert(cached);
+
+        cached.start
+    }
+
+    /// Returns the line offset of the line containing the given byte offset
+    /// of the `Rope`, like [`Rope::line_of_byte()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Rope::byte_len())).
+    #[track_caller]
+    #[inline]
+    pub fn line_of_byte(&mut self, rope: &Rope, byte_offset: usize) -> usize {
+        if byte_offset > rope.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, rope.byte_len());
+        }
+
+        let lines = self.lines_of(rope);
+
+        if let Some(cached) =
+            lines.iter().find(|cached| cached.contains(byte_offset))
+        {
+            return cached.line;
+        }
+
+        let (leaf, offset) =
+            rope.tree.leaf_and_offset_at_measure(ByteMetric(byte_offset));
+
+        let cached =
+            CachedLine::in_leaf(leaf, &offset, byte_offset - offset.bytes());
+
+        lines.insert(cached);
+
+        cached.line
+    }
+
+    /// Returns the lines resolved in the current revision of the `Rope`,
+    /// forgetting the cached ones if they were resolved in another one.
+    #[inline]
+    fn lines_of(&mut self, rope: &Rope) -> &mut CachedLines {
+        let current = Some(rope.revision());
+
+        if self.resolved_in != current {
+            self.lines = CachedLines::default();
+            self.resolved_in = current;
+        }
+
+        &mut self.lines
+    }
+
+    /// Creates a new, empty `LineCache`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl core::fmt::Debug for LineCache {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("LineCache").finish_non_exhaustive()
+    }
+}
+
+/// Returns the byte offset right after the first line break in `s`.
+#[inline]
+fn end_of_first_line_break(s: &str) -> Option<usize> {
+    #[cfg(feature = "unicode-lines")]
+    {
+        s.char_indices()
+            .find(|&(_, ch)| is_line_break(ch))
+            .map(|(idx, ch)| idx + ch.len_utf8())
+    }
+
+    #[cfg(not(feature = "unicode-lines"))]
+    {
+        s.find('\n').map(|idx| idx + 1)
+    }
+}
+
+/// Returns the byte offset right after the last line break in `s`.
+#[inline]
+fn end_of_last_line_break(s: &str) -> Option<usize> {
+    #[cfg(feature = "unicode-lines")]
+    {
+        s.char_indices()
+            .rev()
+            .find(|&(_, ch)| is_line_break(ch))
+            .map(|(idx, ch)| idx + ch.len_utf8())
+    }
+
+    #[cfg(not(feature = "unicode-lines"))]
+    {
+        s.rfind('\n').map(|idx| idx + 1)
+    }
+}
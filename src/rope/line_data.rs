@@ -0,0 +1,639 @@
+use alloc::vec::Vec;
+use core::ops::{Add, AddAssign, Range, RangeBounds, Sub, SubAssign};
+
+use super::utils::panic_messages as panic;
+use super::Rope;
+use crate::range_bounds_to_start_end;
+use crate::tree::{
+    AsSlice,
+    BalancedLeaf,
+    BaseMeasured,
+    Metric,
+    ReplaceableLeaf,
+    Summarize,
+    Tree,
+};
+
+#[cfg(any(test, feature = "small_chunks"))]
+const CHUNK_MAX_LINES: usize = 4;
+
+#[cfg(not(any(test, feature = "small_chunks")))]
+const CHUNK_MAX_LINES: usize = 64;
+
+const CHUNK_MIN_LINES: usize = CHUNK_MAX_LINES / 2;
+
+/// A [`Rope`] storing some optional metadata for each of its lines, like
+/// fold states, diagnostic counts or breakpoints.
+///
+/// The metadata is stored in a [`LineData`] which is spliced by every edit
+/// performed on the `LineDataRope`, so that it stays attached to the same
+/// lines:
+///
+/// - the lines removed by an edit lose their metadata, except for the one
+///   the edit starts on;
+///
+/// - the lines added by an edit don't have any metadata;
+///
+/// - inserting text containing line breaks at the start of a line moves the
+///   line's metadata down together with it.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::line_data::LineDataRope;
+/// # use crop::Rope;
+/// #
+/// let mut r = LineDataRope::from(Rope::from("fn main() {\n    foo();\n}\n"));
+///
+/// r.set_line_data(1, "breakpoint");
+///
+/// r.insert(0, "use foo::foo;\n\n");
+/// assert_eq!(r.line_data().get(3), Some(&"breakpoint"));
+///
+/// r.delete(r.byte_of_line(2)..r.byte_of_line(4));
+/// assert_eq!(r.line_data().get(2), None);
+/// assert!(r.line_data().is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct LineDataRope<T: Clone + 'static> {
+    rope: Rope,
+    data: LineData<T>,
+}
+
+impl<T: Clone + 'static> LineDataRope<T> {
+    /// Deletes the text within the specified byte range, splicing the line
+    /// metadata.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::delete()`].
+    #[track_caller]
+    #[inline]
+    pub fn delete<R>(&mut self, byte_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.replace(byte_range, "");
+    }
+
+    /// Inserts `text` at the given byte offset, splicing the line metadata.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::insert()`].
+    #[track_caller]
+    #[inline]
+    pub fn insert<S>(&mut self, byte_offset: usize, text: S)
+    where
+        S: AsRef<str>,
+    {
+        self.replace(byte_offset..byte_offset, text);
+    }
+
+    /// Consumes the `LineDataRope`, returning its `Rope` and its line
+    /// metadata.
+    #[inline]
+    pub fn into_parts(self) -> (Rope, LineData<T>) {
+        (self.rope, self.data)
+    }
+
+    /// Consumes the `LineDataRope`, returning its `Rope`.
+    #[inline]
+    pub fn into_rope(self) -> Rope {
+        self.rope
+    }
+
+    /// Returns the line metadata.
+    #[inline]
+    pub fn line_data(&self) -> &LineData<T> {
+        &self.data
+    }
+
+    /// Replaces the text within the specified byte range with `text`,
+    /// splicing the line metadata.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::replace()`].
+    #[track_caller]
+    #[inline]
+    pub fn replace<R, S>(&mut self, byte_range: R, text: S)
+    where
+        R: RangeBounds<usize>,
+        S: AsRef<str>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.rope.byte_len());
+
+        let text = text.as_ref();
+
+        let start_line = self.rope.line_of_byte(start);
+        let end_line = self.rope.line_of_byte(end);
+
+        let is_at_line_start =
+            start == end && start == self.rope.byte_of_line(start_line);
+
+        self.rope.replace(start..end, text);
+
+        let inserted_lines =
+            self.rope.line_of_byte(start + text.len()) - start_line;
+
+        if is_at_line_start {
+            self.data.splice(start_line..start_line, inserted_lines);
+        } else {
+            self.data.splice(start_line + 1..end_line + 1, inserted_lines);
+        }
+    }
+
+    /// Returns the `Rope`.
+    #[inline]
+    pub fn rope(&self) -> &Rope {
+        &self.rope
+    }
+
+    /// Sets the metadata of the line at the given index, returning its
+    /// previous metadata.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line index is out of bounds (i.e. greater than or equal
+    /// to [`line_len()`](Rope::line_len())).
+    #[track_caller]
+    #[inline]
+    pub fn set_line_data(&mut self, line_index: usize, value: T) -> Option<T> {
+        // Getting the line takes care of checking the index.
+        let _ = self.rope.line(line_index);
+        self.data.set(line_index, Some(value))
+    }
+
+    /// Removes the metadata of the line at the given index, returning it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line index is out of bounds (i.e. greater than or equal
+    /// to [`line_len()`](Rope::line_len())).
+    #[track_caller]
+    #[inline]
+    pub fn take_line_data(&mut self, line_index: usize) -> Option<T> {
+        let _ = self.rope.line(line_index);
+        self.data.set(line_index, None)
+    }
+}
+
+impl<T: Clone + 'static> core::ops::Deref for LineDataRope<T> {
+    type Target = Rope;
+
+    #[inline]
+    fn deref(&self) -> &Rope {
+        &self.rope
+    }
+}
+
+impl<T: Clone + 'static> Default for LineDataRope<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::from(Rope::new())
+    }
+}
+
+impl<T: Clone + 'static> From<Rope> for LineDataRope<T> {
+    #[track_caller]
+    #[inline]
+    fn from(rope: Rope) -> Self {
+        if rope.limit().is_some() {
+            panic::rope_has_limit("LineDataRope");
+        }
+
+        let lines = rope.line_of_byte(rope.byte_len()) + 1;
+        Self { data: LineData::new(lines), rope }
+    }
+}
+
+/// The optional metadata of every line of a [`LineDataRope`], built on the
+/// same B-tree used by [`Rope`].
+///
+/// There's a slot for every line of the `Rope`, plus one for the empty line
+/// after a trailing line break. Looking up the metadata of a line and
+/// splicing the slots both take `O(log n)` time, where `n` is the number of
+/// lines, and iterating over the lines with some metadata skips the
+/// subtrees without any.
+#[derive(Clone)]
+pub struct LineData<T> {
+    tree: Tree<{ Rope::arity() }, LineChunk<T>>,
+}
+
+impl<T: Clone + 'static> LineData<T> {
+    #[doc(hidden)]
+    pub fn assert_invariants(&self) {
+        self.tree.assert_invariants();
+    }
+
+    /// Returns the metadata of the line at the given index, if it has any.
+    #[inline]
+    pub fn get(&self, line_index: usize) -> Option<&T> {
+        self.slots_where(|offset, summary| {
+            summary.set > 0
+                && offset.lines <= line_index
+                && line_index < offset.lines + summary.lines
+        })
+        .find(|&(idx, _)| idx == line_index)
+        .and_then(|(_, slot)| slot.as_ref())
+    }
+
+    /// Returns `true` if no line has any metadata.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the indices and the metadata of the lines
+    /// that have some, sorted by their index.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> + '_ {
+        self.slots_where(|_, summary| summary.set > 0)
+            .filter_map(|(idx, slot)| slot.as_ref().map(|value| (idx, value)))
+    }
+
+    /// Returns the number of lines that have some metadata.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.summary().set
+    }
+
+    /// Creates a new `LineData` with the given number of empty slots.
+    #[inline]
+    fn new(lines: usize) -> Self {
+        let mut slots = alloc::vec![None; lines];
+
+        let mut leaves = Vec::new();
+
+        while slots.len() > CHUNK_MAX_LINES {
+            let rest = slots.split_off(CHUNK_MAX_LINES);
+            leaves.push(LineChunk { slots });
+            slots = rest;
+        }
+
+        // Make sure the last leaf is at least half full.
+        if let Some(previous) = leaves.last_mut() {
+            if slots.len() < CHUNK_MIN_LINES {
+                let moved = CHUNK_MIN_LINES - slots.len();
+                let split = previous.slots.len() - moved;
+                slots.splice(..0, previous.slots.drain(split..));
+            }
+        }
+
+        leaves.push(LineChunk { slots });
+
+        Self { tree: Tree::from_leaves(leaves) }
+    }
+
+    /// Sets the slot of the line at the given index, returning its previous
+    /// value.
+    #[inline]
+    fn set(&mut self, line_index: usize, value: Option<T>) -> Option<T> {
+        let previous = self.get(line_index).cloned();
+
+        if previous.is_some() || value.is_some() {
+            self.tree.replace(
+                LineCount(line_index)..LineCount(line_index + 1),
+                alloc::vec![value],
+            );
+        }
+
+        previous
+    }
+
+    /// Returns an iterator over the `(index, slot)` pairs of the lines
+    /// contained in the subtrees accepted by `keep`, which is called with
+    /// the summary of everything before a subtree and with the summary of
+    /// the subtree itself.
+    #[inline]
+    fn slots_where<F>(
+        &self,
+        keep: F,
+    ) -> impl Iterator<Item = (usize, &Option<T>)> + '_
+    where
+        F: FnMut(&LineSummary, &LineSummary) -> bool,
+    {
+        self.tree.filter_leaves(keep).into_iter().flat_map(
+            |(offset, slice)| {
+                slice
+                    .slots
+                    .iter()
+                    .enumerate()
+                    .map(move |(idx, slot)| (offset.lines + idx, slot))
+            },
+        )
+    }
+
+    /// Replaces the slots of the lines in the given range with the given
+    /// number of empty slots.
+    #[inline]
+    fn splice(&mut self, lines: Range<usize>, inserted: usize) {
+        if lines.is_empty() && inserted == 0 {
+            return;
+        }
+
+        self.tree.replace(
+            LineCount(lines.start)..LineCount(lines.end),
+            alloc::vec![None; inserted],
+        );
+    }
+}
+
+impl<T: core::fmt::Debug + Clone + 'static> core::fmt::Debug for LineData<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// The leaves of a [`LineData`], storing the slots of consecutive lines.
+#[derive(Clone)]
+struct LineChunk<T> {
+    slots: Vec<Option<T>>,
+}
+
+impl<T> Default for LineChunk<T> {
+    #[inline]
+    fn default() -> Self {
+        Self { slots: Vec::new() }
+    }
+}
+
+impl<T> core::fmt::Debug for LineChunk<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<T> Summarize for LineChunk<T> {
+    type Summary = LineSummary;
+
+    #[inline]
+    fn summarize(&self) -> LineSummary {
+        self.as_slice().summarize()
+    }
+}
+
+impl<T> BaseMeasured for LineChunk<T> {
+    type BaseMetric = LineCount;
+}
+
+impl<T> AsSlice for LineChunk<T> {
+    type Slice<'a>
+        = LineSlice<'a, T>
+    where
+        T: 'a;
+
+    #[inline]
+    fn as_slice(&self) -> LineSlice<'_, T> {
+        LineSlice { slots: &self.slots }
+    }
+}
+
+impl<T: Clone + 'static> BalancedLeaf for LineChunk<T> {
+    #[inline]
+    fn is_underfilled(&self, summary: &LineSummary) -> bool {
+        summary.lines < CHUNK_MIN_LINES
+    }
+
+    #[inline]
+    fn balance_leaves(
+        (left, left_summary): (&mut Self, &mut LineSummary),
+        (right, right_summary): (&mut Self, &mut LineSummary),
+    ) {
+        let total = left.slots.len() + right.slots.len();
+
+        if total <= CHUNK_MAX_LINES {
+            left.slots.append(&mut right.slots);
+        } else if left.slots.len() < right.slots.len() {
+            let moved = right.slots.len() - total / 2;
+            left.slots.extend(right.slots.drain(..moved));
+        } else {
+            let moved = left.slots.len() - total / 2;
+            let split = left.slots.len() - moved;
+            right.slots.splice(..0, left.slots.drain(split..));
+        }
+
+        *left_summary = left.summarize();
+        *right_summary = right.summarize();
+    }
+}
+
+impl<T: Clone + 'static> From<LineSlice<'_, T>> for LineChunk<T> {
+    #[inline]
+    fn from(slice: LineSlice<'_, T>) -> Self {
+        Self { slots: slice.slots.to_vec() }
+    }
+}
+
+impl<T: Clone + 'static> ReplaceableLeaf<LineCount> for LineChunk<T> {
+    type Replacement<'a> = Vec<Option<T>>;
+
+    type ExtraLeaves = alloc::vec::IntoIter<Self>;
+
+    #[inline]
+    fn replace<R>(
+        &mut self,
+        summary: &mut LineSummary,
+        range: R,
+        replacement: Vec<Option<T>>,
+    ) -> Option<Self::ExtraLeaves>
+    where
+        R: RangeBounds<LineCount>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(range, 0, self.slots.len());
+
+        self.slots.splice(start..end, replacement);
+
+        let total = self.slots.len();
+
+        if total <= CHUNK_MAX_LINES {
+            *summary = self.summarize();
+            return None;
+        }
+
+        // Split the slots in chunks of roughly the same size, all of which
+        // are guaranteed to be at least half full.
+        let chunks = (total + CHUNK_MAX_LINES - 1) / CHUNK_MAX_LINES;
+
+        let mut extras = Vec::with_capacity(chunks - 1);
+
+        for idx in (1..chunks).rev() {
+            let split = idx * total / chunks;
+            let slots = self.slots.split_off(split);
+            extras.push(Self { slots });
+        }
+
+        extras.reverse();
+
+        *summary = self.summarize();
+
+        Some(extras.into_iter())
+    }
+
+    #[inline]
+    fn remove_up_to(&mut self, summary: &mut LineSummary, up_to: LineCount) {
+        self.slots.drain(..up_to.0);
+        *summary = self.summarize();
+    }
+}
+
+struct LineSlice<'a, T> {
+    slots: &'a [Option<T>],
+}
+
+impl<T> Clone for LineSlice<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for LineSlice<'_, T> {}
+
+impl<T> Default for LineSlice<'_, T> {
+    #[inline]
+    fn default() -> Self {
+        Self { slots: &[] }
+    }
+}
+
+impl<T> core::fmt::Debug for LineSlice<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list()
+            .entries(self.slots.iter().map(|slot| slot.is_some()))
+            .finish()
+    }
+}
+
+impl<T> Summarize for LineSlice<'_, T> {
+    type Summary = LineSummary;
+
+    #[inline]
+    fn summarize(&self) -> LineSummary {
+        LineSummary {
+            lines: self.slots.len(),
+            set: self.slots.iter().filter(|slot| slot.is_some()).count(),
+        }
+    }
+}
+
+/// The summary of a sequence of line slots.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct LineSummary {
+    /// The number of lines.
+    lines: usize,
+
+    /// The number of lines with some metadata.
+    set: usize,
+}
+
+impl Add<&Self> for LineSummary {
+    type Output = Self;
+
+    #[inline]
+    fn add(mut self, rhs: &Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+impl Sub<&Self> for LineSummary {
+    type Output = Self;
+
+    #[inline]
+    fn sub(mut self, rhs: &Self) -> Self {
+        self -= rhs;
+        self
+    }
+}
+
+impl AddAssign<&Self> for LineSummary {
+    #[inline]
+    fn add_assign(&mut self, rhs: &Self) {
+        self.lines += rhs.lines;
+        self.set += rhs.set;
+    }
+}
+
+impl SubAssign<&Self> for LineSummary {
+    #[inline]
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.lines -= rhs.lines;
+        self.set -= rhs.set;
+    }
+}
+
+/// A metric counting the number of line slots.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct LineCount(usize);
+
+impl Add for LineCount {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl Sub for LineCount {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl AddAssign for LineCount {
+    #[inline]
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0
+    }
+}
+
+impl SubAssign for LineCount {
+    #[inline]
+    fn sub_assign(&mut self, other: Self) {
+        self.0 -= other.0
+    }
+}
+
+impl Add<usize> for LineCount {
+    type Output = usize;
+
+    #[inline]
+    fn add(self, other: usize) -> usize {
+        self.0 + other
+    }
+}
+
+impl From<LineCount> for usize {
+    #[inline]
+    fn from(LineCount(count): LineCount) -> usize {
+        count
+    }
+}
+
+impl Metric<LineSummary> for LineCount {
+    #[inline]
+    fn zero() -> Self {
+        Self(0)
+    }
+
+    #[inline]
+    fn one() -> Self {
+        Self(1)
+    }
+
+    #[inline]
+    fn measure(summary: &LineSummary) -> Self {
+        Self(summary.lines)
+    }
+}
@@ -0,0 +1,328 @@
+use core::ops::Range;
+
+use alloc::string::String;
+
+use super::gap_buffer::GapBuffer;
+use super::EditInfo;
+use crate::tree::{RefCount, Tree, TreeBuilder};
+
+type NodeRef<'a, const ARITY: usize, const MAX_BYTES: usize, C> =
+    crate::tree::NodeRef<'a, ARITY, GapBuffer<MAX_BYTES>, C>;
+
+/// The sequence of characters terminating the lines of a text.
+///
+/// Note that only line feeds are considered line breaks by the rest of the
+/// crate, so e.g. a text using [`Cr`](Self::Cr) line endings is made of a
+/// single line as far as [`Rope::line_len()`](crate::Rope::line_len()) is
+/// concerned.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// A carriage return (`\r`), as used by classic Mac OS.
+    Cr,
+
+    /// A carriage return followed by a line feed (`\r\n`), as used by
+    /// Windows.
+    CrLf,
+
+    /// A line feed (`\n`), as used by Unix-like systems.
+    #[default]
+    Lf,
+}
+
+impl LineEnding {
+    /// Returns the line ending as a string slice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::LineEnding;
+    /// #
+    /// assert_eq!(LineEnding::CrLf.as_str(), "\r\n");
+    /// ```
+    #[inline]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cr => "\r",
+            Self::CrLf => "\r\n",
+            Self::Lf => "\n",
+        }
+    }
+}
+
+/// How a line break at the very end of a text affects its lines.
+///
+/// This is used by the `line_len_with()` and `lines_with()` methods of
+/// [`Rope`](crate::Rope::line_len_with()) and
+/// [`RopeSlice`](crate::RopeSlice::line_len_with()), while the other methods
+/// always use the default, [`EndsLine`](Self::EndsLine).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TrailingLineBreak {
+    /// The final line break terminates the last line, so `"a\n"` is made of
+    /// a single line and an empty text has no lines at all.
+    #[default]
+    EndsLine,
+
+    /// The final line break starts a new empty line, so `"a\n"` is made of
+    /// two lines and an empty text is made of one empty line, like in ropey
+    /// and in most text editors.
+    StartsLine,
+}
+
+/// The number of line endings of each kind found in a text.
+#[derive(Copy, Clone, Debug, Default)]
+pub(super) struct LineEndingCounts {
+    cr: usize,
+    crlf: usize,
+    lf: usize,
+}
+
+impl LineEndingCounts {
+    #[inline]
+    pub(super) fn add(&mut self, line_ending: LineEnding) {
+        match line_ending {
+            LineEnding::Cr => self.cr += 1,
+            LineEnding::CrLf => self.crlf += 1,
+            LineEnding::Lf => self.lf += 1,
+        }
+    }
+
+    /// Returns the most common line ending, preferring line feeds and then
+    /// CRLF sequences in case of ties.
+    #[inline]
+    pub(super) fn most_common(&self) -> LineEnding {
+        if self.lf >= self.crlf && self.lf >= self.cr {
+            LineEnding::Lf
+        } else if self.crlf >= self.cr {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Cr
+        }
+    }
+
+    #[inline]
+    pub(super) fn remove(&mut self, line_ending: LineEnding) {
+        match line_ending {
+            LineEnding::Cr => self.cr -= 1,
+            LineEnding::CrLf => self.crlf -= 1,
+            LineEnding::Lf => self.lf -= 1,
+        }
+    }
+}
+
+impl core::fmt::Display for LineEnding {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returns a copy of `tree` where the text in the byte range of every edit is
+/// replaced with `eol`, rebuilding only the leaves intersecting an edit and
+/// sharing all the other ones with `tree`.
+///
+/// The edits must be sorted from last to first and not overlap. An edit can
+/// span two leaves, e.g. a CRLF sequence split between them.
+#[inline]
+pub(super) fn replace_line_endings<
+    const ARITY: usize,
+    const MAX_BYTES: usize,
+    C: RefCount,
+>(
+    tree: &Tree<ARITY, GapBuffer<MAX_BYTES>, C>,
+    edits: &[EditInfo],
+    eol: &str,
+) -> Tree<ARITY, GapBuffer<MAX_BYTES>, C> {
+    let mut rewriter = EolRewriter {
+        builder: TreeBuilder::new(),
+        edits,
+        eol,
+        offset: 0,
+        pending: String::new(),
+        last: None,
+    };
+
+    rewriter.visit(tree.root_ref());
+
+    debug_assert!(rewriter.edits.is_empty());
+
+    rewriter.finish()
+}
+
+/// A leaf of the `Tree` built by an [`EolRewriter`].
+enum NewLeaf<'a, const ARITY: usize, const MAX_BYTES: usize, C: RefCount> {
+    /// A leaf of the old `Tree` that didn't change.
+    Shared(NodeRef<'a, ARITY, MAX_BYTES, C>),
+
+    /// A leaf made of rewritten text.
+    Rewritten(GapBuffer<MAX_BYTES>),
+}
+
+impl<const ARITY: usize, const MAX_BYTES: usize, C: RefCount>
+    NewLeaf<'_, ARITY, MAX_BYTES, C>
+{
+    #[inline]
+    fn gap_buffer(&self) -> &GapBuffer<MAX_BYTES> {
+        match self {
+            Self::Shared(node) => node.leaf().expect("always a leaf"),
+            Self::Rewritten(leaf) => leaf,
+        }
+    }
+}
+
+/// Builds a new `Tree` by visiting the leaves of an old one in order.
+struct EolRewriter<'a, const ARITY: usize, const MAX_BYTES: usize, C: RefCount>
+{
+    builder: TreeBuilder<ARITY, GapBuffer<MAX_BYTES>, C>,
+
+    /// The edits that haven't been fully carried out yet, from last to
+    /// first.
+    edits: &'a [EditInfo],
+
+    eol: &'a str,
+
+    /// The byte offset of the next leaf in the old `Tree`.
+    offset: usize,
+
+    /// The rewritten text that hasn't been turned into leaves yet because
+    /// it's too short to fill one.
+    pending: String,
+
+    /// The last leaf, which is only appended to the builder once the next
+    /// one is known so that a short `pending` text left at the end can be
+    /// merged into it.
+    last: Option<NewLeaf<'a, ARITY, MAX_BYTES, C>>,
+}
+
+impl<'a, const ARITY: usize, const MAX_BYTES: usize, C: RefCount>
+    EolRewriter<'a, ARITY, MAX_BYTES, C>
+{
+    #[inline]
+    fn visit(&mut self, node: NodeRef<'a, ARITY, MAX_BYTES, C>) {
+        match node.leaf() {
+            Some(leaf) => self.rewrite_leaf(node, leaf),
+            None => node.children().for_each(|child| self.visit(child)),
+        }
+    }
+
+    #[inline]
+    fn rewrite_leaf(
+        &mut self,
+        node: NodeRef<'a, ARITY, MAX_BYTES, C>,
+        leaf: &GapBuffer<MAX_BYTES>,
+    ) {
+        let start = self.offset;
+        let end = start + leaf.len();
+        self.offset = end;
+
+        let is_edited =
+            self.edits.last().map_or(false, |edit| edit.byte_range.start < end);
+
+        if !is_edited && self.pending.is_empty() {
+            self.push(NewLeaf::Shared(node));
+            return;
+        }
+
+        let (left, right) = (leaf.left_chunk(), leaf.right_chunk());
+
+        let mut copied = start;
+
+        while let Some((edit, rest)) = self.edits.split_last() {
+            let range = &edit.byte_range;
+
+            if range.start >= end {
+                break;
+            }
+
+            // The edit can start in the previous leaf..
+            let edit_start = range.start.max(start);
+
+            push_range(
+                &mut self.pending,
+                left,
+                right,
+                copied - start..edit_start - start,
+            );
+
+            if range.start >= start {
+                self.pending.push_str(self.eol);
+            }
+
+            copied = range.end.min(end);
+
+            // ..or end in the next one.
+            if range.end > end {
+                break;
+            }
+
+            self.edits = rest;
+        }
+
+        push_range(&mut self.pending, left, right, copied - start..end - start);
+
+        if self.pending.len() >= GapBuffer::<MAX_BYTES>::min_bytes() {
+            self.flush();
+        }
+    }
+
+    /// Turns all the pending text into leaves.
+    #[inline]
+    fn flush(&mut self) {
+        let pending = core::mem::take(&mut self.pending);
+
+        for segment in GapBuffer::<MAX_BYTES>::segmenter(&pending) {
+            self.push(NewLeaf::Rewritten(GapBuffer::from(segment)));
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, leaf: NewLeaf<'a, ARITY, MAX_BYTES, C>) {
+        match self.last.replace(leaf) {
+            Some(NewLeaf::Shared(node)) => self.builder.append_shared(node),
+            Some(NewLeaf::Rewritten(leaf)) => self.builder.append(leaf),
+            None => {},
+        }
+    }
+
+    #[inline]
+    fn finish(mut self) -> Tree<ARITY, GapBuffer<MAX_BYTES>, C> {
+        let is_underfilled = !self.pending.is_empty()
+            && self.pending.len() < GapBuffer::<MAX_BYTES>::min_bytes();
+
+        if is_underfilled {
+            if let Some(last) = self.last.take() {
+                let last = last.gap_buffer();
+                let mut text =
+                    String::with_capacity(last.len() + self.pending.len());
+                text.push_str(last.left_chunk());
+                text.push_str(last.right_chunk());
+                text.push_str(&self.pending);
+                self.pending = text;
+            }
+        }
+
+        self.flush();
+
+        match self.last.take() {
+            Some(NewLeaf::Shared(node)) => self.builder.append_shared(node),
+            Some(NewLeaf::Rewritten(leaf)) => self.builder.append(leaf),
+            None => {},
+        }
+
+        self.builder.build()
+    }
+}
+
+/// Pushes the text in the given byte range of a leaf whose text is split in
+/// `left` and `right` by its gap.
+#[inline]
+fn push_range(s: &mut String, left: &str, right: &str, range: Range<usize>) {
+    let split = left.len();
+
+    if range.start < split {
+        s.push_str(&left[range.start..range.end.min(split)]);
+    }
+
+    if range.end > split {
+        s.push_str(&right[range.start.max(split) - split..range.end - split]);
+    }
+}
@@ -0,0 +1,224 @@
+use alloc::vec::Vec;
+
+use super::EditInfo;
+
+/// Which way a mark moves when text is inserted exactly at its position.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Gravity {
+    /// The mark sticks to the text on its left, so it stays before any text
+    /// inserted at its position.
+    #[default]
+    Left,
+
+    /// The mark sticks to the text on its right, so it moves after any text
+    /// inserted at its position.
+    Right,
+}
+
+/// A position in a [`Rope`](crate::Rope) following the text appended to it,
+/// created with [`Rope::follow_tail()`](crate::Rope::follow_tail()).
+///
+/// A `Tail` is backed by a mark with left gravity, so it stays in front of
+/// the text appended after it until the next call to
+/// [`Rope::poll_tail()`](crate::Rope::poll_tail()) moves it to the end of
+/// the `Rope`. Like any other mark, it's moved back if the text before it is
+/// deleted, e.g. when a log file is truncated, and it can be removed with
+/// [`Rope::remove_mark()`](crate::Rope::remove_mark()).
+///
+/// A `Tail` also holds the bytes of a character that was cut in half by the
+/// end of the last read of
+/// [`Rope::extend_from_reader()`](crate::Rope::extend_from_reader()), until
+/// the next read completes it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tail {
+    mark: MarkId,
+    incomplete: [u8; 3],
+    incomplete_len: u8,
+}
+
+impl Tail {
+    /// Returns the bytes of the incomplete character read last.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(super) fn incomplete(&self) -> &[u8] {
+        &self.incomplete[..self.incomplete_len as usize]
+    }
+
+    /// Returns the id of the mark backing the tail, which can be used to get
+    /// its byte offset from [`Rope::marks()`](crate::Rope::marks()).
+    #[inline]
+    pub fn mark(&self) -> MarkId {
+        self.mark
+    }
+
+    #[inline]
+    pub(super) fn new(mark: MarkId) -> Self {
+        Self { mark, incomplete: [0; 3], incomplete_len: 0 }
+    }
+
+    /// Sets the bytes of the incomplete character read last.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(super) fn set_incomplete(&mut self, bytes: &[u8]) {
+        self.incomplete[..bytes.len()].copy_from_slice(bytes);
+        self.incomplete_len = bytes.len() as u8;
+    }
+}
+
+/// The identifier of a mark created with
+/// [`Rope::add_mark()`](crate::Rope::add_mark()).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MarkId(u64);
+
+#[derive(Copy, Clone, Debug)]
+struct Mark {
+    id: MarkId,
+    byte_offset: usize,
+    gravity: Gravity,
+}
+
+impl Mark {
+    /// Moves the mark to its position after the given edit.
+    ///
+    /// Marks within the replaced range are moved to its start if they have
+    /// left gravity, and past the inserted text if they have right gravity.
+    #[inline]
+    fn edit(&mut self, edit: &EditInfo) {
+        let EditInfo { byte_range, inserted_len } = edit;
+
+        if self.byte_offset > byte_range.end {
+            self.byte_offset =
+                self.byte_offset - byte_range.len() + inserted_len;
+        } else if self.byte_offset >= byte_range.start {
+            self.byte_offset = match self.gravity {
+                Gravity::Left => byte_range.start,
+                Gravity::Right => byte_range.start + inserted_len,
+            };
+        }
+    }
+}
+
+/// The set of marks of a [`Rope`](crate::Rope), i.e. byte offsets which are
+/// automatically moved by the edits performed on it to keep pointing at the
+/// same position in the text.
+///
+/// Marks are created with [`Rope::add_mark()`](crate::Rope::add_mark()) and
+/// removed with [`Rope::remove_mark()`](crate::Rope::remove_mark()), and the
+/// set can be accessed with [`Rope::marks()`](crate::Rope::marks()).
+///
+/// Looking up a mark takes `O(log n)` time, while every edit of the `Rope`
+/// takes `O(n)` time to update the marks, where `n` is the number of marks.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::Rope;
+/// # use crop::marks::Gravity;
+/// #
+/// let mut r = Rope::from("Hello World");
+///
+/// let left = r.add_mark(5, Gravity::Left);
+/// let right = r.add_mark(5, Gravity::Right);
+///
+/// r.insert(5, ",");
+/// r.insert(0, ">> ");
+///
+/// assert_eq!(r.marks().get(left), Some(8));
+/// assert_eq!(r.marks().get(right), Some(9));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MarkSet {
+    /// The marks, sorted by id.
+    marks: Vec<Mark>,
+    next_id: u64,
+}
+
+/// The mark set returned by [`Rope::marks()`](crate::Rope::marks()) when no
+/// mark has ever been added.
+pub(super) static EMPTY: MarkSet = MarkSet::new();
+
+impl MarkSet {
+    /// Moves all the marks to their positions after the given edit.
+    #[inline]
+    pub(super) fn edit(&mut self, edit: &EditInfo) {
+        for mark in &mut self.marks {
+            mark.edit(edit);
+        }
+    }
+
+    /// Returns the byte offset of the mark with the given id, or `None` if
+    /// it has been removed.
+    #[inline]
+    pub fn get(&self, id: MarkId) -> Option<usize> {
+        self.position(id).map(|idx| self.marks[idx].byte_offset)
+    }
+
+    /// Returns the gravity of the mark with the given id, or `None` if it
+    /// has been removed.
+    #[inline]
+    pub fn gravity(&self, id: MarkId) -> Option<Gravity> {
+        self.position(id).map(|idx| self.marks[idx].gravity)
+    }
+
+    /// Adds a new mark, returning its id.
+    #[inline]
+    pub(super) fn insert(
+        &mut self,
+        byte_offset: usize,
+        gravity: Gravity,
+    ) -> MarkId {
+        let id = MarkId(self.next_id);
+        self.next_id += 1;
+        self.marks.push(Mark { id, byte_offset, gravity });
+        id
+    }
+
+    /// Returns `true` if the set doesn't contain any marks.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    /// Returns an iterator over the ids and byte offsets of the marks, in the
+    /// order in which they were added.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (MarkId, usize)> + '_ {
+        self.marks.iter().map(|mark| (mark.id, mark.byte_offset))
+    }
+
+    /// Returns the number of marks in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.marks.len()
+    }
+
+    #[inline]
+    const fn new() -> Self {
+        Self { marks: Vec::new(), next_id: 0 }
+    }
+
+    /// Returns the index of the mark with the given id in `self.marks`.
+    #[inline]
+    fn position(&self, id: MarkId) -> Option<usize> {
+        self.marks.binary_search_by_key(&id, |mark| mark.id).ok()
+    }
+
+    /// Removes the mark with the given id, returning its byte offset if it
+    /// was in the set.
+    #[inline]
+    pub(super) fn remove(&mut self, id: MarkId) -> Option<usize> {
+        self.position(id).map(|idx| self.marks.remove(idx).byte_offset)
+    }
+
+    /// Moves the mark with the given id to the given byte offset, returning
+    /// its old offset if it was in the set.
+    #[inline]
+    pub(super) fn set(
+        &mut self,
+        id: MarkId,
+        byte_offset: usize,
+    ) -> Option<usize> {
+        let idx = self.position(id)?;
+        Some(core::mem::replace(&mut self.marks[idx].byte_offset, byte_offset))
+    }
+}
@@ -0,0 +1,104 @@
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use super::rope::RopeChunk;
+use super::Rope;
+
+/// A breakdown of the memory used by a [`Rope`], returned by
+/// [`Rope::mem_stats()`].
+///
+/// Nodes that appear more than once in the same `Rope`, e.g. after a call to
+/// [`Rope::repeat()`], are only counted once, while the ones shared with
+/// other `Rope`s, e.g. clones or snapshots, are counted as if they were owned
+/// by this one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemStats {
+    /// The number of bytes of text stored in the chunks, which is the byte
+    /// length of the `Rope` unless some of its nodes are repeated.
+    pub text_bytes: usize,
+
+    /// The number of bytes allocated on the heap for the text of the chunks,
+    /// including their unused capacity. Chunks borrowed from a memory-mapped
+    /// file or a `&'static str` don't use any.
+    pub leaf_bytes: usize,
+
+    /// The number of bytes allocated on the heap for the nodes of the tree,
+    /// i.e. their summaries, the lists of children of the internal nodes and
+    /// the bookkeeping of the chunks, but not the text of the chunks.
+    pub node_bytes: usize,
+
+    /// The number of bytes allocated for the text of the chunks which aren't
+    /// currently used, i.e. the room they have for future insertions.
+    pub slack_bytes: usize,
+
+    /// The number of nodes at each depth of the tree, starting from the
+    /// root. The last entry is the number of chunks.
+    pub nodes_per_depth: Vec<usize>,
+}
+
+impl MemStats {
+    /// Returns how full the chunks of the `Rope` are on average, from `0.0`
+    /// to `1.0`.
+    #[inline]
+    pub fn fill_factor(&self) -> f64 {
+        match self.nodes_per_depth.last() {
+            Some(&chunks) if chunks > 0 => {
+                let capacity = chunks * RopeChunk::max_bytes();
+                self.text_bytes as f64 / capacity as f64
+            },
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the total number of bytes allocated on the heap for the
+    /// `Rope`.
+    #[inline]
+    pub fn total_bytes(&self) -> usize {
+        self.leaf_bytes + self.node_bytes
+    }
+}
+
+/// Walks the tree of `rope`, collecting its [`MemStats`].
+#[inline]
+pub(super) fn mem_stats(rope: &Rope) -> MemStats {
+    type NodeRef<'a> = crate::tree::NodeRef<'a, { Rope::arity() }, RopeChunk>;
+
+    fn walk(
+        node: NodeRef<'_>,
+        depth: usize,
+        seen: &mut BTreeSet<usize>,
+        stats: &mut MemStats,
+    ) {
+        if !seen.insert(node.addr()) {
+            return;
+        }
+
+        if stats.nodes_per_depth.len() == depth {
+            stats.nodes_per_depth.push(0);
+        }
+
+        stats.nodes_per_depth[depth] += 1;
+        stats.node_bytes += node.heap_size();
+
+        match node.leaf() {
+            Some(chunk) => {
+                let (heap_size, slack) = chunk.heap_size();
+                stats.text_bytes += chunk.len();
+                stats.leaf_bytes += heap_size;
+                stats.slack_bytes += slack;
+            },
+
+            None => {
+                for child in node.children() {
+                    walk(child, depth + 1, seen, stats);
+                }
+            },
+        }
+    }
+
+    let mut stats = MemStats::default();
+
+    walk(rope.tree.root_ref(), 0, &mut BTreeSet::new(), &mut stats);
+
+    stats
+}
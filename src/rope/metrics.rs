@@ -1,26 +1,96 @@
 use core::ops::{Add, AddAssign, Sub, SubAssign};
 
+#[cfg(feature = "content-hash")]
+use super::content_hash::PolyHash;
 use super::gap_buffer::GapBuffer;
 use super::gap_slice::GapSlice;
-use crate::tree::{DoubleEndedUnitMetric, Metric, SlicingMetric, UnitMetric};
+#[cfg(feature = "word-metric")]
+use super::word_count::WordCount;
+use crate::tree::{Metric, SlicingMetric};
+
+/// The integer type the counts of a [`ChunkSummary`] are stored as.
+#[cfg(not(feature = "compact-summaries"))]
+pub(super) type Count = usize;
+
+/// The integer type the counts of a [`ChunkSummary`] are stored as, which
+/// halves the size of the summaries stored in every node and chunk but limits
+/// a `Rope` to 4 GiB of text.
+#[cfg(feature = "compact-summaries")]
+pub(super) type Count = u32;
+
+/// Converts a count computed as a `usize` into a [`Count`].
+#[cfg(not(feature = "compact-summaries"))]
+#[inline(always)]
+pub(super) fn narrow(count: usize) -> Count {
+    count
+}
+
+/// Converts a count computed as a `usize` into a [`Count`].
+///
+/// Every operation that could make a `Rope` longer than `Count::MAX` bytes
+/// checks its length before touching its tree, so the panic is only a
+/// backstop.
+#[cfg(feature = "compact-summaries")]
+#[track_caller]
+#[inline(always)]
+pub(super) fn narrow(count: usize) -> Count {
+    match Count::try_from(count) {
+        Ok(count) => count,
+        Err(_) => super::utils::panic_messages::rope_too_long(),
+    }
+}
+
+/// Converts a [`Count`] into a `usize`.
+#[inline(always)]
+pub(super) const fn widen(count: Count) -> usize {
+    #[cfg(not(feature = "compact-summaries"))]
+    {
+        count
+    }
+    #[cfg(feature = "compact-summaries")]
+    {
+        count as usize
+    }
+}
 
 #[derive(Copy, Clone, Default, Debug, PartialEq)]
 #[doc(hidden)]
 pub struct ChunkSummary {
-    bytes: usize,
-    line_breaks: usize,
+    bytes: Count,
+    #[cfg(feature = "line-metric")]
+    line_breaks: Count,
+    /// The number of non-ASCII bytes, which is zero if and only if the
+    /// text is all ASCII. We store a count instead of a flag so that
+    /// summaries can still be subtracted from each other.
+    non_ascii_bytes: Count,
     #[cfg(feature = "utf16-metric")]
-    utf16_code_units: usize,
+    utf16_code_units: Count,
+    /// The hash of the summarized text, which is stale if the summary was
+    /// obtained by subtracting another one from it. See the `content_hash`
+    /// module for more.
+    #[cfg(feature = "content-hash")]
+    pub(super) hash: PolyHash,
+    /// The number of words in the summarized text, which is stale if the
+    /// summary was obtained by subtracting another one from it. See the
+    /// `word_count` module for more.
+    #[cfg(feature = "word-metric")]
+    pub(super) words: WordCount,
 }
 
 impl From<&str> for ChunkSummary {
     #[inline]
     fn from(s: &str) -> Self {
         Self {
-            bytes: s.len(),
-            line_breaks: count::line_breaks(s),
+            bytes: narrow(s.len()),
+            #[cfg(feature = "line-metric")]
+            line_breaks: narrow(count::line_breaks(s)),
+            non_ascii_bytes: narrow(count::non_ascii_bytes(s)),
             #[cfg(feature = "utf16-metric")]
-            utf16_code_units: count::utf16_code_units(s),
+            utf16_code_units: narrow(count::utf16_code_units(s)),
+            #[cfg(feature = "content-hash")]
+            hash: PolyHash::from(s),
+            #[cfg(feature = "word-metric")]
+            words: WordCount::from(s),
         }
     }
 }
@@ -29,10 +99,20 @@ impl From<char> for ChunkSummary {
     #[inline]
     fn from(ch: char) -> Self {
         Self {
-            bytes: ch.len_utf8(),
-            line_breaks: (ch == '\n') as usize,
+            bytes: narrow(ch.len_utf8()),
+            #[cfg(feature = "line-metric")]
+            line_breaks: super::utils::is_line_break(ch) as Count,
+            non_ascii_bytes: narrow(if ch.is_ascii() {
+                0
+            } else {
+                ch.len_utf8()
+            }),
             #[cfg(feature = "utf16-metric")]
-            utf16_code_units: ch.len_utf16(),
+            utf16_code_units: narrow(ch.len_utf16()),
+            #[cfg(feature = "content-hash")]
+            hash: PolyHash::from(&*ch.encode_utf8(&mut [0; 4])),
+            #[cfg(feature = "word-metric")]
+            words: WordCount::from(ch),
         }
     }
 }
@@ -40,12 +120,43 @@ impl From<char> for ChunkSummary {
 impl ChunkSummary {
     #[inline]
     pub fn bytes(&self) -> usize {
-        self.bytes
+        widen(self.bytes)
     }
 
+    /// Returns the content hash of the summarized text, or `None` if it's
+    /// stale.
+    #[cfg(feature = "content-hash")]
+    #[inline]
+    pub(super) fn content_hash(&self) -> Option<u64> {
+        (!self.hash.is_stale()).then(|| self.hash.finish())
+    }
+
+    /// Returns whether any of the counts that can't be subtracted is stale,
+    /// in which case it has to be recomputed from the summarized text.
+    #[cfg(any(feature = "content-hash", feature = "word-metric"))]
+    #[inline]
+    pub(super) fn is_stale(&self) -> bool {
+        #[cfg(feature = "content-hash")]
+        if self.hash.is_stale() {
+            return true;
+        }
+        #[cfg(feature = "word-metric")]
+        if self.words.is_stale() {
+            return true;
+        }
+        false
+    }
+
+    /// Returns whether all the summarized text is ASCII.
+    #[inline]
+    pub fn is_ascii(&self) -> bool {
+        self.non_ascii_bytes == 0
+    }
+
+    #[cfg(feature = "line-metric")]
     #[inline]
     pub fn line_breaks(&self) -> usize {
-        self.line_breaks
+        widen(self.line_breaks)
     }
 
     #[doc(hidden)]
@@ -54,10 +165,23 @@ impl ChunkSummary {
         Self::default()
     }
 
+    #[inline]
+    fn non_ascii_bytes(&self) -> usize {
+        widen(self.non_ascii_bytes)
+    }
+
     #[cfg(feature = "utf16-metric")]
     #[inline]
     pub fn utf16_code_units(&self) -> usize {
-        self.utf16_code_units
+        widen(self.utf16_code_units)
+    }
+
+    /// Returns the number of words in the summarized text, or `None` if
+    /// it's stale.
+    #[cfg(feature = "word-metric")]
+    #[inline]
+    pub(super) fn words(&self) -> Option<usize> {
+        (!self.words.is_stale()).then(|| self.words.words())
     }
 }
 
@@ -102,22 +226,71 @@ impl Sub<&Self> for ChunkSummary {
 }
 
 impl AddAssign<Self> for ChunkSummary {
+    #[track_caller]
     #[inline]
     fn add_assign(&mut self, rhs: Self) {
-        self.bytes += rhs.bytes;
-        self.line_breaks += rhs.line_breaks;
+        // The other counts are never larger than the number of bytes, so
+        // they can't overflow if this doesn't. Like in `narrow()`, the panic
+        // is only a backstop.
+        #[cfg(feature = "compact-summaries")]
+        {
+            self.bytes = match self.bytes.checked_add(rhs.bytes) {
+                Some(bytes) => bytes,
+                None => super::utils::panic_messages::rope_too_long(),
+            };
+        }
+        #[cfg(not(feature = "compact-summaries"))]
+        {
+            self.bytes += rhs.bytes;
+        }
+        #[cfg(feature = "line-metric")]
+        {
+            self.line_breaks += rhs.line_breaks;
+        }
+        self.non_ascii_bytes += rhs.non_ascii_bytes;
         #[cfg(feature = "utf16-metric")]
         {
             self.utf16_code_units += rhs.utf16_code_units;
         }
+        #[cfg(feature = "content-hash")]
+        {
+            self.hash = self.hash.concat(rhs.hash);
+        }
+        #[cfg(feature = "word-metric")]
+        {
+            self.words = self.words.concat(rhs.words);
+        }
     }
 }
 
 impl SubAssign<Self> for ChunkSummary {
     #[inline]
     fn sub_assign(&mut self, rhs: Self) {
+        // We don't know where the subtracted text was, so the hash and the
+        // word count of the difference are only known if either side is
+        // empty.
+        #[cfg(feature = "content-hash")]
+        {
+            if self.bytes == rhs.bytes {
+                self.hash = PolyHash::default();
+            } else if rhs.bytes != 0 {
+                self.hash = PolyHash::STALE;
+            }
+        }
+        #[cfg(feature = "word-metric")]
+        {
+            if self.bytes == rhs.bytes {
+                self.words = WordCount::default();
+            } else if rhs.bytes != 0 {
+                self.words = WordCount::STALE;
+            }
+        }
         self.bytes -= rhs.bytes;
-        self.line_breaks -= rhs.line_breaks;
+        #[cfg(feature = "line-metric")]
+        {
+            self.line_breaks -= rhs.line_breaks;
+        }
+        self.non_ascii_bytes -= rhs.non_ascii_bytes;
         #[cfg(feature = "utf16-metric")]
         {
             self.utf16_code_units -= rhs.utf16_code_units;
@@ -229,20 +402,38 @@ impl SummaryUpTo for ByteMetric {
         debug_assert_eq!(offset.0, byte_offset);
 
         ChunkSummary {
-            bytes: byte_offset,
+            bytes: narrow(byte_offset),
 
-            line_breaks: count::line_breaks_up_to(
+            #[cfg(feature = "line-metric")]
+            line_breaks: narrow(count::line_breaks_up_to(
                 in_str,
                 byte_offset,
-                str_summary.line_breaks,
-            ),
+                str_summary.line_breaks(),
+            )),
 
-            #[cfg(feature = "utf16-metric")]
-            utf16_code_units: count::utf16_code_units_up_to(
+            non_ascii_bytes: narrow(count::non_ascii_bytes_up_to(
                 in_str,
                 byte_offset,
-                str_summary.utf16_code_units,
-            ),
+                str_summary.non_ascii_bytes(),
+            )),
+
+            // In ASCII chunks every byte is also a UTF-16 code unit.
+            #[cfg(feature = "utf16-metric")]
+            utf16_code_units: narrow(if str_summary.is_ascii() {
+                byte_offset
+            } else {
+                count::utf16_code_units_up_to(
+                    in_str,
+                    byte_offset,
+                    str_summary.utf16_code_units(),
+                )
+            }),
+
+            #[cfg(feature = "content-hash")]
+            hash: PolyHash::STALE,
+
+            #[cfg(feature = "word-metric")]
+            words: WordCount::from(&in_str[..byte_offset]),
         }
     }
 }
@@ -260,7 +451,7 @@ impl Metric<ChunkSummary> for ByteMetric {
 
     #[inline]
     fn measure(summary: &ChunkSummary) -> Self {
-        Self(summary.bytes)
+        Self(summary.bytes())
     }
 }
 
@@ -300,276 +491,324 @@ impl<const MAX_BYTES: usize> SlicingMetric<GapBuffer<MAX_BYTES>>
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct RawLineMetric(pub usize);
+#[cfg(feature = "line-metric")]
+pub(super) use line_metric::LineMetric;
+#[cfg(feature = "line-metric")]
+pub use line_metric::RawLineMetric;
 
-impl Add for RawLineMetric {
-    type Output = Self;
+#[cfg(feature = "line-metric")]
+mod line_metric {
+    use super::*;
+    use crate::tree::{DoubleEndedUnitMetric, UnitMetric};
 
-    #[inline]
-    fn add(self, other: Self) -> Self {
-        Self(self.0 + other.0)
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct RawLineMetric(pub usize);
+
+    impl Add for RawLineMetric {
+        type Output = Self;
+
+        #[inline]
+        fn add(self, other: Self) -> Self {
+            Self(self.0 + other.0)
+        }
     }
-}
 
-impl Sub for RawLineMetric {
-    type Output = Self;
+    impl Sub for RawLineMetric {
+        type Output = Self;
 
-    #[inline]
-    fn sub(self, other: Self) -> Self {
-        Self(self.0 - other.0)
+        #[inline]
+        fn sub(self, other: Self) -> Self {
+            Self(self.0 - other.0)
+        }
     }
-}
 
-impl AddAssign for RawLineMetric {
-    #[inline]
-    fn add_assign(&mut self, other: Self) {
-        self.0 += other.0
+    impl AddAssign for RawLineMetric {
+        #[inline]
+        fn add_assign(&mut self, other: Self) {
+            self.0 += other.0
+        }
     }
-}
 
-impl SubAssign for RawLineMetric {
-    #[inline]
-    fn sub_assign(&mut self, other: Self) {
-        self.0 -= other.0
+    impl SubAssign for RawLineMetric {
+        #[inline]
+        fn sub_assign(&mut self, other: Self) {
+            self.0 -= other.0
+        }
     }
-}
 
-impl ToByteOffset for RawLineMetric {
-    #[inline]
-    fn to_byte_offset(&self, s: &str) -> usize {
-        convert::byte_of_line(s, self.0)
+    impl ToByteOffset for RawLineMetric {
+        #[inline]
+        fn to_byte_offset(&self, s: &str) -> usize {
+            convert::byte_of_line(s, self.0)
+        }
     }
-}
 
-impl SummaryUpTo for RawLineMetric {
-    #[cfg_attr(not(feature = "utf16-metric"), allow(unused_variables))]
-    #[inline]
-    fn up_to(
-        in_str: &str,
-        str_summary: ChunkSummary,
-        Self(line_offset): Self,
-        byte_offset: usize,
-    ) -> ChunkSummary {
-        ChunkSummary {
-            bytes: byte_offset,
+    impl SummaryUpTo for RawLineMetric {
+        #[inline]
+        fn up_to(
+            in_str: &str,
+            str_summary: ChunkSummary,
+            Self(line_offset): Self,
+            byte_offset: usize,
+        ) -> ChunkSummary {
+            ChunkSummary {
+                bytes: narrow(byte_offset),
 
-            line_breaks: line_offset,
+                line_breaks: narrow(line_offset),
 
-            #[cfg(feature = "utf16-metric")]
-            utf16_code_units: count::utf16_code_units_up_to(
-                in_str,
-                byte_offset,
-                str_summary.utf16_code_units,
-            ),
+                non_ascii_bytes: narrow(count::non_ascii_bytes_up_to(
+                    in_str,
+                    byte_offset,
+                    str_summary.non_ascii_bytes(),
+                )),
+
+                #[cfg(feature = "utf16-metric")]
+                utf16_code_units: narrow(count::utf16_code_units_up_to(
+                    in_str,
+                    byte_offset,
+                    str_summary.utf16_code_units(),
+                )),
+
+                #[cfg(feature = "content-hash")]
+                hash: PolyHash::STALE,
+
+                #[cfg(feature = "word-metric")]
+                words: WordCount::from(&in_str[..byte_offset]),
+            }
         }
     }
-}
 
-impl Metric<ChunkSummary> for RawLineMetric {
-    #[inline]
-    fn zero() -> Self {
-        Self(0)
-    }
+    impl Metric<ChunkSummary> for RawLineMetric {
+        #[inline]
+        fn zero() -> Self {
+            Self(0)
+        }
 
-    #[inline]
-    fn one() -> Self {
-        Self(1)
-    }
+        #[inline]
+        fn one() -> Self {
+            Self(1)
+        }
 
-    #[inline]
-    fn measure(summary: &ChunkSummary) -> Self {
-        Self(summary.line_breaks)
+        #[inline]
+        fn measure(summary: &ChunkSummary) -> Self {
+            Self(summary.line_breaks())
+        }
     }
-}
 
-impl<const MAX_BYTES: usize> SlicingMetric<GapBuffer<MAX_BYTES>>
-    for RawLineMetric
-{
-    #[inline]
-    fn slice_up_to<'a>(
-        chunk: GapSlice<'a>,
-        line_offset: Self,
-        &summary: &ChunkSummary,
-    ) -> (GapSlice<'a>, ChunkSummary)
-    where
-        'a: 'a,
+    impl<const MAX_BYTES: usize> SlicingMetric<GapBuffer<MAX_BYTES>>
+        for RawLineMetric
     {
-        let (left, _) = chunk.split_at_offset(line_offset, summary);
-        left
-    }
+        #[inline]
+        fn slice_up_to<'a>(
+            chunk: GapSlice<'a>,
+            line_offset: Self,
+            &summary: &ChunkSummary,
+        ) -> (GapSlice<'a>, ChunkSummary)
+        where
+            'a: 'a,
+        {
+            let (left, _) = chunk.split_at_offset(line_offset, summary);
+            left
+        }
 
-    #[inline]
-    fn slice_from<'a>(
-        chunk: GapSlice<'a>,
-        line_offset: Self,
-        &summary: &ChunkSummary,
-    ) -> (GapSlice<'a>, ChunkSummary)
-    where
-        'a: 'a,
-    {
-        let (_, right) = chunk.split_at_offset(line_offset, summary);
-        right
+        #[inline]
+        fn slice_from<'a>(
+            chunk: GapSlice<'a>,
+            line_offset: Self,
+            &summary: &ChunkSummary,
+        ) -> (GapSlice<'a>, ChunkSummary)
+        where
+            'a: 'a,
+        {
+            let (_, right) = chunk.split_at_offset(line_offset, summary);
+            right
+        }
     }
-}
 
-impl<const MAX_BYTES: usize> UnitMetric<GapBuffer<MAX_BYTES>>
-    for RawLineMetric
-{
-    #[inline]
-    fn first_unit<'a>(
-        chunk: GapSlice<'a>,
-        &summary: &ChunkSummary,
-    ) -> (GapSlice<'a>, ChunkSummary, ChunkSummary, GapSlice<'a>, ChunkSummary)
-    where
-        'a: 'a,
+    impl<const MAX_BYTES: usize> UnitMetric<GapBuffer<MAX_BYTES>>
+        for RawLineMetric
     {
-        let ((first, first_summary), (rest, rest_summary)) =
-            chunk.split_at_offset(RawLineMetric(1), summary);
+        #[inline]
+        fn first_unit<'a>(
+            chunk: GapSlice<'a>,
+            &summary: &ChunkSummary,
+        ) -> (
+            GapSlice<'a>,
+            ChunkSummary,
+            ChunkSummary,
+            GapSlice<'a>,
+            ChunkSummary,
+        )
+        where
+            'a: 'a,
+        {
+            let ((first, first_summary), (rest, rest_summary)) =
+                chunk.split_at_offset(RawLineMetric(1), summary);
 
-        (first, first_summary, first_summary, rest, rest_summary)
+            (first, first_summary, first_summary, rest, rest_summary)
+        }
     }
-}
 
-impl<const MAX_BYTES: usize> DoubleEndedUnitMetric<GapBuffer<MAX_BYTES>>
-    for RawLineMetric
-{
-    #[inline]
-    fn last_unit<'a>(
-        slice: GapSlice<'a>,
-        &summary: &ChunkSummary,
-    ) -> (GapSlice<'a>, ChunkSummary, GapSlice<'a>, ChunkSummary, ChunkSummary)
-    where
-        'a: 'a,
+    impl<const MAX_BYTES: usize> DoubleEndedUnitMetric<GapBuffer<MAX_BYTES>>
+        for RawLineMetric
     {
-        let split_offset =
-            summary.line_breaks - (slice.has_trailing_newline() as usize);
+        #[inline]
+        fn last_unit<'a>(
+            slice: GapSlice<'a>,
+            &summary: &ChunkSummary,
+        ) -> (
+            GapSlice<'a>,
+            ChunkSummary,
+            GapSlice<'a>,
+            ChunkSummary,
+            ChunkSummary,
+        )
+        where
+            'a: 'a,
+        {
+            let split_offset = summary.line_breaks()
+                - (slice.has_trailing_newline() as usize);
 
-        let ((rest, rest_summary), (last, last_summary)) =
-            slice.split_at_offset(RawLineMetric(split_offset), summary);
+            let ((rest, rest_summary), (last, last_summary)) =
+                slice.split_at_offset(RawLineMetric(split_offset), summary);
 
-        (rest, rest_summary, last, last_summary, last_summary)
-    }
+            (rest, rest_summary, last, last_summary, last_summary)
+        }
 
-    #[inline]
-    fn remainder<'a>(
-        chunk: GapSlice<'a>,
-        summary: &ChunkSummary,
-    ) -> (GapSlice<'a>, ChunkSummary, GapSlice<'a>, ChunkSummary)
-    where
-        'a: 'a,
-    {
-        if chunk.has_trailing_newline() {
-            (chunk, *summary, GapSlice::empty(), ChunkSummary::new())
-        } else {
-            let (rest, rest_summary, last, last_summary, _) =
-                <Self as DoubleEndedUnitMetric<GapBuffer<MAX_BYTES>>>::last_unit(chunk, summary);
+        #[inline]
+        fn remainder<'a>(
+            chunk: GapSlice<'a>,
+            summary: &ChunkSummary,
+        ) -> (GapSlice<'a>, ChunkSummary, GapSlice<'a>, ChunkSummary)
+        where
+            'a: 'a,
+        {
+            if chunk.has_trailing_newline() {
+                (chunk, *summary, GapSlice::empty(), ChunkSummary::new())
+            } else {
+                let (rest, rest_summary, last, last_summary, _) =
+                    <Self as DoubleEndedUnitMetric<GapBuffer<MAX_BYTES>>>::last_unit(chunk, summary);
 
-            (rest, rest_summary, last, last_summary)
+                (rest, rest_summary, last, last_summary)
+            }
         }
     }
-}
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub(super) struct LineMetric(pub(super) usize);
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct LineMetric(pub usize);
 
-impl Add for LineMetric {
-    type Output = Self;
+    impl Add for LineMetric {
+        type Output = Self;
 
-    #[inline]
-    fn add(self, other: Self) -> Self {
-        Self(self.0 + other.0)
+        #[inline]
+        fn add(self, other: Self) -> Self {
+            Self(self.0 + other.0)
+        }
     }
-}
 
-impl Sub for LineMetric {
-    type Output = Self;
+    impl Sub for LineMetric {
+        type Output = Self;
 
-    #[inline]
-    fn sub(self, other: Self) -> Self {
-        Self(self.0 - other.0)
+        #[inline]
+        fn sub(self, other: Self) -> Self {
+            Self(self.0 - other.0)
+        }
     }
-}
 
-impl AddAssign for LineMetric {
-    #[inline]
-    fn add_assign(&mut self, other: Self) {
-        self.0 += other.0
+    impl AddAssign for LineMetric {
+        #[inline]
+        fn add_assign(&mut self, other: Self) {
+            self.0 += other.0
+        }
     }
-}
 
-impl SubAssign for LineMetric {
-    #[inline]
-    fn sub_assign(&mut self, other: Self) {
-        self.0 -= other.0
+    impl SubAssign for LineMetric {
+        #[inline]
+        fn sub_assign(&mut self, other: Self) {
+            self.0 -= other.0
+        }
     }
-}
 
-impl Metric<ChunkSummary> for LineMetric {
-    #[inline]
-    fn zero() -> Self {
-        Self(0)
-    }
+    impl Metric<ChunkSummary> for LineMetric {
+        #[inline]
+        fn zero() -> Self {
+            Self(0)
+        }
 
-    #[inline]
-    fn one() -> Self {
-        Self(1)
-    }
+        #[inline]
+        fn one() -> Self {
+            Self(1)
+        }
 
-    #[inline]
-    fn measure(summary: &ChunkSummary) -> Self {
-        Self(summary.line_breaks)
+        #[inline]
+        fn measure(summary: &ChunkSummary) -> Self {
+            Self(summary.line_breaks())
+        }
     }
-}
 
-impl<const MAX_BYTES: usize> UnitMetric<GapBuffer<MAX_BYTES>> for LineMetric {
-    #[inline]
-    fn first_unit<'a>(
-        chunk: GapSlice<'a>,
-        summary: &ChunkSummary,
-    ) -> (GapSlice<'a>, ChunkSummary, ChunkSummary, GapSlice<'a>, ChunkSummary)
-    where
-        'a: 'a,
-    {
-        let (mut first, mut first_summary, advance, rest, rest_summary) =
-            <RawLineMetric as UnitMetric<GapBuffer<MAX_BYTES>>>::first_unit(
-                chunk, summary,
-            );
+    impl<const MAX_BYTES: usize> UnitMetric<GapBuffer<MAX_BYTES>> for LineMetric {
+        #[inline]
+        fn first_unit<'a>(
+            chunk: GapSlice<'a>,
+            summary: &ChunkSummary,
+        ) -> (
+            GapSlice<'a>,
+            ChunkSummary,
+            ChunkSummary,
+            GapSlice<'a>,
+            ChunkSummary,
+        )
+        where
+            'a: 'a,
+        {
+            let (mut first, mut first_summary, advance, rest, rest_summary) =
+                <RawLineMetric as UnitMetric<GapBuffer<MAX_BYTES>>>::first_unit(
+                    chunk, summary,
+                );
 
-        first_summary = first.truncate_trailing_line_break(first_summary);
+            first_summary = first.truncate_trailing_line_break(first_summary);
 
-        (first, first_summary, advance, rest, rest_summary)
+            (first, first_summary, advance, rest, rest_summary)
+        }
     }
-}
 
-impl<const MAX_BYTES: usize> DoubleEndedUnitMetric<GapBuffer<MAX_BYTES>>
-    for LineMetric
-{
-    #[inline]
-    fn last_unit<'a>(
-        chunk: GapSlice<'a>,
-        summary: &ChunkSummary,
-    ) -> (GapSlice<'a>, ChunkSummary, GapSlice<'a>, ChunkSummary, ChunkSummary)
-    where
-        'a: 'a,
+    impl<const MAX_BYTES: usize> DoubleEndedUnitMetric<GapBuffer<MAX_BYTES>>
+        for LineMetric
     {
-        let (rest, rest_summary, mut last, mut last_summary, advance) =
-            <RawLineMetric as DoubleEndedUnitMetric<GapBuffer<MAX_BYTES>>>::last_unit(chunk, summary);
+        #[inline]
+        fn last_unit<'a>(
+            chunk: GapSlice<'a>,
+            summary: &ChunkSummary,
+        ) -> (
+            GapSlice<'a>,
+            ChunkSummary,
+            GapSlice<'a>,
+            ChunkSummary,
+            ChunkSummary,
+        )
+        where
+            'a: 'a,
+        {
+            let (rest, rest_summary, mut last, mut last_summary, advance) =
+                <RawLineMetric as DoubleEndedUnitMetric<
+                    GapBuffer<MAX_BYTES>,
+                >>::last_unit(chunk, summary);
 
-        last_summary = last.truncate_trailing_line_break(last_summary);
+            last_summary = last.truncate_trailing_line_break(last_summary);
 
-        (rest, rest_summary, last, last_summary, advance)
-    }
+            (rest, rest_summary, last, last_summary, advance)
+        }
 
-    #[inline]
-    fn remainder<'a>(
-        chunk: GapSlice<'a>,
-        summary: &ChunkSummary,
-    ) -> (GapSlice<'a>, ChunkSummary, GapSlice<'a>, ChunkSummary)
-    where
-        'a: 'a,
-    {
-        <RawLineMetric as DoubleEndedUnitMetric<GapBuffer<MAX_BYTES>>>::remainder(chunk, summary)
+        #[inline]
+        fn remainder<'a>(
+            chunk: GapSlice<'a>,
+            summary: &ChunkSummary,
+        ) -> (GapSlice<'a>, ChunkSummary, GapSlice<'a>, ChunkSummary)
+        where
+            'a: 'a,
+        {
+            <RawLineMetric as DoubleEndedUnitMetric<GapBuffer<MAX_BYTES>>>::remainder(chunk, summary)
+        }
     }
 }
 
@@ -635,15 +874,28 @@ mod utf16_metric {
             byte_offset: usize,
         ) -> ChunkSummary {
             ChunkSummary {
-                bytes: byte_offset,
+                bytes: narrow(byte_offset),
 
-                line_breaks: count::line_breaks_up_to(
+                #[cfg(feature = "line-metric")]
+                line_breaks: narrow(count::line_breaks_up_to(
                     in_str,
                     byte_offset,
-                    str_summary.line_breaks,
-                ),
+                    str_summary.line_breaks(),
+                )),
 
-                utf16_code_units: utf16_code_unit_offset,
+                non_ascii_bytes: narrow(count::non_ascii_bytes_up_to(
+                    in_str,
+                    byte_offset,
+                    str_summary.non_ascii_bytes(),
+                )),
+
+                utf16_code_units: narrow(utf16_code_unit_offset),
+
+                #[cfg(feature = "content-hash")]
+                hash: PolyHash::STALE,
+
+                #[cfg(feature = "word-metric")]
+                words: WordCount::from(&in_str[..byte_offset]),
             }
         }
     }
@@ -661,7 +913,7 @@ mod utf16_metric {
 
         #[inline]
         fn measure(summary: &ChunkSummary) -> Self {
-            Self(summary.utf16_code_units)
+            Self(summary.utf16_code_units())
         }
     }
 
@@ -678,8 +930,15 @@ mod utf16_metric {
         where
             'a: 'a,
         {
-            let (left, _) =
-                chunk.split_at_offset(utf16_code_unit_offset, summary);
+            // In ASCII chunks UTF-16 offsets are also byte offsets.
+            let (left, _) = if summary.is_ascii() {
+                chunk.split_at_offset(
+                    ByteMetric(utf16_code_unit_offset.0),
+                    summary,
+                )
+            } else {
+                chunk.split_at_offset(utf16_code_unit_offset, summary)
+            };
             left
         }
 
@@ -693,37 +952,84 @@ mod utf16_metric {
         where
             'a: 'a,
         {
-            let (_, right) =
-                chunk.split_at_offset(utf16_code_unit_offset, summary);
+            // In ASCII chunks UTF-16 offsets are also byte offsets.
+            let (_, right) = if summary.is_ascii() {
+                chunk.split_at_offset(
+                    ByteMetric(utf16_code_unit_offset.0),
+                    summary,
+                )
+            } else {
+                chunk.split_at_offset(utf16_code_unit_offset, summary)
+            };
             right
         }
     }
 }
 
-use str_utils::*;
+pub(super) use str_utils::*;
 
 mod str_utils {
-    #[cfg(not(miri))]
+    #[cfg(all(
+        not(miri),
+        feature = "line-metric",
+        not(feature = "unicode-lines")
+    ))]
     use str_indices::lines_lf as lines;
     #[cfg(all(not(miri), feature = "utf16-metric"))]
     use str_indices::utf16;
 
     pub mod count {
-        #[cfg(not(miri))]
+        #[cfg(all(
+            not(miri),
+            any(
+                all(
+                    feature = "line-metric",
+                    not(feature = "simd"),
+                    not(feature = "unicode-lines")
+                ),
+                feature = "utf16-metric"
+            )
+        ))]
         use super::*;
 
+        #[cfg(feature = "line-metric")]
         #[inline]
         pub fn line_breaks(s: &str) -> usize {
-            #[cfg(not(miri))]
+            #[cfg(feature = "unicode-lines")]
+            {
+                crate::rope::unicode_lines::count_line_breaks(s)
+            }
+            #[cfg(all(
+                not(miri),
+                feature = "simd",
+                not(feature = "unicode-lines")
+            ))]
+            {
+                crate::rope::simd::count_line_breaks(s)
+            }
+            #[cfg(all(
+                not(miri),
+                not(feature = "simd"),
+                not(feature = "unicode-lines")
+            ))]
             {
                 lines::count_breaks(s)
             }
-            #[cfg(miri)]
+            #[cfg(all(miri, not(feature = "unicode-lines")))]
             {
                 s.bytes().filter(|&b| b == b'\n').count()
             }
         }
 
+        #[inline]
+        pub fn non_ascii_bytes(s: &str) -> usize {
+            if s.is_ascii() {
+                0
+            } else {
+                s.bytes().filter(|b| !b.is_ascii()).count()
+            }
+        }
+
         #[cfg(feature = "utf16-metric")]
         #[inline]
         pub fn utf16_code_units(s: &str) -> usize {
@@ -737,6 +1043,7 @@ mod str_utils {
             }
         }
 
+        #[cfg(feature = "line-metric")]
         #[inline(always)]
         pub fn line_breaks_up_to(
             s: &str,
@@ -746,6 +1053,19 @@ mod str_utils {
             metric_up_to(s, byte_offset, tot_line_breaks, line_breaks)
         }
 
+        #[inline(always)]
+        pub fn non_ascii_bytes_up_to(
+            s: &str,
+            byte_offset: usize,
+            tot_non_ascii_bytes: usize,
+        ) -> usize {
+            if tot_non_ascii_bytes == 0 {
+                return 0;
+            }
+
+            metric_up_to(s, byte_offset, tot_non_ascii_bytes, non_ascii_bytes)
+        }
+
         #[cfg(feature = "utf16-metric")]
         #[inline(always)]
         pub fn utf16_code_units_up_to(
@@ -782,18 +1102,28 @@ mod str_utils {
         }
     }
 
+    #[cfg(any(feature = "line-metric", feature = "utf16-metric"))]
     pub mod convert {
-        #[cfg(not(miri))]
+        #[cfg(all(
+            not(miri),
+            any(not(feature = "unicode-lines"), feature = "utf16-metric")
+        ))]
         use super::*;
 
+        #[cfg(feature = "line-metric")]
         #[inline]
         pub fn byte_of_line(s: &str, line_offset: usize) -> usize {
-            #[cfg(not(miri))]
+            #[cfg(feature = "unicode-lines")]
+            {
+                crate::rope::unicode_lines::byte_of_line(s, line_offset)
+            }
+
+            #[cfg(all(not(miri), not(feature = "unicode-lines")))]
             {
                 lines::to_byte_idx(s, line_offset)
             }
 
-            #[cfg(miri)]
+            #[cfg(all(miri, not(feature = "unicode-lines")))]
             {
                 if line_offset == 0 {
                     return 0;
@@ -1,12 +1,103 @@
+pub(crate) mod annotated;
+mod bom;
+mod chunk_bytes;
+#[cfg(feature = "chunk-pool")]
+pub(crate) mod chunk_pool;
+#[cfg(feature = "serde")]
+pub(crate) mod chunked;
+mod content_hash;
+mod delta;
+pub(crate) mod delta_sync;
+#[cfg(feature = "line-metric")]
+mod dirty_lines;
+pub(crate) mod edit_overlay;
+mod edit_session;
+#[cfg(feature = "editing-traces")]
+pub(crate) mod editing_trace;
+#[cfg(feature = "line-metric")]
+pub(crate) mod filtered_lines;
+pub(crate) mod folded_view;
+mod frozen_rope;
 pub(crate) mod gap_buffer;
 pub(crate) mod gap_slice;
+pub(crate) mod history;
+mod incremental_edit;
+mod interner;
+pub(crate) mod interval_tree;
 pub(crate) mod iterators;
+mod limit;
+#[cfg(feature = "line-metric")]
+mod line_cache;
+#[cfg(feature = "line-metric")]
+pub(crate) mod line_data;
+mod line_ending;
+pub(crate) mod marks;
+mod mem_stats;
 pub mod metrics;
+pub(crate) mod multi_rope;
+pub(crate) mod op_log;
+#[cfg(feature = "paging")]
+pub(crate) mod paging;
+#[cfg(feature = "parallel")]
+mod parallel;
+pub(crate) mod record_index;
+mod revision;
+#[cfg(feature = "rkyv")]
+mod rkyv_impls;
 mod rope;
 mod rope_builder;
+mod rope_cursor;
+#[cfg(feature = "std")]
+mod rope_loader;
+mod rope_ref;
 mod rope_slice;
+pub(crate) mod selections;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(all(
+    feature = "simd",
+    feature = "line-metric",
+    not(feature = "unicode-lines"),
+    not(miri)
+))]
+mod simd;
+mod snapshot;
+#[cfg(feature = "std")]
+pub(crate) mod store;
+mod subscribers;
+pub(crate) mod sum_rope;
+mod too_long;
+pub(crate) mod transaction;
+#[cfg(feature = "unicode-lines")]
+mod unicode_lines;
 mod utils;
+#[cfg(feature = "word-metric")]
+mod word_count;
 
+pub use bom::Bom;
+#[cfg(feature = "mmap")]
+pub use chunk_bytes::StableBytes;
+pub use delta::Delta;
+pub use edit_session::EditSession;
+pub use frozen_rope::FrozenRope;
+pub use incremental_edit::IncrementalEdit;
+pub use interner::ChunkInterner;
+pub use limit::Limit;
+#[cfg(feature = "line-metric")]
+pub use line_cache::LineCache;
+use line_ending::LineEndingCounts;
+pub use line_ending::{LineEnding, TrailingLineBreak};
+pub use mem_stats::MemStats;
+pub use revision::Revision;
+#[cfg(feature = "rkyv")]
+pub use rkyv_impls::{ArchivedRope, ArchivedRopeSlice, RopeResolver};
 pub use rope::Rope;
 pub use rope_builder::RopeBuilder;
+pub use rope_cursor::RopeCursor;
+#[cfg(feature = "std")]
+pub use rope_loader::RopeLoader;
+pub use rope_ref::RopeRef;
 pub use rope_slice::RopeSlice;
+pub use snapshot::Snapshot;
+pub use subscribers::{EditInfo, EditRegion, SubscriptionId};
+pub use too_long::TooLongError;
@@ -0,0 +1,398 @@
+use alloc::vec::Vec;
+use core::ops::RangeBounds;
+
+#[cfg(feature = "line-metric")]
+use super::utils::is_line_break;
+use super::utils::panic_messages as panic;
+use super::RopeSlice;
+use crate::range_bounds_to_start_end;
+
+/// A view presenting a sequence of [`RopeSlice`]s as a single document,
+/// without copying their text.
+///
+/// Pieces are added with [`push()`](Self::push()), and the view can then be
+/// sliced, iterated over and queried for byte and line offsets as if the
+/// pieces were concatenated. Offsets are mapped to the piece containing them
+/// with a binary search, so every query takes `O(log n)` time in the number
+/// of pieces on top of the cost of the same query on a `RopeSlice`.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::multi_rope::MultiRope;
+/// # use crop::Rope;
+/// #
+/// let header = Rope::from("// Generated file.\n");
+/// let body = Rope::from("fn main() {}\n");
+///
+/// let mut doc = MultiRope::new();
+/// doc.push(header.byte_slice(..));
+/// doc.push(body.byte_slice(..));
+///
+/// assert_eq!(doc.line_len(), 2);
+/// assert_eq!(doc.byte_of_line(1), header.byte_len());
+/// assert_eq!(doc.byte_slice(3..12), "Generated");
+/// assert_eq!(doc, "// Generated file.\nfn main() {}\n");
+/// ```
+#[derive(Clone, Default)]
+pub struct MultiRope<'a> {
+    pieces: Vec<RopeSlice<'a>>,
+
+    /// The byte offset and the number of line breaks at the end of every
+    /// piece, counting from the start of the view. Line breaks are only
+    /// counted if the `line-metric` feature is enabled.
+    ends: Vec<(usize, usize)>,
+}
+
+impl<'a> MultiRope<'a> {
+    #[doc(hidden)]
+    pub fn assert_invariants(&self) {
+        assert_eq!(self.pieces.len(), self.ends.len());
+
+        let mut byte_end = 0;
+        let mut line_breaks = 0;
+
+        for (piece, &end) in self.pieces.iter().zip(&self.ends) {
+            assert!(!piece.is_empty());
+            piece.assert_invariants();
+
+            byte_end += piece.byte_len();
+            line_breaks += line_breaks_in(piece);
+            assert_eq!(end, (byte_end, line_breaks));
+        }
+    }
+
+    /// Returns the byte at `byte_index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte index is out of bounds (i.e. greater than or equal
+    /// to [`byte_len()`](Self::byte_len())).
+    #[track_caller]
+    #[inline]
+    pub fn byte(&self, byte_index: usize) -> u8 {
+        if byte_index >= self.byte_len() {
+            panic::byte_index_out_of_bounds(byte_index, self.byte_len());
+        }
+
+        let (piece_idx, byte_index) = self.piece_of_byte(byte_index);
+
+        self.pieces[piece_idx].byte(byte_index)
+    }
+
+    /// Returns the byte length of the view.
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.ends.last().map_or(0, |&(byte_end, _)| byte_end)
+    }
+
+    /// Returns the byte offset of the start of the given line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line offset is out of bounds (i.e. greater than
+    /// [`line_len()`](Self::line_len())).
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn byte_of_line(&self, line_offset: usize) -> usize {
+        if line_offset > self.line_len() {
+            panic::line_offset_out_of_bounds(line_offset, self.line_len());
+        }
+
+        let piece_idx = self
+            .ends
+            .partition_point(|&(_, line_breaks)| line_breaks < line_offset);
+
+        let Some(piece) = self.pieces.get(piece_idx) else {
+            return self.byte_len();
+        };
+
+        let (byte_start, lines_before) = self.start_of_piece(piece_idx);
+
+        byte_start + piece.byte_of_line(line_offset - lines_before)
+    }
+
+    /// Returns a sub-view of the view in the specified byte range, where the
+    /// start and end of the range are interpreted as offsets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start is greater than the end or if the end is out of
+    /// bounds (i.e. greater than [`byte_len()`](Self::byte_len())), or if
+    /// either of them doesn't lie on a code point boundary.
+    #[track_caller]
+    #[inline]
+    pub fn byte_slice<R>(&self, byte_range: R) -> MultiRope<'a>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.byte_len());
+
+        if start > end {
+            panic::byte_start_after_end(start, end);
+        }
+
+        if end > self.byte_len() {
+            panic::byte_offset_out_of_bounds(end, self.byte_len());
+        }
+
+        let mut slice = MultiRope::new();
+
+        if start == end {
+            if start < self.byte_len() {
+                let (piece_idx, start) = self.piece_of_byte(start);
+
+                // Slicing takes care of checking that the offset is on a code
+                // point boundary.
+                let _ = self.pieces[piece_idx].byte_slice(start..start);
+            }
+
+            return slice;
+        }
+
+        let first =
+            self.ends.partition_point(|&(byte_end, _)| byte_end <= start);
+
+        for (idx, piece) in self.pieces.iter().enumerate().skip(first) {
+            let (piece_start, _) = self.start_of_piece(idx);
+
+            if piece_start >= end {
+                break;
+            }
+
+            let piece_end = (end - piece_start).min(piece.byte_len());
+
+            // Slicing takes care of checking that the range is on code point
+            // boundaries.
+            slice.push(
+                piece.byte_slice(start.saturating_sub(piece_start)..piece_end),
+            );
+        }
+
+        slice
+    }
+
+    /// Returns an iterator over the bytes of the view.
+    #[inline]
+    pub fn bytes(&self) -> impl DoubleEndedIterator<Item = u8> + 'a {
+        self.pieces.clone().into_iter().flat_map(|piece| piece.bytes())
+    }
+
+    /// Returns an iterator over the `char`s of the view.
+    #[inline]
+    pub fn chars(&self) -> impl DoubleEndedIterator<Item = char> + 'a {
+        self.pieces.clone().into_iter().flat_map(|piece| piece.chars())
+    }
+
+    /// Returns an iterator over the chunks of the view.
+    #[inline]
+    pub fn chunks(&self) -> impl DoubleEndedIterator<Item = &'a str> + 'a {
+        self.pieces.clone().into_iter().flat_map(|piece| piece.chunks())
+    }
+
+    /// Returns `true` if the view is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    /// Returns the number of lines in the view, counted like in
+    /// [`Rope::line_len()`](crate::Rope::line_len()).
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn line_len(&self) -> usize {
+        let Some(&(_, line_breaks)) = self.ends.last() else { return 0 };
+
+        let last = self.pieces.last().unwrap();
+
+        let ends_with_line_break =
+            last.chars().next_back().map_or(false, is_line_break);
+
+        line_breaks + (!ends_with_line_break) as usize
+    }
+
+    /// Returns the line offset of the given byte offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())).
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn line_of_byte(&self, byte_offset: usize) -> usize {
+        if byte_offset > self.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
+        }
+
+        if byte_offset == self.byte_len() {
+            return self
+                .ends
+                .last()
+                .map_or(0, |&(_, line_breaks)| line_breaks);
+        }
+
+        let (piece_idx, byte_offset) = self.piece_of_byte(byte_offset);
+
+        let (_, lines_before) = self.start_of_piece(piece_idx);
+
+        lines_before + self.pieces[piece_idx].line_of_byte(byte_offset)
+    }
+
+    /// Creates a new, empty `MultiRope`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the index of the piece containing the byte at the given index,
+    /// together with the index of that byte in the piece.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte index is out of bounds (i.e. greater than or equal
+    /// to [`byte_len()`](Self::byte_len())).
+    #[track_caller]
+    #[inline]
+    pub fn piece_of_byte(&self, byte_index: usize) -> (usize, usize) {
+        if byte_index >= self.byte_len() {
+            panic::byte_index_out_of_bounds(byte_index, self.byte_len());
+        }
+
+        let piece_idx =
+            self.ends.partition_point(|&(byte_end, _)| byte_end <= byte_index);
+
+        let (byte_start, _) = self.start_of_piece(piece_idx);
+
+        (piece_idx, byte_index - byte_start)
+    }
+
+    /// Returns the pieces of the view.
+    #[inline]
+    pub fn pieces(&self) -> &[RopeSlice<'a>] {
+        &self.pieces
+    }
+
+    /// Appends a piece at the end of the view. Empty pieces are ignored.
+    #[inline]
+    pub fn push<P>(&mut self, piece: P)
+    where
+        P: Into<RopeSlice<'a>>,
+    {
+        let piece = piece.into();
+
+        if piece.is_empty() {
+            return;
+        }
+
+        let (byte_end, line_breaks) =
+            self.ends.last().copied().unwrap_or_default();
+
+        self.ends.push((
+            byte_end + piece.byte_len(),
+            line_breaks + line_breaks_in(&piece),
+        ));
+
+        self.pieces.push(piece);
+    }
+
+    /// Returns the byte offset and the number of line breaks at the start of
+    /// the piece at the given index.
+    #[inline]
+    fn start_of_piece(&self, piece_idx: usize) -> (usize, usize) {
+        piece_idx
+            .checked_sub(1)
+            .map_or((0, 0), |previous_idx| self.ends[previous_idx])
+    }
+}
+
+/// Returns the number of line breaks in the piece, or zero if the
+/// `line-metric` feature is disabled.
+#[inline]
+fn line_breaks_in(piece: &RopeSlice<'_>) -> usize {
+    #[cfg(feature = "line-metric")]
+    {
+        piece.line_of_byte(piece.byte_len())
+    }
+
+    #[cfg(not(feature = "line-metric"))]
+    {
+        let _ = piece;
+        0
+    }
+}
+
+impl<'a, P: Into<RopeSlice<'a>>> FromIterator<P> for MultiRope<'a> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = P>>(iter: I) -> Self {
+        let mut multi_rope = MultiRope::new();
+        multi_rope.extend(iter);
+        multi_rope
+    }
+}
+
+impl<'a, P: Into<RopeSlice<'a>>> Extend<P> for MultiRope<'a> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = P>>(&mut self, iter: I) {
+        for piece in iter {
+            self.push(piece);
+        }
+    }
+}
+
+impl core::fmt::Debug for MultiRope<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("MultiRope(")?;
+        f.debug_list().entries(self.pieces.iter()).finish()?;
+        f.write_str(")")
+    }
+}
+
+impl core::fmt::Display for MultiRope<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::cmp::PartialEq<str> for MultiRope<'_> {
+    #[inline]
+    fn eq(&self, rhs: &str) -> bool {
+        if self.byte_len() != rhs.len() {
+            return false;
+        }
+
+        let mut rest = rhs.as_bytes();
+
+        self.chunks().all(|chunk| {
+            let (start, end) = rest.split_at(chunk.len());
+            rest = end;
+            start == chunk.as_bytes()
+        })
+    }
+}
+
+impl core::cmp::PartialEq<&str> for MultiRope<'_> {
+    #[inline]
+    fn eq(&self, rhs: &&str) -> bool {
+        self == *rhs
+    }
+}
+
+impl core::cmp::PartialEq<MultiRope<'_>> for MultiRope<'_> {
+    #[inline]
+    fn eq(&self, rhs: &MultiRope<'_>) -> bool {
+        self.byte_len() == rhs.byte_len() && self.bytes().eq(rhs.bytes())
+    }
+}
+
+impl core::cmp::Eq for MultiRope<'_> {}
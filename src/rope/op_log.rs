@@ -0,0 +1,371 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::{Range, RangeBounds};
+
+use super::utils::panic_messages as panic;
+use super::Rope;
+use crate::range_bounds_to_start_end;
+
+/// The identifier of a replica taking part in an editing session.
+///
+/// Every replica editing the same document should be given a different id.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct ReplicaId(pub u64);
+
+/// The globally unique identifier of an [`Op`], made of the id of the replica
+/// that generated it and of a sequence number local to that replica.
+///
+/// Sequence numbers start at zero and are incremented by one every time the
+/// replica performs an edit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OpId {
+    /// The replica that generated the operation.
+    pub replica: ReplicaId,
+
+    /// The position of the operation in the replica's sequence of edits.
+    pub seq: u64,
+}
+
+/// A single edit performed by a replica, replacing the text in a byte range
+/// with some other text.
+///
+/// The byte range is relative to the document as it was right before the
+/// operation was applied.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Op {
+    id: OpId,
+    deleted: Range<usize>,
+    inserted: String,
+}
+
+impl Op {
+    /// Applies the operation to the given `Rope` without checking that its
+    /// range is valid.
+    #[inline]
+    fn apply_unchecked(&self, rope: &mut Rope) {
+        rope.replace(self.deleted.clone(), &self.inserted);
+    }
+
+    /// Returns the byte range that was deleted by the operation.
+    #[inline]
+    pub fn deleted(&self) -> Range<usize> {
+        self.deleted.clone()
+    }
+
+    /// Returns the id of the operation.
+    #[inline]
+    pub fn id(&self) -> OpId {
+        self.id
+    }
+
+    /// Returns the text that was inserted by the operation at the start of
+    /// the [`deleted()`](Self::deleted()) range.
+    #[inline]
+    pub fn inserted(&self) -> &str {
+        &self.inserted
+    }
+
+    /// Creates a new operation.
+    ///
+    /// This is only useful when receiving operations from a source other than
+    /// a [`Replica`], e.g. after deserializing them from the network.
+    #[inline]
+    pub fn new<T>(id: OpId, deleted: Range<usize>, inserted: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self { id, deleted, inserted: inserted.into() }
+    }
+}
+
+/// The error returned by [`Replica::apply()`] when a remote operation can't
+/// be applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ApplyError {
+    /// The operation was generated by the same replica it's being applied
+    /// to.
+    OwnOp(OpId),
+
+    /// The operation has already been applied.
+    Duplicate(OpId),
+
+    /// One or more operations from the same replica that come before this
+    /// one haven't been applied yet.
+    Missing {
+        /// The operation that couldn't be applied.
+        op: OpId,
+
+        /// The sequence number that was expected.
+        expected_seq: u64,
+    },
+
+    /// The operation's range is out of bounds or doesn't lie on code point
+    /// boundaries of the current document.
+    InvalidRange(OpId),
+}
+
+impl core::fmt::Display for ApplyError {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::OwnOp(id) => write!(
+                f,
+                "operation {} of replica {} was generated locally",
+                id.seq, id.replica.0
+            ),
+            Self::Duplicate(id) => write!(
+                f,
+                "operation {} of replica {} has already been applied",
+                id.seq, id.replica.0
+            ),
+            Self::Missing { op, expected_seq } => write!(
+                f,
+                "operation {} of replica {} was received before operation {}",
+                op.seq, op.replica.0, expected_seq
+            ),
+            Self::InvalidRange(id) => write!(
+                f,
+                "operation {} of replica {} has an invalid range",
+                id.seq, id.replica.0
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ApplyError {}
+
+/// A [`Rope`] that records every edit performed on it as an [`Op`].
+///
+/// Each operation is tagged with the [`ReplicaId`] of the replica that
+/// performed it and with a sequence number, producing a canonical stream of
+/// operations which can be exported with [`ops()`](Self::ops()) or
+/// [`ops_since()`](Self::ops_since()) and applied on another replica with
+/// [`apply()`](Self::apply()).
+///
+/// Remote operations are applied as-is, so replicas are expected to receive
+/// them in the same order in which they were recorded. Transforming
+/// concurrent edits is out of scope, but the operation stream is easy to feed
+/// into a CRDT library that does.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::op_log::{Replica, ReplicaId};
+/// #
+/// let mut alice = Replica::new(ReplicaId(1));
+/// let mut bob = Replica::new(ReplicaId(2));
+///
+/// alice.insert(0, "Hello");
+/// alice.insert(5, " World");
+///
+/// for op in alice.ops() {
+///     bob.apply(op).unwrap();
+/// }
+///
+/// assert_eq!(*bob, "Hello World");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Replica {
+    id: ReplicaId,
+    rope: Rope,
+    ops: Vec<Op>,
+
+    /// The number of operations applied from each replica, including this
+    /// one.
+    version: BTreeMap<ReplicaId, u64>,
+}
+
+impl Replica {
+    /// Applies an operation generated by another replica.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation was generated by this replica, if
+    /// it has already been applied, if an earlier operation from the same
+    /// replica hasn't been applied yet or if its range isn't valid in the
+    /// current document. The document is left untouched in all these cases.
+    #[inline]
+    pub fn apply(&mut self, op: &Op) -> Result<(), ApplyError> {
+        let id = op.id;
+
+        if id.replica == self.id {
+            return Err(ApplyError::OwnOp(id));
+        }
+
+        let expected_seq = self.version_of(id.replica);
+
+        if id.seq < expected_seq {
+            return Err(ApplyError::Duplicate(id));
+        } else if id.seq > expected_seq {
+            return Err(ApplyError::Missing { op: id, expected_seq });
+        }
+
+        let Range { start, end } = op.deleted;
+
+        if start > end
+            || end > self.rope.byte_len()
+            || !self.rope.is_char_boundary(start)
+            || !self.rope.is_char_boundary(end)
+        {
+            return Err(ApplyError::InvalidRange(id));
+        }
+
+        op.apply_unchecked(&mut self.rope);
+        self.version.insert(id.replica, expected_seq + 1);
+        self.ops.push(op.clone());
+
+        Ok(())
+    }
+
+    /// Deletes the contents of the document within the specified byte range,
+    /// recording the edit as an [`Op`].
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::delete()`].
+    #[track_caller]
+    #[inline]
+    pub fn delete<R>(&mut self, byte_range: R) -> OpId
+    where
+        R: RangeBounds<usize>,
+    {
+        self.replace(byte_range, "")
+    }
+
+    /// Creates a new replica editing the given document.
+    ///
+    /// Every replica taking part in the same editing session should start
+    /// from the same document.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rope` has a [`limit()`](Rope::limit()).
+    #[track_caller]
+    #[inline]
+    pub fn from_rope(id: ReplicaId, rope: Rope) -> Self {
+        if rope.limit().is_some() {
+            panic::rope_has_limit("Replica");
+        }
+
+        Self { id, rope, ops: Vec::new(), version: BTreeMap::new() }
+    }
+
+    /// Returns the id of this replica.
+    #[inline]
+    pub fn id(&self) -> ReplicaId {
+        self.id
+    }
+
+    /// Inserts `text` at the given byte offset, recording the edit as an
+    /// [`Op`].
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::insert()`].
+    #[track_caller]
+    #[inline]
+    pub fn insert<T>(&mut self, byte_offset: usize, text: T) -> OpId
+    where
+        T: AsRef<str>,
+    {
+        self.replace(byte_offset..byte_offset, text)
+    }
+
+    /// Consumes the replica, returning the document it was editing.
+    #[inline]
+    pub fn into_rope(self) -> Rope {
+        self.rope
+    }
+
+    /// Creates a new replica editing an initially empty document.
+    #[inline]
+    pub fn new(id: ReplicaId) -> Self {
+        Self::from_rope(id, Rope::new())
+    }
+
+    /// Returns all the operations applied to the document so far, both local
+    /// and remote, in the order in which they were applied.
+    #[inline]
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// Returns the operations applied to the document after the first
+    /// `len` ones.
+    ///
+    /// This can be used to incrementally export the operation stream by
+    /// remembering the length of [`ops()`](Self::ops()) at the time of the
+    /// last export.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than the number of operations recorded so
+    /// far.
+    #[track_caller]
+    #[inline]
+    pub fn ops_since(&self, len: usize) -> &[Op] {
+        assert!(
+            len <= self.ops.len(),
+            "op count out of bounds: the count is {len} but only {} \
+             operations have been recorded",
+            self.ops.len()
+        );
+
+        &self.ops[len..]
+    }
+
+    /// Replaces the contents of the document within the specified byte range
+    /// with the given text, recording the edit as an [`Op`].
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`Rope::replace()`].
+    #[track_caller]
+    #[inline]
+    pub fn replace<R, T>(&mut self, byte_range: R, text: T) -> OpId
+    where
+        R: RangeBounds<usize>,
+        T: AsRef<str>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.rope.byte_len());
+
+        let text = text.as_ref();
+
+        self.rope.replace(start..end, text);
+
+        let seq = self.version_of(self.id);
+        self.version.insert(self.id, seq + 1);
+
+        let id = OpId { replica: self.id, seq };
+        self.ops.push(Op::new(id, start..end, text));
+        id
+    }
+
+    /// Returns the document being edited.
+    #[inline]
+    pub fn rope(&self) -> &Rope {
+        &self.rope
+    }
+
+    /// Returns the number of operations from the given replica that have
+    /// been applied to the document, which is also the sequence number of
+    /// the next operation expected from it.
+    #[inline]
+    pub fn version_of(&self, replica: ReplicaId) -> u64 {
+        self.version.get(&replica).copied().unwrap_or(0)
+    }
+}
+
+impl core::ops::Deref for Replica {
+    type Target = Rope;
+
+    #[inline]
+    fn deref(&self) -> &Rope {
+        &self.rope
+    }
+}
@@ -0,0 +1,336 @@
+//! This module contains the [`SpillFile`] used by
+//! [`Rope::page_out()`](super::Rope::page_out()).
+
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::ops::Range;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+
+use memmap2::{Mmap, MmapOptions};
+
+use super::chunk_bytes::{ByteSource, StableBytes};
+use super::gap_buffer::GapBuffer;
+use super::EditInfo;
+use crate::tree::{self, RefCount};
+
+type NodeRef<'a, const ARITY: usize, const MAX_BYTES: usize, C> =
+    tree::NodeRef<'a, ARITY, GapBuffer<MAX_BYTES>, C>;
+
+/// The number of recent edits whose chunks
+/// [`Rope::page_out()`](crate::Rope::page_out()) keeps in memory.
+const RECENT_EDITS: usize = 16;
+
+/// A file the chunks of a [`Rope`](crate::Rope) are paged out to.
+///
+/// The text of the chunks is written to the file and then memory-mapped, so
+/// that it's the operating system that evicts the least recently used pages
+/// from memory and reads them back in when they're accessed again. The tree
+/// of the `Rope` and its metrics stay in memory, so navigating the `Rope` is
+/// still logarithmic in its size.
+///
+/// The file is divided in slots as long as the maximum length of a chunk (2
+/// KiB by default), each one holding the text of a single chunk. A slot is
+/// freed as soon as its chunk is dropped, e.g. because it's been edited or
+/// because all the `Rope`s containing it have been dropped, and it's reused
+/// by the next [`page_out()`](crate::Rope::page_out()), while the free slots
+/// at the end of the file are truncated away. Right after a `page_out()` the
+/// file is therefore never larger than a slot times the number of chunks
+/// paged out to it that are still alive, plus the ones that were freed since
+/// the previous `page_out()`.
+///
+/// The length of the slots is set by the first `Rope` paged out to the file.
+/// Paging out a `Rope` whose chunks have a different maximum length fails
+/// until all the chunks written to the file have been dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use crop::{paging::SpillFile, Rope};
+/// #
+/// let file = std::fs::File::options()
+///     .read(true)
+///     .write(true)
+///     .create(true)
+///     .open("rope.spill")
+///     .unwrap();
+///
+/// // SAFETY: nothing else modifies the file while it's in use.
+/// let mut spill = unsafe { SpillFile::new(file).unwrap() };
+///
+/// let mut r = Rope::from("Hello, world!\n".repeat(1 << 20));
+///
+/// r.page_out(&mut spill).unwrap();
+///
+/// assert_eq!(r.line(1000), "Hello, world!");
+/// ```
+pub struct SpillFile {
+    file: File,
+
+    /// The length of every slot, i.e. the maximum length of the chunks of
+    /// the `Rope`s paged out to the file.
+    slot_len: usize,
+
+    /// The chunk stored in every slot of the file, which is free if it can't
+    /// be upgraded.
+    slots: Vec<Weak<SpilledChunk>>,
+}
+
+impl core::fmt::Debug for SpillFile {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("SpillFile")
+            .field("byte_len", &self.byte_len())
+            .finish()
+    }
+}
+
+impl SpillFile {
+    /// Returns the size of the file in bytes.
+    #[inline]
+    pub fn byte_len(&self) -> u64 {
+        (self.slots.len() * self.slot_len) as u64
+    }
+
+    /// Returns the indices of the free slots in descending order, after
+    /// truncating the ones at the end of the file.
+    #[inline]
+    fn free_slots(&mut self) -> io::Result<Vec<usize>> {
+        let len = self.slots.len();
+
+        while self.slots.last().map_or(false, |slot| slot.strong_count() == 0)
+        {
+            self.slots.pop();
+        }
+
+        if self.slots.len() < len {
+            self.file.set_len(self.byte_len())?;
+        }
+
+        Ok((0..self.slots.len())
+            .rev()
+            .filter(|&idx| self.slots[idx].strong_count() == 0)
+            .collect())
+    }
+
+    /// Creates a new `SpillFile`, truncating `file`.
+    ///
+    /// The file has to be opened for both reading and writing.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified by anything else, including other
+    /// processes, for as long as the `SpillFile` or any `Rope` paged out to it
+    /// is alive. Doing so is undefined behavior, just like for any other
+    /// memory-mapped file.
+    #[inline]
+    pub unsafe fn new(file: File) -> io::Result<Self> {
+        file.set_len(0)?;
+        Ok(Self { file, slot_len: 0, slots: Vec::new() })
+    }
+
+    /// Writes the text of the owned, non-empty leaves under `root` that
+    /// don't contain any of the given byte offsets to the free slots of the
+    /// file, and maps it.
+    ///
+    /// Returns the indices of those leaves, sorted, together with the chunks
+    /// borrowing their text from the mapping, and the number of bytes of
+    /// text written.
+    #[inline]
+    pub(super) fn spill<
+        C: RefCount,
+        const ARITY: usize,
+        const MAX_BYTES: usize,
+    >(
+        &mut self,
+        root: NodeRef<'_, ARITY, MAX_BYTES, C>,
+        hot_offsets: &[usize],
+    ) -> io::Result<(Vec<usize>, Vec<GapBuffer<MAX_BYTES>>, usize)> {
+        fn collect<'a, const ARITY: usize, const MAX_BYTES: usize, C>(
+            node: NodeRef<'a, ARITY, MAX_BYTES, C>,
+            leaves: &mut Vec<&'a GapBuffer<MAX_BYTES>>,
+        ) where
+            C: RefCount,
+        {
+            match node.leaf() {
+                Some(leaf) => leaves.push(leaf),
+                None => node.children().for_each(|c| collect(c, leaves)),
+            }
+        }
+
+        let mut free_slots = self.free_slots()?;
+
+        if self.slots.is_empty() {
+            self.slot_len = MAX_BYTES;
+        } else if self.slot_len != MAX_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the chunks of the rope don't fit the slots of the spill file",
+            ));
+        }
+
+        let slot_len = self.slot_len;
+
+        let mut leaves = Vec::new();
+        collect(root, &mut leaves);
+
+        // The index of every leaf to spill, and the slot it's written to.
+        let mut spilled = Vec::<(usize, usize)>::new();
+
+        let mut written = 0;
+
+        {
+            let mut writer = BufWriter::new(&mut self.file);
+
+            // The slot the writer is positioned at, if any.
+            let mut next_slot = None;
+
+            let mut leaf_start = 0;
+
+            for (leaf_idx, leaf) in leaves.iter().enumerate() {
+                let leaf_range = leaf_start..=leaf_start + leaf.len();
+
+                leaf_start += leaf.len();
+
+                if leaf.is_mapped()
+                    || leaf.is_empty()
+                    || hot_offsets.iter().any(|off| leaf_range.contains(off))
+                {
+                    continue;
+                }
+
+                let slot_idx = free_slots.pop().unwrap_or_else(|| {
+                    self.slots.push(Weak::new());
+                    self.slots.len() - 1
+                });
+
+                // The slot is either past the end of the file or free, i.e.
+                // its chunk has been dropped, so nothing is borrowing the
+                // text stored in it even if a mapping of it is still alive.
+                if next_slot != Some(slot_idx) {
+                    let pos = (slot_idx * slot_len) as u64;
+                    writer.seek(SeekFrom::Start(pos))?;
+                }
+
+                writer.write_all(leaf.left_chunk().as_bytes())?;
+                writer.write_all(leaf.right_chunk().as_bytes())?;
+
+                // Pad the slot so that the next one can be written without
+                // seeking.
+                let padding = (slot_len - leaf.len()) as u64;
+                io::copy(&mut io::repeat(0).take(padding), &mut writer)?;
+
+                next_slot = Some(slot_idx + 1);
+
+                spilled.push((leaf_idx, slot_idx));
+                written += leaf.len();
+            }
+
+            writer.flush()?;
+        }
+
+        if spilled.is_empty() {
+            return Ok((Vec::new(), Vec::new(), 0));
+        }
+
+        let first_slot = spilled.iter().map(|&(_, slot)| slot).min().unwrap();
+        let last_slot = spilled.iter().map(|&(_, slot)| slot).max().unwrap();
+
+        // SAFETY: the slots we just wrote are only rewritten after the chunks
+        // borrowing them have been dropped, and the caller of
+        // `SpillFile::new()` guaranteed that nothing else modifies the file.
+        let mmap = unsafe {
+            MmapOptions::new()
+                .offset((first_slot * slot_len) as u64)
+                .len((last_slot + 1 - first_slot) * slot_len)
+                .map(&self.file)?
+        };
+
+        let mmap = Arc::new(mmap);
+
+        let (leaf_indices, chunks) = spilled
+            .into_iter()
+            .map(|(leaf_idx, slot_idx)| {
+                let leaf = leaves[leaf_idx];
+
+                let start = (slot_idx - first_slot) * slot_len;
+
+                let chunk = Arc::new(SpilledChunk {
+                    mmap: Arc::clone(&mmap),
+                    range: start..start + leaf.len(),
+                });
+
+                self.slots[slot_idx] = Arc::downgrade(&chunk);
+
+                let source: ByteSource = chunk;
+
+                // SAFETY: the slot contains the text of `leaf`, which is
+                // valid UTF-8.
+                let text = unsafe {
+                    core::str::from_utf8_unchecked((*source).as_ref())
+                };
+
+                (leaf_idx, GapBuffer::mapped(&source, text))
+            })
+            .unzip();
+
+        Ok((leaf_indices, chunks, written))
+    }
+}
+
+/// The text of a chunk stored in a slot of a [`SpillFile`].
+///
+/// The `SpillFile` keeps a weak reference to it, so that it can reuse the
+/// slot once it's dropped.
+struct SpilledChunk {
+    mmap: Arc<Mmap>,
+    range: Range<usize>,
+}
+
+impl AsRef<[u8]> for SpilledChunk {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.mmap[self.range.clone()]
+    }
+}
+
+// SAFETY: the mapping is never moved or resized, and the `SpillFile` only
+// rewrites the slot after this has been dropped.
+unsafe impl StableBytes for SpilledChunk {}
+
+/// The byte offsets of the last [`RECENT_EDITS`] edits of a [`Rope`], kept up
+/// to date with the edits made after them.
+///
+/// The chunks containing them are considered hot, and
+/// [`Rope::page_out()`](crate::Rope::page_out()) keeps them in memory.
+#[derive(Clone, Default)]
+pub(super) struct RecentEdits {
+    offsets: Vec<usize>,
+}
+
+impl RecentEdits {
+    #[inline]
+    pub(super) fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// Records a new edit, moving the offsets after it.
+    #[inline]
+    pub(super) fn record(&mut self, edit: &EditInfo) {
+        let Range { start, end } = edit.byte_range;
+
+        for offset in &mut self.offsets {
+            if *offset >= end {
+                *offset = *offset - (end - start) + edit.inserted_len;
+            } else if *offset > start {
+                *offset = start;
+            }
+        }
+
+        if self.offsets.len() == RECENT_EDITS {
+            self.offsets.remove(0);
+        }
+
+        self.offsets.push(start);
+    }
+}
@@ -0,0 +1,429 @@
+//! This module contains the functions used to build the tree of a [`Rope`]
+//! on multiple threads, i.e. by
+//! [`Rope::from_str_parallel()`](super::Rope::from_str_parallel()) and
+//! [`Rope::from_reader_parallel()`](super::Rope::from_reader_parallel()),
+//! and to search and diff it, i.e. by
+//! [`RopeSlice::par_find_all()`](super::RopeSlice::par_find_all()) and
+//! [`Rope::par_changes_since()`](super::Rope::par_changes_since()).
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+use std::io::{self, Read};
+
+use rayon::prelude::*;
+
+use super::metrics::ByteMetric;
+use super::rope::RopeChunk;
+use super::snapshot;
+use super::utils::{ends_with_line_break, from_utf8, split_adjusted};
+use super::Rope;
+use super::RopeSlice;
+use crate::tree::Tree;
+
+type RopeTree = Tree<{ Rope::arity() }, RopeChunk>;
+
+/// The byte length of the segments of the text whose subtrees are built
+/// independently of each other, which is large enough to amortize the cost
+/// of joining them.
+pub(super) const SEGMENT_LEN: usize = RopeChunk::max_bytes() * 512;
+
+/// The number of bytes [`tree_from_reader()`] reads before building their
+/// subtrees.
+const BLOCK_LEN: usize = SEGMENT_LEN * 64;
+
+/// Builds the tree of `s` by splitting it into segments, building the subtree
+/// of each one on the rayon thread pool and then joining them.
+#[inline]
+pub(super) fn tree_from_str(s: &str) -> RopeTree {
+    let mut segments = Vec::with_capacity(s.len() / SEGMENT_LEN + 1);
+
+    let mut rest = s;
+
+    while !rest.is_empty() {
+        let (segment, after) = split_adjusted::<false>(rest, SEGMENT_LEN);
+        segments.push(segment);
+        rest = after;
+    }
+
+    let subtrees =
+        segments.into_par_iter().map(tree_from_segment).collect::<Vec<_>>();
+
+    join(subtrees)
+}
+
+/// Like [`tree_from_str()`], but also validates the bytes as UTF-8 on the
+/// thread pool.
+#[inline]
+pub(super) fn tree_from_utf8(
+    bytes: &[u8],
+) -> Result<RopeTree, core::str::Utf8Error> {
+    let mut segments = Vec::with_capacity(bytes.len() / SEGMENT_LEN + 1);
+
+    let mut rest = bytes;
+
+    while !rest.is_empty() {
+        let end = segment_end(rest, SEGMENT_LEN);
+        let (segment, after) = rest.split_at(end);
+        segments.push(segment);
+        rest = after;
+    }
+
+    let subtrees = segments
+        .par_iter()
+        .map(|segment| from_utf8(segment).map(tree_from_segment))
+        .collect::<Vec<_>>();
+
+    let mut trees = Vec::with_capacity(subtrees.len());
+
+    let mut end = 0;
+
+    for (segment, subtree) in segments.iter().zip(subtrees) {
+        end += segment.len();
+
+        match subtree {
+            Ok(tree) => trees.push(tree),
+
+            // Validate everything up to the end of the invalid segment again
+            // to get an error with the offset in the whole input. This only
+            // happens once so it doesn't matter that it's slower.
+            Err(_) => {
+                return Err(core::str::from_utf8(&bytes[..end])
+                    .expect_err("the segment is invalid"))
+            },
+        }
+    }
+
+    Ok(join(trees))
+}
+
+/// Reads all of `reader` and builds the tree of its contents, returning it
+/// together with whether the text ends with a newline.
+///
+/// The input is read in blocks, and each block is validated and turned into
+/// a subtree on the thread pool before reading the next one, so the whole
+/// input is never held in memory twice.
+#[inline]
+pub(super) fn tree_from_reader<R: Read>(
+    mut reader: R,
+) -> io::Result<(RopeTree, bool)> {
+    let mut tree = RopeTree::default();
+
+    let mut block = Vec::with_capacity(BLOCK_LEN);
+
+    let mut has_trailing_newline = false;
+
+    // The number of bytes read before the current block.
+    let mut offset = 0;
+
+    loop {
+        let to_read = BLOCK_LEN - block.len();
+
+        (&mut reader).take(to_read as u64).read_to_end(&mut block)?;
+
+        let is_last = block.len() < BLOCK_LEN;
+
+        // Leave the last code point for the next block unless this is the
+        // last one, since it could be incomplete.
+        let end = if is_last { block.len() } else { last_char_start(&block) };
+
+        let subtree = tree_from_utf8(&block[..end]).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                alloc::format!(
+                    "stream did not contain valid UTF-8 at byte offset {}",
+                    offset + err.valid_up_to()
+                ),
+            )
+        })?;
+
+        if end > 0 {
+            let last_char = &block[last_char_start(&block[..end])..end];
+
+            has_trailing_newline =
+                from_utf8(last_char).map_or(false, ends_with_line_break);
+        }
+
+        tree.append(subtree);
+
+        if is_last {
+            return Ok((tree, has_trailing_newline));
+        }
+
+        block.drain(..end);
+
+        offset += end;
+    }
+}
+
+/// Joins the subtrees of consecutive segments into a single tree.
+#[inline]
+fn join(trees: Vec<RopeTree>) -> RopeTree {
+    trees.into_iter().fold(RopeTree::default(), |mut tree, subtree| {
+        tree.append(subtree);
+        tree
+    })
+}
+
+/// Builds the subtree of a single segment.
+#[inline]
+fn tree_from_segment(segment: &str) -> RopeTree {
+    Tree::from_leaves(RopeChunk::segmenter(segment).map(RopeChunk::from))
+}
+
+/// Returns the offset of the byte starting the last code point of `bytes`,
+/// or `bytes.len()` if they don't end with a valid code point prefix.
+#[inline]
+fn last_char_start(bytes: &[u8]) -> usize {
+    (bytes.len().saturating_sub(4)..bytes.len())
+        .rev()
+        .find(|&idx| !is_continuation_byte(bytes[idx]))
+        .unwrap_or(bytes.len())
+}
+
+/// Returns the end of the segment starting at the beginning of `bytes`,
+/// i.e. the start of the first code point after `candidate`.
+#[inline]
+fn segment_end(bytes: &[u8], candidate: usize) -> usize {
+    if candidate >= bytes.len() {
+        return bytes.len();
+    }
+
+    // A valid code point has at most 3 continuation bytes. If there are more
+    // the input is invalid anyway, so it doesn't matter where it's cut.
+    (candidate..=bytes.len().min(candidate + 3))
+        .find(|&idx| idx == bytes.len() || !is_continuation_byte(bytes[idx]))
+        .unwrap_or(candidate)
+}
+
+#[inline]
+fn is_continuation_byte(byte: u8) -> bool {
+    byte & 0xc0 == 0x80
+}
+
+/// Returns the offset of a chunk boundary close to the middle of `slice`,
+/// which can be used to split it in two halves to be processed in parallel,
+/// or `None` if it's not worth splitting.
+#[inline]
+pub(super) fn split_point(slice: &RopeSlice<'_>) -> Option<usize> {
+    if slice.tree_slice.leaf_count() < 2 {
+        return None;
+    }
+
+    let byte_len = slice.byte_len();
+
+    let (leaf, ByteMetric(offset)) =
+        slice.tree_slice.leaf_at_measure(ByteMetric(byte_len / 2));
+
+    let split_at = if offset > 0 { offset } else { leaf.len() };
+
+    (split_at > 0 && split_at < byte_len).then_some(split_at)
+}
+
+/// Like [`snapshot::changes_since()`], but narrows down the ranges found by
+/// pruning the shared subtrees on the thread pool.
+///
+/// The shared subtrees act as synchronization points between the two trees,
+/// so the ranges between them can be compared independently of each other.
+#[inline]
+pub(super) fn changes_since(new: &Rope, old: &Rope) -> Vec<Range<usize>> {
+    let changes = new
+        .tree
+        .diff_shared(&old.tree)
+        .into_par_iter()
+        .filter_map(|(old_range, new_range)| {
+            snapshot::narrow(new, old, old_range, new_range)
+        })
+        .collect::<Vec<_>>();
+
+    snapshot::merge_adjacent(changes)
+}
+
+/// Returns the byte offsets of the non-overlapping occurrences of `pattern`
+/// in `slice`, like [`str::match_indices()`].
+///
+/// The slice is split in subslices which are searched on the thread pool for
+/// all the occurrences of the pattern, including overlapping ones, and the
+/// ones that overlap a previous one are then filtered out.
+#[inline]
+pub(super) fn find_all(slice: RopeSlice<'_>, pattern: &str) -> Vec<usize> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches = find_all_in(slice, 0, pattern);
+
+    matches.sort_unstable();
+
+    let mut last_end = 0;
+
+    matches.retain(|&start| {
+        let keep = start >= last_end;
+        if keep {
+            last_end = start + pattern.len();
+        }
+        keep
+    });
+
+    matches
+}
+
+/// Returns the byte offsets of all the occurrences of `pattern` in `slice`,
+/// which starts at `offset`, in no particular order.
+///
+/// An occurrence is found by the call searching the smallest subslice which
+/// contains it, either in one of its chunks or around the point where the
+/// subslice was split or where two of its chunks meet.
+#[inline]
+fn find_all_in(
+    slice: RopeSlice<'_>,
+    offset: usize,
+    pattern: &str,
+) -> Vec<usize> {
+    let split_at = if slice.byte_len() > SEGMENT_LEN {
+        split_point(&slice)
+    } else {
+        None
+    };
+
+    let Some(split_at) = split_at else {
+        return find_all_sequential(slice, offset, pattern);
+    };
+
+    let (mut left, right) = rayon::join(
+        || find_all_in(slice.byte_slice(..split_at), offset, pattern),
+        || {
+            find_all_in(
+                slice.byte_slice(split_at..),
+                offset + split_at,
+                pattern,
+            )
+        },
+    );
+
+    let mut start = split_at.saturating_sub(pattern.len() - 1);
+
+    while !slice.is_char_boundary(start) {
+        start -= 1;
+    }
+
+    let mut end = (split_at + pattern.len() - 1).min(slice.byte_len());
+
+    while !slice.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let around_split =
+        slice.byte_slice(start..end).chunks().collect::<String>();
+
+    find_across(
+        &around_split,
+        offset + start,
+        offset + split_at,
+        pattern,
+        &mut left,
+    );
+
+    left.extend(right);
+
+    left
+}
+
+/// Like [`find_all_in()`], but searches the chunks of `slice` one after the
+/// other on the current thread.
+#[inline]
+fn find_all_sequential(
+    slice: RopeSlice<'_>,
+    mut offset: usize,
+    pattern: &str,
+) -> Vec<usize> {
+    let mut matches = Vec::new();
+
+    // The text before the current chunk which an occurrence crossing the
+    // start of the chunk could start in.
+    let mut tail = String::new();
+
+    for chunk in slice.chunks() {
+        if !tail.is_empty() {
+            let mut head_len = (pattern.len() - 1).min(chunk.len());
+
+            while !chunk.is_char_boundary(head_len) {
+                head_len += 1;
+            }
+
+            let tail_len = tail.len();
+
+            tail.push_str(&chunk[..head_len]);
+
+            find_across(
+                &tail,
+                offset - tail_len,
+                offset,
+                pattern,
+                &mut matches,
+            );
+
+            tail.truncate(tail_len);
+        }
+
+        find_in(chunk, pattern, |start| matches.push(offset + start));
+
+        offset += chunk.len();
+
+        if chunk.len() >= pattern.len() - 1 {
+            tail.clear();
+            tail.push_str(&chunk[tail_start(chunk, pattern)..]);
+        } else {
+            tail.push_str(chunk);
+            tail.drain(..tail_start(&tail, pattern));
+        }
+    }
+
+    matches
+}
+
+/// Pushes the offsets of the occurrences of `pattern` in `text`, which starts
+/// at `offset`, that cross the given `seam`.
+#[inline]
+fn find_across(
+    text: &str,
+    offset: usize,
+    seam: usize,
+    pattern: &str,
+    matches: &mut Vec<usize>,
+) {
+    find_in(text, pattern, |start| {
+        let start = offset + start;
+        if start < seam && start + pattern.len() > seam {
+            matches.push(start);
+        }
+    });
+}
+
+/// Calls `f` with the offsets of all the occurrences of `pattern` in `text`,
+/// including the overlapping ones.
+#[inline]
+fn find_in(text: &str, pattern: &str, mut f: impl FnMut(usize)) {
+    let first_char_len = pattern.chars().next().map_or(1, char::len_utf8);
+
+    let mut from = 0;
+
+    while let Some(idx) = text[from..].find(pattern) {
+        f(from + idx);
+        from += idx + first_char_len;
+    }
+}
+
+/// Returns the start of the shortest suffix of `text` which starts on a code
+/// point boundary and is at least `pattern.len() - 1` bytes long, i.e. the
+/// part of `text` an occurrence crossing its end could start in.
+#[inline]
+fn tail_start(text: &str, pattern: &str) -> usize {
+    let mut start = text.len().saturating_sub(pattern.len() - 1);
+
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+
+    start
+}
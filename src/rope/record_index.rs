@@ -0,0 +1,246 @@
+use alloc::vec::Vec;
+use core::ops::RangeBounds;
+
+use super::rope::{DEFAULT_ARITY, DEFAULT_CHUNK_MAX_BYTES};
+use super::utils::panic_messages as panic;
+use super::{Rope, RopeSlice};
+use crate::range_bounds_to_start_end;
+use crate::tree::{AtomicCount, RefCount};
+
+/// An index of the records of a [`Rope`] delimited by a custom separator
+/// byte, created by [`Rope::record_index()`].
+///
+/// Records work like lines with the separator in place of the line feed:
+/// they don't include their separator, and a separator at the very end of
+/// the `Rope` terminates the last record instead of starting a new empty one.
+/// This makes it possible to work with e.g. `\0`-separated lists or with the
+/// `;`-separated statements of a script using the same API used for lines.
+///
+/// Unlike lines, records are not tracked by the chunk summaries of the
+/// `Rope`, since the separator is only known at runtime. The index is instead
+/// a snapshot of the offsets of the separators, built with a single `O(n)`
+/// pass over the chunks of the `Rope`, after which every query takes
+/// `O(log n)` time in the number of records. The index borrows the `Rope`, so
+/// a new one has to be built to query the records after an edit.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::Rope;
+/// #
+/// let r = Rope::from("foo\0bar\0baz\0");
+///
+/// let index = r.record_index(b'\0');
+///
+/// assert_eq!(index.record_len(), 3);
+/// assert_eq!(index.record(1), "bar");
+/// assert_eq!(index.byte_of_record(2), 8);
+/// assert_eq!(index.record_of_byte(5), 1);
+/// assert_eq!(index.record_slice(1..), "bar\0baz\0");
+/// ```
+pub struct RecordIndex<
+    'a,
+    C: RefCount = AtomicCount,
+    const ARITY: usize = DEFAULT_ARITY,
+    const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+> {
+    rope: &'a Rope<C, ARITY, MAX_BYTES>,
+
+    /// The separator delimiting the records.
+    separator: u8,
+
+    /// The byte offsets of all the separators of the `Rope`, in order.
+    separators: Vec<usize>,
+}
+
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    RecordIndex<'a, C, ARITY, MAX_BYTES>
+{
+    /// Returns the byte offset of the start of the given record.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the record offset is out of bounds (i.e. greater than
+    /// [`record_len()`](Self::record_len())).
+    #[track_caller]
+    #[inline]
+    pub fn byte_of_record(&self, record_offset: usize) -> usize {
+        if record_offset > self.record_len() {
+            panic::record_offset_out_of_bounds(
+                record_offset,
+                self.record_len(),
+            );
+        }
+
+        match record_offset.checked_sub(1) {
+            Some(idx) => self
+                .separators
+                .get(idx)
+                .map_or(self.rope.byte_len(), |&separator| separator + 1),
+            None => 0,
+        }
+    }
+
+    #[track_caller]
+    #[inline]
+    pub(super) fn new(
+        rope: &'a Rope<C, ARITY, MAX_BYTES>,
+        separator: u8,
+    ) -> Self {
+        assert!(
+            separator.is_ascii(),
+            "the record separator must be an ASCII byte, but it is \
+             {separator:#04x}"
+        );
+
+        let mut separators = Vec::new();
+
+        let mut chunk_offset = 0;
+
+        for chunk in rope.chunks() {
+            separators.extend(
+                chunk
+                    .bytes()
+                    .enumerate()
+                    .filter(|&(_, byte)| byte == separator)
+                    .map(|(idx, _)| chunk_offset + idx),
+            );
+
+            chunk_offset += chunk.len();
+        }
+
+        Self { rope, separator, separators }
+    }
+
+    /// Returns the record at the given index, without its separator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the record index is out of bounds (i.e. greater than or
+    /// equal to [`record_len()`](Self::record_len())).
+    #[track_caller]
+    #[inline]
+    pub fn record(
+        &self,
+        record_index: usize,
+    ) -> RopeSlice<'a, C, ARITY, MAX_BYTES> {
+        if record_index >= self.record_len() {
+            panic::record_index_out_of_bounds(record_index, self.record_len());
+        }
+
+        let start = self.byte_of_record(record_index);
+
+        let end = self
+            .separators
+            .get(record_index)
+            .copied()
+            .unwrap_or(self.rope.byte_len());
+
+        self.rope.byte_slice(start..end)
+    }
+
+    /// Returns the number of records in the `Rope`.
+    #[inline]
+    pub fn record_len(&self) -> usize {
+        let last_separator_end =
+            self.separators.last().map_or(0, |&separator| separator + 1);
+
+        self.separators.len()
+            + (self.rope.byte_len() > last_separator_end) as usize
+    }
+
+    /// Returns the record offset of the given byte offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than the
+    /// byte length of the `Rope`).
+    #[track_caller]
+    #[inline]
+    pub fn record_of_byte(&self, byte_offset: usize) -> usize {
+        if byte_offset > self.rope.byte_len() {
+            panic::byte_offset_out_of_bounds(
+                byte_offset,
+                self.rope.byte_len(),
+            );
+        }
+
+        self.separators.partition_point(|&separator| separator < byte_offset)
+    }
+
+    /// Returns a [`RopeSlice`] of the records in the given range, including
+    /// their separators.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start is greater than the end or if the end is out of
+    /// bounds (i.e. greater than [`record_len()`](Self::record_len())).
+    #[track_caller]
+    #[inline]
+    pub fn record_slice<R>(
+        &self,
+        record_range: R,
+    ) -> RopeSlice<'a, C, ARITY, MAX_BYTES>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(record_range, 0, self.record_len());
+
+        if start > end {
+            panic::record_start_after_end(start, end);
+        }
+
+        if end > self.record_len() {
+            panic::record_offset_out_of_bounds(end, self.record_len());
+        }
+
+        self.rope
+            .byte_slice(self.byte_of_record(start)..self.byte_of_record(end))
+    }
+
+    /// Returns an iterator over the records of the `Rope`, without their
+    /// separators.
+    #[inline]
+    pub fn records(
+        &self,
+    ) -> impl ExactSizeIterator<Item = RopeSlice<'a, C, ARITY, MAX_BYTES>> + '_
+    {
+        (0..self.record_len())
+            .map(move |record_index| self.record(record_index))
+    }
+
+    /// Returns the `Rope` the index is over.
+    #[inline]
+    pub fn rope(&self) -> &'a Rope<C, ARITY, MAX_BYTES> {
+        self.rope
+    }
+
+    /// Returns the separator delimiting the records.
+    #[inline]
+    pub fn separator(&self) -> u8 {
+        self.separator
+    }
+}
+
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> Clone
+    for RecordIndex<'_, C, ARITY, MAX_BYTES>
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            rope: self.rope,
+            separator: self.separator,
+            separators: self.separators.clone(),
+        }
+    }
+}
+
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> core::fmt::Debug
+    for RecordIndex<'_, C, ARITY, MAX_BYTES>
+{
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list().entries(self.records()).finish()
+    }
+}
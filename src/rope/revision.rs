@@ -0,0 +1,53 @@
+/// The state of a [`Rope`](crate::Rope) at some point in time, returned by
+/// [`Rope::revision()`](crate::Rope::revision()).
+///
+/// Every `Rope` takes a new id from a process-wide counter when it's created,
+/// and then counts its own edits, so two `Rope`s only have the same revision
+/// if one is a clone of the other that neither has modified since. A clone
+/// shares the id of the `Rope` it was cloned from until its first edit, when
+/// it takes a new id of its own.
+///
+/// Revisions are ordered first by id and then by edit count, so the
+/// revisions of the same `Rope` only ever increase.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Revision {
+    /// The id of the `Rope` that made this revision.
+    rope_id: u64,
+
+    /// The number of edits performed on the `Rope` and on the ones it was
+    /// cloned from since they were created.
+    edits: u64,
+}
+
+impl Revision {
+    /// Returns the first revision of a new `Rope`.
+    #[inline]
+    pub(super) fn new() -> Self {
+        Self { rope_id: next_rope_id(), edits: 0 }
+    }
+
+    /// Returns the revision following this one after an edit, which takes a
+    /// new id if `is_shared` is `true`, i.e. if this revision could also be
+    /// the one of another `Rope`.
+    #[inline]
+    pub(super) fn next(self, is_shared: bool) -> Self {
+        let rope_id = if is_shared { next_rope_id() } else { self.rope_id };
+        Self { rope_id, edits: self.edits + 1 }
+    }
+}
+
+// The counter is a `u64` on every target, so that it never wraps around.
+#[cfg(target_has_atomic = "64")]
+static LAST_ROPE_ID: core::sync::atomic::AtomicU64 =
+    core::sync::atomic::AtomicU64::new(0);
+
+#[cfg(not(target_has_atomic = "64"))]
+static LAST_ROPE_ID: portable_atomic::AtomicU64 =
+    portable_atomic::AtomicU64::new(0);
+
+/// Returns an id that has never been returned before.
+#[inline]
+fn next_rope_id() -> u64 {
+    use core::sync::atomic::Ordering;
+    LAST_ROPE_ID.fetch_add(1, Ordering::Relaxed) + 1
+}
@@ -0,0 +1,632 @@
+//! This module contains the rkyv `Archive`, `Serialize` and `Deserialize`
+//! impls of [`Rope`].
+//!
+//! A `Rope` is archived as the sequence of its chunks, each one together
+//! with the byte and line offsets at which it starts, so an archived `Rope`
+//! can be read in place, e.g. from a memory-mapped file, without having to
+//! rebuild the tree first.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Range, RangeBounds};
+
+use rkyv::bytecheck::{CheckBytes, Verify};
+use rkyv::munge::munge;
+use rkyv::rancor::{Fallible, Source};
+use rkyv::ser::{Allocator, Writer};
+use rkyv::string::{ArchivedString, StringResolver};
+use rkyv::vec::{ArchivedVec, VecResolver};
+use rkyv::{Archive, Archived, Deserialize, Place, Portable, Serialize};
+#[cfg(not(feature = "unicode-lines"))]
+use str_indices::lines_lf;
+
+use super::utils::{
+    debug_no_quotes,
+    ends_with_line_break,
+    is_line_break,
+    panic_messages as panic,
+};
+use super::{Rope, RopeBuilder};
+use crate::range_bounds_to_start_end;
+
+/// The archived form of a [`Rope`], created by serializing it with rkyv.
+///
+/// It can be accessed directly from the archive's bytes without being
+/// deserialized, and turned back into a `Rope` with
+/// [`to_rope()`](Self::to_rope()). Each of its chunks is archived together
+/// with the byte and line offsets at which it starts, so bytes, lines and
+/// byte ranges can be accessed in logarithmic time.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::{ArchivedRope, Rope};
+/// #
+/// let r = Rope::from("Hello\nworld!");
+///
+/// let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&r).unwrap();
+///
+/// let archived =
+///     rkyv::access::<ArchivedRope, rkyv::rancor::Error>(&bytes).unwrap();
+///
+/// assert_eq!(archived.byte_len(), 12);
+/// assert_eq!(archived.line(1), "world!");
+/// assert_eq!(archived.byte_slice(..5), "Hello");
+/// assert_eq!(archived.to_rope(), r);
+/// ```
+#[derive(Portable, CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck, verify)]
+#[repr(C)]
+pub struct ArchivedRope {
+    chunks: ArchivedVec<ArchivedChunk>,
+    byte_len: Archived<u64>,
+    line_breaks: Archived<u64>,
+}
+
+/// An archived chunk of a [`Rope`], together with the summary of the text
+/// before it.
+#[derive(Portable, CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck)]
+#[repr(C)]
+pub struct ArchivedChunk {
+    text: ArchivedString,
+    byte_offset: Archived<u64>,
+    line_offset: Archived<u64>,
+}
+
+impl ArchivedChunk {
+    #[inline]
+    fn byte_offset(&self) -> usize {
+        self.byte_offset.to_native() as usize
+    }
+
+    #[inline]
+    fn line_offset(&self) -> usize {
+        self.line_offset.to_native() as usize
+    }
+}
+
+impl ArchivedRope {
+    /// Returns the byte at `byte_index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte index is out of bounds (i.e. greater than or equal
+    /// to [`byte_len()`](Self::byte_len())).
+    #[inline]
+    pub fn byte(&self, byte_index: usize) -> u8 {
+        if byte_index >= self.byte_len() {
+            panic::byte_index_out_of_bounds(byte_index, self.byte_len());
+        }
+
+        let chunk = &self.chunks[self.chunk_of_byte(byte_index)];
+        chunk.text.as_bytes()[byte_index - chunk.byte_offset()]
+    }
+
+    /// Returns the byte length of the archived `Rope`.
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.byte_len.to_native() as usize
+    }
+
+    /// Returns the byte offset of the start of the given line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line offset is out of bounds (i.e. greater than
+    /// [`line_len()`](Self::line_len())).
+    #[inline]
+    pub fn byte_of_line(&self, line_offset: usize) -> usize {
+        if line_offset > self.line_len() {
+            panic::line_offset_out_of_bounds(line_offset, self.line_len());
+        }
+
+        if line_offset == 0 {
+            return 0;
+        }
+
+        if line_offset == self.line_len() {
+            return self.byte_len();
+        }
+
+        // The line starts after the line break which ends the previous one,
+        // which is in the last chunk starting before it.
+        let chunk = &self.chunks[self
+            .chunks
+            .partition_point(|chunk| chunk.line_offset() < line_offset)
+            - 1];
+
+        chunk.byte_offset()
+            + byte_of_line(&chunk.text, line_offset - chunk.line_offset())
+    }
+
+    /// Returns an [`ArchivedRopeSlice`] over the given byte range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start is greater than the end or if the end is out of
+    /// bounds (i.e. greater than [`byte_len()`](Self::byte_len())), or if
+    /// either of them is not a char boundary.
+    #[track_caller]
+    #[inline]
+    pub fn byte_slice<R>(&self, byte_range: R) -> ArchivedRopeSlice<'_>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.byte_len());
+
+        if start > end {
+            panic::byte_start_after_end(start, end);
+        }
+
+        if end > self.byte_len() {
+            panic::byte_offset_out_of_bounds(end, self.byte_len());
+        }
+
+        for byte_offset in [start, end] {
+            if byte_offset == self.byte_len() {
+                continue;
+            }
+
+            let chunk = &self.chunks[self.chunk_of_byte(byte_offset)];
+            let offset_in_chunk = byte_offset - chunk.byte_offset();
+
+            if !chunk.text.is_char_boundary(offset_in_chunk) {
+                panic::byte_offset_not_char_boundary(
+                    &chunk.text,
+                    offset_in_chunk,
+                );
+            }
+        }
+
+        ArchivedRopeSlice { rope: self, byte_range: start..end }
+    }
+
+    /// Returns the index of the chunk containing the given byte offset,
+    /// which has to be less than the byte length.
+    #[inline]
+    fn chunk_of_byte(&self, byte_offset: usize) -> usize {
+        debug_assert!(byte_offset < self.byte_len());
+
+        self.chunks.partition_point(|chunk| chunk.byte_offset() <= byte_offset)
+            - 1
+    }
+
+    /// Returns an iterator over the chunks of the archived `Rope`.
+    #[inline]
+    pub fn chunks(&self) -> impl DoubleEndedIterator<Item = &str> + '_ {
+        self.chunks.iter().map(|chunk| chunk.text.as_str())
+    }
+
+    /// Returns `true` if the archived `Rope` is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.byte_len() == 0
+    }
+
+    /// Returns an [`ArchivedRopeSlice`] over the line at the given index,
+    /// without its line break.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line index is out of bounds (i.e. greater than or equal
+    /// to [`line_len()`](Self::line_len())).
+    #[track_caller]
+    #[inline]
+    pub fn line(&self, line_index: usize) -> ArchivedRopeSlice<'_> {
+        if line_index >= self.line_len() {
+            panic::line_index_out_of_bounds(line_index, self.line_len());
+        }
+
+        let start = self.byte_of_line(line_index);
+
+        let mut end = self.byte_of_line(line_index + 1);
+
+        if end > start {
+            let last_chunk = &self.chunks[self.chunk_of_byte(end - 1)];
+            let last_char = last_chunk.text[..end - last_chunk.byte_offset()]
+                .chars()
+                .next_back()
+                .expect("the line is not empty");
+
+            if is_line_break(last_char) {
+                end -= last_char.len_utf8();
+
+                let is_crlf = last_char == '\n'
+                    && end > start
+                    && self.byte(end - 1) == b'\r';
+
+                if is_crlf {
+                    end -= 1;
+                }
+            }
+        }
+
+        ArchivedRopeSlice { rope: self, byte_range: start..end }
+    }
+
+    /// Returns the number of lines in the archived `Rope`. Like with a
+    /// `Rope`, a line break at the end of the text doesn't start a new line.
+    #[inline]
+    pub fn line_len(&self) -> usize {
+        let line_breaks = self.line_breaks.to_native() as usize;
+
+        let ends_with_newline = self
+            .chunks()
+            .next_back()
+            .map_or(true, ends_with_line_break);
+
+        line_breaks + (!ends_with_newline) as usize
+    }
+
+    /// Returns the index of the line containing the given byte offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())), or if it's not a char boundary.
+    #[track_caller]
+    #[inline]
+    pub fn line_of_byte(&self, byte_offset: usize) -> usize {
+        if byte_offset > self.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
+        }
+
+        if byte_offset == self.byte_len() {
+            return self.line_breaks.to_native() as usize;
+        }
+
+        let chunk = &self.chunks[self.chunk_of_byte(byte_offset)];
+        let offset_in_chunk = byte_offset - chunk.byte_offset();
+
+        if !chunk.text.is_char_boundary(offset_in_chunk) {
+            panic::byte_offset_not_char_boundary(&chunk.text, offset_in_chunk);
+        }
+
+        let line_breaks = count_line_breaks(&chunk.text[..offset_in_chunk]);
+
+        chunk.line_offset() + line_breaks
+    }
+
+    /// Builds a new `Rope` from the archived one.
+    #[inline]
+    pub fn to_rope(&self) -> Rope {
+        let mut builder = RopeBuilder::new();
+
+        for chunk in self.chunks() {
+            builder.append(chunk);
+        }
+
+        builder.build()
+    }
+}
+
+/// The error returned when validating an archived `Rope` whose chunks don't
+/// match the offsets stored next to them.
+#[derive(Debug)]
+struct InvalidOffsets;
+
+impl fmt::Display for InvalidOffsets {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(
+            "the offsets of the archived chunks don't match their text",
+        )
+    }
+}
+
+impl core::error::Error for InvalidOffsets {}
+
+// SAFETY: `verify()` only succeeds if the chunks are non-empty and the
+// offsets stored in the archive are the ones of their text, which is what
+// the methods of `ArchivedRope` rely on.
+unsafe impl<C> Verify<C> for ArchivedRope
+where
+    C: Fallible + ?Sized,
+    C::Error: Source,
+{
+    #[inline]
+    fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+        let mut byte_offset = 0;
+        let mut line_offset = 0;
+
+        for chunk in self.chunks.iter() {
+            if chunk.text.is_empty()
+                || chunk.byte_offset.to_native() != byte_offset
+                || chunk.line_offset.to_native() != line_offset
+            {
+                return Err(C::Error::new(InvalidOffsets));
+            }
+
+            byte_offset += chunk.text.len() as u64;
+            line_offset += count_line_breaks(&chunk.text) as u64;
+        }
+
+        if self.byte_len.to_native() != byte_offset
+            || self.line_breaks.to_native() != line_offset
+        {
+            return Err(C::Error::new(InvalidOffsets));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ArchivedRope {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ArchivedRope(\"")?;
+
+        for chunk in self.chunks() {
+            debug_no_quotes(chunk, f)?;
+        }
+
+        f.write_str("\")")
+    }
+}
+
+impl fmt::Display for ArchivedRope {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl PartialEq<str> for ArchivedRope {
+    #[inline]
+    fn eq(&self, rhs: &str) -> bool {
+        self.byte_len() == rhs.len() && chunks_eq_str(self.chunks(), rhs)
+    }
+}
+
+impl PartialEq<&str> for ArchivedRope {
+    #[inline]
+    fn eq(&self, rhs: &&str) -> bool {
+        self == *rhs
+    }
+}
+
+impl PartialEq<Rope> for ArchivedRope {
+    #[inline]
+    fn eq(&self, rhs: &Rope) -> bool {
+        if self.byte_len() != rhs.byte_len() {
+            return false;
+        }
+
+        let mut offset = 0;
+
+        for chunk in self.chunks() {
+            let end = offset + chunk.len();
+
+            if !rhs.is_char_boundary(end)
+                || rhs.byte_slice(offset..end) != chunk
+            {
+                return false;
+            }
+
+            offset = end;
+        }
+
+        true
+    }
+}
+
+/// An immutable slice of an [`ArchivedRope`], returned by
+/// [`ArchivedRope::byte_slice()`] and [`ArchivedRope::line()`].
+#[derive(Clone)]
+pub struct ArchivedRopeSlice<'a> {
+    rope: &'a ArchivedRope,
+    byte_range: Range<usize>,
+}
+
+impl<'a> ArchivedRopeSlice<'a> {
+    /// Returns the byte length of the slice.
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.byte_range.len()
+    }
+
+    /// Returns an iterator over the chunks of the slice.
+    #[inline]
+    pub fn chunks(&self) -> impl DoubleEndedIterator<Item = &'a str> + 'a {
+        let Range { start, end } = self.byte_range.clone();
+
+        let chunks = if start < end {
+            let first = self.rope.chunk_of_byte(start);
+            let last = self.rope.chunk_of_byte(end - 1);
+            &self.rope.chunks[first..=last]
+        } else {
+            &[]
+        };
+
+        chunks.iter().map(move |chunk| {
+            let text = chunk.text.as_str();
+            let offset = chunk.byte_offset();
+            let start = start.saturating_sub(offset);
+            let end = (end - offset).min(text.len());
+            &text[start..end]
+        })
+    }
+
+    /// Returns `true` if the slice is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.byte_len() == 0
+    }
+}
+
+impl fmt::Debug for ArchivedRopeSlice<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ArchivedRopeSlice(\"")?;
+
+        for chunk in self.chunks() {
+            debug_no_quotes(chunk, f)?;
+        }
+
+        f.write_str("\")")
+    }
+}
+
+impl fmt::Display for ArchivedRopeSlice<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl PartialEq<str> for ArchivedRopeSlice<'_> {
+    #[inline]
+    fn eq(&self, rhs: &str) -> bool {
+        self.byte_len() == rhs.len() && chunks_eq_str(self.chunks(), rhs)
+    }
+}
+
+impl PartialEq<&str> for ArchivedRopeSlice<'_> {
+    #[inline]
+    fn eq(&self, rhs: &&str) -> bool {
+        self == *rhs
+    }
+}
+
+/// Returns the byte offset of the start of the given line in `text`, or its
+/// length if it has fewer line breaks, counting them like a `Rope` does.
+#[inline]
+fn byte_of_line(text: &str, line_offset: usize) -> usize {
+    #[cfg(feature = "unicode-lines")]
+    {
+        super::unicode_lines::byte_of_line(text, line_offset)
+    }
+
+    #[cfg(not(feature = "unicode-lines"))]
+    {
+        lines_lf::to_byte_idx(text, line_offset)
+    }
+}
+
+/// Returns `true` if the concatenation of `chunks` is equal to `s`.
+#[inline]
+fn chunks_eq_str<'a>(chunks: impl Iterator<Item = &'a str>, s: &str) -> bool {
+    let mut rest = s.as_bytes();
+
+    for chunk in chunks {
+        match rest.strip_prefix(chunk.as_bytes()) {
+            Some(after) => rest = after,
+            None => return false,
+        }
+    }
+
+    rest.is_empty()
+}
+
+/// Returns the number of line breaks in `text`, counted like a `Rope` does.
+#[inline]
+fn count_line_breaks(text: &str) -> usize {
+    #[cfg(feature = "unicode-lines")]
+    {
+        super::unicode_lines::count_line_breaks(text)
+    }
+
+    #[cfg(not(feature = "unicode-lines"))]
+    {
+        lines_lf::count_breaks(text)
+    }
+}
+
+/// The resolver of an archived [`Rope`], used by rkyv.
+pub struct RopeResolver {
+    chunks: VecResolver,
+    chunk_len: usize,
+    byte_len: u64,
+    line_breaks: u64,
+}
+
+impl Archive for Rope {
+    type Archived = ArchivedRope;
+    type Resolver = RopeResolver;
+
+    #[inline]
+    fn resolve(&self, resolver: RopeResolver, out: Place<ArchivedRope>) {
+        munge!(let ArchivedRope { chunks, byte_len, line_breaks } = out);
+        ArchivedVec::resolve_from_len(
+            resolver.chunk_len,
+            resolver.chunks,
+            chunks,
+        );
+        byte_len.write(Archived::<u64>::from_native(resolver.byte_len));
+        line_breaks.write(Archived::<u64>::from_native(resolver.line_breaks));
+    }
+}
+
+impl<S> Serialize<S> for Rope
+where
+    S: Fallible + Allocator + Writer + ?Sized,
+    S::Error: Source,
+{
+    #[inline]
+    fn serialize(&self, serializer: &mut S) -> Result<RopeResolver, S::Error> {
+        let mut byte_offset = 0;
+        let mut line_offset = 0;
+
+        let chunks = self
+            .chunks()
+            .filter(|chunk| !chunk.is_empty())
+            .map(|text| {
+                let chunk = Chunk { text, byte_offset, line_offset };
+                byte_offset += text.len() as u64;
+                line_offset += count_line_breaks(text) as u64;
+                chunk
+            })
+            .collect::<Vec<_>>();
+
+        Ok(RopeResolver {
+            chunks: ArchivedVec::serialize_from_slice(&chunks, serializer)?,
+            chunk_len: chunks.len(),
+            byte_len: byte_offset,
+            line_breaks: line_offset,
+        })
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<Rope, D> for ArchivedRope {
+    #[inline]
+    fn deserialize(&self, _: &mut D) -> Result<Rope, D::Error> {
+        Ok(self.to_rope())
+    }
+}
+
+/// A chunk of a `Rope` and the offsets at which it starts, archived as an
+/// [`ArchivedChunk`].
+struct Chunk<'a> {
+    text: &'a str,
+    byte_offset: u64,
+    line_offset: u64,
+}
+
+impl Archive for Chunk<'_> {
+    type Archived = ArchivedChunk;
+    type Resolver = StringResolver;
+
+    #[inline]
+    fn resolve(&self, resolver: StringResolver, out: Place<ArchivedChunk>) {
+        munge!(let ArchivedChunk { text, byte_offset, line_offset } = out);
+        ArchivedString::resolve_from_str(self.text, resolver, text);
+        byte_offset.write(Archived::<u64>::from_native(self.byte_offset));
+        line_offset.write(Archived::<u64>::from_native(self.line_offset));
+    }
+}
+
+impl<S> Serialize<S> for Chunk<'_>
+where
+    S: Fallible + Writer + ?Sized,
+    S::Error: Source,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<StringResolver, S::Error> {
+        ArchivedString::serialize_from_str(self.text, serializer)
+    }
+}
@@ -1,190 +1,3329 @@
+use alloc::boxed::Box;
 use alloc::string::String;
-use core::ops::RangeBounds;
+use alloc::vec::Vec;
+use core::ops::{Range, RangeBounds};
 
+#[cfg(feature = "mmap")]
+use super::chunk_bytes::StableBytes;
+#[cfg(feature = "compression")]
+use super::chunk_bytes::{self, MAX_DECOMPRESSED_CHUNKS};
+use super::content_hash::{ContentHashCache, PolyHash};
+#[cfg(feature = "line-metric")]
+use super::dirty_lines::DirtyLines;
+#[cfg(feature = "line-metric")]
+use super::filtered_lines::FilteredLines;
 use super::gap_buffer::GapBuffer;
-use super::iterators::{Bytes, Chars, Chunks, Lines, RawLines};
-use super::metrics::{ByteMetric, RawLineMetric};
+use super::iterators::{Bytes, Chars, Chunks, EncodeUtf16, RollingHash};
+#[cfg(feature = "line-metric")]
+use super::iterators::{Lines, RawLines};
+use super::line_ending::replace_line_endings;
+use super::marks::{self, Gravity, MarkId, MarkSet, Tail};
+use super::metrics::ByteMetric;
+#[cfg(any(feature = "content-hash", feature = "word-metric"))]
+use super::metrics::ChunkSummary;
+#[cfg(feature = "line-metric")]
+use super::metrics::RawLineMetric;
+#[cfg(feature = "paging")]
+use super::paging::RecentEdits;
+use super::record_index::RecordIndex;
+use super::subscribers::Subscribers;
 use super::utils::{panic_messages as panic, *};
-use super::RopeSlice;
+use super::{
+    Bom,
+    ChunkInterner,
+    Delta,
+    EditInfo,
+    EditSession,
+    IncrementalEdit,
+    Limit,
+    LineEnding,
+    LineEndingCounts,
+    MemStats,
+    Revision,
+    RopeBuilder,
+    RopeCursor,
+    RopeSlice,
+    Snapshot,
+    SubscriptionId,
+    TooLongError,
+};
+#[cfg(feature = "line-metric")]
+use super::{EditRegion, TrailingLineBreak};
 use crate::range_bounds_to_start_end;
-use crate::tree::Tree;
+use crate::tree::{AtomicCount, LocalCount, RefCount, Tree};
 
 #[cfg(any(test, fuzzing, feature = "arity_4"))]
-const ARITY: usize = 4;
+pub(super) const DEFAULT_ARITY: usize = 4;
 
 #[cfg(not(any(test, fuzzing, feature = "arity_4")))]
-const ARITY: usize = 16;
+pub(super) const DEFAULT_ARITY: usize = 16;
 
 #[cfg(any(test, feature = "small_chunks"))]
-const CHUNK_MAX_BYTES: usize = 4;
+pub(super) const DEFAULT_CHUNK_MAX_BYTES: usize = 4;
 
 // With 4-byte chunks, fuzzing is unbearably slow.
 #[cfg(fuzzing)]
-const CHUNK_MAX_BYTES: usize = 16;
+pub(super) const DEFAULT_CHUNK_MAX_BYTES: usize = 16;
 
 #[cfg(not(any(test, fuzzing, feature = "small_chunks")))]
-const CHUNK_MAX_BYTES: usize = 2048;
+pub(super) const DEFAULT_CHUNK_MAX_BYTES: usize = 2048;
 
-pub(super) type RopeChunk = GapBuffer<CHUNK_MAX_BYTES>;
+pub(super) type RopeChunk = GapBuffer<DEFAULT_CHUNK_MAX_BYTES>;
 
 /// A UTF-8 text rope.
-#[derive(Clone, Default)]
-pub struct Rope {
-    pub(super) tree: Tree<{ Self::arity() }, RopeChunk>,
+///
+/// The nodes of a `Rope` are shared between its clones and reference counted
+/// with atomics by default. Cloning a `Rope` and editing a clone both update
+/// those counts, which is measurably slower in edit-heavy workloads, so a
+/// `Rope<`[`LocalCount`](crate::tree::LocalCount)`>` can be used instead by
+/// programs that never share their ropes across threads, at the cost of it
+/// being neither `Send` nor `Sync`.
+///
+/// The features needing the atomic counts, like snapshots or the parallel
+/// iterators, are only available on the default `Rope`. The usual
+/// constructors and conversions, like [`Rope::new()`] or `From<&str>`, also
+/// only build default `Rope`s, so that their type can always be inferred.
+/// Every other `Rope` is built with [`empty()`](Self::empty()) or
+/// [`from_text()`](Self::from_text()), or converted to and from a default
+/// `Rope` in `O(n)` time.
+///
+/// The last two parameters are the fanout of the tree and the maximum number
+/// of bytes of its chunks, see the
+/// [crate docs](crate#tuning-the-node-sizes) for more.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::{tree::LocalCount, Rope};
+/// #
+/// let mut r = Rope::<LocalCount>::from_text("Hello Earth!");
+///
+/// let snapshot = r.clone();
+///
+/// r.replace(6..11, "Saturn");
+/// assert_eq!(r, "Hello Saturn!");
+/// assert_eq!(snapshot, "Hello Earth!");
+///
+/// assert_eq!(Rope::from(&r), "Hello Saturn!");
+/// ```
+pub struct Rope<
+    C: RefCount = AtomicCount,
+    const ARITY: usize = DEFAULT_ARITY,
+    const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+> {
+    pub(super) tree: Tree<ARITY, GapBuffer<MAX_BYTES>, C>,
     pub(super) has_trailing_newline: bool,
+
+    /// See [`Rope::content_hash()`] for more. It's kept out of the
+    /// `RopeExtras` since it's filled in from a shared reference, when the
+    /// `RopeExtras` can't be allocated.
+    content_hash: ContentHashCache,
+
+    /// See [`Rope::revision()`] for more.
+    revision: Revision,
+
+    /// Whether the revision could also be the one of a clone of the `Rope`,
+    /// in which case the next edit gives it a new id.
+    revision_is_shared: bool,
+
+    /// The state used by the opt-in APIs, or `None` if none of them has ever
+    /// been used.
+    extras: Option<Box<RopeExtras>>,
+}
+
+/// The state of a [`Rope`] that's only needed by some of its APIs, which is
+/// boxed so that `Rope`s that don't use them only pay for a pointer.
+#[derive(Default)]
+struct RopeExtras {
+    /// The lines touched by the edits since the last call to
+    /// `take_dirty_lines()`, or `None` if they're not being tracked.
+    #[cfg(feature = "line-metric")]
+    dirty_lines: Option<DirtyLines>,
+
+    /// See [`Rope::limit()`] for more.
+    limit: Option<Limit>,
+
+    /// The marks added with `add_mark()`.
+    marks: MarkSet,
+
+    /// The callbacks registered with `subscribe()`.
+    subscribers: Subscribers,
+
+    /// The number of chunks decompressed by all the `Rope`s the last time
+    /// this one dropped the decompressed copies of its chunks.
+    #[cfg(feature = "compression")]
+    swept_at: usize,
+
+    /// The last edits, whose chunks are kept in memory by `page_out()`, or
+    /// `None` if the `Rope` has never been paged out.
+    #[cfg(feature = "paging")]
+    recent_edits: Option<RecentEdits>,
+}
+
+impl Clone for RopeExtras {
+    /// The subscribers are not cloned, since they're interested in the edits
+    /// of the original `Rope`.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            #[cfg(feature = "line-metric")]
+            dirty_lines: self.dirty_lines.clone(),
+            limit: self.limit,
+            marks: self.marks.clone(),
+            subscribers: Subscribers::default(),
+            #[cfg(feature = "compression")]
+            swept_at: self.swept_at,
+            #[cfg(feature = "paging")]
+            recent_edits: self.recent_edits.clone(),
+        }
+    }
 }
 
 impl Rope {
-    #[doc(hidden)]
-    pub fn assert_invariants(&self) {
-        self.tree.assert_invariants();
+    /// The maximum number of bytes of text a `Rope` can hold, which is 4 GiB
+    /// with the `compact-summaries` feature and `usize::MAX` without it.
+    ///
+    /// Every method which would make a `Rope` longer than this panics before
+    /// modifying it, while [`try_append()`](Self::try_append()),
+    /// [`try_insert()`](Self::try_insert()) and
+    /// [`try_replace()`](Self::try_replace()) return a [`TooLongError`]
+    /// instead.
+    pub const MAX_BYTE_LEN: usize = super::too_long::MAX_BYTE_LEN;
+
+    /// Returns an [`IncrementalEdit`] replacing the text within the given
+    /// byte range with `text` in bounded steps.
+    ///
+    /// The `Rope` is left untouched until the last step, so it can still be
+    /// read through [`IncrementalEdit::rope()`] while the edit is underway.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start or the end of the byte range don't lie on a code
+    /// point boundary, if the start is greater than the end or if the end is
+    /// out of bounds (i.e. greater than [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello World!");
+    ///
+    /// let text = "Earth ".repeat(1000);
+    ///
+    /// let mut edit = r.begin_edit(6..11, &text);
+    ///
+    /// while !edit.step(1024) {
+    ///     assert_eq!(edit.rope(), "Hello World!");
+    /// }
+    ///
+    /// drop(edit);
+    ///
+    /// assert_eq!(r.byte_len(), 7 + text.len());
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn begin_edit<R, T>(
+        &mut self,
+        byte_range: R,
+        text: T,
+    ) -> IncrementalEdit<'_, T>
+    where
+        R: RangeBounds<usize>,
+        T: AsRef<str>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.byte_len());
+
+        if start > end {
+            panic::byte_start_after_end(start, end);
+        }
+
+        if end > self.byte_len() {
+            panic::byte_offset_out_of_bounds(end, self.byte_len());
+        }
+
+        // Slicing takes care of panicking with a nice message if the range
+        // doesn't lie on char boundaries.
+        let _ = self.byte_slice(start..end);
+
+        IncrementalEdit::new(self, start..end, text)
+    }
+
+    /// Returns an iterator over the byte ranges of this `Rope` whose
+    /// contents changed since the given [`Snapshot`] was taken, sorted by
+    /// their position.
+    ///
+    /// Text that was deleted without being replaced is reported as an empty
+    /// range at the offset where it used to be. Edits that are close to each
+    /// other can be reported as a single range covering all of them.
+    ///
+    /// The ranges are computed by skipping all the subtrees shared between
+    /// the `Rope` and the snapshot, so if the `Rope` was derived from the
+    /// snapshot by a series of edits the running time only depends on the
+    /// number and size of those edits. Comparing a `Rope` with an unrelated
+    /// snapshot works too, but it's as expensive as comparing their
+    /// contents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("foo\nbar\nbaz\n");
+    ///
+    /// let snapshot = r.snapshot();
+    /// assert_eq!(r.changes_since(&snapshot).count(), 0);
+    ///
+    /// r.replace(4..7, "BAR");
+    /// assert_eq!(r.changes_since(&snapshot).collect::<Vec<_>>(), [4..7]);
+    /// ```
+    #[inline]
+    pub fn changes_since(
+        &self,
+        snapshot: &Snapshot,
+    ) -> impl Iterator<Item = Range<usize>> {
+        super::snapshot::changes_since(self, snapshot.rope()).into_iter()
+    }
+
+    /// Like [`changes_since()`](Self::changes_since()), but compares the
+    /// regions between the subtrees shared with the snapshot on rayon's
+    /// global thread pool.
+    ///
+    /// This only pays off when there are many such regions, e.g. after
+    /// edits scattered all over a large `Rope`, or when the `node-hashes`
+    /// feature is enabled and the snapshot was loaded separately from the
+    /// `Rope`, in which case the subtrees storing the same text delimit the
+    /// regions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("foo\nbar\nbaz\n".repeat(1 << 12));
+    ///
+    /// let snapshot = r.snapshot();
+    ///
+    /// r.replace(4..7, "BAR");
+    /// r.insert(r.byte_len() - 4, "qux\n");
+    ///
+    /// let changes = r.par_changes_since(&snapshot).collect::<Vec<_>>();
+    /// assert_eq!(changes, r.changes_since(&snapshot).collect::<Vec<_>>());
+    /// assert_eq!(changes.len(), 2);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    #[cfg(feature = "parallel")]
+    #[inline]
+    pub fn par_changes_since(
+        &self,
+        snapshot: &Snapshot,
+    ) -> impl Iterator<Item = Range<usize>> {
+        super::parallel::changes_since(self, snapshot.rope()).into_iter()
+    }
+
+    /// Returns a parallel iterator over the chunks of this [`Rope`].
+    ///
+    /// See [`RopeSlice::par_chunks()`] for more.
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    #[cfg(feature = "parallel")]
+    #[inline]
+    pub fn par_chunks(&self) -> crate::iter::ParChunks<'_> {
+        self.byte_slice(..).par_chunks()
+    }
+
+    /// Returns the byte offsets of the non-overlapping occurrences of
+    /// `pattern` in the `Rope`, searching its subtrees in parallel.
+    ///
+    /// See [`RopeSlice::par_find_all()`] for more.
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    #[cfg(feature = "parallel")]
+    #[inline]
+    pub fn par_find_all(&self, pattern: &str) -> Vec<usize> {
+        self.byte_slice(..).par_find_all(pattern)
+    }
+
+    /// Returns a [`RopeCursor`] at the given byte offset, which can be used
+    /// to read the text sequentially or to query nearby positions without
+    /// descending the tree every time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())) or if it's not a char boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo\nbär");
+    ///
+    /// let mut cursor = r.cursor(4);
+    ///
+    /// assert_eq!(cursor.next_char(), Some('b'));
+    /// assert_eq!(cursor.next_char(), Some('ä'));
+    /// assert_eq!(cursor.offset(), 7);
+    ///
+    /// cursor.seek(-3);
+    /// assert_eq!(cursor.prev_char(), Some('\n'));
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn cursor(&self, byte_offset: usize) -> RopeCursor<'_> {
+        RopeCursor::new(self.into(), byte_offset)
+    }
+
+    /// Starts an [`EditSession`] at the given byte offset, which buffers the
+    /// text typed at its cursor and inserts it in the `Rope` with a single
+    /// edit.
+    ///
+    /// The pending text is inserted when the session is dropped, when its
+    /// cursor is moved, or when the `Rope` is accessed through it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())) or if it's not a char boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("fn main() {}");
+    ///
+    /// let mut session = r.edit_session(11);
+    ///
+    /// for ch in "\n    todo!();\n".chars() {
+    ///     session.insert_char(ch);
+    /// }
+    ///
+    /// drop(session);
+    ///
+    /// assert_eq!(r, "fn main() {\n    todo!();\n}");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn edit_session(&mut self, byte_offset: usize) -> EditSession<'_> {
+        EditSession::new(self, byte_offset)
+    }
+
+    pub(super) const fn arity() -> usize {
+        DEFAULT_ARITY
+    }
+
+    /// Returns a [`FilteredLines`] view over the lines of the `Rope` for
+    /// which the predicate returns `true`.
+    ///
+    /// The predicate is called once for every line, without its line break.
+    /// The view only stores the indices of the matching lines, so no text is
+    /// copied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo\n\nbar\n\nbaz");
+    ///
+    /// let non_empty = r.filter_lines(|line| !line.is_empty());
+    ///
+    /// assert_eq!(non_empty.original_lines(), [0, 2, 4]);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn filter_lines<F>(&self, predicate: F) -> FilteredLines<'_>
+    where
+        F: FnMut(RopeSlice<'_>) -> bool,
+    {
+        FilteredLines::new(self, predicate)
+    }
+
+    /// Creates a new `Rope` whose chunks borrow their text from `bytes`,
+    /// e.g. a memory-mapped file, instead of copying it.
+    ///
+    /// The bytes are still read once to validate them as UTF-8 and to
+    /// compute the metrics of each chunk, but a chunk's text is only copied
+    /// the first time that chunk is edited. `bytes` is kept alive for as long
+    /// as any chunk borrows from it, and it can be anything implementing
+    /// [`StableBytes`], like a [`memmap2::Mmap`] or a `Vec<u8>`.
+    ///
+    /// Returns an error if the bytes are not valid UTF-8.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bytes are longer than
+    /// [`MAX_BYTE_LEN`](Self::MAX_BYTE_LEN).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::fs::File;
+    /// # use crop::Rope;
+    /// #
+    /// let file = File::open("huge.log").unwrap();
+    ///
+    /// // SAFETY: the file must not be modified while it's mapped.
+    /// let mmap = unsafe { memmap2::Mmap::map(&file).unwrap() };
+    ///
+    /// let mut r = Rope::from_mmap(mmap).unwrap();
+    ///
+    /// // Only the chunk containing the start of the file is copied.
+    /// r.insert(0, "# ");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+    #[cfg(feature = "mmap")]
+    #[track_caller]
+    #[inline]
+    pub fn from_mmap<B>(bytes: B) -> Result<Self, core::str::Utf8Error>
+    where
+        B: StableBytes + Send + Sync + 'static,
+    {
+        let source: super::chunk_bytes::ByteSource =
+            alloc::sync::Arc::new(bytes);
+
+        // The slice is only taken once, and the chunks keep pointing into it.
+        let s = super::utils::from_utf8((*source).as_ref())?;
+
+        if TooLongError::check(0, 0, s.len()).is_err() {
+            panic::rope_too_long();
+        }
+
+        let leaves = RopeChunk::segmenter(s)
+            .map(|chunk| RopeChunk::mapped(&source, chunk));
+
+        Ok(Rope::from_tree(Tree::from_leaves(leaves), ends_with_line_break(s)))
+    }
+
+    /// Creates a new `Rope` by reading UTF-8 text from `reader`, e.g. a file,
+    /// building it on multiple threads.
+    ///
+    /// The input is read in large blocks, and the bytes of each block are
+    /// validated and split into chunks in parallel on rayon's global thread
+    /// pool, like in [`from_str_parallel()`](Self::from_str_parallel()),
+    /// while only one block at a time is kept in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidData`] if the input isn't valid
+    /// UTF-8, or any error returned by the reader.
+    ///
+    /// [`InvalidData`]: std::io::ErrorKind::InvalidData
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::fs::File;
+    /// # use std::io::BufReader;
+    /// # use crop::Rope;
+    /// #
+    /// let file = File::open("huge.log").unwrap();
+    ///
+    /// let r = Rope::from_reader_parallel(BufReader::new(file)).unwrap();
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    #[cfg(feature = "parallel")]
+    #[inline]
+    pub fn from_reader_parallel<R: std::io::Read>(
+        reader: R,
+    ) -> std::io::Result<Self> {
+        let (tree, has_trailing_newline) =
+            super::parallel::tree_from_reader(reader)?;
+
+        Ok(Rope::from_tree(tree, has_trailing_newline))
+    }
+
+    /// Creates a new `Rope` by reading text from `reader`, e.g. a file, in
+    /// the encoding signaled by its byte order mark.
+    ///
+    /// If the text starts with a UTF-8 or UTF-16 [`Bom`] it's decoded in that
+    /// encoding and the BOM is stripped from it, otherwise it's decoded in
+    /// the `fallback` encoding. The BOM that was found is returned along with
+    /// the `Rope`, so that it can be written back when saving the text.
+    ///
+    /// Decoding works like in
+    /// [`from_reader_with_encoding()`](Self::from_reader_with_encoding()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{Bom, Rope};
+    /// #
+    /// let bytes = [0xfe, 0xff, 0x00, 0x48, 0x00, 0x69]; // "Hi" in UTF-16BE
+    ///
+    /// let (r, bom) =
+    ///     Rope::from_reader_with_bom(&bytes[..], encoding_rs::WINDOWS_1252)
+    ///         .unwrap();
+    ///
+    /// assert_eq!(r, "Hi");
+    /// assert_eq!(bom, Some(Bom::Utf16Be));
+    ///
+    /// // Write the BOM back when saving.
+    /// let bom = bom.unwrap();
+    /// let mut saved = bom.as_bytes().to_vec();
+    /// r.write_with_encoding(&mut saved, bom.encoding()).unwrap();
+    /// assert_eq!(saved, bytes);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+    #[cfg(feature = "encoding")]
+    #[inline]
+    pub fn from_reader_with_bom<R: std::io::Read>(
+        mut reader: R,
+        fallback: &'static encoding_rs::Encoding,
+    ) -> std::io::Result<(Self, Option<Bom>)> {
+        // Every BOM is at most 3 bytes long.
+        let mut prefix = [0; 3];
+
+        let mut prefix_len = 0;
+
+        while prefix_len < prefix.len() {
+            match reader.read(&mut prefix[prefix_len..]) {
+                Ok(0) => break,
+                Ok(read) => prefix_len += read,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {
+                },
+                Err(err) => return Err(err),
+            }
+        }
+
+        let prefix = &prefix[..prefix_len];
+
+        let bom = Bom::detect(prefix);
+
+        let (encoding, rest) = match bom {
+            Some(bom) => (bom.encoding(), &prefix[bom.len()..]),
+            None => (fallback, prefix),
+        };
+
+        let rope = Self::from_reader_with_encoding(
+            std::io::Read::chain(rest, reader),
+            encoding,
+        )?;
+
+        Ok((rope, bom))
+    }
+
+    /// Creates a new `Rope` by reading text in the given encoding from
+    /// `reader`, e.g. a file.
+    ///
+    /// The text is decoded into UTF-8 a buffer at a time while it's being
+    /// read, so the whole file is never held in memory in its original
+    /// encoding. Malformed sequences are replaced with the replacement
+    /// character `U+FFFD`, and a byte order mark at the start of the text is
+    /// kept as the `U+FEFF` character. Use
+    /// [`from_reader_with_bom()`](Self::from_reader_with_bom()) to strip it
+    /// instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// # use encoding_rs::UTF_16LE;
+    /// #
+    /// let bytes = [0x48, 0x00, 0xe9, 0x00, 0x0a, 0x00]; // "Hé\n" in UTF-16LE
+    ///
+    /// let r = Rope::from_reader_with_encoding(&bytes[..], UTF_16LE).unwrap();
+    ///
+    /// assert_eq!(r, "Hé\n");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+    #[cfg(feature = "encoding")]
+    #[inline]
+    pub fn from_reader_with_encoding<R: std::io::Read>(
+        mut reader: R,
+        encoding: &'static encoding_rs::Encoding,
+    ) -> std::io::Result<Self> {
+        let mut decoder = encoding.new_decoder_without_bom_handling();
+
+        let mut builder = RopeBuilder::new();
+
+        let mut input = alloc::vec![0; 1 << 13];
+
+        let mut output = String::with_capacity(1 << 14);
+
+        loop {
+            let read = match reader.read(&mut input) {
+                Ok(read) => read,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {
+                    continue
+                },
+                Err(err) => return Err(err),
+            };
+
+            let is_last = read == 0;
+
+            let mut src = &input[..read];
+
+            loop {
+                let (result, decoded, _) =
+                    decoder.decode_to_string(src, &mut output, is_last);
+
+                src = &src[decoded..];
+
+                builder.append(&output);
+
+                output.clear();
+
+                if let encoding_rs::CoderResult::InputEmpty = result {
+                    break;
+                }
+            }
+
+            if is_last {
+                return Ok(builder.build());
+            }
+        }
+    }
+
+    /// Creates a new `Rope` whose chunks borrow their text from a string with
+    /// a `'static` lifetime instead of copying it, e.g. a document embedded
+    /// in the binary with [`include_str!`].
+    ///
+    /// A chunk's text is only copied the first time the chunk is edited, so
+    /// the text that's never edited is only stored once, in the binary, and
+    /// the chunks borrowing it don't allocate anything on the heap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// static HELP: &str = "Usage: crop [OPTIONS] <FILE>\n";
+    ///
+    /// let mut r = Rope::from_static(HELP);
+    /// assert_eq!(r, HELP);
+    ///
+    /// r.replace(7..11, "edit");
+    /// assert_eq!(r, "Usage: edit [OPTIONS] <FILE>\n");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn from_static(s: &'static str) -> Self {
+        if TooLongError::check(0, 0, s.len()).is_err() {
+            panic::rope_too_long();
+        }
+
+        let leaves = RopeChunk::segmenter(s).map(RopeChunk::from_static);
+
+        Rope::from_tree(Tree::from_leaves(leaves), ends_with_line_break(s))
+    }
+
+    /// Creates a new `Rope` from a string slice, building it on multiple
+    /// threads.
+    ///
+    /// The text is split into segments of about a megabyte each, whose chunks
+    /// are created and summarized in parallel on rayon's global thread pool
+    /// before the resulting subtrees are joined together. This is mostly
+    /// useful for texts in the hundreds of megabytes or more, e.g. large log
+    /// files, and texts that fit in a single segment are converted on the
+    /// current thread like with [`Rope::from()`](Self::from).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let s = "Hello, world!\n".repeat(1 << 20);
+    ///
+    /// let r = Rope::from_str_parallel(&s);
+    ///
+    /// assert_eq!(r, s);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    #[cfg(feature = "parallel")]
+    #[track_caller]
+    #[inline]
+    pub fn from_str_parallel(s: &str) -> Self {
+        if TooLongError::check(0, 0, s.len()).is_err() {
+            panic::rope_too_long();
+        }
+
+        if s.len() <= super::parallel::SEGMENT_LEN {
+            return Self::from(s);
+        }
+
+        Self::from_tree(
+            super::parallel::tree_from_str(s),
+            ends_with_line_break(s),
+        )
+    }
+
+    /// Creates a new `Rope` by concatenating the given `String`s, reusing
+    /// their allocations as chunks where possible.
+    ///
+    /// Each `String` is dropped as soon as it's been added to the `Rope`, so
+    /// converting e.g. the lines of an existing buffer doesn't need twice
+    /// the memory. See [`RopeBuilder::push_string()`] for when an allocation
+    /// can be reused.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let lines = vec!["foo\n".to_owned(), "bar\n".to_owned()];
+    ///
+    /// let r = Rope::from_strings(lines);
+    ///
+    /// assert_eq!(r, "foo\nbar\n");
+    /// ```
+    #[inline]
+    pub fn from_strings<I>(strings: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut builder = RopeBuilder::new();
+
+        for string in strings {
+            builder.push_string(string);
+        }
+
+        builder.build()
+    }
+
+    /// Creates a new `Rope` from a vector of bytes, returning an error if
+    /// they're not valid UTF-8.
+    ///
+    /// Unlike converting the bytes into a `String` first, the bytes are
+    /// validated while they're being split into chunks, so they're only read
+    /// once in the common case where they're valid. Code points straddling
+    /// the boundary between two chunks are validated as a whole.
+    ///
+    /// On failure the bytes are returned in the error, just like with
+    /// [`String::from_utf8()`]. The error's [`Utf8Error`] contains the offset
+    /// of the first invalid byte in [`valid_up_to()`].
+    ///
+    /// [`Utf8Error`]: core::str::Utf8Error
+    /// [`valid_up_to()`]: core::str::Utf8Error::valid_up_to()
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from_utf8("Hello, 🌎!".as_bytes().to_vec()).unwrap();
+    /// assert_eq!(r, "Hello, 🌎!");
+    ///
+    /// let err = Rope::from_utf8(vec![b'a', b'b', 0xff, b'c']).unwrap_err();
+    /// assert_eq!(err.utf8_error().valid_up_to(), 2);
+    /// assert_eq!(err.into_bytes(), [b'a', b'b', 0xff, b'c']);
+    /// ```
+    #[inline]
+    pub fn from_utf8(
+        bytes: Vec<u8>,
+    ) -> Result<Self, alloc::string::FromUtf8Error> {
+        match Self::try_from(&*bytes) {
+            Ok(rope) => Ok(rope),
+            Err(_) => Err(String::from_utf8(bytes).unwrap_err()),
+        }
+    }
+
+    /// Creates a new `Rope` from UTF-8 bytes, stripping the UTF-8 byte order
+    /// mark at their start if there is one.
+    ///
+    /// The BOM that was stripped is returned along with the `Rope`, so that
+    /// it can be written back when saving the text. The bytes are validated
+    /// like in the `TryFrom<&[u8]>` impl, and the offset in the returned
+    /// error is relative to the start of `bytes`, BOM included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{Bom, Rope};
+    /// #
+    /// let (r, bom) = Rope::from_utf8_with_bom(b"\xEF\xBB\xBFHi\n").unwrap();
+    /// assert_eq!(r, "Hi\n");
+    /// assert_eq!(r.line(0), "Hi");
+    /// assert_eq!(bom, Some(Bom::Utf8));
+    ///
+    /// let (r, bom) = Rope::from_utf8_with_bom(b"Hi\n").unwrap();
+    /// assert_eq!(r, "Hi\n");
+    /// assert_eq!(bom, None);
+    /// ```
+    #[inline]
+    pub fn from_utf8_with_bom(
+        bytes: &[u8],
+    ) -> Result<(Self, Option<Bom>), core::str::Utf8Error> {
+        let (bytes_no_bom, bom) =
+            match bytes.strip_prefix(Bom::Utf8.as_bytes()) {
+                Some(rest) => (rest, Some(Bom::Utf8)),
+                None => (bytes, None),
+            };
+
+        match Self::try_from(bytes_no_bom) {
+            Ok(rope) => Ok((rope, bom)),
+
+            // The BOM is valid UTF-8, so validating the whole input gives the
+            // same error shifted by its length.
+            Err(_) => Err(core::str::from_utf8(bytes).unwrap_err()),
+        }
+    }
+
+    /// Returns a breakdown of the memory used by the `Rope`, e.g. to show
+    /// memory diagnostics or to decide when to call
+    /// [`shrink_to_fit()`](Self::shrink_to_fit()).
+    ///
+    /// This walks the whole tree, so it runs in `O(n)` time in the number of
+    /// chunks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello, world!\n".repeat(1000));
+    ///
+    /// let stats = r.mem_stats();
+    /// assert_eq!(stats.text_bytes, r.byte_len());
+    /// assert!(stats.total_bytes() > r.byte_len());
+    /// assert_eq!(stats.nodes_per_depth[0], 1);
+    ///
+    /// r.shrink_to_fit();
+    /// assert_eq!(r.mem_stats().slack_bytes, 0);
+    /// ```
+    #[inline]
+    pub fn mem_stats(&self) -> MemStats {
+        super::mem_stats::mem_stats(self)
+    }
+
+    /// Returns a new empty [`Rope`].
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a cheap frozen copy of the `Rope`, which can later be passed
+    /// to [`changes_since()`](Self::changes_since()) to find out which parts
+    /// of the `Rope` have been edited since.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello World");
+    ///
+    /// let snapshot = r.snapshot();
+    /// r.insert(5, ",");
+    ///
+    /// assert_eq!(*snapshot, "Hello World");
+    /// assert_eq!(r, "Hello, World");
+    /// ```
+    #[inline]
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::from(self.clone())
+    }
+}
+
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    Rope<C, ARITY, MAX_BYTES>
+{
+    /// Texts at least this long are inserted by building a tree out of them
+    /// and splicing it into the `Rope`'s, which is faster than adding their
+    /// chunks to the tree one by one once they span several of its levels.
+    const BULK_INSERT_MIN_BYTES: usize = MAX_BYTES * ARITY * ARITY * 2;
+
+    /// Fails the build if the node sizes the `Rope` is instantiated with are
+    /// out of range.
+    const VALID_NODE_SIZES: () = assert!(
+        ARITY >= 4 && MAX_BYTES >= 4 && MAX_BYTES <= u16::MAX as usize,
+        "a Rope's arity has to be at least 4, and its chunks have to hold \
+         between 4 and 65535 bytes",
+    );
+
+    /// Adds a mark at the given byte offset, which will be moved by every
+    /// edit performed on the `Rope` to keep pointing at the same position in
+    /// the text. See [`MarkSet`] for more.
+    ///
+    /// The mark's gravity decides whether it stays before or moves after any
+    /// text inserted exactly at its position. Deleting the text around a
+    /// mark moves it to the start of the deleted range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// # use crop::marks::Gravity;
+    /// #
+    /// let mut r = Rope::from("foo bar baz");
+    ///
+    /// let bar = r.add_mark(4, Gravity::Right);
+    ///
+    /// r.delete(..4);
+    /// assert_eq!(r.marks().get(bar), Some(0));
+    ///
+    /// r.insert(0, "qux ");
+    /// assert_eq!(r.marks().get(bar), Some(4));
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn add_mark(
+        &mut self,
+        byte_offset: usize,
+        gravity: Gravity,
+    ) -> MarkId {
+        if byte_offset > self.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
+        }
+
+        self.extras_mut().marks.insert(byte_offset, gravity)
+    }
+
+    /// Appends another `Rope` to the end of this one.
+    ///
+    /// The two trees are joined by attaching the shallower one to the side
+    /// of the deeper one, so this takes `O(log n)` time and shares all the
+    /// nodes of `other` except the ones along the seam. The
+    /// [`Add`](core::ops::Add) and [`AddAssign`](core::ops::AddAssign)
+    /// implementations of `Rope` are built on this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello ");
+    ///
+    /// r.append(Rope::from("Earth 🌎!"));
+    /// assert_eq!(r, "Hello Earth 🌎!");
+    ///
+    /// let r = r + Rope::from("\n");
+    /// assert_eq!(r, "Hello Earth 🌎!\n");
+    /// ```
+    #[inline]
+    pub fn append(&mut self, other: Self) {
+        let byte_len = self.byte_len();
+
+        self.edit_with(byte_len..byte_len, other.byte_len(), |this| {
+            if !other.is_empty() {
+                this.has_trailing_newline = other.has_trailing_newline;
+            }
+
+            this.tree.append(other.tree);
+        });
+    }
+
+    /// Applies a batch of edits, each replacing the text within a byte range
+    /// with the given string, returning the [`Delta`] describing them.
+    ///
+    /// The byte ranges are all relative to the `Rope` as it is before the
+    /// edits, and they must be sorted and non-overlapping, although they're
+    /// allowed to touch. The edits are applied from last to first so that
+    /// the ranges don't need to be shifted, which is also the order in which
+    /// they appear in the returned `Delta`. All the ranges are validated
+    /// before any edit is applied.
+    ///
+    /// Each edit still costs as much as a call to
+    /// [`replace()`](Self::replace()) to carry out on the text, but the batch
+    /// is a single revision of the `Rope`: the marks and the subscribers are
+    /// only updated once all the edits are applied, and if the `Rope` has a
+    /// [`limit()`](Self::limit()) it's only enforced after the last edit,
+    /// with the deletion of the excess text being the last edit of the
+    /// `Delta`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the byte ranges would cause
+    /// [`replace()`](Self::replace()) to panic, or if a range starts before
+    /// the end of the one preceding it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("foo\nbar\nbaz\n");
+    ///
+    /// // Comment out every line.
+    /// let delta = r.apply_edits(&[(0..0, "// "), (4..4, "// "), (8..8, "// ")]);
+    ///
+    /// assert_eq!(r, "// foo\n// bar\n// baz\n");
+    /// assert_eq!(delta.map_offset(9), 18);
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn apply_edits<T>(&mut self, edits: &[(Range<usize>, T)]) -> Delta
+    where
+        T: AsRef<str>,
+    {
+        let mut previous_end = 0;
+
+        for (byte_range, _) in edits {
+            // Slicing takes care of checking the range.
+            let _ = self.byte_slice(byte_range.clone());
+
+            if byte_range.start < previous_end {
+                panic::byte_ranges_overlap(previous_end, byte_range.start);
+            }
+
+            previous_end = byte_range.end;
+        }
+
+        if edits.is_empty() {
+            return Delta::new();
+        }
+
+        let mut delta = edits
+            .iter()
+            .rev()
+            .map(|(byte_range, text)| EditInfo {
+                byte_range: byte_range.clone(),
+                inserted_len: text.as_ref().len(),
+            })
+            .collect::<Delta>();
+
+        let excess = self.edits_with(delta.iter().as_slice(), |this| {
+            for (byte_range, text) in edits.iter().rev() {
+                let text = text.as_ref();
+
+                let inserted = (text.len() >= Self::BULK_INSERT_MIN_BYTES)
+                    .then(|| {
+                        Tree::from_leaves(
+                            GapBuffer::<MAX_BYTES>::segmenter(text)
+                                .map(GapBuffer::<MAX_BYTES>::from),
+                        )
+                    });
+
+                this.replace_in_tree(byte_range.clone(), text, inserted);
+            }
+        });
+
+        delta.extend(excess);
+
+        delta
+    }
+
+    #[doc(hidden)]
+    pub fn assert_invariants(&self) {
+        self.tree.assert_invariants();
+
+        #[cfg(feature = "content-hash")]
+        if let Some(hash) = self.tree.summary().content_hash() {
+            let chunks = self.chunks();
+            assert_eq!(hash, super::content_hash::hash_text_chunks(chunks));
+        }
+
+        #[cfg(feature = "word-metric")]
+        assert_eq!(
+            self.tree.summary().words(),
+            Some(count_words(self.chunks()))
+        );
+
+        if let Some(last) = self.chunks().next_back() {
+            assert_eq!(self.has_trailing_newline, ends_with_line_break(last));
+        } else {
+            return;
+        }
+
+        let leaves = self.tree.leaves();
+
+        if leaves.len() == 1 {
+            return;
+        }
+
+        for chunk in leaves {
+            assert!(
+                chunk.len() >= GapBuffer::<MAX_BYTES>::chunk_min(),
+                "The chunk {:?} was supposed to contain at least {} bytes \
+                 but actually contains {}",
+                chunk,
+                GapBuffer::<MAX_BYTES>::chunk_min(),
+                chunk.len()
+            );
+
+            chunk.assert_invariants();
+        }
+    }
+
+    /// Returns the byte at `byte_index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte index is out of bounds (i.e. greater than or equal
+    /// to [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("bar");
+    ///
+    /// assert_eq!(r.byte(0), b'b');
+    /// assert_eq!(r.byte(1), b'a');
+    /// assert_eq!(r.byte(2), b'r');
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn byte(&self, byte_index: usize) -> u8 {
+        if byte_index >= self.byte_len() {
+            panic::byte_index_out_of_bounds(byte_index, self.byte_len());
+        }
+
+        let (chunk, ByteMetric(chunk_byte_offset)) =
+            self.tree.leaf_at_measure(ByteMetric(byte_index + 1));
+
+        chunk.byte(byte_index - chunk_byte_offset)
+    }
+
+    /// Returns the length of the `Rope` in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("ƒoo");
+    ///
+    /// assert_eq!(r.byte_len(), 4);
+    /// ```
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.tree.summary().bytes()
+    }
+
+    /// Returns the byte offset of the start of the given line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line offset is out of bounds (i.e. greater than
+    /// [`line_len()`](Self::line_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("ƒoo\nbär\r\nbaz");
+    ///
+    /// assert_eq!(r.byte_of_line(0), 0);
+    /// assert_eq!(r.byte_of_line(1), "ƒoo\n".len());
+    /// assert_eq!(r.byte_of_line(2), "ƒoo\nbär\r\n".len());
+    /// assert_eq!(r.byte_of_line(r.line_len()), r.byte_len());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn byte_of_line(&self, line_offset: usize) -> usize {
+        if line_offset > self.line_len() {
+            panic::line_offset_out_of_bounds(line_offset, self.line_len());
+        }
+
+        if line_offset > self.tree.summary().line_breaks() {
+            return self.byte_len();
+        }
+
+        let ByteMetric(byte_offset) =
+            self.tree.convert_measure(RawLineMetric(line_offset));
+
+        byte_offset
+    }
+
+    /// Returns the byte offset corresponding to the given UTF-16 code unit
+    /// offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the UTF-16 code unit offset is out of bounds (i.e. greater
+    /// than [`utf16_len()`](Self::utf16_len())) or if it doesn't lie on a code
+    /// point boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// // The "𐐀" character is encoded using two code units in UTF-16 and
+    /// // four bytes in UTF-8.
+    /// let r = Rope::from("a𐐀b");
+    /// assert_eq!(r.byte_of_utf16_code_unit(3), 5);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "utf16-metric")))]
+    #[cfg(feature = "utf16-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn byte_of_utf16_code_unit(&self, utf16_offset: usize) -> usize {
+        if utf16_offset > self.utf16_len() {
+            panic::utf16_offset_out_of_bounds(utf16_offset, self.utf16_len())
+        }
+
+        if self.is_ascii() {
+            return utf16_offset;
+        }
+
+        let ByteMetric(byte_offset) = self
+            .tree
+            .convert_measure(super::metrics::Utf16Metric(utf16_offset));
+
+        byte_offset
+    }
+
+    /// Returns an immutable slice of the `Rope` in the specified byte range,
+    /// where the start and end of the range are interpreted as offsets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start or the end of the byte range don't lie on a code
+    /// point boundary, if the start is greater than the end or if the end is
+    /// out of bounds (i.e. greater than [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("🗻∈🌏");
+    ///
+    /// assert_eq!(r.byte_slice(..4), "🗻");
+    /// assert_eq!(r.byte_slice(4..7), "∈");
+    /// assert_eq!(r.byte_slice(7..), "🌏");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn byte_slice<R>(
+        &self,
+        byte_range: R,
+    ) -> RopeSlice<'_, C, ARITY, MAX_BYTES>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.byte_len());
+
+        if start > end {
+            panic::byte_start_after_end(start, end);
+        }
+
+        if end > self.byte_len() {
+            panic::byte_offset_out_of_bounds(end, self.byte_len());
+        }
+
+        self.tree.slice(ByteMetric(start)..ByteMetric(end)).into()
+    }
+
+    /// Returns an iterator over the bytes of this `Rope`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo");
+    ///
+    /// let mut bytes = r.bytes();
+    ///
+    /// assert_eq!(Some(b'f'), bytes.next());
+    /// assert_eq!(Some(b'o'), bytes.next());
+    /// assert_eq!(Some(b'o'), bytes.next());
+    /// assert_eq!(None, bytes.next());
+    /// ```
+    #[inline]
+    pub fn bytes(&self) -> Bytes<'_, C, ARITY, MAX_BYTES> {
+        Bytes::from(self)
+    }
+
+    /// Returns an iterator over the [`char`]s of this `Rope`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("🐻‍❄️");
+    ///
+    /// let mut chars = r.chars();
+    ///
+    /// assert_eq!(Some('🐻'), chars.next());
+    /// assert_eq!(Some('\u{200d}'), chars.next());
+    /// assert_eq!(Some('❄'), chars.next());
+    /// assert_eq!(Some('\u{fe0f}'), chars.next());
+    /// assert_eq!(None, chars.next());
+    /// ```
+    #[inline]
+    pub fn chars(&self) -> Chars<'_, C, ARITY, MAX_BYTES> {
+        Chars::from(self)
+    }
+
+    /// Returns an iterator over the chunks of this [`Rope`].
+    #[inline]
+    pub fn chunks(&self) -> Chunks<'_, C, ARITY, MAX_BYTES> {
+        Chunks::from(self)
+    }
+
+    /// Deletes all the contents of the `Rope`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello Earth 🌎!");
+    ///
+    /// r.clear();
+    /// assert!(r.is_empty());
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Rewrites the tree of the `Rope` so that its chunks are as full as
+    /// possible and use exactly as much memory as their text, and its
+    /// internal nodes have as many children as possible.
+    ///
+    /// After many edits a `Rope` can end up with lots of chunks that are
+    /// only partially filled, which waste memory and make the tree deeper
+    /// than needed. Compacting it packs the text the same way a freshly
+    /// built `Rope` would, and drops any memory that was only kept alive by
+    /// the old tree. A chunk gets its full capacity back the first time it's
+    /// edited, like after calling [`shrink_to_fit()`](Self::shrink_to_fit()).
+    ///
+    /// The text of every chunk is copied, including the ones borrowed from a
+    /// memory-mapped file or compressed, so this runs in `O(n)` time in the
+    /// length of the `Rope`. Since the tree is rebuilt, [`changes_since()`]
+    /// will report the whole `Rope` as changed when called with a snapshot
+    /// taken before compacting it, unless the `node-hashes` feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello, world!\n".repeat(1000));
+    ///
+    /// for line in (0..1000).step_by(10) {
+    ///     let start = r.byte_of_line(line);
+    ///     r.insert(start, "Hi! ");
+    /// }
+    ///
+    /// let before = r.mem_stats();
+    ///
+    /// r.compact();
+    ///
+    /// let after = r.mem_stats();
+    /// assert_eq!(after.slack_bytes, 0);
+    /// assert!(after.total_bytes() < before.total_bytes());
+    /// assert!(after.fill_factor() > before.fill_factor());
+    /// assert_eq!(r.line(10), "Hi! Hello, world!");
+    /// ```
+    ///
+    /// [`changes_since()`]: Self::changes_since()
+    #[inline]
+    pub fn compact(&mut self) {
+        let mut builder = RopeBuilder::empty();
+
+        for chunk in self.chunks() {
+            builder.append(chunk);
+        }
+
+        self.tree = builder.build().tree;
+        self.shrink_to_fit();
+    }
+
+    /// Compresses the chunks of the `Rope` to reduce its memory usage.
+    ///
+    /// The text of every chunk is compressed with [LZ4], and it's decompressed
+    /// the first time it's accessed. The decompressed copies are then kept
+    /// in an LRU cache of 64 chunks per `Rope`, i.e. 128 KiB of text with the
+    /// default chunk size, so that reading the same parts of the `Rope` over
+    /// and over only decompresses them once. Once 64 more chunks have been
+    /// decompressed, the next edit drops the copies of all the chunks except
+    /// the 64 that were read most recently, so a `Rope` never has more than
+    /// 128 of them right after an edit. Calling this method again drops all
+    /// of them. A chunk is kept decompressed once it's edited.
+    ///
+    /// The copies can't be dropped while the `Rope` is only borrowed, since
+    /// the `&str`s returned by its methods may point into them, so reading
+    /// the whole `Rope` through its chunks, lines or slices without editing
+    /// it decompresses it all until the next edit or call to this method.
+    /// Formatting the `Rope`, e.g. with `to_string()`, and computing its
+    /// [`content_hash()`](Self::content_hash()) don't hand out any `&str`,
+    /// so they decompress the chunks without keeping a copy of them.
+    ///
+    /// This is meant for large documents that are mostly read, e.g. logs,
+    /// where only the parts that are being looked at need to be in memory
+    /// uncompressed. Chunks that don't get any smaller when compressed, and
+    /// the ones borrowed from a memory-mapped file, are left as they are.
+    ///
+    /// The compressed chunks replace the original ones, so
+    /// [`changes_since()`] will report them as changed when called with a
+    /// snapshot taken before compressing the `Rope`, unless the
+    /// `node-hashes` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello, world!\n".repeat(1000));
+    ///
+    /// r.compress();
+    /// assert_eq!(r.line(500), "Hello, world!");
+    ///
+    /// r.insert(0, "Hi! ");
+    /// assert_eq!(r.line(0), "Hi! Hello, world!");
+    /// ```
+    ///
+    /// [LZ4]: https://lz4.org
+    /// [`changes_since()`]: Self::changes_since()
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    #[cfg(feature = "compression")]
+    #[inline]
+    pub fn compress(&mut self) {
+        self.map_leaves(GapBuffer::<MAX_BYTES>::compressed);
+    }
+
+    /// Returns a 64-bit hash of the text of the `Rope`, which only depends
+    /// on its contents and not on how they're split into chunks.
+    ///
+    /// With the `content-hash` feature the hash of every chunk is kept in
+    /// its summary and combined up the tree on every edit, so this takes
+    /// `O(1)` time, which makes it a cheap fingerprint for caching layers
+    /// that need to know whether a text has changed. Without it the hash is
+    /// computed in a single pass over the chunks, and it's only cached until
+    /// the next edit by `Rope`s that already keep some extra state, e.g.
+    /// because they have marks or subscribers. `Rope`s with the same hash
+    /// are only equal with high probability, so use `==` if a false positive
+    /// would be a problem.
+    ///
+    /// The hash is the same one returned by [`RopeSlice::content_hash()`]
+    /// for a slice with the same contents, but it's not guaranteed to be
+    /// stable across versions of crop.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello, world!");
+    /// let hash = r.content_hash();
+    ///
+    /// r.insert(5, "!");
+    /// assert_ne!(r.content_hash(), hash);
+    ///
+    /// r.delete(5..6);
+    /// assert_eq!(r.content_hash(), hash);
+    /// assert_eq!(r.byte_slice(..).content_hash(), hash);
+    /// ```
+    #[inline]
+    pub fn content_hash(&self) -> u64 {
+        #[cfg(feature = "content-hash")]
+        if let Some(hash) = self.tree.summary().content_hash() {
+            return hash;
+        }
+
+        self.content_hash.get_or_compute(|| {
+            let mut hash = PolyHash::default();
+
+            let _ = self.try_for_each_chunk(|chunk| {
+                hash = hash.concat(PolyHash::from(chunk));
+                Ok::<_, core::convert::Infallible>(())
+            });
+
+            hash.finish()
+        })
+    }
+
+    /// Removes consecutive repeated lines within the given line range,
+    /// keeping the first one of every run of equal lines.
+    ///
+    /// Lines are compared without their line breaks, and every remaining
+    /// line keeps its own line break.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`line_slice()`](Self::line_slice()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("foo\nfoo\r\nbar\nbar\nfoo");
+    ///
+    /// r.dedup_lines(..);
+    /// assert_eq!(r, "foo\nbar\nfoo");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn dedup_lines<R>(&mut self, line_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.replace_lines(line_range, |lines| {
+            lines.dedup_by(|a, b| a.0 == b.0)
+        });
+    }
+
+    /// Deletes the contents of the `Rope` within the specified byte range,
+    /// where the start and end of the range are interpreted as offsets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start or the end of the byte range don't lie on a code
+    /// point boundary, if the start is greater than the end or if the end is
+    /// out of bounds (i.e. greater than [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello Earth 🌎!");
+    ///
+    /// r.delete(5..16);
+    /// assert_eq!(r, "Hello!");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn delete<R>(&mut self, byte_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.replace(byte_range, "");
+    }
+
+    /// Like [`delete()`](Self::delete()), but returns the region of the
+    /// `Rope` affected by the edit.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`delete()`](Self::delete()).
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn delete_with_info<R>(&mut self, byte_range: R) -> EditRegion
+    where
+        R: RangeBounds<usize>,
+    {
+        self.replace_with_info(byte_range, "")
+    }
+
+    /// Returns the most common line ending in the `Rope`, which is the one
+    /// editors should use for new lines to preserve its EOL convention.
+    ///
+    /// Line endings are counted like in
+    /// [`normalize_eols()`](Self::normalize_eols()). Ties are broken by
+    /// preferring line feeds, then CRLF sequences, and a `Rope` without any
+    /// line endings is reported as using line feeds.
+    ///
+    /// The line endings aren't tracked by the tree, so this scans the whole
+    /// text and runs in time linear in the byte length of the `Rope`. It's
+    /// meant to be called once after loading a file, with the result kept
+    /// around for as long as it's needed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{LineEnding, Rope};
+    /// #
+    /// let r = Rope::from("foo\r\nbar\r\nbaz\n");
+    /// assert_eq!(r.detect_line_ending(), LineEnding::CrLf);
+    ///
+    /// let r = Rope::from("foo");
+    /// assert_eq!(r.detect_line_ending(), LineEnding::Lf);
+    /// ```
+    #[inline]
+    pub fn detect_line_ending(&self) -> LineEnding {
+        let mut counts = LineEndingCounts::default();
+        self.for_each_line_ending(|_, eol| counts.add(eol));
+        counts.most_common()
+    }
+
+    /// Returns the digest of the text of the `Rope` computed with the given
+    /// [`Digest`](digest::Digest), e.g. SHA-256 or BLAKE3.
+    ///
+    /// The chunks of the `Rope` are fed to the hasher one after the other,
+    /// so no intermediate `String` is allocated. The result is the same as
+    /// the digest of the text as a whole.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// use sha2::{Digest, Sha256};
+    ///
+    /// let r = Rope::from("Hello, world!");
+    ///
+    /// assert_eq!(r.digest::<Sha256>(), Sha256::digest("Hello, world!"));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+    #[cfg(feature = "digest")]
+    #[inline]
+    pub fn digest<D: digest::Digest>(&self) -> digest::Output<D> {
+        self.byte_slice(..).digest::<D>()
+    }
+
+    /// Performs an edit replacing the text within the given byte range with
+    /// `inserted_len` bytes of new text.
+    ///
+    /// The edit itself is carried out by `edit`, which is responsible for
+    /// updating both the tree and the trailing newline flag. After that this
+    /// bumps the revision and updates the dirty lines, the marks and the
+    /// subscribers, so every edit of the `Rope` should go through here.
+    ///
+    /// Returns the number of bytes deleted from the start of the `Rope` to
+    /// enforce its limit after the edit, if any.
+    #[inline]
+    fn edit_with<F>(
+        &mut self,
+        byte_range: Range<usize>,
+        inserted_len: usize,
+        edit: F,
+    ) -> usize
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.edits_with(&[EditInfo { byte_range, inserted_len }], edit)
+            .map_or(0, |deletion| deletion.byte_range.end)
+    }
+
+    /// Like [`edit_with()`](Self::edit_with()), but for a batch of edits
+    /// which are all carried out by `edit`, returning the deletion done to
+    /// enforce the limit, if any.
+    ///
+    /// The edits must be sorted from last to first and not overlap, so that
+    /// they're relative both to the text before the batch and to the text
+    /// left by the edits before them. The bookkeeping is only done once for
+    /// the whole batch, and the marks and the subscribers see the edits in
+    /// order once they've all been carried out.
+    #[inline]
+    fn edits_with<F>(
+        &mut self,
+        edits: &[EditInfo],
+        edit: F,
+    ) -> Option<EditInfo>
+    where
+        F: FnOnce(&mut Self),
+    {
+        debug_assert!(!edits.is_empty());
+
+        let len_after = edits.iter().try_fold(self.byte_len(), |len, edit| {
+            TooLongError::check(len, edit.byte_range.len(), edit.inserted_len)
+                .map(|()| len - edit.byte_range.len() + edit.inserted_len)
+        });
+
+        if len_after.is_err() {
+            panic::rope_too_long();
+        }
+
+        // The range covering all the edits, and the number of bytes it spans
+        // after them.
+        #[cfg(feature = "line-metric")]
+        let (start, end, inserted_len) = {
+            let start = edits.last().unwrap().byte_range.start;
+            let end = edits[0].byte_range.end;
+            let inserted_len = edits.iter().fold(end - start, |len, edit| {
+                len - edit.byte_range.len() + edit.inserted_len
+            });
+            (start, end, inserted_len)
+        };
+
+        #[cfg(feature = "line-metric")]
+        let dirty_lines_before = self
+            .extras
+            .as_ref()
+            .map_or(false, |extras| extras.dirty_lines.is_some())
+            .then(|| (self.line_of_byte(start), self.line_of_byte(end)));
+
+        edit(self);
+
+        #[cfg(any(feature = "content-hash", feature = "word-metric"))]
+        self.tree.refresh_summaries(
+            ChunkSummary::is_stale,
+            GapBuffer::<MAX_BYTES>::refresh_summary,
+        );
+
+        self.revision = self.revision.next(self.revision_is_shared);
+        self.revision_is_shared = false;
+
+        self.content_hash.clear();
+
+        #[cfg(feature = "compression")]
+        self.evict_decompressed();
+
+        #[cfg(feature = "line-metric")]
+        if let Some((start_line, old_end_line)) = dirty_lines_before {
+            let new_end_line = self.line_of_byte(start + inserted_len);
+
+            if let Some(dirty_lines) = self
+                .extras
+                .as_mut()
+                .and_then(|extras| extras.dirty_lines.as_mut())
+            {
+                dirty_lines.record(start_line, old_end_line, new_end_line);
+            }
+        }
+
+        if let Some(extras) = &mut self.extras {
+            for edit in edits {
+                extras.marks.edit(edit);
+                extras.subscribers.notify(edit);
+
+                #[cfg(feature = "paging")]
+                if let Some(recent_edits) = &mut extras.recent_edits {
+                    recent_edits.record(edit);
+                }
+            }
+        }
+
+        // Deleting the excess text is an edit of its own, which is seen by
+        // the marks and the subscribers after the one that caused it. Any
+        // edit can make the `Rope` exceed its limit, even one that shortens
+        // it, e.g. by replacing a character with a line break.
+        self.enforce_limit()
+    }
+
+    /// Drops the decompressed copies of all but the
+    /// [`MAX_DECOMPRESSED_CHUNKS`] chunks that were read most recently.
+    ///
+    /// This walks all the leaves, so it only does it if more than that many
+    /// chunks are decompressed across all the `Rope`s, and if at least as
+    /// many have been decompressed since the last time it did.
+    #[cfg(feature = "compression")]
+    #[inline]
+    fn evict_decompressed(&mut self) {
+        type NodeRef<'a, const ARITY: usize, const MAX_BYTES: usize, C> =
+            crate::tree::NodeRef<'a, ARITY, GapBuffer<MAX_BYTES>, C>;
+
+        fn collect<const ARITY: usize, const MAX_BYTES: usize, C: RefCount>(
+            node: NodeRef<'_, ARITY, MAX_BYTES, C>,
+            leaf_idx: &mut usize,
+            decompressed: &mut Vec<(usize, usize)>,
+        ) {
+            match node.leaf() {
+                Some(leaf) => {
+                    if let Some(last_read) = leaf.last_read_decompressed() {
+                        decompressed.push((last_read, *leaf_idx));
+                    }
+                    *leaf_idx += 1;
+                },
+                None => node
+                    .children()
+                    .for_each(|c| collect(c, leaf_idx, decompressed)),
+            }
+        }
+
+        let decompressions = chunk_bytes::decompressions();
+
+        let swept_at =
+            self.extras.as_ref().map_or(0, |extras| extras.swept_at);
+
+        if chunk_bytes::decompressed() <= MAX_DECOMPRESSED_CHUNKS
+            || decompressions - swept_at < MAX_DECOMPRESSED_CHUNKS
+        {
+            return;
+        }
+
+        self.extras_mut().swept_at = decompressions;
+
+        let mut decompressed = Vec::new();
+        collect(self.tree.root_ref(), &mut 0, &mut decompressed);
+
+        if decompressed.len() <= MAX_DECOMPRESSED_CHUNKS {
+            return;
+        }
+
+        // Keep the most recently read chunks, and evict the others in the
+        // order they appear in the tree.
+        decompressed.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut to_evict = decompressed[MAX_DECOMPRESSED_CHUNKS..]
+            .iter()
+            .map(|&(_, leaf_idx)| leaf_idx)
+            .collect::<Vec<_>>();
+
+        to_evict.sort_unstable();
+
+        self.tree.update_leaves(
+            &to_evict,
+            GapBuffer::<MAX_BYTES>::evict_decompressed,
+        );
+    }
+
+    /// Returns a new empty `Rope`.
+    ///
+    /// Unlike [`Rope::new()`], which always returns a `Rope` with the default
+    /// reference count and node sizes, this works for any of them, so they
+    /// have to be known from the context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{tree::LocalCount, Rope};
+    /// #
+    /// let mut r = Rope::<LocalCount>::empty();
+    /// assert!(r.is_empty());
+    ///
+    /// r.insert(0, "Hello");
+    /// assert_eq!(r, "Hello");
+    /// ```
+    #[inline]
+    pub fn empty() -> Self {
+        Self::from_tree(Tree::default(), false)
+    }
+
+    /// Returns an iterator over the UTF-16 code units of this `Rope`.
+    ///
+    /// This is useful to hand the text over to environments storing strings
+    /// as UTF-16, like JavaScript, without collecting it into a `String`
+    /// first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("a🌎");
+    ///
+    /// let code_units = r.encode_utf16().collect::<Vec<_>>();
+    ///
+    /// assert_eq!(code_units, [0x61, 0xd83c, 0xdf0e]);
+    /// ```
+    #[inline]
+    pub fn encode_utf16(&self) -> EncodeUtf16<'_, C, ARITY, MAX_BYTES> {
+        EncodeUtf16::from(self)
+    }
+
+    /// Deletes the text at the start of the `Rope` exceeding its
+    /// [`limit()`](Self::limit()), if any, returning the deletion.
+    #[inline]
+    fn enforce_limit(&mut self) -> Option<EditInfo> {
+        let excess = self.limit()?.excess(self);
+
+        if excess > 0 {
+            self.delete(..excess);
+            Some(EditInfo { byte_range: 0..excess, inserted_len: 0 })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the [`RopeExtras`], allocating them if this is the first
+    /// time they're needed.
+    #[inline]
+    fn extras_mut(&mut self) -> &mut RopeExtras {
+        self.extras.get_or_insert_with(Box::default)
+    }
+
+    /// Reads UTF-8 text from `reader` until it reaches its end and appends
+    /// it to the `Rope`, returning the number of bytes appended.
+    ///
+    /// Since the reader is only read up to its current end, calling this
+    /// again on the same reader later appends the text that was written to
+    /// it in the meantime, which together with
+    /// [`poll_tail()`](Self::poll_tail()) makes it easy to follow a growing
+    /// file like `tail -f` does. If the text read ends in the middle of a
+    /// character, its bytes are kept in the [`Tail`] and the character is
+    /// only appended once the next call reads the rest of it. Nothing is
+    /// appended if there's no new complete character to read, so the
+    /// revision of the `Rope` doesn't change.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidData`] if the text isn't valid
+    /// UTF-8, or any error returned by the reader. Nothing is appended in
+    /// that case.
+    ///
+    /// [`InvalidData`]: std::io::ErrorKind::InvalidData
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("foo\n");
+    ///
+    /// let mut tail = r.follow_tail();
+    ///
+    /// // The first read ends in the middle of the 'ä'.
+    /// let (first, second): (&[u8], &[u8]) = (b"b\xc3", b"\xa4r\n");
+    ///
+    /// assert_eq!(r.extend_from_reader(&mut tail, first).unwrap(), 1);
+    /// assert_eq!(r.poll_tail(&tail), "b");
+    ///
+    /// assert_eq!(r.extend_from_reader(&mut tail, second).unwrap(), 4);
+    /// assert_eq!(r.poll_tail(&tail), "är\n");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn extend_from_reader<R: std::io::Read>(
+        &mut self,
+        tail: &mut Tail,
+        mut reader: R,
+    ) -> std::io::Result<usize> {
+        let mut bytes = tail.incomplete().to_vec();
+
+        let carried = bytes.len();
+
+        reader.read_to_end(&mut bytes)?;
+
+        let invalid_data = |valid_up_to: usize| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                alloc::format!(
+                    "stream did not contain valid UTF-8 at byte offset {}",
+                    valid_up_to.saturating_sub(carried)
+                ),
+            )
+        };
+
+        // The bytes of a character cut in half by the end of the input are
+        // carried over to the next read.
+        let complete_len = super::utils::complete_utf8_len(&bytes);
+
+        let text = super::utils::from_utf8(&bytes[..complete_len])
+            .map_err(|err| invalid_data(err.valid_up_to()))?;
+
+        let incomplete = &bytes[complete_len..];
+
+        if let Err(err) = core::str::from_utf8(incomplete) {
+            if err.error_len().is_some() {
+                return Err(invalid_data(complete_len + err.valid_up_to()));
+            }
+        }
+
+        if !text.is_empty() {
+            self.push_str(text);
+        }
+
+        tail.set_incomplete(incomplete);
+
+        Ok(text.len())
+    }
+
+    /// Calls `f` with the byte range and the kind of every line ending of the
+    /// `Rope`, in order, in a single pass over its chunks.
+    ///
+    /// CRLF sequences, line feeds and carriage returns not followed by a line
+    /// feed are all considered line endings, and CRLF sequences split across
+    /// two chunks are reported once.
+    #[inline]
+    fn for_each_line_ending<F>(&self, mut f: F)
+    where
+        F: FnMut(Range<usize>, LineEnding),
+    {
+        // The offset of the last carriage return, if it may still be
+        // followed by a line feed at the start of the next chunk.
+        let mut pending_cr = None;
+
+        let mut chunk_offset = 0;
+
+        for chunk in self.chunks() {
+            for (idx, eol) in chunk.match_indices(['\r', '\n']) {
+                let offset = chunk_offset + idx;
+
+                match (pending_cr.take(), eol) {
+                    (Some(cr), "\n") if cr + 1 == offset => {
+                        f(cr..offset + 1, LineEnding::CrLf);
+                        continue;
+                    },
+                    (Some(cr), _) => f(cr..cr + 1, LineEnding::Cr),
+                    (None, _) => {},
+                }
+
+                if eol == "\r" {
+                    pending_cr = Some(offset);
+                } else {
+                    f(offset..offset + 1, LineEnding::Lf);
+                }
+            }
+
+            chunk_offset += chunk.len();
+        }
+
+        if let Some(cr) = pending_cr {
+            f(cr..cr + 1, LineEnding::Cr);
+        }
+    }
+
+    /// Returns a new `Rope` built by passing every character of this one to
+    /// `f`, keeping the ones it maps to `Some` and dropping the others.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo\tbar\u{7}\r\n");
+    ///
+    /// let stripped = r.filter_map_chars(|ch| match ch {
+    ///     '\t' => Some(' '),
+    ///     '\n' => Some('\n'),
+    ///     _ if ch.is_control() => None,
+    ///     _ => Some(ch),
+    /// });
+    ///
+    /// assert_eq!(stripped, "foo bar\n");
+    /// ```
+    #[inline]
+    pub fn filter_map_chars<F>(&self, mut f: F) -> Self
+    where
+        F: FnMut(char) -> Option<char>,
+    {
+        let mut builder = RopeBuilder::empty();
+
+        let mut buffer = String::new();
+
+        for chunk in self.chunks() {
+            buffer.clear();
+            buffer.extend(chunk.chars().filter_map(&mut f));
+            builder.append(&buffer);
+        }
+
+        builder.build()
+    }
+
+    /// Returns a [`Tail`] at the end of the `Rope`, which can be passed to
+    /// [`poll_tail()`](Self::poll_tail()) to get the text appended after it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("foo\n");
+    ///
+    /// let tail = r.follow_tail();
+    ///
+    /// r.push_str("bar\n");
+    /// r.push_str("baz\n");
+    /// assert_eq!(r.poll_tail(&tail), "bar\nbaz\n");
+    /// assert_eq!(r.poll_tail(&tail), "");
+    /// ```
+    #[inline]
+    pub fn follow_tail(&mut self) -> Tail {
+        Tail::new(self.add_mark(self.byte_len(), Gravity::Left))
+    }
+
+    /// Returns a new `Rope` with the given text.
+    ///
+    /// This is the same as `Rope::from(text)`, except that it works for any
+    /// reference count and node sizes, which have to be known from the
+    /// context.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the text is longer than
+    /// [`MAX_BYTE_LEN`](Self::MAX_BYTE_LEN).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{tree::AtomicCount, Rope};
+    /// #
+    /// let r = Rope::<AtomicCount, 32, 16384>::from_text("foo\nbar\n");
+    /// assert_eq!(r.line(1), "bar");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn from_text<T: AsRef<str>>(text: T) -> Self {
+        let text = text.as_ref();
+
+        if TooLongError::check(0, 0, text.len()).is_err() {
+            panic::rope_too_long();
+        }
+
+        Self::from_tree(
+            Tree::from_leaves(
+                GapBuffer::<MAX_BYTES>::segmenter(text).map(GapBuffer::from),
+            ),
+            ends_with_line_break(text),
+        )
+    }
+
+    /// Creates a new `Rope` from its tree.
+    #[inline]
+    pub(super) fn from_tree(
+        #[cfg_attr(not(feature = "word-metric"), allow(unused_mut))]
+        mut tree: Tree<ARITY, GapBuffer<MAX_BYTES>, C>,
+        has_trailing_newline: bool,
+    ) -> Self {
+        let () = Self::VALID_NODE_SIZES;
+
+        // Splitting and joining trees can leave some of the word counts
+        // stale, and `word_len()` should never have to recount them.
+        #[cfg(feature = "word-metric")]
+        tree.refresh_summaries(
+            ChunkSummary::is_stale,
+            GapBuffer::<MAX_BYTES>::refresh_summary,
+        );
+
+        Self {
+            tree,
+            has_trailing_newline,
+            content_hash: ContentHashCache::default(),
+            revision: Revision::new(),
+            revision_is_shared: false,
+            extras: None,
+        }
+    }
+
+    /// Returns an iterator over the extended grapheme clusters of this
+    /// `Rope`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("arg!\r\n🐻‍❄️");
+    ///
+    /// let mut graphemes = r.graphemes();
+    ///
+    /// assert_eq!(Some("a"), graphemes.next().as_deref());
+    /// assert_eq!(Some("r"), graphemes.next().as_deref());
+    /// assert_eq!(Some("g"), graphemes.next().as_deref());
+    /// assert_eq!(Some("!"), graphemes.next().as_deref());
+    /// assert_eq!(Some("\r\n"), graphemes.next().as_deref());
+    /// assert_eq!(Some("🐻‍❄️"), graphemes.next().as_deref());
+    /// assert_eq!(None, graphemes.next());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "graphemes")))]
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn graphemes(
+        &self,
+    ) -> crate::iter::Graphemes<'_, C, ARITY, MAX_BYTES> {
+        crate::iter::Graphemes::from(self)
+    }
+
+    /// Inserts `text` in the `Rope` at the given byte offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset doesn't lie on a code point boundary or if
+    /// it's out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello Earth!");
+    ///
+    /// r.insert(11, " 🌎");
+    /// assert_eq!(r, "Hello Earth 🌎!");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn insert<T>(&mut self, byte_offset: usize, text: T)
+    where
+        T: AsRef<str>,
+    {
+        self.replace(byte_offset..byte_offset, text)
+    }
+
+    /// Inserts a single character in the `Rope` at the given byte offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`insert()`](Self::insert()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello Earth!");
+    ///
+    /// r.insert_char(11, '🌎');
+    /// assert_eq!(r, "Hello Earth🌎!");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn insert_char(&mut self, byte_offset: usize, ch: char) {
+        self.insert(byte_offset, ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Inserts the contents of another `Rope` at the given byte offset.
+    ///
+    /// Rather than copying the text, this splices the tree of `rope` into
+    /// the tree of `self`, sharing all of its nodes except the ones at the
+    /// seams, so it takes `O(log n)` time regardless of the length of the
+    /// inserted text.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`insert()`](Self::insert()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello!");
+    ///
+    /// r.insert_rope(5, &Rope::from(" Earth 🌎"));
+    /// assert_eq!(r, "Hello Earth 🌎!");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn insert_rope(&mut self, byte_offset: usize, rope: &Self) {
+        self.insert_slice(byte_offset, rope.byte_slice(..));
+    }
+
+    /// Inserts the contents of a [`RopeSlice`] at the given byte offset.
+    ///
+    /// Like with [`insert_rope()`](Self::insert_rope()), the nodes of the
+    /// slice are shared instead of copying its text.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`insert()`](Self::insert()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("foo bar");
+    ///
+    /// let other = Rope::from("baz qux");
+    /// r.insert_slice(4, other.byte_slice(..4));
+    /// assert_eq!(r, "foo baz bar");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn insert_slice(
+        &mut self,
+        byte_offset: usize,
+        slice: RopeSlice<'_, C, ARITY, MAX_BYTES>,
+    ) {
+        if byte_offset > self.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
+        }
+
+        // Slicing takes care of checking that the offset is on a code point
+        // boundary.
+        let _ = self.byte_slice(byte_offset..byte_offset);
+
+        let inserted = Tree::from(slice.tree_slice);
+
+        self.edit_with(byte_offset..byte_offset, slice.byte_len(), |this| {
+            if byte_offset == this.byte_len() && slice.byte_len() > 0 {
+                this.has_trailing_newline = slice.has_trailing_newline;
+            }
+
+            this.splice_tree(byte_offset, inserted);
+        });
+    }
+
+    /// Splices `inserted` into the tree of the `Rope` at the given byte
+    /// offset by joining it with the trees of the text before and after the
+    /// offset, which share all their nodes with the current tree except the
+    /// ones along the split.
+    #[inline]
+    fn splice_tree(
+        &mut self,
+        byte_offset: usize,
+        inserted: Tree<ARITY, GapBuffer<MAX_BYTES>, C>,
+    ) {
+        let byte_len = self.byte_len();
+
+        let mut tree = if byte_offset == 0 {
+            Tree::default()
+        } else {
+            Tree::from(self.tree.slice(ByteMetric(0)..ByteMetric(byte_offset)))
+        };
+
+        tree.append(inserted);
+
+        if byte_offset < byte_len {
+            tree.append(Tree::from(
+                self.tree.slice(ByteMetric(byte_offset)..ByteMetric(byte_len)),
+            ));
+        }
+
+        self.tree = tree;
+    }
+
+    /// Like [`insert()`](Self::insert()), but returns the region of the
+    /// `Rope` affected by the edit.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`insert()`](Self::insert()).
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn insert_with_info<T>(
+        &mut self,
+        byte_offset: usize,
+        text: T,
+    ) -> EditRegion
+    where
+        T: AsRef<str>,
+    {
+        self.replace_with_info(byte_offset..byte_offset, text)
+    }
+
+    /// Makes the chunks of the `Rope` share their text with every other
+    /// chunk with the same contents interned by `interner`, including the
+    /// ones of other `Rope`s.
+    ///
+    /// Only whole chunks are compared, so this saves memory when the same
+    /// text is loaded several times or when it repeats at the same chunk
+    /// boundaries, e.g. in very repetitive logs or generated files. A shared
+    /// chunk is copied the first time it's edited. Chunks borrowed from a
+    /// memory-mapped file or compressed are left as they are.
+    ///
+    /// The interned chunks replace the original ones, so [`changes_since()`]
+    /// will report them as changed when called with a snapshot taken before
+    /// interning them, unless the `node-hashes` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{ChunkInterner, Rope};
+    /// #
+    /// let text = "Hello, world!\n".repeat(1000);
+    ///
+    /// let mut interner = ChunkInterner::new();
+    ///
+    /// let mut a = Rope::from(&*text);
+    /// a.intern_chunks(&mut interner);
+    /// let num_chunks = interner.len();
+    ///
+    /// // The chunks of `b` are the same as the ones of `a`, so they're
+    /// // shared instead of being interned again.
+    /// let mut b = Rope::from(&*text);
+    /// b.intern_chunks(&mut interner);
+    /// assert_eq!(interner.len(), num_chunks);
+    ///
+    /// b.insert(0, "Hi! ");
+    /// assert_eq!(a, text);
+    /// assert_eq!(b.line(0), "Hi! Hello, world!");
+    /// ```
+    ///
+    /// [`changes_since()`]: Self::changes_since()
+    #[inline]
+    pub fn intern_chunks(&mut self, interner: &mut ChunkInterner) {
+        self.map_leaves(|leaf| leaf.interned(interner));
+    }
+
+    /// Returns `true` if all the text in the `Rope` is ASCII.
+    ///
+    /// This runs in `O(1)` time, and a few methods use it to skip work which
+    /// is only needed for non-ASCII text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("Hello, world!");
+    /// assert!(r.is_ascii());
+    ///
+    /// let r = Rope::from("Hello, 🌎!");
+    /// assert!(!r.is_ascii());
+    /// ```
+    #[inline]
+    pub fn is_ascii(&self) -> bool {
+        self.tree.summary().is_ascii()
+    }
+
+    /// Returns `true` if the given byte offset lies on a [`char`] boundary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("Löwe 老虎 Léopard");
+    ///
+    /// assert!(r.is_char_boundary(0));
+    /// assert!(r.is_char_boundary(r.byte_len()));
+    /// assert!(r.is_char_boundary(6)); // between ' ' and '老'
+    /// assert!(!r.is_char_boundary(2)); // between the 1st and 2nd byte of 'ö'
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn is_char_boundary(&self, byte_offset: usize) -> bool {
+        if byte_offset > self.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
+        }
+
+        if self.is_ascii() {
+            return true;
+        }
+
+        let (chunk, ByteMetric(chunk_byte_offset)) =
+            self.tree.leaf_at_measure(ByteMetric(byte_offset));
+
+        chunk.is_char_boundary(byte_offset - chunk_byte_offset)
+    }
+
+    /// Returns `true` if the `Rope`'s byte length is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::new();
+    /// assert!(r.is_empty());
+    ///
+    /// r.insert(0, "hey");
+    /// assert!(!r.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.byte_len() == 0
+    }
+
+    /// Returns `true` if the given byte offset lies on a grapheme cluster
+    /// boundary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("aargh!\r\n🐻‍❄️");
+    ///
+    /// assert!(r.is_grapheme_boundary(0));
+    /// assert!(r.is_grapheme_boundary(r.byte_len()));
+    /// assert!(!r.is_grapheme_boundary(7)); // between '\r' and '\n'
+    /// assert!(r.is_grapheme_boundary(8)); // between '\n' and '🐻‍❄️'
+    /// assert!(!r.is_grapheme_boundary(12)); // between the 1st and 2nd code point of '🐻‍❄️'
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "graphemes")))]
+    #[cfg(feature = "graphemes")]
+    #[track_caller]
+    #[inline]
+    pub fn is_grapheme_boundary(&self, byte_offset: usize) -> bool {
+        if byte_offset > self.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
+        }
+
+        if byte_offset == 0 || byte_offset == self.byte_len() {
+            return true;
+        }
+
+        // The only ASCII grapheme cluster made of more than one character
+        // is a CRLF sequence, so there's no need to segment the text if the
+        // offset is between two ASCII characters.
+        let prev = self.byte(byte_offset - 1);
+        let next = self.byte(byte_offset);
+
+        if prev.is_ascii() && next.is_ascii() {
+            return prev != b'\r' || next != b'\n';
+        }
+
+        is_grapheme_boundary(self.chunks(), self.byte_len(), byte_offset)
+    }
+
+    /// Returns the cap on the length of the `Rope` set with
+    /// [`set_limit()`](Self::set_limit()), if any.
+    #[inline]
+    pub fn limit(&self) -> Option<Limit> {
+        self.extras.as_ref().and_then(|extras| extras.limit)
+    }
+
+    /// Returns the line at `line_index`, without its line terminator.
+    ///
+    /// If you want to include the line break consider taking a
+    /// [`line_slice()`](Self::line_slice()) in the
+    /// `line_index..line_index + 1` range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line index is out of bounds (i.e. greater than or equal
+    /// to [`line_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo\nbar\r\nbaz");
+    ///
+    /// assert_eq!(r.line(0), "foo");
+    /// assert_eq!(r.line(1), "bar");
+    /// assert_eq!(r.line(2), "baz");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn line(
+        &self,
+        line_index: usize,
+    ) -> RopeSlice<'_, C, ARITY, MAX_BYTES> {
+        if line_index >= self.line_len() {
+            panic::line_index_out_of_bounds(line_index, self.line_len());
+        }
+
+        let tree_slice = self
+            .tree
+            .slice(RawLineMetric(line_index)..RawLineMetric(line_index + 1));
+
+        let mut line = RopeSlice { tree_slice, has_trailing_newline: false };
+
+        if line.tree_slice.summary().line_breaks() == 1 {
+            line.truncate_trailing_line_break();
+        }
+
+        line
+    }
+
+    /// Returns the number of lines in the `Rope`.
+    ///
+    /// The final line break is optional and doesn't count as a separate empty
+    /// line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::new();
+    ///
+    /// assert_eq!(r.line_len(), 0);
+    ///
+    /// r.insert(0, "a");
+    /// assert_eq!(r.line_len(), 1);
+    ///
+    /// r.insert(1, "\n");
+    /// assert_eq!(r.line_len(), 1);
+    ///
+    /// r.insert(2, "b");
+    /// assert_eq!(r.line_len(), 2);
+    ///
+    /// r.insert(3, "\r\n");
+    /// assert_eq!(r.line_len(), 2);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn line_len(&self) -> usize {
+        self.tree.summary().line_breaks() + 1
+            - (self.has_trailing_newline as usize)
+            - (self.is_empty() as usize)
+    }
+
+    /// Returns the number of lines in the `Rope`, where the final line break
+    /// is handled according to `trailing`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{Rope, TrailingLineBreak};
+    /// #
+    /// let r = Rope::from("foo\nbar\n");
+    ///
+    /// assert_eq!(r.line_len_with(TrailingLineBreak::EndsLine), 2);
+    /// assert_eq!(r.line_len_with(TrailingLineBreak::StartsLine), 3);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn line_len_with(&self, trailing: TrailingLineBreak) -> usize {
+        match trailing {
+            TrailingLineBreak::EndsLine => self.line_len(),
+            TrailingLineBreak::StartsLine => {
+                self.tree.summary().line_breaks() + 1
+            },
+        }
+    }
+
+    /// Returns the line offset of the given byte.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())), or if it's not a char boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo\nbar\r\nbaz");
+    ///
+    /// assert_eq!(r.line_of_byte(0), 0);
+    /// assert_eq!(r.line_of_byte(3), 0);
+    /// assert_eq!(r.line_of_byte(4), 1);
+    /// assert_eq!(r.line_of_byte(8), 1); // between the '\r' and the '\n'
+    /// assert_eq!(r.line_of_byte(r.byte_len()), 2);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn line_of_byte(&self, byte_offset: usize) -> usize {
+        if byte_offset > self.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
+        }
+
+        let RawLineMetric(line_offset) =
+            self.tree.convert_measure(ByteMetric(byte_offset));
+
+        line_offset
+    }
+
+    /// Returns an immutable slice of the `Rope` in the specified line range,
+    /// where the start and end of the range are interpreted as offsets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start is greater than the end or if the end is out of
+    /// bounds (i.e. greater than [`line_len()`](Self::line_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo\nbar\r\nbaz\nfoobar\n");
+    ///
+    /// assert_eq!(r.line_slice(..1), "foo\n");
+    /// assert_eq!(r.line_slice(1..3), "bar\r\nbaz\n");
+    /// assert_eq!(r.line_slice(3..4), "foobar\n");
+    /// assert_eq!(r.line_slice(4..), "");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn line_slice<R>(
+        &self,
+        line_range: R,
+    ) -> RopeSlice<'_, C, ARITY, MAX_BYTES>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(line_range, 0, self.line_len());
+
+        if start > end {
+            panic::line_start_after_end(start, end);
+        }
+
+        if end > self.line_len() {
+            panic::line_offset_out_of_bounds(end, self.line_len());
+        }
+
+        self.tree.slice(RawLineMetric(start)..RawLineMetric(end)).into()
+    }
+
+    /// Returns an iterator over the lines of this `Rope`, not including the
+    /// line terminators.
+    ///
+    /// The final line break is optional and doesn't cause the iterator to
+    /// return a final empty line.
+    ///
+    /// If you want to include the line breaks consider using the
+    /// [`raw_lines()`](Self::raw_lines()) method instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo\nbar\r\nbaz\n");
+    ///
+    /// let mut lines = r.lines();
+    ///
+    /// assert_eq!("foo", lines.next().unwrap());
+    /// assert_eq!("bar", lines.next().unwrap());
+    /// assert_eq!("baz", lines.next().unwrap());
+    /// assert_eq!(None, lines.next());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn lines(&self) -> Lines<'_, C, ARITY, MAX_BYTES> {
+        Lines::from(self)
+    }
+
+    /// Returns an iterator over the lines of the `Rope`, not including the
+    /// line terminators, where the final line break is handled according to
+    /// `trailing`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{Rope, TrailingLineBreak};
+    /// #
+    /// let r = Rope::from("foo\nbar\n");
+    ///
+    /// let mut lines = r.lines_with(TrailingLineBreak::StartsLine);
+    ///
+    /// assert_eq!("foo", lines.next().unwrap());
+    /// assert_eq!("bar", lines.next().unwrap());
+    /// assert_eq!("", lines.next().unwrap());
+    /// assert_eq!(None, lines.next());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn lines_with(
+        &self,
+        trailing: TrailingLineBreak,
+    ) -> Lines<'_, C, ARITY, MAX_BYTES> {
+        let lines = self.lines();
+
+        if trailing == TrailingLineBreak::StartsLine
+            && (self.is_empty() || self.has_trailing_newline)
+        {
+            lines.with_trailing_line(self.byte_slice(self.byte_len()..))
+        } else {
+            lines
+        }
+    }
+
+    /// Returns a new `Rope` built by passing every chunk of this one to `f`
+    /// and concatenating the results.
+    ///
+    /// The text is streamed through `f` without being collected first, but
+    /// the chunks are only guaranteed to start and end on code point
+    /// boundaries, so `f` shouldn't make assumptions about where they're
+    /// split (e.g. a CRLF line break can be split across two chunks).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::borrow::Cow;
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo\tbar\tbaz");
+    ///
+    /// let expanded = r.map_chunks(|chunk| {
+    ///     if chunk.contains('\t') {
+    ///         Cow::Owned(chunk.replace('\t', "    "))
+    ///     } else {
+    ///         Cow::Borrowed(chunk)
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(expanded, "foo    bar    baz");
+    /// ```
+    #[inline]
+    pub fn map_chunks<'a, F>(&'a self, mut f: F) -> Self
+    where
+        F: FnMut(&'a str) -> alloc::borrow::Cow<'a, str>,
+    {
+        let mut builder = RopeBuilder::empty();
+
+        for chunk in self.chunks() {
+            builder.append(f(chunk));
+        }
+
+        builder.build()
+    }
+
+    /// Replaces every leaf of the tree for which `f` returns `Some` with the
+    /// returned chunk, which must have the same text.
+    ///
+    /// The leaves are replaced in place, so the structure of the tree and the
+    /// summaries of its nodes are left as they are, and only the nodes on the
+    /// paths to the replaced leaves are cloned if they're shared.
+    #[inline]
+    fn map_leaves<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&GapBuffer<MAX_BYTES>) -> Option<GapBuffer<MAX_BYTES>>,
+    {
+        type NodeRef<'a, const ARITY: usize, const MAX_BYTES: usize, C> =
+            crate::tree::NodeRef<'a, ARITY, GapBuffer<MAX_BYTES>, C>;
+
+        fn collect<
+            'a,
+            const ARITY: usize,
+            const MAX_BYTES: usize,
+            C: RefCount,
+        >(
+            node: NodeRef<'a, ARITY, MAX_BYTES, C>,
+            leaves: &mut Vec<&'a GapBuffer<MAX_BYTES>>,
+        ) {
+            match node.leaf() {
+                Some(leaf) => leaves.push(leaf),
+                None => node.children().for_each(|c| collect(c, leaves)),
+            }
+        }
+
+        let mut leaves = Vec::new();
+        collect(self.tree.root_ref(), &mut leaves);
+
+        let (leaf_indices, mapped): (Vec<_>, Vec<_>) = leaves
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, leaf)| Some((idx, f(leaf)?)))
+            .unzip();
+
+        let mut mapped = mapped.into_iter();
+
+        self.tree.update_leaves(&leaf_indices, |leaf| {
+            *leaf = mapped.next().expect("one chunk per index");
+        });
+    }
+
+    /// Returns the set of marks added with [`add_mark()`](Self::add_mark()).
+    #[inline]
+    pub fn marks(&self) -> &MarkSet {
+        self.extras.as_ref().map_or(&marks::EMPTY, |extras| &extras.marks)
+    }
+
+    /// Returns an iterator over the lines of this `Rope`, including the
+    /// line terminators.
+    ///
+    /// The final line break is optional and doesn't cause the iterator to
+    /// return a final empty line.
+    ///
+    /// If you don't want to include the line breaks consider using the
+    /// [`lines()`](Self::lines()) method instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("foo\nbar\r\nbaz");
+    ///
+    /// let mut raw_lines = r.raw_lines();
+    ///
+    /// assert_eq!("foo\n", raw_lines.next().unwrap());
+    /// assert_eq!("bar\r\n", raw_lines.next().unwrap());
+    /// assert_eq!("baz", raw_lines.next().unwrap());
+    /// assert_eq!(None, raw_lines.next());
+    ///
+    /// r.insert(r.byte_len(), "\n");
+    ///
+    /// let mut raw_lines = r.raw_lines();
+    ///
+    /// assert_eq!("foo\n", raw_lines.next().unwrap());
+    /// assert_eq!("bar\r\n", raw_lines.next().unwrap());
+    /// assert_eq!("baz\n", raw_lines.next().unwrap());
+    /// assert_eq!(None, raw_lines.next());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn raw_lines(&self) -> RawLines<'_, C, ARITY, MAX_BYTES> {
+        RawLines::from(self)
+    }
+
+    /// Converts every line ending of the `Rope` to the given one.
+    ///
+    /// CRLF sequences, line feeds and carriage returns not followed by a line
+    /// feed are all considered line endings. They're found in a single pass
+    /// over the chunks, and the `Rope` is left untouched if they all match
+    /// `line_ending` already. Otherwise a second pass rebuilds the tree,
+    /// rewriting only the chunks containing a line ending that differs and
+    /// sharing all the other ones with the old tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{LineEnding, Rope};
+    /// #
+    /// let mut r = Rope::from("foo\r\nbar\rbaz\n");
+    ///
+    /// r.normalize_eols(LineEnding::Lf);
+    /// assert_eq!(r, "foo\nbar\nbaz\n");
+    ///
+    /// r.normalize_eols(LineEnding::CrLf);
+    /// assert_eq!(r, "foo\r\nbar\r\nbaz\r\n");
+    /// ```
+    #[inline]
+    pub fn normalize_eols(&mut self, line_ending: LineEnding) {
+        let eol = line_ending.as_str();
+
+        let mut edits = Vec::new();
+
+        self.for_each_line_ending(|byte_range, found| {
+            if found != line_ending {
+                edits.push(EditInfo { byte_range, inserted_len: eol.len() });
+            }
+        });
+
+        if edits.is_empty() {
+            return;
+        }
+
+        edits.reverse();
+
+        let _ = self.edits_with(&edits, |this| {
+            this.tree = replace_line_endings(&this.tree, &edits, eol);
+
+            this.has_trailing_newline = this
+                .chunks()
+                .next_back()
+                .map_or(false, ends_with_line_break);
+        });
+    }
+
+    /// Overwrites the text starting at the given byte offset with `text`,
+    /// replacing as many bytes as its length.
+    ///
+    /// Since the length of the `Rope` doesn't change, the text is rewritten
+    /// inside the chunks it falls in without splitting, merging or
+    /// rebalancing any of them, so this is much faster than
+    /// [`replace()`](Self::replace()) when it spans several chunks. That's
+    /// only possible if the chunk boundaries within the overwritten range are
+    /// also char boundaries of `text`, otherwise this falls back to
+    /// `replace()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset or the end of the overwritten range don't
+    /// lie on a code point boundary, or if the end is out of bounds (i.e.
+    /// greater than [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("00 1f 2e\nff 00 a0\n");
+    ///
+    /// r.overwrite(3, "c0");
+    /// r.overwrite(9, "ba\nab");
+    /// assert_eq!(r, "00 c0 2e\nba\nab a0\n");
+    /// assert_eq!(r.line_len(), 3);
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn overwrite<T>(&mut self, byte_offset: usize, text: T)
+    where
+        T: AsRef<str>,
+    {
+        let text = text.as_ref();
 
-        if let Some(last) = self.chunks().next_back() {
-            assert_eq!(self.has_trailing_newline, last.ends_with('\n'));
-        } else {
-            return;
+        let end = byte_offset + text.len();
+
+        if end > self.byte_len() {
+            panic::byte_offset_out_of_bounds(end, self.byte_len());
         }
 
-        let leaves = self.tree.leaves();
+        // Slicing takes care of checking that the offsets are on code point
+        // boundaries.
+        let slice = self.byte_slice(byte_offset..end);
 
-        if leaves.len() == 1 {
-            return;
-        }
+        let mut leaf_end = 0;
 
-        for chunk in leaves {
-            assert!(
-                chunk.len() >= RopeChunk::chunk_min(),
-                "The chunk {:?} was supposed to contain at least {} bytes \
-                 but actually contains {}",
-                chunk,
-                RopeChunk::chunk_min(),
-                chunk.len()
-            );
+        let splits_chars = slice.tree_slice.leaves().any(|leaf| {
+            leaf_end += leaf.len();
+            !text.is_char_boundary(leaf_end)
+        });
 
-            chunk.assert_invariants();
+        if splits_chars {
+            return self.replace(byte_offset..end, text);
         }
+
+        self.edit_with(byte_offset..end, text.len(), |this| {
+            if end == this.byte_len() && !text.is_empty() {
+                this.has_trailing_newline = ends_with_line_break(text);
+            }
+
+            this.tree.replace_in_place(
+                ByteMetric(byte_offset)..ByteMetric(end),
+                |Range { start, end }| {
+                    &text[start.0 - byte_offset..end.0 - byte_offset]
+                },
+            );
+        });
     }
 
-    /// Returns the byte at `byte_index`.
+    /// Returns the text between the given [`Tail`] and the end of the
+    /// `Rope`, i.e. the text appended since the tail was created or last
+    /// polled, and moves the tail to the end of the `Rope`.
+    ///
+    /// This takes `O(log n)` time regardless of how much text was appended,
+    /// since the text is returned as a slice of the `Rope`.
     ///
     /// # Panics
     ///
-    /// Panics if the byte index is out of bounds (i.e. greater than or equal
-    /// to [`byte_len()`](Self::byte_len())).
+    /// Panics if the mark backing the tail has been removed with
+    /// [`remove_mark()`](Self::remove_mark()).
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("bar");
+    /// let mut r = Rope::from("foo\nbar\n");
     ///
-    /// assert_eq!(r.byte(0), b'b');
-    /// assert_eq!(r.byte(1), b'a');
-    /// assert_eq!(r.byte(2), b'r');
+    /// let tail = r.follow_tail();
+    ///
+    /// r.push_str("baz\n");
+    /// assert_eq!(r.poll_tail(&tail), "baz\n");
+    ///
+    /// // Truncating the text moves the tail back.
+    /// r.clear();
+    /// r.push_str("qux\n");
+    /// assert_eq!(r.poll_tail(&tail), "qux\n");
     /// ```
     #[track_caller]
     #[inline]
-    pub fn byte(&self, byte_index: usize) -> u8 {
-        if byte_index >= self.byte_len() {
-            panic::byte_index_out_of_bounds(byte_index, self.byte_len());
-        }
+    pub fn poll_tail(
+        &mut self,
+        tail: &Tail,
+    ) -> RopeSlice<'_, C, ARITY, MAX_BYTES> {
+        let byte_len = self.byte_len();
 
-        let (chunk, ByteMetric(chunk_byte_offset)) =
-            self.tree.leaf_at_measure(ByteMetric(byte_index + 1));
+        let Some(start) = self
+            .extras
+            .as_mut()
+            .and_then(|extras| extras.marks.set(tail.mark(), byte_len))
+        else {
+            panic!("the mark of the tail has been removed");
+        };
 
-        chunk.byte(byte_index - chunk_byte_offset)
+        self.byte_slice(start..)
     }
 
-    /// Returns the length of the `Rope` in bytes.
+    /// Appends a single character at the end of the `Rope`.
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("ƒoo");
+    /// let mut r = Rope::from("Hello Earth ");
     ///
-    /// assert_eq!(r.byte_len(), 4);
+    /// r.push_char('🌎');
+    /// assert_eq!(r, "Hello Earth 🌎");
     /// ```
     #[inline]
-    pub fn byte_len(&self) -> usize {
-        self.tree.summary().bytes()
+    pub fn push_char(&mut self, ch: char) {
+        self.insert_char(self.byte_len(), ch)
     }
 
-    /// Returns the byte offset of the start of the given line.
+    /// Appends `text` at the end of the `Rope`.
+    ///
+    /// The last chunk of the `Rope` is reached without searching the tree
+    /// for it, so this is the fastest way to grow a `Rope` one piece at a
+    /// time, e.g. when following a log file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello");
+    ///
+    /// r.push_str(" Earth 🌎!");
+    /// assert_eq!(r, "Hello Earth 🌎!");
+    /// ```
+    #[inline]
+    pub fn push_str<T>(&mut self, text: T)
+    where
+        T: AsRef<str>,
+    {
+        let text = text.as_ref();
+        let byte_len = self.byte_len();
+
+        self.edit_with(byte_len..byte_len, text.len(), |this| {
+            if !text.is_empty() {
+                this.has_trailing_newline = ends_with_line_break(text);
+            }
+
+            if text.len() >= Self::BULK_INSERT_MIN_BYTES {
+                this.tree.append(Tree::from_leaves(
+                    GapBuffer::<MAX_BYTES>::segmenter(text)
+                        .map(GapBuffer::<MAX_BYTES>::from),
+                ));
+            } else {
+                this.tree.push::<ByteMetric>(text);
+            }
+        });
+    }
+
+    /// Returns a [`RecordIndex`] over the records of the `Rope`, i.e. the
+    /// pieces of text delimited by the given separator byte.
     ///
     /// # Panics
     ///
-    /// Panics if the line offset is out of bounds (i.e. greater than
-    /// [`line_len()`](Self::line_len())).
+    /// Panics if the separator is not an ASCII byte.
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("ƒoo\nbär\r\nbaz");
+    /// let r = Rope::from("a,b\nc,d\n");
     ///
-    /// assert_eq!(r.byte_of_line(0), 0);
-    /// assert_eq!(r.byte_of_line(1), "ƒoo\n".len());
-    /// assert_eq!(r.byte_of_line(2), "ƒoo\nbär\r\n".len());
-    /// assert_eq!(r.byte_of_line(r.line_len()), r.byte_len());
+    /// let rows = r.record_index(b'\n');
+    /// assert_eq!(rows.record_len(), 2);
+    /// assert_eq!(rows.record(1), "c,d");
+    ///
+    /// let fields = r.record_index(b',');
+    /// assert_eq!(fields.record_len(), 3);
+    /// assert_eq!(fields.record(1), "b\nc");
     /// ```
     #[track_caller]
     #[inline]
-    pub fn byte_of_line(&self, line_offset: usize) -> usize {
-        if line_offset > self.line_len() {
-            panic::line_offset_out_of_bounds(line_offset, self.line_len());
-        }
+    pub fn record_index(
+        &self,
+        separator: u8,
+    ) -> RecordIndex<'_, C, ARITY, MAX_BYTES> {
+        RecordIndex::new(self, separator)
+    }
 
-        if line_offset > self.tree.summary().line_breaks() {
-            return self.byte_len();
+    /// Deletes the text within the specified byte range, returning it as a
+    /// new `Rope`.
+    ///
+    /// The returned `Rope` shares all the nodes of the tree that were fully
+    /// contained in the range, so this is much cheaper than copying the text
+    /// before deleting it. This is the `Rope` equivalent of
+    /// [`String::drain()`], and can be used to cut text or to move it to
+    /// another `Rope` with [`insert_rope()`](Self::insert_rope()).
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`delete()`](Self::delete()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello Earth 🌎!");
+    ///
+    /// let removed = r.remove(5..16);
+    /// assert_eq!(r, "Hello!");
+    /// assert_eq!(removed, " Earth 🌎");
+    /// ```
+    #[doc(alias = "drain")]
+    #[track_caller]
+    #[inline]
+    pub fn remove<R>(&mut self, byte_range: R) -> Self
+    where
+        R: RangeBounds<usize>,
+    {
+        self.splice(byte_range, "")
+    }
+
+    /// Deletes the character starting at the given byte offset, returning
+    /// it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset doesn't lie on a code point boundary or if
+    /// it's out of bounds (i.e. greater than or equal to
+    /// [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello Earth 🌎!");
+    ///
+    /// assert_eq!(r.remove_char(12), '🌎');
+    /// assert_eq!(r, "Hello Earth !");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn remove_char(&mut self, byte_offset: usize) -> char {
+        if byte_offset >= self.byte_len() {
+            panic::byte_index_out_of_bounds(byte_offset, self.byte_len());
         }
 
-        let ByteMetric(byte_offset) =
-            self.tree.convert_measure(RawLineMetric(line_offset));
+        // Slicing takes care of checking that the offset is on a code point
+        // boundary.
+        let ch = self.byte_slice(byte_offset..).chars().next().unwrap();
 
-        byte_offset
+        self.delete(byte_offset..byte_offset + ch.len_utf8());
+
+        ch
     }
 
-    /// Returns the byte offset corresponding to the given UTF-16 code unit
-    /// offset.
+    /// Removes a mark added with [`add_mark()`](Self::add_mark()), returning
+    /// its byte offset if it hadn't already been removed.
+    #[inline]
+    pub fn remove_mark(&mut self, id: MarkId) -> Option<usize> {
+        self.extras.as_mut().and_then(|extras| extras.marks.remove(id))
+    }
+
+    /// Returns a new `Rope` containing the text of this one repeated `n`
+    /// times.
+    ///
+    /// The result is built by repeatedly appending the `Rope` to itself, so
+    /// its tree shares the nodes of `self` instead of copying their text, and
+    /// building it takes `O(log n)` appends.
     ///
     /// # Panics
     ///
-    /// Panics if the UTF-16 code unit offset is out of bounds (i.e. greater
-    /// than [`utf16_len()`](Self::utf16_len())) or if it doesn't lie on a code
-    /// point boundary.
+    /// Panics if the result would be longer than
+    /// [`MAX_BYTE_LEN`](Self::MAX_BYTE_LEN).
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// // The "𐐀" character is encoded using two code units in UTF-16 and
-    /// // four bytes in UTF-8.
-    /// let r = Rope::from("a𐐀b");
-    /// assert_eq!(r.byte_of_utf16_code_unit(3), 5);
+    /// let r = Rope::from("ab\n");
+    ///
+    /// assert_eq!(r.repeat(3), "ab\nab\nab\n");
+    /// assert!(r.repeat(0).is_empty());
     /// ```
-    #[cfg_attr(docsrs, doc(cfg(feature = "utf16-metric")))]
-    #[cfg(feature = "utf16-metric")]
     #[track_caller]
     #[inline]
-    pub fn byte_of_utf16_code_unit(&self, utf16_offset: usize) -> usize {
-        if utf16_offset > self.utf16_len() {
-            panic::utf16_offset_out_of_bounds(utf16_offset, self.utf16_len())
+    pub fn repeat(&self, mut n: usize) -> Self {
+        let is_too_long = self
+            .byte_len()
+            .checked_mul(n)
+            .map_or(true, |len| TooLongError::check(0, 0, len).is_err());
+
+        if is_too_long {
+            panic::rope_too_long();
         }
 
-        let ByteMetric(byte_offset) = self
-            .tree
-            .convert_measure(super::metrics::Utf16Metric(utf16_offset));
+        let mut repeated = Self::empty();
 
-        byte_offset
+        let mut power =
+            Self::from_tree(self.tree.clone(), self.has_trailing_newline);
+
+        while n > 0 {
+            if n & 1 == 1 {
+                repeated.append(power.clone());
+            }
+
+            n >>= 1;
+
+            if n > 0 {
+                power.append(power.clone());
+            }
+        }
+
+        repeated
     }
 
-    /// Returns an immutable slice of the `Rope` in the specified byte range,
-    /// where the start and end of the range are interpreted as offsets.
+    /// Replaces the contents of the `Rope` within the specified byte range
+    /// with the given string, where the start and end of the range are
+    /// interpreted as byte offsets.
+    ///
+    /// Long texts are turned into a balanced tree of their own which is then
+    /// spliced into the `Rope`, so inserting them costs about as much as
+    /// creating a `Rope` from them.
     ///
     /// # Panics
     ///
@@ -197,478 +3336,874 @@ impl Rope {
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("🗻∈🌏");
+    /// let mut r = Rope::from("Hello Earth 🌎!");
+    ///
+    /// r.replace(6..16, "Saturn 🪐");
+    /// assert_eq!(r, "Hello Saturn 🪐!");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn replace<R, T>(&mut self, byte_range: R, text: T)
+    where
+        R: RangeBounds<usize>,
+        T: AsRef<str>,
+    {
+        if self.try_replace(byte_range, text).is_err() {
+            panic::rope_too_long();
+        }
+    }
+
+    /// Replaces the text within the given byte range with `text`, splicing
+    /// in `inserted` instead of adding the chunks of `text` to the tree if
+    /// it's given, in which case it must have been built out of `text`.
+    ///
+    /// Returns the number of bytes deleted from the start of the `Rope` to
+    /// enforce its limit, like [`edit_with()`](Self::edit_with()).
+    #[inline]
+    pub(super) fn replace_with(
+        &mut self,
+        byte_range: Range<usize>,
+        text: &str,
+        inserted: Option<Tree<ARITY, GapBuffer<MAX_BYTES>, C>>,
+    ) -> usize {
+        self.edit_with(byte_range.clone(), text.len(), |this| {
+            this.replace_in_tree(byte_range, text, inserted);
+        })
+    }
+
+    /// Replaces the text within the given byte range with the contents of
+    /// `slice` as a single edit, sharing the nodes of the slice like
+    /// [`insert_slice()`](Self::insert_slice()) does.
+    #[track_caller]
+    #[inline]
+    pub(super) fn replace_with_slice(
+        &mut self,
+        byte_range: Range<usize>,
+        slice: RopeSlice<'_, C, ARITY, MAX_BYTES>,
+    ) {
+        // Slicing takes care of checking the range.
+        let _ = self.byte_slice(byte_range.clone());
+
+        if slice.byte_len() == 0 {
+            self.replace_with(byte_range, "", None);
+            return;
+        }
+
+        let inserted = Tree::from(slice.tree_slice);
+
+        self.edit_with(byte_range.clone(), slice.byte_len(), |this| {
+            let Range { start, end } = byte_range;
+
+            if end == this.byte_len() {
+                this.has_trailing_newline = slice.has_trailing_newline;
+            }
+
+            if start < end {
+                this.tree.replace(ByteMetric(start)..ByteMetric(end), "");
+            }
+
+            this.splice_tree(start, inserted);
+        });
+    }
+
+    /// Replaces the text within the given byte range with `text` in the tree
+    /// and updates the trailing newline flag, without any of the bookkeeping
+    /// done by [`edit_with()`](Self::edit_with()), so it must only be called
+    /// from within its closure.
+    #[inline]
+    fn replace_in_tree(
+        &mut self,
+        byte_range: Range<usize>,
+        text: &str,
+        inserted: Option<Tree<ARITY, GapBuffer<MAX_BYTES>, C>>,
+    ) {
+        let Range { start, end } = byte_range;
+
+        let mut update_trailing = false;
+
+        if end == self.byte_len() {
+            if !text.is_empty() {
+                self.has_trailing_newline = ends_with_line_break(text);
+            } else if start == 0 {
+                self.has_trailing_newline = false;
+            } else {
+                update_trailing = true;
+            }
+        }
+
+        if let Some(inserted) = inserted {
+            if start < end {
+                self.tree.replace(ByteMetric(start)..ByteMetric(end), "");
+            }
+
+            self.splice_tree(start, inserted);
+        } else if start == self.byte_len() {
+            self.tree.push::<ByteMetric>(text);
+        } else {
+            self.tree.replace(ByteMetric(start)..ByteMetric(end), text);
+        }
+
+        if update_trailing {
+            self.has_trailing_newline =
+                ends_with_line_break(self.chunks().next_back().unwrap());
+        }
+    }
+
+    /// Replaces the lines within the given line range with the ones left in
+    /// the vector after calling `edit` on it.
+    ///
+    /// The lines are passed to `edit` as `(line, line_break)` pairs. If the
+    /// range ends with the last line of the `Rope` and that line doesn't end
+    /// with a line break, the line that's last after the edit gives its line
+    /// break to the one that was missing it, so that the `Rope` still doesn't
+    /// end with a line break.
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    fn replace_lines<R, F>(&mut self, line_range: R, edit: F)
+    where
+        R: RangeBounds<usize>,
+        F: for<'a> FnOnce(
+            &mut Vec<(
+                RopeSlice<'a, C, ARITY, MAX_BYTES>,
+                RopeSlice<'a, C, ARITY, MAX_BYTES>,
+            )>,
+        ),
+    {
+        let (start, end) =
+            range_bounds_to_start_end(line_range, 0, self.line_len());
+
+        // Slicing takes care of checking the range.
+        let slice = self.line_slice(start..end);
+
+        let byte_start = self.byte_of_line(start);
+        let byte_end = byte_start + slice.byte_len();
+
+        let mut lines = Vec::new();
+
+        for raw_line in slice.raw_lines() {
+            let len = raw_line.byte_len();
+
+            let line_break_len = match raw_line.chars().next_back() {
+                Some('\n') if len > 1 && raw_line.byte(len - 2) == b'\r' => 2,
+                Some(ch) if is_line_break(ch) => ch.len_utf8(),
+                _ => 0,
+            };
+
+            lines.push((
+                raw_line.byte_slice(..len - line_break_len),
+                raw_line.byte_slice(len - line_break_len..),
+            ));
+        }
+
+        let Some(&(_, last_line_break)) = lines.last() else { return };
+
+        // Only the last line of the `Rope` can be missing a line break.
+
+        edit(&mut lines);
+
+        match lines.split_last_mut() {
+            Some(((_, line_break), rest)) if last_line_break.is_empty() => {
+                let displaced =
+                    core::mem::replace(line_break, last_line_break);
+
+                if let Some((_, line_break)) = rest
+                    .iter_mut()
+                    .find(|(_, line_break)| line_break.is_empty())
+                {
+                    *line_break = displaced;
+                }
+            },
+            _ => {},
+        }
+
+        let mut text = String::with_capacity(slice.byte_len());
+
+        for (line, line_break) in &lines {
+            for chunk in line.chunks().chain(line_break.chunks()) {
+                text.push_str(chunk);
+            }
+        }
+
+        if slice != text {
+            self.replace(byte_start..byte_end, text);
+        }
+    }
+
+    /// Like [`replace()`](Self::replace()), but returns the region of the
+    /// `Rope` affected by the edit, which can be used to invalidate any
+    /// state derived from it without having to convert the byte offsets to
+    /// line offsets.
+    ///
+    /// If the edit makes the `Rope` exceed its [`limit()`](Self::limit()),
+    /// the new lines are relative to the text left after deleting the
+    /// excess from its start.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`replace()`](Self::replace()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{EditInfo, Rope};
+    /// #
+    /// let mut r = Rope::from("foo\nbar\nbaz\n");
     ///
-    /// assert_eq!(r.byte_slice(..4), "🗻");
-    /// assert_eq!(r.byte_slice(4..7), "∈");
-    /// assert_eq!(r.byte_slice(7..), "🌏");
+    /// let region = r.replace_with_info(2..9, "o\n\n\n\nb");
+    ///
+    /// assert_eq!(r, "foo\n\n\n\nbaz\n");
+    /// assert_eq!(region.edit, EditInfo { byte_range: 2..9, inserted_len: 6 });
+    /// assert_eq!(region.old_lines, 0..3);
+    /// assert_eq!(region.new_lines, 0..5);
     /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
     #[track_caller]
     #[inline]
-    pub fn byte_slice<R>(&self, byte_range: R) -> RopeSlice<'_>
+    pub fn replace_with_info<R, T>(
+        &mut self,
+        byte_range: R,
+        text: T,
+    ) -> EditRegion
     where
         R: RangeBounds<usize>,
+        T: AsRef<str>,
     {
         let (start, end) =
             range_bounds_to_start_end(byte_range, 0, self.byte_len());
 
-        if start > end {
-            panic::byte_start_after_end(start, end);
-        }
+        let text = text.as_ref();
 
-        if end > self.byte_len() {
-            panic::byte_offset_out_of_bounds(end, self.byte_len());
-        }
+        let start_line = self.line_of_byte(start);
+        let old_end_line = self.line_of_byte(end);
 
-        self.tree.slice(ByteMetric(start)..ByteMetric(end)).into()
+        let Ok(trimmed) = self.try_replace_trimming(start..end, text) else {
+            panic::rope_too_long();
+        };
+
+        // The text deleted to enforce the limit shifts everything after it,
+        // possibly including part or all of the inserted text.
+        let new_start_line = self.line_of_byte(start.saturating_sub(trimmed));
+
+        let new_end_line =
+            self.line_of_byte((start + text.len()).saturating_sub(trimmed));
+
+        EditRegion {
+            edit: EditInfo {
+                byte_range: start..end,
+                inserted_len: text.len(),
+            },
+            old_lines: start_line..old_end_line + 1,
+            new_lines: new_start_line..new_end_line + 1,
+        }
     }
 
-    /// Returns an iterator over the bytes of this `Rope`.
+    /// Returns the revision of the `Rope`, which changes every time the
+    /// `Rope` is modified.
+    ///
+    /// Revisions are unique across all `Rope`s, so two `Rope`s only share a
+    /// revision if one is a clone of the other that neither has modified
+    /// since. Editing a `Rope` only increments a counter of its own, so this
+    /// doesn't add any synchronization between the threads editing different
+    /// `Rope`s. See [`Revision`] for more.
+    ///
+    /// This allows caches keyed on the state of a document (e.g. layouts or
+    /// syntax highlights) to be invalidated without hashing its contents.
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("foo");
+    /// let mut r = Rope::from("foo");
+    /// assert_ne!(r.revision(), Rope::from("foo").revision());
     ///
-    /// let mut bytes = r.bytes();
+    /// let created = r.revision();
+    /// r.insert(3, "bar");
+    /// let rev = r.revision();
+    /// assert!(rev > created);
     ///
-    /// assert_eq!(Some(b'f'), bytes.next());
-    /// assert_eq!(Some(b'o'), bytes.next());
-    /// assert_eq!(Some(b'o'), bytes.next());
-    /// assert_eq!(None, bytes.next());
+    /// let mut clone = r.clone();
+    /// assert_eq!(clone.revision(), rev);
+    ///
+    /// clone.insert(0, "baz");
+    /// r.insert(0, "baz");
+    /// assert!(clone.revision() > rev);
+    /// assert!(r.revision() > rev);
+    /// assert_ne!(r.revision(), clone.revision());
     /// ```
     #[inline]
-    pub fn bytes(&self) -> Bytes<'_> {
-        Bytes::from(self)
+    pub fn revision(&self) -> Revision {
+        self.revision
     }
 
-    /// Returns an iterator over the [`char`]s of this `Rope`.
+    /// Returns an iterator over the hashes of every window of `window_size`
+    /// consecutive bytes of the `Rope`, from left to right.
+    ///
+    /// See [`RopeSlice::rolling_hash()`] for more.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_size` is zero.
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("🐻‍❄️");
+    /// let r = Rope::from("abcabc");
     ///
-    /// let mut chars = r.chars();
+    /// let hashes = r.rolling_hash(3).collect::<Vec<_>>();
     ///
-    /// assert_eq!(Some('🐻'), chars.next());
-    /// assert_eq!(Some('\u{200d}'), chars.next());
-    /// assert_eq!(Some('❄'), chars.next());
-    /// assert_eq!(Some('\u{fe0f}'), chars.next());
-    /// assert_eq!(None, chars.next());
+    /// assert_eq!(hashes.len(), 4);
+    /// assert_eq!(hashes[0], hashes[3]);
     /// ```
+    #[track_caller]
     #[inline]
-    pub fn chars(&self) -> Chars<'_> {
-        Chars::from(self)
-    }
-
-    /// Returns an iterator over the chunks of this [`Rope`].
-    #[inline]
-    pub fn chunks(&self) -> Chunks<'_> {
-        Chunks::from(self)
+    pub fn rolling_hash(
+        &self,
+        window_size: usize,
+    ) -> RollingHash<'_, C, ARITY, MAX_BYTES> {
+        RollingHash::new(self.bytes(), window_size)
     }
 
-    /// Deletes the contents of the `Rope` within the specified byte range,
-    /// where the start and end of the range are interpreted as offsets.
+    /// Sets or removes a cap on the length of the `Rope`, after which every
+    /// edit making it exceed the [`Limit`] deletes text from its start, e.g.
+    /// to keep the scrollback of a terminal within a bounded amount of
+    /// memory.
     ///
-    /// # Panics
+    /// The text is deleted with a separate edit right after the one that
+    /// made the `Rope` exceed the limit, so the marks and the subscribers see
+    /// both, and the `Rope` is brought within the new limit right away if it
+    /// already exceeds it. Since deleting a range only detaches the subtrees
+    /// it contains, this takes `O(log n)` time apart from the time needed to
+    /// free them.
     ///
-    /// Panics if the start or the end of the byte range don't lie on a code
-    /// point boundary, if the start is greater than the end or if the end is
-    /// out of bounds (i.e. greater than [`byte_len()`](Self::byte_len())).
+    /// The wrappers recording the edits of a `Rope`, i.e.
+    /// [`History`](crate::history::History),
+    /// [`Replica`](crate::op_log::Replica),
+    /// [`AnnotatedRope`](crate::annotated::AnnotatedRope) and
+    /// [`LineDataRope`](crate::line_data::LineDataRope), don't accept a
+    /// `Rope` with a limit, and neither does
+    /// [`Transaction::apply()`](crate::transaction::Transaction::apply()).
+    ///
+    /// The limit is inherited by the clones of the `Rope`.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use crop::Rope;
+    /// # use crop::{Limit, Rope};
     /// #
-    /// let mut r = Rope::from("Hello Earth 🌎!");
+    /// let mut r = Rope::from("foo\nbar\n");
     ///
-    /// r.delete(5..16);
-    /// assert_eq!(r, "Hello!");
+    /// r.set_limit(Some(Limit::Lines(3)));
+    ///
+    /// r.push_str("baz\n");
+    /// assert_eq!(r, "foo\nbar\nbaz\n");
+    ///
+    /// r.push_str("qux\nquux\n");
+    /// assert_eq!(r, "baz\nqux\nquux\n");
+    ///
+    /// r.set_limit(Some(Limit::Bytes(9)));
+    /// assert_eq!(r, "qux\nquux\n");
+    ///
+    /// r.set_limit(Some(Limit::Bytes(6)));
+    /// assert_eq!(r, "\nquux\n");
     /// ```
-    #[track_caller]
     #[inline]
-    pub fn delete<R>(&mut self, byte_range: R)
-    where
-        R: RangeBounds<usize>,
-    {
-        self.replace(byte_range, "");
-    }
-
-    pub(super) const fn arity() -> usize {
-        ARITY
+    pub fn set_limit(&mut self, limit: Option<Limit>) {
+        if limit.is_some() || self.extras.is_some() {
+            self.extras_mut().limit = limit;
+        }
+        self.enforce_limit();
     }
 
-    /// Returns an iterator over the extended grapheme clusters of this
-    /// `Rope`.
+    /// Shrinks the memory used by the chunks of the `Rope` to the length of
+    /// their text.
+    ///
+    /// Every chunk normally reserves room for the maximum number of bytes it
+    /// can hold, so that the text inserted in it doesn't have to be moved
+    /// around. For large documents that are mostly read that room is never
+    /// used, and this frees it. A shrunk chunk gets its full capacity back
+    /// the first time it's edited, so only the chunks around the edits pay
+    /// for them.
+    ///
+    /// Chunks that are borrowed from a memory-mapped file or compressed are
+    /// left as they are.
+    ///
+    /// The shrunk chunks replace the original ones, so
+    /// [`changes_since()`](Self::changes_since()) will report them as changed
+    /// when called with a snapshot taken before shrinking them, unless the
+    /// `node-hashes` feature is enabled.
     ///
     /// # Examples
     ///
-    /// ```no_run
+    /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("arg!\r\n🐻‍❄️");
+    /// let mut r = Rope::from("Hello, world!\n".repeat(1000));
     ///
-    /// let mut graphemes = r.graphemes();
+    /// r.shrink_to_fit();
+    /// assert_eq!(r.line(500), "Hello, world!");
     ///
-    /// assert_eq!(Some("a"), graphemes.next().as_deref());
-    /// assert_eq!(Some("r"), graphemes.next().as_deref());
-    /// assert_eq!(Some("g"), graphemes.next().as_deref());
-    /// assert_eq!(Some("!"), graphemes.next().as_deref());
-    /// assert_eq!(Some("\r\n"), graphemes.next().as_deref());
-    /// assert_eq!(Some("🐻‍❄️"), graphemes.next().as_deref());
-    /// assert_eq!(None, graphemes.next());
+    /// r.insert(0, "Hi! ");
+    /// assert_eq!(r.line(0), "Hi! Hello, world!");
     /// ```
-    #[cfg_attr(docsrs, doc(cfg(feature = "graphemes")))]
-    #[cfg(feature = "graphemes")]
     #[inline]
-    pub fn graphemes(&self) -> crate::iter::Graphemes<'_> {
-        crate::iter::Graphemes::from(self)
+    pub fn shrink_to_fit(&mut self) {
+        self.map_leaves(GapBuffer::<MAX_BYTES>::shrunk);
     }
 
-    /// Inserts `text` in the `Rope` at the given byte offset.
+    /// Sorts the lines within the given line range with a comparator
+    /// function.
+    ///
+    /// The sort is stable and lines are compared without their line breaks,
+    /// which move together with their lines. Only the text within the range
+    /// is replaced.
     ///
     /// # Panics
     ///
-    /// Panics if the byte offset doesn't lie on a code point boundary or if
-    /// it's out of bounds (i.e. greater than
-    /// [`byte_len()`](Self::byte_len())).
+    /// Panics in the same cases as [`line_slice()`](Self::line_slice()).
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let mut r = Rope::from("Hello Earth!");
+    /// let mut r = Rope::from("c\nb\na\nd");
     ///
-    /// r.insert(11, " 🌎");
-    /// assert_eq!(r, "Hello Earth 🌎!");
+    /// r.sort_lines(..3, |a, b| a.chars().cmp(b.chars()));
+    /// assert_eq!(r, "a\nb\nc\nd");
+    ///
+    /// r.sort_lines(.., |a, b| b.chars().cmp(a.chars()));
+    /// assert_eq!(r, "d\nc\nb\na");
     /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
     #[track_caller]
     #[inline]
-    pub fn insert<T>(&mut self, byte_offset: usize, text: T)
+    pub fn sort_lines<R, F>(&mut self, line_range: R, mut compare: F)
     where
-        T: AsRef<str>,
+        R: RangeBounds<usize>,
+        F: FnMut(
+            &RopeSlice<'_, C, ARITY, MAX_BYTES>,
+            &RopeSlice<'_, C, ARITY, MAX_BYTES>,
+        ) -> core::cmp::Ordering,
     {
-        self.replace(byte_offset..byte_offset, text)
+        self.replace_lines(line_range, |lines| {
+            lines.sort_by(|a, b| compare(&a.0, &b.0))
+        });
     }
 
-    /// Returns `true` if the given byte offset lies on a [`char`] boundary.
+    /// Replaces the text within the specified byte range with `text`,
+    /// returning the replaced text as a new `Rope`.
+    ///
+    /// Like with [`remove()`](Self::remove()), the returned `Rope` shares
+    /// all the nodes of the tree that were fully contained in the range.
     ///
     /// # Panics
     ///
-    /// Panics if the byte offset is out of bounds (i.e. greater than
-    /// [`byte_len()`](Self::byte_len())).
+    /// Panics in the same cases as [`replace()`](Self::replace()).
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("Löwe 老虎 Léopard");
+    /// let mut r = Rope::from("Hello Earth 🌎!");
     ///
-    /// assert!(r.is_char_boundary(0));
-    /// assert!(r.is_char_boundary(r.byte_len()));
-    /// assert!(r.is_char_boundary(6)); // between ' ' and '老'
-    /// assert!(!r.is_char_boundary(2)); // between the 1st and 2nd byte of 'ö'
+    /// let replaced = r.splice(6..16, "Saturn 🪐");
+    /// assert_eq!(r, "Hello Saturn 🪐!");
+    /// assert_eq!(replaced, "Earth 🌎");
     /// ```
     #[track_caller]
     #[inline]
-    pub fn is_char_boundary(&self, byte_offset: usize) -> bool {
-        if byte_offset > self.byte_len() {
-            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
-        }
+    pub fn splice<R, T>(&mut self, byte_range: R, text: T) -> Self
+    where
+        R: RangeBounds<usize>,
+        T: AsRef<str>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.byte_len());
 
-        let (chunk, ByteMetric(chunk_byte_offset)) =
-            self.tree.leaf_at_measure(ByteMetric(byte_offset));
+        let replaced = Rope::from(self.byte_slice(start..end));
 
-        chunk.is_char_boundary(byte_offset - chunk_byte_offset)
+        self.replace(start..end, text);
+
+        replaced
     }
 
-    /// Returns `true` if the `Rope`'s byte length is zero.
+    /// Splits the `Rope` at the start of the given line, returning the text
+    /// before it and the text from it to the end as two new `Rope`s.
+    ///
+    /// Both `Rope`s share the nodes of `self` except the ones along the
+    /// split, so this takes `O(log n)` time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line offset is out of bounds (i.e. greater than
+    /// [`line_len()`](Self::line_len())).
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let mut r = Rope::new();
-    /// assert!(r.is_empty());
+    /// let r = Rope::from("foo\nbar\r\nbaz");
     ///
-    /// r.insert(0, "hey");
-    /// assert!(!r.is_empty());
+    /// let (before, after) = r.split_at_line(1);
+    /// assert_eq!(before, "foo\n");
+    /// assert_eq!(after, "bar\r\nbaz");
+    ///
+    /// let (before, after) = r.split_at_line(r.line_len());
+    /// assert_eq!(before, r);
+    /// assert!(after.is_empty());
     /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
     #[inline]
-    pub fn is_empty(&self) -> bool {
-        self.byte_len() == 0
+    pub fn split_at_line(&self, line_offset: usize) -> (Self, Self) {
+        let byte_offset = self.byte_of_line(line_offset);
+
+        (
+            Rope::from(self.byte_slice(..byte_offset)),
+            Rope::from(self.byte_slice(byte_offset..)),
+        )
     }
 
-    /// Returns `true` if the given byte offset lies on a grapheme cluster
-    /// boundary.
+    /// Splits the `Rope` into `n` parts of roughly equal byte length, e.g. to
+    /// process them in parallel on different threads.
+    ///
+    /// The parts are returned in order and concatenating them gives back the
+    /// original `Rope`. Every part except the last one ends on a code point
+    /// boundary as close as possible to a multiple of `byte_len() / n`, so if
+    /// the `Rope` is shorter than `n` bytes some of the parts will be empty.
+    /// The parts share the nodes of `self`, so this takes `O(n log n)` time.
     ///
     /// # Panics
     ///
-    /// Panics if the byte offset is out of bounds (i.e. greater than
-    /// [`byte_len()`](Self::byte_len())).
+    /// Panics if `n` is zero.
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("aargh!\r\n🐻‍❄️");
+    /// let r = Rope::from("aaaabbbbcccc");
     ///
-    /// assert!(r.is_grapheme_boundary(0));
-    /// assert!(r.is_grapheme_boundary(r.byte_len()));
-    /// assert!(!r.is_grapheme_boundary(7)); // between '\r' and '\n'
-    /// assert!(r.is_grapheme_boundary(8)); // between '\n' and '🐻‍❄️'
-    /// assert!(!r.is_grapheme_boundary(12)); // between the 1st and 2nd code point of '🐻‍❄️'
+    /// let parts = r.split_into(3);
+    /// assert_eq!(parts, ["aaaa", "bbbb", "cccc"]);
+    ///
+    /// let r = Rope::from("ƒoo");
+    ///
+    /// let parts = r.split_into(2);
+    /// assert_eq!(parts, ["ƒ", "oo"]);
     /// ```
-    #[cfg_attr(docsrs, doc(cfg(feature = "graphemes")))]
-    #[cfg(feature = "graphemes")]
     #[track_caller]
     #[inline]
-    pub fn is_grapheme_boundary(&self, byte_offset: usize) -> bool {
-        if byte_offset > self.byte_len() {
-            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
+    pub fn split_into(&self, n: usize) -> Vec<Self> {
+        assert!(n > 0, "can't split a rope into zero parts");
+
+        let byte_len = self.byte_len();
+
+        let mut parts = Vec::with_capacity(n);
+
+        let mut start = 0;
+
+        for idx in 1..=n {
+            let mut end = byte_len / n * idx + (byte_len % n) * idx / n;
+
+            while !self.is_char_boundary(end) {
+                end += 1;
+            }
+
+            let end = end.max(start);
+            parts.push(Rope::from(self.byte_slice(start..end)));
+            start = end;
         }
 
-        is_grapheme_boundary(self.chunks(), self.byte_len(), byte_offset)
+        parts
     }
 
-    /// Returns the line at `line_index`, without its line terminator.
+    /// Splits the `Rope` at the given byte offset, returning everything after
+    /// it as a new `Rope` and keeping everything before it in `self`.
     ///
-    /// If you want to include the line break consider taking a
-    /// [`line_slice()`](Self::line_slice()) in the
-    /// `line_index..line_index + 1` range.
+    /// Both halves share all the nodes of the original tree except the ones
+    /// along the split, so this takes `O(log n)` time.
     ///
     /// # Panics
     ///
-    /// Panics if the line index is out of bounds (i.e. greater than or equal
-    /// to [`line_len()`](Self::byte_len())).
+    /// Panics if the byte offset doesn't lie on a code point boundary or if
+    /// it's out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())).
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("foo\nbar\r\nbaz");
+    /// let mut r = Rope::from("Hello Earth 🌎!");
     ///
-    /// assert_eq!(r.line(0), "foo");
-    /// assert_eq!(r.line(1), "bar");
-    /// assert_eq!(r.line(2), "baz");
+    /// let rest = r.split_off(5);
+    /// assert_eq!(r, "Hello");
+    /// assert_eq!(rest, " Earth 🌎!");
     /// ```
     #[track_caller]
     #[inline]
-    pub fn line(&self, line_index: usize) -> RopeSlice<'_> {
-        if line_index >= self.line_len() {
-            panic::line_index_out_of_bounds(line_index, self.line_len());
-        }
-
-        let tree_slice = self
-            .tree
-            .slice(RawLineMetric(line_index)..RawLineMetric(line_index + 1));
-
-        let mut line = RopeSlice { tree_slice, has_trailing_newline: false };
-
-        if line.tree_slice.summary().line_breaks() == 1 {
-            line.truncate_trailing_line_break();
-        }
-
-        line
+    pub fn split_off(&mut self, byte_offset: usize) -> Self {
+        let rest = Rope::from(self.byte_slice(byte_offset..));
+        self.truncate(byte_offset);
+        rest
     }
 
-    /// Returns the number of lines in the `Rope`.
+    /// Registers a callback which will be called with an [`EditInfo`] after
+    /// every edit performed on the `Rope`, returning an id that can be
+    /// passed to [`unsubscribe()`](Self::unsubscribe()) to remove it.
     ///
-    /// The final line break is optional and doesn't count as a separate empty
-    /// line.
+    /// Callbacks are called in the order in which they were registered.
+    /// They're not inherited by the clones of the `Rope`, and so neither by
+    /// its [`snapshot()`](Self::snapshot())s.
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
+    /// # use std::sync::{Arc, Mutex};
     /// #
-    /// let mut r = Rope::new();
-    ///
-    /// assert_eq!(r.line_len(), 0);
+    /// let mut r = Rope::from("Hello World");
     ///
-    /// r.insert(0, "a");
-    /// assert_eq!(r.line_len(), 1);
+    /// let edits = Arc::new(Mutex::new(Vec::new()));
     ///
-    /// r.insert(1, "\n");
-    /// assert_eq!(r.line_len(), 1);
+    /// let id = r.subscribe({
+    ///     let edits = Arc::clone(&edits);
+    ///     move |edit| edits.lock().unwrap().push(edit.clone())
+    /// });
     ///
-    /// r.insert(2, "b");
-    /// assert_eq!(r.line_len(), 2);
+    /// r.replace(6..11, "Earth!");
+    /// r.unsubscribe(id);
+    /// r.delete(..6);
     ///
-    /// r.insert(3, "\r\n");
-    /// assert_eq!(r.line_len(), 2);
+    /// let edits = edits.lock().unwrap();
+    /// assert_eq!(edits.len(), 1);
+    /// assert_eq!(edits[0].byte_range, 6..11);
+    /// assert_eq!(edits[0].inserted_len, 6);
     /// ```
     #[inline]
-    pub fn line_len(&self) -> usize {
-        self.tree.summary().line_breaks() + 1
-            - (self.has_trailing_newline as usize)
-            - (self.is_empty() as usize)
+    pub fn subscribe<F>(&mut self, callback: F) -> SubscriptionId
+    where
+        F: FnMut(&EditInfo) + Send + Sync + 'static,
+    {
+        self.extras_mut().subscribers.insert(Box::new(callback))
     }
 
-    /// Returns the line offset of the given byte.
+    /// Returns the line ranges touched by the edits performed since the last
+    /// call to this method, sorted by their position, and starts collecting
+    /// a new set of dirty lines.
     ///
-    /// # Panics
+    /// The line ranges refer to the current contents of the `Rope`, with
+    /// ranges touched by more than one edit being merged. Note that deleting
+    /// one or more line breaks only marks the line where the deletion
+    /// happened as dirty, so renderers should also check whether
+    /// [`line_len()`](Self::line_len()) changed to know if they need to clear
+    /// lines past the end of the `Rope`.
     ///
-    /// Panics if the byte offset is out of bounds (i.e. greater than
-    /// [`byte_len()`](Self::byte_len())).
+    /// Returns an empty vector if dirty lines are not being tracked. See
+    /// [`track_dirty_lines()`](Self::track_dirty_lines()) for more.
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("foo\nbar\r\nbaz");
+    /// let mut r = Rope::from("foo\nbar\nbaz\nqux\n");
     ///
-    /// assert_eq!(r.line_of_byte(0), 0);
-    /// assert_eq!(r.line_of_byte(3), 0);
-    /// assert_eq!(r.line_of_byte(4), 1);
-    /// assert_eq!(r.line_of_byte(8), 1); // between the '\r' and the '\n'
-    /// assert_eq!(r.line_of_byte(r.byte_len()), 2);
+    /// r.track_dirty_lines(true);
+    ///
+    /// r.insert(0, "// ");
+    /// r.replace(11..14, "BAZ\nBAZ");
+    ///
+    /// assert_eq!(r.take_dirty_lines(), [0..1, 2..4]);
+    /// assert!(r.take_dirty_lines().is_empty());
     /// ```
-    #[track_caller]
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
     #[inline]
-    pub fn line_of_byte(&self, byte_offset: usize) -> usize {
-        if byte_offset > self.byte_len() {
-            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
-        }
-
-        let RawLineMetric(line_offset) =
-            self.tree.convert_measure(ByteMetric(byte_offset));
+    pub fn take_dirty_lines(&mut self) -> Vec<Range<usize>> {
+        self.extras
+            .as_mut()
+            .and_then(|extras| extras.dirty_lines.as_mut())
+            .map(DirtyLines::take)
+            .unwrap_or_default()
+    }
 
-        line_offset
+    /// Starts or stops tracking the lines touched by the edits performed on
+    /// the `Rope`, which can be retrieved by calling
+    /// [`take_dirty_lines()`](Self::take_dirty_lines()).
+    ///
+    /// Clones of a `Rope` that's tracking its dirty lines keep tracking them
+    /// independently from the original.
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn track_dirty_lines(&mut self, track: bool) {
+        if track {
+            self.extras_mut().dirty_lines.get_or_insert_with(Default::default);
+        } else if let Some(extras) = &mut self.extras {
+            extras.dirty_lines = None;
+        }
     }
 
-    /// Returns an immutable slice of the `Rope` in the specified line range,
-    /// where the start and end of the range are interpreted as offsets.
+    /// Shortens the `Rope` to the given byte length, deleting everything
+    /// after it.
+    ///
+    /// If the byte offset is greater than or equal to the `Rope`'s
+    /// [`byte_len()`](Self::byte_len()) this has no effect.
     ///
     /// # Panics
     ///
-    /// Panics if the start is greater than the end or if the end is out of
-    /// bounds (i.e. greater than [`line_len()`](Self::line_len())).
+    /// Panics if the byte offset is in bounds but doesn't lie on a code point
+    /// boundary.
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("foo\nbar\r\nbaz\nfoobar\n");
+    /// let mut r = Rope::from("Hello Earth 🌎!");
     ///
-    /// assert_eq!(r.line_slice(..1), "foo\n");
-    /// assert_eq!(r.line_slice(1..3), "bar\r\nbaz\n");
-    /// assert_eq!(r.line_slice(3..4), "foobar\n");
-    /// assert_eq!(r.line_slice(4..), "");
+    /// r.truncate(5);
+    /// assert_eq!(r, "Hello");
+    ///
+    /// r.truncate(10);
+    /// assert_eq!(r, "Hello");
     /// ```
     #[track_caller]
     #[inline]
-    pub fn line_slice<R>(&self, line_range: R) -> RopeSlice<'_>
-    where
-        R: RangeBounds<usize>,
-    {
-        let (start, end) =
-            range_bounds_to_start_end(line_range, 0, self.line_len());
+    pub fn truncate(&mut self, byte_offset: usize) {
+        let byte_len = self.byte_len();
 
-        if start > end {
-            panic::line_start_after_end(start, end);
+        if byte_offset >= byte_len {
+            return;
         }
 
-        if end > self.line_len() {
-            panic::line_offset_out_of_bounds(end, self.line_len());
-        }
+        self.edit_with(byte_offset..byte_len, 0, |this| {
+            let kept = this.byte_slice(..byte_offset);
 
-        self.tree.slice(RawLineMetric(start)..RawLineMetric(end)).into()
+            let has_trailing_newline = kept.has_trailing_newline;
+
+            let tree = if byte_offset == 0 {
+                Tree::default()
+            } else {
+                Tree::from(kept.tree_slice)
+            };
+
+            this.tree = tree;
+            this.has_trailing_newline = has_trailing_newline;
+        });
     }
 
-    /// Returns an iterator over the lines of this `Rope`, not including the
-    /// line terminators.
-    ///
-    /// The final line break is optional and doesn't cause the iterator to
-    /// return a final empty line.
-    ///
-    /// If you want to include the line breaks consider using the
-    /// [`raw_lines()`](Self::raw_lines()) method instead.
+    /// Like [`append()`](Self::append()), but returns an error instead of
+    /// panicking if the `Rope` would become longer than
+    /// [`MAX_BYTE_LEN`](Self::MAX_BYTE_LEN), in which case it's left
+    /// untouched.
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let r = Rope::from("foo\nbar\r\nbaz\n");
-    ///
-    /// let mut lines = r.lines();
+    /// let mut r = Rope::from("Hello ");
     ///
-    /// assert_eq!("foo", lines.next().unwrap());
-    /// assert_eq!("bar", lines.next().unwrap());
-    /// assert_eq!("baz", lines.next().unwrap());
-    /// assert_eq!(None, lines.next());
+    /// assert!(r.try_append(Rope::from("Earth 🌎!")).is_ok());
+    /// assert_eq!(r, "Hello Earth 🌎!");
     /// ```
     #[inline]
-    pub fn lines(&self) -> Lines<'_> {
-        Lines::from(self)
+    pub fn try_append(&mut self, other: Self) -> Result<(), TooLongError> {
+        TooLongError::check(self.byte_len(), 0, other.byte_len())?;
+        self.append(other);
+        Ok(())
     }
 
-    /// Returns an iterator over the lines of this `Rope`, including the
-    /// line terminators.
+    /// Calls `f` with every non-empty chunk of the `Rope`, stopping at the
+    /// first error it returns.
     ///
-    /// The final line break is optional and doesn't cause the iterator to
-    /// return a final empty line.
+    /// Unlike iterating over [`chunks()`](Self::chunks()), this doesn't keep
+    /// a decompressed copy of the compressed chunks that don't have one, so
+    /// it's what the methods reading the whole `Rope` in a single pass
+    /// without borrowing its text should use.
+    #[inline]
+    fn try_for_each_chunk<E>(
+        &self,
+        mut f: impl FnMut(&str) -> Result<(), E>,
+    ) -> Result<(), E> {
+        type NodeRef<'a, const ARITY: usize, const MAX_BYTES: usize, C> =
+            crate::tree::NodeRef<'a, ARITY, GapBuffer<MAX_BYTES>, C>;
+
+        fn visit<const ARITY: usize, const MAX_BYTES: usize, C, E>(
+            node: NodeRef<'_, ARITY, MAX_BYTES, C>,
+            f: &mut impl FnMut(&str) -> Result<(), E>,
+        ) -> Result<(), E>
+        where
+            C: RefCount,
+        {
+            match node.leaf() {
+                Some(leaf) => leaf.with_chunks(|left, right| {
+                    [left, right]
+                        .into_iter()
+                        .filter(|chunk| !chunk.is_empty())
+                        .try_for_each(&mut *f)
+                }),
+                None => node.children().try_for_each(|c| visit(c, f)),
+            }
+        }
+
+        visit(self.tree.root_ref(), &mut f)
+    }
+
+    /// Like [`insert()`](Self::insert()), but returns an error instead of
+    /// panicking if the `Rope` would become longer than
+    /// [`MAX_BYTE_LEN`](Self::MAX_BYTE_LEN), in which case it's left
+    /// untouched.
     ///
-    /// If you don't want to include the line breaks consider using the
-    /// [`lines()`](Self::lines()) method instead.
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`insert()`](Self::insert()), except for
+    /// the length of the `Rope`.
     ///
     /// # Examples
     ///
     /// ```
     /// # use crop::Rope;
     /// #
-    /// let mut r = Rope::from("foo\nbar\r\nbaz");
-    ///
-    /// let mut raw_lines = r.raw_lines();
-    ///
-    /// assert_eq!("foo\n", raw_lines.next().unwrap());
-    /// assert_eq!("bar\r\n", raw_lines.next().unwrap());
-    /// assert_eq!("baz", raw_lines.next().unwrap());
-    /// assert_eq!(None, raw_lines.next());
-    ///
-    /// r.insert(r.byte_len(), "\n");
-    ///
-    /// let mut raw_lines = r.raw_lines();
+    /// let mut r = Rope::from("Hello!");
     ///
-    /// assert_eq!("foo\n", raw_lines.next().unwrap());
-    /// assert_eq!("bar\r\n", raw_lines.next().unwrap());
-    /// assert_eq!("baz\n", raw_lines.next().unwrap());
-    /// assert_eq!(None, raw_lines.next());
+    /// assert!(r.try_insert(5, " Earth 🌎").is_ok());
+    /// assert_eq!(r, "Hello Earth 🌎!");
     /// ```
+    #[track_caller]
     #[inline]
-    pub fn raw_lines(&self) -> RawLines<'_> {
-        RawLines::from(self)
-    }
-
-    /// Returns a new empty [`Rope`].
-    #[inline]
-    pub fn new() -> Self {
-        Self::default()
+    pub fn try_insert<T>(
+        &mut self,
+        byte_offset: usize,
+        text: T,
+    ) -> Result<(), TooLongError>
+    where
+        T: AsRef<str>,
+    {
+        self.try_replace(byte_offset..byte_offset, text)
     }
 
-    /// Replaces the contents of the `Rope` within the specified byte range
-    /// with the given string, where the start and end of the range are
-    /// interpreted as byte offsets.
+    /// Like [`replace()`](Self::replace()), but returns an error instead of
+    /// panicking if the `Rope` would become longer than
+    /// [`MAX_BYTE_LEN`](Self::MAX_BYTE_LEN), in which case it's left
+    /// untouched.
     ///
     /// # Panics
     ///
-    /// Panics if the start or the end of the byte range don't lie on a code
-    /// point boundary, if the start is greater than the end or if the end is
-    /// out of bounds (i.e. greater than [`byte_len()`](Self::byte_len())).
+    /// Panics in the same cases as [`replace()`](Self::replace()), except
+    /// for the length of the `Rope`.
     ///
     /// # Examples
     ///
@@ -677,15 +4212,34 @@ impl Rope {
     /// #
     /// let mut r = Rope::from("Hello Earth 🌎!");
     ///
-    /// r.replace(6..16, "Saturn 🪐");
+    /// assert!(r.try_replace(6..16, "Saturn 🪐").is_ok());
     /// assert_eq!(r, "Hello Saturn 🪐!");
     /// ```
     #[track_caller]
     #[inline]
-    pub fn replace<R, T>(&mut self, byte_range: R, text: T)
+    pub fn try_replace<R, T>(
+        &mut self,
+        byte_range: R,
+        text: T,
+    ) -> Result<(), TooLongError>
     where
         R: RangeBounds<usize>,
         T: AsRef<str>,
+    {
+        self.try_replace_trimming(byte_range, text.as_ref()).map(|_| ())
+    }
+
+    /// Like [`try_replace()`](Self::try_replace()), but returns the number
+    /// of bytes deleted from the start of the `Rope` to enforce its limit.
+    #[track_caller]
+    #[inline]
+    fn try_replace_trimming<R>(
+        &mut self,
+        byte_range: R,
+        text: &str,
+    ) -> Result<usize, TooLongError>
+    where
+        R: RangeBounds<usize>,
     {
         let (start, end) =
             range_bounds_to_start_end(byte_range, 0, self.byte_len());
@@ -695,29 +4249,31 @@ impl Rope {
         }
 
         if end > self.byte_len() {
-            panic::byte_offset_out_of_bounds(end, self.line_len());
+            panic::byte_offset_out_of_bounds(end, self.byte_len());
         }
 
-        let text = text.as_ref();
-
-        let mut update_trailing = false;
+        // This has to be checked before building the tree of a long text,
+        // whose summary could already be too long.
+        TooLongError::check(self.byte_len(), end - start, text.len())?;
 
-        if end == self.byte_len() {
-            if !text.is_empty() {
-                self.has_trailing_newline = text.ends_with('\n');
-            } else if start == 0 {
-                self.has_trailing_newline = false;
-            } else {
-                update_trailing = true;
-            }
-        }
+        let inserted =
+            (text.len() >= Self::BULK_INSERT_MIN_BYTES).then(|| {
+                Tree::from_leaves(
+                    GapBuffer::<MAX_BYTES>::segmenter(text)
+                        .map(GapBuffer::<MAX_BYTES>::from),
+                )
+            });
 
-        self.tree.replace(ByteMetric(start)..ByteMetric(end), text);
+        Ok(self.replace_with(start..end, text, inserted))
+    }
 
-        if update_trailing {
-            self.has_trailing_newline =
-                self.chunks().next_back().unwrap().ends_with('\n');
-        }
+    /// Removes a callback registered with [`subscribe()`](Self::subscribe()),
+    /// returning whether it was still registered.
+    #[inline]
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.extras
+            .as_mut()
+            .map_or(false, |extras| extras.subscribers.remove(id))
     }
 
     /// Returns the number of UTF-16 code units the `Rope` would have if it
@@ -766,6 +4322,10 @@ impl Rope {
             panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
         }
 
+        if self.is_ascii() {
+            return byte_offset;
+        }
+
         let super::metrics::Utf16Metric(utf16_offset) =
             self.tree.convert_measure(ByteMetric(byte_offset));
 
@@ -797,7 +4357,10 @@ impl Rope {
     #[cfg(feature = "utf16-metric")]
     #[track_caller]
     #[inline]
-    pub fn utf16_slice<R>(&self, utf16_range: R) -> RopeSlice<'_>
+    pub fn utf16_slice<R>(
+        &self,
+        utf16_range: R,
+    ) -> RopeSlice<'_, C, ARITY, MAX_BYTES>
     where
         R: RangeBounds<usize>,
     {
@@ -816,19 +4379,258 @@ impl Rope {
 
         self.tree.slice(Utf16Metric(start)..Utf16Metric(end)).into()
     }
+
+    /// Returns the number of words in the `Rope`, where a word is a maximal
+    /// sequence of non-whitespace characters (like in `wc -w`).
+    ///
+    /// Every chunk keeps track of the number of words it contains, so this
+    /// runs in `O(1)` time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let mut r = Rope::from("Hello there,\nGeneral Kenobi!");
+    /// assert_eq!(r.word_len(), 4);
+    ///
+    /// r.replace(5..6, "");
+    /// assert_eq!(r.word_len(), 3);
+    ///
+    /// r.insert(2, " ");
+    /// assert_eq!(r.word_len(), 4);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "word-metric")))]
+    #[cfg(feature = "word-metric")]
+    #[inline]
+    pub fn word_len(&self) -> usize {
+        // The stale word counts are refreshed after every edit.
+        self.tree.summary().words().unwrap()
+    }
+
+    /// Writes the text of the `Rope` to `writer` in the given encoding, e.g.
+    /// to save it to a file that was opened with
+    /// [`from_reader_with_encoding()`](Self::from_reader_with_encoding()).
+    ///
+    /// The chunks are encoded one at a time, so the whole text is never
+    /// held in memory in the target encoding. The writer isn't buffered, so
+    /// wrapping it in a [`BufWriter`](std::io::BufWriter) is usually a good
+    /// idea.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidData`] if the text contains a
+    /// character that can't be represented in the encoding, in which case
+    /// only part of the text may have been written. Any error returned by
+    /// the writer is returned as is.
+    ///
+    /// [`InvalidData`]: std::io::ErrorKind::InvalidData
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("Hé\n");
+    ///
+    /// let mut latin1 = Vec::new();
+    /// r.write_with_encoding(&mut latin1, encoding_rs::WINDOWS_1252).unwrap();
+    /// assert_eq!(latin1, [0x48, 0xe9, 0x0a]);
+    ///
+    /// let mut utf16 = Vec::new();
+    /// r.write_with_encoding(&mut utf16, encoding_rs::UTF_16BE).unwrap();
+    /// assert_eq!(utf16, [0x00, 0x48, 0x00, 0xe9, 0x00, 0x0a]);
+    ///
+    /// let r = Rope::from("🦀");
+    /// assert!(r
+    ///     .write_with_encoding(&mut latin1, encoding_rs::WINDOWS_1252)
+    ///     .is_err());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+    #[cfg(feature = "encoding")]
+    #[inline]
+    pub fn write_with_encoding<W: std::io::Write>(
+        &self,
+        mut writer: W,
+        encoding: &'static encoding_rs::Encoding,
+    ) -> std::io::Result<()> {
+        use encoding_rs::{EncoderResult, UTF_16BE, UTF_16LE};
+
+        let mut output = alloc::vec![0; 1 << 14];
+
+        // encoding_rs can only decode UTF-16, so we encode it ourselves.
+        if encoding == UTF_16LE || encoding == UTF_16BE {
+            for chunk in self.chunks() {
+                output.clear();
+
+                for unit in chunk.encode_utf16() {
+                    output.extend_from_slice(&if encoding == UTF_16LE {
+                        unit.to_le_bytes()
+                    } else {
+                        unit.to_be_bytes()
+                    });
+                }
+
+                writer.write_all(&output)?;
+            }
+
+            return Ok(());
+        }
+
+        let mut encoder = encoding.new_encoder();
+
+        let mut chunks = self.chunks().peekable();
+
+        while let Some(chunk) = chunks.next() {
+            let is_last = chunks.peek().is_none();
+
+            let mut src = chunk;
+
+            loop {
+                let (result, read, written) = encoder
+                    .encode_from_utf8_without_replacement(
+                        src,
+                        &mut output,
+                        is_last,
+                    );
+
+                writer.write_all(&output[..written])?;
+
+                src = &src[read..];
+
+                match result {
+                    EncoderResult::InputEmpty => break,
+                    EncoderResult::OutputFull => {},
+                    EncoderResult::Unmappable(ch) => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            alloc::format!(
+                                "{ch:?} can't be encoded in {}",
+                                encoding.name()
+                            ),
+                        ));
+                    },
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "paging")]
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    Rope<C, ARITY, MAX_BYTES>
+{
+    /// Pages the chunks of the `Rope` out to the given [`SpillFile`],
+    /// returning the number of bytes of text written to it.
+    ///
+    /// Only the cold chunks that are in memory are written, i.e. the ones
+    /// that have been edited since the last time the `Rope` was paged out,
+    /// except the ones containing any of the last 16 edits made since the
+    /// first time it was, which are likely to be edited again soon. After
+    /// this the operating system is free to evict their text from memory, and
+    /// it reads it back in from the file when it's accessed again. A chunk is
+    /// copied back into memory the first time it's edited, which frees its
+    /// slot in the file.
+    ///
+    /// The paged out chunks replace the original ones, so [`changes_since()`]
+    /// will report them as changed when called with a snapshot taken before
+    /// paging them out, unless the `node-hashes` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the file fails, or if the chunks of
+    /// the `Rope` don't have the same maximum length as the ones of the other
+    /// `Rope`s paged out to it.
+    ///
+    /// [`SpillFile`]: crate::paging::SpillFile
+    /// [`changes_since()`]: Self::changes_since()
+    #[cfg_attr(docsrs, doc(cfg(feature = "paging")))]
+    #[inline]
+    pub fn page_out(
+        &mut self,
+        spill: &mut super::paging::SpillFile,
+    ) -> std::io::Result<u64> {
+        let hot_offsets = self
+            .extras
+            .as_ref()
+            .and_then(|extras| extras.recent_edits.as_ref())
+            .map_or(&[][..], RecentEdits::offsets);
+
+        let (leaf_indices, spilled, written) =
+            spill.spill(self.tree.root_ref(), hot_offsets)?;
+
+        let mut spilled = spilled.into_iter();
+
+        self.tree.update_leaves(&leaf_indices, |leaf| {
+            *leaf = spilled.next().expect("one chunk per index");
+        });
+
+        self.extras_mut().recent_edits.get_or_insert_with(Default::default);
+
+        Ok(written as u64)
+    }
+}
+
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<RopeSlice<'_, C, ARITY, MAX_BYTES>> for Rope<C, ARITY, MAX_BYTES>
+{
+    #[inline]
+    fn from(rope_slice: RopeSlice<'_, C, ARITY, MAX_BYTES>) -> Self {
+        Self::from_tree(
+            Tree::from(rope_slice.tree_slice),
+            rope_slice.has_trailing_newline,
+        )
+    }
+}
+
+impl From<&Rope> for Rope<LocalCount> {
+    #[inline]
+    fn from(rope: &Rope) -> Self {
+        Self::from_tree(
+            Tree::from_leaves(rope.tree.leaves().map(RopeChunk::from)),
+            rope.has_trailing_newline,
+        )
+    }
+}
+
+impl From<&Rope<LocalCount>> for Rope {
+    #[inline]
+    fn from(rope: &Rope<LocalCount>) -> Self {
+        Self::from_tree(
+            Tree::from_leaves(rope.tree.leaves().map(RopeChunk::from)),
+            rope.has_trailing_newline,
+        )
+    }
+}
+
+impl Default for Rope {
+    #[inline]
+    fn default() -> Self {
+        Self::empty()
+    }
 }
 
-impl From<RopeSlice<'_>> for Rope {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> Clone
+    for Rope<C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn from(rope_slice: RopeSlice<'_>) -> Rope {
+    fn clone(&self) -> Self {
         Self {
-            has_trailing_newline: rope_slice.has_trailing_newline,
-            tree: Tree::from(rope_slice.tree_slice),
+            tree: self.tree.clone(),
+            has_trailing_newline: self.has_trailing_newline,
+            content_hash: self.content_hash.clone(),
+            revision: self.revision,
+            revision_is_shared: true,
+            extras: self.extras.clone(),
         }
     }
 }
 
-impl core::fmt::Debug for Rope {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> core::fmt::Debug
+    for Rope<C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.write_str("Rope(\"")?;
@@ -837,25 +4639,20 @@ impl core::fmt::Debug for Rope {
     }
 }
 
-impl core::fmt::Display for Rope {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::fmt::Display for Rope<C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        for chunk in self.chunks() {
-            f.write_str(chunk)?;
-        }
-        Ok(())
+        self.try_for_each_chunk(|chunk| f.write_str(chunk))
     }
 }
 
 impl From<&str> for Rope {
+    #[track_caller]
     #[inline]
     fn from(s: &str) -> Self {
-        Rope {
-            has_trailing_newline: s.ends_with('\n'),
-            tree: Tree::from_leaves(
-                RopeChunk::segmenter(s).map(RopeChunk::from),
-            ),
-        }
+        Self::from_text(s)
     }
 }
 
@@ -866,6 +4663,51 @@ impl From<String> for Rope {
     }
 }
 
+impl TryFrom<&[u8]> for Rope {
+    type Error = core::str::Utf8Error;
+
+    /// Validates the bytes one window at a time, appending each valid
+    /// window to a [`RopeBuilder`] while it's still in cache.
+    #[inline]
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        const WINDOW_LEN: usize = 1 << 16;
+
+        let mut builder = RopeBuilder::new();
+
+        let mut rest = bytes;
+
+        while !rest.is_empty() {
+            let mut window = &rest[..rest.len().min(WINDOW_LEN)];
+
+            // Leave a code point that's split by the end of the window to the
+            // next window, so that only invalid inputs need to be validated
+            // again.
+            if window.len() < rest.len() {
+                window = &window[..super::utils::complete_utf8_len(window)];
+            }
+
+            let valid = match super::utils::from_utf8(window) {
+                Ok(valid) => valid,
+
+                // Validate everything up to the error again to get an error
+                // with the offset in the whole input. This only happens once
+                // so it doesn't matter that it's slower.
+                Err(_) => {
+                    let end = bytes.len() - rest.len() + window.len();
+                    return Err(core::str::from_utf8(&bytes[..end])
+                        .expect_err("the window is invalid"));
+                },
+            };
+
+            builder.append(valid);
+
+            rest = &rest[valid.len()..];
+        }
+
+        Ok(builder.build())
+    }
+}
+
 impl From<alloc::borrow::Cow<'_, str>> for Rope {
     #[inline]
     fn from(moo: alloc::borrow::Cow<'_, str>) -> Self {
@@ -876,6 +4718,27 @@ impl From<alloc::borrow::Cow<'_, str>> for Rope {
     }
 }
 
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> core::ops::Add
+    for Rope<C, ARITY, MAX_BYTES>
+{
+    type Output = Self;
+
+    #[inline]
+    fn add(mut self, rhs: Self) -> Self {
+        self.append(rhs);
+        self
+    }
+}
+
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::ops::AddAssign for Rope<C, ARITY, MAX_BYTES>
+{
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.append(rhs);
+    }
+}
+
 impl core::str::FromStr for Rope {
     type Err = core::convert::Infallible;
 
@@ -885,78 +4748,197 @@ impl core::str::FromStr for Rope {
     }
 }
 
-impl core::cmp::PartialEq<Rope> for Rope {
+impl<
+        C: RefCount,
+        D: RefCount,
+        const ARITY: usize,
+        const MAX_BYTES: usize,
+        const RHS_ARITY: usize,
+        const RHS_MAX_BYTES: usize,
+    > core::cmp::PartialEq<Rope<D, RHS_ARITY, RHS_MAX_BYTES>>
+    for Rope<C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn eq(&self, rhs: &Rope) -> bool {
+    fn eq(&self, rhs: &Rope<D, RHS_ARITY, RHS_MAX_BYTES>) -> bool {
+        #[cfg(feature = "line-metric")]
+        if self.line_len() != rhs.line_len() {
+            return false;
+        }
+
         (self.byte_len() == rhs.byte_len())
-            && (self.line_len() == rhs.line_len())
             && chunks_eq_chunks(self.chunks(), rhs.chunks())
     }
 }
 
-impl core::cmp::PartialEq<RopeSlice<'_>> for Rope {
+impl<
+        C: RefCount,
+        D: RefCount,
+        const ARITY: usize,
+        const MAX_BYTES: usize,
+        const RHS_ARITY: usize,
+        const RHS_MAX_BYTES: usize,
+    > core::cmp::PartialEq<RopeSlice<'_, D, RHS_ARITY, RHS_MAX_BYTES>>
+    for Rope<C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn eq(&self, rhs: &RopeSlice<'_>) -> bool {
+    fn eq(&self, rhs: &RopeSlice<'_, D, RHS_ARITY, RHS_MAX_BYTES>) -> bool {
+        #[cfg(feature = "line-metric")]
+        if self.line_len() != rhs.line_len() {
+            return false;
+        }
+
         (self.byte_len() == rhs.byte_len())
-            && (self.line_len() == rhs.line_len())
             && chunks_eq_chunks(self.chunks(), rhs.chunks())
     }
 }
 
-impl core::cmp::PartialEq<str> for Rope {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<str> for Rope<C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn eq(&self, rhs: &str) -> bool {
         (self.byte_len() == rhs.len()) && chunks_eq_str(self.chunks(), rhs)
     }
 }
 
-impl core::cmp::PartialEq<Rope> for str {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<Rope<C, ARITY, MAX_BYTES>> for str
+{
     #[inline]
-    fn eq(&self, rhs: &Rope) -> bool {
+    fn eq(&self, rhs: &Rope<C, ARITY, MAX_BYTES>) -> bool {
         rhs == self
     }
 }
 
-impl core::cmp::PartialEq<&str> for Rope {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<&str> for Rope<C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn eq(&self, rhs: &&str) -> bool {
         self == *rhs
     }
 }
 
-impl core::cmp::PartialEq<Rope> for &str {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<Rope<C, ARITY, MAX_BYTES>> for &str
+{
     #[inline]
-    fn eq(&self, rhs: &Rope) -> bool {
+    fn eq(&self, rhs: &Rope<C, ARITY, MAX_BYTES>) -> bool {
         rhs == self
     }
 }
 
-impl core::cmp::PartialEq<String> for Rope {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<String> for Rope<C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn eq(&self, rhs: &String) -> bool {
         self == &**rhs
     }
 }
 
-impl core::cmp::PartialEq<Rope> for String {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<Rope<C, ARITY, MAX_BYTES>> for String
+{
     #[inline]
-    fn eq(&self, rhs: &Rope) -> bool {
+    fn eq(&self, rhs: &Rope<C, ARITY, MAX_BYTES>) -> bool {
         rhs == self
     }
 }
 
-impl core::cmp::PartialEq<alloc::borrow::Cow<'_, str>> for Rope {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<alloc::borrow::Cow<'_, str>>
+    for Rope<C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn eq(&self, rhs: &alloc::borrow::Cow<'_, str>) -> bool {
         self == &**rhs
     }
 }
 
-impl core::cmp::PartialEq<Rope> for alloc::borrow::Cow<'_, str> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<Rope<C, ARITY, MAX_BYTES>>
+    for alloc::borrow::Cow<'_, str>
+{
     #[inline]
-    fn eq(&self, rhs: &Rope) -> bool {
+    fn eq(&self, rhs: &Rope<C, ARITY, MAX_BYTES>) -> bool {
         rhs == self
     }
 }
 
-impl core::cmp::Eq for Rope {}
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> core::cmp::Eq
+    for Rope<C, ARITY, MAX_BYTES>
+{
+}
+
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> core::hash::Hash
+    for Rope<C, ARITY, MAX_BYTES>
+{
+    #[inline]
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        hash_chunks(self.chunks(), state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+
+    use super::*;
+
+    type NodeRef<'a> = crate::tree::NodeRef<'a, DEFAULT_ARITY, RopeChunk>;
+
+    fn collect_addrs(node: NodeRef<'_>, addrs: &mut BTreeSet<usize>) {
+        addrs.insert(node.addr());
+        node.children().for_each(|child| collect_addrs(child, addrs));
+    }
+
+    /// Returns the number of nodes of the tree rooted at `node` that aren't
+    /// in `shared`.
+    fn count_unshared(node: NodeRef<'_>, shared: &BTreeSet<usize>) -> usize {
+        if shared.contains(&node.addr()) {
+            return 0;
+        }
+
+        1 + node
+            .children()
+            .map(|child| count_unshared(child, shared))
+            .sum::<usize>()
+    }
+
+    /// Deleting a range detaches the subtrees fully covered by it without
+    /// visiting them, so only the nodes on the paths to its two ends are
+    /// copied, no matter how many chunks are deleted.
+    #[test]
+    fn delete_only_copies_the_boundary_paths() {
+        let text = "Hello, world!\n".repeat(10_000);
+
+        let original = Rope::from(text.as_str());
+
+        let mut addrs = BTreeSet::new();
+        collect_addrs(original.tree.root_ref(), &mut addrs);
+
+        let depth = core::iter::successors(
+            Some(original.tree.root_ref()),
+            |node| node.children().next(),
+        )
+        .count();
+
+        for percent in [1, 10, 50, 90, 99] {
+            let len = text.len() * percent / 100;
+            let start = (text.len() - len) / 2;
+
+            let mut r = original.clone();
+            r.delete(start..start + len);
+            r.assert_invariants();
+
+            let copied = count_unshared(r.tree.root_ref(), &addrs);
+
+            assert!(
+                copied <= 4 * depth,
+                "deleting {percent}% copied {copied} nodes of a tree of \
+                 depth {depth}"
+            );
+        }
+    }
+}
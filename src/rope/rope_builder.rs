@@ -1,17 +1,57 @@
+use alloc::string::String;
+
 use super::gap_buffer::GapBuffer;
 use super::metrics::ChunkSummary;
-use super::rope::RopeChunk;
-use super::utils::split_adjusted;
-use super::Rope;
-use crate::tree::TreeBuilder;
+use super::rope::{DEFAULT_ARITY, DEFAULT_CHUNK_MAX_BYTES};
+use super::utils::{
+    complete_utf8_len,
+    ends_with_line_break,
+    from_utf8,
+    panic_messages as panic,
+    split_adjusted,
+};
+use super::{LineEnding, LineEndingCounts, Rope, RopeSlice, TooLongError};
+use crate::tree::{AtomicCount, RefCount, Tree, TreeBuilder};
 
 /// An incremental [`Rope`](crate::Rope) builder.
-#[derive(Clone, Default)]
-pub struct RopeBuilder {
-    tree_builder: TreeBuilder<{ Rope::arity() }, RopeChunk>,
-    buffer: RopeChunk,
+#[derive(Clone)]
+pub struct RopeBuilder<
+    C: RefCount = AtomicCount,
+    const ARITY: usize = DEFAULT_ARITY,
+    const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+> {
+    /// The tree containing the text appended up to and including the last
+    /// `Rope` or `RopeSlice` whose nodes were spliced in, which the tree
+    /// being built is appended to when building the `Rope`.
+    head: Tree<ARITY, GapBuffer<MAX_BYTES>, C>,
+    tree_builder: TreeBuilder<ARITY, GapBuffer<MAX_BYTES>, C>,
+    buffer: GapBuffer<MAX_BYTES>,
     buffer_len_left: usize,
     rope_has_trailing_newline: bool,
+
+    /// The number of bytes appended so far.
+    byte_len: usize,
+
+    /// The line endings found in the appended text, if they're being
+    /// normalized.
+    eol_counts: Option<LineEndingCounts>,
+
+    /// Whether the last appended text ended with a carriage return that was
+    /// converted to a line feed, in which case a line feed at the start of
+    /// the next text belongs to the same line ending.
+    pending_cr: bool,
+
+    /// The bytes of a code point left incomplete by the last call to
+    /// [`push_bytes()`](Self::push_bytes()), which are completed by the next
+    /// one.
+    incomplete: [u8; 3],
+
+    /// The number of bytes of `incomplete` in use.
+    incomplete_len: usize,
+
+    /// The number of bytes the chunks are filled up to, if it was changed
+    /// with [`chunk_len()`](Self::chunk_len()).
+    chunk_len: Option<usize>,
 }
 
 /// Pushes as mush of the slice as possible onto the left chunk of the gap
@@ -24,13 +64,16 @@ pub struct RopeBuilder {
 fn gap_buffer_push_with_remainder<'a, const MAX_BYTES: usize>(
     buffer: &mut GapBuffer<MAX_BYTES>,
     buffer_len_left: &mut usize,
+    chunk_len: usize,
     s: &'a str,
 ) -> Option<&'a str> {
     debug_assert_eq!(buffer.len_right(), 0);
 
+    debug_assert!(chunk_len <= MAX_BYTES);
+
     let len_left = *buffer_len_left;
 
-    let space_left = MAX_BYTES - len_left;
+    let space_left = chunk_len.saturating_sub(len_left);
 
     let (push, rest) = split_adjusted::<false>(s, space_left);
 
@@ -49,17 +92,178 @@ fn gap_buffer_push_with_remainder<'a, const MAX_BYTES: usize>(
 }
 
 impl RopeBuilder {
+    /// Creates a new `RopeBuilder`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for RopeBuilder {
+    #[inline]
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    RopeBuilder<C, ARITY, MAX_BYTES>
+{
     /// Appends `text` to the end of the `Rope` being built.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rope` would become longer than
+    /// [`Rope::MAX_BYTE_LEN`].
+    #[track_caller]
     #[inline]
     pub fn append<T>(&mut self, text: T) -> &mut Self
     where
         T: AsRef<str>,
     {
-        let mut text = text.as_ref();
+        match self.try_append(text) {
+            Ok(this) => this,
+            Err(_) => panic::rope_too_long(),
+        }
+    }
+
+    /// Appends the contents of `rope` to the end of the `Rope` being built.
+    ///
+    /// This is the same as calling
+    /// [`append_slice()`](Self::append_slice()) with a slice spanning the
+    /// whole `rope`, so all of its nodes are shared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{Rope, RopeBuilder};
+    /// #
+    /// let header = Rope::from("// Header\n");
+    ///
+    /// let mut builder = RopeBuilder::new();
+    ///
+    /// builder.append_rope(&header).append("fn main() {}\n");
+    ///
+    /// assert_eq!(builder.build(), "// Header\nfn main() {}\n");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn append_rope(
+        &mut self,
+        rope: &Rope<C, ARITY, MAX_BYTES>,
+    ) -> &mut Self {
+        self.append_slice(rope.byte_slice(..))
+    }
+
+    /// Appends the contents of `slice` to the end of the `Rope` being built.
+    ///
+    /// Instead of copying the text of the slice, the nodes of the `Rope` it
+    /// was taken from are shared with the one being built, with only the
+    /// chunks at the start and end of the slice being copied. This takes
+    /// `O(log n)` time, so a `Rope` can be cheaply composed out of pieces of
+    /// other `Rope`s.
+    ///
+    /// Slices shorter than a single chunk, and all slices if the line
+    /// endings are being normalized, are copied like with
+    /// [`append()`](Self::append()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{Rope, RopeBuilder};
+    /// #
+    /// let r = Rope::from("foo\nbar\nbaz\n");
+    ///
+    /// let mut builder = RopeBuilder::new();
+    ///
+    /// builder.append_slice(r.line_slice(2..3)).append_slice(r.line_slice(..2));
+    ///
+    /// assert_eq!(builder.build(), "baz\nfoo\nbar\n");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn append_slice(
+        &mut self,
+        slice: RopeSlice<'_, C, ARITY, MAX_BYTES>,
+    ) -> &mut Self {
+        match self.try_append_slice(slice) {
+            Ok(this) => this,
+            Err(_) => panic::rope_too_long(),
+        }
+    }
+
+    #[inline]
+    fn append_str(&mut self, text: &str) {
+        if self.eol_counts.is_some() {
+            self.append_normalized(text);
+        } else {
+            self.append_raw(text);
+        }
+    }
+
+    /// Appends `text` converting its line endings to line feeds, and counts
+    /// them.
+    #[inline]
+    fn append_normalized(&mut self, mut text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        let mut counts = self.eol_counts.unwrap_or_default();
+
+        if core::mem::take(&mut self.pending_cr) {
+            if let Some(rest) = text.strip_prefix('\n') {
+                counts.remove(LineEnding::Cr);
+                counts.add(LineEnding::CrLf);
+                text = rest;
+            }
+        }
+
+        let mut start = 0;
+
+        for (idx, eol) in text.match_indices(['\r', '\n']) {
+            // This is the line feed of a CRLF sequence we've already handled.
+            if idx < start {
+                continue;
+            }
+
+            if eol == "\n" {
+                counts.add(LineEnding::Lf);
+                continue;
+            }
+
+            self.append_raw(&text[start..idx]);
+            self.append_raw("\n");
+
+            if text[idx + 1..].starts_with('\n') {
+                counts.add(LineEnding::CrLf);
+                start = idx + 2;
+            } else {
+                counts.add(LineEnding::Cr);
+                self.pending_cr = idx + 1 == text.len();
+                start = idx + 1;
+            }
+        }
+
+        self.append_raw(&text[start..]);
+
+        self.eol_counts = Some(counts);
+    }
+
+    #[inline]
+    fn append_raw(&mut self, mut text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.byte_len += text.len();
+
+        let chunk_len = self.target_chunk_len();
 
         while let Some(rest) = gap_buffer_push_with_remainder(
             &mut self.buffer,
             &mut self.buffer_len_left,
+            chunk_len,
             text,
         ) {
             self.buffer.left_summary =
@@ -73,8 +277,6 @@ impl RopeBuilder {
         }
 
         self.rope_has_trailing_newline = self.buffer.has_trailing_newline();
-
-        self
     }
 
     #[inline]
@@ -105,7 +307,9 @@ impl RopeBuilder {
     /// assert_eq!(rope, "ƒoo\nbär\r\nbaz");
     /// ```
     #[inline]
-    pub fn build(mut self) -> Rope {
+    pub fn build(mut self) -> Rope<C, ARITY, MAX_BYTES> {
+        self.flush_incomplete();
+
         if self.buffer_len_left > 0 {
             self.buffer.left_summary =
                 ChunkSummary::from(self.buffer_left_chunk());
@@ -116,15 +320,492 @@ impl RopeBuilder {
             self.tree_builder.append(self.buffer);
         }
 
-        Rope {
-            tree: self.tree_builder.build(),
-            has_trailing_newline: self.rope_has_trailing_newline,
-        }
+        let mut tree = self.head;
+
+        tree.append(self.tree_builder.build());
+
+        Rope::from_tree(tree, self.rope_has_trailing_newline)
+    }
+
+    /// Returns an error if appending `additional` more bytes would make the
+    /// `Rope` longer than [`Rope::MAX_BYTE_LEN`].
+    ///
+    /// A code point left incomplete by [`push_bytes()`](Self::push_bytes())
+    /// is counted as the replacement character it could be turned into.
+    #[inline]
+    fn check_len(&self, additional: usize) -> Result<(), TooLongError> {
+        let incomplete = if self.incomplete_len > 0 {
+            char::REPLACEMENT_CHARACTER.len_utf8()
+        } else {
+            0
+        };
+
+        TooLongError::check(self.byte_len + incomplete, 0, additional)
+    }
+
+    /// Sets the number of bytes the chunks of the `Rope` are filled up to
+    /// when appending text from now on, which by default is the maximum
+    /// number of bytes a chunk can hold.
+    ///
+    /// Leaving some room in every chunk makes the first edits to a freshly
+    /// loaded document cheaper, since the chunks they hit don't have to be
+    /// split. Chunks can't be made larger than the maximum, which is set by
+    /// the `MAX_BYTES` parameter of the `Rope` (see the
+    /// [crate docs](crate#tuning-the-node-sizes)), so `chunk_len` is clamped
+    /// to it, and to the minimum length a chunk needs to not be rebalanced.
+    ///
+    /// Slices spliced in with [`append_slice()`](Self::append_slice()) keep
+    /// their chunks as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeBuilder;
+    /// #
+    /// let text = "Hello, world!\n".repeat(1000);
+    ///
+    /// let mut builder = RopeBuilder::new();
+    ///
+    /// builder.chunk_len(1024).append(&text);
+    ///
+    /// let rope = builder.build();
+    ///
+    /// assert_eq!(rope, text);
+    /// assert!(rope.chunks().all(|chunk| chunk.len() <= 1024));
+    /// ```
+    #[inline]
+    pub fn chunk_len(&mut self, chunk_len: usize) -> &mut Self {
+        let min = GapBuffer::<MAX_BYTES>::min_bytes().max(4).min(MAX_BYTES);
+        self.chunk_len = Some(chunk_len.clamp(min, MAX_BYTES));
+        self
     }
 
     /// Creates a new `RopeBuilder`.
+    ///
+    /// Unlike [`RopeBuilder::new()`], which always builds a `Rope` with the
+    /// default reference count and node sizes, this works for any of them, so
+    /// they have to be known from the context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{tree::LocalCount, RopeBuilder};
+    /// #
+    /// let mut builder = RopeBuilder::<LocalCount>::empty();
+    /// builder.append("Hello, ").append("world!");
+    ///
+    /// assert_eq!(builder.build(), "Hello, world!");
+    /// ```
     #[inline]
-    pub fn new() -> Self {
-        Self::default()
+    pub fn empty() -> Self {
+        Self {
+            head: Tree::default(),
+            tree_builder: TreeBuilder::default(),
+            buffer: GapBuffer::default(),
+            buffer_len_left: 0,
+            rope_has_trailing_newline: false,
+            byte_len: 0,
+            eol_counts: None,
+            pending_cr: false,
+            incomplete: [0; 3],
+            incomplete_len: 0,
+            chunk_len: None,
+        }
+    }
+
+    /// Replaces the code point left incomplete by the last call to
+    /// [`push_bytes()`](Self::push_bytes()), if any, with the replacement
+    /// character.
+    #[inline]
+    fn flush_incomplete(&mut self) {
+        if core::mem::take(&mut self.incomplete_len) > 0 {
+            self.append_str(
+                char::REPLACEMENT_CHARACTER.encode_utf8(&mut [0; 4]),
+            );
+        }
+    }
+
+    /// Returns the bytes of the code point left incomplete by the last call
+    /// to [`push_bytes()`](Self::push_bytes()), which are waiting for the
+    /// next call to complete them.
+    ///
+    /// If this isn't empty once all the bytes have been pushed the input
+    /// ended in the middle of a code point, and [`build()`](Self::build())
+    /// will replace it with the replacement character `U+FFFD`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeBuilder;
+    /// #
+    /// let mut builder = RopeBuilder::new();
+    ///
+    /// builder.push_bytes(&[b'a', 0xf0, 0x9f]).unwrap();
+    /// assert_eq!(builder.incomplete_bytes(), [0xf0, 0x9f]);
+    ///
+    /// builder.push_bytes(&[0xa6, 0x80]).unwrap();
+    /// assert!(builder.incomplete_bytes().is_empty());
+    ///
+    /// assert_eq!(builder.build(), "a🦀");
+    /// ```
+    #[inline]
+    pub fn incomplete_bytes(&self) -> &[u8] {
+        &self.incomplete[..self.incomplete_len]
+    }
+
+    /// Appends `text` to the end of the `Rope` being built, taking ownership
+    /// of it.
+    ///
+    /// If `text` doesn't fit in the chunk currently being filled but is
+    /// short enough to be a chunk on its own, its allocation is reused for
+    /// the next chunk instead of copying it into a fresh buffer. Either way
+    /// it's dropped before returning, so building a `Rope` out of a list of
+    /// `String`s doesn't need to keep all of them alive until the end.
+    ///
+    /// The allocation is never reused when the line endings are being
+    /// normalized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeBuilder;
+    /// #
+    /// let mut builder = RopeBuilder::new();
+    ///
+    /// for line in ["foo\n", "bar\n", "baz\n"] {
+    ///     builder.push_string(line.to_owned());
+    /// }
+    ///
+    /// assert_eq!(builder.build(), "foo\nbar\nbaz\n");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn push_string(&mut self, text: String) -> &mut Self {
+        if self.check_len(text.len()).is_err() {
+            panic::rope_too_long();
+        }
+
+        self.flush_incomplete();
+
+        let len_left = self.buffer_len_left;
+
+        // Only reuse the allocation if the current buffer would be filled up
+        // anyway, and if it has enough text to not be underfilled once it's
+        // appended to the tree.
+        let chunk_len = self.target_chunk_len();
+
+        let should_reuse = self.eol_counts.is_none()
+            && text.len() > chunk_len.saturating_sub(len_left)
+            && text.len() <= chunk_len
+            && len_left >= GapBuffer::<MAX_BYTES>::min_bytes();
+
+        if !should_reuse {
+            self.append_str(&text);
+            return self;
+        }
+
+        let has_trailing_newline = ends_with_line_break(&text);
+
+        let len = text.len();
+
+        let mut buffer = core::mem::replace(
+            &mut self.buffer,
+            GapBuffer::<MAX_BYTES>::from_string_unsummarized(text),
+        );
+
+        if len_left > 0 {
+            buffer.left_summary = ChunkSummary::from(
+                // SAFETY: we only append string slices to the left chunk of
+                // the gap buffer so it's guaranteed to be valid UTF-8.
+                unsafe {
+                    core::str::from_utf8_unchecked(&buffer.bytes[..len_left])
+                },
+            );
+
+            self.tree_builder.append(buffer);
+        }
+
+        self.buffer_len_left = len;
+
+        self.byte_len += len;
+
+        self.rope_has_trailing_newline = has_trailing_newline;
+
+        self
+    }
+
+    /// Makes the builder convert the line endings of all the text appended
+    /// from now on to line feeds, keeping track of the ones it found.
+    ///
+    /// CRLF sequences, line feeds and carriage returns not followed by a line
+    /// feed are all considered line endings, even when a CRLF sequence is
+    /// split across two calls to [`append()`](Self::append()). The most
+    /// common one can be retrieved with
+    /// [`original_line_ending()`](Self::original_line_ending()) before
+    /// building the `Rope`, so that it can later be saved back with its
+    /// original convention.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{LineEnding, RopeBuilder};
+    /// #
+    /// let mut builder = RopeBuilder::new();
+    ///
+    /// builder.normalize_eols().append("foo\r").append("\nbar\r\nbaz");
+    ///
+    /// let eol = builder.original_line_ending();
+    /// assert_eq!(eol, Some(LineEnding::CrLf));
+    ///
+    /// let mut rope = builder.build();
+    /// assert_eq!(rope, "foo\nbar\nbaz");
+    ///
+    /// rope.normalize_eols(eol.unwrap());
+    /// assert_eq!(rope, "foo\r\nbar\r\nbaz");
+    /// ```
+    #[inline]
+    pub fn normalize_eols(&mut self) -> &mut Self {
+        self.eol_counts.get_or_insert_with(LineEndingCounts::default);
+        self
+    }
+
+    /// Returns the most common line ending in the text appended after
+    /// calling [`normalize_eols()`](Self::normalize_eols()), counted before
+    /// the conversion and with ties broken like in
+    /// [`Rope::detect_line_ending()`](crate::Rope::detect_line_ending()), or
+    /// `None` if the line endings aren't being normalized.
+    #[inline]
+    pub fn original_line_ending(&self) -> Option<LineEnding> {
+        self.eol_counts.as_ref().map(LineEndingCounts::most_common)
+    }
+
+    /// Appends UTF-8 encoded `bytes` to the end of the `Rope` being built,
+    /// validating them along the way.
+    ///
+    /// The bytes don't have to end on a code point boundary: a code point
+    /// split across two calls is buffered until the next call completes it,
+    /// so the buffers of a reader can be pushed as they come in. See
+    /// [`incomplete_bytes()`](Self::incomplete_bytes()) to check whether the
+    /// input ended in the middle of a code point.
+    ///
+    /// Appending a `&str` with [`append()`](Self::append()) while a code
+    /// point is incomplete replaces it with the replacement character
+    /// `U+FFFD`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes aren't valid UTF-8. The valid bytes
+    /// before the invalid sequence are still appended, while the rest are
+    /// discarded. The offset in the error is relative to the start of the
+    /// first code point that hadn't been appended yet, i.e. to the start of
+    /// the incomplete code point buffered by the previous call if there was
+    /// one, or to the start of `bytes` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeBuilder;
+    /// #
+    /// let mut builder = RopeBuilder::new();
+    ///
+    /// let bytes = "Hello, 🌎!".as_bytes();
+    ///
+    /// // The globe is split across the two calls.
+    /// builder.push_bytes(&bytes[..9]).unwrap();
+    /// builder.push_bytes(&bytes[9..]).unwrap();
+    ///
+    /// assert_eq!(builder.build(), "Hello, 🌎!");
+    ///
+    /// let mut builder = RopeBuilder::new();
+    ///
+    /// let err = builder.push_bytes(&[b'a', b'b', 0xff, b'c']).err().unwrap();
+    /// assert_eq!(err.valid_up_to(), 2);
+    /// assert_eq!(builder.build(), "ab");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn push_bytes(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<&mut Self, core::str::Utf8Error> {
+        // The bytes of a code point left incomplete at the end of the input
+        // could be turned into a longer replacement character.
+        let additional = bytes.len() + char::REPLACEMENT_CHARACTER.len_utf8();
+
+        if self.check_len(additional).is_err() {
+            panic::rope_too_long();
+        }
+
+        let mut rest = bytes;
+
+        let incomplete_len = core::mem::take(&mut self.incomplete_len);
+
+        if incomplete_len > 0 {
+            // Complete the buffered code point using the first few bytes.
+            let mut code_point = [0; 4];
+
+            let taken = (4 - incomplete_len).min(rest.len());
+
+            let len = incomplete_len + taken;
+
+            code_point[..incomplete_len]
+                .copy_from_slice(&self.incomplete[..incomplete_len]);
+
+            code_point[incomplete_len..len].copy_from_slice(&rest[..taken]);
+
+            let valid_up_to = match core::str::from_utf8(&code_point[..len]) {
+                Ok(_) => len,
+
+                Err(err) if err.valid_up_to() > 0 => err.valid_up_to(),
+
+                // Still incomplete, so all the bytes have been taken.
+                Err(err) if err.error_len().is_none() => {
+                    self.incomplete[..len].copy_from_slice(&code_point[..len]);
+                    self.incomplete_len = len;
+                    return Ok(self);
+                },
+
+                Err(err) => return Err(err),
+            };
+
+            // SAFETY: the first `valid_up_to` bytes are valid UTF-8.
+            self.append_str(unsafe {
+                core::str::from_utf8_unchecked(&code_point[..valid_up_to])
+            });
+
+            rest = &rest[valid_up_to - incomplete_len..];
+        }
+
+        // Validate everything except a code point that's split by the end of
+        // the input, which is buffered until the next call. The details of
+        // the error are only needed if the input is invalid.
+        let complete_len = complete_utf8_len(rest);
+
+        if let Ok(valid) = from_utf8(&rest[..complete_len]) {
+            let incomplete = &rest[complete_len..];
+
+            let is_prefix = core::str::from_utf8(incomplete)
+                .map_or_else(|err| err.error_len().is_none(), |_| true);
+
+            if is_prefix {
+                self.append_str(valid);
+                self.incomplete[..incomplete.len()]
+                    .copy_from_slice(incomplete);
+                self.incomplete_len = incomplete.len();
+                return Ok(self);
+            }
+        }
+
+        let err =
+            core::str::from_utf8(rest).expect_err("the input is invalid");
+
+        let (valid, invalid) = rest.split_at(err.valid_up_to());
+
+        // SAFETY: `valid_up_to()` bytes are valid UTF-8.
+        self.append_str(unsafe { core::str::from_utf8_unchecked(valid) });
+
+        if err.error_len().is_none() {
+            self.incomplete[..invalid.len()].copy_from_slice(invalid);
+            self.incomplete_len = invalid.len();
+            return Ok(self);
+        }
+
+        if incomplete_len == 0 {
+            return Err(err);
+        }
+
+        // Validate the input again starting from the bytes buffered by the
+        // previous call to get an error with the documented offset. This only
+        // happens once so it doesn't matter that it allocates.
+        let error_len = err.error_len().expect("the error isn't an EOF");
+
+        let end = bytes.len() - invalid.len() + error_len;
+
+        let mut input = alloc::vec::Vec::with_capacity(incomplete_len + end);
+        input.extend_from_slice(&self.incomplete[..incomplete_len]);
+        input.extend_from_slice(&bytes[..end]);
+
+        Err(core::str::from_utf8(&input).expect_err("the input is invalid"))
+    }
+
+    /// Returns the number of bytes the chunks are filled up to.
+    #[inline]
+    fn target_chunk_len(&self) -> usize {
+        self.chunk_len.unwrap_or(MAX_BYTES)
+    }
+
+    /// Like [`append()`](Self::append()), but returns an error instead of
+    /// panicking if the `Rope` would become longer than
+    /// [`Rope::MAX_BYTE_LEN`], in which case nothing is appended.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeBuilder;
+    /// #
+    /// let mut builder = RopeBuilder::new();
+    ///
+    /// builder.try_append("foo\n").unwrap().try_append("bar\n").unwrap();
+    ///
+    /// assert_eq!(builder.build(), "foo\nbar\n");
+    /// ```
+    #[inline]
+    pub fn try_append<T>(&mut self, text: T) -> Result<&mut Self, TooLongError>
+    where
+        T: AsRef<str>,
+    {
+        let text = text.as_ref();
+
+        self.check_len(text.len())?;
+
+        self.flush_incomplete();
+
+        self.append_str(text);
+
+        Ok(self)
+    }
+
+    /// Like [`append_slice()`](Self::append_slice()), but returns an error
+    /// instead of panicking if the `Rope` would become longer than
+    /// [`Rope::MAX_BYTE_LEN`], in which case nothing is appended.
+    #[inline]
+    pub fn try_append_slice(
+        &mut self,
+        slice: RopeSlice<'_, C, ARITY, MAX_BYTES>,
+    ) -> Result<&mut Self, TooLongError> {
+        self.check_len(slice.byte_len())?;
+
+        self.flush_incomplete();
+
+        // Splicing in a short slice isn't worth finishing the tree being
+        // built, and normalizing the line endings needs to look at every
+        // byte anyway.
+        if self.eol_counts.is_some() || slice.byte_len() <= MAX_BYTES {
+            for chunk in slice.chunks() {
+                self.append_str(chunk);
+            }
+            return Ok(self);
+        }
+
+        let mut tree_builder = core::mem::take(&mut self.tree_builder);
+
+        if self.buffer_len_left > 0 {
+            self.buffer.left_summary =
+                ChunkSummary::from(self.buffer_left_chunk());
+
+            tree_builder.append(core::mem::take(&mut self.buffer));
+
+            self.buffer_len_left = 0;
+        }
+
+        self.head.append(tree_builder.build());
+
+        self.head.append(Tree::from(slice.tree_slice));
+
+        self.rope_has_trailing_newline = slice.has_trailing_newline;
+
+        self.byte_len += slice.byte_len();
+
+        Ok(self)
     }
 }
@@ -0,0 +1,419 @@
+use super::iterators::Chunks;
+use super::metrics::ByteMetric;
+use super::utils::panic_messages as panic;
+use super::RopeSlice;
+
+/// The maximum number of chunks [`RopeCursor::seek_to()`] walks over before
+/// falling back to descending the tree from its root.
+const MAX_WALKED_CHUNKS: usize = 2;
+
+/// A cursor over the text of a [`Rope`](crate::Rope) or a [`RopeSlice`]
+/// which remembers the chunk it's in.
+///
+/// Looking up a position in a `Rope` descends its tree from the root down to
+/// the leaf containing it, which takes `O(log n)` time. A `RopeCursor` keeps
+/// the chunk containing its current offset around, together with the
+/// iterators it used to reach its neighbours, so that reading the text
+/// sequentially or seeking to a nearby offset only takes `O(1)` amortized
+/// time. Seeking farther than a couple of chunks away descends the tree
+/// again.
+///
+/// This struct is created by the `cursor` method on [`Rope`](
+/// crate::Rope::cursor()) and [`RopeSlice`](RopeSlice::cursor()). See their
+/// documentation for more.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::Rope;
+/// #
+/// let r = Rope::from("let foo = 42;");
+///
+/// let mut cursor = r.cursor(4);
+///
+/// let mut ident = String::new();
+///
+/// while let Some(ch) = cursor.peek_char() {
+///     if !ch.is_alphanumeric() {
+///         break;
+///     }
+///     ident.push(ch);
+///     cursor.next_char();
+/// }
+///
+/// assert_eq!(ident, "foo");
+/// assert_eq!(cursor.offset(), 7);
+///
+/// cursor.seek(3);
+/// assert_eq!(cursor.offset(), 10);
+/// assert_eq!(cursor.peek_char(), Some('4'));
+/// ```
+#[derive(Clone)]
+pub struct RopeCursor<'a> {
+    slice: RopeSlice<'a>,
+
+    /// The chunk containing the cursor, which is only empty if the text is.
+    chunk: &'a str,
+
+    /// The byte offset of the start of [`chunk`](Self::chunk) in the text.
+    chunk_start: usize,
+
+    /// The byte offset of the cursor in the text, which is always between
+    /// the start and the end of [`chunk`](Self::chunk) (both inclusive).
+    offset: usize,
+
+    /// The chunks after [`chunk`](Self::chunk), created the first time the
+    /// cursor moves past its end.
+    forward: Option<Chunks<'a>>,
+
+    /// The chunks before [`chunk`](Self::chunk), created the first time the
+    /// cursor moves before its start.
+    backward: Option<Chunks<'a>>,
+}
+
+impl<'a> RopeCursor<'a> {
+    #[inline]
+    fn chunk_end(&self) -> usize {
+        self.chunk_start + self.chunk.len()
+    }
+
+    /// Returns the text from the cursor to the end of the chunk containing
+    /// it, without moving the cursor.
+    ///
+    /// The returned string is only empty if the cursor is at the end of the
+    /// text, so it can be used to read the text in bulk by seeking past it
+    /// after processing it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo bar");
+    ///
+    /// let mut cursor = r.cursor(0);
+    ///
+    /// let mut text = String::new();
+    ///
+    /// loop {
+    ///     let chunk = cursor.chunk();
+    ///     if chunk.is_empty() {
+    ///         break;
+    ///     }
+    ///     text.push_str(chunk);
+    ///     cursor.seek(chunk.len() as isize);
+    /// }
+    ///
+    /// assert_eq!(text, "foo bar");
+    /// ```
+    #[inline]
+    pub fn chunk(&mut self) -> &'a str {
+        while self.offset == self.chunk_end() {
+            if !self.next_chunk() {
+                return "";
+            }
+        }
+
+        &self.chunk[self.offset - self.chunk_start..]
+    }
+
+    #[track_caller]
+    #[inline]
+    pub(super) fn new(slice: RopeSlice<'a>, byte_offset: usize) -> Self {
+        if byte_offset > slice.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, slice.byte_len());
+        }
+
+        let mut cursor = Self {
+            slice,
+            chunk: "",
+            chunk_start: 0,
+            offset: 0,
+            forward: None,
+            backward: None,
+        };
+        cursor.relocate(byte_offset);
+        cursor.set_offset(byte_offset);
+        cursor
+    }
+
+    /// Returns the [`char`] after the cursor and moves the cursor past it,
+    /// or returns `None` if the cursor is at the end of the text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("bär");
+    ///
+    /// let mut cursor = r.cursor(1);
+    ///
+    /// assert_eq!(cursor.next_char(), Some('ä'));
+    /// assert_eq!(cursor.next_char(), Some('r'));
+    /// assert_eq!(cursor.next_char(), None);
+    /// assert_eq!(cursor.offset(), 4);
+    /// ```
+    #[inline]
+    pub fn next_char(&mut self) -> Option<char> {
+        let ch = self.peek_char()?;
+        self.offset += ch.len_utf8();
+        Some(ch)
+    }
+
+    /// Moves to the chunk after the current one, returning `false` if there
+    /// isn't one. The offset of the cursor is left unchanged.
+    #[inline]
+    fn next_chunk(&mut self) -> bool {
+        let chunk_end = self.chunk_end();
+
+        let slice = self.slice;
+
+        let Some(chunk) = self
+            .forward
+            .get_or_insert_with(|| slice.byte_slice(chunk_end..).chunks())
+            .next()
+        else {
+            return false;
+        };
+
+        self.chunk = chunk;
+        self.chunk_start = chunk_end;
+        self.backward = None;
+        true
+    }
+
+    /// Returns the byte offset of the cursor.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the [`char`] after the cursor without moving it, or `None` if
+    /// the cursor is at the end of the text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo");
+    ///
+    /// let mut cursor = r.cursor(2);
+    ///
+    /// assert_eq!(cursor.peek_char(), Some('o'));
+    /// assert_eq!(cursor.offset(), 2);
+    /// ```
+    #[inline]
+    pub fn peek_char(&mut self) -> Option<char> {
+        self.chunk().chars().next()
+    }
+
+    /// Returns the [`char`] before the cursor and moves the cursor before it,
+    /// or returns `None` if the cursor is at the start of the text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("bär");
+    ///
+    /// let mut cursor = r.cursor(3);
+    ///
+    /// assert_eq!(cursor.prev_char(), Some('ä'));
+    /// assert_eq!(cursor.prev_char(), Some('b'));
+    /// assert_eq!(cursor.prev_char(), None);
+    /// assert_eq!(cursor.offset(), 0);
+    /// ```
+    #[inline]
+    pub fn prev_char(&mut self) -> Option<char> {
+        while self.offset == self.chunk_start {
+            if !self.prev_chunk() {
+                return None;
+            }
+        }
+
+        let ch = self.chunk[..self.offset - self.chunk_start]
+            .chars()
+            .next_back()?;
+
+        self.offset -= ch.len_utf8();
+        Some(ch)
+    }
+
+    /// Moves to the chunk before the current one, returning `false` if there
+    /// isn't one. The offset of the cursor is left unchanged.
+    #[inline]
+    fn prev_chunk(&mut self) -> bool {
+        let chunk_start = self.chunk_start;
+
+        let slice = self.slice;
+
+        let Some(chunk) = self
+            .backward
+            .get_or_insert_with(|| slice.byte_slice(..chunk_start).chunks())
+            .next_back()
+        else {
+            return false;
+        };
+
+        self.chunk = chunk;
+        self.chunk_start = chunk_start - chunk.len();
+        self.forward = None;
+        true
+    }
+
+    /// Moves to the chunk containing the given byte offset by descending the
+    /// tree from its root.
+    #[inline]
+    fn relocate(&mut self, byte_offset: usize) {
+        // Look for the leaf containing the byte at the offset, or the last
+        // byte if the offset is at the end of the text.
+        let measure = if byte_offset < self.slice.byte_len() {
+            byte_offset + 1
+        } else {
+            byte_offset
+        };
+
+        let (leaf, ByteMetric(leaf_start)) =
+            self.slice.tree_slice.leaf_at_measure(ByteMetric(measure));
+
+        let offset_in_leaf = byte_offset - leaf_start;
+
+        if offset_in_leaf < leaf.len_left() || leaf.len_right() == 0 {
+            self.chunk = leaf.left_chunk();
+            self.chunk_start = leaf_start;
+        } else {
+            self.chunk = leaf.right_chunk();
+            self.chunk_start = leaf_start + leaf.len_left();
+        }
+
+        self.forward = None;
+        self.backward = None;
+    }
+
+    /// Moves the cursor by the given number of bytes, forward if it's
+    /// positive and backward if it's negative.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the resulting offset is negative, out of bounds (i.e.
+    /// greater than the byte length of the text) or if it's not a char
+    /// boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo bar");
+    ///
+    /// let mut cursor = r.cursor(0);
+    ///
+    /// cursor.seek(4);
+    /// assert_eq!(cursor.peek_char(), Some('b'));
+    ///
+    /// cursor.seek(-2);
+    /// assert_eq!(cursor.peek_char(), Some('o'));
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn seek(&mut self, byte_delta: isize) {
+        let byte_offset = if byte_delta < 0 {
+            let Some(offset) =
+                self.offset.checked_sub(byte_delta.unsigned_abs())
+            else {
+                panic!(
+                    "cannot seek {byte_delta} bytes from byte offset {}",
+                    self.offset
+                );
+            };
+            offset
+        } else {
+            self.offset.saturating_add(byte_delta as usize)
+        };
+
+        self.seek_to(byte_offset);
+    }
+
+    /// Moves the cursor to the given byte offset.
+    ///
+    /// This takes `O(1)` amortized time if the offset is in the chunk
+    /// containing the cursor or in one of its neighbours, and `O(log n)`
+    /// time otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than the byte
+    /// length of the text) or if it's not a char boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo bar");
+    ///
+    /// let mut cursor = r.cursor(0);
+    ///
+    /// cursor.seek_to(4);
+    /// assert_eq!(cursor.offset(), 4);
+    /// assert_eq!(cursor.peek_char(), Some('b'));
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn seek_to(&mut self, byte_offset: usize) {
+        if byte_offset > self.slice.byte_len() {
+            panic::byte_offset_out_of_bounds(
+                byte_offset,
+                self.slice.byte_len(),
+            );
+        }
+
+        let mut walked = 0;
+
+        while byte_offset > self.chunk_end()
+            && walked < MAX_WALKED_CHUNKS
+            && self.next_chunk()
+        {
+            walked += 1;
+        }
+
+        while byte_offset < self.chunk_start
+            && walked < MAX_WALKED_CHUNKS
+            && self.prev_chunk()
+        {
+            walked += 1;
+        }
+
+        if byte_offset < self.chunk_start || byte_offset > self.chunk_end() {
+            self.relocate(byte_offset);
+        }
+
+        self.set_offset(byte_offset);
+    }
+
+    /// Sets the offset of the cursor, which must be within the current
+    /// chunk.
+    #[track_caller]
+    #[inline]
+    fn set_offset(&mut self, byte_offset: usize) {
+        let offset_in_chunk = byte_offset - self.chunk_start;
+
+        if !self.chunk.is_char_boundary(offset_in_chunk) {
+            panic::byte_offset_not_char_boundary(self.chunk, offset_in_chunk);
+        }
+
+        self.offset = byte_offset;
+    }
+}
+
+impl core::fmt::Debug for RopeCursor<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("RopeCursor")
+            .field("offset", &self.offset)
+            .finish_non_exhaustive()
+    }
+}
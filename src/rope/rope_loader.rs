@@ -0,0 +1,211 @@
+use alloc::vec::Vec;
+use std::io::{self, Read};
+
+use super::utils;
+use super::Rope;
+
+/// Loads a [`Rope`] from a reader in bounded steps, exposing the text read
+/// so far as a usable `Rope`.
+///
+/// Every call to [`step()`](Self::step()) reads a bounded number of bytes
+/// from the reader and appends them to the `Rope`, so that an editor can
+/// render the start of a huge file while the rest of it is still being
+/// read, and report the progress of the load with
+/// [`bytes_loaded()`](Self::bytes_loaded()) and
+/// [`lines_loaded()`](Self::lines_loaded()).
+///
+/// The text is expected to be UTF-8. A code point split between two steps is
+/// held back until the step reading the rest of it.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::RopeLoader;
+/// #
+/// let text = "Hello, 🌎!\n".repeat(1000);
+///
+/// let mut loader = RopeLoader::new(text.as_bytes());
+///
+/// loader.step(100).unwrap();
+/// assert!(!loader.is_done());
+/// assert!(loader.rope().byte_len() <= 100);
+///
+/// while !loader.step(4096).unwrap() {}
+///
+/// assert_eq!(loader.bytes_loaded(), text.len());
+/// assert_eq!(loader.into_rope(), text);
+/// ```
+pub struct RopeLoader<R> {
+    reader: R,
+
+    /// The text read so far.
+    rope: Rope,
+
+    /// The bytes read in the current step, starting with the bytes of the
+    /// incomplete code point held back by the previous one, if any.
+    buffer: Vec<u8>,
+
+    /// The number of bytes read from the reader.
+    bytes_loaded: usize,
+
+    is_done: bool,
+}
+
+impl<R: Read> RopeLoader<R> {
+    /// Returns the number of bytes read from the reader so far.
+    #[inline]
+    pub fn bytes_loaded(&self) -> usize {
+        self.bytes_loaded
+    }
+
+    /// Reads the rest of the text at once and returns the whole `Rope`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in the same cases as [`step()`](Self::step()).
+    #[inline]
+    pub fn finish(mut self) -> io::Result<Rope> {
+        self.step(usize::MAX)?;
+        Ok(self.rope)
+    }
+
+    /// Returns the `Rope` loaded so far, consuming the loader.
+    #[inline]
+    pub fn into_rope(self) -> Rope {
+        self.rope
+    }
+
+    /// Returns `true` if the reader has been read to the end.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.is_done
+    }
+
+    /// Returns the number of lines loaded so far, the last of which may
+    /// still be incomplete.
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn lines_loaded(&self) -> usize {
+        self.rope.line_len()
+    }
+
+    /// Creates a new `RopeLoader` reading from `reader`, which hasn't loaded
+    /// anything yet.
+    #[inline]
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            rope: Rope::new(),
+            buffer: Vec::new(),
+            bytes_loaded: 0,
+            is_done: false,
+        }
+    }
+
+    /// Returns the `Rope` loaded so far.
+    #[inline]
+    pub fn rope(&self) -> &Rope {
+        &self.rope
+    }
+
+    /// Reads at most `max_bytes` from the reader and appends them to the
+    /// `Rope`, returning whether the reader has been read to the end.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidData`] if the text isn't valid
+    /// UTF-8, or any error returned by the reader. The valid text read
+    /// before an invalid sequence is kept in the `Rope`, after which the
+    /// loader is done.
+    ///
+    /// [`InvalidData`]: std::io::ErrorKind::InvalidData
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeLoader;
+    /// #
+    /// let mut loader = RopeLoader::new("🌎🌍".as_bytes());
+    ///
+    /// // The first step only reads half of the globe, so it's held back.
+    /// assert!(!loader.step(2).unwrap());
+    /// assert_eq!(loader.rope(), "");
+    ///
+    /// assert!(!loader.step(4).unwrap());
+    /// assert_eq!(loader.rope(), "🌎");
+    ///
+    /// assert!(loader.step(4).unwrap());
+    /// assert_eq!(loader.rope(), "🌎🌍");
+    /// ```
+    #[inline]
+    pub fn step(&mut self, max_bytes: usize) -> io::Result<bool> {
+        if self.is_done {
+            return Ok(true);
+        }
+
+        let held_back = self.buffer.len();
+
+        let max_bytes = max_bytes.max(1);
+
+        (&mut self.reader)
+            .take(max_bytes as u64)
+            .read_to_end(&mut self.buffer)?;
+
+        let read = self.buffer.len() - held_back;
+
+        self.bytes_loaded += read;
+
+        self.is_done = read < max_bytes;
+
+        let valid_up_to = match utils::from_utf8(&self.buffer) {
+            Ok(_) => self.buffer.len(),
+
+            Err(err) if err.error_len().is_none() && !self.is_done => {
+                err.valid_up_to()
+            },
+
+            Err(err) => {
+                let offset = self.rope.byte_len() + err.valid_up_to();
+
+                // SAFETY: the first `valid_up_to` bytes are valid UTF-8.
+                self.rope.push_str(unsafe {
+                    core::str::from_utf8_unchecked(
+                        &self.buffer[..err.valid_up_to()],
+                    )
+                });
+
+                self.buffer.clear();
+
+                self.is_done = true;
+
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    alloc::format!(
+                        "stream did not contain valid UTF-8 at byte offset \
+                         {offset}",
+                    ),
+                ));
+            },
+        };
+
+        // SAFETY: the first `valid_up_to` bytes are valid UTF-8.
+        self.rope.push_str(unsafe {
+            core::str::from_utf8_unchecked(&self.buffer[..valid_up_to])
+        });
+
+        self.buffer.drain(..valid_up_to);
+
+        Ok(self.is_done)
+    }
+}
+
+impl<R> core::fmt::Debug for RopeLoader<R> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("RopeLoader")
+            .field("bytes_loaded", &self.bytes_loaded)
+            .field("is_done", &self.is_done)
+            .finish_non_exhaustive()
+    }
+}
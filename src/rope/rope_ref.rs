@@ -0,0 +1,586 @@
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::ops::RangeBounds;
+
+use super::metrics::ChunkSummary;
+#[cfg(any(feature = "line-metric", feature = "utf16-metric"))]
+use super::metrics::{convert, count};
+use super::rope::RopeChunk;
+#[cfg(feature = "line-metric")]
+use super::utils::{ends_with_line_break, is_line_break};
+use super::utils::{panic_messages as panic, split_adjusted};
+use super::Rope;
+use crate::range_bounds_to_start_end;
+
+/// A read-only view over a borrowed string slice which supports the same
+/// logarithmic-time queries as a [`Rope`](crate::Rope), without copying the
+/// text.
+///
+/// When it's created the text is split into blocks the size of a `Rope`'s
+/// chunks, and the summaries of the text before each block are computed once
+/// and stored in a flat array. Converting between byte and line (or UTF-16)
+/// offsets then binary searches that array and only scans a single block,
+/// which makes `RopeRef` a good fit for indexing, searching and measuring
+/// text that's owned by someone else.
+///
+/// Since the text is contiguous in memory, all the slicing methods return
+/// plain `&str`s borrowed from the original text.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::RopeRef;
+/// #
+/// let text = String::from("foo\nbär\r\nbaz");
+///
+/// let r = RopeRef::new(&text);
+///
+/// assert_eq!(r.line_len(), 3);
+/// assert_eq!(r.line(1), "bär");
+/// assert_eq!(r.line_of_byte(text.find('z').unwrap()), 2);
+/// assert_eq!(r.byte_slice(4..), "bär\r\nbaz");
+/// ```
+#[derive(Clone)]
+pub struct RopeRef<'a> {
+    text: &'a str,
+
+    /// The summaries of the text before the start of every block, followed
+    /// by the summary of the whole text.
+    #[cfg_attr(
+        not(any(feature = "line-metric", feature = "utf16-metric")),
+        allow(dead_code)
+    )]
+    prefixes: Cow<'a, [ChunkSummary]>,
+}
+
+impl<'a> RopeRef<'a> {
+    /// Returns the underlying string slice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// let r = RopeRef::new("foo");
+    ///
+    /// assert_eq!(r.as_str(), "foo");
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the index of the block containing the first position where
+    /// `is_after` returns `true` for the summary of the text before it.
+    #[cfg_attr(
+        not(any(feature = "line-metric", feature = "utf16-metric")),
+        allow(dead_code)
+    )]
+    #[inline]
+    fn block_before<F>(&self, is_after: F) -> usize
+    where
+        F: FnMut(&ChunkSummary) -> bool,
+    {
+        // The first summary is always empty, so this is at least 1.
+        self.prefixes.partition_point(is_after) - 1
+    }
+
+    /// Returns the length of the text in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// let r = RopeRef::new("ƒoo");
+    ///
+    /// assert_eq!(r.byte_len(), 4);
+    /// ```
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Returns the byte offset of the start of the given line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line offset is out of bounds (i.e. greater than
+    /// [`line_len()`](Self::line_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// let r = RopeRef::new("ƒoo\nbär\r\nbaz");
+    ///
+    /// assert_eq!(r.byte_of_line(0), 0);
+    /// assert_eq!(r.byte_of_line(1), "ƒoo\n".len());
+    /// assert_eq!(r.byte_of_line(2), "ƒoo\nbär\r\n".len());
+    /// assert_eq!(r.byte_of_line(r.line_len()), r.byte_len());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn byte_of_line(&self, line_offset: usize) -> usize {
+        if line_offset > self.line_len() {
+            panic::line_offset_out_of_bounds(line_offset, self.line_len());
+        }
+
+        if line_offset == 0 {
+            return 0;
+        }
+
+        if line_offset > self.summary().line_breaks() {
+            return self.byte_len();
+        }
+
+        let block =
+            self.block_before(|prefix| prefix.line_breaks() < line_offset);
+
+        let prefix = &self.prefixes[block];
+
+        prefix.bytes()
+            + convert::byte_of_line(
+                &self.text[prefix.bytes()..],
+                line_offset - prefix.line_breaks(),
+            )
+    }
+
+    /// Returns the byte offset corresponding to the given UTF-16 code unit
+    /// offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the UTF-16 code unit offset is out of bounds (i.e. greater
+    /// than [`utf16_len()`](Self::utf16_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// // The "𐐀" character is encoded using two code units in UTF-16 and
+    /// // four bytes in UTF-8.
+    /// let r = RopeRef::new("a𐐀b");
+    /// assert_eq!(r.byte_of_utf16_code_unit(3), 5);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "utf16-metric")))]
+    #[cfg(feature = "utf16-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn byte_of_utf16_code_unit(&self, utf16_offset: usize) -> usize {
+        if utf16_offset > self.utf16_len() {
+            panic::utf16_offset_out_of_bounds(utf16_offset, self.utf16_len())
+        }
+
+        if self.summary().is_ascii() {
+            return utf16_offset;
+        }
+
+        let block = self
+            .block_before(|prefix| prefix.utf16_code_units() <= utf16_offset);
+
+        let prefix = &self.prefixes[block];
+
+        if self.is_ascii_block(block) {
+            return prefix.bytes() + utf16_offset - prefix.utf16_code_units();
+        }
+
+        prefix.bytes()
+            + convert::byte_of_utf16_code_unit(
+                &self.text[prefix.bytes()..],
+                utf16_offset - prefix.utf16_code_units(),
+            )
+    }
+
+    /// Returns the text in the specified byte range, where the start and end
+    /// of the range are interpreted as offsets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start or the end of the byte range don't lie on a code
+    /// point boundary, if the start is greater than the end or if the end is
+    /// out of bounds (i.e. greater than [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// let r = RopeRef::new("🗻∈🌏");
+    ///
+    /// assert_eq!(r.byte_slice(..4), "🗻");
+    /// assert_eq!(r.byte_slice(4..7), "∈");
+    /// assert_eq!(r.byte_slice(7..), "🌏");
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn byte_slice<R>(&self, byte_range: R) -> &'a str
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(byte_range, 0, self.byte_len());
+
+        if start > end {
+            panic::byte_start_after_end(start, end);
+        }
+
+        if end > self.byte_len() {
+            panic::byte_offset_out_of_bounds(end, self.byte_len());
+        }
+
+        for offset in [start, end] {
+            if !self.text.is_char_boundary(offset) {
+                panic::byte_offset_not_char_boundary(self.text, offset);
+            }
+        }
+
+        &self.text[start..end]
+    }
+
+    /// Creates a `RopeRef` from its parts, which must have been returned by
+    /// [`into_parts()`](Self::into_parts()).
+    #[inline]
+    pub(super) fn from_parts(
+        text: &'a str,
+        prefixes: &'a [ChunkSummary],
+    ) -> Self {
+        Self { text, prefixes: Cow::Borrowed(prefixes) }
+    }
+
+    /// Returns the text and the summaries of its blocks.
+    #[inline]
+    pub(super) fn into_parts(self) -> (&'a str, Cow<'a, [ChunkSummary]>) {
+        (self.text, self.prefixes)
+    }
+
+    /// Returns `true` if the block at the given index only contains ASCII
+    /// text.
+    #[cfg(feature = "utf16-metric")]
+    #[inline]
+    fn is_ascii_block(&self, block: usize) -> bool {
+        self.prefixes
+            .get(block + 1)
+            .map_or(true, |&next| (next - self.prefixes[block]).is_ascii())
+    }
+
+    /// Returns `true` if the text is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// assert!(RopeRef::new("").is_empty());
+    /// assert!(!RopeRef::new("a").is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.byte_len() == 0
+    }
+
+    /// Returns the line at `line_index`, without its line terminator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line index is out of bounds (i.e. greater than or equal
+    /// to [`line_len()`](Self::line_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// let r = RopeRef::new("foo\nbar\r\nbaz");
+    ///
+    /// assert_eq!(r.line(0), "foo");
+    /// assert_eq!(r.line(1), "bar");
+    /// assert_eq!(r.line(2), "baz");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn line(&self, line_index: usize) -> &'a str {
+        if line_index >= self.line_len() {
+            panic::line_index_out_of_bounds(line_index, self.line_len());
+        }
+
+        let line = self.line_slice(line_index..line_index + 1);
+
+        match line.chars().next_back() {
+            Some('\n') => {
+                let line = &line[..line.len() - 1];
+                line.strip_suffix('\r').unwrap_or(line)
+            },
+            Some(ch) if is_line_break(ch) => {
+                &line[..line.len() - ch.len_utf8()]
+            },
+            _ => line,
+        }
+    }
+
+    /// Returns the number of lines in the text.
+    ///
+    /// The final line break is optional and doesn't count as a separate empty
+    /// line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// assert_eq!(RopeRef::new("").line_len(), 0);
+    /// assert_eq!(RopeRef::new("a").line_len(), 1);
+    /// assert_eq!(RopeRef::new("a\n").line_len(), 1);
+    /// assert_eq!(RopeRef::new("a\nb").line_len(), 2);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn line_len(&self) -> usize {
+        self.summary().line_breaks() + 1
+            - (ends_with_line_break(self.text) as usize)
+            - (self.is_empty() as usize)
+    }
+
+    /// Returns the line offset of the given byte.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// let r = RopeRef::new("foo\nbar\r\nbaz");
+    ///
+    /// assert_eq!(r.line_of_byte(0), 0);
+    /// assert_eq!(r.line_of_byte(3), 0);
+    /// assert_eq!(r.line_of_byte(4), 1);
+    /// assert_eq!(r.line_of_byte(8), 1); // between the '\r' and the '\n'
+    /// assert_eq!(r.line_of_byte(r.byte_len()), 2);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn line_of_byte(&self, mut byte_offset: usize) -> usize {
+        if byte_offset > self.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
+        }
+
+        // Line breaks are ASCII, so they can't be part of the code point
+        // containing the byte.
+        while !self.text.is_char_boundary(byte_offset) {
+            byte_offset -= 1;
+        }
+
+        let block = self.block_before(|prefix| prefix.bytes() <= byte_offset);
+
+        let prefix = &self.prefixes[block];
+
+        prefix.line_breaks()
+            + count::line_breaks(&self.text[prefix.bytes()..byte_offset])
+    }
+
+    /// Returns the text in the specified line range, where the start and end
+    /// of the range are interpreted as offsets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start is greater than the end or if the end is out of
+    /// bounds (i.e. greater than [`line_len()`](Self::line_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// let r = RopeRef::new("foo\nbar\r\nbaz\nfoobar\n");
+    ///
+    /// assert_eq!(r.line_slice(..1), "foo\n");
+    /// assert_eq!(r.line_slice(1..3), "bar\r\nbaz\n");
+    /// assert_eq!(r.line_slice(3..4), "foobar\n");
+    /// assert_eq!(r.line_slice(4..), "");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn line_slice<R>(&self, line_range: R) -> &'a str
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(line_range, 0, self.line_len());
+
+        if start > end {
+            panic::line_start_after_end(start, end);
+        }
+
+        if end > self.line_len() {
+            panic::line_offset_out_of_bounds(end, self.line_len());
+        }
+
+        &self.text[self.byte_of_line(start)..self.byte_of_line(end)]
+    }
+
+    /// Creates a new `RopeRef` over the given text, computing the summaries
+    /// of its blocks.
+    ///
+    /// This reads the whole text once, and allocates one summary for every
+    /// few kilobytes of text.
+    #[inline]
+    pub fn new(text: &'a str) -> Self {
+        let mut prefixes =
+            Vec::with_capacity(text.len() / RopeChunk::max_bytes() + 2);
+
+        let mut summary = ChunkSummary::new();
+
+        prefixes.push(summary);
+
+        let mut rest = text;
+
+        while !rest.is_empty() {
+            let (block, after) =
+                split_adjusted::<false>(rest, RopeChunk::max_bytes());
+            summary += ChunkSummary::from(block);
+            prefixes.push(summary);
+            rest = after;
+        }
+
+        Self { text, prefixes: Cow::Owned(prefixes) }
+    }
+
+    /// Returns the summary of the whole text.
+    #[cfg_attr(
+        not(any(feature = "line-metric", feature = "utf16-metric")),
+        allow(dead_code)
+    )]
+    #[inline]
+    fn summary(&self) -> &ChunkSummary {
+        self.prefixes.last().unwrap()
+    }
+
+    /// Returns the UTF-16 code unit offset corresponding to the given byte
+    /// offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// // The "𐐀" character is encoded using two code units in UTF-16 and
+    /// // four bytes in UTF-8.
+    /// let r = RopeRef::new("a𐐀b");
+    /// assert_eq!(r.utf16_code_unit_of_byte(5), 3);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "utf16-metric")))]
+    #[cfg(feature = "utf16-metric")]
+    #[track_caller]
+    #[inline]
+    pub fn utf16_code_unit_of_byte(&self, mut byte_offset: usize) -> usize {
+        if byte_offset > self.byte_len() {
+            panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
+        }
+
+        if self.summary().is_ascii() {
+            return byte_offset;
+        }
+
+        while !self.text.is_char_boundary(byte_offset) {
+            byte_offset -= 1;
+        }
+
+        let block = self.block_before(|prefix| prefix.bytes() <= byte_offset);
+
+        let prefix = &self.prefixes[block];
+
+        if self.is_ascii_block(block) {
+            return prefix.utf16_code_units() + byte_offset - prefix.bytes();
+        }
+
+        prefix.utf16_code_units()
+            + count::utf16_code_units(&self.text[prefix.bytes()..byte_offset])
+    }
+
+    /// Returns the length of the text in UTF-16 code units.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::RopeRef;
+    /// #
+    /// let r = RopeRef::new("🗻∈🌏");
+    ///
+    /// assert_eq!(r.utf16_len(), 5);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "utf16-metric")))]
+    #[cfg(feature = "utf16-metric")]
+    #[inline]
+    pub fn utf16_len(&self) -> usize {
+        self.summary().utf16_code_units()
+    }
+}
+
+impl<'a> From<&'a str> for RopeRef<'a> {
+    #[inline]
+    fn from(text: &'a str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<RopeRef<'_>> for Rope {
+    #[inline]
+    fn from(rope_ref: RopeRef<'_>) -> Self {
+        Rope::from(rope_ref.text)
+    }
+}
+
+impl core::fmt::Debug for RopeRef<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_tuple("RopeRef").field(&self.text).finish()
+    }
+}
+
+impl core::fmt::Display for RopeRef<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.text)
+    }
+}
+
+impl PartialEq<str> for RopeRef<'_> {
+    #[inline]
+    fn eq(&self, rhs: &str) -> bool {
+        self.text == rhs
+    }
+}
+
+impl PartialEq<&str> for RopeRef<'_> {
+    #[inline]
+    fn eq(&self, rhs: &&str) -> bool {
+        self.text == *rhs
+    }
+}
+
+impl PartialEq<RopeRef<'_>> for Rope {
+    #[inline]
+    fn eq(&self, rhs: &RopeRef<'_>) -> bool {
+        *self == rhs.text
+    }
+}
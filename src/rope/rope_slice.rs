@@ -1,22 +1,38 @@
 use alloc::string::String;
 use core::ops::RangeBounds;
 
-use super::iterators::{Bytes, Chars, Chunks, Lines, RawLines};
-use super::metrics::{ByteMetric, RawLineMetric};
-use super::rope::RopeChunk;
+use super::content_hash::hash_text_chunks;
+use super::gap_buffer::GapBuffer;
+use super::iterators::{Bytes, Chars, Chunks, EncodeUtf16, RollingHash};
+#[cfg(feature = "line-metric")]
+use super::iterators::{Lines, RawLines};
+use super::metrics::ByteMetric;
+#[cfg(feature = "line-metric")]
+use super::metrics::RawLineMetric;
+use super::rope::{DEFAULT_ARITY, DEFAULT_CHUNK_MAX_BYTES};
 use super::utils::{panic_messages as panic, *};
-use super::Rope;
+#[cfg(feature = "line-metric")]
+use super::TrailingLineBreak;
+use super::{Rope, RopeCursor};
 use crate::range_bounds_to_start_end;
-use crate::tree::TreeSlice;
+use crate::tree::{AtomicCount, RefCount, TreeSlice};
 
-/// An immutable slice of a [`Rope`](crate::Rope).
+/// An immutable slice of a [`Rope`](crate::Rope), with the same reference
+/// count type.
 #[derive(Copy, Clone)]
-pub struct RopeSlice<'a> {
-    pub(super) tree_slice: TreeSlice<'a, { Rope::arity() }, RopeChunk>,
+pub struct RopeSlice<
+    'a,
+    C: RefCount = AtomicCount,
+    const ARITY: usize = DEFAULT_ARITY,
+    const MAX_BYTES: usize = DEFAULT_CHUNK_MAX_BYTES,
+> {
+    pub(super) tree_slice: TreeSlice<'a, ARITY, GapBuffer<MAX_BYTES>, C>,
     pub(super) has_trailing_newline: bool,
 }
 
-impl<'a> RopeSlice<'a> {
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    RopeSlice<'a, C, ARITY, MAX_BYTES>
+{
     #[doc(hidden)]
     pub fn assert_invariants(&self) {
         self.tree_slice.assert_invariants();
@@ -104,6 +120,8 @@ impl<'a> RopeSlice<'a> {
     /// assert_eq!(s.byte_of_line(1), "bär\r\n".len());
     /// assert_eq!(s.byte_of_line(s.line_len()), s.byte_len());
     /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
     #[track_caller]
     #[inline]
     pub fn byte_of_line(&self, line_offset: usize) -> usize {
@@ -150,6 +168,10 @@ impl<'a> RopeSlice<'a> {
             panic::utf16_offset_out_of_bounds(utf16_offset, self.utf16_len())
         }
 
+        if self.is_ascii() {
+            return utf16_offset;
+        }
+
         let ByteMetric(byte_offset) = self
             .tree_slice
             .convert_measure(super::metrics::Utf16Metric(utf16_offset));
@@ -179,7 +201,10 @@ impl<'a> RopeSlice<'a> {
     /// ```
     #[track_caller]
     #[inline]
-    pub fn byte_slice<R>(self, byte_range: R) -> RopeSlice<'a>
+    pub fn byte_slice<R>(
+        self,
+        byte_range: R,
+    ) -> RopeSlice<'a, C, ARITY, MAX_BYTES>
     where
         R: RangeBounds<usize>,
     {
@@ -216,7 +241,7 @@ impl<'a> RopeSlice<'a> {
     /// assert_eq!(None, bytes.next());
     /// ```
     #[inline]
-    pub fn bytes(&self) -> Bytes<'a> {
+    pub fn bytes(&self) -> Bytes<'a, C, ARITY, MAX_BYTES> {
         Bytes::from(self)
     }
 
@@ -238,16 +263,89 @@ impl<'a> RopeSlice<'a> {
     /// assert_eq!(None, chars.next());
     /// ```
     #[inline]
-    pub fn chars(&self) -> Chars<'a> {
+    pub fn chars(&self) -> Chars<'a, C, ARITY, MAX_BYTES> {
         Chars::from(self)
     }
 
     /// Returns an iterator over the chunks of this `RopeSlice`.
     #[inline]
-    pub fn chunks(&self) -> Chunks<'a> {
+    pub fn chunks(&self) -> Chunks<'a, C, ARITY, MAX_BYTES> {
         Chunks::from(self)
     }
 
+    /// Returns a 64-bit hash of the text of the `RopeSlice`, which is the
+    /// same as the one returned by [`Rope::content_hash()`] for a `Rope`
+    /// with the same contents.
+    ///
+    /// Unlike the one of a `Rope` this hash is not cached, so every call
+    /// takes a pass over the chunks of the slice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo bar foo");
+    ///
+    /// assert_eq!(
+    ///     r.byte_slice(..3).content_hash(),
+    ///     r.byte_slice(8..).content_hash()
+    /// );
+    /// assert_eq!(
+    ///     r.byte_slice(4..7).content_hash(),
+    ///     Rope::from("bar").content_hash()
+    /// );
+    /// ```
+    #[inline]
+    pub fn content_hash(&self) -> u64 {
+        hash_text_chunks(self.chunks())
+    }
+
+    /// Returns the digest of the text of the `RopeSlice` computed with the
+    /// given [`Digest`](digest::Digest), feeding it one chunk at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// use sha2::{Digest, Sha256};
+    ///
+    /// let r = Rope::from("foo bar baz");
+    ///
+    /// assert_eq!(r.byte_slice(4..7).digest::<Sha256>(), Sha256::digest("bar"));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+    #[cfg(feature = "digest")]
+    #[inline]
+    pub fn digest<D: digest::Digest>(&self) -> digest::Output<D> {
+        let mut hasher = D::new();
+
+        for chunk in self.chunks() {
+            hasher.update(chunk);
+        }
+
+        hasher.finalize()
+    }
+
+    /// Returns an iterator over the UTF-16 code units of this `RopeSlice`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("a🌎b");
+    /// let s = r.byte_slice(1..5);
+    ///
+    /// let code_units = s.encode_utf16().collect::<Vec<_>>();
+    ///
+    /// assert_eq!(code_units, [0xd83c, 0xdf0e]);
+    /// ```
+    #[inline]
+    pub fn encode_utf16(&self) -> EncodeUtf16<'a, C, ARITY, MAX_BYTES> {
+        EncodeUtf16::from(self)
+    }
+
     /// Returns an iterator over the extended grapheme clusters of this
     /// `RopeSlice`.
     ///
@@ -269,10 +367,32 @@ impl<'a> RopeSlice<'a> {
     #[cfg_attr(docsrs, doc(cfg(feature = "graphemes")))]
     #[cfg(feature = "graphemes")]
     #[inline]
-    pub fn graphemes(&self) -> crate::iter::Graphemes<'a> {
+    pub fn graphemes(
+        &self,
+    ) -> crate::iter::Graphemes<'a, C, ARITY, MAX_BYTES> {
         crate::iter::Graphemes::from(self)
     }
 
+    /// Returns `true` if all the text in the `RopeSlice` is ASCII.
+    ///
+    /// This runs in `O(1)` time, and a few methods use it to skip work which
+    /// is only needed for non-ASCII text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("Hello, 🌎!");
+    ///
+    /// assert!(r.byte_slice(..7).is_ascii());
+    /// assert!(!r.byte_slice(7..).is_ascii());
+    /// ```
+    #[inline]
+    pub fn is_ascii(&self) -> bool {
+        self.tree_slice.summary().is_ascii()
+    }
+
     /// Returns `true` if the given byte offset lies on a [`char`] boundary.
     ///
     /// # Panics
@@ -300,6 +420,10 @@ impl<'a> RopeSlice<'a> {
             panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
         }
 
+        if self.is_ascii() {
+            return true;
+        }
+
         let (chunk, ByteMetric(chunk_byte_offset)) =
             self.tree_slice.leaf_at_measure(ByteMetric(byte_offset));
 
@@ -353,6 +477,20 @@ impl<'a> RopeSlice<'a> {
             panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
         }
 
+        if byte_offset == 0 || byte_offset == self.byte_len() {
+            return true;
+        }
+
+        // The only ASCII grapheme cluster made of more than one character
+        // is a CRLF sequence, so there's no need to segment the text if the
+        // offset is between two ASCII characters.
+        let prev = self.byte(byte_offset - 1);
+        let next = self.byte(byte_offset);
+
+        if prev.is_ascii() && next.is_ascii() {
+            return prev != b'\r' || next != b'\n';
+        }
+
         is_grapheme_boundary(self.chunks(), self.byte_len(), byte_offset)
     }
 
@@ -378,9 +516,14 @@ impl<'a> RopeSlice<'a> {
     /// assert_eq!(s.line(0), "foo");
     /// assert_eq!(s.line(1), "bar");
     /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
     #[track_caller]
     #[inline]
-    pub fn line(self, line_index: usize) -> RopeSlice<'a> {
+    pub fn line(
+        self,
+        line_index: usize,
+    ) -> RopeSlice<'a, C, ARITY, MAX_BYTES> {
         if line_index >= self.line_len() {
             panic::line_offset_out_of_bounds(line_index, self.line_len());
         }
@@ -425,6 +568,8 @@ impl<'a> RopeSlice<'a> {
     /// let s = r.byte_slice(..0);
     /// assert_eq!(s.line_len(), 0);
     /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
     #[inline]
     pub fn line_len(&self) -> usize {
         self.tree_slice.summary().line_breaks() + 1
@@ -432,6 +577,31 @@ impl<'a> RopeSlice<'a> {
             - (self.is_empty() as usize)
     }
 
+    /// Returns the number of lines in the `RopeSlice`, where the final line break
+    /// is handled according to `trailing`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{Rope, TrailingLineBreak};
+    /// #
+    /// let r = Rope::from("foo\nbar\n");
+    ///
+    /// assert_eq!(r.line_len_with(TrailingLineBreak::EndsLine), 2);
+    /// assert_eq!(r.line_len_with(TrailingLineBreak::StartsLine), 3);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn line_len_with(&self, trailing: TrailingLineBreak) -> usize {
+        match trailing {
+            TrailingLineBreak::EndsLine => self.line_len(),
+            TrailingLineBreak::StartsLine => {
+                self.tree_slice.summary().line_breaks() + 1
+            },
+        }
+    }
+
     /// Returns the line offset of the given byte.
     ///
     /// # Panics
@@ -455,6 +625,8 @@ impl<'a> RopeSlice<'a> {
     /// assert_eq!(s.line_of_byte(4), 0); // between the '\r' and the '\n'
     /// assert_eq!(s.line_of_byte(s.byte_len()), 1);
     /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
     #[track_caller]
     #[inline]
     pub fn line_of_byte(&self, byte_offset: usize) -> usize {
@@ -489,9 +661,14 @@ impl<'a> RopeSlice<'a> {
     /// assert_eq!(s.line_slice(3..4), "foob");
     /// assert_eq!(s.line_slice(4..), "");
     /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
     #[track_caller]
     #[inline]
-    pub fn line_slice<R>(self, line_range: R) -> RopeSlice<'a>
+    pub fn line_slice<R>(
+        self,
+        line_range: R,
+    ) -> RopeSlice<'a, C, ARITY, MAX_BYTES>
     where
         R: RangeBounds<usize>,
     {
@@ -532,11 +709,49 @@ impl<'a> RopeSlice<'a> {
     /// assert_eq!("baz", lines.next().unwrap());
     /// assert_eq!(None, lines.next());
     /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
     #[inline]
-    pub fn lines(&self) -> Lines<'a> {
+    pub fn lines(&self) -> Lines<'a, C, ARITY, MAX_BYTES> {
         Lines::from(self)
     }
 
+    /// Returns an iterator over the lines of the `RopeSlice`, not including the
+    /// line terminators, where the final line break is handled according to
+    /// `trailing`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::{Rope, TrailingLineBreak};
+    /// #
+    /// let r = Rope::from("foo\nbar\n");
+    ///
+    /// let mut lines = r.lines_with(TrailingLineBreak::StartsLine);
+    ///
+    /// assert_eq!("foo", lines.next().unwrap());
+    /// assert_eq!("bar", lines.next().unwrap());
+    /// assert_eq!("", lines.next().unwrap());
+    /// assert_eq!(None, lines.next());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
+    #[inline]
+    pub fn lines_with(
+        &self,
+        trailing: TrailingLineBreak,
+    ) -> Lines<'a, C, ARITY, MAX_BYTES> {
+        let lines = self.lines();
+
+        if trailing == TrailingLineBreak::StartsLine
+            && (self.is_empty() || self.has_trailing_newline)
+        {
+            lines.with_trailing_line(self.byte_slice(self.byte_len()..))
+        } else {
+            lines
+        }
+    }
+
     /// Returns an iterator over the lines of this `RopeSlice`, including the
     /// line terminators.
     ///
@@ -570,29 +785,285 @@ impl<'a> RopeSlice<'a> {
     /// assert_eq!("baz\n", raw_lines.next().unwrap());
     /// assert_eq!(None, raw_lines.next());
     /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "line-metric")))]
+    #[cfg(feature = "line-metric")]
     #[inline]
-    pub fn raw_lines(&self) -> RawLines<'a> {
+    pub fn raw_lines(&self) -> RawLines<'a, C, ARITY, MAX_BYTES> {
         RawLines::from(self)
     }
 
+    /// Returns an iterator over the hashes of every window of `window_size`
+    /// consecutive bytes of the `RopeSlice`, from left to right.
+    ///
+    /// The windows can span multiple chunks, and the hash of a window only
+    /// depends on its bytes, so equal windows always have the same hash no
+    /// matter where they are. Each hash is computed from the previous one in
+    /// constant time, so iterating over all of them takes `O(n)` time. The
+    /// iterator is empty if the slice is shorter than `window_size`.
+    ///
+    /// The hash is a polynomial rolling hash, which isn't resistant to
+    /// collisions crafted on purpose and isn't guaranteed to be stable across
+    /// versions of crop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_size` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("abcabc");
+    /// let s = r.byte_slice(..);
+    ///
+    /// let hashes = s.rolling_hash(3).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(hashes.len(), 4);
+    /// assert_eq!(hashes[0], hashes[3]);
+    /// assert_ne!(hashes[0], hashes[1]);
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn rolling_hash(
+        &self,
+        window_size: usize,
+    ) -> RollingHash<'a, C, ARITY, MAX_BYTES> {
+        RollingHash::new(self.bytes(), window_size)
+    }
+
+    /// Returns a sub-slice of this `RopeSlice` with the given prefix removed,
+    /// or `None` if the slice doesn't start with it.
+    ///
+    /// The prefix is compared chunk by chunk, so the slice's text is never
+    /// copied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("// foo");
+    /// let s = r.byte_slice(..);
+    ///
+    /// assert_eq!(s.strip_prefix("// ").unwrap(), "foo");
+    /// assert_eq!(s.strip_prefix("/*"), None);
+    /// ```
+    #[inline]
+    pub fn strip_prefix(
+        self,
+        prefix: &str,
+    ) -> Option<RopeSlice<'a, C, ARITY, MAX_BYTES>> {
+        if prefix.len() > self.byte_len() {
+            return None;
+        }
+
+        let mut prefix_rest = prefix.as_bytes();
+
+        for chunk in self.chunks() {
+            if prefix_rest.is_empty() {
+                break;
+            }
+
+            let len = chunk.len().min(prefix_rest.len());
+
+            if chunk.as_bytes()[..len] != prefix_rest[..len] {
+                return None;
+            }
+
+            prefix_rest = &prefix_rest[len..];
+        }
+
+        // The prefix is valid UTF-8 and it matched, so its length is on a
+        // char boundary.
+        Some(self.byte_slice(prefix.len()..))
+    }
+
+    /// Returns a sub-slice of this `RopeSlice` with the given suffix removed,
+    /// or `None` if the slice doesn't end with it.
+    ///
+    /// The suffix is compared chunk by chunk, so the slice's text is never
+    /// copied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo */");
+    /// let s = r.byte_slice(..);
+    ///
+    /// assert_eq!(s.strip_suffix(" */").unwrap(), "foo");
+    /// assert_eq!(s.strip_suffix("//"), None);
+    /// ```
+    #[inline]
+    pub fn strip_suffix(
+        self,
+        suffix: &str,
+    ) -> Option<RopeSlice<'a, C, ARITY, MAX_BYTES>> {
+        if suffix.len() > self.byte_len() {
+            return None;
+        }
+
+        let mut suffix_rest = suffix.as_bytes();
+
+        for chunk in self.chunks().rev() {
+            if suffix_rest.is_empty() {
+                break;
+            }
+
+            let len = chunk.len().min(suffix_rest.len());
+
+            if chunk.as_bytes()[chunk.len() - len..]
+                != suffix_rest[suffix_rest.len() - len..]
+            {
+                return None;
+            }
+
+            suffix_rest = &suffix_rest[..suffix_rest.len() - len];
+        }
+
+        // The suffix is valid UTF-8 and it matched, so its start is on a char
+        // boundary.
+        Some(self.byte_slice(..self.byte_len() - suffix.len()))
+    }
+
+    /// Returns a sub-slice of this `RopeSlice` with leading and trailing
+    /// whitespace removed, where whitespace is defined by
+    /// [`char::is_whitespace()`].
+    ///
+    /// Only the chunks containing the removed whitespace are scanned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("\n  foo bar \r\n");
+    ///
+    /// assert_eq!(r.byte_slice(..).trim(), "foo bar");
+    /// ```
+    #[inline]
+    pub fn trim(self) -> RopeSlice<'a, C, ARITY, MAX_BYTES> {
+        self.trim_start().trim_end()
+    }
+
+    /// Returns a sub-slice of this `RopeSlice` with trailing whitespace
+    /// removed, where whitespace is defined by [`char::is_whitespace()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("\n  foo bar \r\n");
+    ///
+    /// assert_eq!(r.byte_slice(..).trim_end(), "\n  foo bar");
+    /// ```
+    #[inline]
+    pub fn trim_end(self) -> RopeSlice<'a, C, ARITY, MAX_BYTES> {
+        self.trim_end_by(char::is_whitespace)
+    }
+
+    /// Returns a sub-slice of this `RopeSlice` with all the trailing chars
+    /// matching the predicate removed.
+    #[inline]
+    fn trim_end_by<F>(
+        self,
+        mut predicate: F,
+    ) -> RopeSlice<'a, C, ARITY, MAX_BYTES>
+    where
+        F: FnMut(char) -> bool,
+    {
+        let trimmed = self
+            .chars()
+            .rev()
+            .take_while(|&ch| predicate(ch))
+            .map(char::len_utf8)
+            .sum::<usize>();
+
+        self.byte_slice(..self.byte_len() - trimmed)
+    }
+
+    /// Returns a sub-slice of this `RopeSlice` with all the leading and
+    /// trailing occurrences of `ch` removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("\"\"foo \"bar\"\"");
+    ///
+    /// assert_eq!(r.byte_slice(..).trim_matches('"'), "foo \"bar");
+    /// ```
+    #[inline]
+    pub fn trim_matches(self, ch: char) -> RopeSlice<'a, C, ARITY, MAX_BYTES> {
+        self.trim_start_by(|c| c == ch).trim_end_by(|c| c == ch)
+    }
+
+    /// Returns a sub-slice of this `RopeSlice` with leading whitespace
+    /// removed, where whitespace is defined by [`char::is_whitespace()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("\n  foo bar \r\n");
+    ///
+    /// assert_eq!(r.byte_slice(..).trim_start(), "foo bar \r\n");
+    /// ```
+    #[inline]
+    pub fn trim_start(self) -> RopeSlice<'a, C, ARITY, MAX_BYTES> {
+        self.trim_start_by(char::is_whitespace)
+    }
+
+    /// Returns a sub-slice of this `RopeSlice` with all the leading chars
+    /// matching the predicate removed.
+    #[inline]
+    fn trim_start_by<F>(
+        self,
+        mut predicate: F,
+    ) -> RopeSlice<'a, C, ARITY, MAX_BYTES>
+    where
+        F: FnMut(char) -> bool,
+    {
+        let trimmed = self
+            .chars()
+            .take_while(|&ch| predicate(ch))
+            .map(char::len_utf8)
+            .sum::<usize>();
+
+        self.byte_slice(trimmed..)
+    }
+
     /// Removes the last char from the range spanned by this slice.
     ///
     /// # Panics
     ///
     /// Panics if the slice is empty or if the relative offset is not on a char
     /// boundary.
+    #[cfg(feature = "line-metric")]
     #[inline]
     pub(super) fn truncate_last_char(&mut self) {
         debug_assert!(!self.is_empty());
 
         let slice = &mut self.tree_slice;
 
-        // The last slice only contains one byte so we have to re-slice.
-        if slice.end_summary.bytes() == 1 {
-            *self = self.byte_slice(..self.byte_len() - 1);
+        let last_char_len = slice
+            .end_slice
+            .last_chunk()
+            .chars()
+            .next_back()
+            .map_or(0, char::len_utf8);
+
+        // The last slice only contains the last char so we have to re-slice.
+        if slice.end_summary.bytes() == last_char_len {
+            *self = self.byte_slice(..self.byte_len() - last_char_len);
         }
-        // The last slice contains more than 2 bytes so we can just mutate
-        // in place.
+        // The last slice contains more than the last char so we can just
+        // mutate in place.
         else {
             let last = &mut slice.end_slice;
 
@@ -611,23 +1082,24 @@ impl<'a> RopeSlice<'a> {
         }
     }
 
-    /// Removes the trailing line break (either LF or CRLF) from the range
-    /// spanned by this slice.
+    /// Removes the trailing line break (a CRLF or any character counted as a
+    /// line break) from the range spanned by this slice.
     ///
     /// # Panics
     ///
     /// Panics if this slice doesn't have a trailing line break.
+    #[cfg(feature = "line-metric")]
     #[inline]
     pub(super) fn truncate_trailing_line_break(&mut self) {
-        debug_assert!(self
-            .tree_slice
-            .end_slice()
-            .last_chunk()
-            .ends_with('\n'));
+        let last_chunk = self.tree_slice.end_slice().last_chunk();
+
+        debug_assert!(ends_with_line_break(last_chunk));
+
+        let is_lf = last_chunk.ends_with('\n');
 
         self.truncate_last_char();
 
-        if self.tree_slice.end_slice().last_chunk().ends_with('\r') {
+        if is_lf && self.tree_slice.end_slice().last_chunk().ends_with('\r') {
             self.truncate_last_char();
         }
     }
@@ -680,6 +1152,10 @@ impl<'a> RopeSlice<'a> {
             panic::byte_offset_out_of_bounds(byte_offset, self.byte_len());
         }
 
+        if self.is_ascii() {
+            return byte_offset;
+        }
+
         let super::metrics::Utf16Metric(utf16_offset) =
             self.tree_slice.convert_measure(ByteMetric(byte_offset));
 
@@ -712,7 +1188,10 @@ impl<'a> RopeSlice<'a> {
     #[cfg(feature = "utf16-metric")]
     #[track_caller]
     #[inline]
-    pub fn utf16_slice<R>(self, utf16_range: R) -> RopeSlice<'a>
+    pub fn utf16_slice<R>(
+        self,
+        utf16_range: R,
+    ) -> RopeSlice<'a, C, ARITY, MAX_BYTES>
     where
         R: RangeBounds<usize>,
     {
@@ -731,11 +1210,129 @@ impl<'a> RopeSlice<'a> {
 
         self.tree_slice.slice(Utf16Metric(start)..Utf16Metric(end)).into()
     }
+
+    /// Returns the number of words in the `RopeSlice`, where a word is a
+    /// maximal sequence of non-whitespace characters (like in `wc -w`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("Hello there,\nGeneral Kenobi!");
+    /// assert_eq!(r.byte_slice(..).word_len(), 4);
+    /// assert_eq!(r.byte_slice(2..15).word_len(), 3);
+    /// assert_eq!(r.byte_slice(5..6).word_len(), 0);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "word-metric")))]
+    #[cfg(feature = "word-metric")]
+    #[inline]
+    pub fn word_len(&self) -> usize {
+        match self.tree_slice.summary().words() {
+            Some(words) => words,
+            None => count_words(self.chunks()),
+        }
+    }
+}
+
+impl<'a> RopeSlice<'a> {
+    /// Returns a [`RopeCursor`] at the given byte offset, which can be used
+    /// to read the text sequentially or to query nearby positions without
+    /// descending the tree every time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte offset is out of bounds (i.e. greater than
+    /// [`byte_len()`](Self::byte_len())) or if it's not a char boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo\nbär\nbaz");
+    /// let s = r.byte_slice(4..9);
+    ///
+    /// let mut cursor = s.cursor(0);
+    ///
+    /// assert_eq!(cursor.peek_char(), Some('b'));
+    /// assert_eq!(cursor.prev_char(), None);
+    /// ```
+    #[track_caller]
+    #[inline]
+    pub fn cursor(&self, byte_offset: usize) -> RopeCursor<'a> {
+        RopeCursor::new(*self, byte_offset)
+    }
+
+    /// Returns a parallel iterator over the chunks of this `RopeSlice`, which
+    /// yields the same chunks as [`chunks()`](Self::chunks()) but processes
+    /// them on rayon's global thread pool.
+    ///
+    /// The work is split between threads by repeatedly cutting the slice in
+    /// two at the chunk boundary closest to its middle, which is found by
+    /// descending the tree. Collecting the chunks preserves their order.
+    /// This is mostly useful to hash, count or lint large `RopeSlice`s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// use rayon::prelude::*;
+    ///
+    /// let r = Rope::from("Hello, world!\n".repeat(1 << 16));
+    ///
+    /// let line_breaks = r
+    ///     .byte_slice(..)
+    ///     .par_chunks()
+    ///     .map(|chunk| chunk.matches('\n').count())
+    ///     .sum::<usize>();
+    ///
+    /// assert_eq!(line_breaks, 1 << 16);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    #[cfg(feature = "parallel")]
+    #[inline]
+    pub fn par_chunks(&self) -> crate::iter::ParChunks<'a> {
+        crate::iter::ParChunks::from(*self)
+    }
+
+    /// Returns the byte offsets of the non-overlapping occurrences of
+    /// `pattern` in the `RopeSlice`, searching its subtrees on rayon's global
+    /// thread pool.
+    ///
+    /// The occurrences are the same ones [`str::match_indices()`] would find
+    /// if the `RopeSlice` was a string, including the ones spanning several
+    /// chunks, and they're returned in order. An empty pattern has no
+    /// occurrences.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::Rope;
+    /// #
+    /// let r = Rope::from("foo bar foo\n".repeat(1 << 16));
+    ///
+    /// let matches = r.byte_slice(4..).par_find_all("foo");
+    ///
+    /// assert_eq!(matches.len(), (1 << 17) - 1);
+    /// assert_eq!(matches[..2], [4, 8]);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    #[cfg(feature = "parallel")]
+    #[inline]
+    pub fn par_find_all(&self, pattern: &str) -> Vec<usize> {
+        super::parallel::find_all(*self, pattern)
+    }
 }
 
-impl<'a> From<TreeSlice<'a, { Rope::arity() }, RopeChunk>> for RopeSlice<'a> {
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<TreeSlice<'a, ARITY, GapBuffer<MAX_BYTES>, C>>
+    for RopeSlice<'a, C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn from(tree_slice: TreeSlice<'a, { Rope::arity() }, RopeChunk>) -> Self {
+    fn from(
+        tree_slice: TreeSlice<'a, ARITY, GapBuffer<MAX_BYTES>, C>,
+    ) -> Self {
         Self {
             has_trailing_newline: tree_slice
                 .end_slice()
@@ -746,7 +1343,19 @@ impl<'a> From<TreeSlice<'a, { Rope::arity() }, RopeChunk>> for RopeSlice<'a> {
     }
 }
 
-impl core::fmt::Debug for RopeSlice<'_> {
+impl<'a, C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    From<&'a Rope<C, ARITY, MAX_BYTES>>
+    for RopeSlice<'a, C, ARITY, MAX_BYTES>
+{
+    #[inline]
+    fn from(rope: &'a Rope<C, ARITY, MAX_BYTES>) -> Self {
+        rope.byte_slice(..)
+    }
+}
+
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> core::fmt::Debug
+    for RopeSlice<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.write_str("RopeSlice(\"")?;
@@ -755,7 +1364,9 @@ impl core::fmt::Debug for RopeSlice<'_> {
     }
 }
 
-impl core::fmt::Display for RopeSlice<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::fmt::Display for RopeSlice<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         for chunk in self.chunks() {
@@ -765,76 +1376,128 @@ impl core::fmt::Display for RopeSlice<'_> {
     }
 }
 
-impl core::cmp::PartialEq<RopeSlice<'_>> for RopeSlice<'_> {
+impl<
+        C: RefCount,
+        D: RefCount,
+        const ARITY: usize,
+        const MAX_BYTES: usize,
+        const RHS_ARITY: usize,
+        const RHS_MAX_BYTES: usize,
+    > core::cmp::PartialEq<RopeSlice<'_, D, RHS_ARITY, RHS_MAX_BYTES>>
+    for RopeSlice<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn eq(&self, rhs: &RopeSlice<'_>) -> bool {
+    fn eq(&self, rhs: &RopeSlice<'_, D, RHS_ARITY, RHS_MAX_BYTES>) -> bool {
+        #[cfg(feature = "line-metric")]
+        if self.line_len() != rhs.line_len() {
+            return false;
+        }
+
         (self.byte_len() == rhs.byte_len())
-            && (self.line_len() == rhs.line_len())
             && chunks_eq_chunks(self.chunks(), rhs.chunks())
     }
 }
 
-impl core::cmp::PartialEq<Rope> for RopeSlice<'_> {
+impl<
+        C: RefCount,
+        D: RefCount,
+        const ARITY: usize,
+        const MAX_BYTES: usize,
+        const RHS_ARITY: usize,
+        const RHS_MAX_BYTES: usize,
+    > core::cmp::PartialEq<Rope<D, RHS_ARITY, RHS_MAX_BYTES>>
+    for RopeSlice<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
-    fn eq(&self, rhs: &Rope) -> bool {
+    fn eq(&self, rhs: &Rope<D, RHS_ARITY, RHS_MAX_BYTES>) -> bool {
         rhs == self
     }
 }
 
-impl core::cmp::PartialEq<str> for RopeSlice<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<str> for RopeSlice<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn eq(&self, rhs: &str) -> bool {
         (self.byte_len() == rhs.len()) && chunks_eq_str(self.chunks(), rhs)
     }
 }
 
-impl core::cmp::PartialEq<RopeSlice<'_>> for str {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<RopeSlice<'_, C, ARITY, MAX_BYTES>> for str
+{
     #[inline]
-    fn eq(&self, rhs: &RopeSlice<'_>) -> bool {
+    fn eq(&self, rhs: &RopeSlice<'_, C, ARITY, MAX_BYTES>) -> bool {
         rhs == self
     }
 }
 
-impl core::cmp::PartialEq<&str> for RopeSlice<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<&str> for RopeSlice<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn eq(&self, rhs: &&str) -> bool {
         self == *rhs
     }
 }
 
-impl core::cmp::PartialEq<RopeSlice<'_>> for &str {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<RopeSlice<'_, C, ARITY, MAX_BYTES>> for &str
+{
     #[inline]
-    fn eq(&self, rhs: &RopeSlice<'_>) -> bool {
+    fn eq(&self, rhs: &RopeSlice<'_, C, ARITY, MAX_BYTES>) -> bool {
         rhs == self
     }
 }
 
-impl core::cmp::PartialEq<String> for RopeSlice<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<String> for RopeSlice<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn eq(&self, rhs: &String) -> bool {
         self == &**rhs
     }
 }
 
-impl core::cmp::PartialEq<RopeSlice<'_>> for String {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<RopeSlice<'_, C, ARITY, MAX_BYTES>> for String
+{
     #[inline]
-    fn eq(&self, rhs: &RopeSlice<'_>) -> bool {
+    fn eq(&self, rhs: &RopeSlice<'_, C, ARITY, MAX_BYTES>) -> bool {
         rhs == self
     }
 }
 
-impl core::cmp::PartialEq<alloc::borrow::Cow<'_, str>> for RopeSlice<'_> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<alloc::borrow::Cow<'_, str>>
+    for RopeSlice<'_, C, ARITY, MAX_BYTES>
+{
     #[inline]
     fn eq(&self, rhs: &alloc::borrow::Cow<'_, str>) -> bool {
         self == &**rhs
     }
 }
 
-impl core::cmp::PartialEq<RopeSlice<'_>> for alloc::borrow::Cow<'_, str> {
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize>
+    core::cmp::PartialEq<RopeSlice<'_, C, ARITY, MAX_BYTES>>
+    for alloc::borrow::Cow<'_, str>
+{
     #[inline]
-    fn eq(&self, rhs: &RopeSlice<'_>) -> bool {
+    fn eq(&self, rhs: &RopeSlice<'_, C, ARITY, MAX_BYTES>) -> bool {
         rhs == self
     }
 }
 
-impl core::cmp::Eq for RopeSlice<'_> {}
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> core::cmp::Eq
+    for RopeSlice<'_, C, ARITY, MAX_BYTES>
+{
+}
+
+impl<C: RefCount, const ARITY: usize, const MAX_BYTES: usize> core::hash::Hash
+    for RopeSlice<'_, C, ARITY, MAX_BYTES>
+{
+    #[inline]
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        hash_chunks(self.chunks(), state)
+    }
+}
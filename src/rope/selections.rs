@@ -0,0 +1,217 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use super::delta::{self, Delta};
+
+/// A non-empty set of byte ranges, one of which is the primary one, like the
+/// selections and cursors of a multi-cursor editor.
+///
+/// Cursors are represented by empty ranges. The ranges can be added in any
+/// order and are allowed to overlap until the set is
+/// [`normalize()`](Self::normalize())d, which sorts them and merges the
+/// overlapping ones. After an edit the ranges can be moved to their new
+/// positions with [`transform()`](Self::transform()).
+///
+/// # Examples
+///
+/// ```
+/// # use crop::selections::Selections;
+/// # use crop::{Delta, EditInfo};
+/// #
+/// let mut selections = Selections::new(8..8);
+/// selections.push(2..5);
+/// selections.push(4..6);
+///
+/// selections.normalize();
+/// assert_eq!(selections.ranges(), [2..6, 8..8]);
+/// assert_eq!(selections.primary(), 8..8);
+///
+/// // Type a character at every cursor.
+/// selections.transform(&Delta::from(EditInfo {
+///     byte_range: 8..8,
+///     inserted_len: 1,
+/// }));
+/// assert_eq!(selections.primary(), 9..9);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Selections {
+    ranges: Vec<Range<usize>>,
+    primary: usize,
+}
+
+impl Selections {
+    /// Returns an iterator over the ranges of the set.
+    #[inline]
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = Range<usize>> + '_ {
+        self.ranges.iter().cloned()
+    }
+
+    /// Returns the number of ranges in the set, which is always at least
+    /// one.
+    #[allow(clippy::len_without_is_empty)]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Creates a new `Selections` containing a single range, which is the
+    /// primary one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than its end.
+    #[track_caller]
+    #[inline]
+    pub fn new(range: Range<usize>) -> Self {
+        check_range(&range);
+        Self { ranges: alloc::vec![range], primary: 0 }
+    }
+
+    /// Sorts the ranges by their start and merges the ones that overlap,
+    /// keeping track of the primary range.
+    ///
+    /// Two ranges overlap if they share at least one byte, or if one of them
+    /// is empty and it's contained in the other one, including at its
+    /// boundaries.
+    #[inline]
+    pub fn normalize(&mut self) {
+        let mut ranges = core::mem::take(&mut self.ranges)
+            .into_iter()
+            .enumerate()
+            .collect::<Vec<_>>();
+
+        ranges.sort_by_key(|(_, range)| (range.start, range.end));
+
+        let mut primary_idx = 0;
+
+        for (idx, range) in ranges {
+            let is_primary = idx == self.primary;
+
+            match self.ranges.last_mut() {
+                Some(last) if overlap(last, &range) => {
+                    last.end = last.end.max(range.end);
+                },
+                _ => self.ranges.push(range),
+            }
+
+            if is_primary {
+                primary_idx = self.ranges.len() - 1;
+            }
+        }
+
+        self.primary = primary_idx;
+    }
+
+    /// Returns the primary range.
+    #[inline]
+    pub fn primary(&self) -> Range<usize> {
+        self.ranges[self.primary].clone()
+    }
+
+    /// Returns the index of the primary range.
+    #[inline]
+    pub fn primary_index(&self) -> usize {
+        self.primary
+    }
+
+    /// Adds a range to the set, returning its index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than its end.
+    #[track_caller]
+    #[inline]
+    pub fn push(&mut self, range: Range<usize>) -> usize {
+        check_range(&range);
+        self.ranges.push(range);
+        self.ranges.len() - 1
+    }
+
+    /// Returns the ranges of the set.
+    #[inline]
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    /// Removes the range at the given index, returning it. If it was the
+    /// primary range, the one before it becomes the new primary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds or if it's the only range in the
+    /// set.
+    #[track_caller]
+    #[inline]
+    pub fn remove(&mut self, idx: usize) -> Range<usize> {
+        assert!(
+            self.ranges.len() > 1,
+            "can't remove the only range of a selection set"
+        );
+
+        let range = self.ranges.remove(idx);
+
+        if self.primary > idx || (self.primary == idx && idx > 0) {
+            self.primary -= 1;
+        }
+
+        range
+    }
+
+    /// Sets the range at the given index as the primary one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds (i.e. greater than or equal to
+    /// [`len()`](Self::len())).
+    #[track_caller]
+    #[inline]
+    pub fn set_primary(&mut self, idx: usize) {
+        assert!(
+            idx < self.ranges.len(),
+            "selection index out of bounds: the set has {} ranges but the \
+             index is {idx}",
+            self.ranges.len()
+        );
+
+        self.primary = idx;
+    }
+
+    /// Moves the ranges to their positions after the edits in the delta,
+    /// then [`normalize()`](Self::normalize())s the set.
+    ///
+    /// The boundaries within a replaced range are moved past the inserted
+    /// text, so cursors at the position of an insertion end up after it and
+    /// ranges whose text is replaced collapse into cursors after the new
+    /// text.
+    #[inline]
+    pub fn transform(&mut self, delta: &Delta) {
+        for edit in delta {
+            for range in &mut self.ranges {
+                *range =
+                    delta::map(range.start, edit)..delta::map(range.end, edit);
+            }
+        }
+
+        self.normalize();
+    }
+}
+
+#[track_caller]
+#[inline]
+fn check_range(range: &Range<usize>) {
+    assert!(
+        range.start <= range.end,
+        "byte start after end: {} > {} in selection range",
+        range.start,
+        range.end
+    );
+}
+
+/// Returns whether two ranges, the first of which doesn't start after the
+/// second, should be merged.
+#[inline]
+fn overlap(first: &Range<usize>, second: &Range<usize>) -> bool {
+    second.start < first.end
+        || (second.start == first.end
+            && (first.is_empty() || second.is_empty()))
+}
@@ -0,0 +1,93 @@
+//! This module contains the `Serialize` and `Deserialize` impls of [`Rope`]
+//! and [`FrozenRope`], and the `Serialize` impl of [`RopeSlice`].
+//!
+//! Both are serialized as strings by handing their `Display` impl to
+//! [`Serializer::collect_str()`], which lets serializers supporting it write
+//! out the chunks one at a time instead of collecting them into a `String`
+//! first.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{FrozenRope, Rope, RopeSlice};
+
+impl Serialize for Rope {
+    #[inline]
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for RopeSlice<'_> {
+    #[inline]
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for FrozenRope {
+    #[inline]
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rope {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(StrVisitor(PhantomData))
+    }
+}
+
+impl<'de> Deserialize<'de> for FrozenRope {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(StrVisitor(PhantomData))
+    }
+}
+
+/// Deserializes a `T` from a string.
+struct StrVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for StrVisitor<T>
+where
+    T: for<'a> From<&'a str>,
+{
+    type Value = T;
+
+    #[inline]
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    #[inline]
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<T, E> {
+        Ok(T::from(s))
+    }
+
+    #[inline]
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<T, E> {
+        match super::utils::from_utf8(bytes) {
+            Ok(s) => Ok(T::from(s)),
+            Err(_) => {
+                Err(E::invalid_value(de::Unexpected::Bytes(bytes), &self))
+            },
+        }
+    }
+}
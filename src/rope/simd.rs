@@ -0,0 +1,286 @@
+//! This module contains an explicitly vectorized line break counter, which is
+//! used to summarize chunks when the `simd` feature is enabled.
+//!
+//! On `x86_64` the AVX2 version is selected at runtime if the CPU supports it
+//! and the `std` feature is enabled, and the SSE2 one (which every `x86_64`
+//! CPU supports) is used otherwise. On `aarch64` the NEON version is always
+//! used, and on `wasm32` the SIMD128 one is used if the `simd128` target
+//! feature is enabled at compile time, since WebAssembly has no runtime
+//! detection. On every other platform this falls back to `str_indices`.
+
+/// The byte length of the inputs below which counting the line breaks one
+/// byte at a time is faster than setting up the vector registers.
+const SCALAR_THRESHOLD: usize = 16;
+
+/// The maximum number of vectors whose comparisons can be accumulated in a
+/// vector of `u8` counters before they overflow.
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    all(target_arch = "wasm32", target_feature = "simd128")
+))]
+const MAX_ACCUMULATED: usize = u8::MAX as usize;
+
+/// Returns the number of line feeds in `s`.
+#[inline]
+pub(super) fn count_line_breaks(s: &str) -> usize {
+    let bytes = s.as_bytes();
+
+    if bytes.len() < SCALAR_THRESHOLD {
+        return count_scalar(bytes);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        #[cfg(feature = "std")]
+        if std::is_x86_feature_detected!("avx2") {
+            // SAFETY: we just checked that the CPU supports AVX2.
+            return unsafe { x86_64::count_avx2(bytes) };
+        }
+
+        // SAFETY: SSE2 is part of the `x86_64` baseline.
+        unsafe { x86_64::count_sse2(bytes) }
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    {
+        // SAFETY: the `neon` target feature is enabled.
+        unsafe { aarch64::count_neon(bytes) }
+    }
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        wasm32::count_simd128(bytes)
+    }
+
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        all(target_arch = "aarch64", target_feature = "neon"),
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
+    {
+        str_indices::lines_lf::count_breaks(s)
+    }
+}
+
+#[inline]
+fn count_scalar(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&b| b == b'\n').count()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use core::arch::x86_64::*;
+
+    use super::{count_scalar, MAX_ACCUMULATED};
+
+    /// Counts the line feeds 32 bytes at a time.
+    ///
+    /// The comparison of each vector with a vector of line feeds gives `-1`
+    /// in the matching lanes, which are subtracted from a vector of `u8`
+    /// counters. The counters are only summed horizontally every
+    /// [`MAX_ACCUMULATED`] vectors, before they can overflow.
+    #[cfg(feature = "std")]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn count_avx2(bytes: &[u8]) -> usize {
+        const LANES: usize = 32;
+
+        let line_feeds = _mm256_set1_epi8(b'\n' as i8);
+        let zero = _mm256_setzero_si256();
+
+        let mut vectors = bytes.chunks_exact(LANES);
+        let mut count = 0;
+
+        loop {
+            let mut counters = zero;
+            let mut accumulated = 0;
+
+            while accumulated < MAX_ACCUMULATED {
+                let Some(vector) = vectors.next() else { break };
+                let vector = _mm256_loadu_si256(vector.as_ptr().cast());
+                let matches = _mm256_cmpeq_epi8(vector, line_feeds);
+                counters = _mm256_sub_epi8(counters, matches);
+                accumulated += 1;
+            }
+
+            // Sums every 8 counters into a `u64`.
+            let sums = _mm256_sad_epu8(counters, zero);
+            let mut lanes = [0u64; 4];
+            _mm256_storeu_si256(lanes.as_mut_ptr().cast(), sums);
+            count += lanes.iter().sum::<u64>() as usize;
+
+            if accumulated < MAX_ACCUMULATED {
+                break;
+            }
+        }
+
+        count + count_scalar(vectors.remainder())
+    }
+
+    /// Same as [`count_avx2()`], but 16 bytes at a time.
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn count_sse2(bytes: &[u8]) -> usize {
+        const LANES: usize = 16;
+
+        let line_feeds = _mm_set1_epi8(b'\n' as i8);
+        let zero = _mm_setzero_si128();
+
+        let mut vectors = bytes.chunks_exact(LANES);
+        let mut count = 0;
+
+        loop {
+            let mut counters = zero;
+            let mut accumulated = 0;
+
+            while accumulated < MAX_ACCUMULATED {
+                let Some(vector) = vectors.next() else { break };
+                let vector = _mm_loadu_si128(vector.as_ptr().cast());
+                let matches = _mm_cmpeq_epi8(vector, line_feeds);
+                counters = _mm_sub_epi8(counters, matches);
+                accumulated += 1;
+            }
+
+            let sums = _mm_sad_epu8(counters, zero);
+            let mut lanes = [0u64; 2];
+            _mm_storeu_si128(lanes.as_mut_ptr().cast(), sums);
+            count += lanes.iter().sum::<u64>() as usize;
+
+            if accumulated < MAX_ACCUMULATED {
+                break;
+            }
+        }
+
+        count + count_scalar(vectors.remainder())
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+mod aarch64 {
+    use core::arch::aarch64::*;
+
+    use super::{count_scalar, MAX_ACCUMULATED};
+
+    /// Counts the line feeds 16 bytes at a time, in the same way as the
+    /// `x86_64` versions.
+    #[inline]
+    pub(super) unsafe fn count_neon(bytes: &[u8]) -> usize {
+        const LANES: usize = 16;
+
+        let line_feeds = vdupq_n_u8(b'\n');
+
+        let mut vectors = bytes.chunks_exact(LANES);
+        let mut count = 0;
+
+        loop {
+            let mut counters = vdupq_n_u8(0);
+            let mut accumulated = 0;
+
+            while accumulated < MAX_ACCUMULATED {
+                let Some(vector) = vectors.next() else { break };
+                let vector = vld1q_u8(vector.as_ptr());
+                let matches = vceqq_u8(vector, line_feeds);
+                counters = vsubq_u8(counters, matches);
+                accumulated += 1;
+            }
+
+            // The sum of 16 counters can't be more than `255 * 16`, so it
+            // always fits in a `u16`.
+            count += vaddlvq_u8(counters) as usize;
+
+            if accumulated < MAX_ACCUMULATED {
+                break;
+            }
+        }
+
+        count + count_scalar(vectors.remainder())
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod wasm32 {
+    use core::arch::wasm32::*;
+
+    use super::{count_scalar, MAX_ACCUMULATED};
+
+    /// Counts the line feeds 16 bytes at a time, in the same way as the
+    /// `x86_64` versions.
+    #[inline]
+    pub(super) fn count_simd128(bytes: &[u8]) -> usize {
+        const LANES: usize = 16;
+
+        let line_feeds = u8x16_splat(b'\n');
+
+        let mut vectors = bytes.chunks_exact(LANES);
+        let mut count = 0;
+
+        loop {
+            let mut counters = u8x16_splat(0);
+            let mut accumulated = 0;
+
+            while accumulated < MAX_ACCUMULATED {
+                let Some(vector) = vectors.next() else { break };
+                // SAFETY: the vector is 16 bytes long, and loads don't need
+                // to be aligned.
+                let vector = unsafe { v128_load(vector.as_ptr().cast()) };
+                let matches = u8x16_eq(vector, line_feeds);
+                counters = u8x16_sub(counters, matches);
+                accumulated += 1;
+            }
+
+            // Widens the counters to 4 `u32`s by summing adjacent pairs
+            // twice.
+            let sums = u32x4_extadd_pairwise_u16x8(
+                u16x8_extadd_pairwise_u8x16(counters),
+            );
+
+            count += (u32x4_extract_lane::<0>(sums)
+                + u32x4_extract_lane::<1>(sums)
+                + u32x4_extract_lane::<2>(sums)
+                + u32x4_extract_lane::<3>(sums)) as usize;
+
+            if accumulated < MAX_ACCUMULATED {
+                break;
+            }
+        }
+
+        count + count_scalar(vectors.remainder())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_line_breaks_matches_scalar() {
+        let text = (0..20_000)
+            .map(|i| if i % 3 == 0 || i % 7 == 0 { '\n' } else { 'a' })
+            .collect::<String>();
+
+        // Every short length, and lengths around the number of bytes after
+        // which the counters are flushed.
+        let lens = (0..600)
+            .chain((16 * 255 - 40)..(16 * 255 + 40))
+            .chain((32 * 255 - 40)..(32 * 255 + 40))
+            .chain([20_000]);
+
+        for len in lens {
+            for start in 0..3 {
+                let s = &text[start..len.max(start)];
+                let expected = count_scalar(s.as_bytes());
+
+                assert_eq!(count_line_breaks(s), expected, "{start}..{len}");
+
+                #[cfg(target_arch = "x86_64")]
+                assert_eq!(
+                    unsafe { x86_64::count_sse2(s.as_bytes()) },
+                    expected,
+                    "{start}..{len}"
+                );
+            }
+        }
+
+        let all_line_feeds = "\n".repeat(10_000);
+        assert_eq!(count_line_breaks(&all_line_feeds), 10_000);
+    }
+}
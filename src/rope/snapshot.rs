@@ -0,0 +1,132 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use super::metrics::ByteMetric;
+use super::Rope;
+
+/// A frozen copy of a [`Rope`], created by [`Rope::snapshot()`].
+///
+/// Taking a snapshot is as cheap as cloning the `Rope`, and since the
+/// snapshot shares its nodes with the `Rope` it was taken from,
+/// [`Rope::changes_since()`] can efficiently find the regions that changed
+/// since the snapshot was taken by skipping all the shared subtrees.
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    rope: Rope,
+}
+
+impl Snapshot {
+    /// Consumes the snapshot, returning the `Rope` it contains.
+    #[inline]
+    pub fn into_rope(self) -> Rope {
+        self.rope
+    }
+
+    /// Returns the `Rope` as it was when the snapshot was taken.
+    #[inline]
+    pub fn rope(&self) -> &Rope {
+        &self.rope
+    }
+}
+
+impl core::ops::Deref for Snapshot {
+    type Target = Rope;
+
+    #[inline]
+    fn deref(&self) -> &Rope {
+        &self.rope
+    }
+}
+
+impl From<Rope> for Snapshot {
+    #[inline]
+    fn from(rope: Rope) -> Self {
+        Self { rope }
+    }
+}
+
+/// Returns the byte ranges of `new` whose contents differ from the ones in
+/// `old`, sorted by their position.
+///
+/// The ranges found by pruning the shared subtrees are narrowed down by
+/// trimming the bytes they have in common with the corresponding ranges in
+/// `old`, which can be different subtrees storing the same text.
+#[inline]
+pub(super) fn changes_since(new: &Rope, old: &Rope) -> Vec<Range<usize>> {
+    merge_adjacent(new.tree.diff_shared(&old.tree).into_iter().filter_map(
+        |(old_range, new_range)| narrow(new, old, old_range, new_range),
+    ))
+}
+
+/// Merges the consecutive ranges in `changes` that touch each other.
+#[inline]
+pub(super) fn merge_adjacent<I>(changes: I) -> Vec<Range<usize>>
+where
+    I: IntoIterator<Item = Range<usize>>,
+{
+    let mut merged = Vec::<Range<usize>>::new();
+
+    for change in changes {
+        match merged.last_mut() {
+            Some(last) if last.end == change.start => last.end = change.end,
+            _ => merged.push(change),
+        }
+    }
+
+    merged
+}
+
+/// Trims the bytes that a range of `old` and the corresponding range of `new`
+/// have in common at their start and end, returning what's left of the range
+/// of `new`, or `None` if their contents are the same.
+#[inline]
+pub(super) fn narrow(
+    new: &Rope,
+    old: &Rope,
+    old_range: Range<ByteMetric>,
+    new_range: Range<ByteMetric>,
+) -> Option<Range<usize>> {
+    let ByteMetric(mut old_start) = old_range.start;
+    let ByteMetric(mut old_end) = old_range.end;
+    let ByteMetric(mut new_start) = new_range.start;
+    let ByteMetric(mut new_end) = new_range.end;
+
+    let old_slice = old.byte_slice(old_start..old_end);
+    let new_slice = new.byte_slice(new_start..new_end);
+
+    let mut prefix = old_slice
+        .bytes()
+        .zip(new_slice.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    while !old_slice.is_char_boundary(prefix)
+        || !new_slice.is_char_boundary(prefix)
+    {
+        prefix -= 1;
+    }
+
+    let max_suffix = old_slice.byte_len().min(new_slice.byte_len()) - prefix;
+
+    let mut suffix = old_slice
+        .bytes()
+        .rev()
+        .zip(new_slice.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    while !old_slice.is_char_boundary(old_slice.byte_len() - suffix)
+        || !new_slice.is_char_boundary(new_slice.byte_len() - suffix)
+    {
+        suffix -= 1;
+    }
+
+    old_start += prefix;
+    new_start += prefix;
+    old_end -= suffix;
+    new_end -= suffix;
+
+    (old_start != old_end || new_start != new_end)
+        .then_some(new_start..new_end)
+}
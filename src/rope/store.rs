@@ -0,0 +1,532 @@
+//! This module contains the [`Store`], which persists the versions of a
+//! [`Rope`] in an append-only file.
+//!
+//! The file starts with a short header followed by a sequence of records,
+//! each made of a tag byte, the length of its payload as a little-endian
+//! `u32`, the payload itself and a checksum of everything before it. There are
+//! three kinds of records:
+//!
+//! - leaves, whose payload is the text of a chunk;
+//! - internal nodes, whose payload is the offsets of their children;
+//! - versions, whose payload is the offset of their root node.
+//!
+//! A node is always written after its children, and a version after all of
+//! its nodes, so a file whose tail was cut short by a crash still contains
+//! every version before the last complete version record.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use super::rope::RopeChunk;
+use super::utils::ends_with_line_break;
+use super::Rope;
+use crate::tree::{self, Tree};
+
+type NodeRef<'a> = tree::NodeRef<'a, { Rope::arity() }, RopeChunk>;
+
+type OwnedNode = tree::OwnedNode<{ Rope::arity() }, RopeChunk>;
+
+const MAGIC: [u8; 8] = *b"cropstor";
+
+const FORMAT_VERSION: u32 = 1;
+
+const HEADER_LEN: u64 = MAGIC.len() as u64 + 4;
+
+/// The length of a record minus the length of its payload.
+const RECORD_OVERHEAD: u64 = 1 + 4 + 8;
+
+const LEAF: u8 = 0;
+
+const INODE: u8 = 1;
+
+const VERSION: u8 = 2;
+
+/// The maximum depth of the trees read back from the file, which no valid
+/// tree can get close to.
+const MAX_DEPTH: usize = 64;
+
+/// A store persisting the versions of a [`Rope`] in an append-only file.
+///
+/// Every time a `Rope` is [`save()`](Self::save())d only the nodes of its
+/// tree that weren't already written for the last saved or loaded version
+/// are appended to the file, so the subtrees shared by consecutive versions
+/// of a document are stored once. This makes the store a cheap way to keep
+/// the whole history of a document on disk.
+///
+/// Opening a store only reads the list of its versions, and each version is
+/// read from the file when it's [`load()`](Self::load())ed.
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::Cursor;
+/// # use crop::{store::Store, Rope};
+/// #
+/// let mut store = Store::create(Cursor::new(Vec::new())).unwrap();
+///
+/// let mut r = Rope::from("Hello, world!");
+/// store.save(&r).unwrap();
+///
+/// r.replace(7..12, "Earth");
+/// store.save(&r).unwrap();
+///
+/// let mut store = Store::open(store.into_inner()).unwrap();
+///
+/// assert_eq!(store.len(), 2);
+/// assert_eq!(store.load(0).unwrap(), "Hello, world!");
+/// assert_eq!(store.load(1).unwrap(), "Hello, Earth!");
+/// ```
+pub struct Store<F> {
+    file: F,
+
+    /// The offset right after the last complete version record, where the
+    /// next records will be written.
+    end: u64,
+
+    /// The offsets of the root records of the versions, from oldest to
+    /// newest.
+    versions: Vec<u64>,
+
+    /// The nodes of the last saved or loaded tree keyed by the offset of
+    /// their records, including all of their descendants.
+    ///
+    /// Holding on to them guarantees that their addresses aren't reused by
+    /// other nodes and that they're never mutated in place.
+    nodes: BTreeMap<u64, OwnedNode>,
+
+    /// The offsets of the records of the nodes in `nodes`, keyed by the
+    /// nodes' addresses.
+    offsets: BTreeMap<usize, u64>,
+}
+
+impl<F> core::fmt::Debug for Store<F> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Store")
+            .field("versions", &self.versions.len())
+            .field("byte_len", &self.end)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The nodes of the tree being saved or loaded, which replace the ones
+/// remembered by the [`Store`] once the operation completes.
+#[derive(Default)]
+struct Pending {
+    nodes: BTreeMap<u64, OwnedNode>,
+    offsets: BTreeMap<usize, u64>,
+}
+
+impl Pending {
+    #[inline]
+    fn insert(&mut self, node: NodeRef<'_>, offset: u64) {
+        self.offsets.insert(node.addr(), offset);
+        self.nodes.insert(offset, node.to_owned());
+    }
+}
+
+impl<F> Store<F> {
+    /// Moves `node` and all of its descendants from the nodes remembered by
+    /// the store to the pending ones.
+    #[inline]
+    fn carry(&self, node: NodeRef<'_>, pending: &mut Pending) {
+        if pending.offsets.contains_key(&node.addr()) {
+            return;
+        }
+
+        for child in node.children() {
+            self.carry(child, pending);
+        }
+
+        if let Some(&offset) = self.offsets.get(&node.addr()) {
+            pending.insert(node, offset);
+        }
+    }
+
+    /// Returns a mutable reference to the underlying file.
+    ///
+    /// Writing to the file through this reference can corrupt the store.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut F {
+        &mut self.file
+    }
+
+    /// Returns a shared reference to the underlying file, e.g. to call
+    /// [`File::sync_data()`](std::fs::File::sync_data) on it.
+    #[inline]
+    pub fn get_ref(&self) -> &F {
+        &self.file
+    }
+
+    /// Consumes the store, returning the underlying file.
+    #[inline]
+    pub fn into_inner(self) -> F {
+        self.file
+    }
+
+    /// Returns `true` if no version has been saved in the store.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.versions.is_empty()
+    }
+
+    /// Returns the number of versions saved in the store.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.versions.len()
+    }
+
+    /// Appends the records of `node` and of all its descendants that haven't
+    /// been written yet to `buf`, returning the offset of the node's record.
+    #[inline]
+    fn write_node(
+        &self,
+        node: NodeRef<'_>,
+        pending: &mut Pending,
+        buf: &mut Vec<u8>,
+    ) -> u64 {
+        if let Some(&offset) = pending.offsets.get(&node.addr()) {
+            return offset;
+        }
+
+        if let Some(&offset) = self.offsets.get(&node.addr()) {
+            self.carry(node, pending);
+            return offset;
+        }
+
+        let record = if let Some(leaf) = node.leaf() {
+            let left = leaf.left_chunk().as_bytes();
+            let right = leaf.right_chunk().as_bytes();
+            push_record(buf, self.end, LEAF, &[left, right])
+        } else {
+            let children = node
+                .children()
+                .flat_map(|child| {
+                    self.write_node(child, pending, buf).to_le_bytes()
+                })
+                .collect::<Vec<u8>>();
+
+            push_record(buf, self.end, INODE, &[&children])
+        };
+
+        pending.insert(node, record);
+
+        record
+    }
+}
+
+impl<F: Read + Write + Seek> Store<F> {
+    /// Creates a new store with no versions, writing its header at the start
+    /// of `file`.
+    ///
+    /// The file is expected to be empty, and anything after the header is
+    /// overwritten by the versions saved in the store.
+    #[inline]
+    pub fn create(mut file: F) -> io::Result<Self> {
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&MAGIC)?;
+        file.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        file.flush()?;
+
+        Ok(Self {
+            file,
+            end: HEADER_LEN,
+            versions: Vec::new(),
+            nodes: BTreeMap::new(),
+            offsets: BTreeMap::new(),
+        })
+    }
+
+    /// Loads the version at the given index, where `0` is the oldest one.
+    ///
+    /// The nodes shared with the last saved or loaded version are reused
+    /// instead of being read from the file again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds (i.e. `version >= store.len()`).
+    #[track_caller]
+    #[inline]
+    pub fn load(&mut self, version: usize) -> io::Result<Rope> {
+        assert!(
+            version < self.len(),
+            "index out of bounds: the version is {version} but the length is \
+             {}",
+            self.len()
+        );
+
+        let mut pending = Pending::default();
+
+        let root = self.read_node(self.versions[version], 0, &mut pending)?;
+
+        let tree = Tree::from_root(root).ok_or_else(invalid_data)?;
+
+        if tree.leaf_count() > 1
+            && tree.leaves().any(|leaf| {
+                leaf.len() == 0 || leaf.len() < RopeChunk::chunk_min()
+            })
+        {
+            return Err(invalid_data());
+        }
+
+        let has_trailing_newline = tree
+            .leaves()
+            .next_back()
+            .map_or(false, |leaf| ends_with_line_break(leaf.last_chunk()));
+
+        self.nodes = pending.nodes;
+        self.offsets = pending.offsets;
+
+        Ok(Rope::from_tree(tree, has_trailing_newline))
+    }
+
+    /// Opens a store previously created with [`create()`](Self::create()),
+    /// reading the list of its versions.
+    ///
+    /// If the file ends with an incomplete version, e.g. because the program
+    /// crashed while saving it, that version is ignored and overwritten by
+    /// the next one to be saved.
+    #[inline]
+    pub fn open(mut file: F) -> io::Result<Self> {
+        let mut header = [0; HEADER_LEN as usize];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+
+        if header[..MAGIC.len()] != MAGIC
+            || header[MAGIC.len()..] != FORMAT_VERSION.to_le_bytes()
+        {
+            return Err(invalid_data());
+        }
+
+        let file_len = file.seek(SeekFrom::End(0))?;
+
+        let mut store = Self {
+            file,
+            end: HEADER_LEN,
+            versions: Vec::new(),
+            nodes: BTreeMap::new(),
+            offsets: BTreeMap::new(),
+        };
+
+        let mut offset = HEADER_LEN;
+
+        while offset + RECORD_OVERHEAD <= file_len {
+            let (tag, len) = store.read_header(offset)?;
+
+            let record_end = offset + RECORD_OVERHEAD + len as u64;
+
+            if record_end > file_len {
+                break;
+            }
+
+            match tag {
+                LEAF | INODE => {},
+
+                VERSION => {
+                    let Ok(payload) = store.read_record(offset, record_end)
+                    else {
+                        break;
+                    };
+
+                    let Ok(root) = <[u8; 8]>::try_from(&*payload) else {
+                        break;
+                    };
+
+                    let root = u64::from_le_bytes(root);
+
+                    if root < HEADER_LEN || root >= offset {
+                        break;
+                    }
+
+                    store.versions.push(root);
+                    store.end = record_end;
+                },
+
+                _ => break,
+            }
+
+            offset = record_end;
+        }
+
+        Ok(store)
+    }
+
+    /// Reads the tag and the payload length of the record at `offset`.
+    #[inline]
+    fn read_header(&mut self, offset: u64) -> io::Result<(u8, u32)> {
+        let mut header = [0; 5];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut header)?;
+        let len =
+            u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+        Ok((header[0], len))
+    }
+
+    /// Reads the node whose record starts at `offset`, together with all of
+    /// its descendants.
+    #[inline]
+    fn read_node(
+        &mut self,
+        offset: u64,
+        depth: usize,
+        pending: &mut Pending,
+    ) -> io::Result<OwnedNode> {
+        if let Some(node) = pending.nodes.get(&offset) {
+            return Ok(node.clone());
+        }
+
+        if let Some(node) = self.nodes.get(&offset).cloned() {
+            self.carry(node.node_ref(), pending);
+            return Ok(node);
+        }
+
+        if depth > MAX_DEPTH || offset < HEADER_LEN || offset >= self.end {
+            return Err(invalid_data());
+        }
+
+        let (tag, len) = self.read_header(offset)?;
+
+        let record_end = offset + RECORD_OVERHEAD + len as u64;
+
+        if record_end > self.end {
+            return Err(invalid_data());
+        }
+
+        let payload = self.read_record(offset, record_end)?;
+
+        let node = match tag {
+            LEAF => {
+                let text = super::utils::from_utf8(&payload)
+                    .map_err(|_| invalid_data())?;
+
+                if text.len() > RopeChunk::max_bytes() {
+                    return Err(invalid_data());
+                }
+
+                OwnedNode::leaf(RopeChunk::from(text))
+            },
+
+            INODE if payload.len() % 8 == 0 => {
+                let mut children = Vec::with_capacity(payload.len() / 8);
+
+                for child in payload.chunks_exact(8) {
+                    let mut bytes = [0; 8];
+                    bytes.copy_from_slice(child);
+                    let child = u64::from_le_bytes(bytes);
+
+                    // Children are always written before their parent, which
+                    // also rules out cycles.
+                    if child >= offset {
+                        return Err(invalid_data());
+                    }
+
+                    children.push(self.read_node(
+                        child,
+                        depth + 1,
+                        pending,
+                    )?);
+                }
+
+                OwnedNode::internal(children).ok_or_else(invalid_data)?
+            },
+
+            _ => return Err(invalid_data()),
+        };
+
+        pending.insert(node.node_ref(), offset);
+
+        Ok(node)
+    }
+
+    /// Reads the payload of the record spanning `offset..record_end`,
+    /// checking it against the record's checksum.
+    #[inline]
+    fn read_record(
+        &mut self,
+        offset: u64,
+        record_end: u64,
+    ) -> io::Result<Vec<u8>> {
+        let mut record = alloc::vec![0; (record_end - offset) as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut record)?;
+
+        let (record, sum) = record.split_at(record.len() - 8);
+
+        if checksum(record).to_le_bytes() != sum {
+            return Err(invalid_data());
+        }
+
+        Ok(record[5..].to_vec())
+    }
+
+    /// Saves the `Rope` as a new version, returning its index.
+    ///
+    /// Only the nodes which weren't part of the last saved or loaded version
+    /// are written to the file, which is flushed before returning. The file
+    /// isn't synced to disk though, which is left to the caller.
+    #[inline]
+    pub fn save(&mut self, rope: &Rope) -> io::Result<usize> {
+        let mut pending = Pending::default();
+
+        let mut buf = Vec::new();
+
+        let root =
+            self.write_node(rope.tree.root_ref(), &mut pending, &mut buf);
+
+        push_record(&mut buf, self.end, VERSION, &[&root.to_le_bytes()]);
+
+        self.file.seek(SeekFrom::Start(self.end))?;
+        self.file.write_all(&buf)?;
+        self.file.flush()?;
+
+        self.end += buf.len() as u64;
+        self.versions.push(root);
+        self.nodes = pending.nodes;
+        self.offsets = pending.offsets;
+
+        Ok(self.versions.len() - 1)
+    }
+}
+
+/// Returns the 64-bit FNV-1a hash of the bytes.
+#[inline]
+fn checksum(bytes: &[u8]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[inline]
+fn invalid_data() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "corrupted crop store")
+}
+
+/// Appends a record with the given tag and payload to `buf`, returning its
+/// offset in the file assuming `buf` will be written at `buf_offset`.
+#[inline]
+fn push_record(
+    buf: &mut Vec<u8>,
+    buf_offset: u64,
+    tag: u8,
+    payload: &[&[u8]],
+) -> u64 {
+    let start = buf.len();
+
+    let len = payload.iter().map(|part| part.len()).sum::<usize>();
+
+    buf.push(tag);
+    buf.extend_from_slice(&(len as u32).to_le_bytes());
+
+    for part in payload {
+        buf.extend_from_slice(part);
+    }
+
+    let sum = checksum(&buf[start..]);
+    buf.extend_from_slice(&sum.to_le_bytes());
+
+    buf_offset + start as u64
+}
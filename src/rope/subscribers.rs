@@ -0,0 +1,105 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Information about an edit performed on a [`Rope`](crate::Rope), passed
+/// to the callbacks registered with
+/// [`Rope::subscribe()`](crate::Rope::subscribe()).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EditInfo {
+    /// The byte range that was replaced, relative to the `Rope` as it was
+    /// before the edit.
+    pub byte_range: Range<usize>,
+
+    /// The length in bytes of the text inserted at the start of
+    /// [`byte_range`](Self::byte_range).
+    pub inserted_len: usize,
+}
+
+impl EditInfo {
+    /// Returns the byte range of the inserted text, relative to the `Rope`
+    /// as it is after the edit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use crop::EditInfo;
+    /// #
+    /// let edit = EditInfo { byte_range: 2..5, inserted_len: 7 };
+    ///
+    /// assert_eq!(edit.inserted_range(), 2..9);
+    /// ```
+    #[inline]
+    pub fn inserted_range(&self) -> Range<usize> {
+        self.byte_range.start..self.byte_range.start + self.inserted_len
+    }
+}
+
+/// The region of a [`Rope`](crate::Rope) affected by an edit, returned by
+/// [`Rope::replace_with_info()`](crate::Rope::replace_with_info()) and the
+/// other `*_with_info()` methods.
+///
+/// The line ranges are in terms of line offsets, i.e. the values returned by
+/// [`Rope::line_of_byte()`](crate::Rope::line_of_byte()), and they always
+/// contain at least one line.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EditRegion {
+    /// The edit that was performed.
+    pub edit: EditInfo,
+
+    /// The lines spanned by the replaced byte range, relative to the `Rope`
+    /// as it was before the edit.
+    pub old_lines: Range<usize>,
+
+    /// The lines spanned by the inserted text, relative to the `Rope` as it
+    /// is after the edit.
+    pub new_lines: Range<usize>,
+}
+
+/// The identifier of a callback registered with
+/// [`Rope::subscribe()`](crate::Rope::subscribe()), which can be used to
+/// [`unsubscribe()`](crate::Rope::unsubscribe()) it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SubscriptionId(u64);
+
+type Callback = Box<dyn FnMut(&EditInfo) + Send + Sync>;
+
+/// The callbacks registered on a [`Rope`](crate::Rope), in the order in which
+/// they were registered.
+#[derive(Default)]
+pub(super) struct Subscribers {
+    callbacks: Vec<(SubscriptionId, Callback)>,
+    next_id: u64,
+}
+
+impl Subscribers {
+    /// Registers a new callback, returning its id.
+    #[inline]
+    pub(super) fn insert(&mut self, callback: Callback) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.callbacks.push((id, callback));
+        id
+    }
+
+    /// Calls every callback with the given edit.
+    #[inline]
+    pub(super) fn notify(&mut self, edit: &EditInfo) {
+        for (_, callback) in &mut self.callbacks {
+            callback(edit);
+        }
+    }
+
+    /// Removes the callback with the given id, returning whether it was
+    /// registered.
+    #[inline]
+    pub(super) fn remove(&mut self, id: SubscriptionId) -> bool {
+        let Ok(idx) = self.callbacks.binary_search_by_key(&id, |(id, _)| *id)
+        else {
+            return false;
+        };
+
+        let _ = self.callbacks.remove(idx);
+        true
+    }
+}
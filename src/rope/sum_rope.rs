@@ -0,0 +1,875 @@
+use alloc::vec::Vec;
+use core::ops::{Add, AddAssign, Range, RangeBounds, Sub, SubAssign};
+
+use super::utils::panic_messages as panic;
+use super::Rope;
+use crate::range_bounds_to_start_end;
+use crate::tree::{
+    AsSlice,
+    BalancedLeaf,
+    BaseMeasured,
+    Metric,
+    ReplaceableLeaf,
+    SlicingMetric,
+    Summarize,
+    Tree,
+    TreeSlice,
+};
+
+#[cfg(any(test, feature = "small_chunks"))]
+const CHUNK_MAX_ITEMS: usize = 4;
+
+#[cfg(not(any(test, feature = "small_chunks")))]
+const CHUNK_MAX_ITEMS: usize = 64;
+
+const CHUNK_MIN_ITEMS: usize = CHUNK_MAX_ITEMS / 2;
+
+/// A sequence of arbitrary items built on the same B-tree used by [`Rope`],
+/// which keeps the sum of the items' [summaries](Summarize) up to date
+/// through every edit.
+///
+/// Inserting, removing and looking up an item by its index all take
+/// `O(log n)` time, and so does finding the item at a given offset of any
+/// [`Metric`] of the summaries, e.g. the span containing a byte offset in a
+/// sequence of styled spans. Clones share their nodes like the ones of a
+/// `Rope`, so cloning a `SumRope` is cheap.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::sum_rope::SumRope;
+/// # use crop::tree::Summarize;
+/// #
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Span {
+///     len: usize,
+///     bold: bool,
+/// }
+///
+/// impl Summarize for Span {
+///     // The total length of the spans.
+///     type Summary = usize;
+///
+///     fn summarize(&self) -> usize {
+///         self.len
+///     }
+/// }
+///
+/// let mut spans = SumRope::new();
+///
+/// spans.push(Span { len: 5, bold: false });
+/// spans.push(Span { len: 3, bold: true });
+/// spans.insert(1, Span { len: 2, bold: false });
+///
+/// assert_eq!(spans.len(), 3);
+/// assert_eq!(*spans.summary(), 10);
+///
+/// let removed = spans.remove(0);
+/// assert_eq!(removed, Span { len: 5, bold: false });
+///
+/// let bold = spans.iter().filter(|span| span.bold).count();
+/// assert_eq!(bold, 1);
+///
+/// assert_eq!(*spans.slice(1..).summary(), 3);
+/// ```
+#[derive(Clone)]
+pub struct SumRope<T: Summarize + Clone + 'static> {
+    tree: Tree<{ Rope::arity() }, ItemChunk<T>>,
+}
+
+impl<T: Summarize + Clone + 'static> SumRope<T> {
+    #[doc(hidden)]
+    pub fn assert_invariants(&self) {
+        self.tree.assert_invariants();
+    }
+
+    /// Removes the items in the given range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start is greater than the end or if the end is out of
+    /// bounds (i.e. greater than [`len()`](Self::len())).
+    #[track_caller]
+    #[inline]
+    pub fn delete<R>(&mut self, item_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.splice(item_range, core::iter::empty());
+    }
+
+    /// Returns the item at the given index, or `None` if the index is out of
+    /// bounds.
+    #[inline]
+    pub fn get(&self, item_index: usize) -> Option<&T> {
+        if item_index >= self.len() {
+            return None;
+        }
+
+        let (slice, ItemCount(offset)) =
+            self.tree.leaf_at_measure(ItemCount(item_index + 1));
+
+        slice.items.get(item_index - offset)
+    }
+
+    /// Returns the index of the item containing the given `M`-offset, i.e.
+    /// of the first item such that the `M`-measure of all the items up to
+    /// and including it is greater than the offset. Returns
+    /// [`len()`](Self::len()) if no such item exists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use core::ops::{Add, AddAssign, Sub, SubAssign};
+    /// # use crop::sum_rope::SumRope;
+    /// # use crop::tree::{Metric, Summarize};
+    /// #
+    /// #[derive(Clone, Debug)]
+    /// struct Span(usize);
+    ///
+    /// impl Summarize for Span {
+    ///     type Summary = usize;
+    ///
+    ///     fn summarize(&self) -> usize {
+    ///         self.0
+    ///     }
+    /// }
+    ///
+    /// // A metric measuring the length of the spans.
+    /// #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    /// struct Len(usize);
+    ///
+    /// impl Metric<usize> for Len {
+    ///     fn zero() -> Self {
+    ///         Len(0)
+    ///     }
+    ///
+    ///     fn one() -> Self {
+    ///         Len(1)
+    ///     }
+    ///
+    ///     fn measure(len: &usize) -> Self {
+    ///         Len(*len)
+    ///     }
+    /// }
+    /// #
+    /// # impl Add for Len {
+    /// #     type Output = Len;
+    /// #     fn add(self, other: Len) -> Len {
+    /// #         Len(self.0 + other.0)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl Sub for Len {
+    /// #     type Output = Len;
+    /// #     fn sub(self, other: Len) -> Len {
+    /// #         Len(self.0 - other.0)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl AddAssign for Len {
+    /// #     fn add_assign(&mut self, other: Len) {
+    /// #         self.0 += other.0;
+    /// #     }
+    /// # }
+    /// #
+    /// # impl SubAssign for Len {
+    /// #     fn sub_assign(&mut self, other: Len) {
+    /// #         self.0 -= other.0;
+    /// #     }
+    /// # }
+    ///
+    /// let spans = [3, 0, 4, 2].into_iter().map(Span).collect::<SumRope<_>>();
+    ///
+    /// assert_eq!(spans.index_of_measure(Len(2)), 0);
+    /// assert_eq!(spans.index_of_measure(Len(3)), 2);
+    /// assert_eq!(spans.index_of_measure(Len(8)), 3);
+    /// assert_eq!(spans.index_of_measure(Len(9)), 4);
+    ///
+    /// assert_eq!(spans.measure_of_index::<Len>(3), Len(7));
+    /// ```
+    #[inline]
+    pub fn index_of_measure<M>(&self, measure: M) -> usize
+    where
+        M: Metric<T::Summary>,
+    {
+        if measure >= self.measure::<M>() {
+            return self.len();
+        }
+
+        let leaves = self.tree.filter_leaves(|offset, summary| {
+            let start = M::measure(&offset.summary);
+            start <= measure && measure < start + M::measure(&summary.summary)
+        });
+
+        let (offset, slice) = leaves
+            .into_iter()
+            .next()
+            .expect("the offset is within the measure of the items");
+
+        let mut end = M::measure(&offset.summary);
+
+        slice
+            .items
+            .iter()
+            .position(|item| {
+                end += M::measure(&item.summarize());
+                end > measure
+            })
+            .map(|idx| offset.len + idx)
+            .expect("the leaf contains the offset")
+    }
+
+    /// Inserts an item at the given index, shifting all the items after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds (i.e. greater than
+    /// [`len()`](Self::len())).
+    #[track_caller]
+    #[inline]
+    pub fn insert(&mut self, item_offset: usize, item: T) {
+        self.splice(item_offset..item_offset, [item]);
+    }
+
+    /// Returns `true` if the `SumRope` doesn't contain any items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the items of the `SumRope`.
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        self.tree.leaves().flat_map(|slice| slice.items.iter())
+    }
+
+    /// Returns the number of items in the `SumRope`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.summary().len
+    }
+
+    /// Returns the `M`-measure of all the items.
+    #[inline]
+    pub fn measure<M>(&self) -> M
+    where
+        M: Metric<T::Summary>,
+    {
+        M::measure(self.summary())
+    }
+
+    /// Returns the `M`-measure of all the items before the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds (i.e. greater than
+    /// [`len()`](Self::len())).
+    #[track_caller]
+    #[inline]
+    pub fn measure_of_index<M>(&self, item_offset: usize) -> M
+    where
+        M: Metric<T::Summary>,
+    {
+        if item_offset > self.len() {
+            panic::item_offset_out_of_bounds(item_offset, self.len());
+        }
+
+        if item_offset == 0 {
+            return M::zero();
+        }
+
+        let (slice, offset) =
+            self.tree.leaf_and_offset_at_measure(ItemCount(item_offset));
+
+        slice.items[..item_offset - offset.len]
+            .iter()
+            .fold(M::measure(&offset.summary), |measure, item| {
+                measure + M::measure(&item.summarize())
+            })
+    }
+
+    /// Creates a new, empty `SumRope`.
+    #[inline]
+    pub fn new() -> Self {
+        Self { tree: Tree::default() }
+    }
+
+    /// Appends an item at the end of the `SumRope`.
+    #[inline]
+    pub fn push(&mut self, item: T) {
+        self.tree.push::<ItemCount>(alloc::vec![item]);
+    }
+
+    /// Removes the item at the given index, returning it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds (i.e. greater than or equal to
+    /// [`len()`](Self::len())).
+    #[track_caller]
+    #[inline]
+    pub fn remove(&mut self, item_index: usize) -> T {
+        let Some(item) = self.get(item_index).cloned() else {
+            panic::item_index_out_of_bounds(item_index, self.len());
+        };
+
+        self.splice(item_index..item_index + 1, core::iter::empty());
+
+        item
+    }
+
+    /// Returns an immutable slice of the `SumRope` in the specified item
+    /// range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start is greater than the end or if the end is out of
+    /// bounds (i.e. greater than [`len()`](Self::len())).
+    #[track_caller]
+    #[inline]
+    pub fn slice<R>(&self, item_range: R) -> SumSlice<'_, T>
+    where
+        R: RangeBounds<usize>,
+    {
+        let Range { start, end } = self.range(item_range);
+        SumSlice { slice: self.tree.slice(ItemCount(start)..ItemCount(end)) }
+    }
+
+    /// Replaces the items in the given range with the given items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start is greater than the end or if the end is out of
+    /// bounds (i.e. greater than [`len()`](Self::len())).
+    #[track_caller]
+    #[inline]
+    pub fn splice<R, I>(&mut self, item_range: R, items: I)
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        let Range { start, end } = self.range(item_range);
+
+        let items = items.into_iter().collect::<Vec<_>>();
+
+        if start == end && items.is_empty() {
+            return;
+        }
+
+        self.tree.replace(ItemCount(start)..ItemCount(end), items);
+    }
+
+    /// Returns the sum of the summaries of all the items.
+    #[inline]
+    pub fn summary(&self) -> &T::Summary {
+        &self.tree.summary().summary
+    }
+
+    /// Converts the range into a `start..end` item range, checking that it's
+    /// within bounds.
+    #[track_caller]
+    #[inline]
+    fn range<R>(&self, item_range: R) -> Range<usize>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(item_range, 0, self.len());
+
+        if start > end {
+            panic::item_start_after_end(start, end);
+        }
+
+        if end > self.len() {
+            panic::item_offset_out_of_bounds(end, self.len());
+        }
+
+        start..end
+    }
+}
+
+impl<T: Summarize + Clone + 'static> core::fmt::Debug for SumRope<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Summarize + Clone + 'static> Default for SumRope<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Summarize + Clone + 'static> From<SumSlice<'_, T>> for SumRope<T> {
+    #[inline]
+    fn from(slice: SumSlice<'_, T>) -> Self {
+        Self { tree: Tree::from(slice.slice) }
+    }
+}
+
+impl<T: Summarize + Clone + 'static> FromIterator<T> for SumRope<T> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut items = iter.into_iter().collect::<Vec<_>>();
+
+        let mut leaves = Vec::new();
+
+        while items.len() > CHUNK_MAX_ITEMS {
+            let rest = items.split_off(CHUNK_MAX_ITEMS);
+            leaves.push(ItemChunk { items });
+            items = rest;
+        }
+
+        // Make sure the last leaf is at least half full.
+        if let Some(previous) = leaves.last_mut() {
+            if items.len() < CHUNK_MIN_ITEMS {
+                let moved = CHUNK_MIN_ITEMS - items.len();
+                let split = previous.items.len() - moved;
+                items.splice(..0, previous.items.drain(split..));
+            }
+        }
+
+        leaves.push(ItemChunk { items });
+
+        Self { tree: Tree::from_leaves(leaves) }
+    }
+}
+
+impl<T: Summarize + Clone + PartialEq + 'static> PartialEq for SumRope<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Summarize + Clone + Eq + 'static> Eq for SumRope<T> {}
+
+/// An immutable slice of a [`SumRope`].
+pub struct SumSlice<'a, T: Summarize + Clone + 'static> {
+    slice: TreeSlice<'a, { Rope::arity() }, ItemChunk<T>>,
+}
+
+impl<T: Summarize + Clone + 'static> Clone for SumSlice<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self { slice: self.slice.clone() }
+    }
+}
+
+impl<'a, T: Summarize + Clone + 'static> SumSlice<'a, T> {
+    /// Returns the item at the given index, or `None` if the index is out of
+    /// bounds.
+    #[inline]
+    pub fn get(&self, item_index: usize) -> Option<&'a T> {
+        if item_index >= self.len() {
+            return None;
+        }
+
+        let (slice, ItemCount(offset)) =
+            self.slice.leaf_at_measure(ItemCount(item_index + 1));
+
+        slice.items.get(item_index - offset)
+    }
+
+    /// Returns `true` if the `SumSlice` doesn't contain any items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the items of the `SumSlice`.
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'a T> + 'a {
+        self.slice.leaves().flat_map(|slice| slice.items.iter())
+    }
+
+    /// Returns the number of items in the `SumSlice`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.slice.summary().len
+    }
+
+    /// Returns the `M`-measure of all the items.
+    #[inline]
+    pub fn measure<M>(&self) -> M
+    where
+        M: Metric<T::Summary>,
+    {
+        M::measure(self.summary())
+    }
+
+    /// Returns a sub-slice of the `SumSlice` in the specified item range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start is greater than the end or if the end is out of
+    /// bounds (i.e. greater than [`len()`](Self::len())).
+    #[track_caller]
+    #[inline]
+    pub fn slice<R>(&self, item_range: R) -> Self
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(item_range, 0, self.len());
+
+        if start > end {
+            panic::item_start_after_end(start, end);
+        }
+
+        if end > self.len() {
+            panic::item_offset_out_of_bounds(end, self.len());
+        }
+
+        Self {
+            slice: self.slice.clone().slice(ItemCount(start)..ItemCount(end)),
+        }
+    }
+
+    /// Returns the sum of the summaries of all the items.
+    #[inline]
+    pub fn summary(&self) -> &T::Summary {
+        &self.slice.summary().summary
+    }
+}
+
+impl<T: Summarize + Clone + 'static> core::fmt::Debug for SumSlice<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// The leaves of a [`SumRope`], storing consecutive items.
+#[derive(Clone)]
+struct ItemChunk<T> {
+    items: Vec<T>,
+}
+
+impl<T> Default for ItemChunk<T> {
+    #[inline]
+    fn default() -> Self {
+        Self { items: Vec::new() }
+    }
+}
+
+impl<T: Summarize> core::fmt::Debug for ItemChunk<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<T: Summarize> Summarize for ItemChunk<T> {
+    type Summary = ItemSummary<T::Summary>;
+
+    #[inline]
+    fn summarize(&self) -> Self::Summary {
+        self.as_slice().summarize()
+    }
+}
+
+impl<T: Summarize> BaseMeasured for ItemChunk<T> {
+    type BaseMetric = ItemCount;
+}
+
+impl<T: Summarize> AsSlice for ItemChunk<T> {
+    type Slice<'a>
+        = ItemSlice<'a, T>
+    where
+        T: 'a;
+
+    #[inline]
+    fn as_slice(&self) -> ItemSlice<'_, T> {
+        ItemSlice { items: &self.items }
+    }
+}
+
+impl<T: Summarize + Clone + 'static> BalancedLeaf for ItemChunk<T> {
+    #[inline]
+    fn is_underfilled(&self, summary: &Self::Summary) -> bool {
+        summary.len < CHUNK_MIN_ITEMS
+    }
+
+    #[inline]
+    fn balance_leaves(
+        (left, left_summary): (&mut Self, &mut Self::Summary),
+        (right, right_summary): (&mut Self, &mut Self::Summary),
+    ) {
+        let total = left.items.len() + right.items.len();
+
+        if total <= CHUNK_MAX_ITEMS {
+            left.items.append(&mut right.items);
+        } else if left.items.len() < right.items.len() {
+            let moved = right.items.len() - total / 2;
+            left.items.extend(right.items.drain(..moved));
+        } else {
+            let moved = left.items.len() - total / 2;
+            let split = left.items.len() - moved;
+            right.items.splice(..0, left.items.drain(split..));
+        }
+
+        *left_summary = left.summarize();
+        *right_summary = right.summarize();
+    }
+}
+
+impl<T: Summarize + Clone + 'static> From<ItemSlice<'_, T>> for ItemChunk<T> {
+    #[inline]
+    fn from(slice: ItemSlice<'_, T>) -> Self {
+        Self { items: slice.items.to_vec() }
+    }
+}
+
+impl<T: Summarize + Clone + 'static> ReplaceableLeaf<ItemCount>
+    for ItemChunk<T>
+{
+    type Replacement<'a> = Vec<T>;
+
+    type ExtraLeaves = alloc::vec::IntoIter<Self>;
+
+    #[inline]
+    fn replace<R>(
+        &mut self,
+        summary: &mut Self::Summary,
+        range: R,
+        replacement: Vec<T>,
+    ) -> Option<Self::ExtraLeaves>
+    where
+        R: RangeBounds<ItemCount>,
+    {
+        let (start, end) =
+            range_bounds_to_start_end(range, 0, self.items.len());
+
+        self.items.splice(start..end, replacement);
+
+        let total = self.items.len();
+
+        if total <= CHUNK_MAX_ITEMS {
+            *summary = self.summarize();
+            return None;
+        }
+
+        // Split the items in chunks of roughly the same size, all of which
+        // are guaranteed to be at least half full.
+        let chunks = (total + CHUNK_MAX_ITEMS - 1) / CHUNK_MAX_ITEMS;
+
+        let mut extras = Vec::with_capacity(chunks - 1);
+
+        for idx in (1..chunks).rev() {
+            let split = idx * total / chunks;
+            let items = self.items.split_off(split);
+            extras.push(Self { items });
+        }
+
+        extras.reverse();
+
+        *summary = self.summarize();
+
+        Some(extras.into_iter())
+    }
+
+    #[inline]
+    fn remove_up_to(&mut self, summary: &mut Self::Summary, up_to: ItemCount) {
+        self.items.drain(..up_to.0);
+        *summary = self.summarize();
+    }
+}
+
+struct ItemSlice<'a, T> {
+    items: &'a [T],
+}
+
+impl<T> Clone for ItemSlice<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ItemSlice<'_, T> {}
+
+impl<T> Default for ItemSlice<'_, T> {
+    #[inline]
+    fn default() -> Self {
+        Self { items: &[] }
+    }
+}
+
+impl<T: Summarize> core::fmt::Debug for ItemSlice<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list().entries(self.items).finish()
+    }
+}
+
+impl<T: Summarize> Summarize for ItemSlice<'_, T> {
+    type Summary = ItemSummary<T::Summary>;
+
+    #[inline]
+    fn summarize(&self) -> Self::Summary {
+        ItemSummary {
+            len: self.items.len(),
+            summary: self
+                .items
+                .iter()
+                .fold(T::Summary::default(), |summary, item| {
+                    summary + &item.summarize()
+                }),
+        }
+    }
+}
+
+/// The summary of a sequence of items.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ItemSummary<S> {
+    /// The number of items.
+    len: usize,
+
+    /// The sum of the summaries of the items.
+    summary: S,
+}
+
+impl<S> Add<&Self> for ItemSummary<S>
+where
+    S: for<'a> AddAssign<&'a S>,
+{
+    type Output = Self;
+
+    #[inline]
+    fn add(mut self, rhs: &Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+impl<S> Sub<&Self> for ItemSummary<S>
+where
+    S: for<'a> SubAssign<&'a S>,
+{
+    type Output = Self;
+
+    #[inline]
+    fn sub(mut self, rhs: &Self) -> Self {
+        self -= rhs;
+        self
+    }
+}
+
+impl<S> AddAssign<&Self> for ItemSummary<S>
+where
+    S: for<'a> AddAssign<&'a S>,
+{
+    #[inline]
+    fn add_assign(&mut self, rhs: &Self) {
+        self.len += rhs.len;
+        self.summary += &rhs.summary;
+    }
+}
+
+impl<S> SubAssign<&Self> for ItemSummary<S>
+where
+    S: for<'a> SubAssign<&'a S>,
+{
+    #[inline]
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.len -= rhs.len;
+        self.summary -= &rhs.summary;
+    }
+}
+
+/// A metric counting the number of items.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct ItemCount(usize);
+
+impl Add for ItemCount {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl Sub for ItemCount {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl AddAssign for ItemCount {
+    #[inline]
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0
+    }
+}
+
+impl SubAssign for ItemCount {
+    #[inline]
+    fn sub_assign(&mut self, other: Self) {
+        self.0 -= other.0
+    }
+}
+
+impl Add<usize> for ItemCount {
+    type Output = usize;
+
+    #[inline]
+    fn add(self, other: usize) -> usize {
+        self.0 + other
+    }
+}
+
+impl From<ItemCount> for usize {
+    #[inline]
+    fn from(ItemCount(count): ItemCount) -> usize {
+        count
+    }
+}
+
+impl<S> Metric<ItemSummary<S>> for ItemCount {
+    #[inline]
+    fn zero() -> Self {
+        Self(0)
+    }
+
+    #[inline]
+    fn one() -> Self {
+        Self(1)
+    }
+
+    #[inline]
+    fn measure(summary: &ItemSummary<S>) -> Self {
+        Self(summary.len)
+    }
+}
+
+impl<T: Summarize + Clone + 'static> SlicingMetric<ItemChunk<T>>
+    for ItemCount
+{
+    #[inline]
+    fn slice_up_to<'a>(
+        slice: <ItemChunk<T> as AsSlice>::Slice<'a>,
+        ItemCount(up_to): Self,
+        _: &ItemSummary<T::Summary>,
+    ) -> (ItemSlice<'a, T>, ItemSummary<T::Summary>) {
+        let left = ItemSlice { items: &slice.items[..up_to] };
+        (left, left.summarize())
+    }
+
+    #[inline]
+    fn slice_from<'a>(
+        slice: <ItemChunk<T> as AsSlice>::Slice<'a>,
+        ItemCount(from): Self,
+        _: &ItemSummary<T::Summary>,
+    ) -> (ItemSlice<'a, T>, ItemSummary<T::Summary>) {
+        let right = ItemSlice { items: &slice.items[from..] };
+        (right, right.summarize())
+    }
+}
@@ -0,0 +1,44 @@
+use super::metrics::{widen, Count};
+
+/// The maximum number of bytes of text a [`Rope`](crate::Rope) can hold,
+/// which is 4 GiB with the `compact-summaries` feature.
+pub(super) const MAX_BYTE_LEN: usize = widen(Count::MAX);
+
+/// The error returned by the `try_` variants of the methods of
+/// [`Rope`](crate::Rope) and [`RopeBuilder`](crate::RopeBuilder) when the
+/// text they'd add would make the `Rope` longer than
+/// [`Rope::MAX_BYTE_LEN`](crate::Rope::MAX_BYTE_LEN).
+///
+/// The `Rope` or the `RopeBuilder` is left untouched when this is returned.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TooLongError;
+
+impl TooLongError {
+    /// Returns an error if replacing `removed_len` bytes of a text of
+    /// `byte_len` bytes with `inserted_len` bytes would make it longer than
+    /// [`MAX_BYTE_LEN`].
+    #[allow(clippy::absurd_extreme_comparisons)]
+    #[inline]
+    pub(super) fn check(
+        byte_len: usize,
+        removed_len: usize,
+        inserted_len: usize,
+    ) -> Result<(), Self> {
+        // Without the `compact-summaries` feature the maximum is
+        // `usize::MAX`, so only the addition can fail.
+        match (byte_len - removed_len).checked_add(inserted_len) {
+            Some(len) if len <= MAX_BYTE_LEN => Ok(()),
+            _ => Err(Self),
+        }
+    }
+}
+
+impl core::fmt::Display for TooLongError {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "a rope can't hold more than {MAX_BYTE_LEN} bytes of text")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TooLongError {}
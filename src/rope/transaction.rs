@@ -0,0 +1,342 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use super::{Delta, EditInfo, Rope};
+
+/// A single operation of a [`Transaction`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Keeps the given number of bytes unchanged.
+    Retain(usize),
+
+    /// Deletes the given number of bytes.
+    Delete(usize),
+
+    /// Inserts some text.
+    Insert(String),
+}
+
+/// The error returned by [`Transaction::apply()`] when a transaction can't
+/// be applied to a [`Rope`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionError {
+    /// The transaction was built for a `Rope` with a different length.
+    LengthMismatch {
+        /// The byte length of the `Rope` the transaction was built for.
+        expected: usize,
+
+        /// The byte length of the `Rope` it was applied to.
+        actual: usize,
+    },
+
+    /// One of the operations starts or ends at the given byte offset, which
+    /// isn't a code point boundary of the `Rope`.
+    NotCharBoundary(usize),
+
+    /// The `Rope` has a [`limit()`](Rope::limit()), which could delete text
+    /// from its start in the middle of the transaction and make its inverse
+    /// wrong.
+    RopeHasLimit,
+}
+
+impl core::fmt::Display for TransactionError {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "the transaction was built for a rope of {expected} bytes \
+                 but the rope has {actual} bytes"
+            ),
+            Self::NotCharBoundary(offset) => {
+                write!(f, "byte offset {offset} is not a char boundary")
+            },
+            Self::RopeHasLimit => {
+                f.write_str("the rope has a limit on its length")
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TransactionError {}
+
+/// A compound edit described as a sequence of [`Operation`]s which walk the
+/// text of a [`Rope`] from start to end, retaining, deleting or inserting
+/// text along the way.
+///
+/// A transaction is built for a `Rope` of a given length, and any text after
+/// the last retained or deleted byte is implicitly retained. It's validated
+/// as a whole before being [`apply()`](Self::apply())ed, so either all of its
+/// operations are performed or none is, and applying it returns the inverse
+/// transaction which undoes it.
+///
+/// # Examples
+///
+/// ```
+/// # use crop::transaction::Transaction;
+/// # use crop::Rope;
+/// #
+/// let mut r = Rope::from("Hello World");
+///
+/// let mut t = Transaction::new(&r);
+/// t.retain(5).insert(",").retain(1).delete(5).insert("Rust");
+///
+/// let inverse = t.apply(&mut r).unwrap();
+/// assert_eq!(r, "Hello, Rust");
+///
+/// inverse.apply(&mut r).unwrap();
+/// assert_eq!(r, "Hello World");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Transaction {
+    operations: Vec<Operation>,
+
+    /// The byte length of the `Rope` the transaction is built for.
+    len_before: usize,
+
+    /// The number of bytes of that `Rope` which have been retained or
+    /// deleted.
+    consumed: usize,
+}
+
+impl Transaction {
+    /// Applies the transaction to the `Rope`, returning its inverse.
+    ///
+    /// The operations are performed as a single batch of edits through
+    /// [`Rope::apply_edits()`], so the revision of the `Rope` only changes
+    /// once and its marks and subscribers are only updated after the whole
+    /// transaction has been applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error without modifying the `Rope` if its length is
+    /// different from the one of the `Rope` the transaction was built for,
+    /// if any of the operations starts or ends in the middle of a code
+    /// point, or if the `Rope` has a [`limit()`](Rope::limit()).
+    #[inline]
+    pub fn apply(
+        &self,
+        rope: &mut Rope,
+    ) -> Result<Transaction, TransactionError> {
+        if rope.limit().is_some() {
+            return Err(TransactionError::RopeHasLimit);
+        }
+
+        if rope.byte_len() != self.len_before {
+            return Err(TransactionError::LengthMismatch {
+                expected: self.len_before,
+                actual: rope.byte_len(),
+            });
+        }
+
+        let mut inverse = Transaction {
+            operations: Vec::new(),
+            len_before: self.len_after(),
+            consumed: 0,
+        };
+
+        let mut offset = 0;
+
+        for operation in &self.operations {
+            match operation {
+                Operation::Retain(len) => {
+                    offset += len;
+                    inverse.retain(*len);
+                },
+                Operation::Delete(len) => {
+                    let deleted = offset..offset + len;
+
+                    for offset in [deleted.start, deleted.end] {
+                        if !rope.is_char_boundary(offset) {
+                            return Err(TransactionError::NotCharBoundary(
+                                offset,
+                            ));
+                        }
+                    }
+
+                    inverse.insert(rope.byte_slice(deleted).to_string());
+                    offset += len;
+                },
+                Operation::Insert(text) => {
+                    if !rope.is_char_boundary(offset) {
+                        return Err(TransactionError::NotCharBoundary(offset));
+                    }
+
+                    inverse.delete(text.len());
+                },
+            }
+        }
+
+        rope.apply_edits(&self.edits().collect::<Vec<_>>());
+
+        Ok(inverse)
+    }
+
+    /// Advances past the given number of bytes of the `Rope` the transaction
+    /// is built for.
+    #[track_caller]
+    #[inline]
+    fn consume(&mut self, byte_len: usize) {
+        assert!(
+            self.consumed + byte_len <= self.len_before,
+            "byte offset out of bounds: the transaction is built for a rope \
+             of {} bytes but the offset is {}",
+            self.len_before,
+            self.consumed + byte_len
+        );
+
+        self.consumed += byte_len;
+    }
+
+    /// Deletes the given number of bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this goes past the end of the `Rope` the transaction was
+    /// built for.
+    #[track_caller]
+    #[inline]
+    pub fn delete(&mut self, byte_len: usize) -> &mut Self {
+        self.consume(byte_len);
+
+        if byte_len > 0 {
+            match self.operations.last_mut() {
+                Some(Operation::Delete(len)) => *len += byte_len,
+                _ => self.operations.push(Operation::Delete(byte_len)),
+            }
+        }
+
+        self
+    }
+
+    /// Returns the edits performed by the transaction as a [`Delta`].
+    #[inline]
+    pub fn delta(&self) -> Delta {
+        // The difference between the bytes inserted and the ones deleted by
+        // the edits before the current one.
+        let (mut inserted, mut deleted) = (0, 0);
+
+        self.edits()
+            .map(|(byte_range, text)| {
+                let start = byte_range.start + inserted - deleted;
+                inserted += text.len();
+                deleted += byte_range.len();
+                EditInfo {
+                    byte_range: start..start + byte_range.len(),
+                    inserted_len: text.len(),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns an iterator over the byte ranges replaced by the transaction
+    /// and the text they're replaced with, sorted and relative to the text
+    /// as it is before the transaction.
+    ///
+    /// A deletion directly followed by an insertion is turned into a single
+    /// replacement.
+    #[inline]
+    fn edits(&self) -> impl Iterator<Item = (Range<usize>, &str)> + '_ {
+        let mut operations = self.operations.iter().peekable();
+        let mut offset = 0;
+
+        core::iter::from_fn(move || loop {
+            let (deleted, inserted) = match operations.next()? {
+                Operation::Retain(len) => {
+                    offset += len;
+                    continue;
+                },
+                Operation::Delete(len) => {
+                    let inserted = match operations
+                        .next_if(|op| matches!(op, Operation::Insert(_)))
+                    {
+                        Some(Operation::Insert(text)) => text.as_str(),
+                        _ => "",
+                    };
+                    (*len, inserted)
+                },
+                Operation::Insert(text) => (0, text.as_str()),
+            };
+
+            let start = offset;
+            offset += deleted;
+            return Some((start..offset, inserted));
+        })
+    }
+
+    /// Inserts some text.
+    #[inline]
+    pub fn insert<T>(&mut self, text: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        let text = text.into();
+
+        if !text.is_empty() {
+            match self.operations.last_mut() {
+                Some(Operation::Insert(inserted)) => inserted.push_str(&text),
+                _ => self.operations.push(Operation::Insert(text)),
+            }
+        }
+
+        self
+    }
+
+    /// Returns the byte length of the `Rope` after applying the transaction.
+    #[inline]
+    pub fn len_after(&self) -> usize {
+        self.operations.iter().fold(self.len_before, |len, operation| {
+            match operation {
+                Operation::Retain(_) => len,
+                Operation::Delete(deleted) => len - deleted,
+                Operation::Insert(text) => len + text.len(),
+            }
+        })
+    }
+
+    /// Returns the byte length of the `Rope` the transaction is built for.
+    #[inline]
+    pub fn len_before(&self) -> usize {
+        self.len_before
+    }
+
+    /// Creates a new, empty `Transaction` for the given `Rope`.
+    #[inline]
+    pub fn new(rope: &Rope) -> Self {
+        Self {
+            operations: Vec::new(),
+            len_before: rope.byte_len(),
+            consumed: 0,
+        }
+    }
+
+    /// Returns the operations of the transaction. Consecutive operations of
+    /// the same kind are merged, and empty ones are skipped.
+    #[inline]
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Keeps the given number of bytes unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this goes past the end of the `Rope` the transaction was
+    /// built for.
+    #[track_caller]
+    #[inline]
+    pub fn retain(&mut self, byte_len: usize) -> &mut Self {
+        self.consume(byte_len);
+
+        if byte_len > 0 {
+            match self.operations.last_mut() {
+                Some(Operation::Retain(len)) => *len += byte_len,
+                _ => self.operations.push(Operation::Retain(byte_len)),
+            }
+        }
+
+        self
+    }
+}
@@ -0,0 +1,82 @@
+//! The line breaks counted by the line metric when the `unicode-lines`
+//! feature is enabled.
+//!
+//! Every character that's a mandatory line break according to Unicode is
+//! counted, except for the carriage return: line feeds, vertical tabs, form
+//! feeds, next lines (U+0085), line separators (U+2028) and paragraph
+//! separators (U+2029). A CRLF is still a single line break since it's
+//! counted by its line feed.
+//!
+//! The line breaks of a chunk are stored in its `ChunkSummary` and added up
+//! like every other count, which only works if every line break is a single
+//! character. A lone carriage return would also be a line break, but a CRLF
+//! can be split in two across a chunk boundary, and the chunk ending with the
+//! carriage return can't know whether the next one starts with a line feed.
+
+/// Returns `true` if the character is a line break.
+#[inline]
+pub(super) fn is_line_break(ch: char) -> bool {
+    matches!(
+        ch,
+        '\n' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// Returns the number of line breaks in `s`.
+#[inline]
+pub(super) fn count_line_breaks(s: &str) -> usize {
+    let bytes = s.as_bytes();
+
+    if s.is_ascii() {
+        return bytes.iter().filter(|&&b| is_ascii_line_break(b)).count();
+    }
+
+    (0..bytes.len()).filter(|&idx| ends_line_break(bytes, idx)).count()
+}
+
+/// Returns the byte offset of the start of the given line, or the length of
+/// `s` if it has fewer line breaks than `line_offset`.
+#[inline]
+pub(super) fn byte_of_line(s: &str, line_offset: usize) -> usize {
+    if line_offset == 0 {
+        return 0;
+    }
+
+    let bytes = s.as_bytes();
+
+    let mut seen = 0;
+
+    for idx in 0..bytes.len() {
+        if ends_line_break(bytes, idx) {
+            seen += 1;
+
+            if seen == line_offset {
+                return idx + 1;
+            }
+        }
+    }
+
+    bytes.len()
+}
+
+#[inline(always)]
+fn is_ascii_line_break(byte: u8) -> bool {
+    matches!(byte, b'\n' | 0x0B | 0x0C)
+}
+
+/// Returns `true` if the byte at `idx` is the last byte of a line break.
+///
+/// The non-ASCII line breaks are encoded as `C2 85` (NEL), `E2 80 A8` (LS)
+/// and `E2 80 A9` (PS). The lead bytes can only start a character, so
+/// looking at the bytes before `idx` is enough to tell them apart from the
+/// continuation bytes of other characters.
+#[inline(always)]
+fn ends_line_break(bytes: &[u8], idx: usize) -> bool {
+    match bytes[idx] {
+        0x85 => idx >= 1 && bytes[idx - 1] == 0xC2,
+        0xA8 | 0xA9 => {
+            idx >= 2 && bytes[idx - 2] == 0xE2 && bytes[idx - 1] == 0x80
+        },
+        byte => is_ascii_line_break(byte),
+    }
+}
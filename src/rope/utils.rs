@@ -1,8 +1,6 @@
 //! This module contains utility functions on strings and code to be shared
 //! between `Rope`s and `RopeSlice`s, `RopeChunk`s and `ChunkSlice`s.
 
-use super::iterators::Chunks;
-
 /// Adjusts the candidate byte offset to make sure it's a char boundary for
 /// `s`. Offsets past the end of the string will be clipped to the length of
 /// the string.
@@ -37,16 +35,16 @@ pub(super) fn adjust_split_point<const WITH_RIGHT_BIAS: bool>(
     offset
 }
 
-/// Checks equality between the chunks yielded by iterating over two
-/// [`Chunks`].
+/// Checks equality between the text yielded by two iterators over the chunks
+/// of `Rope`s or `RopeSlice`s.
 ///
 /// This is used in the `PartialEq` implementation between `Rope`s and
 /// `RopeSlice`s. It's assumed that if we get this far both chunks yield the
 /// same number of bytes.
 #[inline]
-pub(super) fn chunks_eq_chunks(
-    mut lhs: Chunks<'_>,
-    mut rhs: Chunks<'_>,
+pub(super) fn chunks_eq_chunks<'a, 'b>(
+    mut lhs: impl Iterator<Item = &'a str>,
+    mut rhs: impl Iterator<Item = &'b str>,
 ) -> bool {
     let mut left_chunk = lhs.next().unwrap_or("").as_bytes();
     let mut right_chunk = rhs.next().unwrap_or("").as_bytes();
@@ -88,14 +86,17 @@ pub(super) fn chunks_eq_chunks(
     }
 }
 
-/// Checks equality between the chunks yielded by iterating over a [`Chunks`]
-/// and a string slice.
+/// Checks equality between the text yielded by an iterator over the chunks
+/// of a `Rope` or `RopeSlice` and a string slice.
 ///
 /// This is used in the `PartialEq` implementation between `Rope`/`RopeSlice`s
 /// and strings. It's assumed that if we get this far `chunks` and `s` have the
 /// same number of bytes.
 #[inline]
-pub(super) fn chunks_eq_str(chunks: Chunks<'_>, s: &str) -> bool {
+pub(super) fn chunks_eq_str<'a>(
+    chunks: impl Iterator<Item = &'a str>,
+    s: &str,
+) -> bool {
     let s = s.as_bytes();
     let mut checked = 0;
     for chunk in chunks {
@@ -107,11 +108,98 @@ pub(super) fn chunks_eq_str(chunks: Chunks<'_>, s: &str) -> bool {
     true
 }
 
-/// Iterates over the string slices yielded by [`Chunks`], writing the debug
-/// output of each chunk to a formatter.
+/// Returns the number of words in the text made by concatenating the
+/// chunks, where a word is a maximal sequence of non-whitespace characters.
+#[cfg(feature = "word-metric")]
+#[inline]
+pub(super) fn count_words<'a, C>(chunks: C) -> usize
+where
+    C: IntoIterator<Item = &'a str>,
+{
+    let mut words = 0;
+    let mut in_word = false;
+
+    for chunk in chunks {
+        for ch in chunk.chars() {
+            let is_word_char = !ch.is_whitespace();
+            words += (is_word_char && !in_word) as usize;
+            in_word = is_word_char;
+        }
+    }
+
+    words
+}
+
+/// Returns `true` if `s` ends with a character the line metric counts as a
+/// line break.
+#[inline]
+pub(super) fn ends_with_line_break(s: &str) -> bool {
+    s.chars().next_back().map_or(false, is_line_break)
+}
+
+/// Returns `true` if the line metric counts the character as a line break,
+/// which is only the case for line feeds unless the `unicode-lines` feature
+/// is enabled.
+#[inline]
+pub(super) fn is_line_break(ch: char) -> bool {
+    #[cfg(feature = "unicode-lines")]
+    {
+        super::unicode_lines::is_line_break(ch)
+    }
+
+    #[cfg(not(feature = "unicode-lines"))]
+    {
+        ch == '\n'
+    }
+}
+
+/// Feeds the text yielded by an iterator over chunks to a hasher in the same
+/// way regardless of how it's split into chunks.
+///
+/// Hashers are not required to produce the same hash if the same bytes are
+/// written with different calls to [`Hasher::write()`], so we copy the text
+/// in fixed-size blocks before writing it.
+///
+/// [`Hasher::write()`]: core::hash::Hasher::write()
+#[inline]
+pub(super) fn hash_chunks<'a, H: core::hash::Hasher>(
+    chunks: impl Iterator<Item = &'a str>,
+    state: &mut H,
+) {
+    const BLOCK_SIZE: usize = 256;
+
+    let mut block = [0; BLOCK_SIZE];
+    let mut block_len = 0;
+
+    for chunk in chunks {
+        let mut bytes = chunk.as_bytes();
+
+        while !bytes.is_empty() {
+            let copied = bytes.len().min(BLOCK_SIZE - block_len);
+            block[block_len..block_len + copied]
+                .copy_from_slice(&bytes[..copied]);
+            block_len += copied;
+            bytes = &bytes[copied..];
+
+            if block_len == BLOCK_SIZE {
+                state.write(&block);
+                block_len = 0;
+            }
+        }
+    }
+
+    state.write(&block[..block_len]);
+
+    // Like `str`s, terminate the text with a byte that can't appear in valid
+    // UTF-8 so that e.g. `("ab", "c")` and `("a", "bc")` hash differently.
+    state.write_u8(0xff);
+}
+
+/// Iterates over the given chunks, writing the debug output of each one to a
+/// formatter.
 #[inline]
-pub(super) fn debug_chunks(
-    chunks: Chunks<'_>,
+pub(super) fn debug_chunks<'a>(
+    chunks: impl Iterator<Item = &'a str>,
     f: &mut core::fmt::Formatter<'_>,
 ) -> core::fmt::Result {
     for chunk in chunks {
@@ -149,8 +237,8 @@ pub(super) fn debug_no_quotes(
 /// constructed by concatenating the chunks yielded by `chunks`.
 #[cfg(feature = "graphemes")]
 #[inline]
-pub(super) fn is_grapheme_boundary(
-    mut chunks: Chunks<'_>,
+pub(super) fn is_grapheme_boundary<'a>(
+    mut chunks: impl DoubleEndedIterator<Item = &'a str>,
     byte_len: usize,
     byte_offset: usize,
 ) -> bool {
@@ -198,6 +286,53 @@ pub(super) fn is_grapheme_boundary(
     }
 }
 
+/// Returns the length of the longest prefix of `bytes` which doesn't end in
+/// the middle of a code point, judging only by the first byte of the last
+/// one. The prefix isn't validated, so it can still be invalid UTF-8.
+#[inline]
+pub(super) fn complete_utf8_len(bytes: &[u8]) -> usize {
+    // A truncated code point is at most 3 bytes long.
+    let last_start = (bytes.len().saturating_sub(3)..bytes.len())
+        .rev()
+        .find(|&idx| bytes[idx] & 0xc0 != 0x80);
+
+    let Some(start) = last_start else { return bytes.len() };
+
+    let code_point_len = match bytes[start] {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    };
+
+    if start + code_point_len > bytes.len() {
+        start
+    } else {
+        bytes.len()
+    }
+}
+
+/// Validates `bytes` as UTF-8.
+///
+/// If the `simdutf8` feature is enabled the bytes are first validated with
+/// `simdutf8`'s fast validator, which doesn't tell where the error is, and
+/// only invalid inputs are validated again to get a [`Utf8Error`].
+///
+/// [`Utf8Error`]: core::str::Utf8Error
+#[inline]
+pub(super) fn from_utf8(bytes: &[u8]) -> Result<&str, core::str::Utf8Error> {
+    #[cfg(feature = "simdutf8")]
+    {
+        simdutf8::basic::from_utf8(bytes)
+            .or_else(|_| core::str::from_utf8(bytes))
+    }
+
+    #[cfg(not(feature = "simdutf8"))]
+    {
+        core::str::from_utf8(bytes)
+    }
+}
+
 #[inline]
 pub(super) fn split_adjusted<const WITH_RIGHT_BIAS: bool>(
     s: &str,
@@ -269,6 +404,21 @@ pub mod panic_messages {
         );
     }
 
+    #[track_caller]
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn byte_ranges_overlap(
+        previous_end: usize,
+        byte_start: usize,
+    ) -> ! {
+        debug_assert!(byte_start < previous_end);
+
+        panic!(
+            "byte ranges overlap: the start is {byte_start} but the previous \
+             range ends at {previous_end}"
+        );
+    }
+
     #[track_caller]
     #[cold]
     #[inline(never)]
@@ -284,6 +434,52 @@ pub mod panic_messages {
         );
     }
 
+    #[track_caller]
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn item_index_out_of_bounds(
+        item_index: usize,
+        item_len: usize,
+    ) -> ! {
+        debug_assert!(item_index >= item_len);
+
+        panic!(
+            "item index out of bounds: the index is {item_index} but the \
+             length is {item_len}"
+        );
+    }
+
+    #[track_caller]
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn item_offset_out_of_bounds(
+        item_offset: usize,
+        item_len: usize,
+    ) -> ! {
+        debug_assert!(item_offset > item_len);
+
+        panic!(
+            "item offset out of bounds: the offset is {item_offset} but the \
+             length is {item_len}"
+        );
+    }
+
+    #[track_caller]
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn item_start_after_end(
+        item_start: usize,
+        item_end: usize,
+    ) -> ! {
+        debug_assert!(item_start > item_end);
+
+        panic!(
+            "item start after end: the start is {item_start} but the end is \
+             {item_end}"
+        );
+    }
+
+    #[cfg(any(feature = "line-metric", feature = "rkyv"))]
     #[track_caller]
     #[cold]
     #[inline(never)]
@@ -299,6 +495,7 @@ pub mod panic_messages {
         );
     }
 
+    #[cfg(any(feature = "line-metric", feature = "rkyv"))]
     #[track_caller]
     #[cold]
     #[inline(never)]
@@ -314,6 +511,7 @@ pub mod panic_messages {
         );
     }
 
+    #[cfg(feature = "line-metric")]
     #[track_caller]
     #[cold]
     #[inline(never)]
@@ -329,6 +527,68 @@ pub mod panic_messages {
         );
     }
 
+    #[track_caller]
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn record_index_out_of_bounds(
+        record_index: usize,
+        record_len: usize,
+    ) -> ! {
+        debug_assert!(record_index >= record_len);
+
+        panic!(
+            "record index out of bounds: the index is {record_index} but the \
+             length is {record_len}"
+        );
+    }
+
+    #[track_caller]
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn record_offset_out_of_bounds(
+        record_offset: usize,
+        record_len: usize,
+    ) -> ! {
+        debug_assert!(record_offset > record_len);
+
+        panic!(
+            "record offset out of bounds: the offset is {record_offset} but \
+             the length is {record_len}"
+        );
+    }
+
+    #[track_caller]
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn record_start_after_end(
+        record_start: usize,
+        record_end: usize,
+    ) -> ! {
+        debug_assert!(record_start > record_end);
+
+        panic!(
+            "record start after end: the start is {record_start} but the end \
+             is {record_end}"
+        );
+    }
+
+    #[track_caller]
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn rope_has_limit(wrapper: &str) -> ! {
+        panic!(
+            "a {wrapper} can't wrap a Rope with a limit: it wouldn't see the \
+             text deleted from the start of the Rope to enforce it"
+        );
+    }
+
+    #[track_caller]
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn rope_too_long() -> ! {
+        panic!("{}", crate::TooLongError);
+    }
+
     #[cfg(feature = "utf16-metric")]
     #[track_caller]
     #[cold]
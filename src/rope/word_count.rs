@@ -0,0 +1,166 @@
+//! The word count returned by [`Rope::word_len()`].
+//!
+//! A word is a maximal sequence of non-whitespace characters, so the words
+//! of two pieces of text can't just be added up: a word ending one piece and
+//! one starting the other are the same word in the concatenation. With the
+//! `word-metric` feature enabled every `ChunkSummary` stores the number of
+//! words in its text together with whether the text starts and ends with a
+//! word character, which is all that's needed to count the words of the
+//! concatenation.
+//!
+//! Like the content hash, the count of the difference of two summaries can't
+//! be computed without knowing the text around the subtracted one, so it's
+//! marked as stale and recomputed after every edit. Splitting a chunk knows
+//! the character at the split point, so the chunks and slices of a `Rope`
+//! always have an up to date count.
+//!
+//! [`Rope::word_len()`]: super::Rope::word_len()
+
+use super::metrics::{narrow, widen, Count};
+
+/// How a piece of text starts or ends.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+enum Edge {
+    /// The text is empty.
+    #[default]
+    Empty,
+
+    /// The text starts or ends with a whitespace character.
+    Space,
+
+    /// The text starts or ends with a word character.
+    Word,
+
+    /// The count has to be recomputed before it can be used.
+    Stale,
+}
+
+impl From<char> for Edge {
+    #[inline]
+    fn from(ch: char) -> Self {
+        if ch.is_whitespace() {
+            Self::Space
+        } else {
+            Self::Word
+        }
+    }
+}
+
+/// The number of words in a piece of text, together with how it starts and
+/// ends.
+///
+/// A stale count compares equal to any other count, so that summaries
+/// storing them can still be compared by their other counts.
+#[derive(Copy, Clone, Debug, Default)]
+pub(super) struct WordCount {
+    words: Count,
+    first: Edge,
+    last: Edge,
+}
+
+impl From<&str> for WordCount {
+    #[inline]
+    fn from(s: &str) -> Self {
+        let mut words = 0;
+        let mut last = Edge::Empty;
+
+        for ch in s.chars() {
+            let edge = Edge::from(ch);
+            words += (edge == Edge::Word && last != Edge::Word) as usize;
+            last = edge;
+        }
+
+        Self {
+            words: narrow(words),
+            first: s.chars().next().map_or(Edge::Empty, Edge::from),
+            last,
+        }
+    }
+}
+
+impl From<char> for WordCount {
+    #[inline]
+    fn from(ch: char) -> Self {
+        let edge = Edge::from(ch);
+        Self { words: (edge == Edge::Word) as Count, first: edge, last: edge }
+    }
+}
+
+impl PartialEq for WordCount {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.is_stale()
+            || other.is_stale()
+            || (self.words == other.words
+                && self.first == other.first
+                && self.last == other.last)
+    }
+}
+
+impl WordCount {
+    /// A count that has to be recomputed before it can be used.
+    pub(super) const STALE: Self =
+        Self { words: 0, first: Edge::Stale, last: Edge::Stale };
+
+    /// Returns the count of the text made by appending `rhs`'s text to
+    /// `self`'s, which is stale if either of them is.
+    #[inline]
+    pub(super) fn concat(self, rhs: Self) -> Self {
+        if self.is_stale() || rhs.is_stale() {
+            return Self::STALE;
+        }
+
+        if self.first == Edge::Empty {
+            return rhs;
+        }
+
+        if rhs.first == Edge::Empty {
+            return self;
+        }
+
+        let joined = self.last == Edge::Word && rhs.first == Edge::Word;
+
+        Self {
+            words: self.words + rhs.words - joined as Count,
+            first: self.first,
+            last: rhs.last,
+        }
+    }
+
+    #[inline]
+    pub(super) fn is_stale(&self) -> bool {
+        self.first == Edge::Stale
+    }
+
+    /// Returns the count of the text left by removing `prefix`'s text from
+    /// the start of `self`'s, where `next` is the first character after the
+    /// prefix, or `None` if there isn't one.
+    #[inline]
+    pub(super) fn strip_prefix(
+        self,
+        prefix: Self,
+        next: Option<char>,
+    ) -> Self {
+        if self.is_stale() || prefix.is_stale() {
+            return Self::STALE;
+        }
+
+        let Some(next) = next else { return Self::default() };
+
+        let first = Edge::from(next);
+
+        let joined = prefix.last == Edge::Word && first == Edge::Word;
+
+        Self {
+            words: self.words - prefix.words + joined as Count,
+            first,
+            last: self.last,
+        }
+    }
+
+    #[inline]
+    pub(super) fn words(&self) -> usize {
+        debug_assert!(!self.is_stale());
+        widen(self.words)
+    }
+}
@@ -1,17 +1,27 @@
 use alloc::vec::Vec;
 
-use super::{Arc, Inode, Leaf, Metric, Node, Tree, TreeSlice};
+use super::{
+    ArcNode,
+    AtomicCount,
+    Inode,
+    Leaf,
+    Metric,
+    Node,
+    RefCount,
+    Tree,
+    TreeSlice,
+};
 
 /// An iterator over the leaves of `Tree`s and `TreeSlice`s.
 //
 // This iterator is implemented using two independent iterators advancing in
 // opposite directions.
-pub struct Leaves<'a, const ARITY: usize, L: Leaf> {
+pub struct Leaves<'a, const ARITY: usize, L: Leaf, C: RefCount = AtomicCount> {
     /// Iterates over the leaves from front to back.
-    forward: LeavesForward<'a, ARITY, L>,
+    forward: LeavesForward<'a, ARITY, L, C>,
 
     /// Iterates over the leaves from back to front.
-    backward: LeavesBackward<'a, ARITY, L>,
+    backward: LeavesBackward<'a, ARITY, L, C>,
 
     /// The number of leaves that have been yielded so far.
     leaves_yielded: usize,
@@ -20,7 +30,9 @@ pub struct Leaves<'a, const ARITY: usize, L: Leaf> {
     leaves_total: usize,
 }
 
-impl<const ARITY: usize, L: Leaf> Clone for Leaves<'_, ARITY, L> {
+impl<const ARITY: usize, L: Leaf, C: RefCount> Clone
+    for Leaves<'_, ARITY, L, C>
+{
     #[inline]
     fn clone(&self) -> Self {
         Self {
@@ -31,11 +43,11 @@ impl<const ARITY: usize, L: Leaf> Clone for Leaves<'_, ARITY, L> {
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf> From<&'a Tree<ARITY, L>>
-    for Leaves<'a, ARITY, L>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount> From<&'a Tree<ARITY, L, C>>
+    for Leaves<'a, ARITY, L, C>
 {
     #[inline]
-    fn from(tree: &'a Tree<ARITY, L>) -> Leaves<'a, ARITY, L> {
+    fn from(tree: &'a Tree<ARITY, L, C>) -> Leaves<'a, ARITY, L, C> {
         Self {
             forward: LeavesForward::from(tree),
             backward: LeavesBackward::from(tree),
@@ -45,11 +57,11 @@ impl<'a, const ARITY: usize, L: Leaf> From<&'a Tree<ARITY, L>>
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf> From<&TreeSlice<'a, ARITY, L>>
-    for Leaves<'a, ARITY, L>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount>
+    From<&TreeSlice<'a, ARITY, L, C>> for Leaves<'a, ARITY, L, C>
 {
     #[inline]
-    fn from(slice: &TreeSlice<'a, ARITY, L>) -> Leaves<'a, ARITY, L> {
+    fn from(slice: &TreeSlice<'a, ARITY, L, C>) -> Leaves<'a, ARITY, L, C> {
         Self {
             forward: LeavesForward::from(slice),
             backward: LeavesBackward::from(slice),
@@ -59,7 +71,9 @@ impl<'a, const ARITY: usize, L: Leaf> From<&TreeSlice<'a, ARITY, L>>
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf> Iterator for Leaves<'a, ARITY, L> {
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount> Iterator
+    for Leaves<'a, ARITY, L, C>
+{
     type Item = L::Slice<'a>;
 
     #[inline]
@@ -79,8 +93,8 @@ impl<'a, const ARITY: usize, L: Leaf> Iterator for Leaves<'a, ARITY, L> {
     }
 }
 
-impl<const ARITY: usize, L: Leaf> DoubleEndedIterator
-    for Leaves<'_, ARITY, L>
+impl<const ARITY: usize, L: Leaf, C: RefCount> DoubleEndedIterator
+    for Leaves<'_, ARITY, L, C>
 {
     #[inline]
     fn next_back(&mut self) -> Option<Self::Item> {
@@ -93,35 +107,37 @@ impl<const ARITY: usize, L: Leaf> DoubleEndedIterator
     }
 }
 
-impl<const ARITY: usize, L: Leaf> ExactSizeIterator for Leaves<'_, ARITY, L> {
+impl<const ARITY: usize, L: Leaf, C: RefCount> ExactSizeIterator
+    for Leaves<'_, ARITY, L, C>
+{
     #[inline]
     fn len(&self) -> usize {
         self.leaves_total - self.leaves_yielded
     }
 }
 
-impl<const ARITY: usize, L: Leaf> core::iter::FusedIterator
-    for Leaves<'_, ARITY, L>
+impl<const ARITY: usize, L: Leaf, C: RefCount> core::iter::FusedIterator
+    for Leaves<'_, ARITY, L, C>
 {
 }
 
 #[derive(Debug)]
-struct LeavesForward<'a, const N: usize, L: Leaf> {
+struct LeavesForward<'a, const N: usize, L: Leaf, C: RefCount> {
     /// Whether `Self` has been initialized by calling
     /// [`initialize`](Self::initialize()).
     is_initialized: bool,
 
     /// The root of the `Tree` or `TreeSlice` we're iterating over.
-    root: &'a Node<N, L>,
+    root: &'a Node<N, L, C>,
 
     /// The path from the root down to (but not including) the internal node
     /// containing `leaves`. It follows that the depth of the last node (if
     /// there is one) is 2.
-    path: Vec<(&'a Inode<N, L>, usize)>,
+    path: Vec<(&'a Inode<N, L, C>, usize)>,
 
     /// The current leaves. All the nodes in the slice are guaranteed to be
     /// leaf nodes.
-    leaves: &'a [Arc<Node<N, L>>],
+    leaves: &'a [ArcNode<N, L, C>],
 
     /// The index of the next leaf in [`leaves`](Self::leaves) that'll be
     /// yielded by [`next`](Self::next()).
@@ -148,18 +164,20 @@ struct LeavesForward<'a, const N: usize, L: Leaf> {
     whole_total: usize,
 }
 
-impl<const N: usize, L: Leaf> Clone for LeavesForward<'_, N, L> {
+impl<const N: usize, L: Leaf, C: RefCount> Clone
+    for LeavesForward<'_, N, L, C>
+{
     #[inline]
     fn clone(&self) -> Self {
         Self { path: self.path.clone(), ..*self }
     }
 }
 
-impl<'a, const N: usize, L: Leaf> From<&'a Tree<N, L>>
-    for LeavesForward<'a, N, L>
+impl<'a, const N: usize, L: Leaf, C: RefCount> From<&'a Tree<N, L, C>>
+    for LeavesForward<'a, N, L, C>
 {
     #[inline]
-    fn from(tree: &'a Tree<N, L>) -> LeavesForward<'a, N, L> {
+    fn from(tree: &'a Tree<N, L, C>) -> LeavesForward<'a, N, L, C> {
         Self {
             is_initialized: false,
             base_offset: L::BaseMetric::zero(),
@@ -177,11 +195,13 @@ impl<'a, const N: usize, L: Leaf> From<&'a Tree<N, L>>
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf> From<&TreeSlice<'a, ARITY, L>>
-    for LeavesForward<'a, ARITY, L>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount>
+    From<&TreeSlice<'a, ARITY, L, C>> for LeavesForward<'a, ARITY, L, C>
 {
     #[inline]
-    fn from(slice: &TreeSlice<'a, ARITY, L>) -> LeavesForward<'a, ARITY, L> {
+    fn from(
+        slice: &TreeSlice<'a, ARITY, L, C>,
+    ) -> LeavesForward<'a, ARITY, L, C> {
         Self {
             is_initialized: false,
             base_offset: L::BaseMetric::measure(&slice.offset),
@@ -197,10 +217,10 @@ impl<'a, const ARITY: usize, L: Leaf> From<&TreeSlice<'a, ARITY, L>>
     }
 }
 
-impl<'a, const N: usize, L: Leaf> LeavesForward<'a, N, L> {
+impl<'a, const N: usize, L: Leaf, C: RefCount> LeavesForward<'a, N, L, C> {
     #[allow(clippy::type_complexity)]
     #[inline]
-    fn initialize(&mut self) -> (L::Slice<'a>, &'a [Arc<Node<N, L>>]) {
+    fn initialize(&mut self) -> (L::Slice<'a>, &'a [ArcNode<N, L, C>]) {
         debug_assert!(!self.is_initialized);
 
         self.is_initialized = true;
@@ -282,7 +302,7 @@ impl<'a, const N: usize, L: Leaf> LeavesForward<'a, N, L> {
     }
 
     #[inline]
-    fn next_bunch(&mut self) -> &'a [Arc<Node<N, L>>] {
+    fn next_bunch(&mut self) -> &'a [ArcNode<N, L, C>] {
         let mut inode = loop {
             let &mut (inode, ref mut visited) = self.path.last_mut().unwrap();
 
@@ -349,22 +369,22 @@ impl<'a, const N: usize, L: Leaf> LeavesForward<'a, N, L> {
 }
 
 #[derive(Debug)]
-struct LeavesBackward<'a, const N: usize, L: Leaf> {
+struct LeavesBackward<'a, const N: usize, L: Leaf, C: RefCount> {
     /// Whether `Self` has been initialized by calling
     /// [`initialize`](Self::initialize()).
     is_initialized: bool,
 
     /// The root of the `Tree` or `TreeSlice` we're iterating over.
-    root: &'a Node<N, L>,
+    root: &'a Node<N, L, C>,
 
     /// The path from the root down to (but not including) the internal node
     /// containing `leaves`. It follows that the depth of the last node (if
     /// there is one) is 2.
-    path: Vec<(&'a Inode<N, L>, usize)>,
+    path: Vec<(&'a Inode<N, L, C>, usize)>,
 
     /// The current leaves. All the nodes in the slice are guaranteed to be
     /// leaf nodes.
-    leaves: &'a [Arc<Node<N, L>>],
+    leaves: &'a [ArcNode<N, L, C>],
 
     /// The index of the last leaf in [`leaves`](Self::leaves) that was yielded
     /// by [`previous`](Self::previous()).
@@ -392,18 +412,20 @@ struct LeavesBackward<'a, const N: usize, L: Leaf> {
     whole_total: usize,
 }
 
-impl<const N: usize, L: Leaf> Clone for LeavesBackward<'_, N, L> {
+impl<const N: usize, L: Leaf, C: RefCount> Clone
+    for LeavesBackward<'_, N, L, C>
+{
     #[inline]
     fn clone(&self) -> Self {
         Self { path: self.path.clone(), ..*self }
     }
 }
 
-impl<'a, const N: usize, L: Leaf> From<&'a Tree<N, L>>
-    for LeavesBackward<'a, N, L>
+impl<'a, const N: usize, L: Leaf, C: RefCount> From<&'a Tree<N, L, C>>
+    for LeavesBackward<'a, N, L, C>
 {
     #[inline]
-    fn from(tree: &'a Tree<N, L>) -> LeavesBackward<'a, N, L> {
+    fn from(tree: &'a Tree<N, L, C>) -> LeavesBackward<'a, N, L, C> {
         Self {
             is_initialized: false,
             base_offset: L::BaseMetric::zero(),
@@ -421,11 +443,13 @@ impl<'a, const N: usize, L: Leaf> From<&'a Tree<N, L>>
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf> From<&TreeSlice<'a, ARITY, L>>
-    for LeavesBackward<'a, ARITY, L>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount>
+    From<&TreeSlice<'a, ARITY, L, C>> for LeavesBackward<'a, ARITY, L, C>
 {
     #[inline]
-    fn from(slice: &TreeSlice<'a, ARITY, L>) -> LeavesBackward<'a, ARITY, L> {
+    fn from(
+        slice: &TreeSlice<'a, ARITY, L, C>,
+    ) -> LeavesBackward<'a, ARITY, L, C> {
         let base_offset = slice.root().base_measure()
             - L::BaseMetric::measure(&slice.offset)
             - slice.base_measure();
@@ -445,10 +469,10 @@ impl<'a, const ARITY: usize, L: Leaf> From<&TreeSlice<'a, ARITY, L>>
     }
 }
 
-impl<'a, const N: usize, L: Leaf> LeavesBackward<'a, N, L> {
+impl<'a, const N: usize, L: Leaf, C: RefCount> LeavesBackward<'a, N, L, C> {
     #[allow(clippy::type_complexity)]
     #[inline]
-    fn initialize(&mut self) -> (L::Slice<'a>, &'a [Arc<Node<N, L>>]) {
+    fn initialize(&mut self) -> (L::Slice<'a>, &'a [ArcNode<N, L, C>]) {
         debug_assert!(!self.is_initialized);
 
         self.is_initialized = true;
@@ -529,7 +553,7 @@ impl<'a, const N: usize, L: Leaf> LeavesBackward<'a, N, L> {
     }
 
     #[inline]
-    fn previous_bunch(&mut self) -> &'a [Arc<Node<N, L>>] {
+    fn previous_bunch(&mut self) -> &'a [ArcNode<N, L, C>] {
         let mut inode = loop {
             let &mut (inode, ref mut visited) = self.path.last_mut().unwrap();
 
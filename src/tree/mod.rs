@@ -1,7 +1,167 @@
+//! A generic self-balancing B-tree whose nodes cache a summary of their
+//! subtree, which is what a [`Rope`](crate::Rope) is built on.
+//!
+//! A [`Tree`] stores a sequence of leaves implementing the [`Leaf`] trait.
+//! Every leaf can be [summarized](Summarize), and every internal node stores
+//! the sum of the summaries of its children. A [`Metric`] is a quantity that
+//! can be measured from a summary, like the number of bytes or lines in a
+//! piece of text, and the tree can be indexed and sliced by any of its
+//! metrics in logarithmic time. The balancing, slicing and editing logic is
+//! all generic, so the same tree can store text, line metadata, style runs
+//! or anything else with a meaningful summary.
+//!
+//! The nodes of a `Tree` are reference counted and shared between its
+//! clones, and they can be walked or assembled one at a time through
+//! [`NodeRef`] and [`OwnedNode`]. The reference counts are atomic by
+//! default, and can be made non-atomic with the [`LocalCount`] type
+//! parameter for trees that never leave the thread they're created on.
+//!
+//! The traits the leaves and metrics implement come with contracts, which
+//! are documented on each trait. The tree relies on them to stay balanced
+//! and to return correct results, but breaking them is never unsafe.
+//!
+//! # Examples
+//!
+//! ```
+//! # use core::ops::{Add, AddAssign, Sub, SubAssign};
+//! use crop::tree::{AsSlice, BaseMeasured, Metric, Summarize, Tree};
+//!
+//! // A leaf storing a run of numbers.
+//! #[derive(Debug, Default)]
+//! struct Numbers(Vec<u64>);
+//!
+//! #[derive(Copy, Clone, Debug)]
+//! struct NumbersSlice<'a>(&'a [u64]);
+//!
+//! // The summary of a run of numbers.
+//! #[derive(Clone, Debug, Default, PartialEq)]
+//! struct Stats {
+//!     count: usize,
+//!     sum: u64,
+//! }
+//!
+//! // A metric counting the numbers.
+//! #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+//! struct Count(usize);
+//!
+//! impl Summarize for Numbers {
+//!     type Summary = Stats;
+//!
+//!     fn summarize(&self) -> Stats {
+//!         self.as_slice().summarize()
+//!     }
+//! }
+//!
+//! impl Summarize for NumbersSlice<'_> {
+//!     type Summary = Stats;
+//!
+//!     fn summarize(&self) -> Stats {
+//!         Stats { count: self.0.len(), sum: self.0.iter().sum() }
+//!     }
+//! }
+//!
+//! impl AsSlice for Numbers {
+//!     type Slice<'a> = NumbersSlice<'a>;
+//!
+//!     fn as_slice(&self) -> NumbersSlice<'_> {
+//!         NumbersSlice(&self.0)
+//!     }
+//! }
+//!
+//! impl BaseMeasured for Numbers {
+//!     type BaseMetric = Count;
+//! }
+//!
+//! impl Metric<Stats> for Count {
+//!     fn zero() -> Self {
+//!         Count(0)
+//!     }
+//!
+//!     fn one() -> Self {
+//!         Count(1)
+//!     }
+//!
+//!     fn measure(stats: &Stats) -> Self {
+//!         Count(stats.count)
+//!     }
+//! }
+//! #
+//! # impl Add<&Stats> for Stats {
+//! #     type Output = Stats;
+//! #     fn add(mut self, other: &Stats) -> Stats {
+//! #         self += other;
+//! #         self
+//! #     }
+//! # }
+//! #
+//! # impl Sub<&Stats> for Stats {
+//! #     type Output = Stats;
+//! #     fn sub(mut self, other: &Stats) -> Stats {
+//! #         self -= other;
+//! #         self
+//! #     }
+//! # }
+//! #
+//! # impl AddAssign<&Stats> for Stats {
+//! #     fn add_assign(&mut self, other: &Stats) {
+//! #         self.count += other.count;
+//! #         self.sum += other.sum;
+//! #     }
+//! # }
+//! #
+//! # impl SubAssign<&Stats> for Stats {
+//! #     fn sub_assign(&mut self, other: &Stats) {
+//! #         self.count -= other.count;
+//! #         self.sum -= other.sum;
+//! #     }
+//! # }
+//! #
+//! # impl Add for Count {
+//! #     type Output = Count;
+//! #     fn add(self, other: Count) -> Count {
+//! #         Count(self.0 + other.0)
+//! #     }
+//! # }
+//! #
+//! # impl Sub for Count {
+//! #     type Output = Count;
+//! #     fn sub(self, other: Count) -> Count {
+//! #         Count(self.0 - other.0)
+//! #     }
+//! # }
+//! #
+//! # impl AddAssign for Count {
+//! #     fn add_assign(&mut self, other: Count) {
+//! #         self.0 += other.0;
+//! #     }
+//! # }
+//! #
+//! # impl SubAssign for Count {
+//! #     fn sub_assign(&mut self, other: Count) {
+//! #         self.0 -= other.0;
+//! #     }
+//! # }
+//!
+//! let tree = Tree::<4, Numbers>::from_leaves(
+//!     (0..10).map(|i| Numbers((i * 10..(i + 1) * 10).collect())),
+//! );
+//!
+//! assert_eq!(tree.leaf_count(), 10);
+//! assert_eq!(tree.measure::<Count>(), Count(100));
+//! assert_eq!(tree.summary().sum, (0..100u64).sum::<u64>());
+//!
+//! // The leaf containing the 42nd number starts after 40 numbers.
+//! let (leaf, Count(offset)) = tree.leaf_at_measure(Count(42));
+//! assert_eq!(offset, 40);
+//! assert_eq!(leaf.0[41 - offset], 41);
+//! ```
+
 mod leaves;
 mod node;
+mod node_hash;
 mod node_internal;
 mod node_leaf;
+mod node_ref;
 mod tiny_arc;
 mod traits;
 mod tree;
@@ -11,10 +171,13 @@ mod units;
 
 use iter_chain::ExactChain;
 pub use leaves::Leaves;
-use node::Node;
+use node::{ArcNode, Node};
+use node_hash::NodeHash;
 use node_internal::Inode;
 use node_leaf::Lnode;
+pub use node_ref::{NodeRef, OwnedNode};
 use tiny_arc::Arc;
+pub use tiny_arc::{AtomicCount, LocalCount, RefCount};
 pub use traits::*;
 pub use tree::Tree;
 pub use tree_builder::TreeBuilder;
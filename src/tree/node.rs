@@ -1,20 +1,27 @@
+#[cfg(feature = "node-hashes")]
+use super::traits::HashedLeaf;
 use super::traits::{BalancedLeaf, Leaf, Metric, SlicingMetric};
-use super::{Arc, Inode, Lnode};
+use super::{Arc, Inode, Lnode, RefCount};
+
+/// A reference counted node, which is how nodes are stored in a tree.
+pub(super) type ArcNode<const N: usize, L, C> = Arc<Node<N, L, C>, C>;
 
 #[derive(Clone)]
-pub(super) enum Node<const N: usize, L: Leaf> {
-    Internal(Inode<N, L>),
+pub(super) enum Node<const N: usize, L: Leaf, C: RefCount> {
+    Internal(Inode<N, L, C>),
     Leaf(Lnode<L>),
 }
 
-impl<const N: usize, L: Leaf + Default> Default for Node<N, L> {
+impl<const N: usize, L: Leaf + Default, C: RefCount> Default
+    for Node<N, L, C>
+{
     #[inline]
     fn default() -> Self {
         Node::Leaf(Lnode::default())
     }
 }
 
-impl<const N: usize, L: Leaf> core::fmt::Debug for Node<N, L> {
+impl<const N: usize, L: Leaf, C: RefCount> core::fmt::Debug for Node<N, L, C> {
     #[inline]
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         if !f.alternate() {
@@ -35,7 +42,7 @@ impl<const N: usize, L: Leaf> core::fmt::Debug for Node<N, L> {
     }
 }
 
-impl<const N: usize, L: Leaf> Node<N, L> {
+impl<const N: usize, L: Leaf, C: RefCount> Node<N, L, C> {
     /// Asserts the invariants of this node, then if it's an inode it calls
     /// itself recursively on all of its children.
     pub(super) fn assert_invariants(&self) {
@@ -150,7 +157,7 @@ impl<const N: usize, L: Leaf> Node<N, L> {
     }
 
     #[inline]
-    pub(super) fn get_internal(&self) -> &Inode<N, L> {
+    pub(super) fn get_internal(&self) -> &Inode<N, L, C> {
         match self {
             Node::Internal(inode) => inode,
             Node::Leaf(_) => panic!(""),
@@ -158,13 +165,29 @@ impl<const N: usize, L: Leaf> Node<N, L> {
     }
 
     #[inline]
-    pub(super) fn get_internal_mut(&mut self) -> &mut Inode<N, L> {
+    pub(super) fn get_internal_mut(&mut self) -> &mut Inode<N, L, C> {
         match self {
             Node::Internal(inode) => inode,
             Node::Leaf(_) => panic!(""),
         }
     }
 
+    /// Returns the hash of the contents of this node, see the [`node_hash`]
+    /// module for how it's computed.
+    ///
+    /// [`node_hash`]: super::node_hash
+    #[cfg(feature = "node-hashes")]
+    #[inline]
+    pub(super) fn hash(&self) -> u64
+    where
+        L: HashedLeaf,
+    {
+        match self {
+            Node::Internal(inode) => inode.hash(),
+            Node::Leaf(leaf) => L::hash_slice(leaf.as_slice()),
+        }
+    }
+
     #[inline]
     pub(super) fn is_empty(&self) -> bool {
         match self {
@@ -251,7 +274,7 @@ impl<const N: usize, L: Leaf> Node<N, L> {
     ///
     /// Panics if the `Arc` enclosing the root has a strong counter > 1.
     #[inline]
-    pub(super) fn replace_with_single_child(node: &mut Arc<Self>) {
+    pub(super) fn replace_with_single_child(node: &mut Arc<Self, C>) {
         while let Self::Internal(inode) = Arc::get_mut(node).unwrap() {
             if inode.len() == 1 {
                 *node = Arc::clone(inode.first());
@@ -261,6 +284,27 @@ impl<const N: usize, L: Leaf> Node<N, L> {
         }
     }
 
+    /// Recomputes the summary of this node and of all its descendants for
+    /// which `is_stale` returns `true`.
+    #[cfg(any(feature = "content-hash", feature = "word-metric"))]
+    #[inline]
+    pub(super) fn refresh_summary<S, R>(
+        &mut self,
+        is_stale: &S,
+        refresh_leaf: &R,
+    ) where
+        S: Fn(&L::Summary) -> bool,
+        R: Fn(&mut L, &mut L::Summary),
+        L: Clone,
+    {
+        match self {
+            Node::Internal(inode) => {
+                inode.refresh_summary(is_stale, refresh_leaf)
+            },
+            Node::Leaf(leaf) => leaf.refresh_summary(refresh_leaf),
+        }
+    }
+
     #[inline]
     pub(super) fn summary(&self) -> &L::Summary {
         match self {
@@ -268,4 +312,27 @@ impl<const N: usize, L: Leaf> Node<N, L> {
             Node::Leaf(leaf) => leaf.summary(),
         }
     }
+
+    /// Calls `update_leaf` on the leaves of this node at the given indices,
+    /// where `offset` is the index of its first leaf in the tree.
+    #[inline]
+    pub(super) fn update_leaves<F>(
+        &mut self,
+        leaf_indices: &[usize],
+        offset: usize,
+        update_leaf: &mut F,
+    ) where
+        F: FnMut(&mut L),
+        L: Clone,
+    {
+        match self {
+            Node::Internal(inode) => {
+                inode.update_leaves(leaf_indices, offset, update_leaf)
+            },
+            Node::Leaf(leaf) => {
+                debug_assert_eq!(leaf_indices, [offset]);
+                update_leaf(leaf.value_mut());
+            },
+        }
+    }
 }
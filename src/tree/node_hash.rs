@@ -0,0 +1,95 @@
+//! This module contains the per-inode hash cache used to compare subtrees by
+//! content in [`Tree::diff_shared()`](super::Tree::diff_shared()).
+//!
+//! The hash of a leaf is computed from its contents, and the hash of an inode
+//! is computed from the hashes of its children. Inodes cache their hash the
+//! first time it's asked for, and every method taking an inode by mutable
+//! reference clears it. Since a subtree shared between two trees is the same
+//! allocation, it only needs to be hashed once for both of them.
+//!
+//! Without the `node-hashes` feature the cache is a zero-sized type whose
+//! methods are no-ops.
+
+/// Combines the hashes of the children of an inode into the hash of the
+/// inode, which is never zero.
+#[cfg(feature = "node-hashes")]
+#[inline]
+pub(super) fn combine<I>(hashes: I) -> u64
+where
+    I: IntoIterator<Item = u64>,
+{
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = hashes
+        .into_iter()
+        .flat_map(u64::to_le_bytes)
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+
+    hash.max(1)
+}
+
+/// The cached hash of an inode, or nothing if it hasn't been computed since
+/// the inode was last mutated.
+#[derive(Debug, Default)]
+pub(super) struct NodeHash {
+    /// The cached hash, where zero means that there's no cached hash.
+    #[cfg(all(feature = "node-hashes", target_has_atomic = "64"))]
+    hash: core::sync::atomic::AtomicU64,
+}
+
+impl NodeHash {
+    #[inline]
+    pub(super) fn clear(&mut self) {
+        #[cfg(all(feature = "node-hashes", target_has_atomic = "64"))]
+        {
+            *self.hash.get_mut() = 0;
+        }
+    }
+
+    /// Returns the cached hash, calling `compute` to fill in the cache if
+    /// it's empty.
+    #[cfg(feature = "node-hashes")]
+    #[inline]
+    pub(super) fn get_or_compute(&self, compute: impl FnOnce() -> u64) -> u64 {
+        #[cfg(target_has_atomic = "64")]
+        {
+            use core::sync::atomic::Ordering;
+
+            let cached = self.hash.load(Ordering::Relaxed);
+
+            if cached != 0 {
+                return cached;
+            }
+
+            let hash = compute();
+            debug_assert_ne!(hash, 0);
+            self.hash.store(hash, Ordering::Relaxed);
+            hash
+        }
+
+        #[cfg(not(target_has_atomic = "64"))]
+        {
+            compute()
+        }
+    }
+}
+
+impl Clone for NodeHash {
+    #[inline]
+    fn clone(&self) -> Self {
+        #[cfg(all(feature = "node-hashes", target_has_atomic = "64"))]
+        {
+            use core::sync::atomic::{AtomicU64, Ordering};
+
+            Self { hash: AtomicU64::new(self.hash.load(Ordering::Relaxed)) }
+        }
+
+        #[cfg(not(all(feature = "node-hashes", target_has_atomic = "64")))]
+        {
+            Self {}
+        }
+    }
+}
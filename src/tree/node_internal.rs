@@ -3,18 +3,21 @@ use alloc::vec::Vec;
 use core::ops::{Range, RangeBounds};
 
 use super::traits::*;
-use super::{Arc, ExactChain, Node};
+use super::{Arc, ArcNode, ExactChain, Node, NodeHash, RefCount};
 use crate::range_bounds_to_start_end;
 
 #[derive(Clone)]
-pub(super) struct Inode<const N: usize, L: Leaf> {
-    children: Vec<Arc<Node<N, L>>>,
+pub(super) struct Inode<const N: usize, L: Leaf, C: RefCount> {
+    children: Vec<ArcNode<N, L, C>>,
     summary: L::Summary,
     depth: usize,
     leaf_count: usize,
+    hash: NodeHash,
 }
 
-impl<const N: usize, L: Leaf> core::fmt::Debug for Inode<N, L> {
+impl<const N: usize, L: Leaf, C: RefCount> core::fmt::Debug
+    for Inode<N, L, C>
+{
     #[inline]
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         if !f.alternate() {
@@ -30,7 +33,7 @@ impl<const N: usize, L: Leaf> core::fmt::Debug for Inode<N, L> {
     }
 }
 
-impl<const N: usize, L: Leaf> Inode<N, L> {
+impl<const N: usize, L: Leaf, C: RefCount> Inode<N, L, C> {
     /// Appends the node at the right depth.
     ///
     /// If all the nodes on the right side of the subtree up to the one to
@@ -39,11 +42,13 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     #[inline]
     pub(super) fn append_at_depth(
         &mut self,
-        mut node: Arc<Node<N, L>>,
+        mut node: ArcNode<N, L, C>,
     ) -> Option<Self>
     where
         L: BalancedLeaf + Clone,
     {
+        self.hash.clear();
+
         debug_assert!(node.depth() < self.depth());
 
         if self.depth() > node.depth() + 1 {
@@ -138,6 +143,9 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     /// Panics if `other` is at a different depth.
     #[inline]
     pub(super) fn balance(&mut self, other: &mut Self) {
+        self.hash.clear();
+        other.hash.clear();
+
         debug_assert_eq!(self.depth(), other.depth());
 
         if !self.is_underfilled() && !other.is_underfilled() {
@@ -191,6 +199,8 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     where
         L: BalancedLeaf + Clone,
     {
+        self.hash.clear();
+
         debug_assert!(self.len() > 1);
 
         if !self.child(child_idx).is_underfilled() {
@@ -220,6 +230,8 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     where
         L: BalancedLeaf + Clone,
     {
+        self.hash.clear();
+
         self.balance_first_child_with_second();
 
         let first_is_underfilled = self.with_child_mut(0, |first| {
@@ -241,22 +253,29 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     ///
     /// # Panics
     ///
-    /// Panics if the `Arc` enclosing the last child has a strong counter > 1.
+    /// Panics if the last child is an inode and the `Arc` enclosing it has a
+    /// strong counter > 1. Leaves can be shared with other trees.
     #[inline]
     pub(super) fn balance_right_side(&mut self)
     where
         L: BalancedLeaf + Clone,
     {
+        self.hash.clear();
+
         self.balance_last_child_with_penultimate();
 
         let last_is_underfilled =
             self.with_child_mut(self.len() - 1, |last| {
-                if let Node::Internal(last) = Arc::get_mut(last).unwrap() {
-                    last.balance_right_side();
-                    last.is_underfilled()
-                } else {
-                    false
+                if last.is_leaf() {
+                    return false;
                 }
+
+                let Node::Internal(last) = Arc::get_mut(last).unwrap() else {
+                    unreachable!();
+                };
+
+                last.balance_right_side();
+                last.is_underfilled()
             });
 
         if last_is_underfilled && self.len() > 1 {
@@ -284,6 +303,8 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     where
         L: BalancedLeaf + Clone,
     {
+        self.hash.clear();
+
         debug_assert!(self.len() >= 2);
 
         // Check for early returns.
@@ -345,13 +366,16 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     /// - this inode has only one child (the penultimate child is assumed to
     ///   exist);
     ///
-    /// - the `Arc` enclosing the last child has a strong counter > 1. This
-    ///   function assumes that there are zero `Arc::clone`s of the last child.
+    /// - the last child is an inode and the `Arc` enclosing it has a strong
+    ///   counter > 1. This function assumes that there are zero `Arc::clone`s
+    ///   of the last child unless it's a leaf, which is cloned if needed.
     #[inline]
     pub(super) fn balance_last_child_with_penultimate(&mut self)
     where
         L: BalancedLeaf + Clone,
     {
+        self.hash.clear();
+
         debug_assert!(self.len() >= 2);
 
         // Check for early returns.
@@ -363,7 +387,13 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
 
         let (penultimate, last) = self.two_mut(last_idx - 1, last_idx);
 
-        match (Arc::make_mut(penultimate), Arc::get_mut(last).unwrap()) {
+        let last = if last.is_leaf() {
+            Arc::make_mut(last)
+        } else {
+            Arc::get_mut(last).unwrap()
+        };
+
+        match (Arc::make_mut(penultimate), last) {
             (Node::Internal(penultimate), Node::Internal(last)) => {
                 // Move the last child's children to the penultimate child,
                 // then remove the last child.
@@ -409,15 +439,21 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     }
 
     #[inline]
-    pub(super) fn child(&self, child_idx: usize) -> &Arc<Node<N, L>> {
+    pub(super) fn child(&self, child_idx: usize) -> &ArcNode<N, L, C> {
         &self.children[child_idx]
     }
 
     #[inline]
-    pub(super) fn children(&self) -> &[Arc<Node<N, L>>] {
+    pub(super) fn children(&self) -> &[ArcNode<N, L, C>] {
         &self.children
     }
 
+    /// Returns the number of bytes allocated for the children.
+    #[inline]
+    pub(super) fn children_heap_size(&self) -> usize {
+        self.children.capacity() * core::mem::size_of::<ArcNode<N, L, C>>()
+    }
+
     /// Returns the index of the child at the given measure together
     /// with the combined `M`-offset of the other children up to but not
     /// including that child.
@@ -506,10 +542,12 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     pub(super) fn drain<R>(
         &mut self,
         idx_range: R,
-    ) -> alloc::vec::Drain<'_, Arc<Node<N, L>>>
+    ) -> alloc::vec::Drain<'_, ArcNode<N, L, C>>
     where
         R: RangeBounds<usize>,
     {
+        self.hash.clear();
+
         let (start, end) = range_bounds_to_start_end(idx_range, 0, self.len());
 
         debug_assert!(start <= end);
@@ -530,6 +568,7 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
             depth: 1,
             leaf_count: 0,
             summary: Default::default(),
+            hash: NodeHash::default(),
         }
     }
 
@@ -537,7 +576,7 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     ///
     /// Panics if the inode is empty.
     #[inline]
-    pub(super) fn first(&self) -> &Arc<Node<N, L>> {
+    pub(super) fn first(&self) -> &ArcNode<N, L, C> {
         &self.children[0]
     }
 
@@ -550,9 +589,9 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     #[inline]
     pub(super) fn from_children<I>(children: I) -> Self
     where
-        I: IntoIterator<Item = Arc<Node<N, L>>>,
+        I: IntoIterator<Item = ArcNode<N, L, C>>,
     {
-        let children = children.into_iter().collect::<Vec<Arc<Node<N, L>>>>();
+        let children = children.into_iter().collect::<Vec<ArcNode<N, L, C>>>();
 
         debug_assert!(!children.is_empty());
         debug_assert!(children.len() <= Self::max_children());
@@ -567,7 +606,13 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
             summary += child.summary();
         }
 
-        Self { children, depth, leaf_count, summary }
+        Self {
+            children,
+            depth,
+            leaf_count,
+            summary,
+            hash: NodeHash::default(),
+        }
     }
 
     /// Constructs a new inode from an arbitrarily long sequence of nodes.
@@ -583,7 +628,7 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     #[inline]
     pub(super) fn from_nodes<I>(nodes: I) -> Self
     where
-        I: IntoIterator<Item = Arc<Node<N, L>>>,
+        I: IntoIterator<Item = ArcNode<N, L, C>>,
         I::IntoIter: ExactSizeIterator,
     {
         let nodes = nodes.into_iter();
@@ -611,6 +656,21 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
         Self::from_children(nodes)
     }
 
+    /// Returns the hash of this inode, computing it from the hashes of its
+    /// children if it's not already cached.
+    #[cfg(feature = "node-hashes")]
+    #[inline]
+    pub(super) fn hash(&self) -> u64
+    where
+        L: HashedLeaf,
+    {
+        self.hash.get_or_compute(|| {
+            super::node_hash::combine(
+                self.children.iter().map(|child| child.hash()),
+            )
+        })
+    }
+
     #[inline]
     pub(super) fn is_underfilled(&self) -> bool {
         self.len() < Self::min_children()
@@ -628,8 +688,10 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     pub(super) fn insert(
         &mut self,
         child_offset: usize,
-        child: Arc<Node<N, L>>,
+        child: ArcNode<N, L, C>,
     ) {
+        self.hash.clear();
+
         if self.is_empty() {
             self.depth = child.depth() + 1;
         }
@@ -638,8 +700,23 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
         debug_assert_eq!(child.depth() + 1, self.depth());
 
         self.leaf_count += child.leaf_count();
-        self.summary += child.summary();
-        self.children.insert(child_offset, child);
+
+        // Summaries have to be added in the order of the leaves they
+        // summarize, so if the child isn't the last one we have to add them
+        // all up again.
+        if child_offset == self.len() {
+            self.summary += child.summary();
+            self.children.push(child);
+        } else {
+            self.children.insert(child_offset, child);
+            self.summary = self.children.iter().fold(
+                L::Summary::default(),
+                |mut summary, child| {
+                    summary += child.summary();
+                    summary
+                },
+            );
+        }
     }
 
     /// Inserts a node shallower than this inode's children at the right depth
@@ -655,10 +732,12 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     pub(super) fn insert_at_depth(
         &mut self,
         child_offset: usize,
-        node: Arc<Node<N, L>>,
+        node: ArcNode<N, L, C>,
     ) where
         L: BalancedLeaf + Clone,
     {
+        self.hash.clear();
+
         debug_assert!(!self.is_empty());
         debug_assert!(child_offset <= self.len());
         debug_assert!(self.depth() >= 2);
@@ -713,9 +792,11 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
         children: I,
     ) -> Option<impl ExactSizeIterator<Item = Self>>
     where
-        I: IntoIterator<Item = Arc<Node<N, L>>>,
+        I: IntoIterator<Item = ArcNode<N, L, C>>,
         I::IntoIter: ExactSizeIterator,
     {
+        self.hash.clear();
+
         let mut children = children.into_iter();
 
         if self.len() + children.len() <= Self::max_children() {
@@ -781,7 +862,7 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     }
 
     #[inline]
-    pub(super) fn last(&self) -> &Arc<Node<N, L>> {
+    pub(super) fn last(&self) -> &ArcNode<N, L, C> {
         let last_idx = self.len() - 1;
         &self.children[last_idx]
     }
@@ -823,11 +904,13 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     #[inline]
     pub(super) fn prepend_at_depth(
         &mut self,
-        mut node: Arc<Node<N, L>>,
+        mut node: ArcNode<N, L, C>,
     ) -> Option<Self>
     where
         L: BalancedLeaf + Clone,
     {
+        self.hash.clear();
+
         debug_assert!(node.depth() < self.depth());
 
         if self.depth() > node.depth() + 1 {
@@ -877,7 +960,9 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     /// Panics if the inode is already full or if `child` is a depth different
     /// than `self.depth() - 1` if the inode already contained some children.
     #[inline]
-    pub(super) fn push(&mut self, child: Arc<Node<N, L>>) {
+    pub(super) fn push(&mut self, child: ArcNode<N, L, C>) {
+        self.hash.clear();
+
         if self.is_empty() {
             self.depth = child.depth() + 1;
         }
@@ -895,8 +980,35 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     /// # Panics
     ///
     /// Panics if `child_idx` is greater or equal to the length of this inode.
+    /// Recomputes the summary of this inode from the ones of its children,
+    /// after refreshing the children for which `is_stale` returns `true`.
+    #[cfg(any(feature = "content-hash", feature = "word-metric"))]
+    #[inline]
+    pub(super) fn refresh_summary<S, R>(
+        &mut self,
+        is_stale: &S,
+        refresh_leaf: &R,
+    ) where
+        S: Fn(&L::Summary) -> bool,
+        R: Fn(&mut L, &mut L::Summary),
+        L: Clone,
+    {
+        let mut summary = L::Summary::default();
+
+        for child in &mut self.children {
+            if is_stale(child.summary()) {
+                Arc::make_mut(child).refresh_summary(is_stale, refresh_leaf);
+            }
+            summary += child.summary();
+        }
+
+        self.summary = summary;
+    }
+
     #[inline]
-    pub(super) fn remove(&mut self, child_idx: usize) -> Arc<Node<N, L>> {
+    pub(super) fn remove(&mut self, child_idx: usize) -> ArcNode<N, L, C> {
+        self.hash.clear();
+
         debug_assert!(child_idx < self.len());
         let child = self.children.remove(child_idx);
         self.leaf_count -= child.leaf_count();
@@ -919,8 +1031,10 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
     pub(super) fn swap(
         &mut self,
         child_idx: usize,
-        new_child: Arc<Node<N, L>>,
+        new_child: ArcNode<N, L, C>,
     ) {
+        self.hash.clear();
+
         debug_assert!(child_idx < self.len());
         debug_assert_eq!(new_child.depth() + 1, self.depth());
 
@@ -933,6 +1047,41 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
         self.children[child_idx] = new_child;
     }
 
+    /// Calls `update_leaf` on the leaves of this inode at the given indices,
+    /// where `offset` is the index of its first leaf in the tree.
+    #[inline]
+    pub(super) fn update_leaves<F>(
+        &mut self,
+        mut leaf_indices: &[usize],
+        mut offset: usize,
+        update_leaf: &mut F,
+    ) where
+        F: FnMut(&mut L),
+        L: Clone,
+    {
+        for child in &mut self.children {
+            let end = offset + child.leaf_count();
+
+            let (in_child, rest) = leaf_indices
+                .split_at(leaf_indices.partition_point(|&idx| idx < end));
+
+            if !in_child.is_empty() {
+                Arc::make_mut(child).update_leaves(
+                    in_child,
+                    offset,
+                    update_leaf,
+                );
+            }
+
+            if rest.is_empty() {
+                break;
+            }
+
+            leaf_indices = rest;
+            offset = end;
+        }
+    }
+
     /// Returns mutable references to the child nodes at `first_idx` and
     /// `second_idx`, respectively.
     ///
@@ -945,7 +1094,9 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
         &mut self,
         first_idx: usize,
         second_idx: usize,
-    ) -> (&mut Arc<Node<N, L>>, &mut Arc<Node<N, L>>) {
+    ) -> (&mut ArcNode<N, L, C>, &mut ArcNode<N, L, C>) {
+        self.hash.clear();
+
         debug_assert!(first_idx < second_idx);
         debug_assert!(second_idx < self.len());
 
@@ -965,8 +1116,10 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
         fun: F,
     ) -> T
     where
-        F: FnOnce(&mut Arc<Node<N, L>>) -> T,
+        F: FnOnce(&mut ArcNode<N, L, C>) -> T,
     {
+        self.hash.clear();
+
         let child = &mut self.children[child_idx];
 
         self.summary -= child.summary();
@@ -984,40 +1137,44 @@ impl<const N: usize, L: Leaf> Inode<N, L> {
 /// Takes an iterator of `n` nodes (with `n >= min_children`) at depth `d`
 /// and gives back inodes of depth `d + 1` that are all guaranteed to have
 /// between `min_children` and `max_children` children.
-struct ChildSegmenter<const N: usize, L, Children>
+struct ChildSegmenter<const N: usize, L, C, Children>
 where
     L: Leaf,
-    Children: ExactSizeIterator<Item = Arc<Node<N, L>>>,
+    C: RefCount,
+    Children: ExactSizeIterator<Item = ArcNode<N, L, C>>,
 {
     children: Children,
 }
 
-impl<const N: usize, L, Children> ChildSegmenter<N, L, Children>
+impl<const N: usize, L, C, Children> ChildSegmenter<N, L, C, Children>
 where
     L: Leaf,
-    Children: ExactSizeIterator<Item = Arc<Node<N, L>>>,
+    C: RefCount,
+    Children: ExactSizeIterator<Item = ArcNode<N, L, C>>,
 {
     /// # Panics
     ///
     /// Panics if `children` yields less than `min_children` children.
     #[inline]
     fn new(children: Children) -> Self {
-        debug_assert!(children.len() >= Inode::<N, L>::min_children());
+        debug_assert!(children.len() >= Inode::<N, L, C>::min_children());
         Self { children }
     }
 }
 
-impl<const N: usize, L, Children> Iterator for ChildSegmenter<N, L, Children>
+impl<const N: usize, L, C, Children> Iterator
+    for ChildSegmenter<N, L, C, Children>
 where
     L: Leaf,
-    Children: ExactSizeIterator<Item = Arc<Node<N, L>>>,
+    C: RefCount,
+    Children: ExactSizeIterator<Item = ArcNode<N, L, C>>,
 {
-    type Item = Inode<N, L>;
+    type Item = Inode<N, L, C>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let min_children = Inode::<N, L>::min_children();
-        let max_children = Inode::<N, L>::max_children();
+        let min_children = Inode::<N, L, C>::min_children();
+        let max_children = Inode::<N, L, C>::max_children();
         let remaining = self.children.len();
 
         debug_assert!(remaining == 0 || remaining >= min_children);
@@ -1048,16 +1205,17 @@ where
     }
 }
 
-impl<const N: usize, L, Children> ExactSizeIterator
-    for ChildSegmenter<N, L, Children>
+impl<const N: usize, L, C, Children> ExactSizeIterator
+    for ChildSegmenter<N, L, C, Children>
 where
     L: Leaf,
-    Children: ExactSizeIterator<Item = Arc<Node<N, L>>>,
+    C: RefCount,
+    Children: ExactSizeIterator<Item = ArcNode<N, L, C>>,
 {
     #[inline]
     fn len(&self) -> usize {
         let remaining = self.children.len();
-        let max_children = Inode::<N, L>::max_children();
+        let max_children = Inode::<N, L, C>::max_children();
         remaining / max_children + ((remaining % max_children != 0) as usize)
     }
 }
@@ -1067,8 +1225,8 @@ where
 /// Called by the `Debug` impl of [`Inode`] when using the pretty-print
 /// modifier (i.e. `{:#?}`).
 #[inline]
-fn pretty_print_inode<const N: usize, L: Leaf>(
-    inode: &Inode<N, L>,
+fn pretty_print_inode<const N: usize, L: Leaf, C: RefCount>(
+    inode: &Inode<N, L, C>,
     shifts: &mut String,
     ident: &str,
     last_shift_byte_len: usize,
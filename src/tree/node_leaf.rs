@@ -85,6 +85,15 @@ impl<L: Leaf> Lnode<L> {
         Self { value, summary }
     }
 
+    #[cfg(any(feature = "content-hash", feature = "word-metric"))]
+    #[inline]
+    pub(super) fn refresh_summary<R>(&mut self, refresh_leaf: &R)
+    where
+        R: Fn(&mut L, &mut L::Summary),
+    {
+        refresh_leaf(&mut self.value, &mut self.summary);
+    }
+
     #[inline]
     pub(super) fn remove_up_to<M>(&mut self, up_to: M)
     where
@@ -115,4 +124,16 @@ impl<L: Leaf> Lnode<L> {
     pub(super) fn summary(&self) -> &L::Summary {
         &self.summary
     }
+
+    #[inline]
+    pub(super) fn value(&self) -> &L {
+        &self.value
+    }
+
+    /// Returns a mutable reference to the value of the leaf, which must be
+    /// left with the same summary.
+    #[inline]
+    pub(super) fn value_mut(&mut self) -> &mut L {
+        &mut self.value
+    }
 }
@@ -0,0 +1,166 @@
+use alloc::vec::Vec;
+
+use super::traits::Leaf;
+use super::{Arc, ArcNode, AtomicCount, Inode, Lnode, Node, RefCount, Tree};
+
+/// A borrowed node of a [`Tree`], exposing its structure so that the tree can
+/// be walked one node at a time.
+pub struct NodeRef<'a, const N: usize, L: Leaf, C: RefCount = AtomicCount> {
+    node: &'a ArcNode<N, L, C>,
+}
+
+impl<const N: usize, L: Leaf, C: RefCount> Clone for NodeRef<'_, N, L, C> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<const N: usize, L: Leaf, C: RefCount> Copy for NodeRef<'_, N, L, C> {}
+
+impl<'a, const N: usize, L: Leaf, C: RefCount> NodeRef<'a, N, L, C> {
+    /// Returns the address of the node, which is shared by all the trees
+    /// containing it.
+    ///
+    /// Nodes are never mutated while they're shared, so two nodes with the
+    /// same address alive at the same time always have the same contents.
+    #[inline]
+    pub fn addr(&self) -> usize {
+        Arc::addr(self.node)
+    }
+
+    /// Returns the children of the node, or an empty iterator if the node
+    /// is a leaf.
+    #[inline]
+    pub fn children(&self) -> impl ExactSizeIterator<Item = Self> + 'a {
+        let children = match &**self.node {
+            Node::Internal(inode) => inode.children(),
+            Node::Leaf(_) => &[],
+        };
+
+        children.iter().map(|node| NodeRef { node })
+    }
+
+    /// Returns the number of bytes allocated on the heap for the node
+    /// itself, i.e. including the list of children of an internal node but
+    /// not the children themselves nor the heap memory owned by a leaf's
+    /// value.
+    #[inline]
+    pub fn heap_size(&self) -> usize {
+        let children = match &**self.node {
+            Node::Internal(inode) => inode.children_heap_size(),
+            Node::Leaf(_) => 0,
+        };
+
+        Arc::<Node<N, L, C>, C>::alloc_size() + children
+    }
+
+    /// Returns the value of the leaf if the node is a leaf, or `None` if
+    /// it's an internal node.
+    #[inline]
+    pub fn leaf(&self) -> Option<&'a L> {
+        match &**self.node {
+            Node::Internal(_) => None,
+            Node::Leaf(leaf) => Some(leaf.value()),
+        }
+    }
+
+    /// Returns the summary of the node, i.e. of all the leaves in its
+    /// subtree.
+    #[inline]
+    pub fn summary(&self) -> &'a L::Summary {
+        self.node.summary()
+    }
+
+    /// Returns an owned handle to the node.
+    #[inline]
+    pub fn to_owned(&self) -> OwnedNode<N, L, C> {
+        OwnedNode { node: Arc::clone(self.node) }
+    }
+}
+
+/// An owned node used to build a [`Tree`] from the bottom up, e.g. when
+/// reading it back from storage.
+pub struct OwnedNode<const N: usize, L: Leaf, C: RefCount = AtomicCount> {
+    node: ArcNode<N, L, C>,
+}
+
+impl<const N: usize, L: Leaf, C: RefCount> Clone for OwnedNode<N, L, C> {
+    #[inline]
+    fn clone(&self) -> Self {
+        OwnedNode { node: Arc::clone(&self.node) }
+    }
+}
+
+impl<const N: usize, L: Leaf, C: RefCount> OwnedNode<N, L, C> {
+    /// Creates a new internal node from its children, returning `None` if
+    /// there are zero or more than `N` of them or if they're not all at the
+    /// same depth.
+    #[inline]
+    pub fn internal(children: Vec<Self>) -> Option<Self> {
+        let depth = children.first()?.node.depth();
+
+        if children.len() > Inode::<N, L, C>::max_children()
+            || children.iter().any(|child| child.node.depth() != depth)
+        {
+            return None;
+        }
+
+        let inode =
+            Inode::from_children(children.into_iter().map(|child| child.node));
+
+        Some(OwnedNode { node: Arc::new(Node::Internal(inode)) })
+    }
+
+    /// Creates a new leaf node.
+    #[inline]
+    pub fn leaf(value: L) -> Self {
+        OwnedNode { node: Arc::new(Node::Leaf(Lnode::from(value))) }
+    }
+
+    /// Returns a borrowed handle to the node.
+    #[inline]
+    pub fn node_ref(&self) -> NodeRef<'_, N, L, C> {
+        NodeRef { node: &self.node }
+    }
+
+    #[inline]
+    pub(super) fn into_arc(self) -> ArcNode<N, L, C> {
+        self.node
+    }
+}
+
+impl<const ARITY: usize, L: Leaf, C: RefCount> Tree<ARITY, L, C> {
+    /// Creates a new `Tree` from its root node, returning `None` if the root
+    /// is an internal node with less than 2 children or if any of the other
+    /// internal nodes has less than the minimum number of children.
+    #[inline]
+    pub fn from_root(root: OwnedNode<ARITY, L, C>) -> Option<Self> {
+        fn is_valid<const N: usize, L: Leaf, C: RefCount>(
+            node: &Node<N, L, C>,
+        ) -> bool {
+            match node {
+                Node::Internal(inode) => {
+                    !inode.is_underfilled()
+                        && inode.children().iter().all(|c| is_valid(c))
+                },
+                Node::Leaf(_) => true,
+            }
+        }
+
+        if let Node::Internal(inode) = &*root.node {
+            if inode.len() < 2 || !inode.children().iter().all(|c| is_valid(c))
+            {
+                return None;
+            }
+        }
+
+        Some(Self::with_root(root.node))
+    }
+
+    /// Returns a borrowed handle to the root of the `Tree`.
+    #[inline]
+    pub fn root_ref(&self) -> NodeRef<'_, ARITY, L, C> {
+        NodeRef { node: &self.root }
+    }
+}
@@ -2,30 +2,102 @@
 //! references, inspired by the `Arc` implementation in [rclite] (of course,
 //! all bugs are mine).
 //!
+//! The reference count is generic over [`RefCount`], so the same `Arc` is an
+//! `Rc` in all but name when it's not atomic.
+//!
+//! With the `chunk-pool` feature the allocations are made with the allocator
+//! set with `chunk_pool::set_allocator()`.
+//!
 //! [rclite]: https://github.com/fereidani/rclite
 
-use alloc::boxed::Box;
-use core::mem::MaybeUninit;
+use core::alloc::Layout;
 use core::ptr::{addr_of_mut, NonNull};
-use core::sync::atomic;
+
+#[cfg(feature = "chunk-pool")]
+use crate::rope::chunk_pool;
 
 /// A tiny `Arc` without weak references.
-pub(super) struct Arc<T> {
-    ptr: NonNull<ArcInner<T>>,
+pub(super) struct Arc<T, C: RefCount> {
+    ptr: NonNull<ArcInner<T, C>>,
 }
 
-unsafe impl<T: Sync + Send> Send for Arc<T> {}
-unsafe impl<T: Sync + Send> Sync for Arc<T> {}
+unsafe impl<T, C> Send for Arc<T, C>
+where
+    T: Sync + Send,
+    C: RefCount,
+    C::Counter: Sync + Send,
+{
+}
 
-struct ArcInner<T> {
-    counter: atomic::AtomicUsize,
+unsafe impl<T, C> Sync for Arc<T, C>
+where
+    T: Sync + Send,
+    C: RefCount,
+    C::Counter: Sync + Send,
+{
+}
+
+struct ArcInner<T, C: RefCount> {
+    counter: C::Counter,
     data: T,
 }
 
-unsafe impl<T: Sync + Send> Send for ArcInner<T> {}
-unsafe impl<T: Sync + Send> Sync for ArcInner<T> {}
+impl<T, C: RefCount> Arc<T, C> {
+    /// Allocates the memory of a new `ArcInner`, leaving it uninitialized.
+    #[inline]
+    fn alloc_inner() -> NonNull<ArcInner<T, C>> {
+        let layout = Layout::new::<ArcInner<T, C>>();
+
+        // SAFETY: the layout has a non-zero size since it contains the
+        // counter.
+        #[cfg(feature = "chunk-pool")]
+        let ptr = unsafe { chunk_pool::allocator().alloc(layout) };
+
+        // SAFETY: same as above.
+        #[cfg(not(feature = "chunk-pool"))]
+        let ptr = unsafe { alloc::alloc::alloc(layout) };
+
+        match NonNull::new(ptr.cast::<ArcInner<T, C>>()) {
+            Some(ptr) => ptr,
+            None => alloc::alloc::handle_alloc_error(layout),
+        }
+    }
+
+    /// Drops the `ArcInner` and frees its memory.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have been returned by [`alloc_inner()`] and
+    /// initialized, and it must never be accessed again after this.
+    ///
+    /// [`alloc_inner()`]: Self::alloc_inner()
+    #[inline]
+    unsafe fn dealloc_inner(ptr: NonNull<ArcInner<T, C>>) {
+        let layout = Layout::new::<ArcInner<T, C>>();
+
+        core::ptr::drop_in_place(ptr.as_ptr());
+
+        #[cfg(feature = "chunk-pool")]
+        chunk_pool::allocator().dealloc(ptr.as_ptr().cast(), layout);
+
+        #[cfg(not(feature = "chunk-pool"))]
+        alloc::alloc::dealloc(ptr.as_ptr().cast(), layout);
+    }
+
+    /// Returns the address of the allocation, which can be used to identify
+    /// the `Arc` and all of its clones.
+    #[inline]
+    pub(super) fn addr(this: &Self) -> usize {
+        this.ptr.as_ptr() as usize
+    }
+
+    /// Returns the size of the allocation storing the value and its
+    /// reference count.
+    #[inline]
+    pub(super) const fn alloc_size() -> usize {
+        core::mem::size_of::<ArcInner<T, C>>()
+    }
 
-impl<T> Arc<T> {
     #[inline]
     pub(super) fn get_mut(this: &mut Self) -> Option<&mut T> {
         if this.is_unique() {
@@ -43,7 +115,7 @@ impl<T> Arc<T> {
     }
 
     #[inline]
-    fn inner(&self) -> &ArcInner<T> {
+    fn inner(&self) -> &ArcInner<T, C> {
         // SAFETY: the inner pointer is valid as long as there's at least one
         // `Arc` pointing to it.
         unsafe { self.ptr.as_ref() }
@@ -51,17 +123,15 @@ impl<T> Arc<T> {
 
     #[inline]
     fn is_unique(&self) -> bool {
-        self.inner().counter.load(atomic::Ordering::Relaxed) == 1
+        C::get(&self.inner().counter) == 1
     }
 
     #[inline]
     pub(super) fn new(data: T) -> Self {
-        let inner = ArcInner { counter: atomic::AtomicUsize::new(1), data };
+        let ptr = Self::alloc_inner();
 
-        // SAFETY: the pointer returned by `Box::into_raw()` is guaranteed to
-        // be non-null.
-        let ptr =
-            unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(inner))) };
+        // SAFETY: the pointer is valid for writes of an `ArcInner`.
+        unsafe { ptr.as_ptr().write(ArcInner { counter: C::new(1), data }) };
 
         Self { ptr }
     }
@@ -72,55 +142,65 @@ impl<T> Arc<T> {
     }
 }
 
-impl<T: Clone> Arc<T> {
+impl<T: Clone, C: RefCount> Arc<T, C> {
+    /// Returns a mutable reference to the value, cloning it first if it's
+    /// shared with other `Arc`s.
+    ///
+    /// Trees that were never cloned only contain unique nodes, so the check
+    /// is kept inline while the cloning is moved out of the hot path.
     #[inline]
     pub(super) fn make_mut(this: &mut Self) -> &mut T {
-        if !this.is_unique() {
-            *this = this.optimized_clone();
+        if unlikely(!this.is_unique()) {
+            this.clone_inner();
         }
 
         // SAFETY: either the reference was unique or we just cloned the T.
         unsafe { Self::get_mut_unchecked(this) }
     }
 
+    /// Replaces this `Arc` with a unique copy of its value.
+    #[cold]
+    #[inline(never)]
+    fn clone_inner(&mut self) {
+        *self = self.optimized_clone();
+    }
+
     #[inline]
     fn optimized_clone(&self) -> Self {
         // See the homonymous function in `rclite` for more details.
 
-        let mut buffer: Box<MaybeUninit<ArcInner<T>>> =
-            Box::new(MaybeUninit::uninit());
+        let ptr = Self::alloc_inner();
 
-        let ptr = unsafe {
-            let ptr = buffer.as_mut_ptr();
+        unsafe {
+            let ptr = ptr.as_ptr();
             // Here we use `write()` instead of assignment via `=` to avoid
             // dropping the old, uninitialized value.
             addr_of_mut!((*ptr).data).write(T::clone(self));
-            (*ptr).counter = atomic::AtomicUsize::new(1);
-            NonNull::new_unchecked(Box::into_raw(buffer) as *mut ArcInner<T>)
-        };
+            addr_of_mut!((*ptr).counter).write(C::new(1));
+        }
 
         Arc { ptr }
     }
 }
 
-impl<T: core::fmt::Debug> core::fmt::Debug for Arc<T> {
+impl<T: core::fmt::Debug, C: RefCount> core::fmt::Debug for Arc<T, C> {
     #[inline]
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         core::fmt::Debug::fmt(&**self, f)
     }
 }
 
-impl<T: Default> Default for Arc<T> {
+impl<T: Default, C: RefCount> Default for Arc<T, C> {
     #[inline]
     fn default() -> Self {
         Self::new(T::default())
     }
 }
 
-impl<T> Clone for Arc<T> {
+impl<T, C: RefCount> Clone for Arc<T, C> {
     #[inline]
     fn clone(&self) -> Self {
-        let old = self.inner().counter.fetch_add(1, atomic::Ordering::Relaxed);
+        let old = C::increment(&self.inner().counter);
 
         // Check for overflow on the counter. See the `Arc` implementation in
         // `alloc` for more details.
@@ -133,7 +213,7 @@ impl<T> Clone for Arc<T> {
     }
 }
 
-impl<T> core::ops::Deref for Arc<T> {
+impl<T, C: RefCount> core::ops::Deref for Arc<T, C> {
     type Target = T;
 
     #[inline]
@@ -142,17 +222,127 @@ impl<T> core::ops::Deref for Arc<T> {
     }
 }
 
-impl<T> Drop for Arc<T> {
+impl<T, C: RefCount> Drop for Arc<T, C> {
     #[inline]
     fn drop(&mut self) {
-        let old = self.inner().counter.fetch_sub(1, atomic::Ordering::Release);
+        let old = C::decrement(&self.inner().counter);
 
         if old == 1 {
-            atomic::fence(atomic::Ordering::Acquire);
+            C::acquire_fence();
 
             // SAFETY: this is the last owner of the `Arc` so the memory has
-            // not yet been reclaimed by a previous call to `Box::from_raw()`.
-            let _ = unsafe { Box::from_raw(self.ptr.as_ptr()) };
+            // not yet been reclaimed by a previous call to
+            // `dealloc_inner()`.
+            unsafe { Self::dealloc_inner(self.ptr) };
+        }
+    }
+}
+
+/// How the nodes of a [`Tree`](super::Tree) are reference counted.
+///
+/// It's implemented for [`AtomicCount`], which lets trees be sent to and
+/// shared with other threads like an `Arc`, and for [`LocalCount`], which
+/// makes cloning and editing trees a bit faster but keeps them on the thread
+/// they were created on, like an `Rc`.
+///
+/// This trait is sealed and can't be implemented outside of this crate.
+pub trait RefCount:
+    sealed::Sealed + Copy + Clone + core::fmt::Debug + Default + 'static
+{
+}
+
+/// Reference counts the nodes of a [`Tree`](super::Tree) with atomics.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AtomicCount;
+
+/// Reference counts the nodes of a [`Tree`](super::Tree) with plain
+/// integers.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LocalCount;
+
+impl RefCount for AtomicCount {}
+
+impl RefCount for LocalCount {}
+
+mod sealed {
+    use core::cell::Cell;
+    use core::sync::atomic::{self, AtomicUsize, Ordering};
+
+    use super::{AtomicCount, LocalCount};
+
+    pub trait Sealed {
+        type Counter;
+
+        /// Synchronizes with the decrements of the other clones before the
+        /// data is dropped.
+        fn acquire_fence();
+
+        /// Decrements the count, returning its previous value.
+        fn decrement(counter: &Self::Counter) -> usize;
+
+        /// Returns the count, synchronizing with the decrements of the
+        /// other clones so that the data can be mutated in place if it's 1.
+        fn get(counter: &Self::Counter) -> usize;
+
+        /// Increments the count, returning its previous value.
+        fn increment(counter: &Self::Counter) -> usize;
+
+        fn new(count: usize) -> Self::Counter;
+    }
+
+    impl Sealed for AtomicCount {
+        type Counter = AtomicUsize;
+
+        #[inline]
+        fn acquire_fence() {
+            atomic::fence(Ordering::Acquire);
+        }
+
+        #[inline]
+        fn decrement(counter: &AtomicUsize) -> usize {
+            counter.fetch_sub(1, Ordering::Release)
+        }
+
+        #[inline]
+        fn get(counter: &AtomicUsize) -> usize {
+            counter.load(Ordering::Acquire)
+        }
+
+        #[inline]
+        fn increment(counter: &AtomicUsize) -> usize {
+            counter.fetch_add(1, Ordering::Relaxed)
+        }
+
+        #[inline]
+        fn new(count: usize) -> AtomicUsize {
+            AtomicUsize::new(count)
+        }
+    }
+
+    impl Sealed for LocalCount {
+        type Counter = Cell<usize>;
+
+        #[inline]
+        fn acquire_fence() {}
+
+        #[inline]
+        fn decrement(counter: &Cell<usize>) -> usize {
+            counter.replace(counter.get() - 1)
+        }
+
+        #[inline]
+        fn get(counter: &Cell<usize>) -> usize {
+            counter.get()
+        }
+
+        #[inline]
+        fn increment(counter: &Cell<usize>) -> usize {
+            counter.replace(counter.get() + 1)
+        }
+
+        #[inline]
+        fn new(count: usize) -> Cell<usize> {
+            Cell::new(count)
         }
     }
 }
@@ -1,7 +1,24 @@
 use core::fmt::Debug;
 use core::ops::{Add, AddAssign, RangeBounds, Sub, SubAssign};
 
+/// Types that can be summarized by a value which can be computed
+/// incrementally, like the byte and line counts of a piece of text.
+///
+/// Every node of a [`Tree`](super::Tree) caches the summary of its subtree,
+/// which is obtained by adding up the summaries of its children. Summaries
+/// have to behave like a monoid with an inverse:
+///
+/// - [`Default`] returns the summary of empty content, and adding it to any
+///   summary leaves the latter unchanged;
+///
+/// - addition is associative, i.e. `(a + b) + c == a + (b + c)`;
+///
+/// - subtraction undoes addition, i.e. `(a + b) - b == a`.
+///
+/// The summary of the concatenation of two pieces of content must be the sum
+/// of their summaries.
 pub trait Summarize: Debug {
+    /// The type of the summary.
     type Summary: Debug
         + Default
         + Clone
@@ -11,25 +28,48 @@ pub trait Summarize: Debug {
         + for<'a> SubAssign<&'a Self::Summary>
         + PartialEq<Self::Summary>;
 
+    /// Returns the summary of `self`.
     fn summarize(&self) -> Self::Summary;
 }
 
+/// Leaves with a base metric, which every other metric is ultimately
+/// converted from and to.
+///
+/// The base metric has to be able to address every position inside a leaf,
+/// e.g. the byte offsets of a text chunk. A leaf whose base measure is zero
+/// is considered empty.
 pub trait BaseMeasured: Summarize {
+    /// The base metric.
     type BaseMetric: Metric<Self::Summary>;
 }
 
+/// Leaves that can be borrowed as a slice, like a `String` as a `&str`.
+///
+/// The slice must have the same summary as the leaf it was borrowed from.
 pub trait AsSlice: Summarize {
+    /// The borrowed form of the leaf.
     type Slice<'a>: Copy + Summarize<Summary = Self::Summary>
     where
         Self: 'a;
 
+    /// Borrows the leaf as a slice.
     fn as_slice(&self) -> Self::Slice<'_>;
 }
 
+/// The types that can be stored in the leaves of a [`Tree`](super::Tree).
+///
+/// This is implemented for every type implementing [`Summarize`],
+/// [`BaseMeasured`] and [`AsSlice`].
 pub trait Leaf: Summarize + BaseMeasured + AsSlice {}
 
 impl<T: Summarize + BaseMeasured + AsSlice> Leaf for T {}
 
+/// Leaves that can be rebalanced with their siblings, which is needed to edit
+/// a [`Tree`](super::Tree).
+///
+/// A leaf is created from a slice when a slice of a `Tree` is converted into
+/// a new `Tree`, so the leaf created from a slice must have the same summary
+/// as the slice.
 pub trait BalancedLeaf: Leaf + for<'a> From<Self::Slice<'a>> {
     /// Returns whether the leaf node is too small to be on its own and should
     /// be rebalanced with another leaf.
@@ -38,16 +78,39 @@ pub trait BalancedLeaf: Leaf + for<'a> From<Self::Slice<'a>> {
     /// Balance two leaves.
     ///
     /// The `right` leaf can be left empty if the two leaves can be combined
-    /// into a single one.
+    /// into a single one. Otherwise neither of them can be underfilled
+    /// afterwards. The content of the two leaves, read from left to right,
+    /// must not change, and both summaries must be updated to match it.
     fn balance_leaves(
         left: (&mut Self, &mut Self::Summary),
         right: (&mut Self, &mut Self::Summary),
     );
 }
 
+/// Leaves whose content can be hashed, which lets
+/// [`Tree::diff_shared()`](super::Tree::diff_shared()) compare subtrees by
+/// content when the `node-hashes` feature is enabled.
+pub trait HashedLeaf: Leaf {
+    /// Returns a hash of the contents of the slice.
+    ///
+    /// Slices with the same contents must have the same hash, no matter how
+    /// their contents are laid out in memory.
+    fn hash_slice(slice: Self::Slice<'_>) -> u64;
+
+    /// Returns whether the two slices have the same contents, which is used
+    /// to confirm that subtrees with the same hash are really equal.
+    fn slices_eq(lhs: Self::Slice<'_>, rhs: Self::Slice<'_>) -> bool;
+}
+
+/// Leaves whose content can be replaced in a range of the `M`-metric, which
+/// is what [`Tree::replace()`](super::Tree::replace()) calls on the leaves
+/// intersecting the replaced range.
 pub trait ReplaceableLeaf<M: Metric<Self::Summary>>: BalancedLeaf {
+    /// The content that replaces a range of the leaf, e.g. a `&str` for a
+    /// text chunk.
     type Replacement<'a>;
 
+    /// The iterator over the leaves that didn't fit in the replaced leaf.
     type ExtraLeaves: ExactSizeIterator<Item = Self>;
 
     /// Replace the contents of the leaf in the range with the given
@@ -57,6 +120,8 @@ pub trait ReplaceableLeaf<M: Metric<Self::Summary>>: BalancedLeaf {
     /// iterator over the leaves to insert right after this leaf. Note that in
     /// this case both this leaf and all the leaves yielded by the iterator are
     /// assumed to not be underfilled.
+    ///
+    /// The `summary` must be updated to match the new content of the leaf.
     fn replace<R>(
         &mut self,
         summary: &mut Self::Summary,
@@ -66,9 +131,18 @@ pub trait ReplaceableLeaf<M: Metric<Self::Summary>>: BalancedLeaf {
     where
         R: RangeBounds<M>;
 
+    /// Removes the content of the leaf before `up_to`, updating its
+    /// `summary`.
     fn remove_up_to(&mut self, summary: &mut Self::Summary, up_to: M);
 }
 
+/// A quantity measured from a summary, used to index into a
+/// [`Tree`](super::Tree), like the byte or line offset of a `Rope`.
+///
+/// Measuring has to be compatible with the addition and subtraction of
+/// summaries, i.e. `measure(&(a + b)) == measure(&a) + measure(&b)`, and
+/// measures are never negative, so the `M`-offset of a position only grows
+/// when moving forward in the `Tree`.
 pub trait Metric<Summary: ?Sized>:
     Debug
     + Copy
@@ -94,12 +168,16 @@ pub trait Metric<Summary: ?Sized>:
 
 /// Metrics that can be used to slice `Tree`s and `TreeSlice`s.
 pub trait SlicingMetric<L: Leaf>: Metric<L::Summary> {
+    /// Returns the sub-slice of `slice` before `up_to`, together with its
+    /// summary. `summary` is the summary of the whole `slice`.
     fn slice_up_to<'a>(
         slice: L::Slice<'a>,
         up_to: Self,
         summary: &L::Summary,
     ) -> (L::Slice<'a>, L::Summary);
 
+    /// Returns the sub-slice of `slice` starting at `from`, together with its
+    /// summary. `summary` is the summary of the whole `slice`.
     fn slice_from<'a>(
         slice: L::Slice<'a>,
         from: Self,
@@ -1,22 +1,118 @@
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::ops::Range;
 
 use super::*;
 
 /// A self-balancing tree with metadata stored in each node.
+///
+/// The nodes are reference counted as specified by `C`, see [`RefCount`].
 #[derive(Default)]
-pub struct Tree<const ARITY: usize, L: Leaf> {
-    pub(super) root: Arc<Node<ARITY, L>>,
+pub struct Tree<const ARITY: usize, L: Leaf, C: RefCount = AtomicCount> {
+    pub(super) root: ArcNode<ARITY, L, C>,
+
+    /// The path to the leaf modified by the last call to
+    /// [`replace()`](Self::replace()), or `None` if the structure of the
+    /// tree has changed since then.
+    ///
+    /// It's boxed so that it only takes up a pointer in trees that are never
+    /// edited, and it's only allocated by the first edit.
+    hot_leaf: Option<Box<HotLeaf<L>>>,
+}
+
+/// The path from the root of a [`Tree`] to the leaf modified by its last
+/// edit.
+///
+/// Consecutive edits usually hit the same leaf, e.g. when typing, so as long
+/// as the edits don't split, merge or rebalance any node the next one can
+/// follow the path instead of searching for the leaf containing its range on
+/// every level of the tree.
+struct HotLeaf<L: Leaf> {
+    /// The index of every node on the path in its parent, starting from the
+    /// child of the root.
+    child_indices: Vec<usize>,
+
+    /// The summary of all the leaves before the hot one.
+    offset: L::Summary,
+}
+
+impl<L: Leaf> Default for HotLeaf<L> {
+    #[inline]
+    fn default() -> Self {
+        Self { child_indices: Vec::new(), offset: L::Summary::default() }
+    }
 }
 
-impl<const ARITY: usize, L: Leaf> Clone for Tree<ARITY, L> {
+impl<L: Leaf> HotLeaf<L> {
+    /// Returns the leaf at the end of the path.
+    #[inline]
+    fn leaf<'a, const N: usize, C: RefCount>(
+        root: &'a ArcNode<N, L, C>,
+        child_indices: &[usize],
+    ) -> &'a Lnode<L> {
+        child_indices
+            .iter()
+            .fold(root, |node, &idx| node.get_internal().child(idx))
+            .get_leaf()
+    }
+
+    /// Returns the summary of all the leaves before the one at the end of
+    /// the path.
+    #[inline]
+    fn offset<const N: usize, C: RefCount>(
+        root: &ArcNode<N, L, C>,
+        child_indices: &[usize],
+    ) -> L::Summary {
+        let mut offset = L::Summary::default();
+        let mut node = root;
+
+        for &idx in child_indices {
+            let inode = node.get_internal();
+
+            for child in &inode.children()[..idx] {
+                offset += child.summary();
+            }
+
+            node = inode.child(idx);
+        }
+
+        offset
+    }
+
+    /// Returns the given range relative to the start of the hot leaf, or
+    /// `None` if the leaf doesn't contain it.
+    #[inline]
+    fn range_in_leaf<const N: usize, M, C: RefCount>(
+        &self,
+        root: &ArcNode<N, L, C>,
+        range: Range<M>,
+    ) -> Option<Range<M>>
+    where
+        M: Metric<L::Summary>,
+    {
+        let start = M::measure(&self.offset);
+
+        if range.start < start {
+            return None;
+        }
+
+        let leaf = Self::leaf(root, &self.child_indices);
+
+        (range.end <= start + leaf.measure::<M>())
+            .then(|| range.start - start..range.end - start)
+    }
+}
+
+impl<const ARITY: usize, L: Leaf, C: RefCount> Clone for Tree<ARITY, L, C> {
     #[inline]
     fn clone(&self) -> Self {
-        Tree { root: Arc::clone(&self.root) }
+        Tree::with_root(Arc::clone(&self.root))
     }
 }
 
-impl<const ARITY: usize, L: Leaf> core::fmt::Debug for Tree<ARITY, L> {
+impl<const ARITY: usize, L: Leaf, C: RefCount> core::fmt::Debug
+    for Tree<ARITY, L, C>
+{
     #[inline]
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         if !f.alternate() {
@@ -27,11 +123,11 @@ impl<const ARITY: usize, L: Leaf> core::fmt::Debug for Tree<ARITY, L> {
     }
 }
 
-impl<const ARITY: usize, L: BalancedLeaf + Clone> From<TreeSlice<'_, ARITY, L>>
-    for Tree<ARITY, L>
+impl<const ARITY: usize, L: BalancedLeaf + Clone, C: RefCount>
+    From<TreeSlice<'_, ARITY, L, C>> for Tree<ARITY, L, C>
 {
     #[inline]
-    fn from(slice: TreeSlice<'_, ARITY, L>) -> Tree<ARITY, L> {
+    fn from(slice: TreeSlice<'_, ARITY, L, C>) -> Tree<ARITY, L, C> {
         let root = if slice.base_measure() == slice.root().base_measure() {
             // If the TreeSlice and its root have the same base measure it
             // means the TreeSlice spanned the whole Tree from which it was
@@ -64,11 +160,82 @@ impl<const ARITY: usize, L: BalancedLeaf + Clone> From<TreeSlice<'_, ARITY, L>>
             from_treeslice::into_tree_root(slice)
         };
 
-        Tree { root }
+        Tree::with_root(root)
     }
 }
 
-impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
+impl<const ARITY: usize, L: Leaf, C: RefCount> Tree<ARITY, L, C> {
+    /// Appends another `Tree` to the end of this one.
+    ///
+    /// The shallower of the two trees is attached to the side of the deeper
+    /// one at the matching depth, so this takes `O(log n)` time and all the
+    /// nodes of both trees except the ones along the seam are shared with
+    /// the result.
+    #[inline]
+    pub fn append(&mut self, mut other: Self)
+    where
+        L: BalancedLeaf + Clone,
+    {
+        if other.root.is_empty() {
+            return;
+        }
+
+        if self.root.is_empty() {
+            *self = other;
+            return;
+        }
+
+        self.hot_leaf = None;
+
+        use core::cmp::Ordering::*;
+
+        match self.root.depth().cmp(&other.root.depth()) {
+            Equal => {
+                Arc::make_mut(&mut self.root)
+                    .balance(Arc::make_mut(&mut other.root));
+
+                if !other.root.is_empty() {
+                    let left = Arc::clone(&self.root);
+                    self.root =
+                        Arc::new(Node::Internal(Inode::from_children([
+                            left, other.root,
+                        ])));
+                }
+            },
+
+            Greater => {
+                let extra = Arc::make_mut(&mut self.root)
+                    .get_internal_mut()
+                    .append_at_depth(other.root);
+
+                if let Some(extra) = extra {
+                    let left = Arc::clone(&self.root);
+                    self.root =
+                        Arc::new(Node::Internal(Inode::from_children([
+                            left,
+                            Arc::new(Node::Internal(extra)),
+                        ])));
+                }
+            },
+
+            Less => {
+                let extra = Arc::make_mut(&mut other.root)
+                    .get_internal_mut()
+                    .prepend_at_depth(Arc::clone(&self.root));
+
+                self.root = match extra {
+                    Some(extra) => {
+                        Arc::new(Node::Internal(Inode::from_children([
+                            Arc::new(Node::Internal(extra)),
+                            other.root,
+                        ])))
+                    },
+                    None => other.root,
+                };
+            },
+        }
+    }
+
     #[doc(hidden)]
     pub fn assert_invariants(&self) {
         match &*self.root {
@@ -86,6 +253,7 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
         }
     }
 
+    /// Returns the measure of this `Tree` in its base metric.
     #[inline]
     pub fn base_measure(&self) -> L::BaseMetric {
         self.measure::<L::BaseMetric>()
@@ -104,6 +272,76 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
         self.root.convert_measure(up_to)
     }
 
+    /// Returns the ranges of `old` and of `self` that are not covered by
+    /// subtrees shared between the two trees, sorted by their position.
+    ///
+    /// Each item is an `(old_range, new_range)` pair, with consecutive pairs
+    /// separated by content shared by both trees. Subtrees are compared by
+    /// identity and not by content, so the returned ranges can include
+    /// content that's equal in both trees.
+    ///
+    /// With the `node-hashes` feature subtrees are compared by the hash of
+    /// their contents instead, so subtrees storing the same content are
+    /// considered shared even if they're not the same allocation, e.g.
+    /// because one of the trees was serialized and deserialized. Two
+    /// different allocations with the same hash are then compared by their
+    /// summaries and leaves, so a hash collision is never reported as
+    /// shared content.
+    ///
+    /// This is meant to be called on trees that were derived from one
+    /// another by a series of edits, in which case the running time only
+    /// depends on the number and size of the edits and not on the size of
+    /// the trees.
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    pub fn diff_shared(
+        &self,
+        old: &Self,
+    ) -> Vec<(Range<L::BaseMetric>, Range<L::BaseMetric>)>
+    where
+        L: HashedLeaf,
+    {
+        let mut diff = Vec::new();
+
+        if !Arc::ptr_eq(&self.root, &old.root) {
+            shared_diff::diff_runs(
+                alloc::vec![&old.root],
+                L::BaseMetric::zero(),
+                alloc::vec![&self.root],
+                L::BaseMetric::zero(),
+                &mut diff,
+            );
+        }
+
+        diff
+    }
+
+    /// Returns the leaves in the subtrees accepted by `keep`, together with
+    /// the summary of all the leaves that come before them.
+    ///
+    /// `keep` is called with the summary of the leaves before a node and
+    /// with the summary of the node itself, and the node's children are only
+    /// visited if it returns `true`. This allows queries on the summaries to
+    /// skip entire subtrees, e.g. the ones whose summary shows they can't
+    /// contain a match.
+    #[inline]
+    pub fn filter_leaves<F>(
+        &self,
+        mut keep: F,
+    ) -> Vec<(L::Summary, L::Slice<'_>)>
+    where
+        F: FnMut(&L::Summary, &L::Summary) -> bool,
+    {
+        let mut leaves = Vec::new();
+        filter_leaves::visit(
+            &self.root,
+            L::Summary::default(),
+            &mut keep,
+            &mut leaves,
+        );
+        leaves
+    }
+
     /// Creates a new `Tree` from a sequence of leaves.
     ///
     /// If the iterator doesn't yield any items the `Tree` will contain a
@@ -119,7 +357,9 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
 
         let Some(first) = leaves.next() else { return Self::default() };
 
-        let Some(second) = leaves.next() else { return Self { root: first } };
+        let Some(second) = leaves.next() else {
+            return Self::with_root(first);
+        };
 
         let leaves = {
             let (lo, hi) = leaves.size_hint();
@@ -132,7 +372,7 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
 
         let root = Inode::from_nodes(leaves);
 
-        Self { root: Arc::new(Node::Internal(root)) }
+        Self::with_root(Arc::new(Node::Internal(root)))
     }
 
     /// Returns the leaf containing the `measure`-th unit of the `M`-metric,
@@ -147,6 +387,42 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
         self.root.leaf_at_measure(measure)
     }
 
+    /// Like [`leaf_at_measure()`](Self::leaf_at_measure()), but returns the
+    /// summary of all the leaves before the leaf instead of just their
+    /// `M`-measure.
+    #[inline]
+    pub fn leaf_and_offset_at_measure<M>(
+        &self,
+        measure: M,
+    ) -> (L::Slice<'_>, L::Summary)
+    where
+        M: Metric<L::Summary>,
+    {
+        debug_assert!(measure <= self.measure::<M>());
+
+        let mut offset = L::Summary::default();
+
+        let mut node = &self.root;
+
+        loop {
+            match &**node {
+                Node::Internal(inode) => {
+                    let (child_idx, _) =
+                        inode.child_at_measure(measure - M::measure(&offset));
+
+                    for child in &inode.children()[..child_idx] {
+                        offset += child.summary();
+                    }
+
+                    node = inode.child(child_idx);
+                },
+
+                Node::Leaf(leaf) => return (leaf.as_slice(), offset),
+            }
+        }
+    }
+
+    /// Returns the number of leaves in this `Tree`.
     #[inline]
     pub fn leaf_count(&self) -> usize {
         self.root.leaf_count()
@@ -154,7 +430,7 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
 
     /// Returns an iterator over the leaves of this `Tree`.
     #[inline]
-    pub fn leaves(&self) -> Leaves<'_, ARITY, L> {
+    pub fn leaves(&self) -> Leaves<'_, ARITY, L, C> {
         Leaves::from(self)
     }
 
@@ -168,7 +444,111 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
         M::measure(self.summary())
     }
 
+    /// Inserts the given replacement at the end of the `Tree`.
+    ///
+    /// This is the same as replacing the empty range at the end of the
+    /// `Tree`, except that the last leaf is reached by following the last
+    /// child of every node instead of searching for it.
+    #[inline]
+    pub fn push<M>(&mut self, replace_with: L::Replacement<'_>)
+    where
+        M: Metric<L::Summary>,
+        L: ReplaceableLeaf<M> + Clone,
+    {
+        let mut hot_leaf = self.hot_leaf.take().unwrap_or_default();
+
+        hot_leaf.child_indices.clear();
+
+        let mut node = &self.root;
+
+        while let Node::Internal(inode) = &**node {
+            hot_leaf.child_indices.push(inode.len() - 1);
+            node = inode.last();
+        }
+
+        let leaf = node.get_leaf();
+
+        let leaf_len = leaf.measure::<M>();
+
+        // All the leaves except the last one come before it.
+        hot_leaf.offset = self.root.summary().clone() - leaf.summary();
+
+        let mut is_clean = true;
+
+        let extras = tree_replace::replace_at_path(
+            &mut self.root,
+            &hot_leaf.child_indices,
+            leaf_len..leaf_len,
+            replace_with,
+            &mut is_clean,
+        );
+
+        if is_clean {
+            debug_assert!(extras.is_none());
+            self.hot_leaf = Some(hot_leaf);
+        } else {
+            self.push_extras(extras);
+        }
+    }
+
+    /// Recomputes the summaries for which `is_stale` returns `true`, calling
+    /// `refresh_leaf` on the leaves and adding up the summaries of the
+    /// children of the internal nodes.
+    ///
+    /// The summary of an internal node has to be stale whenever the one of
+    /// any of its children is, so only the nodes on the paths to the leaves
+    /// with a stale summary are visited.
+    #[cfg(any(feature = "content-hash", feature = "word-metric"))]
+    #[inline]
+    pub(crate) fn refresh_summaries<S, R>(
+        &mut self,
+        is_stale: S,
+        refresh_leaf: R,
+    ) where
+        S: Fn(&L::Summary) -> bool,
+        R: Fn(&mut L, &mut L::Summary),
+        L: Clone,
+    {
+        if is_stale(self.root.summary()) {
+            Arc::make_mut(&mut self.root)
+                .refresh_summary(&is_stale, &refresh_leaf);
+        }
+    }
+
+    /// Replaces the part of the range contained in every leaf intersecting
+    /// it with the replacement returned by calling `replacement` with that
+    /// part, without changing the structure of the `Tree`.
+    ///
+    /// The replacements must not change the `M`-measure of the leaves or
+    /// make them split or become underfilled.
+    #[track_caller]
+    #[inline]
+    pub fn replace_in_place<'a, M, F>(
+        &mut self,
+        range: Range<M>,
+        mut replacement: F,
+    ) where
+        M: Metric<L::Summary>,
+        L: ReplaceableLeaf<M> + Clone,
+        F: FnMut(Range<M>) -> L::Replacement<'a>,
+    {
+        // The summaries of the leaves before the hot one can change.
+        self.hot_leaf = None;
+
+        tree_replace::replace_in_place(
+            &mut self.root,
+            range,
+            M::zero(),
+            &mut replacement,
+        );
+    }
+
     /// Replaces a range of the `Tree` with the given replacement.
+    ///
+    /// If removing the range leaves the root with a single child, the root
+    /// is replaced by that child, and so on until it has at least two
+    /// children or it's a leaf, so the tree never keeps levels its content
+    /// doesn't need.
     #[track_caller]
     #[inline]
     pub fn replace<M>(
@@ -179,9 +559,67 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
         M: Metric<L::Summary>,
         L: ReplaceableLeaf<M> + Clone,
     {
-        if let Some(extras) =
-            tree_replace::replace(&mut self.root, range, replace_with)
-        {
+        let mut hot_leaf = match self.hot_leaf.take() {
+            Some(hot_leaf) => {
+                if let Some(leaf_range) =
+                    hot_leaf.range_in_leaf(&self.root, range.clone())
+                {
+                    let mut is_clean = true;
+
+                    let extras = tree_replace::replace_at_path(
+                        &mut self.root,
+                        &hot_leaf.child_indices,
+                        leaf_range,
+                        replace_with,
+                        &mut is_clean,
+                    );
+
+                    if is_clean {
+                        debug_assert!(extras.is_none());
+                        self.hot_leaf = Some(hot_leaf);
+                    } else {
+                        self.push_extras(extras);
+                    }
+
+                    return;
+                }
+
+                hot_leaf
+            },
+
+            None => Box::default(),
+        };
+
+        // Reuse the allocations of the old path for the new one.
+        hot_leaf.child_indices.clear();
+
+        let mut path = Some(core::mem::take(&mut hot_leaf.child_indices));
+
+        let extras = tree_replace::replace(
+            &mut self.root,
+            range,
+            replace_with,
+            &mut path,
+        );
+
+        match path {
+            Some(child_indices) => {
+                debug_assert!(extras.is_none());
+
+                hot_leaf.offset = HotLeaf::offset(&self.root, &child_indices);
+                hot_leaf.child_indices = child_indices;
+                self.hot_leaf = Some(hot_leaf);
+            },
+
+            None => self.push_extras(extras),
+        }
+    }
+
+    /// Pushes the extra nodes returned by replacing a range of the root
+    /// after it, adding a new root on top of them.
+    #[inline]
+    fn push_extras(&mut self, extras: Option<Vec<ArcNode<ARITY, L, C>>>) {
+        if let Some(extras) = extras {
             debug_assert!(extras
                 .iter()
                 .all(|n| n.depth() == self.root.depth()));
@@ -193,14 +631,19 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
     }
 
     #[inline]
-    pub(super) fn root(&self) -> &Arc<Node<ARITY, L>> {
+    pub(super) fn root(&self) -> &ArcNode<ARITY, L, C> {
         &self.root
     }
 
+    #[inline]
+    pub(super) fn with_root(root: ArcNode<ARITY, L, C>) -> Self {
+        Self { root, hot_leaf: None }
+    }
+
     /// Returns a slice of the `Tree` in the range of the given metric.
     #[track_caller]
     #[inline]
-    pub fn slice<M>(&self, range: Range<M>) -> TreeSlice<'_, ARITY, L>
+    pub fn slice<M>(&self, range: Range<M>) -> TreeSlice<'_, ARITY, L, C>
     where
         M: SlicingMetric<L>,
         L::BaseMetric: SlicingMetric<L>,
@@ -213,6 +656,7 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
         TreeSlice::from_range_in_root(&self.root, range)
     }
 
+    /// Returns the summary of all the leaves of this `Tree`.
     #[inline]
     pub fn summary(&self) -> &L::Summary {
         self.root.summary()
@@ -220,13 +664,278 @@ impl<const ARITY: usize, L: Leaf> Tree<ARITY, L> {
 
     /// Returns an iterator over the `M`-units of this `Tree`.
     #[inline]
-    pub fn units<M>(&self) -> Units<'_, ARITY, L, M>
+    pub fn units<M>(&self) -> Units<'_, ARITY, L, M, C>
     where
         M: Metric<L::Summary>,
         for<'d> L::Slice<'d>: Default,
     {
         Units::from(self)
     }
+
+    /// Calls `update_leaf` on the leaves at the given indices, which must be
+    /// sorted and in bounds.
+    ///
+    /// `update_leaf` must not change the summaries of the leaves, which is
+    /// why only the nodes on the paths to those leaves are visited, and only
+    /// those are cloned if they're shared with other trees.
+    #[inline]
+    pub(crate) fn update_leaves<F>(
+        &mut self,
+        leaf_indices: &[usize],
+        mut update_leaf: F,
+    ) where
+        F: FnMut(&mut L),
+        L: Clone,
+    {
+        if !leaf_indices.is_empty() {
+            Arc::make_mut(&mut self.root).update_leaves(
+                leaf_indices,
+                0,
+                &mut update_leaf,
+            );
+        }
+    }
+}
+
+mod shared_diff {
+    //! This module implements [`Tree::diff_shared`].
+    //!
+    //! The two trees are compared one level at a time, starting from their
+    //! roots. At each step the tallest nodes are matched by identity, and
+    //! the nodes between two matches are replaced by their children and
+    //! compared recursively. Since a shared subtree has the same height in
+    //! both trees, it's always compared with its copy before being expanded.
+    //!
+    //! Nodes are matched by address, or by the hash of their contents with
+    //! the `node-hashes` feature. In the latter case a match between two
+    //! different allocations is only accepted after checking that their
+    //! summaries and leaves are equal, so that a hash collision can't hide
+    //! an edit.
+
+    use alloc::collections::BTreeMap;
+
+    use super::*;
+
+    type Run<'a, const N: usize, L, C> = Vec<&'a ArcNode<N, L, C>>;
+
+    type Diff<M> = Vec<(Range<M>, Range<M>)>;
+
+    #[inline]
+    fn measure<const N: usize, L: Leaf, C: RefCount>(
+        run: &[&ArcNode<N, L, C>],
+    ) -> L::BaseMetric {
+        run.iter()
+            .fold(L::BaseMetric::zero(), |acc, node| acc + node.base_measure())
+    }
+
+    /// Returns the key used to match the nodes of the two trees.
+    #[inline]
+    fn key<const N: usize, L: HashedLeaf, C: RefCount>(
+        node: &ArcNode<N, L, C>,
+    ) -> u64 {
+        #[cfg(feature = "node-hashes")]
+        {
+            node.hash()
+        }
+
+        #[cfg(not(feature = "node-hashes"))]
+        {
+            Arc::addr(node) as u64
+        }
+    }
+
+    /// Returns whether two nodes with the same key store the same content.
+    #[inline]
+    fn is_match<const N: usize, L: HashedLeaf, C: RefCount>(
+        old: &ArcNode<N, L, C>,
+        new: &ArcNode<N, L, C>,
+    ) -> bool {
+        if Arc::ptr_eq(old, new) {
+            return true;
+        }
+
+        if cfg!(not(feature = "node-hashes")) || old.summary() != new.summary()
+        {
+            return false;
+        }
+
+        let (mut old_leaves, mut new_leaves) = (Vec::new(), Vec::new());
+        push_leaves(old, &mut old_leaves);
+        push_leaves(new, &mut new_leaves);
+
+        old_leaves.len() == new_leaves.len()
+            && old_leaves
+                .into_iter()
+                .zip(new_leaves)
+                .all(|(old, new)| L::slices_eq(old, new))
+    }
+
+    /// Pushes the slices of all the leaves of `node` onto `leaves`, in order.
+    #[inline]
+    fn push_leaves<'a, const N: usize, L: Leaf, C: RefCount>(
+        node: &'a Node<N, L, C>,
+        leaves: &mut Vec<L::Slice<'a>>,
+    ) {
+        match node {
+            Node::Internal(inode) => {
+                for child in inode.children() {
+                    push_leaves(child, leaves);
+                }
+            },
+            Node::Leaf(leaf) => leaves.push(leaf.as_slice()),
+        }
+    }
+
+    #[inline]
+    fn expand<'a, const N: usize, L: Leaf, C: RefCount>(
+        run: &[&'a ArcNode<N, L, C>],
+        height: usize,
+    ) -> Run<'a, N, L, C> {
+        let mut expanded = Vec::with_capacity(run.len() * N);
+
+        for &node in run {
+            match &**node {
+                Node::Internal(inode) if node.depth() == height => {
+                    expanded.extend(inode.children())
+                },
+                _ => expanded.push(node),
+            }
+        }
+
+        expanded
+    }
+
+    pub(super) fn diff_runs<'a, const N: usize, L: HashedLeaf, C: RefCount>(
+        old: Run<'a, N, L, C>,
+        old_start: L::BaseMetric,
+        new: Run<'a, N, L, C>,
+        new_start: L::BaseMetric,
+        diff: &mut Diff<L::BaseMetric>,
+    ) {
+        if old.is_empty() && new.is_empty() {
+            return;
+        }
+
+        if old.is_empty() || new.is_empty() {
+            let old_end = old_start + measure(&old);
+            let new_end = new_start + measure(&new);
+            diff.push((old_start..old_end, new_start..new_end));
+            return;
+        }
+
+        let height = old
+            .iter()
+            .chain(new.iter())
+            .map(|node| node.depth())
+            .max()
+            .unwrap();
+
+        let old_indices = old
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.depth() == height)
+            .map(|(idx, node)| (key(node), idx))
+            .collect::<BTreeMap<_, _>>();
+
+        // The `(old_idx, new_idx)` pairs of the shared nodes, in increasing
+        // order.
+        let mut matches = Vec::new();
+
+        for (new_idx, node) in new.iter().enumerate() {
+            if node.depth() != height {
+                continue;
+            }
+
+            if let Some(&old_idx) = old_indices.get(&key(node)) {
+                if matches.last().map_or(true, |&(last, _)| old_idx > last)
+                    && is_match(old[old_idx], node)
+                {
+                    matches.push((old_idx, new_idx));
+                }
+            }
+        }
+
+        let mut old_pos = 0;
+        let mut new_pos = 0;
+        let mut old_offset = old_start;
+        let mut new_offset = new_start;
+
+        let end = (old.len(), new.len());
+
+        for (old_idx, new_idx) in matches.into_iter().chain(Some(end)) {
+            let old_run = &old[old_pos..old_idx];
+            let new_run = &new[new_pos..new_idx];
+
+            let old_run_measure = measure(old_run);
+            let new_run_measure = measure(new_run);
+
+            if height == 0 || old_run.is_empty() || new_run.is_empty() {
+                if !old_run.is_empty() || !new_run.is_empty() {
+                    diff.push((
+                        old_offset..old_offset + old_run_measure,
+                        new_offset..new_offset + new_run_measure,
+                    ));
+                }
+            } else {
+                diff_runs(
+                    expand(old_run, height),
+                    old_offset,
+                    expand(new_run, height),
+                    new_offset,
+                    diff,
+                );
+            }
+
+            old_offset += old_run_measure;
+            new_offset += new_run_measure;
+
+            if (old_idx, new_idx) != end {
+                let shared = old[old_idx].base_measure();
+                old_offset += shared;
+                new_offset += shared;
+            }
+
+            old_pos = old_idx + 1;
+            new_pos = new_idx + 1;
+        }
+    }
+}
+
+mod filter_leaves {
+    //! Contains the recursive traversal used by [`Tree::filter_leaves()`].
+
+    use super::*;
+
+    /// Pushes the leaves of `node` accepted by `keep` onto `leaves`, returning
+    /// the summary of everything up to the end of `node`.
+    pub(super) fn visit<'a, const N: usize, L, C: RefCount, F>(
+        node: &'a ArcNode<N, L, C>,
+        offset: L::Summary,
+        keep: &mut F,
+        leaves: &mut Vec<(L::Summary, L::Slice<'a>)>,
+    ) -> L::Summary
+    where
+        L: Leaf,
+        F: FnMut(&L::Summary, &L::Summary) -> bool,
+    {
+        if !keep(&offset, node.summary()) {
+            return offset + node.summary();
+        }
+
+        match &**node {
+            Node::Internal(inode) => {
+                inode.children().iter().fold(offset, |offset, child| {
+                    visit(child, offset, keep, leaves)
+                })
+            },
+
+            Node::Leaf(leaf) => {
+                let end = offset.clone() + leaf.summary();
+                leaves.push((offset, leaf.as_slice()));
+                end
+            },
+        }
+    }
 }
 
 mod from_treeslice {
@@ -243,9 +952,13 @@ mod from_treeslice {
     /// This function can only be called if the slice spans at least 3 leaves.
     /// Leaf counts of 1 and 2 must be handled by the caller.
     #[inline]
-    pub(super) fn into_tree_root<const N: usize, L: BalancedLeaf + Clone>(
-        slice: TreeSlice<'_, N, L>,
-    ) -> Arc<Node<N, L>> {
+    pub(super) fn into_tree_root<
+        const N: usize,
+        L: BalancedLeaf + Clone,
+        C: RefCount,
+    >(
+        slice: TreeSlice<'_, N, L, C>,
+    ) -> ArcNode<N, L, C> {
         debug_assert!(slice.leaf_count() >= 3);
 
         let (root, invalid_in_first, invalid_in_last) = cut_tree_slice(slice);
@@ -263,12 +976,11 @@ mod from_treeslice {
             Node::replace_with_single_child(&mut root);
         }
 
-        if invalid_in_last > 0 {
+        // Balancing the left side can leave the root with a single leaf,
+        // which is then pulled up. In that case the root is also the last
+        // leaf and there's nothing left to balance.
+        if invalid_in_last > 0 && root.is_internal() {
             {
-                // For the root to become a leaf node after the previous call
-                // to `pull_up_singular` the TreeSlice would've had to span 2
-                // leaves, and that case case should have already been handled
-                // before calling this function.
                 let inode =
                     Arc::get_mut(&mut root).unwrap().get_internal_mut();
 
@@ -298,9 +1010,9 @@ mod from_treeslice {
     ///
     /// Panics if the slice spans less than 3 leaves.
     #[inline]
-    fn cut_tree_slice<const N: usize, L: BalancedLeaf + Clone>(
-        slice: TreeSlice<'_, N, L>,
-    ) -> (Inode<N, L>, usize, usize) {
+    fn cut_tree_slice<const N: usize, L: BalancedLeaf + Clone, C: RefCount>(
+        slice: TreeSlice<'_, N, L, C>,
+    ) -> (Inode<N, L, C>, usize, usize) {
         debug_assert!(slice.leaf_count() >= 3);
 
         let mut root = Inode::empty();
@@ -373,13 +1085,13 @@ mod from_treeslice {
     /// Recursively removes all the nodes before `take_from`, replacing the
     /// leaf at `take_from` with `start_slice`. Returns the resulting node.
     #[inline]
-    fn cut_start_rec<const N: usize, L: BalancedLeaf + Clone>(
-        node: &Arc<Node<N, L>>,
+    fn cut_start_rec<const N: usize, L: BalancedLeaf + Clone, C: RefCount>(
+        node: &ArcNode<N, L, C>,
         take_from: L::BaseMetric,
         start_slice: L::Slice<'_>,
         start_summary: L::Summary,
         invalid_nodes: &mut usize,
-    ) -> Arc<Node<N, L>> {
+    ) -> ArcNode<N, L, C> {
         match &**node {
             Node::Internal(i) => {
                 let mut inode = Inode::empty();
@@ -441,13 +1153,13 @@ mod from_treeslice {
     /// Recursively removes all the nodes after `take_up_to`, replacing the
     /// leaf at `take_up_to` with `end_slice`. Returns the resulting node.
     #[inline]
-    fn cut_end_rec<const N: usize, L: BalancedLeaf + Clone>(
-        node: &Arc<Node<N, L>>,
+    fn cut_end_rec<const N: usize, L: BalancedLeaf + Clone, C: RefCount>(
+        node: &ArcNode<N, L, C>,
         take_up_to: L::BaseMetric,
         end_slice: L::Slice<'_>,
         end_summary: L::Summary,
         invalid_nodes: &mut usize,
-    ) -> Arc<Node<N, L>> {
+    ) -> ArcNode<N, L, C> {
         match &**node {
             Node::Internal(i) => {
                 let mut inode = Inode::empty();
@@ -518,13 +1230,18 @@ mod tree_replace {
     /// Viceversa, if the replacement was deletion-heavy it'll usually return
     /// `None`, and `node` could be underfilled or even at a lower depth than
     /// it was before calling this function.
+    ///
+    /// The index of every child descended into is pushed to `path`, which is
+    /// set to `None` if the range spans more than one leaf or if any node had
+    /// to be split, merged or rebalanced.
     #[track_caller]
     #[inline]
-    pub(super) fn replace<const N: usize, M, L>(
-        node: &mut Arc<Node<N, L>>,
+    pub(super) fn replace<const N: usize, M, L, C: RefCount>(
+        node: &mut ArcNode<N, L, C>,
         mut range: Range<M>,
         replace_with: L::Replacement<'_>,
-    ) -> Option<Vec<Arc<Node<N, L>>>>
+        path: &mut Option<Vec<usize>>,
+    ) -> Option<Vec<ArcNode<N, L, C>>>
     where
         M: Metric<L::Summary>,
         L: ReplaceableLeaf<M> + Clone,
@@ -533,15 +1250,21 @@ mod tree_replace {
             Node::Internal(inode) => inode,
 
             Node::Leaf(leaf) => {
-                return leaf.replace(range, replace_with).map(|extras| {
-                    extras.map(Node::Leaf).map(Arc::new).collect()
-                });
+                let extras = replace_in_leaf(leaf, range, replace_with);
+
+                if extras.is_some() {
+                    *path = None;
+                }
+
+                return extras;
             },
         };
 
         let Some((child_idx, offset)) =
             inode.child_containing_range(range.clone())
         else {
+            *path = None;
+
             let extras = replace_range_in_deepest(inode, range, replace_with);
 
             Node::replace_with_single_child(node);
@@ -552,10 +1275,166 @@ mod tree_replace {
         range.start -= offset;
         range.end -= offset;
 
+        if let Some(path) = path {
+            path.push(child_idx);
+        }
+
         let extras = inode.with_child_mut(child_idx, |child| {
-            replace(child, range, replace_with)
+            replace(child, range, replace_with, path)
         });
 
+        let mut is_clean = path.is_some();
+
+        let extras = fix_child(node, child_idx, extras, &mut is_clean);
+
+        if !is_clean {
+            *path = None;
+        }
+
+        extras
+    }
+
+    /// Like [`replace`], but instead of searching for the leaf containing
+    /// the range it descends into the children at the given indices, and the
+    /// range is relative to the start of the leaf at the end of the path.
+    ///
+    /// `is_clean` is set to `false` if any node had to be split, merged or
+    /// rebalanced, in which case the path is no longer valid.
+    #[track_caller]
+    #[inline]
+    pub(super) fn replace_at_path<const N: usize, M, L, C: RefCount>(
+        node: &mut ArcNode<N, L, C>,
+        child_indices: &[usize],
+        range: Range<M>,
+        replace_with: L::Replacement<'_>,
+        is_clean: &mut bool,
+    ) -> Option<Vec<ArcNode<N, L, C>>>
+    where
+        M: Metric<L::Summary>,
+        L: ReplaceableLeaf<M> + Clone,
+    {
+        let Some((&child_idx, child_indices)) = child_indices.split_first()
+        else {
+            let leaf = Arc::make_mut(node).get_leaf_mut();
+
+            let extras = replace_in_leaf(leaf, range, replace_with);
+
+            if extras.is_some() {
+                *is_clean = false;
+            }
+
+            return extras;
+        };
+
+        let extras = Arc::make_mut(node).get_internal_mut().with_child_mut(
+            child_idx,
+            |child| {
+                replace_at_path(
+                    child,
+                    child_indices,
+                    range,
+                    replace_with,
+                    is_clean,
+                )
+            },
+        );
+
+        fix_child(node, child_idx, extras, is_clean)
+    }
+
+    /// Implements [`Tree::replace_in_place()`], where `offset` is the
+    /// `M`-offset of `node` in the tree.
+    #[track_caller]
+    #[inline]
+    pub(super) fn replace_in_place<'a, const N: usize, M, L, C: RefCount, F>(
+        node: &mut ArcNode<N, L, C>,
+        range: Range<M>,
+        offset: M,
+        replacement: &mut F,
+    ) where
+        M: Metric<L::Summary>,
+        L: ReplaceableLeaf<M> + Clone,
+        F: FnMut(Range<M>) -> L::Replacement<'a>,
+    {
+        let inode = match Arc::make_mut(node) {
+            Node::Internal(inode) => inode,
+
+            Node::Leaf(leaf) => {
+                let replace_with = replacement(range.clone());
+
+                let extras = leaf.replace(
+                    range.start - offset..range.end - offset,
+                    replace_with,
+                );
+
+                assert!(extras.is_none(), "the leaf was split");
+
+                return;
+            },
+        };
+
+        let mut child_offset = offset;
+
+        for child_idx in 0..inode.len() {
+            let child_end = child_offset + inode.child(child_idx).measure();
+
+            if child_end > range.start {
+                let child_range =
+                    range.start.max(child_offset)..range.end.min(child_end);
+
+                inode.with_child_mut(child_idx, |child| {
+                    replace_in_place(
+                        child,
+                        child_range,
+                        child_offset,
+                        replacement,
+                    )
+                });
+
+                if child_end >= range.end {
+                    break;
+                }
+            }
+
+            child_offset = child_end;
+        }
+    }
+
+    /// Replaces the range of the leaf, returning the extra leaves to insert
+    /// after it (if any) as nodes.
+    #[track_caller]
+    #[inline]
+    fn replace_in_leaf<const N: usize, M, L, C: RefCount>(
+        leaf: &mut Lnode<L>,
+        range: Range<M>,
+        replace_with: L::Replacement<'_>,
+    ) -> Option<Vec<ArcNode<N, L, C>>>
+    where
+        M: Metric<L::Summary>,
+        L: ReplaceableLeaf<M> + Clone,
+    {
+        leaf.replace(range, replace_with)
+            .map(|extras| extras.map(Node::Leaf).map(Arc::new).collect())
+    }
+
+    /// Rebalances the inode in `node` after its `child_idx`-th child was
+    /// edited, which returned the given extra nodes.
+    ///
+    /// Sets `is_clean` to `false` if the inode's children had to be
+    /// changed.
+    #[inline]
+    fn fix_child<const N: usize, L, C: RefCount>(
+        node: &mut ArcNode<N, L, C>,
+        child_idx: usize,
+        extras: Option<Vec<ArcNode<N, L, C>>>,
+        is_clean: &mut bool,
+    ) -> Option<Vec<ArcNode<N, L, C>>>
+    where
+        L: BalancedLeaf + Clone,
+    {
+        // The node was already made unique when descending into the child.
+        let inode = Arc::make_mut(node).get_internal_mut();
+
         let child = inode.child(child_idx);
 
         // Case 1: there are some extra child nodes to insert *after* the child
@@ -565,6 +1444,8 @@ mod tree_replace {
                 .iter()
                 .all(|n| n.depth() == inode.depth() - 1));
 
+            *is_clean = false;
+
             inode.insert_children(child_idx + 1, extras).map(|extras| {
                 extras.map(Node::Internal).map(Arc::new).collect()
             })
@@ -573,6 +1454,7 @@ mod tree_replace {
         // and needs to be rebalanced with one if its siblings.
         else if child.depth() == inode.depth() - 1 && child.is_underfilled()
         {
+            *is_clean = false;
             inode.balance_child(child_idx);
             Node::replace_with_single_child(node);
             None
@@ -581,6 +1463,7 @@ mod tree_replace {
         // needs to be appended/prepended to another child.
         else if child.depth() < inode.depth() - 1 {
             debug_assert!(inode.depth() >= 2);
+            *is_clean = false;
             let child = inode.remove(child_idx);
             inode.insert_at_depth(child_idx, child);
             Node::replace_with_single_child(node);
@@ -603,11 +1486,11 @@ mod tree_replace {
     /// even contain a single child) if it was deletion-heavy.
     #[track_caller]
     #[inline]
-    fn replace_range_in_deepest<const N: usize, M, L>(
-        inode: &mut Inode<N, L>,
+    fn replace_range_in_deepest<const N: usize, M, L, C: RefCount>(
+        inode: &mut Inode<N, L, C>,
         range: Range<M>,
         replace_with: L::Replacement<'_>,
-    ) -> Option<Vec<Arc<Node<N, L>>>>
+    ) -> Option<Vec<ArcNode<N, L, C>>>
     where
         M: Metric<L::Summary>,
         L: ReplaceableLeaf<M> + Clone,
@@ -711,11 +1594,16 @@ mod tree_replace {
     /// insertion-heavy.
     #[track_caller]
     #[inline]
-    fn inode_replace_nodes_in_start_and_end_subtrees<const N: usize, M, L>(
-        inode: &mut Inode<N, L>,
+    fn inode_replace_nodes_in_start_and_end_subtrees<
+        const N: usize,
+        M,
+        L,
+        C: RefCount,
+    >(
+        inode: &mut Inode<N, L, C>,
         range: Range<M>,
         replace_with: L::Replacement<'_>,
-    ) -> (usize, usize, Option<Vec<Arc<Node<N, L>>>>)
+    ) -> (usize, usize, Option<Vec<ArcNode<N, L, C>>>)
     where
         M: Metric<L::Summary>,
         L: ReplaceableLeaf<M> + Clone,
@@ -804,12 +1692,12 @@ mod tree_replace {
     /// removes them if there are no extra leaves.
     #[track_caller]
     #[inline]
-    fn replace_nodes_in_start_subtree<const N: usize, M, L>(
-        node: &mut Node<N, L>,
+    fn replace_nodes_in_start_subtree<const N: usize, M, L, C: RefCount>(
+        node: &mut Node<N, L, C>,
         replace_from: M,
         replace_with: L::Replacement<'_>,
         should_rebalance: &mut bool,
-    ) -> Option<impl ExactSizeIterator<Item = Arc<Node<N, L>>>>
+    ) -> Option<impl ExactSizeIterator<Item = ArcNode<N, L, C>>>
     where
         M: Metric<L::Summary>,
         L: ReplaceableLeaf<M> + Clone,
@@ -863,10 +1751,10 @@ mod tree_replace {
     /// nodes before that leaf, or removes them if there are no extra leaves.
     #[track_caller]
     #[inline]
-    fn replace_nodes_in_end_subtree<const N: usize, M, L>(
-        node: &mut Node<N, L>,
+    fn replace_nodes_in_end_subtree<const N: usize, M, L, C: RefCount>(
+        node: &mut Node<N, L, C>,
         replace_up_to: M,
-        extra_leaves: &mut Option<Vec<Arc<Node<N, L>>>>,
+        extra_leaves: &mut Option<Vec<ArcNode<N, L, C>>>,
         should_rebalance: &mut bool,
     ) where
         M: Metric<L::Summary>,
@@ -952,12 +1840,12 @@ mod tree_replace {
     /// If the iterator is exhausted before the whole range has been replaced
     /// it'll just remove the remaining children.
     #[inline]
-    fn replace_child_range_with_leaves<const N: usize, L, I>(
-        inode: &mut Inode<N, L>,
+    fn replace_child_range_with_leaves<const N: usize, L, C: RefCount, I>(
+        inode: &mut Inode<N, L, C>,
         child_range: Range<usize>,
         leaves: &mut I,
     ) where
-        I: Iterator<Item = Arc<Node<N, L>>> + ExactSizeIterator,
+        I: Iterator<Item = ArcNode<N, L, C>> + ExactSizeIterator,
         L: BalancedLeaf + Clone,
     {
         debug_assert!(child_range.start >= 1);
@@ -1036,10 +1924,14 @@ mod tree_replace {
     /// replaces the children in the given index range going backwards, i.e.
     /// starting from the last child.
     #[inline]
-    fn replace_child_range_with_leaves_from_back<const N: usize, L>(
-        inode: &mut Inode<N, L>,
+    fn replace_child_range_with_leaves_from_back<
+        const N: usize,
+        L,
+        C: RefCount,
+    >(
+        inode: &mut Inode<N, L, C>,
         child_range: Range<usize>,
-        leaves: &mut Vec<Arc<Node<N, L>>>,
+        leaves: &mut Vec<ArcNode<N, L, C>>,
     ) where
         L: BalancedLeaf + Clone,
     {
@@ -1124,8 +2016,8 @@ mod tree_replace {
     /// The left and right side of the seam are under the children before and
     /// after the `seam_offset`, respectively.
     #[inline]
-    fn fix_seam_between_subtrees<const N: usize, L>(
-        inode: &mut Inode<N, L>,
+    fn fix_seam_between_subtrees<const N: usize, L, C: RefCount>(
+        inode: &mut Inode<N, L, C>,
         seam_offset: usize,
         start_should_rebalance: bool,
         end_should_rebalance: bool,
@@ -1329,21 +2221,21 @@ mod tree_replace {
         /// The minimum number of leaves required by [`Inode::from_nodes()`] to
         /// produce an internal node of the target depth with at least
         /// [`Inode::min_children()`] children.
-        const fn min_leaves_for_depth<const N: usize, L: Leaf>(
+        const fn min_leaves_for_depth<const N: usize, L: Leaf, C: RefCount>(
             target_depth: usize,
         ) -> usize {
-            (Inode::<N, L>::min_children() - 1)
-                * max_leaves_for_depth::<N, L>(target_depth - 1)
+            (Inode::<N, L, C>::min_children() - 1)
+                * max_leaves_for_depth::<N, L, C>(target_depth - 1)
                 + 1
         }
 
         /// The maximum number of leaves that can be fed to
         /// [`Inode::from_nodes()`] to produce an internal node of the target
         /// depth with no more than [`Inode::max_children()`] children.
-        const fn max_leaves_for_depth<const N: usize, L: Leaf>(
+        const fn max_leaves_for_depth<const N: usize, L: Leaf, C: RefCount>(
             target_depth: usize,
         ) -> usize {
-            Inode::<N, L>::max_children().pow(target_depth as u32)
+            Inode::<N, L, C>::max_children().pow(target_depth as u32)
         }
 
         /// Transforms an iterator over leaf nodes into internal nodes at a
@@ -1351,10 +2243,11 @@ mod tree_replace {
         /// `min_children` and `max_children` children, except for the last
         /// node which can be at a lower depth than the target (can even be a
         /// leaf node) and contain less than `min_children` children.
-        pub(super) struct TargetDepth<const N: usize, L, Leaves>
+        pub(super) struct TargetDepth<const N: usize, L, C, Leaves>
         where
             L: Leaf,
-            Leaves: ExactSizeIterator<Item = Arc<Node<N, L>>>,
+            C: RefCount,
+            Leaves: ExactSizeIterator<Item = ArcNode<N, L, C>>,
         {
             leaves: Leaves,
             target_depth: usize,
@@ -1362,10 +2255,11 @@ mod tree_replace {
             max_leaves_for_depth: usize,
         }
 
-        impl<const N: usize, L, Leaves> TargetDepth<N, L, Leaves>
+        impl<const N: usize, L, C, Leaves> TargetDepth<N, L, C, Leaves>
         where
             L: Leaf,
-            Leaves: ExactSizeIterator<Item = Arc<Node<N, L>>>,
+            C: RefCount,
+            Leaves: ExactSizeIterator<Item = ArcNode<N, L, C>>,
         {
             /// # Panics
             ///
@@ -1381,22 +2275,23 @@ mod tree_replace {
                 Self {
                     leaves,
                     target_depth,
-                    min_leaves_for_depth: min_leaves_for_depth::<N, L>(
+                    min_leaves_for_depth: min_leaves_for_depth::<N, L, C>(
                         target_depth,
                     ),
-                    max_leaves_for_depth: max_leaves_for_depth::<N, L>(
+                    max_leaves_for_depth: max_leaves_for_depth::<N, L, C>(
                         target_depth,
                     ),
                 }
             }
         }
 
-        impl<const N: usize, L, Leaves> Iterator for TargetDepth<N, L, Leaves>
+        impl<const N: usize, L, C, Leaves> Iterator for TargetDepth<N, L, C, Leaves>
         where
             L: Leaf,
-            Leaves: ExactSizeIterator<Item = Arc<Node<N, L>>>,
+            C: RefCount,
+            Leaves: ExactSizeIterator<Item = ArcNode<N, L, C>>,
         {
-            type Item = Arc<Node<N, L>>;
+            type Item = ArcNode<N, L, C>;
 
             #[inline]
             fn next(&mut self) -> Option<Self::Item> {
@@ -1412,11 +2307,12 @@ mod tree_replace {
 
                         debug_assert!(
                             last.depth() < self.target_depth
-                                || last.len() < Inode::<N, L>::min_children()
+                                || last.len()
+                                    < Inode::<N, L, C>::min_children()
                         );
 
                         debug_assert!(
-                            last.len() <= Inode::<N, L>::max_children()
+                            last.len() <= Inode::<N, L, C>::max_children()
                         );
 
                         Arc::new(Node::Internal(last))
@@ -1431,11 +2327,11 @@ mod tree_replace {
                     debug_assert_eq!(inode.depth(), self.target_depth);
 
                     debug_assert!(
-                        inode.len() >= Inode::<N, L>::min_children()
+                        inode.len() >= Inode::<N, L, C>::min_children()
                     );
 
                     debug_assert!(
-                        inode.len() <= Inode::<N, L>::max_children()
+                        inode.len() <= Inode::<N, L, C>::max_children()
                     );
 
                     Some(Arc::new(Node::Internal(inode)))
@@ -1446,26 +2342,28 @@ mod tree_replace {
         /// Same as `TargetDepth` except the inodes are constructed from back
         /// to front instead of front to back by draining the nodes off of the
         /// vector.
-        pub(super) struct TargetDepthFromBack<'a, const N: usize, L>
+        pub(super) struct TargetDepthFromBack<'a, const N: usize, L, C>
         where
             L: Leaf,
+            C: RefCount,
         {
-            leaves: &'a mut Vec<Arc<Node<N, L>>>,
+            leaves: &'a mut Vec<ArcNode<N, L, C>>,
             target_depth: usize,
             min_leaves_for_depth: usize,
             max_leaves_for_depth: usize,
         }
 
-        impl<'a, const N: usize, L> TargetDepthFromBack<'a, N, L>
+        impl<'a, const N: usize, L, C> TargetDepthFromBack<'a, N, L, C>
         where
             L: Leaf,
+            C: RefCount,
         {
             /// # Panics
             ///
             /// Panics if `leaves` is empty or if the target_depth is 0.
             #[inline]
             pub(in crate::tree) fn new(
-                leaves: &'a mut Vec<Arc<Node<N, L>>>,
+                leaves: &'a mut Vec<ArcNode<N, L, C>>,
                 target_depth: usize,
             ) -> Self {
                 debug_assert!(!leaves.is_empty());
@@ -1474,21 +2372,22 @@ mod tree_replace {
                 Self {
                     leaves,
                     target_depth,
-                    min_leaves_for_depth: min_leaves_for_depth::<N, L>(
+                    min_leaves_for_depth: min_leaves_for_depth::<N, L, C>(
                         target_depth,
                     ),
-                    max_leaves_for_depth: max_leaves_for_depth::<N, L>(
+                    max_leaves_for_depth: max_leaves_for_depth::<N, L, C>(
                         target_depth,
                     ),
                 }
             }
         }
 
-        impl<const N: usize, L> Iterator for TargetDepthFromBack<'_, N, L>
+        impl<const N: usize, L, C> Iterator for TargetDepthFromBack<'_, N, L, C>
         where
             L: Leaf,
+            C: RefCount,
         {
-            type Item = Arc<Node<N, L>>;
+            type Item = ArcNode<N, L, C>;
 
             #[inline]
             fn next(&mut self) -> Option<Self::Item> {
@@ -1507,11 +2406,12 @@ mod tree_replace {
 
                         debug_assert!(
                             last.depth() < self.target_depth
-                                || last.len() < Inode::<N, L>::min_children()
+                                || last.len()
+                                    < Inode::<N, L, C>::min_children()
                         );
 
                         debug_assert!(
-                            last.len() <= Inode::<N, L>::max_children()
+                            last.len() <= Inode::<N, L, C>::max_children()
                         );
 
                         Arc::new(Node::Internal(last))
@@ -1530,11 +2430,11 @@ mod tree_replace {
                     debug_assert_eq!(inode.depth(), self.target_depth);
 
                     debug_assert!(
-                        inode.len() >= Inode::<N, L>::min_children()
+                        inode.len() >= Inode::<N, L, C>::min_children()
                     );
 
                     debug_assert!(
-                        inode.len() <= Inode::<N, L>::max_children()
+                        inode.len() <= Inode::<N, L, C>::max_children()
                     );
 
                     Some(Arc::new(Node::Internal(inode)))
@@ -1647,9 +2547,57 @@ mod tests {
         type BaseMetric = LeavesMetric;
     }
 
+    impl HashedLeaf for usize {
+        // Only keeps the lowest bits so that the tests can cause collisions.
+        fn hash_slice(slice: UsizeSlice<'_>) -> u64 {
+            *slice.0 as u64 % 256
+        }
+
+        fn slices_eq(lhs: UsizeSlice<'_>, rhs: UsizeSlice<'_>) -> bool {
+            lhs.0 == rhs.0
+        }
+    }
+
     #[test]
     fn easy() {
         let tree = Tree::<4, usize>::from_leaves(0..20);
         assert_eq!(190, tree.summary().count);
     }
+
+    #[cfg(feature = "node-hashes")]
+    #[test]
+    fn diff_shared_by_hash() {
+        let old = Tree::<4, usize>::from_leaves(0..200);
+
+        let new = Tree::<4, usize>::from_leaves((0..200).map(|n| {
+            if n == 50 {
+                1000
+            } else {
+                n
+            }
+        }));
+
+        assert_eq!(new.diff_shared(&old), [(50..51, 50..51)]);
+
+        let copy = Tree::<4, usize>::from_leaves(0..200);
+
+        assert!(copy.diff_shared(&old).is_empty());
+    }
+
+    #[cfg(feature = "node-hashes")]
+    #[test]
+    fn diff_shared_hash_collision() {
+        let old = Tree::<4, usize>::from_leaves(0..200);
+
+        // 306 has the same hash as 50, so the two trees have the same hash.
+        let new = Tree::<4, usize>::from_leaves((0..200).map(|n| {
+            if n == 50 {
+                306
+            } else {
+                n
+            }
+        }));
+
+        assert_eq!(new.diff_shared(&old), [(50..51, 50..51)]);
+    }
 }
@@ -1,11 +1,22 @@
 use alloc::vec::Vec;
 
 use super::traits::{BalancedLeaf, Leaf};
-use super::{Arc, Inode, Lnode, Node, Tree};
+use super::{
+    Arc,
+    ArcNode,
+    AtomicCount,
+    Inode,
+    Lnode,
+    Node,
+    NodeRef,
+    RefCount,
+    Tree,
+};
 
 /// An incremental [`Tree`] builder.
 #[derive(Clone)]
-pub struct TreeBuilder<const ARITY: usize, L: Leaf> {
+pub struct TreeBuilder<const ARITY: usize, L: Leaf, C: RefCount = AtomicCount>
+{
     /// A stack of internal nodes.
     ///
     /// # Invariants
@@ -26,25 +37,45 @@ pub struct TreeBuilder<const ARITY: usize, L: Leaf> {
     ///
     /// - all the inodes in the last stack level (assuming there are any) have
     ///   a depth of 1.
-    stack: Vec<Vec<Arc<Node<ARITY, L>>>>,
+    stack: Vec<Vec<ArcNode<ARITY, L, C>>>,
 
     /// A bunch of leaves waiting to be grouped into an internal node.
-    leaves: Vec<Arc<Node<ARITY, L>>>,
+    leaves: Vec<ArcNode<ARITY, L, C>>,
 }
 
-impl<const ARITY: usize, L: Leaf> Default for TreeBuilder<ARITY, L> {
+impl<const ARITY: usize, L: Leaf, C: RefCount> Default
+    for TreeBuilder<ARITY, L, C>
+{
     #[inline]
     fn default() -> Self {
         Self { stack: Vec::new(), leaves: Vec::with_capacity(ARITY) }
     }
 }
 
-impl<const ARITY: usize, L: Leaf> TreeBuilder<ARITY, L> {
+impl<const ARITY: usize, L: Leaf, C: RefCount> TreeBuilder<ARITY, L, C> {
+    /// Appends a leaf to the `Tree` being built.
     #[inline]
     pub fn append(&mut self, leaf: L) {
+        self.append_node(Arc::new(Node::Leaf(Lnode::from(leaf))));
+    }
+
+    /// Appends a leaf node of another `Tree`, which is shared with it
+    /// instead of being copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is an internal node.
+    #[inline]
+    pub(crate) fn append_shared(&mut self, leaf: NodeRef<'_, ARITY, L, C>) {
+        assert!(leaf.leaf().is_some(), "the node is not a leaf");
+        self.append_node(leaf.to_owned().into_arc());
+    }
+
+    #[inline]
+    fn append_node(&mut self, leaf: ArcNode<ARITY, L, C>) {
         debug_assert!(self.leaves.len() < ARITY);
 
-        self.leaves.push(Arc::new(Node::Leaf(Lnode::from(leaf))));
+        self.leaves.push(leaf);
 
         if self.leaves.len() < ARITY {
             return;
@@ -103,7 +134,7 @@ impl<const ARITY: usize, L: Leaf> TreeBuilder<ARITY, L> {
 
     /// Completes the build and outputs the final `Tree`, consuming `self`.
     #[inline]
-    pub fn build(mut self) -> Tree<ARITY, L>
+    pub fn build(mut self) -> Tree<ARITY, L, C>
     where
         L: Default + BalancedLeaf + Clone,
     {
@@ -114,7 +145,9 @@ impl<const ARITY: usize, L: Leaf> TreeBuilder<ARITY, L> {
                 // empty Tree. This is why we need the `Default` bound on `L`.
                 return Tree::default();
             } else if self.leaves.len() == 1 {
-                return Tree { root: self.leaves.into_iter().next().unwrap() };
+                return Tree::with_root(
+                    self.leaves.into_iter().next().unwrap(),
+                );
             }
         }
 
@@ -167,10 +200,10 @@ impl<const ARITY: usize, L: Leaf> TreeBuilder<ARITY, L> {
 
         Node::replace_with_single_child(&mut root);
 
-        Tree { root }
+        Tree::with_root(root)
     }
 
-    #[allow(dead_code)]
+    /// Creates a new `TreeBuilder` with no leaves.
     #[inline]
     pub fn new() -> Self {
         Self::default()
@@ -4,10 +4,15 @@ use super::*;
 
 /// An immutable slice of a [`Tree`].
 #[derive(Debug)]
-pub struct TreeSlice<'a, const ARITY: usize, L: Leaf> {
+pub struct TreeSlice<
+    'a,
+    const ARITY: usize,
+    L: Leaf,
+    C: RefCount = AtomicCount,
+> {
     /// The deepest node that contains all the leaves between (and including)
     /// [`start_slice`](Self::start_slice) and [`end_slice`](Self::end_slice).
-    pub(super) root: &'a Arc<Node<ARITY, L>>,
+    pub(super) root: &'a ArcNode<ARITY, L, C>,
 
     /// The summary of the subtree under [`root`](Self::root) up to the start
     /// of the [`start_slice`](Self::start_slice).
@@ -33,7 +38,9 @@ pub struct TreeSlice<'a, const ARITY: usize, L: Leaf> {
     pub(super) leaf_count: usize,
 }
 
-impl<const ARITY: usize, L: Leaf> Clone for TreeSlice<'_, ARITY, L> {
+impl<const ARITY: usize, L: Leaf, C: RefCount> Clone
+    for TreeSlice<'_, ARITY, L, C>
+{
     #[inline]
     fn clone(&self) -> Self {
         TreeSlice {
@@ -46,12 +53,14 @@ impl<const ARITY: usize, L: Leaf> Clone for TreeSlice<'_, ARITY, L> {
     }
 }
 
-impl<const ARITY: usize, L: Leaf> Copy for TreeSlice<'_, ARITY, L> where
-    L::Summary: Copy
+impl<const ARITY: usize, L: Leaf, C: RefCount> Copy
+    for TreeSlice<'_, ARITY, L, C>
+where
+    L::Summary: Copy,
 {
 }
 
-impl<'a, const ARITY: usize, L: Leaf> TreeSlice<'a, ARITY, L> {
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount> TreeSlice<'a, ARITY, L, C> {
     /*
       Public methods
     */
@@ -116,6 +125,7 @@ impl<'a, const ARITY: usize, L: Leaf> TreeSlice<'a, ARITY, L> {
         }
     }
 
+    /// Returns the measure of this `TreeSlice` in its base metric.
     #[inline]
     pub fn base_measure(&self) -> L::BaseMetric {
         self.measure::<L::BaseMetric>()
@@ -140,11 +150,13 @@ impl<'a, const ARITY: usize, L: Leaf> TreeSlice<'a, ARITY, L> {
         }
     }
 
+    /// Returns the part of the last leaf spanned by this `TreeSlice`.
     #[inline]
     pub fn end_slice(&self) -> L::Slice<'a> {
         self.end_slice
     }
 
+    /// Returns the summary of [`end_slice()`](Self::end_slice()).
     #[inline]
     pub fn end_summary(&self) -> &L::Summary {
         &self.end_summary
@@ -177,16 +189,21 @@ impl<'a, const ARITY: usize, L: Leaf> TreeSlice<'a, ARITY, L> {
         }
     }
 
+    /// Returns the number of leaves spanned by this `TreeSlice`, including
+    /// the ones it only partially covers.
     #[inline]
     pub fn leaf_count(&self) -> usize {
         self.leaf_count
     }
 
+    /// Returns an iterator over the leaves of this `TreeSlice`, the first and
+    /// last of which are sliced to its range.
     #[inline]
-    pub fn leaves(&self) -> Leaves<'a, ARITY, L> {
+    pub fn leaves(&self) -> Leaves<'a, ARITY, L, C> {
         Leaves::from(self)
     }
 
+    /// Returns the `M`-measure of this `TreeSlice`.
     #[inline]
     pub fn measure<M>(&self) -> M
     where
@@ -196,34 +213,37 @@ impl<'a, const ARITY: usize, L: Leaf> TreeSlice<'a, ARITY, L> {
     }
 
     #[inline]
-    pub(super) fn root(&self) -> &'a Arc<Node<ARITY, L>> {
+    pub(super) fn root(&self) -> &'a ArcNode<ARITY, L, C> {
         self.root
     }
 
+    /// Returns the part of the first leaf spanned by this `TreeSlice`.
     #[inline]
     pub fn start_slice(&self) -> L::Slice<'a> {
         self.start_slice
     }
 
+    /// Returns the summary of [`start_slice()`](Self::start_slice()).
     #[inline]
     pub fn start_summary(&self) -> &L::Summary {
         &self.start_summary
     }
 
+    /// Returns the summary of this `TreeSlice`.
     #[inline]
     pub fn summary(&self) -> &L::Summary {
         &self.summary
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf> TreeSlice<'a, ARITY, L>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount> TreeSlice<'a, ARITY, L, C>
 where
     for<'d> L::Slice<'d>: Default,
 {
     #[track_caller]
     #[inline]
     pub(super) fn from_range_in_root<M>(
-        root: &'a Arc<Node<ARITY, L>>,
+        root: &'a ArcNode<ARITY, L, C>,
         range: Range<M>,
     ) -> Self
     where
@@ -243,6 +263,7 @@ where
         }
     }
 
+    /// Returns a slice of this `TreeSlice` in the range of the given metric.
     #[track_caller]
     #[inline]
     pub fn slice<M>(self, mut range: Range<M>) -> Self
@@ -306,7 +327,7 @@ where
     #[track_caller]
     #[inline]
     fn slice_impl<S, E>(
-        root: &'a Arc<Node<ARITY, L>>,
+        root: &'a ArcNode<ARITY, L, C>,
         start: S,
         end: E,
     ) -> Self
@@ -359,8 +380,9 @@ where
         slice
     }
 
+    /// Returns an iterator over the `M`-units of this `TreeSlice`.
     #[inline]
-    pub fn units<M>(&self) -> Units<'a, ARITY, L, M>
+    pub fn units<M>(&self) -> Units<'a, ARITY, L, M, C>
     where
         M: Metric<L::Summary>,
     {
@@ -372,11 +394,11 @@ where
 /// range between `start` and `end`, together with the `S` and `E` offsets with
 /// respect to that node.
 #[inline]
-fn deepest_node_containing_range<const N: usize, L, S, E>(
-    mut node: &Arc<Node<N, L>>,
+fn deepest_node_containing_range<const N: usize, L, C: RefCount, S, E>(
+    mut node: &ArcNode<N, L, C>,
     mut start: S,
     mut end: E,
-) -> (&Arc<Node<N, L>>, S, E)
+) -> (&ArcNode<N, L, C>, S, E)
 where
     L: Leaf,
     S: Metric<L::Summary>,
@@ -428,11 +450,15 @@ where
 ///
 /// Also returns the summary between the input `node` and the returned node.
 #[inline]
-pub(super) fn deepest_node_containing_base_range<const N: usize, L>(
-    mut node: &Arc<Node<N, L>>,
+pub(super) fn deepest_node_containing_base_range<
+    const N: usize,
+    L,
+    C: RefCount,
+>(
+    mut node: &ArcNode<N, L, C>,
     mut start: L::BaseMetric,
     mut end: L::BaseMetric,
-) -> (&Arc<Node<N, L>>, L::Summary)
+) -> (&ArcNode<N, L, C>, L::Summary)
 where
     L: Leaf,
 {
@@ -499,9 +525,9 @@ where
 /// the other fields of the slice are valid.
 #[track_caller]
 #[inline]
-fn build_slice<'a, const N: usize, L, S, E>(
-    slice: &mut TreeSlice<'a, N, L>,
-    node: &'a Arc<Node<N, L>>,
+fn build_slice<'a, const N: usize, L, C: RefCount, S, E>(
+    slice: &mut TreeSlice<'a, N, L, C>,
+    node: &'a ArcNode<N, L, C>,
     start: S,
     end: E,
     recompute_root: &mut bool,
@@ -2,7 +2,15 @@ use alloc::vec::Vec;
 
 use super::traits::{DoubleEndedUnitMetric, Leaf, Metric, UnitMetric};
 use super::tree_slice;
-use super::{Arc, Lnode, Node, Tree, TreeSlice};
+use super::{
+    ArcNode,
+    AtomicCount,
+    Lnode,
+    Node,
+    RefCount,
+    Tree,
+    TreeSlice,
+};
 
 /// An iterator over the units of a metric.
 //
@@ -20,24 +28,30 @@ use super::{Arc, Lnode, Node, Tree, TreeSlice};
 // backward). Once that reaches zero this iterator will stop yielding any more
 // items.
 #[derive(Clone)]
-pub struct Units<'a, const ARITY: usize, L: Leaf, M: Metric<L::Summary>> {
+pub struct Units<
+    'a,
+    const ARITY: usize,
+    L: Leaf,
+    M: Metric<L::Summary>,
+    C: RefCount = AtomicCount,
+> {
     /// Iterates over the `M`-units from front to back.
-    forward: UnitsForward<'a, ARITY, L, M>,
+    forward: UnitsForward<'a, ARITY, L, M, C>,
 
     /// Iterates over the `M`-units from back to front.
-    backward: UnitsBackward<'a, ARITY, L, M>,
+    backward: UnitsBackward<'a, ARITY, L, M, C>,
 
     /// The base measure of all the `TreeSlice`s which are yet to be yielded.
     remaining: L::BaseMetric,
 }
 
-impl<'a, const ARITY: usize, L: Leaf, M: Metric<L::Summary>>
-    From<&'a Tree<ARITY, L>> for Units<'a, ARITY, L, M>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount, M: Metric<L::Summary>>
+    From<&'a Tree<ARITY, L, C>> for Units<'a, ARITY, L, M, C>
 where
     for<'d> L::Slice<'d>: Default,
 {
     #[inline]
-    fn from(tree: &'a Tree<ARITY, L>) -> Units<'a, ARITY, L, M> {
+    fn from(tree: &'a Tree<ARITY, L, C>) -> Units<'a, ARITY, L, M, C> {
         Self {
             forward: UnitsForward::from(tree),
             backward: UnitsBackward::from(tree),
@@ -46,13 +60,15 @@ where
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf, M: Metric<L::Summary>>
-    From<&TreeSlice<'a, ARITY, L>> for Units<'a, ARITY, L, M>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount, M: Metric<L::Summary>>
+    From<&TreeSlice<'a, ARITY, L, C>> for Units<'a, ARITY, L, M, C>
 where
     for<'d> L::Slice<'d>: Default,
 {
     #[inline]
-    fn from(tree_slice: &TreeSlice<'a, ARITY, L>) -> Units<'a, ARITY, L, M> {
+    fn from(
+        tree_slice: &TreeSlice<'a, ARITY, L, C>,
+    ) -> Units<'a, ARITY, L, M, C> {
         Self {
             forward: UnitsForward::from(tree_slice),
             backward: UnitsBackward::from(tree_slice),
@@ -61,8 +77,8 @@ where
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf, M: UnitMetric<L>> Iterator
-    for Units<'a, ARITY, L, M>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount, M: UnitMetric<L>> Iterator
+    for Units<'a, ARITY, L, M, C>
 {
     /// The iterator returns the next `TreeSlice` in the iterating range
     /// together with its advance.
@@ -90,7 +106,7 @@ impl<'a, const ARITY: usize, L: Leaf, M: UnitMetric<L>> Iterator
     /// following glyph.
     ///
     /// [1]: https://freetype.org/freetype2/docs/glyphs/glyph-metrics-3.svg
-    type Item = (TreeSlice<'a, ARITY, L>, L::BaseMetric);
+    type Item = (TreeSlice<'a, ARITY, L, C>, L::BaseMetric);
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
@@ -135,8 +151,12 @@ impl<'a, const ARITY: usize, L: Leaf, M: UnitMetric<L>> Iterator
     }
 }
 
-impl<const ARITY: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
-    DoubleEndedIterator for Units<'_, ARITY, L, M>
+impl<
+        const ARITY: usize,
+        L: Leaf,
+        C: RefCount,
+        M: DoubleEndedUnitMetric<L>,
+    > DoubleEndedIterator for Units<'_, ARITY, L, M, C>
 {
     #[inline]
     fn next_back(&mut self) -> Option<Self::Item> {
@@ -184,13 +204,19 @@ impl<const ARITY: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
     }
 }
 
-impl<const ARITY: usize, L: Leaf, M: UnitMetric<L>> core::iter::FusedIterator
-    for Units<'_, ARITY, L, M>
+impl<const ARITY: usize, L: Leaf, C: RefCount, M: UnitMetric<L>>
+    core::iter::FusedIterator for Units<'_, ARITY, L, M, C>
 {
 }
 
 #[derive(Debug)]
-struct UnitsForward<'a, const N: usize, L: Leaf, M: Metric<L::Summary>> {
+struct UnitsForward<
+    'a,
+    const N: usize,
+    L: Leaf,
+    M: Metric<L::Summary>,
+    C: RefCount,
+> {
     /// Whether `Self` has been initialized by calling
     /// [`initialize`](UnitsForward::initialize()).
     is_initialized: bool,
@@ -199,10 +225,10 @@ struct UnitsForward<'a, const N: usize, L: Leaf, M: Metric<L::Summary>> {
     /// path are guaranteed to be internal nodes, and the second item in each
     /// tuple represents the child index of next node in the path, or the index
     /// of the leaf node for the last node.
-    path: Vec<(&'a Arc<Node<N, L>>, usize)>,
+    path: Vec<(&'a ArcNode<N, L, C>, usize)>,
 
     /// The current leaf node.
-    leaf_node: &'a Arc<Node<N, L>>,
+    leaf_node: &'a ArcNode<N, L, C>,
 
     /// How much of `leaf_node`'s summary has already been yielded.
     yielded_in_leaf: L::Summary,
@@ -239,8 +265,8 @@ struct UnitsForward<'a, const N: usize, L: Leaf, M: Metric<L::Summary>> {
     units_total: M,
 }
 
-impl<const N: usize, L: Leaf, M: Metric<L::Summary>> Clone
-    for UnitsForward<'_, N, L, M>
+impl<const N: usize, L: Leaf, C: RefCount, M: Metric<L::Summary>> Clone
+    for UnitsForward<'_, N, L, M, C>
 {
     #[inline]
     fn clone(&self) -> Self {
@@ -255,13 +281,13 @@ impl<const N: usize, L: Leaf, M: Metric<L::Summary>> Clone
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf, M: Metric<L::Summary>>
-    From<&'a Tree<ARITY, L>> for UnitsForward<'a, ARITY, L, M>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount, M: Metric<L::Summary>>
+    From<&'a Tree<ARITY, L, C>> for UnitsForward<'a, ARITY, L, M, C>
 where
     for<'d> L::Slice<'d>: Default,
 {
     #[inline]
-    fn from(tree: &'a Tree<ARITY, L>) -> UnitsForward<'a, ARITY, L, M> {
+    fn from(tree: &'a Tree<ARITY, L, C>) -> UnitsForward<'a, ARITY, L, M, C> {
         Self {
             is_initialized: false,
             path: Vec::with_capacity(tree.root().depth()),
@@ -280,20 +306,22 @@ where
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf, M: Metric<L::Summary>>
-    From<&TreeSlice<'a, ARITY, L>> for UnitsForward<'a, ARITY, L, M>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount, M: Metric<L::Summary>>
+    From<&TreeSlice<'a, ARITY, L, C>> for UnitsForward<'a, ARITY, L, M, C>
 where
     for<'d> L::Slice<'d>: Default,
 {
     #[inline]
     fn from(
-        tree_slice: &TreeSlice<'a, ARITY, L>,
-    ) -> UnitsForward<'a, ARITY, L, M> {
+        tree_slice: &TreeSlice<'a, ARITY, L, C>,
+    ) -> UnitsForward<'a, ARITY, L, M, C> {
         Self {
             is_initialized: false,
             path: Vec::with_capacity(tree_slice.root().depth()),
             leaf_node: tree_slice.root(),
-            yielded_in_leaf: L::Summary::default(),
+            // If the root is a leaf this is the summary of the leaf up to the
+            // start of the slice, which is needed by `initialize()`.
+            yielded_in_leaf: tree_slice.offset.clone(),
             start_slice: L::Slice::default(),
             start_summary: L::Summary::default(),
             first_slice: Some((
@@ -313,7 +341,9 @@ where
     }
 }
 
-impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
+impl<'a, const N: usize, L: Leaf, C: RefCount, M: UnitMetric<L>>
+    UnitsForward<'a, N, L, M, C>
+{
     /// Initializes `Self` by populating the path down to the internal node
     /// containing the leaf node at `base_offset`, which is set to `leaf_node`.
     ///
@@ -352,8 +382,14 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
 
                     match self.first_slice.take() {
                         Some((slice, summary)) => {
-                            self.yielded_in_leaf =
-                                leaf.summary().clone() - &summary;
+                            // If the leaf is the root the whole slice is
+                            // contained in it, and `yielded_in_leaf` is
+                            // already set to its offset. Otherwise the first
+                            // slice extends to the end of the leaf.
+                            if !self.path.is_empty() {
+                                self.yielded_in_leaf =
+                                    leaf.summary().clone() - &summary;
+                            }
 
                             self.start_slice = slice;
                             self.start_summary = summary;
@@ -427,7 +463,7 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
     /// should only be called when `self.start_slice` has an `M`-measure of at
     /// least `M::one()`.
     #[inline]
-    fn next_unit_in_leaf(&mut self) -> (TreeSlice<'a, N, L>, L::Summary) {
+    fn next_unit_in_leaf(&mut self) -> (TreeSlice<'a, N, L, C>, L::Summary) {
         debug_assert!(M::measure(&self.start_summary) > M::zero());
         debug_assert!(self.units_total > self.units_yielded);
 
@@ -486,7 +522,7 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
     #[inline]
     fn next_leaf_with_measure(
         &mut self,
-    ) -> (&'a Lnode<L>, &'a Arc<Node<N, L>>, L::Summary, L::Summary, usize)
+    ) -> (&'a Lnode<L>, &'a ArcNode<N, L, C>, L::Summary, L::Summary, usize)
     {
         debug_assert!(self.units_total > self.units_yielded);
 
@@ -560,7 +596,7 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
     ///
     /// Invariants: the returned [`Node`] is guaranteed to be a leaf node.
     #[inline]
-    fn previous_leaf(&self) -> &'a Arc<Node<N, L>> {
+    fn previous_leaf(&self) -> &'a ArcNode<N, L, C> {
         let mut path_idx = self.path.len() - 1;
 
         let mut node = loop {
@@ -592,7 +628,7 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
     /// when the `TreeSlice` is not totally contained in `self.leaf_node` and
     /// it's not the remainder.
     #[inline]
-    fn next_unit_in_range(&mut self) -> (TreeSlice<'a, N, L>, L::Summary) {
+    fn next_unit_in_range(&mut self) -> (TreeSlice<'a, N, L, C>, L::Summary) {
         debug_assert_eq!(M::measure(&self.start_summary), M::zero());
         debug_assert!(self.units_total > self.units_yielded);
 
@@ -613,11 +649,11 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
         let start_slice = self.start_slice;
         let start_summary = self.start_summary.clone();
 
-        let (leaf, mut root, mut offset, mut summary, mut leaf_count) =
+        let (leaf, mut root, mut offset, summary, mut leaf_count) =
             self.next_leaf_with_measure();
 
         offset += &self.yielded_in_leaf;
-        summary += &start_summary;
+        let mut summary = start_summary.clone() + &summary;
         leaf_count += 1;
 
         let (slice, slice_summary) = {
@@ -717,7 +753,7 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
     #[inline]
     fn last_leaf(
         &self,
-    ) -> (&'a Lnode<L>, &'a Arc<Node<N, L>>, L::Summary, L::Summary, usize)
+    ) -> (&'a Lnode<L>, &'a ArcNode<N, L, C>, L::Summary, L::Summary, usize)
     {
         // Step 1: find the index of deepest node in the path that fully
         // contains `range`.
@@ -778,6 +814,13 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
             for child in &inode.children()[..child_idx] {
                 before += child.summary();
             }
+        }
+
+        // The children after the path come before the ones after its parent,
+        // so we have to add them up from the bottom of the path.
+        for &(node, child_idx) in self.path[root_idx + 1..].iter().rev() {
+            // Every node in the path is an internal node.
+            let inode = node.get_internal();
 
             for child in &inode.children()[child_idx + 1..] {
                 summary += child.summary();
@@ -852,7 +895,7 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
     /// iterate forward this only gets called when we are sure there's a
     /// remainder to yield.
     #[inline]
-    fn remainder(&mut self) -> (TreeSlice<'a, N, L>, L::Summary) {
+    fn remainder(&mut self) -> (TreeSlice<'a, N, L, C>, L::Summary) {
         debug_assert_eq!(self.units_total, self.units_yielded);
         debug_assert!(self.base_total > self.base_yielded);
 
@@ -890,10 +933,9 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
         let start_slice = self.start_slice;
         let start_summary = core::mem::take(&mut self.start_summary);
 
-        let (last_leaf, root, before, mut summary, leaf_count) =
-            self.last_leaf();
+        let (last_leaf, root, before, summary, leaf_count) = self.last_leaf();
 
-        summary += &start_summary;
+        let mut summary = start_summary.clone() + &summary;
 
         let (end_slice, end_summary) = match self.last_slice.take() {
             Some((slice, summary)) => (slice, summary),
@@ -925,7 +967,13 @@ impl<'a, const N: usize, L: Leaf, M: UnitMetric<L>> UnitsForward<'a, N, L, M> {
 }
 
 #[derive(Debug)]
-struct UnitsBackward<'a, const N: usize, L: Leaf, M: Metric<L::Summary>> {
+struct UnitsBackward<
+    'a,
+    const N: usize,
+    L: Leaf,
+    M: Metric<L::Summary>,
+    C: RefCount,
+> {
     /// Whether `Self` has been initialized by calling
     /// [`initialize`](UnitsBackward::initialize()).
     is_initialized: bool,
@@ -934,10 +982,10 @@ struct UnitsBackward<'a, const N: usize, L: Leaf, M: Metric<L::Summary>> {
     /// path are guaranteed to be internal nodes, and the second item in each
     /// tuple represents the child index of next node in the path, or the index
     /// of the leaf node for the last node.
-    path: Vec<(&'a Arc<Node<N, L>>, usize)>,
+    path: Vec<(&'a ArcNode<N, L, C>, usize)>,
 
     /// The current leaf node.
-    leaf_node: &'a Arc<Node<N, L>>,
+    leaf_node: &'a ArcNode<N, L, C>,
 
     /// How much of `leaf_node`'s base measure has already been yielded.
     yielded_in_leaf: L::Summary,
@@ -968,8 +1016,8 @@ struct UnitsBackward<'a, const N: usize, L: Leaf, M: Metric<L::Summary>> {
     units_remaining: M,
 }
 
-impl<const N: usize, L: Leaf, M: Metric<L::Summary>> Clone
-    for UnitsBackward<'_, N, L, M>
+impl<const N: usize, L: Leaf, C: RefCount, M: Metric<L::Summary>> Clone
+    for UnitsBackward<'_, N, L, M, C>
 {
     #[inline]
     fn clone(&self) -> Self {
@@ -984,13 +1032,13 @@ impl<const N: usize, L: Leaf, M: Metric<L::Summary>> Clone
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf, M: Metric<L::Summary>>
-    From<&'a Tree<ARITY, L>> for UnitsBackward<'a, ARITY, L, M>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount, M: Metric<L::Summary>>
+    From<&'a Tree<ARITY, L, C>> for UnitsBackward<'a, ARITY, L, M, C>
 where
     for<'d> L::Slice<'d>: Default,
 {
     #[inline]
-    fn from(tree: &'a Tree<ARITY, L>) -> UnitsBackward<'a, ARITY, L, M> {
+    fn from(tree: &'a Tree<ARITY, L, C>) -> UnitsBackward<'a, ARITY, L, M, C> {
         Self {
             is_initialized: false,
             path: Vec::with_capacity(tree.root().depth()),
@@ -1007,20 +1055,22 @@ where
     }
 }
 
-impl<'a, const ARITY: usize, L: Leaf, M: Metric<L::Summary>>
-    From<&TreeSlice<'a, ARITY, L>> for UnitsBackward<'a, ARITY, L, M>
+impl<'a, const ARITY: usize, L: Leaf, C: RefCount, M: Metric<L::Summary>>
+    From<&TreeSlice<'a, ARITY, L, C>> for UnitsBackward<'a, ARITY, L, M, C>
 where
     for<'d> L::Slice<'d>: Default,
 {
     #[inline]
     fn from(
-        tree_slice: &TreeSlice<'a, ARITY, L>,
-    ) -> UnitsBackward<'a, ARITY, L, M> {
+        tree_slice: &TreeSlice<'a, ARITY, L, C>,
+    ) -> UnitsBackward<'a, ARITY, L, M, C> {
         Self {
             is_initialized: false,
             path: Vec::with_capacity(tree_slice.root().depth()),
             leaf_node: tree_slice.root(),
-            yielded_in_leaf: L::Summary::default(),
+            // If the root is a leaf this is the summary of the leaf up to the
+            // start of the slice, which is needed by `initialize()`.
+            yielded_in_leaf: tree_slice.offset.clone(),
             end_slice: L::Slice::default(),
             end_summary: L::Summary::default(),
             first_slice: Some((
@@ -1038,8 +1088,13 @@ where
     }
 }
 
-impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
-    UnitsBackward<'a, N, L, M>
+impl<
+        'a,
+        const N: usize,
+        L: Leaf,
+        C: RefCount,
+        M: DoubleEndedUnitMetric<L>,
+    > UnitsBackward<'a, N, L, M, C>
 {
     /// Initializes `Self` by populating the path down to the internal node
     /// containing the leaf node at `base_start + base_remaining`, which is set
@@ -1082,8 +1137,17 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
 
                     match self.last_slice.take() {
                         Some((slice, summary)) => {
-                            self.yielded_in_leaf =
-                                leaf.summary().clone() - &summary;
+                            // If the leaf is the root the whole slice is
+                            // contained in it, and it can be followed by some
+                            // text that's not part of it. Otherwise the last
+                            // slice starts at the start of the leaf.
+                            self.yielded_in_leaf = if self.path.is_empty() {
+                                leaf.summary().clone()
+                                    - &self.yielded_in_leaf
+                                    - &summary
+                            } else {
+                                leaf.summary().clone() - &summary
+                            };
 
                             self.end_slice = slice;
                             self.end_summary = summary;
@@ -1151,7 +1215,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
     #[inline]
     fn first_leaf(
         &self,
-    ) -> (&'a Lnode<L>, &'a Arc<Node<N, L>>, L::Summary, L::Summary, usize)
+    ) -> (&'a Lnode<L>, &'a ArcNode<N, L, C>, L::Summary, L::Summary, usize)
     {
         // Step 1: find the index of deepest node in the path that fully
         // contains `range`.
@@ -1198,7 +1262,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
         // root, increasing `after`, `summary` and `leaf_count` as you go.
 
         let mut after = L::Summary::default();
-        let mut summary = L::Summary::default();
+        let mut path_summary = L::Summary::default();
         let mut leaf_count = 0;
 
         for &(node, child_idx) in &self.path[root_idx + 1..] {
@@ -1206,7 +1270,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
             let inode = node.get_internal();
 
             for child in &inode.children()[..child_idx] {
-                summary += child.summary();
+                path_summary += child.summary();
                 leaf_count += child.leaf_count();
             }
 
@@ -1215,6 +1279,10 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
             }
         }
 
+        // The children before the path come after the ones found while going
+        // down to the first leaf, so we add them at the end.
+        let mut summary = L::Summary::default();
+
         let (root, child_idx) = self.path[root_idx];
 
         // Every node in the path is an internal node.
@@ -1256,10 +1324,14 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
                         let child_measure = child.base_measure();
 
                         if offset + child_measure > range.start {
+                            // These children come before the ones we've
+                            // already added up.
+                            let mut children_summary = L::Summary::default();
                             for child in children {
-                                summary += child.summary();
+                                children_summary += child.summary();
                                 leaf_count += child.leaf_count();
                             }
+                            summary = children_summary + &summary;
                             node = child;
                             continue 'outer;
                         } else {
@@ -1271,6 +1343,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
                 },
 
                 Node::Leaf(leaf) => {
+                    summary += &path_summary;
                     return (leaf, root, after, summary, leaf_count);
                 },
             }
@@ -1284,7 +1357,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
     ///
     /// - by [`Self::previous()`] when there's one final unit to yield.
     #[inline]
-    fn first(&mut self) -> (TreeSlice<'a, N, L>, L::Summary) {
+    fn first(&mut self) -> (TreeSlice<'a, N, L, C>, L::Summary) {
         debug_assert!(self.base_remaining > L::BaseMetric::zero());
 
         let (_, _, end_slice, end_summary, mut advance) =
@@ -1293,10 +1366,14 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
         // First, check if the current leaf node is the root. If it is we're
         // done.
         if self.base_remaining == L::BaseMetric::measure(&advance) {
+            let offset = self.leaf_node.summary().clone()
+                - &self.yielded_in_leaf
+                - &self.end_summary;
+
             return (
                 TreeSlice {
                     root: self.leaf_node,
-                    offset: L::Summary::default(),
+                    offset,
                     summary: end_summary.clone(),
                     start_slice: end_slice,
                     start_summary: end_summary.clone(),
@@ -1357,7 +1434,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
 
         advance += &start_summary;
 
-        summary += &start_summary;
+        let summary = start_summary.clone() + &summary;
 
         let offset =
             root.summary().clone() - &after - &self.yielded_in_leaf - &advance;
@@ -1383,7 +1460,9 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
     /// correctly `self.end_slice` cannot have any `M`-remainder and it needs
     /// to contain at least 2 `M`-units.
     #[inline]
-    fn previous_unit_in_leaf(&mut self) -> (TreeSlice<'a, N, L>, L::Summary) {
+    fn previous_unit_in_leaf(
+        &mut self,
+    ) -> (TreeSlice<'a, N, L, C>, L::Summary) {
         debug_assert!(M::measure(&self.end_summary) > M::one());
         debug_assert!(self.units_remaining > M::zero());
 
@@ -1394,7 +1473,9 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
             L::BaseMetric::measure(&rest_summary) > L::BaseMetric::zero()
         );
 
-        let offset = rest_summary.clone();
+        let offset = self.leaf_node.summary().clone()
+            - &self.yielded_in_leaf
+            - &advance;
 
         self.yielded_in_leaf += &advance;
         self.end_slice = rest;
@@ -1446,7 +1527,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
     #[inline]
     fn previous_leaf_with_measure(
         &mut self,
-    ) -> (&'a Lnode<L>, &'a Arc<Node<N, L>>, L::Summary, L::Summary, usize)
+    ) -> (&'a Lnode<L>, &'a ArcNode<N, L, C>, L::Summary, L::Summary, usize)
     {
         debug_assert!(self.units_remaining > M::zero());
 
@@ -1474,7 +1555,9 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
                     self.path.push((node, idx));
                     break 'outer;
                 } else {
-                    summary += child.summary();
+                    // We're going backwards, so every child comes before the
+                    // ones we've already added up.
+                    summary = child.summary().clone() + &summary;
                     leaf_count += child.leaf_count();
                 }
             }
@@ -1499,7 +1582,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
                             node = child;
                             continue 'outer;
                         } else {
-                            summary += child.summary();
+                            summary = child.summary().clone() + &summary;
                             leaf_count += child.leaf_count();
                         }
                     }
@@ -1522,7 +1605,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
     ///
     /// Invariants: the returned node is guaranteed to be a leaf node.
     #[inline]
-    fn next_leaf(&self) -> &'a Arc<Node<N, L>> {
+    fn next_leaf(&self) -> &'a ArcNode<N, L, C> {
         let mut path_idx = self.path.len() - 1;
 
         let mut node = loop {
@@ -1563,7 +1646,9 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
     ///
     /// [1]: UnitsBackward::previous_leaf_with_measure()
     #[inline]
-    fn previous_unit_in_range(&mut self) -> (TreeSlice<'a, N, L>, L::Summary) {
+    fn previous_unit_in_range(
+        &mut self,
+    ) -> (TreeSlice<'a, N, L, C>, L::Summary) {
         debug_assert!(self.units_remaining > M::zero());
 
         let (_, _, end_slice, end_summary, mut advance) =
@@ -1663,7 +1748,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
         self.end_summary = rest_summary;
 
         if L::BaseMetric::measure(&start_summary) > L::BaseMetric::zero() {
-            summary += &start_summary;
+            summary = start_summary.clone() + &summary;
             leaf_count += 1;
         }
         // This edge case can happen when the remainder of `slice` is empty.
@@ -1740,7 +1825,7 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
     /// It also follows that if `M` is the `BaseMetric` this function will
     /// always return `None`.
     #[inline]
-    fn remainder(&mut self) -> Option<(TreeSlice<'a, N, L>, L::Summary)> {
+    fn remainder(&mut self) -> Option<(TreeSlice<'a, N, L, C>, L::Summary)> {
         debug_assert!(self.base_remaining > L::BaseMetric::zero());
 
         if M::measure(&self.end_summary) > M::zero() {
@@ -1748,7 +1833,9 @@ impl<'a, const N: usize, L: Leaf, M: DoubleEndedUnitMetric<L>>
                 M::remainder(self.end_slice, &self.end_summary);
 
             if L::BaseMetric::measure(&summary) > L::BaseMetric::zero() {
-                let offset = rest_summary.clone();
+                let offset = self.leaf_node.summary().clone()
+                    - &self.yielded_in_leaf
+                    - &summary;
 
                 self.yielded_in_leaf += &summary;
                 self.end_slice = rest;
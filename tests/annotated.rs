@@ -0,0 +1,115 @@
+use crop::annotated::AnnotatedRope;
+use crop::Rope;
+use rand::{thread_rng, Rng};
+
+mod common;
+
+use common::MEDIUM;
+
+#[test]
+fn annotated_edits_move_annotations() {
+    let mut r = AnnotatedRope::from(Rope::from("Hello World"));
+
+    let world = r.annotate(6..11, "noun");
+
+    r.insert(0, "Oh, ");
+    r.insert(10, "big ");
+
+    assert_eq!(*r, "Oh, Hello big World");
+    assert_eq!(r.annotations_in(..).collect::<Vec<_>>(), [(14..19, &"noun")]);
+
+    r.delete(14..17);
+    assert_eq!(r.annotations_in(..).collect::<Vec<_>>(), [(14..16, &"noun")]);
+
+    assert_eq!(r.remove_annotation(world), Some((14..16, "noun")));
+    assert_eq!(r.annotations_in(..).count(), 0);
+}
+
+#[test]
+fn annotated_annotations_in_are_clipped() {
+    let mut r = AnnotatedRope::from(Rope::from("aaaa bbbb cccc"));
+
+    r.annotate(0..9, 1);
+    r.annotate(5..14, 2);
+
+    assert_eq!(
+        r.annotations_in(3..7).collect::<Vec<_>>(),
+        [(3..7, &1), (5..7, &2)]
+    );
+}
+
+#[test]
+fn annotated_line_annotations() {
+    let mut r = AnnotatedRope::from(Rope::from("foo\nbar\r\nbaz"));
+
+    // Spans the line break between the first two lines.
+    r.annotate(2..5, 'a');
+    r.annotate(9..12, 'b');
+
+    assert_eq!(r.line_annotations(0).collect::<Vec<_>>(), [(2..3, &'a')]);
+    assert_eq!(r.line_annotations(1).collect::<Vec<_>>(), [(0..1, &'a')]);
+    assert_eq!(r.line_annotations(2).collect::<Vec<_>>(), [(0..3, &'b')]);
+
+    for (idx, line) in r.lines().enumerate() {
+        for (range, _) in r.line_annotations(idx) {
+            assert!(range.end <= line.byte_len());
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn annotated_out_of_bounds() {
+    let mut r = AnnotatedRope::from(Rope::from("foo"));
+    r.annotate(2..4, ());
+}
+
+/// Annotates random words and checks that they still point at the same words
+/// after random edits elsewhere in the text.
+#[cfg_attr(miri, ignore)]
+#[test]
+fn annotated_random_edits() {
+    let mut rng = thread_rng();
+
+    let mut r = AnnotatedRope::from(Rope::from(MEDIUM));
+
+    let mut words = Vec::new();
+
+    for _ in 0..20 {
+        let start = rng.gen_range(0..r.byte_len() - 10);
+        if !r.is_char_boundary(start) || !r.is_char_boundary(start + 5) {
+            continue;
+        }
+        let word = r.byte_slice(start..start + 5).to_string();
+        words.push((r.annotate(start..start + 5, words.len()), word));
+    }
+
+    for _ in 0..200 {
+        let start = rng.gen_range(0..=r.byte_len());
+        let end = rng.gen_range(start..=r.byte_len().min(start + 10));
+
+        if !r.is_char_boundary(start) || !r.is_char_boundary(end) {
+            continue;
+        }
+
+        // Skip the edits touching an annotation.
+        if r.annotations()
+            .overlapping(start.saturating_sub(1)..end + 1)
+            .count()
+            > 0
+        {
+            continue;
+        }
+
+        r.replace(start..end, "🐸");
+    }
+
+    let annotations = r.annotations().iter().collect::<Vec<_>>();
+
+    assert_eq!(annotations.len(), words.len());
+
+    for (id, range, &idx) in annotations {
+        assert_eq!(words[idx].0, id);
+        assert_eq!(r.byte_slice(range), words[idx].1);
+    }
+}
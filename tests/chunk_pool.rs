@@ -0,0 +1,150 @@
+mod common;
+
+#[cfg(feature = "chunk-pool")]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crop::chunk_pool::{self, SetAllocatorError, ThreadLocalPool};
+    use crop::Rope;
+    use rand::Rng;
+
+    use crate::common::{CURSED_LIPSUM, LARGE, TINY};
+
+    /// The maximum number of bytes of the chunks of the default `Rope`.
+    #[cfg(feature = "small_chunks")]
+    const CHUNK_MAX_BYTES: usize = 4;
+
+    #[cfg(not(feature = "small_chunks"))]
+    const CHUNK_MAX_BYTES: usize = 2048;
+
+    /// A `ThreadLocalPool` which counts the chunks and nodes it's asked to
+    /// allocate.
+    struct CountingPool {
+        chunks: AtomicUsize,
+        nodes: AtomicUsize,
+    }
+
+    unsafe impl GlobalAlloc for CountingPool {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            if layout == chunk_pool::chunk_layout::<CHUNK_MAX_BYTES>() {
+                self.chunks.fetch_add(1, Ordering::Relaxed);
+            } else {
+                self.nodes.fetch_add(1, Ordering::Relaxed);
+            }
+            ThreadLocalPool.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            ThreadLocalPool.dealloc(ptr, layout)
+        }
+    }
+
+    static POOL: CountingPool = CountingPool {
+        chunks: AtomicUsize::new(0),
+        nodes: AtomicUsize::new(0),
+    };
+
+    /// All the tests use the same allocator since it's shared by every
+    /// thread, and it can only be set once.
+    fn set_allocator() {
+        let _ = chunk_pool::set_allocator(&POOL);
+    }
+
+    #[test]
+    fn chunk_pool_custom_allocator() {
+        set_allocator();
+
+        let chunks_before = POOL.chunks.load(Ordering::Relaxed);
+        let nodes_before = POOL.nodes.load(Ordering::Relaxed);
+        let r = Rope::from(LARGE);
+        let chunks_after = POOL.chunks.load(Ordering::Relaxed);
+        let nodes_after = POOL.nodes.load(Ordering::Relaxed);
+
+        let nodes_per_depth = r.mem_stats().nodes_per_depth;
+        let chunks = *nodes_per_depth.last().unwrap();
+        let nodes = nodes_per_depth.iter().sum::<usize>();
+        assert!(chunks_after - chunks_before >= chunks);
+        assert!(nodes_after - nodes_before >= nodes);
+    }
+
+    #[test]
+    fn chunk_pool_set_allocator_once() {
+        set_allocator();
+
+        assert_eq!(
+            chunk_pool::set_allocator(&ThreadLocalPool),
+            Err(SetAllocatorError)
+        );
+
+        let r = Rope::from(LARGE);
+        r.assert_invariants();
+        assert_eq!(r, LARGE);
+    }
+
+    #[test]
+    fn chunk_pool_recycles_buffers() {
+        set_allocator();
+        chunk_pool::set_max_pooled(16);
+        chunk_pool::clear();
+
+        let r = Rope::from(LARGE);
+        drop(r);
+        assert_eq!(chunk_pool::pooled(), 16);
+
+        // The new rope reuses the pooled buffers.
+        let r = Rope::from(LARGE);
+        r.assert_invariants();
+        assert_eq!(r, LARGE);
+        drop(r);
+        assert_eq!(chunk_pool::pooled(), 16);
+
+        chunk_pool::set_max_pooled(4);
+        assert_eq!(chunk_pool::pooled(), 4);
+
+        chunk_pool::clear();
+        assert_eq!(chunk_pool::pooled(), 0);
+    }
+
+    #[test]
+    fn chunk_pool_disabled() {
+        set_allocator();
+        chunk_pool::set_max_pooled(0);
+
+        let r = Rope::from(LARGE);
+        drop(r);
+        assert_eq!(chunk_pool::pooled(), 0);
+    }
+
+    #[test]
+    fn chunk_pool_edits() {
+        set_allocator();
+
+        let mut rng = rand::thread_rng();
+
+        for s in ["", TINY, LARGE, CURSED_LIPSUM] {
+            let mut r = Rope::from(s);
+            let mut string = s.to_owned();
+
+            for _ in 0..50 {
+                // Editing while the snapshot is alive copies the edited
+                // chunks, and dropping it gives their buffers to the pool.
+                let snapshot = r.clone();
+
+                let start = rng.gen_range(0..=r.byte_len());
+                let end = rng.gen_range(start..=r.byte_len());
+
+                if r.is_char_boundary(start) && r.is_char_boundary(end) {
+                    r.replace(start..end, "bar\r\n🦀");
+                    string.replace_range(start..end, "bar\r\n🦀");
+                }
+
+                drop(snapshot);
+
+                r.assert_invariants();
+                assert_eq!(r, string);
+                assert_eq!(r.line_len(), Rope::from(&*string).line_len());
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use rand::Rng;
+
 pub const TINY: &str = include_str!("tiny.txt");
 pub const SMALL: &str = include_str!("small.txt");
 pub const MEDIUM: &str = include_str!("medium.txt");
@@ -30,3 +32,22 @@ pub const TEXT_LINES: &str = "Hello there!  How're you doing?\nIt's a fine \
 pub const TEXT_EMOJI: &str = "Hello there!🐸  How're you doing?🐸  It's a \
                               fine day, isn't it?🐸  Aren't you glad we're \
                               alive?🐸  こんにちは、みんなさん！";
+
+/// Returns a random byte offset of `s` lying on a char boundary.
+pub fn random_char_boundary(rng: &mut impl Rng, s: &str) -> usize {
+    random_char_boundary_from(rng, s, 0)
+}
+
+/// Returns a random byte offset of `s` lying on a char boundary which is at
+/// least `min`.
+pub fn random_char_boundary_from(
+    rng: &mut impl Rng,
+    s: &str,
+    min: usize,
+) -> usize {
+    let mut offset = rng.gen_range(min..=s.len());
+    while !s.is_char_boundary(offset) {
+        offset += 1;
+    }
+    offset
+}
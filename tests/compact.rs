@@ -0,0 +1,58 @@
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, TINY};
+use crop::marks::Gravity;
+use crop::Rope;
+use rand::Rng;
+
+#[test]
+fn compact_after_edits() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", TINY, LARGE, CURSED_LIPSUM] {
+        let mut r = Rope::from(s);
+        let mut string = s.to_owned();
+
+        for _ in 0..200 {
+            let start = rng.gen_range(0..=r.byte_len());
+            let end = rng.gen_range(start..=(start + 10).min(r.byte_len()));
+
+            if r.is_char_boundary(start) && r.is_char_boundary(end) {
+                r.replace(start..end, "bar\r\n🦀");
+                string.replace_range(start..end, "bar\r\n🦀");
+            }
+        }
+
+        let snapshot = r.clone();
+
+        r.compact();
+        r.assert_invariants();
+        assert_eq!(r, string);
+        assert_eq!(snapshot, string);
+        #[cfg(feature = "line-metric")]
+        assert_eq!(r.line_len(), Rope::from(&*string).line_len());
+
+        let stats = r.mem_stats();
+        let fresh = Rope::from(&*string).mem_stats();
+        assert_eq!(stats.slack_bytes, 0);
+        assert_eq!(stats.nodes_per_depth, fresh.nodes_per_depth);
+
+        // The compacted rope can still be edited.
+        r.insert(0, "foo\n");
+        r.assert_invariants();
+        assert_eq!(r.byte_slice(..4), "foo\n");
+    }
+}
+
+#[test]
+fn compact_keeps_marks() {
+    let mut r = Rope::from(LARGE);
+    r.insert(1000, "foo");
+
+    let mark = r.add_mark(2000, Gravity::Left);
+
+    r.compact();
+    r.insert(0, "bar");
+
+    assert_eq!(r.marks().get(mark), Some(2003));
+}
@@ -0,0 +1,117 @@
+mod common;
+
+#[cfg(feature = "compression")]
+mod tests {
+    use crop::Rope;
+    use rand::Rng;
+
+    use crate::common::{CURSED_LIPSUM, LARGE, MEDIUM, TINY};
+
+    #[test]
+    fn compress_large() {
+        let mut r = Rope::from(LARGE);
+        let snapshot = r.clone();
+
+        r.compress();
+        r.assert_invariants();
+        assert_eq!(r, LARGE);
+        assert_eq!(snapshot, LARGE);
+        assert_eq!(r.line_len(), snapshot.line_len());
+
+        // Compressing again only evicts the decompressed chunks.
+        r.compress();
+        r.assert_invariants();
+        assert_eq!(r, LARGE);
+    }
+
+    /// Reads a compressed `Rope` from start to end while appending to it,
+    /// like a log that's being followed, and checks that the decompressed
+    /// chunks don't pile up.
+    #[test]
+    fn compress_read_while_appending() {
+        // Long enough to be split into more than twice as many chunks as the
+        // ones that can stay decompressed.
+        let text = MEDIUM.repeat(3);
+
+        let mut r = Rope::from(&*text);
+
+        // The text of a leaf is yielded in at most two chunks.
+        let max_leaf_len = 2 * r.chunks().map(str::len).max().unwrap();
+
+        r.compress();
+
+        // The chunks holding the appended text have room for more, which
+        // doesn't depend on how much of the `Rope` has been read.
+        let resident = |r: &Rope| {
+            let stats = r.mem_stats();
+            stats.leaf_bytes - stats.slack_bytes
+        };
+
+        let compressed = resident(&r);
+
+        let mut appended = 0;
+
+        for (line_idx, line) in text.lines().enumerate() {
+            assert_eq!(r.line(line_idx), line);
+
+            r.insert(r.byte_len(), "\nfoo");
+            appended += "\nfoo".len();
+
+            // Computing the memory stats walks all the leaves, so they're
+            // only checked every few lines.
+            if line_idx % 32 != 0 {
+                continue;
+            }
+
+            // At most 128 chunks are decompressed after every edit, plus the
+            // one that was edited.
+            assert!(
+                resident(&r) <= compressed + appended + 129 * max_leaf_len,
+                "{} bytes resident after reading line {line_idx}",
+                resident(&r),
+            );
+        }
+
+        assert_eq!(r.byte_slice(..text.len()), text);
+    }
+
+    /// Formatting a compressed `Rope` or hashing its contents doesn't keep
+    /// any decompressed chunk around.
+    #[test]
+    fn compress_read_without_borrowing() {
+        let mut r = Rope::from(MEDIUM);
+        r.compress();
+
+        let compressed = r.mem_stats().leaf_bytes;
+
+        assert_eq!(r.to_string(), MEDIUM);
+        assert_eq!(r.content_hash(), Rope::from(MEDIUM).content_hash());
+        assert_eq!(r.mem_stats().leaf_bytes, compressed);
+    }
+
+    #[test]
+    fn compress_edits() {
+        let mut rng = rand::thread_rng();
+
+        for s in ["", TINY, MEDIUM, CURSED_LIPSUM] {
+            let mut r = Rope::from(s);
+            let mut string = s.to_owned();
+
+            for _ in 0..20 {
+                r.compress();
+
+                let start = rng.gen_range(0..=r.byte_len());
+                let end = rng.gen_range(start..=r.byte_len());
+
+                if r.is_char_boundary(start) && r.is_char_boundary(end) {
+                    r.replace(start..end, "bar\r\n🦀");
+                    string.replace_range(start..end, "bar\r\n🦀");
+                }
+
+                r.assert_invariants();
+                assert_eq!(r, string);
+                assert_eq!(r.line_len(), Rope::from(&*string).line_len());
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use crop::{Rope, RopeBuilder, RopeSlice};
+use rand::Rng;
+
+mod common;
+
+use common::{
+    random_char_boundary,
+    CURSED_LIPSUM,
+    LARGE,
+    MEDIUM,
+    SMALL,
+    TINY,
+};
+
+#[test]
+fn content_hash_independent_of_chunking() {
+    for s in ["", "a", TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = Rope::from(s);
+
+        let mut builder = RopeBuilder::new();
+        for line in s.split_inclusive('\n') {
+            builder.append(line);
+        }
+        assert_eq!(builder.build().content_hash(), r.content_hash());
+
+        let mut pushed = Rope::new();
+        for chunk in s.split_inclusive(' ') {
+            pushed.push_str(chunk);
+        }
+        assert_eq!(pushed.content_hash(), r.content_hash());
+
+        assert_eq!(r.byte_slice(..).content_hash(), r.content_hash());
+        assert_eq!(r.clone().content_hash(), r.content_hash());
+    }
+}
+
+#[test]
+fn content_hash_random_edits() {
+    let mut rng = rand::thread_rng();
+
+    let insertions = ["", "a", "foo\n", "🦀", "\r\n"];
+
+    for s in [SMALL, MEDIUM, CURSED_LIPSUM] {
+        let mut r = Rope::from(s);
+        let mut string = s.to_owned();
+
+        for _ in 0..50 {
+            let start = random_char_boundary(&mut rng, &string);
+            let end = random_char_boundary(&mut rng, &string);
+            let (start, end) = (start.min(end), start.max(end));
+            let text = insertions[rng.gen_range(0..insertions.len())];
+
+            let hash_before = r.content_hash();
+            let changed = string[start..end] != *text;
+
+            r.replace(start..end, text);
+            string.replace_range(start..end, text);
+
+            r.assert_invariants();
+            assert_eq!(r.content_hash() != hash_before, changed);
+            assert_eq!(r.content_hash(), Rope::from(&*string).content_hash());
+        }
+    }
+}
+
+#[test]
+fn content_hash_distinguishes_permutations() {
+    let r = Rope::from("ab");
+    assert_ne!(r.content_hash(), Rope::from("ba").content_hash());
+    assert_ne!(r.content_hash(), Rope::new().content_hash());
+}
+
+/// A hasher that records every call to `write()`, so that it'd return a
+/// different hash for the same bytes written in different calls.
+#[derive(Default)]
+struct WriteRecorder(Vec<Vec<u8>>);
+
+impl Hasher for WriteRecorder {
+    fn finish(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.push(bytes.to_vec());
+    }
+}
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = WriteRecorder::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn hash_independent_of_chunking() {
+    for s in ["", "a", TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = Rope::from(s);
+
+        let mut pushed = Rope::new();
+        for chunk in s.split_inclusive(' ') {
+            pushed.push_str(chunk);
+        }
+
+        assert_eq!(hash(&pushed), hash(&r));
+        assert_eq!(hash(&r.byte_slice(..)), hash(&r));
+        assert_eq!(hash(&pushed.byte_slice(..)), hash(&r));
+    }
+}
+
+#[test]
+fn hash_as_set_key() {
+    let r = Rope::from("foo\nbar\nfoo\n");
+
+    let lines = [0..4, 4..8, 8..12]
+        .into_iter()
+        .map(|range| r.byte_slice(range))
+        .collect::<HashSet<RopeSlice<'_>>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.contains(&r.byte_slice(4..8)));
+
+    // The cached content hash is interior mutable, but it doesn't affect
+    // `Hash` or `Eq`.
+    #[allow(clippy::mutable_key_type)]
+    let mut ropes = HashSet::new();
+    ropes.insert(Rope::from("foo"));
+    assert!(!ropes.insert(Rope::from(r.byte_slice(..3))));
+    assert!(ropes.insert(Rope::from("foo\n")));
+}
@@ -0,0 +1,121 @@
+use crop::delta_sync::{Signature, SyncDelta, SyncError, SyncOp};
+use crop::Rope;
+use rand::Rng;
+
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, MEDIUM, TINY};
+
+#[test]
+fn delta_sync_identical() {
+    let r = Rope::from(LARGE);
+
+    let signature = Signature::new(r.byte_slice(..));
+    let delta = signature.delta(r.byte_slice(..));
+
+    assert_eq!(delta.ops(), [SyncOp::Copy(0..LARGE.len())]);
+    assert_eq!(delta.inserted_len(), 0);
+    assert_eq!(delta.apply(r.byte_slice(..)).unwrap(), LARGE);
+}
+
+#[test]
+fn delta_sync_empty() {
+    let empty = Rope::new();
+    let r = Rope::from(MEDIUM);
+
+    let signature = Signature::new(empty.byte_slice(..));
+    assert!(signature.blocks().is_empty());
+
+    let delta = signature.delta(r.byte_slice(..));
+    assert_eq!(delta.ops(), [SyncOp::Insert(MEDIUM.to_owned())]);
+    assert_eq!(delta.apply(empty.byte_slice(..)).unwrap(), MEDIUM);
+
+    let signature = Signature::new(r.byte_slice(..));
+    let delta = signature.delta(empty.byte_slice(..));
+    assert!(delta.ops().is_empty());
+    assert_eq!(delta.apply(r.byte_slice(..)).unwrap(), "");
+}
+
+#[test]
+fn delta_sync_only_sends_edited_blocks() {
+    let old = Rope::from(LARGE);
+    let mut new = old.clone();
+
+    new.insert(LARGE.len() / 3, "foo");
+    new.delete(LARGE.len() / 2..LARGE.len() / 2 + 100);
+
+    let signature = Signature::new(old.byte_slice(..));
+    assert_eq!(
+        signature.blocks().iter().map(|b| b.len).sum::<usize>(),
+        LARGE.len()
+    );
+
+    let delta = signature.delta(new.byte_slice(..));
+    assert!(delta.inserted_len() < LARGE.len() / 100);
+    assert_eq!(delta.apply(old.byte_slice(..)).unwrap(), new);
+}
+
+#[test]
+fn delta_sync_random_edits() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let old = Rope::from(s);
+        let mut new = old.clone();
+
+        for _ in 0..20 {
+            let start = rng.gen_range(0..=new.byte_len());
+            let end = rng.gen_range(start..=new.byte_len());
+
+            if new.is_char_boundary(start) && new.is_char_boundary(end) {
+                new.replace(start..end, "bar\r\n🦀");
+            }
+        }
+
+        let signature = Signature::new(old.byte_slice(..));
+        let synced = signature
+            .delta(new.byte_slice(..))
+            .apply(old.byte_slice(..))
+            .unwrap();
+
+        synced.assert_invariants();
+        assert_eq!(synced, new);
+    }
+}
+
+#[test]
+fn delta_sync_invalid_range() {
+    let r = Rope::from("Löwe 老虎 Léopard");
+
+    let delta = SyncDelta::from_ops(vec![SyncOp::Copy(0..100)]);
+    assert_eq!(
+        delta.apply(r.byte_slice(..)),
+        Err(SyncError::InvalidRange(0..100))
+    );
+
+    let delta = SyncDelta::from_ops(vec![SyncOp::Copy(0..2)]);
+    assert_eq!(
+        delta.apply(r.byte_slice(..)),
+        Err(SyncError::InvalidRange(0..2))
+    );
+}
+
+#[test]
+fn delta_sync_from_ops_merges() {
+    let delta = SyncDelta::from_ops(vec![
+        SyncOp::Copy(0..3),
+        SyncOp::Copy(3..5),
+        SyncOp::Insert("foo".to_owned()),
+        SyncOp::Insert("bar".to_owned()),
+        SyncOp::Copy(8..10),
+    ]);
+
+    assert_eq!(
+        delta.ops(),
+        [
+            SyncOp::Copy(0..5),
+            SyncOp::Insert("foobar".to_owned()),
+            SyncOp::Copy(8..10),
+        ]
+    );
+}
@@ -0,0 +1,50 @@
+mod common;
+
+#[cfg(feature = "digest")]
+mod tests {
+    use crop::{Rope, RopeBuilder};
+    use rand::Rng;
+    use sha2::{Digest, Sha256};
+
+    use crate::common::{CURSED_LIPSUM, LARGE, MEDIUM, SMALL, TINY};
+
+    #[test]
+    fn digest_independent_of_chunking() {
+        for s in ["", "a", TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+            let r = Rope::from(s);
+            assert_eq!(r.digest::<Sha256>(), Sha256::digest(s));
+
+            let mut builder = RopeBuilder::new();
+            for chunk in s.split_inclusive(' ') {
+                builder.append(chunk);
+            }
+            assert_eq!(builder.build().digest::<Sha256>(), Sha256::digest(s));
+        }
+    }
+
+    #[test]
+    fn digest_slices_random() {
+        let mut rng = rand::thread_rng();
+
+        for s in [TINY, SMALL, MEDIUM, LARGE] {
+            let r = Rope::from(s);
+
+            for _ in 0..20 {
+                let mut start = rng.gen_range(0..=s.len());
+                while !s.is_char_boundary(start) {
+                    start += 1;
+                }
+
+                let mut end = rng.gen_range(start..=s.len());
+                while !s.is_char_boundary(end) {
+                    end += 1;
+                }
+
+                assert_eq!(
+                    r.byte_slice(start..end).digest::<Sha256>(),
+                    Sha256::digest(&s[start..end])
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+#![allow(clippy::single_range_in_vec_init)]
+
+use crop::Rope;
+use rand::{thread_rng, Rng};
+
+mod common;
+
+use common::MEDIUM;
+
+#[test]
+fn dirty_lines_disabled_by_default() {
+    let mut r = Rope::from("foo\nbar\n");
+    r.insert(0, "a");
+    assert!(r.take_dirty_lines().is_empty());
+
+    r.track_dirty_lines(true);
+    r.insert(0, "a");
+    r.track_dirty_lines(false);
+    assert!(r.take_dirty_lines().is_empty());
+}
+
+#[test]
+fn dirty_lines_shift_after_line_edits() {
+    let mut r = Rope::from("a\nb\nc\nd\ne\n");
+    r.track_dirty_lines(true);
+
+    // Mark line 3 ("d") as dirty.
+    r.insert(6, "D");
+    assert_eq!(r.take_dirty_lines(), [3..4]);
+
+    r.insert(6, "D");
+    // Inserting two lines at the start shifts it down.
+    r.insert(0, "x\ny\n");
+    assert_eq!(r.take_dirty_lines(), [0..3, 5..6]);
+
+    r.insert(10, "D");
+    // Deleting the line break between "a" and "b" shifts it up.
+    r.delete(5..6);
+    assert_eq!(r.take_dirty_lines(), [2..3, 4..5]);
+}
+
+#[test]
+fn dirty_lines_merge_overlapping() {
+    let mut r = Rope::from("a\nb\nc\nd\ne\n");
+    r.track_dirty_lines(true);
+
+    r.insert(0, "A");
+    r.insert(5, "C");
+    r.insert(3, "B");
+
+    assert_eq!(r.take_dirty_lines(), [0..3]);
+}
+
+#[test]
+fn dirty_lines_clones_track_independently() {
+    let mut r = Rope::from("a\nb\n");
+    r.track_dirty_lines(true);
+
+    let mut clone = r.clone();
+    clone.insert(2, "B");
+    r.insert(0, "A");
+
+    assert_eq!(r.take_dirty_lines(), [0..1]);
+    assert_eq!(clone.take_dirty_lines(), [1..2]);
+}
+
+/// Checks that every line whose contents differ between `before` and
+/// `after`, taking into account the lines added or removed by the edit, is
+/// reported as dirty.
+#[cfg_attr(miri, ignore)]
+#[test]
+fn dirty_lines_random() {
+    let mut rng = thread_rng();
+
+    for _ in 0..20 {
+        let mut r = Rope::from(MEDIUM);
+        r.track_dirty_lines(true);
+
+        let before = r.clone();
+
+        for _ in 0..rng.gen_range(1..10) {
+            let start = rng.gen_range(0..=r.byte_len());
+            let end = rng.gen_range(start..=r.byte_len().min(start + 200));
+            let text = ["", "a", "foo\n", "\n\n"][rng.gen_range(0..4)];
+            r.replace(start..end, text);
+        }
+
+        let dirty = r.take_dirty_lines();
+
+        // Lines before the first dirty range and after the last one must be
+        // unchanged.
+        let first = dirty.first().unwrap().start;
+        let last = dirty.last().unwrap().end;
+
+        for line in 0..first.min(before.line_len()) {
+            assert_eq!(r.line(line), before.line(line));
+        }
+
+        let tail = r.line_len().saturating_sub(last);
+        for i in 1..=tail.min(before.line_len()) {
+            assert_eq!(
+                r.line(r.line_len() - i),
+                before.line(before.line_len() - i)
+            );
+        }
+
+        for window in dirty.windows(2) {
+            assert!(window[0].end < window[1].start);
+        }
+    }
+}
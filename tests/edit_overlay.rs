@@ -0,0 +1,140 @@
+use crop::edit_overlay::EditOverlay;
+use crop::Rope;
+use rand::Rng;
+
+mod common;
+
+use common::{random_char_boundary_from, CURSED_LIPSUM, SMALL, TINY};
+
+/// Adds random non-overlapping edits to an overlay over `text`, returning the
+/// overlay together with the expected string.
+fn random_overlay(text: &str) -> (Rope, EditOverlay, String) {
+    let mut rng = rand::thread_rng();
+
+    let rope = Rope::from(text);
+
+    // Pick the edits in increasing order to avoid overlaps, then add them in
+    // a shuffled order.
+    let mut edits = Vec::new();
+    let mut offset = 0;
+
+    while offset < text.len() && edits.len() < 20 {
+        let start = random_char_boundary_from(&mut rng, text, offset);
+        let end = random_char_boundary_from(&mut rng, text, start);
+        let inserted = ["", "a", "bcd", "\n", "🦀\r\n"][rng.gen_range(0..5)];
+        edits.push((start..end, inserted));
+        offset = end + 1;
+    }
+
+    let mut expected = String::new();
+    let mut offset = 0;
+    for (range, inserted) in &edits {
+        expected.push_str(&text[offset..range.start]);
+        expected.push_str(inserted);
+        offset = range.end;
+    }
+    expected.push_str(&text[offset..]);
+
+    let mut overlay = EditOverlay::new(&rope);
+    while !edits.is_empty() {
+        let (range, inserted) = edits.remove(rng.gen_range(0..edits.len()));
+        overlay.replace(range, inserted);
+    }
+
+    (rope, overlay, expected)
+}
+
+#[test]
+fn edit_overlay_random() {
+    for s in [TINY, SMALL, CURSED_LIPSUM] {
+        for _ in 0..10 {
+            let (mut rope, overlay, expected) = random_overlay(s);
+
+            let view = overlay.view();
+            view.assert_invariants();
+            assert_eq!(view, expected.as_str());
+            assert_eq!(view.line_len(), Rope::from(&*expected).line_len());
+            drop(view);
+
+            let into_rope = overlay.clone().into_rope();
+            into_rope.assert_invariants();
+            assert_eq!(into_rope, expected);
+
+            assert_eq!(rope, s);
+            overlay.commit(&mut rope);
+            rope.assert_invariants();
+            assert_eq!(rope, expected);
+        }
+    }
+}
+
+#[test]
+fn edit_overlay_insertions_at_same_offset() {
+    let r = Rope::from("ac");
+
+    let mut overlay = EditOverlay::new(&r);
+    overlay.insert(1, "b");
+    overlay.delete(1..2);
+    overlay.insert(1, "B");
+    overlay.insert(2, "d");
+
+    assert_eq!(overlay.len(), 4);
+    assert_eq!(overlay.view(), "abBd");
+
+    let edits = overlay.edits().map(|(r, t)| (r, t.to_string()));
+    assert_eq!(
+        edits.collect::<Vec<_>>(),
+        [
+            (1..1, "b".to_owned()),
+            (1..1, "B".to_owned()),
+            (1..2, String::new()),
+            (2..2, "d".to_owned()),
+        ]
+    );
+
+    let mut r = r;
+    overlay.commit(&mut r);
+    assert_eq!(r, "abBd");
+}
+
+#[test]
+fn edit_overlay_discard() {
+    let mut r = Rope::from("foo\nbar\n");
+    let revision = r.revision();
+
+    let mut overlay = EditOverlay::new(&r);
+    overlay.replace(.., "baz");
+    assert_eq!(overlay.view(), "baz");
+    drop(overlay);
+
+    assert_eq!(r, "foo\nbar\n");
+    assert_eq!(r.revision(), revision);
+    assert!(r.take_dirty_lines().is_empty());
+}
+
+#[test]
+#[should_panic]
+fn edit_overlay_overlapping_edits() {
+    let r = Rope::from("foo bar baz");
+    let mut overlay = EditOverlay::new(&r);
+    overlay.replace(4..7, "qux");
+    overlay.delete(6..9);
+}
+
+#[test]
+#[should_panic]
+fn edit_overlay_out_of_bounds() {
+    let r = Rope::from("foo");
+    let mut overlay = EditOverlay::new(&r);
+    overlay.insert(4, "bar");
+}
+
+#[test]
+#[should_panic]
+fn edit_overlay_commit_after_edit() {
+    let mut r = Rope::from("foo");
+    let mut overlay = EditOverlay::new(&r);
+    overlay.insert(3, "bar");
+    r.insert(0, "baz");
+    overlay.commit(&mut r);
+}
@@ -0,0 +1,121 @@
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, TINY};
+use crop::{EditInfo, Rope};
+use rand::Rng;
+
+#[test]
+fn edit_session_typing() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", TINY, LARGE, CURSED_LIPSUM] {
+        let mut r = Rope::from(s);
+        let mut string = s.to_owned();
+
+        let mut offset = 0;
+
+        {
+            let mut session = r.edit_session(0);
+
+            for _ in 0..1000 {
+                match rng.gen_range(0..10) {
+                    0 => {
+                        let ch = session.backspace();
+                        let expected = string[..offset].chars().next_back();
+                        assert_eq!(ch, expected);
+                        if let Some(ch) = ch {
+                            offset -= ch.len_utf8();
+                            string.remove(offset);
+                        }
+                    },
+                    1 => {
+                        let target = rng.gen_range(0..=string.len());
+                        if string.is_char_boundary(target) {
+                            session.move_to(target);
+                            offset = target;
+                            assert_eq!(session.rope(), string.as_str());
+                        }
+                    },
+                    _ => {
+                        let ch = ['a', '\n', 'ä', '🦀'][rng.gen_range(0..4)];
+                        session.insert_char(ch);
+                        string.insert(offset, ch);
+                        offset += ch.len_utf8();
+                    },
+                }
+
+                assert_eq!(session.offset(), offset);
+            }
+        }
+
+        r.assert_invariants();
+        assert_eq!(r, string);
+        assert_eq!(r.line_len(), Rope::from(&*string).line_len());
+    }
+}
+
+#[test]
+fn edit_session_single_edit_per_flush() {
+    let mut r = Rope::from("foo\n");
+
+    let edits = Arc::new(Mutex::new(Vec::new()));
+    r.subscribe({
+        let edits = Arc::clone(&edits);
+        move |edit| edits.lock().unwrap().push(edit.clone())
+    });
+
+    let mut session = r.edit_session(4);
+
+    for ch in "bar\nbaz".chars() {
+        session.insert_char(ch);
+    }
+
+    session.backspace();
+    session.insert_char('z');
+
+    assert_eq!(session.rope(), "foo\nbar\nbaz");
+
+    // Flushing again without any new text doesn't edit the `Rope`.
+    let _ = session.rope();
+
+    drop(session);
+
+    assert_eq!(r.line(2), "baz");
+    assert_eq!(
+        *edits.lock().unwrap(),
+        [EditInfo { byte_range: 4..4, inserted_len: 7 }]
+    );
+}
+
+#[test]
+fn edit_session_backspace_past_start() {
+    let mut r = Rope::from("foo bar");
+
+    let mut session = r.edit_session(4);
+    session.insert("x");
+
+    assert_eq!(session.backspace(), Some('x'));
+    assert_eq!(session.backspace(), Some(' '));
+    assert_eq!(session.backspace(), Some('o'));
+    session.insert("!");
+
+    drop(session);
+
+    assert_eq!(r, "fo!bar");
+}
+
+#[test]
+#[should_panic]
+fn edit_session_out_of_bounds() {
+    let mut r = Rope::from("foo");
+    let _ = r.edit_session(4);
+}
+
+#[test]
+#[should_panic]
+fn edit_session_not_char_boundary() {
+    let mut r = Rope::from("bär");
+    let _ = r.edit_session(2);
+}
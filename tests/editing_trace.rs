@@ -0,0 +1,110 @@
+mod common;
+
+#[cfg(feature = "editing-traces")]
+mod tests {
+    use crop::editing_trace::{EditingTrace, TracePatch};
+    use crop::Rope;
+    use rand::Rng;
+
+    use crate::common::{CURSED_LIPSUM, MEDIUM};
+
+    /// Generates a random trace starting from `start_content`, with char
+    /// positions.
+    fn random_trace(start_content: &str) -> EditingTrace {
+        let mut rng = rand::thread_rng();
+
+        let mut text = start_content.chars().collect::<Vec<_>>();
+
+        let mut patches = Vec::new();
+
+        for _ in 0..200 {
+            let pos = rng.gen_range(0..=text.len());
+            let del = rng.gen_range(0..=(text.len() - pos).min(5));
+            let ins = ["", "a", "ñ", "🦀\n", "bar"][rng.gen_range(0..5)];
+
+            text.splice(pos..pos + del, ins.chars());
+
+            patches.push(TracePatch { pos, del, ins: ins.to_owned() });
+        }
+
+        EditingTrace {
+            start_content: start_content.to_owned(),
+            end_content: text.into_iter().collect(),
+            patches,
+        }
+    }
+
+    #[test]
+    fn editing_trace_deserialize_editing_traces() {
+        let json = r#"{
+            "startContent": "",
+            "endContent": "Hi!",
+            "numAgents": 1,
+            "txns": [
+                { "time": "2023-01-01T00:00:00Z", "patches": [[0, 0, "Hi"]] },
+                { "patches": [[2, 0, "!"]] }
+            ]
+        }"#;
+
+        let trace = serde_json::from_str::<EditingTrace>(json).unwrap();
+
+        assert_eq!(trace.start_content, "");
+        assert_eq!(trace.end_content, "Hi!");
+        assert_eq!(trace.patches.len(), 2);
+        assert_eq!(trace.replay(), "Hi!");
+    }
+
+    #[test]
+    fn editing_trace_deserialize_automerge_perf() {
+        let json = r#"{
+            "edits": [[0, 0, "a"], [1, 0, "b"], [0, 1], [1, 0, "ç"]],
+            "finalText": "bç"
+        }"#;
+
+        let trace = serde_json::from_str::<EditingTrace>(json).unwrap();
+
+        assert_eq!(
+            trace.patches[2],
+            TracePatch { pos: 0, del: 1, ins: String::new() }
+        );
+        assert_eq!(trace.replay(), trace.end_content);
+    }
+
+    #[test]
+    fn editing_trace_deserialize_invalid() {
+        assert!(serde_json::from_str::<EditingTrace>(r#"{ "edits": [[0]] }"#)
+            .is_err());
+        assert!(serde_json::from_str::<EditingTrace>("[]").is_err());
+    }
+
+    #[test]
+    fn editing_trace_random() {
+        for s in ["", MEDIUM, CURSED_LIPSUM] {
+            let trace = random_trace(s);
+
+            let r = trace.replay();
+            r.assert_invariants();
+            assert_eq!(r, trace.end_content);
+
+            let mut r = Rope::from(s);
+
+            for (range, text) in trace.byte_edits() {
+                r.replace(range, text);
+            }
+
+            assert_eq!(r, trace.end_content);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn editing_trace_out_of_bounds() {
+        let trace = EditingTrace {
+            start_content: "ñ".into(),
+            end_content: String::new(),
+            patches: vec![TracePatch { pos: 0, del: 2, ins: String::new() }],
+        };
+
+        trace.replay();
+    }
+}
@@ -0,0 +1,202 @@
+mod common;
+
+#[cfg(feature = "encoding")]
+mod tests {
+    use std::io::{ErrorKind, Read};
+
+    use crop::{Bom, Rope};
+    use encoding_rs::{
+        Encoding,
+        SHIFT_JIS,
+        UTF_16BE,
+        UTF_16LE,
+        UTF_8,
+        WINDOWS_1252,
+    };
+
+    use crate::common::{CURSED_LIPSUM, LARGE, MEDIUM, SMALL, TINY};
+
+    /// A reader yielding at most `max` bytes at a time, so that multi-byte
+    /// sequences are split across reads.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        max: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.max.min(buf.len()).min(self.bytes.len());
+            buf[..len].copy_from_slice(&self.bytes[..len]);
+            self.bytes = &self.bytes[len..];
+            Ok(len)
+        }
+    }
+
+    fn encode(s: &str, encoding: &'static Encoding) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        Rope::from(s).write_with_encoding(&mut bytes, encoding).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn encoding_utf16_roundtrip() {
+        for s in [TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+            for encoding in [UTF_16LE, UTF_16BE] {
+                let bytes = encode(s, encoding);
+
+                let expected = s
+                    .encode_utf16()
+                    .flat_map(|unit| {
+                        if encoding == UTF_16LE {
+                            unit.to_le_bytes()
+                        } else {
+                            unit.to_be_bytes()
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                assert_eq!(bytes, expected);
+
+                let r = Rope::from_reader_with_encoding(&*bytes, encoding)
+                    .unwrap();
+
+                assert_eq!(r, s);
+            }
+        }
+    }
+
+    #[test]
+    fn encoding_windows_1252_roundtrip() {
+        let s = "Café, naïve façade — “quoted” €5\n".repeat(1000);
+
+        let bytes = encode(&s, WINDOWS_1252);
+
+        assert_eq!(bytes.len(), s.chars().count());
+        assert_eq!(&*WINDOWS_1252.encode(&s).0, &*bytes);
+
+        let r =
+            Rope::from_reader_with_encoding(&*bytes, WINDOWS_1252).unwrap();
+        assert_eq!(r, s);
+    }
+
+    #[test]
+    fn encoding_shift_jis_roundtrip() {
+        let s = "こんにちは、世界！ｶﾀｶﾅ and ASCII\n".repeat(1000);
+
+        let bytes = encode(&s, SHIFT_JIS);
+
+        assert_eq!(&*SHIFT_JIS.encode(&s).0, &*bytes);
+
+        let r = Rope::from_reader_with_encoding(&*bytes, SHIFT_JIS).unwrap();
+        assert_eq!(r, s);
+    }
+
+    #[test]
+    fn encoding_utf8_is_identity() {
+        let bytes = encode(CURSED_LIPSUM, UTF_8);
+        assert_eq!(bytes, CURSED_LIPSUM.as_bytes());
+
+        let r = Rope::from_reader_with_encoding(&*bytes, UTF_8).unwrap();
+        assert_eq!(r, CURSED_LIPSUM);
+    }
+
+    #[test]
+    fn encoding_sequences_split_across_reads() {
+        let s = "こんにちは、世界！\n".repeat(100);
+
+        for encoding in [UTF_16LE, UTF_16BE, SHIFT_JIS, UTF_8] {
+            let bytes = encode(&s, encoding);
+
+            for max in [1, 3, 7] {
+                let reader = Trickle { bytes: &bytes, max };
+                let r =
+                    Rope::from_reader_with_encoding(reader, encoding).unwrap();
+                assert_eq!(r, s);
+            }
+        }
+    }
+
+    #[test]
+    fn encoding_malformed_input_is_replaced() {
+        // An unpaired surrogate followed by an odd trailing byte.
+        let bytes = [0x61, 0x00, 0x00, 0xd8, 0x62, 0x00, 0x63];
+
+        let r = Rope::from_reader_with_encoding(&bytes[..], UTF_16LE).unwrap();
+
+        assert_eq!(r, "a\u{fffd}b\u{fffd}");
+    }
+
+    #[test]
+    fn encoding_bom_is_kept() {
+        let bytes = [0xff, 0xfe, 0x61, 0x00];
+
+        let r = Rope::from_reader_with_encoding(&bytes[..], UTF_16LE).unwrap();
+
+        assert_eq!(r, "\u{feff}a");
+    }
+
+    #[test]
+    fn encoding_unmappable_char_errors() {
+        let r = Rope::from("Hello, 🦀!");
+
+        let err =
+            r.write_with_encoding(&mut Vec::new(), WINDOWS_1252).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("windows-1252"));
+    }
+
+    #[test]
+    fn encoding_empty() {
+        let r = Rope::new();
+
+        for encoding in [UTF_16LE, WINDOWS_1252, SHIFT_JIS] {
+            let bytes = encode("", encoding);
+            assert!(bytes.is_empty());
+
+            let r2 =
+                Rope::from_reader_with_encoding(&*bytes, encoding).unwrap();
+            assert_eq!(r2, r);
+        }
+    }
+
+    #[test]
+    fn encoding_bom_detected_and_stripped() {
+        let s = "Hé, 世界!\n".repeat(100);
+
+        for bom in [Bom::Utf8, Bom::Utf16Le, Bom::Utf16Be] {
+            let mut bytes = bom.as_bytes().to_vec();
+            bytes.extend(encode(&s, bom.encoding()));
+
+            for max in [1, 2, 1 << 16] {
+                let reader = Trickle { bytes: &bytes, max };
+
+                let (r, detected) =
+                    Rope::from_reader_with_bom(reader, WINDOWS_1252).unwrap();
+
+                assert_eq!(r, s);
+                assert_eq!(detected, Some(bom));
+            }
+        }
+    }
+
+    #[test]
+    fn encoding_bom_fallback() {
+        let s = "Café\n";
+
+        let bytes = encode(s, WINDOWS_1252);
+
+        let (r, bom) =
+            Rope::from_reader_with_bom(&*bytes, WINDOWS_1252).unwrap();
+
+        assert_eq!(r, s);
+        assert_eq!(bom, None);
+
+        // Inputs shorter than a BOM.
+        for bytes in [&b""[..], b"a", b"ab", b"\xEF\xBB"] {
+            let (r, bom) = Rope::from_reader_with_bom(bytes, UTF_8).unwrap();
+            assert_eq!(r, String::from_utf8_lossy(bytes));
+            assert_eq!(bom, None);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crop::Rope;
+
+mod common;
+
+use common::{LARGE, TINY};
+
+#[test]
+fn filter_lines_matches_str() {
+    for text in [TINY, LARGE, "", "\n", "foo\r\nbar"] {
+        let r = Rope::from(text);
+
+        let predicate = |line: &str| line.len() % 3 == 0;
+
+        let expected = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| predicate(line))
+            .collect::<Vec<_>>();
+
+        let filtered = r.filter_lines(|line| predicate(&line.to_string()));
+
+        assert_eq!(filtered.len(), expected.len());
+
+        for (idx, (original, line)) in filtered.iter().enumerate() {
+            assert_eq!(original, expected[idx].0);
+            assert_eq!(line, expected[idx].1);
+            assert_eq!(filtered.line(idx), line);
+            assert_eq!(filtered.original_line(idx), original);
+            assert_eq!(filtered.filtered_line(original), Some(idx));
+        }
+    }
+}
+
+#[test]
+fn filter_lines_none() {
+    let r = Rope::from("foo\nbar\n");
+
+    let filtered = r.filter_lines(|_| false);
+
+    assert!(filtered.is_empty());
+    assert_eq!(filtered.filtered_line(0), None);
+    assert_eq!(filtered.iter().count(), 0);
+}
+
+#[test]
+#[should_panic]
+fn filter_lines_out_of_bounds() {
+    let r = Rope::from("foo\nbar\n");
+    let filtered = r.filter_lines(|line| line == "bar");
+    filtered.line(1);
+}
@@ -0,0 +1,128 @@
+use crop::folded_view::FoldedView;
+use crop::Rope;
+use rand::Rng;
+
+mod common;
+
+use common::{random_char_boundary, CURSED_LIPSUM, SMALL, TINY};
+
+/// Checks the view and the offset mappings against a mask of the hidden
+/// bytes of `s`.
+fn check(folded: &FoldedView<'_>, s: &str, hidden: &[bool]) {
+    let visible = s
+        .bytes()
+        .zip(hidden)
+        .filter_map(|(byte, &hidden)| (!hidden).then_some(byte))
+        .collect::<Vec<_>>();
+
+    let view = folded.view();
+    view.assert_invariants();
+    assert_eq!(view, std::str::from_utf8(&visible).unwrap());
+
+    let mut view_offset = 0;
+
+    for byte_offset in 0..=s.len() {
+        assert_eq!(folded.to_view_offset(byte_offset), view_offset);
+
+        // Every view offset is mapped back to the first byte offset mapped to
+        // it.
+        if byte_offset == 0 || !hidden[byte_offset - 1] {
+            assert_eq!(folded.to_rope_offset(view_offset), byte_offset);
+        }
+
+        if byte_offset < s.len() {
+            assert_eq!(
+                folded.fold_of_byte(byte_offset).is_some(),
+                hidden[byte_offset]
+            );
+
+            view_offset += !hidden[byte_offset] as usize;
+        }
+    }
+
+    assert_eq!(view_offset, view.byte_len());
+
+    let folds = folded.folds();
+    for pair in folds.windows(2) {
+        assert!(pair[0].end < pair[1].start);
+    }
+    for fold in folds {
+        assert!(fold.start < fold.end);
+        assert!(hidden[fold.clone()].iter().all(|&h| h));
+    }
+}
+
+#[test]
+fn folded_view_random() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, SMALL, CURSED_LIPSUM] {
+        let r = Rope::from(s);
+        let mut folded = FoldedView::new(&r);
+        let mut hidden = vec![false; s.len()];
+
+        check(&folded, s, &hidden);
+
+        for _ in 0..50 {
+            let start = random_char_boundary(&mut rng, s);
+            let mut end = random_char_boundary(&mut rng, s);
+            let start = start.min(end);
+            end = end.max(start);
+
+            let fold = rng.gen_bool(0.6);
+
+            if fold {
+                folded.fold(start..end);
+            } else {
+                folded.unfold(start..end);
+            }
+
+            hidden[start..end].iter_mut().for_each(|h| *h = fold);
+
+            check(&folded, s, &hidden);
+        }
+    }
+}
+
+#[test]
+fn folded_view_merges_touching_folds() {
+    let r = Rope::from("foo bar baz");
+
+    let mut folded = FoldedView::new(&r);
+    folded.fold(3..4);
+    folded.fold(7..8);
+    folded.fold(4..7);
+    assert_eq!(folded.folds().len(), 1);
+    assert_eq!(folded.folds()[0], 3..8);
+    assert_eq!(folded.view(), "foobaz");
+
+    folded.unfold(5..6);
+    assert_eq!(folded.folds(), [3..5, 6..8]);
+    assert_eq!(folded.view(), "fooabaz");
+    assert_eq!(folded.to_view_offset(4), 3);
+    assert_eq!(folded.to_view_offset(5), 3);
+    assert_eq!(folded.to_view_offset(6), 4);
+    assert_eq!(folded.to_rope_offset(3), 3);
+    assert_eq!(folded.to_rope_offset(4), 6);
+
+    folded.unfold(..);
+    assert!(folded.folds().is_empty());
+    assert_eq!(folded.view(), "foo bar baz");
+}
+
+#[test]
+#[should_panic]
+fn folded_view_fold_not_char_boundary() {
+    let r = Rope::from("🦀");
+    let mut folded = FoldedView::new(&r);
+    folded.fold(1..4);
+}
+
+#[test]
+#[should_panic]
+fn folded_view_to_rope_offset_out_of_bounds() {
+    let r = Rope::from("foo bar");
+    let mut folded = FoldedView::new(&r);
+    folded.fold(..4);
+    folded.to_rope_offset(4);
+}
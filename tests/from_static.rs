@@ -0,0 +1,48 @@
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, SMALL, TINY};
+use crop::Rope;
+use rand::Rng;
+
+#[test]
+fn from_static() {
+    for s in ["", "a", TINY, SMALL, LARGE, CURSED_LIPSUM] {
+        let r = Rope::from_static(s);
+        r.assert_invariants();
+        assert_eq!(r, s);
+        assert_eq!(r, Rope::from(s));
+    }
+}
+
+#[test]
+fn from_static_doesnt_copy_text() {
+    let stats = Rope::from_static(LARGE).mem_stats();
+    assert_eq!(stats.text_bytes, LARGE.len());
+    assert_eq!(stats.leaf_bytes, 0);
+    assert_eq!(stats.slack_bytes, 0);
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn from_static_edits() {
+    let mut rng = rand::thread_rng();
+
+    let mut r = Rope::from_static(LARGE);
+    let mut string = LARGE.to_owned();
+    let snapshot = r.clone();
+
+    for _ in 0..20 {
+        let start = rng.gen_range(0..=r.byte_len());
+        let end = rng.gen_range(start..=r.byte_len());
+
+        if r.is_char_boundary(start) && r.is_char_boundary(end) {
+            r.replace(start..end, "foo\r\n🦀");
+            string.replace_range(start..end, "foo\r\n🦀");
+            r.assert_invariants();
+            assert_eq!(r, string);
+        }
+    }
+
+    assert_eq!(snapshot, LARGE);
+    assert!(r.mem_stats().leaf_bytes > 0);
+}
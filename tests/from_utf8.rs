@@ -0,0 +1,121 @@
+use crop::{Bom, Rope};
+use rand::Rng;
+
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, MEDIUM, TINY};
+
+#[test]
+fn from_utf8_valid() {
+    for s in ["", "a", TINY, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = Rope::from_utf8(s.as_bytes().to_vec()).unwrap();
+        r.assert_invariants();
+        assert_eq!(r, s);
+
+        let r = Rope::try_from(s.as_bytes()).unwrap();
+        r.assert_invariants();
+        assert_eq!(r, s);
+    }
+}
+
+#[test]
+fn from_utf8_code_points_straddling_windows() {
+    // Make sure multi-byte code points land on every possible offset
+    // relative to the internal validation windows.
+    let s = CURSED_LIPSUM.repeat(1 << 11);
+
+    for offset in 0..4 {
+        let s = &s[s.char_indices().nth(offset).unwrap().0..];
+        let r = Rope::try_from(s.as_bytes()).unwrap();
+        r.assert_invariants();
+        assert_eq!(r, s);
+    }
+}
+
+#[test]
+fn from_utf8_invalid() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, MEDIUM, LARGE, CURSED_LIPSUM] {
+        for _ in 0..10 {
+            let mut bytes = s.as_bytes().to_vec();
+
+            let idx = rng.gen_range(0..bytes.len());
+            bytes[idx] = [0xff, 0x80, 0xc3][rng.gen_range(0..3)];
+
+            let expected = core::str::from_utf8(&bytes);
+
+            match Rope::try_from(&*bytes) {
+                Ok(r) => assert_eq!(r, expected.unwrap()),
+                Err(err) => assert_eq!(err, expected.unwrap_err()),
+            }
+
+            match Rope::from_utf8(bytes.clone()) {
+                Ok(r) => assert_eq!(r, expected.unwrap()),
+                Err(err) => {
+                    assert_eq!(err.utf8_error(), expected.unwrap_err());
+                    assert_eq!(err.into_bytes(), bytes);
+                },
+            }
+        }
+    }
+}
+
+#[test]
+fn from_utf8_invalid_around_windows() {
+    // Put an invalid byte on every offset around the end of the first
+    // internal validation window.
+    let s = CURSED_LIPSUM.repeat(1 << 10);
+
+    for idx in (1 << 16) - 8..(1 << 16) + 8 {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes[idx] = 0xff;
+
+        let expected = core::str::from_utf8(&bytes).unwrap_err();
+        assert_eq!(Rope::try_from(&*bytes).unwrap_err(), expected);
+    }
+}
+
+#[test]
+fn from_utf8_truncated_at_end() {
+    let bytes = "foo🌎".as_bytes();
+
+    let err = Rope::try_from(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(err.valid_up_to(), 3);
+    assert_eq!(err.error_len(), None);
+
+    let mut long = LARGE.as_bytes().to_vec();
+    long.extend_from_slice(&bytes[..bytes.len() - 2]);
+
+    let err = Rope::try_from(&*long).unwrap_err();
+    assert_eq!(err.valid_up_to(), LARGE.len() + 3);
+}
+
+#[test]
+fn from_utf8_with_bom() {
+    for s in ["", "a", TINY, MEDIUM, CURSED_LIPSUM] {
+        let mut bytes = Bom::Utf8.as_bytes().to_vec();
+        bytes.extend_from_slice(s.as_bytes());
+
+        let (r, bom) = Rope::from_utf8_with_bom(&bytes).unwrap();
+        r.assert_invariants();
+        assert_eq!(r, s);
+        assert_eq!(bom, Some(Bom::Utf8));
+
+        let (r, bom) = Rope::from_utf8_with_bom(s.as_bytes()).unwrap();
+        assert_eq!(r, s);
+        assert_eq!(bom, None);
+    }
+
+    // Only the first BOM is stripped.
+    let (r, _) =
+        Rope::from_utf8_with_bom(b"\xEF\xBB\xBF\xEF\xBB\xBFa").unwrap();
+    assert_eq!(r, "\u{feff}a");
+
+    let err = Rope::from_utf8_with_bom(b"\xEF\xBB\xBFab\xFFc").unwrap_err();
+    assert_eq!(err.valid_up_to(), 5);
+
+    // A UTF-16 BOM isn't valid UTF-8.
+    let err = Rope::from_utf8_with_bom(b"\xFF\xFEa\x00").unwrap_err();
+    assert_eq!(err.valid_up_to(), 0);
+}
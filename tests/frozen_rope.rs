@@ -0,0 +1,57 @@
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, TINY};
+use crop::{FrozenRope, Rope};
+use rand::Rng;
+
+#[test]
+fn frozen_rope_from_edited_rope() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", TINY, LARGE, CURSED_LIPSUM] {
+        let mut r = Rope::from(s);
+
+        for _ in 0..20 {
+            let start = rng.gen_range(0..=r.byte_len());
+            let end = rng.gen_range(start..=r.byte_len());
+
+            if r.is_char_boundary(start) && r.is_char_boundary(end) {
+                r.replace(start..end, "bar\r\n🦀");
+            }
+        }
+
+        let frozen = FrozenRope::from(&r);
+
+        assert_eq!(r, frozen);
+        assert_eq!(frozen.byte_len(), r.byte_len());
+        assert_eq!(frozen.is_empty(), r.is_empty());
+
+        let rope_ref = frozen.as_rope_ref();
+        assert_eq!(rope_ref.line_len(), r.line_len());
+
+        for _ in 0..100 {
+            let line = rng.gen_range(0..=r.line_len());
+            assert_eq!(rope_ref.byte_of_line(line), r.byte_of_line(line));
+        }
+
+        let thawed = Rope::from(&frozen);
+        thawed.assert_invariants();
+        assert_eq!(thawed, r);
+    }
+}
+
+#[test]
+fn frozen_rope_clones_share_text() {
+    let frozen = FrozenRope::from(LARGE.to_owned());
+    let clone = frozen.clone();
+
+    assert_eq!(frozen.as_str().as_ptr(), clone.as_str().as_ptr());
+    assert_eq!(frozen, clone);
+    assert_ne!(frozen, FrozenRope::from(TINY));
+}
+
+#[test]
+fn frozen_rope_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<FrozenRope>();
+}
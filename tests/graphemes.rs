@@ -131,6 +131,50 @@ fn graphemes_is_boundary_two_flags() {
     assert!(r.is_grapheme_boundary(16));
 }
 
+#[cfg(feature = "graphemes")]
+#[test]
+fn graphemes_is_boundary_ascii() {
+    let r = Rope::from("a\r\nb\rc\n\r");
+    assert!(r.is_ascii());
+
+    for i in 0..=r.byte_len() {
+        assert_eq!(r.is_grapheme_boundary(i), i != 2);
+    }
+
+    let s = r.byte_slice(2..);
+    assert!(s.is_grapheme_boundary(0));
+}
+
+#[cfg(feature = "graphemes")]
+#[test]
+fn graphemes_is_boundary_mixed() {
+    // No regional indicators since the boundaries are taken from
+    // `graphemes()`, see the TODO above.
+    let text = "a\r\nbü\r\nc\u{301}d🐻\u{200d}❄\u{fe0f}\r".repeat(64);
+    let r = Rope::from(text.as_str());
+    assert!(!r.is_ascii());
+
+    let mut boundaries = vec![false; r.byte_len() + 1];
+    let mut offset = 0;
+
+    for grapheme in r.graphemes() {
+        boundaries[offset] = true;
+        offset += grapheme.len();
+    }
+
+    boundaries[offset] = true;
+
+    for (i, &is_boundary) in boundaries.iter().enumerate() {
+        assert_eq!(r.is_grapheme_boundary(i), is_boundary, "{i}");
+    }
+
+    let s = r.byte_slice(1..r.byte_len() - 1);
+
+    for i in 0..=s.byte_len() {
+        assert_eq!(s.is_grapheme_boundary(i), i == 0 || boundaries[i + 1]);
+    }
+}
+
 #[cfg(feature = "graphemes")]
 #[should_panic]
 #[test]
@@ -0,0 +1,422 @@
+use std::sync::{Arc, Mutex};
+
+use crop::history::{Change, Grouping, History};
+use crop::{EditInfo, Rope};
+use rand::{thread_rng, Rng};
+
+mod common;
+
+use common::TEXT;
+
+#[test]
+fn history_undo_redo_roundtrip() {
+    let mut h = History::new(Rope::from(TEXT));
+
+    let mut states = vec![h.rope().clone()];
+
+    h.insert(0, "🐸");
+    states.push(h.rope().clone());
+
+    h.delete(10..40);
+    states.push(h.rope().clone());
+
+    h.replace(h.byte_len() - 3.., "\r\n");
+    states.push(h.rope().clone());
+
+    h.delete(..);
+    states.push(h.rope().clone());
+
+    for state in states.iter().rev().skip(1) {
+        assert!(h.undo());
+        h.assert_invariants();
+        assert_eq!(*h, *state);
+    }
+
+    assert!(!h.undo());
+
+    for state in states.iter().skip(1) {
+        assert!(h.redo());
+        h.assert_invariants();
+        assert_eq!(*h, *state);
+    }
+
+    assert!(!h.redo());
+}
+
+#[test]
+fn history_undo_redo_are_single_edits() {
+    // Both the removed and the inserted text span many chunks.
+    let text = TEXT.repeat(100);
+
+    let mut r = Rope::from(&*text);
+
+    let edits = Arc::new(Mutex::new(Vec::new()));
+    let cloned = Arc::clone(&edits);
+    r.subscribe(move |edit| cloned.lock().unwrap().push(edit.clone()));
+
+    let mut h = History::new(r);
+
+    let half = text.len() / 2;
+
+    h.replace(10..half, &text);
+    edits.lock().unwrap().clear();
+
+    assert!(h.undo());
+    assert_eq!(*h, text);
+
+    assert!(h.redo());
+    h.assert_invariants();
+
+    assert_eq!(
+        *edits.lock().unwrap(),
+        [
+            EditInfo {
+                byte_range: 10..10 + text.len(),
+                inserted_len: half - 10,
+            },
+            EditInfo { byte_range: 10..half, inserted_len: text.len() },
+        ]
+    );
+}
+
+#[test]
+fn history_new_edit_clears_redo() {
+    let mut h = History::new(Rope::from(TEXT));
+
+    h.insert(0, "a");
+    h.insert(0, "b");
+    assert!(h.undo());
+    assert!(h.can_redo());
+
+    h.insert(0, "c");
+    assert!(!h.can_redo());
+    assert!(!h.redo());
+
+    assert!(h.undo());
+    assert!(h.undo());
+    assert_eq!(*h, TEXT);
+}
+
+#[test]
+fn history_max_depth() {
+    let mut h = History::new(Rope::new());
+    h.set_max_depth(Some(2));
+
+    h.insert(0, "a");
+    h.insert(1, "b");
+    h.insert(2, "c");
+
+    assert!(h.undo());
+    assert!(h.undo());
+    assert!(!h.undo());
+    assert_eq!(*h, "a");
+}
+
+#[test]
+fn history_memory_budget() {
+    let mut h = History::new(Rope::new());
+
+    h.insert(0, "aaaa");
+    h.insert(4, "bbbb");
+    h.insert(8, "cccc");
+    assert_eq!(h.memory_used(), 12);
+
+    h.set_memory_budget(Some(8));
+    assert_eq!(h.memory_used(), 8);
+
+    assert!(h.undo());
+    assert!(h.undo());
+    assert!(!h.undo());
+    assert_eq!(*h, "aaaa");
+
+    h.clear();
+    assert_eq!(h.memory_used(), 0);
+    assert!(!h.can_redo());
+}
+
+#[test]
+fn history_undo_tree_branches() {
+    let mut h = History::new(Rope::from("ab"));
+    let root = h.current();
+
+    h.insert(2, "c");
+    let abc = h.current();
+    h.insert(3, "d");
+    let abcd = h.current();
+
+    assert!(h.undo());
+    assert!(h.undo());
+    h.insert(0, "x");
+    let xab = h.current();
+
+    h.undo();
+    h.insert(2, "y");
+    let aby = h.current();
+
+    assert_eq!(h.branches().collect::<Vec<_>>(), [abcd, xab, aby]);
+    assert_eq!(h.children(root), [abc, xab, aby]);
+    assert_eq!(h.parent(abcd), Some(abc));
+    assert_eq!(h.parent(root), None);
+
+    assert!(h.jump_to(abcd));
+    assert_eq!(*h, "abcd");
+    h.assert_invariants();
+
+    assert!(h.jump_to(xab));
+    assert_eq!(*h, "xab");
+
+    assert!(h.jump_to(aby));
+    assert_eq!(*h, "aby");
+
+    assert!(h.jump_to(root));
+    assert_eq!(*h, "ab");
+
+    // Redo follows the most recently visited branch.
+    assert!(h.redo());
+    assert_eq!(*h, "aby");
+
+    assert!(h.jump_to(abc));
+    assert!(h.undo());
+    assert!(h.redo());
+    assert_eq!(*h, "abc");
+    assert!(h.redo());
+    assert_eq!(*h, "abcd");
+}
+
+#[test]
+fn history_undo_tree_forgets_oldest() {
+    let mut h = History::new(Rope::new());
+
+    h.insert(0, "aaaa");
+    let a = h.current();
+    h.undo();
+    h.insert(0, "bbbb");
+    h.insert(4, "cccc");
+
+    h.set_memory_budget(Some(8));
+
+    // The abandoned "aaaa" branch is the oldest state, so it goes first.
+    assert!(!h.jump_to(a));
+    assert_eq!(h.memory_used(), 8);
+    assert_eq!(h.branches().count(), 1);
+
+    h.set_max_depth(Some(1));
+    assert!(h.undo());
+    assert!(!h.undo());
+    assert_eq!(*h, "bbbb");
+}
+
+#[test]
+fn history_grouping_typing() {
+    let mut h = History::new(Rope::new());
+    h.set_grouping(Grouping::new().coalesce_typing(true));
+
+    for (offset, ch) in "hello world".char_indices() {
+        h.insert(offset, ch.encode_utf8(&mut [0; 4]));
+    }
+
+    // Backspacing is coalesced too.
+    h.delete(10..11);
+    h.delete(9..10);
+
+    // Edits in a different region start a new group.
+    h.insert(0, ">");
+
+    assert_eq!(*h, ">hello wor");
+
+    assert!(h.undo());
+    assert_eq!(*h, "hello wor");
+
+    assert!(h.undo());
+    assert_eq!(*h, "hello world");
+
+    assert!(h.undo());
+    assert_eq!(*h, "hello");
+
+    assert!(h.undo());
+    assert_eq!(*h, "");
+
+    assert!(!h.undo());
+}
+
+#[test]
+fn history_grouping_time_gap() {
+    use core::sync::atomic::{AtomicU64, Ordering};
+    use core::time::Duration;
+
+    static NOW: AtomicU64 = AtomicU64::new(0);
+
+    fn clock() -> Duration {
+        Duration::from_millis(NOW.load(Ordering::Relaxed))
+    }
+
+    let mut h = History::new(Rope::new());
+    h.set_grouping(Grouping::new().max_gap(Duration::from_millis(500), clock));
+
+    h.insert(0, "a");
+    NOW.store(200, Ordering::Relaxed);
+    h.insert(0, "b");
+    NOW.store(1000, Ordering::Relaxed);
+    h.insert(0, "c");
+
+    assert!(h.undo());
+    assert_eq!(*h, "ba");
+    assert!(h.undo());
+    assert_eq!(*h, "");
+}
+
+#[test]
+fn history_explicit_groups() {
+    let mut h = History::new(Rope::from("foo"));
+
+    h.begin_group();
+    h.insert(0, "(");
+    h.begin_group();
+    h.insert(4, ")");
+    h.end_group();
+    h.insert(0, "!");
+    h.end_group();
+
+    h.insert(0, "?");
+
+    assert_eq!(*h, "?!(foo)");
+
+    assert!(h.undo());
+    assert_eq!(*h, "!(foo)");
+
+    assert!(h.undo());
+    assert_eq!(*h, "foo");
+
+    assert!(h.redo());
+    assert_eq!(*h, "!(foo)");
+
+    // Unbalanced calls to `end_group()` are ignored.
+    h.end_group();
+    h.insert(0, "a");
+    h.insert(1, "b");
+    assert!(h.undo());
+    assert_eq!(*h, "a!(foo)");
+}
+
+#[test]
+fn history_checkpoint_diff_and_revert() {
+    let mut h = History::new(Rope::from("foo\nbar\nbaz\n"));
+
+    let saved = h.checkpoint("saved");
+    assert_eq!(saved.label(), "saved");
+    assert!(h.diff_since(&saved).is_empty());
+
+    // Edits that cancel out are not reported.
+    h.insert(4, "x");
+    h.delete(4..5);
+    assert!(h.diff_since(&saved).is_empty());
+
+    h.replace(4..7, "BAR");
+    h.insert(h.byte_len(), "qux\n");
+
+    assert_eq!(
+        h.diff_since(&saved),
+        [Change { old: 4..7, new: 4..7 }, Change { old: 12..12, new: 12..16 }]
+    );
+
+    // The diff also works across branches of the undo tree.
+    h.undo();
+    h.undo();
+    h.undo();
+    h.undo();
+    h.insert(0, "!");
+    assert_eq!(h.diff_since(&saved), [Change { old: 0..0, new: 0..1 }]);
+
+    h.revert_to(&saved);
+    assert_eq!(*h, "foo\nbar\nbaz\n");
+    assert!(h.undo());
+    assert_eq!(*h, "!foo\nbar\nbaz\n");
+}
+
+#[test]
+fn history_revert_is_one_edit_per_change() {
+    let text = TEXT.repeat(100);
+
+    let mut r = Rope::from(&*text);
+
+    let edits = Arc::new(Mutex::new(Vec::new()));
+    let cloned = Arc::clone(&edits);
+    r.subscribe(move |edit| cloned.lock().unwrap().push(edit.clone()));
+
+    let mut h = History::new(r);
+
+    let saved = h.checkpoint("saved");
+
+    let half = text.len() / 2;
+
+    h.delete(10..half);
+    h.insert(0, "foo");
+    let edited = h.rope().clone();
+    edits.lock().unwrap().clear();
+
+    h.revert_to(&saved);
+    assert_eq!(*h, text);
+    h.assert_invariants();
+
+    assert_eq!(
+        *edits.lock().unwrap(),
+        [
+            EditInfo { byte_range: 13..13, inserted_len: half - 10 },
+            EditInfo { byte_range: 0..3, inserted_len: 0 },
+        ]
+    );
+
+    assert!(h.undo());
+    assert_eq!(*h, edited);
+}
+
+#[test]
+fn history_diff_since_random() {
+    let mut rng = thread_rng();
+
+    for _ in 0..50 {
+        let mut h = History::new(Rope::from(TEXT));
+
+        for _ in 0..rng.gen_range(0..5) {
+            random_edit(&mut h, &mut rng);
+        }
+
+        let saved = h.checkpoint("");
+
+        for _ in 0..rng.gen_range(0..20) {
+            if rng.gen_bool(0.2) {
+                h.undo();
+            } else {
+                random_edit(&mut h, &mut rng);
+            }
+        }
+
+        let changes = h.diff_since(&saved);
+
+        // Applying the changes to the checkpoint's document must produce the
+        // current document.
+        let mut rebuilt = saved.rope().clone();
+        for change in changes.iter().rev() {
+            let new = h.byte_slice(change.new.clone()).to_string();
+            rebuilt.replace(change.old.clone(), new);
+        }
+        assert_eq!(rebuilt, *h);
+
+        h.revert_to(&saved);
+        assert_eq!(*h, *saved.rope());
+        h.assert_invariants();
+    }
+}
+
+fn random_edit(h: &mut History, rng: &mut impl Rng) {
+    let mut start = rng.gen_range(0..=h.byte_len());
+    while !h.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = rng.gen_range(start..=h.byte_len().min(start + 10));
+    while !h.is_char_boundary(end) {
+        end -= 1;
+    }
+    let text = ["", "a", "🐸", "foo\n", "\r\n"][rng.gen_range(0..5)];
+    h.replace(start..end, text);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{CURSED_LIPSUM, MEDIUM, SMALL, TINY};
+use crop::Rope;
+use rand::Rng;
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn incremental_edit_random() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", TINY, SMALL, MEDIUM, CURSED_LIPSUM] {
+        let mut r = Rope::from(s);
+        let mut string = s.to_owned();
+
+        for _ in 0..10 {
+            let mut start = rng.gen_range(0..=string.len());
+            while !string.is_char_boundary(start) {
+                start -= 1;
+            }
+
+            let mut end = rng.gen_range(start..=string.len());
+            while !string.is_char_boundary(end) {
+                end += 1;
+            }
+
+            let text = [TINY, SMALL, MEDIUM, CURSED_LIPSUM, ""]
+                [rng.gen_range(0..5)];
+
+            let revision = r.revision();
+
+            let mut edit = r.begin_edit(start..end, text);
+
+            let max_bytes = rng.gen_range(256..4096);
+
+            while !edit.step(max_bytes) {
+                assert_eq!(edit.rope().revision(), revision);
+            }
+
+            assert_eq!(edit.remaining(), 0);
+
+            drop(edit);
+
+            string.replace_range(start..end, text);
+            assert_eq!(r, string);
+            r.assert_invariants();
+        }
+    }
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn incremental_edit_drop_finishes() {
+    let mut r = Rope::from(SMALL);
+    let mut string = SMALL.to_owned();
+
+    {
+        let mut edit = r.begin_edit(10..100, MEDIUM);
+        edit.step(1000);
+        assert!(!edit.is_done());
+        assert!(edit.remaining() < MEDIUM.len());
+    }
+
+    string.replace_range(10..100, MEDIUM);
+    assert_eq!(r, string);
+    r.assert_invariants();
+
+    r.begin_edit(..r.byte_len(), "").finish();
+    assert!(r.is_empty());
+}
+
+#[test]
+#[should_panic]
+fn incremental_edit_out_of_bounds() {
+    let mut r = Rope::from("Hello");
+    let _ = r.begin_edit(2..6, "");
+}
+
+#[test]
+#[should_panic]
+fn incremental_edit_not_char_boundary() {
+    let mut r = Rope::from("bär");
+    let _ = r.begin_edit(2..3, "");
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, TINY};
+use crop::{ChunkInterner, Rope};
+use rand::Rng;
+
+#[test]
+fn intern_repeated_chunks() {
+    let text = "abcd".repeat(100_000);
+
+    let mut interner = ChunkInterner::new();
+
+    let mut r = Rope::from(&*text);
+    r.intern_chunks(&mut interner);
+    r.assert_invariants();
+
+    // All the chunks but the last one store the same text.
+    assert!(interner.len() <= 2);
+    assert_eq!(r, text);
+
+    // Interning again doesn't change anything.
+    r.intern_chunks(&mut interner);
+    assert!(interner.len() <= 2);
+    assert_eq!(r, text);
+}
+
+#[test]
+fn intern_edits() {
+    let mut rng = rand::thread_rng();
+
+    let mut interner = ChunkInterner::new();
+
+    for s in ["", TINY, LARGE, CURSED_LIPSUM] {
+        let original = Rope::from(s);
+
+        let mut r = Rope::from(s);
+        let mut string = s.to_owned();
+
+        for _ in 0..20 {
+            r.intern_chunks(&mut interner);
+
+            let start = rng.gen_range(0..=r.byte_len());
+            let end = rng.gen_range(start..=r.byte_len());
+
+            if r.is_char_boundary(start) && r.is_char_boundary(end) {
+                r.replace(start..end, "bar\r\n🦀");
+                string.replace_range(start..end, "bar\r\n🦀");
+            }
+
+            r.assert_invariants();
+            assert_eq!(r, string);
+            assert_eq!(r.line_len(), Rope::from(&*string).line_len());
+        }
+
+        assert_eq!(original, s);
+    }
+}
+
+#[test]
+fn intern_purge() {
+    // Every chunk of this text is different.
+    let text = (0..20_000).map(|i| format!("{i}\n")).collect::<String>();
+
+    let mut interner = ChunkInterner::new();
+
+    let mut a = Rope::from(&*text);
+    a.intern_chunks(&mut interner);
+    let num_chunks = interner.len();
+    assert!(num_chunks > 0);
+
+    let mut b = Rope::from(&*text);
+    b.intern_chunks(&mut interner);
+    assert_eq!(interner.len(), num_chunks);
+
+    // Editing `a` copies its first chunk, which is still used by `b`.
+    a.insert(0, "foo");
+    interner.purge();
+    assert_eq!(interner.len(), num_chunks);
+
+    drop(b);
+    interner.purge();
+    assert_eq!(interner.len(), num_chunks - 1);
+
+    drop(a);
+    interner.purge();
+    assert!(interner.is_empty());
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn intern_then_compress() {
+    let mut interner = ChunkInterner::new();
+
+    let mut r = Rope::from(LARGE);
+    r.intern_chunks(&mut interner);
+    r.compress();
+    r.intern_chunks(&mut interner);
+    r.assert_invariants();
+    assert_eq!(r, LARGE);
+
+    r.insert(0, "foo\n");
+    assert_eq!(r.line(0), "foo");
+}
@@ -0,0 +1,216 @@
+use std::ops::Range;
+
+use crop::interval_tree::{IntervalId, IntervalTree};
+use crop::{EditInfo, Rope};
+use rand::{thread_rng, Rng};
+
+/// Moves an offset the same way the `IntervalTree` moves the ends of its
+/// intervals.
+fn move_end(offset: usize, edit: &EditInfo) -> usize {
+    let Range { start, end } = edit.byte_range.clone();
+
+    if offset < start {
+        offset
+    } else if offset <= end {
+        start + edit.inserted_len
+    } else {
+        offset - (end - start) + edit.inserted_len
+    }
+}
+
+/// Moves an offset the same way the `IntervalTree` moves the starts of its
+/// intervals.
+fn move_start(offset: usize, edit: &EditInfo) -> usize {
+    let Range { start, end } = edit.byte_range.clone();
+
+    if start < end && (start..=end).contains(&offset) {
+        start
+    } else {
+        move_end(offset, edit)
+    }
+}
+
+fn sorted(
+    mut items: Vec<(IntervalId, Range<usize>, usize)>,
+) -> Vec<(IntervalId, Range<usize>, usize)> {
+    items.sort_by_key(|(id, range, _)| (range.start, *id));
+    items
+}
+
+#[test]
+fn interval_tree_empty() {
+    let tree = IntervalTree::<()>::new();
+    assert!(tree.is_empty());
+    assert_eq!(tree.containing(0).count(), 0);
+    assert_eq!(tree.overlapping(..).count(), 0);
+}
+
+#[test]
+fn interval_tree_insert_and_query() {
+    let mut tree = IntervalTree::new();
+
+    let a = tree.insert(0..10, 'a');
+    let b = tree.insert(5..15, 'b');
+    let c = tree.insert(20..25, 'c');
+    let d = tree.insert(7..7, 'd');
+
+    assert_eq!(tree.len(), 4);
+
+    assert_eq!(
+        tree.containing(7).collect::<Vec<_>>(),
+        [(a, 0..10, &'a'), (b, 5..15, &'b')]
+    );
+
+    assert_eq!(
+        tree.overlapping(14..21).collect::<Vec<_>>(),
+        [(b, 5..15, &'b'), (c, 20..25, &'c')]
+    );
+
+    assert_eq!(tree.overlapping(15..20).count(), 0);
+
+    assert_eq!(
+        tree.iter().map(|(id, _, _)| id).collect::<Vec<_>>(),
+        [a, b, d, c]
+    );
+
+    assert_eq!(tree.remove(b), Some((5..15, 'b')));
+    assert_eq!(tree.remove(b), None);
+    assert_eq!(tree.len(), 3);
+    assert_eq!(tree.containing(12).count(), 0);
+}
+
+#[test]
+fn interval_tree_edits() {
+    let mut tree = IntervalTree::new();
+
+    let id = tree.insert(5..10, ());
+
+    // Inserting at the start moves the interval.
+    tree.edit(&EditInfo { byte_range: 5..5, inserted_len: 2 });
+    assert_eq!(tree.iter().next().unwrap().1, 7..12);
+
+    // Inserting at the end extends it.
+    tree.edit(&EditInfo { byte_range: 12..12, inserted_len: 3 });
+    assert_eq!(tree.iter().next().unwrap().1, 7..15);
+
+    // Deleting across the start shrinks it.
+    tree.edit(&EditInfo { byte_range: 5..9, inserted_len: 0 });
+    assert_eq!(tree.iter().next().unwrap().1, 5..11);
+
+    // Replacing its text keeps the new text in it.
+    tree.edit(&EditInfo { byte_range: 5..11, inserted_len: 2 });
+    assert_eq!(tree.iter().next().unwrap().1, 5..7);
+
+    // Deleting everything in it leaves it empty.
+    tree.edit(&EditInfo { byte_range: 0..20, inserted_len: 0 });
+    assert_eq!(tree.iter().next().unwrap(), (id, 0..0, &()));
+}
+
+#[test]
+fn interval_tree_follows_rope() {
+    use std::sync::{Arc, Mutex};
+
+    let mut r = Rope::from("fn foo() {}\nfn bar() {}\n");
+
+    let tree = Arc::new(Mutex::new(IntervalTree::new()));
+    let bar = tree.lock().unwrap().insert(15..18, "function name");
+
+    r.subscribe({
+        let tree = Arc::clone(&tree);
+        move |edit| tree.lock().unwrap().edit(edit)
+    });
+
+    r.insert(0, "// Functions.\n");
+    r.replace(17..20, "qux_baz");
+
+    let tree = tree.lock().unwrap();
+    let (id, range, _) = tree.containing(34).next().unwrap();
+    assert_eq!(id, bar);
+    assert_eq!(r.byte_slice(range), "bar");
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn interval_tree_random() {
+    let mut rng = thread_rng();
+
+    let mut tree = IntervalTree::new();
+    let mut model = Vec::<(IntervalId, Range<usize>, usize)>::new();
+    let mut len = 1000;
+
+    for i in 0..2000 {
+        match rng.gen_range(0..10) {
+            0..=3 => {
+                let start = rng.gen_range(0..=len);
+                let end = rng.gen_range(start..=len.min(start + 100));
+                let id = tree.insert(start..end, i);
+                model.push((id, start..end, i));
+            },
+
+            4 if !model.is_empty() => {
+                let idx = rng.gen_range(0..model.len());
+                let (id, range, value) = model.swap_remove(idx);
+                assert_eq!(tree.remove(id), Some((range, value)));
+            },
+
+            _ => {
+                let start = rng.gen_range(0..=len);
+                let end = rng.gen_range(start..=len.min(start + 50));
+                let edit = EditInfo {
+                    byte_range: start..end,
+                    inserted_len: rng.gen_range(0..50),
+                };
+                tree.edit(&edit);
+                len = len - (end - start) + edit.inserted_len;
+
+                for (_, range, _) in &mut model {
+                    *range = move_start(range.start, &edit)
+                        ..move_end(range.end, &edit);
+                }
+            },
+        }
+
+        tree.assert_invariants();
+        assert_eq!(tree.len(), model.len());
+
+        let offset = rng.gen_range(0..=len);
+
+        let expected = sorted(
+            model
+                .iter()
+                .filter(|(_, range, _)| range.contains(&offset))
+                .cloned()
+                .collect(),
+        );
+
+        let actual = sorted(
+            tree.containing(offset).map(|(id, r, &v)| (id, r, v)).collect(),
+        );
+
+        assert_eq!(expected, actual);
+
+        let start = rng.gen_range(0..=len);
+        let end = rng.gen_range(start..=len);
+
+        let expected = sorted(
+            model
+                .iter()
+                .filter(|(_, range, _)| range.start < end && start < range.end)
+                .cloned()
+                .collect(),
+        );
+
+        let actual = sorted(
+            tree.overlapping(start..end)
+                .map(|(id, r, &v)| (id, r, v))
+                .collect(),
+        );
+
+        assert_eq!(expected, actual);
+    }
+
+    assert_eq!(
+        sorted(model),
+        sorted(tree.iter().map(|(id, r, &v)| (id, r, v)).collect())
+    );
+}
@@ -1,4 +1,4 @@
-use crop::Rope;
+use crop::{Rope, TrailingLineBreak};
 use rand::{thread_rng, Rng};
 
 mod common;
@@ -271,6 +271,41 @@ fn iter_chars_cursed() {
     }
 }
 
+#[test]
+fn iter_encode_utf16_empty() {
+    let r = Rope::new();
+    assert_eq!(0, r.encode_utf16().count());
+    assert_eq!(0, r.byte_slice(..).encode_utf16().count());
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn iter_encode_utf16_over_random_slices() {
+    let mut rng = thread_rng();
+
+    for s in [TINY, SMALL, MEDIUM, CURSED_LIPSUM] {
+        let r = Rope::from(s);
+
+        assert!(r.encode_utf16().eq(s.encode_utf16()));
+
+        for _ in 0..10 {
+            let mut start = rng.gen_range(0..=r.byte_len());
+            while !s.is_char_boundary(start) {
+                start -= 1;
+            }
+
+            let mut end = rng.gen_range(start..=r.byte_len());
+            while !s.is_char_boundary(end) {
+                end += 1;
+            }
+
+            let slice = r.byte_slice(start..end);
+
+            assert!(slice.encode_utf16().eq(s[start..end].encode_utf16()));
+        }
+    }
+}
+
 #[test]
 fn iter_lines_empty() {
     let r = Rope::new();
@@ -612,3 +647,195 @@ fn iter_raw_lines_over_random_slices() {
         }
     }
 }
+
+/// Tests that the lines yielded by iterating over a `RopeSlice` can be
+/// sliced further, which requires their offset into the underlying leaves to
+/// be correct even if the slice starts in the middle of a leaf.
+#[cfg_attr(miri, ignore)]
+#[test]
+fn iter_lines_then_slice() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["d\nc\r\nb\na\n", "a\n\nb\n\n\nc", TINY, SMALL, MEDIUM] {
+        let rope = Rope::from(s);
+
+        for _ in 0..100 {
+            let mut start = rng.gen_range(0..=s.len());
+            while !s.is_char_boundary(start) {
+                start -= 1;
+            }
+
+            let mut end = rng.gen_range(start..=s.len());
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let rope_slice = rope.byte_slice(start..end);
+            let str_lines = s[start..end].split_inclusive('\n');
+
+            let forward = rope_slice.raw_lines().collect::<Vec<_>>();
+            let mut backward =
+                rope_slice.raw_lines().rev().collect::<Vec<_>>();
+            backward.reverse();
+
+            assert_eq!(forward.len(), str_lines.clone().count());
+            assert_eq!(backward.len(), str_lines.clone().count());
+
+            for ((fw, bw), str_line) in
+                forward.iter().zip(&backward).zip(str_lines)
+            {
+                let Some(first) = str_line.chars().next() else { continue };
+                let last = str_line.chars().next_back().unwrap();
+                let len = str_line.len();
+
+                for line in [fw, bw] {
+                    assert_eq!(*line, str_line);
+                    assert_eq!(
+                        line.byte_slice(..first.len_utf8()),
+                        first.to_string()
+                    );
+                    assert_eq!(
+                        line.byte_slice(len - last.len_utf8()..),
+                        last.to_string()
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn iter_lines_with_trailing_line_break() {
+    let mut rng = thread_rng();
+
+    for s in
+        ["", "\n", "a", "a\r\n", "a\nb", TINY, SMALL, MEDIUM, CURSED_LIPSUM]
+    {
+        let r = Rope::from(s);
+
+        for _ in 0..10 {
+            let start = rng.gen_range(0..=r.byte_len());
+            let end = rng.gen_range(start..=r.byte_len());
+
+            if !r.is_char_boundary(start) || !r.is_char_boundary(end) {
+                continue;
+            }
+
+            let slice = r.byte_slice(start..end);
+            let str_slice = &s[start..end];
+
+            let mut expected = str_slice.split('\n').collect::<Vec<_>>();
+
+            // Only strip the carriage returns of CRLF line breaks.
+            let last = expected.len() - 1;
+            for line in &mut expected[..last] {
+                *line = line.strip_suffix('\r').unwrap_or(line);
+            }
+
+            let lines = slice.lines_with(TrailingLineBreak::StartsLine);
+            assert_eq!(lines.len(), expected.len());
+            assert_eq!(lines.collect::<Vec<_>>(), expected);
+
+            let lines = slice.lines_with(TrailingLineBreak::StartsLine).rev();
+            assert_eq!(
+                lines.collect::<Vec<_>>(),
+                expected.iter().rev().copied().collect::<Vec<_>>()
+            );
+
+            assert_eq!(
+                slice.line_len_with(TrailingLineBreak::StartsLine),
+                expected.len()
+            );
+
+            assert_eq!(
+                slice.lines_with(TrailingLineBreak::EndsLine).count(),
+                slice.line_len_with(TrailingLineBreak::EndsLine)
+            );
+        }
+
+        let expected = s.split('\n').count();
+        assert_eq!(r.line_len_with(TrailingLineBreak::StartsLine), expected);
+        assert_eq!(
+            r.lines_with(TrailingLineBreak::StartsLine).count(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn iter_rolling_hash_short() {
+    let r = Rope::from("abc");
+
+    assert_eq!(r.rolling_hash(4).count(), 0);
+    assert_eq!(r.rolling_hash(3).count(), 1);
+    assert_eq!(r.rolling_hash(1).count(), 3);
+    assert_eq!(Rope::new().rolling_hash(1).count(), 0);
+}
+
+#[test]
+#[should_panic]
+fn iter_rolling_hash_zero_window() {
+    let r = Rope::from("abc");
+    let _ = r.rolling_hash(0);
+}
+
+#[test]
+fn iter_rolling_hash_matches_windows() {
+    let mut rng = thread_rng();
+
+    for s in [TINY, SMALL, MEDIUM, CURSED_LIPSUM] {
+        let r = Rope::from(s);
+
+        for _ in 0..5 {
+            let start = rng.gen_range(0..=s.len());
+            let end = rng.gen_range(start..=s.len());
+            let window_size = rng.gen_range(1..=64);
+
+            if !(s.is_char_boundary(start) && s.is_char_boundary(end)) {
+                continue;
+            }
+
+            let slice = r.byte_slice(start..end);
+
+            let hashes = slice.rolling_hash(window_size);
+
+            assert_eq!(
+                hashes.len(),
+                (slice.byte_len() + 1).saturating_sub(window_size)
+            );
+
+            // Hashing each window from scratch gives the same result.
+            for (offset, hash) in hashes.enumerate() {
+                let end = offset + window_size;
+
+                if slice.is_char_boundary(offset)
+                    && slice.is_char_boundary(end)
+                {
+                    let window = slice.byte_slice(offset..end);
+                    assert_eq!(
+                        window.rolling_hash(window_size).next(),
+                        Some(hash)
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn iter_rolling_hash_equal_windows() {
+    let r = Rope::from(LARGE);
+
+    let window_size = 32;
+
+    let mut seen = std::collections::HashMap::new();
+
+    for (offset, hash) in r.rolling_hash(window_size).enumerate().take(20_000)
+    {
+        let window = &LARGE.as_bytes()[offset..offset + window_size];
+
+        if let Some(other) = seen.insert(hash, window) {
+            assert_eq!(other, window);
+        }
+    }
+}
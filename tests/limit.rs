@@ -0,0 +1,212 @@
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, SMALL, TINY};
+use crop::{EditInfo, Limit, Rope};
+use rand::Rng;
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn limit_bytes_random_appends() {
+    let mut rng = rand::thread_rng();
+
+    for max_bytes in [0, 1, 100, 10_000, 1 << 20] {
+        let mut r = Rope::new();
+        r.set_limit(Some(Limit::Bytes(max_bytes)));
+
+        let mut string = String::new();
+
+        for _ in 0..100 {
+            let text = [TINY, SMALL, CURSED_LIPSUM, "🐕‍🦺"][rng.gen_range(0..4)];
+
+            r.push_str(text);
+            string.push_str(text);
+
+            if string.len() > max_bytes {
+                let mut excess = string.len() - max_bytes;
+                while !string.is_char_boundary(excess) {
+                    excess += 1;
+                }
+                string.drain(..excess);
+            }
+
+            assert!(r.byte_len() <= max_bytes);
+            assert_eq!(r, string);
+        }
+
+        r.assert_invariants();
+    }
+}
+
+#[cfg(feature = "line-metric")]
+#[cfg_attr(miri, ignore)]
+#[test]
+fn limit_lines_random_appends() {
+    let mut rng = rand::thread_rng();
+
+    for max_lines in [0, 1, 10, 1000] {
+        let mut r = Rope::new();
+        r.set_limit(Some(Limit::Lines(max_lines)));
+
+        let mut lines = std::collections::VecDeque::new();
+
+        for _ in 0..200 {
+            let line = &SMALL[..rng.gen_range(0..80)];
+
+            r.push_str(line);
+            r.push_char('\n');
+
+            lines.push_back(line);
+
+            if lines.len() > max_lines {
+                lines.pop_front();
+            }
+
+            assert_eq!(r.line_len(), lines.len());
+
+            for (idx, line) in lines.iter().enumerate() {
+                assert_eq!(r.line(idx), *line);
+            }
+        }
+
+        r.assert_invariants();
+    }
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn limit_set_on_long_rope() {
+    let mut r = Rope::from(LARGE);
+
+    r.set_limit(Some(Limit::Bytes(1000)));
+    assert_eq!(r, &LARGE[LARGE.len() - 1000..]);
+    r.assert_invariants();
+
+    // Edits that don't make the `Rope` longer don't delete anything.
+    r.delete(..10);
+    r.replace(..10, "foo");
+    assert_eq!(r.byte_len(), 983);
+
+    r.set_limit(None);
+    r.push_str(TINY);
+    assert_eq!(r.byte_len(), 983 + TINY.len());
+    assert_eq!(r.limit(), None);
+
+    // The limit is inherited by clones.
+    r.set_limit(Some(Limit::Bytes(100)));
+    let mut clone = r.clone();
+    clone.push_str(TINY);
+    assert_eq!(clone.byte_len(), 100);
+}
+
+#[test]
+fn limit_marks_and_subscribers() {
+    let mut r = Rope::from("foo\nbar\n");
+
+    let bar = r.add_mark(4, crop::marks::Gravity::Left);
+
+    let edits = Arc::new(Mutex::new(Vec::new()));
+
+    r.subscribe({
+        let edits = Arc::clone(&edits);
+        move |edit| edits.lock().unwrap().push(edit.clone())
+    });
+
+    r.set_limit(Some(Limit::Bytes(8)));
+    r.push_str("baz\n");
+
+    assert_eq!(r, "bar\nbaz\n");
+    assert_eq!(r.marks().get(bar), Some(0));
+
+    let edits = edits.lock().unwrap();
+    assert_eq!(edits.len(), 2);
+    assert_eq!(edits[0].byte_range, 8..8);
+    assert_eq!(edits[1].byte_range, 0..4);
+    assert_eq!(edits[1].inserted_len, 0);
+}
+
+#[test]
+fn limit_edits_not_growing_the_rope() {
+    let mut r = Rope::from("a\nb\nc");
+    r.set_limit(Some(Limit::Lines(3)));
+
+    r.replace(0..1, "\n");
+    assert_eq!(r, "\nb\nc");
+    assert_eq!(r.line_len(), 3);
+
+    let mut r = Rope::from("ab\nc\nd");
+    r.set_limit(Some(Limit::Lines(3)));
+
+    r.overwrite(1, "\n");
+    assert_eq!(r, "\nc\nd");
+    assert_eq!(r.line_len(), 3);
+}
+
+#[test]
+fn limit_apply_edits() {
+    let mut r = Rope::from("aaaa\nbbbb\n");
+    r.set_limit(Some(Limit::Bytes(10)));
+
+    let delta = r.apply_edits(&[(0..0, "x"), (5..5, "y")]);
+
+    assert_eq!(r, "aaa\nybbbb\n");
+    assert_eq!(delta.len(), 3);
+    assert_eq!(delta.iter().last().unwrap().byte_range, 0..2);
+}
+
+#[test]
+fn limit_edits_with_info() {
+    let mut r = Rope::from("a\nb\nc\n");
+    r.set_limit(Some(Limit::Bytes(6)));
+
+    let region = r.insert_with_info(6, "xx\nyy\n");
+    assert_eq!(r, "xx\nyy\n");
+    assert_eq!(region.edit, EditInfo { byte_range: 6..6, inserted_len: 6 });
+    assert_eq!(region.old_lines, 3..4);
+    assert_eq!(region.new_lines, 0..3);
+
+    // The limit is shorter than the inserted text.
+    let region = r.replace_with_info(1..3, "foo\nbar\n");
+    assert_eq!(r, "ar\nyy\n");
+    assert_eq!(region.old_lines, 0..2);
+    assert_eq!(region.new_lines, 0..2);
+}
+
+#[test]
+fn limit_edit_session() {
+    let mut r = Rope::from("0123456789");
+    r.set_limit(Some(Limit::Bytes(10)));
+
+    let mut session = r.edit_session(10);
+    session.insert("abc");
+    assert_eq!(session.rope(), "3456789abc");
+    assert_eq!(session.offset(), 10);
+
+    session.insert("d");
+    drop(session);
+
+    assert_eq!(r, "456789abcd");
+}
+
+#[test]
+#[should_panic(expected = "can't wrap a Rope with a limit")]
+fn limit_history() {
+    let mut r = Rope::from("foo");
+    r.set_limit(Some(Limit::Bytes(10)));
+    let _ = crop::history::History::new(r);
+}
+
+#[test]
+fn limit_transaction() {
+    use crop::transaction::{Transaction, TransactionError};
+
+    let mut r = Rope::from("foo");
+    r.set_limit(Some(Limit::Bytes(10)));
+
+    let mut t = Transaction::new(&r);
+    t.retain(3).insert("bar");
+
+    assert_eq!(t.apply(&mut r), Err(TransactionError::RopeHasLimit));
+    assert_eq!(r, "foo");
+}
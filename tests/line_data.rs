@@ -0,0 +1,118 @@
+use crop::line_data::LineDataRope;
+use crop::Rope;
+use rand::{thread_rng, Rng};
+
+mod common;
+
+use common::{random_char_boundary, MEDIUM};
+
+#[test]
+fn line_data_follows_lines() {
+    let mut r = LineDataRope::from(Rope::from("a\nb\nc\nd\n"));
+
+    r.set_line_data(0, 'a');
+    r.set_line_data(2, 'c');
+    r.set_line_data(3, 'd');
+
+    // Inserting lines at the start of a line moves its data down.
+    r.insert(r.byte_of_line(2), "x\ny\n");
+    assert_eq!(
+        r.line_data().iter().collect::<Vec<_>>(),
+        [(0, &'a'), (4, &'c'), (5, &'d')]
+    );
+
+    // Inserting lines within a line keeps its data on the first one.
+    r.insert(1, "\nz");
+    assert_eq!(
+        r.line_data().iter().collect::<Vec<_>>(),
+        [(0, &'a'), (5, &'c'), (6, &'d')]
+    );
+
+    // Joining two lines keeps the data of the first one.
+    r.delete(r.byte_of_line(6) - 1..r.byte_of_line(6));
+    assert_eq!(*r, "a\nz\nb\nx\ny\ncd\n");
+    assert_eq!(
+        r.line_data().iter().collect::<Vec<_>>(),
+        [(0, &'a'), (5, &'c')]
+    );
+}
+
+#[test]
+fn line_data_set_and_take() {
+    let mut r = LineDataRope::from(Rope::from("foo\nbar"));
+
+    assert_eq!(r.set_line_data(1, 1), None);
+    assert_eq!(r.set_line_data(1, 2), Some(1));
+    assert_eq!(r.line_data().len(), 1);
+
+    assert_eq!(r.take_line_data(1), Some(2));
+    assert_eq!(r.take_line_data(1), None);
+    assert!(r.line_data().is_empty());
+}
+
+#[test]
+#[should_panic]
+fn line_data_set_out_of_bounds() {
+    let mut r = LineDataRope::from(Rope::from("foo\n"));
+    r.set_line_data(1, ());
+}
+
+/// Checks the line data against a `Vec` spliced with the same rules.
+#[test]
+fn line_data_random() {
+    let mut rng = thread_rng();
+
+    let mut r = LineDataRope::from(Rope::from(MEDIUM));
+    let mut model = vec![None; r.line_of_byte(r.byte_len()) + 1];
+
+    for i in 0..300 {
+        if rng.gen_bool(0.3) && r.line_len() > 0 {
+            let line = rng.gen_range(0..r.line_len());
+            r.set_line_data(line, i);
+            model[line] = Some(i);
+        } else {
+            let start = random_char_boundary(&mut rng, &r.to_string());
+            let end = (start + rng.gen_range(0..40)).min(r.byte_len());
+            let end = (end..=r.byte_len())
+                .find(|&end| r.is_char_boundary(end))
+                .unwrap();
+
+            let text =
+                ["", "a", "\n", "ab\ncd", "\n\n\n"][rng.gen_range(0..5)];
+
+            let start_line = r.line_of_byte(start);
+            let end_line = r.line_of_byte(end);
+            let inserted = text.matches('\n').count();
+
+            if start == end && start == r.byte_of_line(start_line) {
+                model.splice(
+                    start_line..start_line,
+                    std::iter::repeat(None).take(inserted),
+                );
+            } else {
+                model.splice(
+                    start_line + 1..end_line + 1,
+                    std::iter::repeat(None).take(inserted),
+                );
+            }
+
+            r.replace(start..end, text);
+        }
+
+        r.line_data().assert_invariants();
+
+        assert_eq!(model.len(), r.line_of_byte(r.byte_len()) + 1);
+
+        let expected = model
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, data)| data.as_ref().map(|data| (idx, data)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(r.line_data().iter().collect::<Vec<_>>(), expected);
+
+        for (idx, data) in model.iter().enumerate() {
+            assert_eq!(r.line_data().get(idx), data.as_ref());
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crop::tree::LocalCount;
+use crop::{Rope, RopeBuilder};
+use rand::Rng;
+
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, MEDIUM, SMALL, TINY};
+
+#[test]
+fn local_rope_clone_and_edit() {
+    let mut r = Rope::<LocalCount>::from(&Rope::from(LARGE));
+    let snapshot = r.clone();
+
+    r.insert(0, "foo\n");
+    r.delete(r.byte_len() - 10..);
+    r.assert_invariants();
+
+    assert_eq!(snapshot, LARGE);
+    assert_eq!(r.byte_of_line(1), 4);
+
+    drop(snapshot);
+    r.replace(..4, "");
+    r.assert_invariants();
+    assert_eq!(r, &LARGE[..LARGE.len() - 10]);
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn local_rope_random_edits() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", TINY, SMALL, MEDIUM, CURSED_LIPSUM] {
+        let mut r = Rope::<LocalCount>::from_text(s);
+        let mut s = s.to_owned();
+
+        for _ in 0..100 {
+            let mut start = rng.gen_range(0..=s.len());
+            while !s.is_char_boundary(start) {
+                start -= 1;
+            }
+
+            let mut end = rng.gen_range(start..=(start + 30).min(s.len()));
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let text = ["", "a", "\n", "ä\nb", "foo bar baz\r\n"]
+                [rng.gen_range(0..5)];
+
+            r.replace(start..end, text);
+            s.replace_range(start..end, text);
+
+            r.assert_invariants();
+            assert_eq!(r.byte_len(), s.len());
+            assert_eq!(r.line_len(), Rope::from(s.as_str()).line_len());
+
+            if !s.is_empty() {
+                let mut idx = rng.gen_range(0..s.len());
+                assert_eq!(r.byte(idx), s.as_bytes()[idx]);
+
+                while !s.is_char_boundary(idx) {
+                    idx -= 1;
+                }
+
+                assert_eq!(
+                    r.line_of_byte(idx),
+                    s[..idx].matches('\n').count()
+                );
+            }
+        }
+
+        assert_eq!(r, s);
+    }
+}
+
+#[test]
+fn local_rope_slices_and_iterators() {
+    let r = Rope::from(MEDIUM);
+    let local = Rope::<LocalCount>::from(&r);
+
+    let slice = local.byte_slice(10..local.byte_len() - 10);
+    assert_eq!(slice, r.byte_slice(10..r.byte_len() - 10));
+    assert_eq!(Rope::from(&Rope::<LocalCount>::from(slice)), slice);
+
+    assert!(local.chunks().eq(r.chunks()));
+    assert!(local.lines().eq(r.lines()));
+    assert!(local.chars().rev().eq(r.chars().rev()));
+}
+
+#[test]
+fn local_rope_to_and_from_rope() {
+    for s in ["", "\n", TINY, SMALL, MEDIUM] {
+        let rope = Rope::from(s);
+
+        let local = Rope::<LocalCount>::from(&rope);
+        local.assert_invariants();
+        assert_eq!(local, rope);
+
+        let rope = Rope::from(&local);
+        rope.assert_invariants();
+        assert_eq!(rope, s);
+        assert_eq!(rope.line_len(), local.line_len());
+    }
+}
+
+#[test]
+fn local_rope_constructors() {
+    let mut r = Rope::<LocalCount>::empty();
+    assert!(r.is_empty());
+
+    r.insert(0, TINY);
+    assert_eq!(r, Rope::<LocalCount>::from_text(TINY));
+
+    let mut builder = RopeBuilder::<LocalCount>::empty();
+    builder.append(TINY);
+    assert_eq!(builder.build(), r);
+
+    // The default constructors still infer the default `Rope`.
+    let r = Rope::default();
+    assert!(r.is_empty());
+
+    let mut builder = RopeBuilder::default();
+    builder.append(TINY);
+    assert_eq!(builder.build(), Rope::from(TINY));
+}
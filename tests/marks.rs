@@ -0,0 +1,126 @@
+use crop::marks::Gravity;
+use crop::Rope;
+use rand::{thread_rng, Rng};
+
+mod common;
+
+use common::MEDIUM;
+
+#[test]
+fn marks_gravity_on_insertion() {
+    let mut r = Rope::from("foo bar");
+
+    let left = r.add_mark(3, Gravity::Left);
+    let right = r.add_mark(3, Gravity::Right);
+    let before = r.add_mark(1, Gravity::Right);
+    let after = r.add_mark(5, Gravity::Left);
+
+    r.insert(3, "baz");
+
+    assert_eq!(r.marks().get(left), Some(3));
+    assert_eq!(r.marks().get(right), Some(6));
+    assert_eq!(r.marks().get(before), Some(1));
+    assert_eq!(r.marks().get(after), Some(8));
+}
+
+#[test]
+fn marks_inside_replaced_range() {
+    let mut r = Rope::from("Hello World");
+
+    let left = r.add_mark(8, Gravity::Left);
+    let right = r.add_mark(8, Gravity::Right);
+    let end = r.add_mark(11, Gravity::Left);
+
+    r.replace(6..11, "Earth!!");
+
+    assert_eq!(r.marks().get(left), Some(6));
+    assert_eq!(r.marks().get(right), Some(13));
+    assert_eq!(r.marks().get(end), Some(6));
+
+    r.delete(..);
+
+    assert_eq!(r.marks().get(left), Some(0));
+    assert_eq!(r.marks().get(right), Some(0));
+}
+
+#[test]
+fn marks_remove() {
+    let mut r = Rope::from("foo");
+
+    assert!(r.marks().is_empty());
+
+    let a = r.add_mark(0, Gravity::Left);
+    let b = r.add_mark(3, Gravity::Right);
+
+    assert_eq!(r.marks().len(), 2);
+    assert_eq!(r.marks().gravity(b), Some(Gravity::Right));
+    assert_eq!(r.remove_mark(a), Some(0));
+    assert_eq!(r.remove_mark(a), None);
+    assert_eq!(r.marks().get(a), None);
+    assert_eq!(r.marks().iter().collect::<Vec<_>>(), [(b, 3)]);
+}
+
+#[test]
+fn marks_cloned_with_rope() {
+    let mut r = Rope::from("foo");
+    let mark = r.add_mark(1, Gravity::Left);
+
+    let mut clone = r.clone();
+    clone.insert(0, "bar");
+
+    assert_eq!(r.marks().get(mark), Some(1));
+    assert_eq!(clone.marks().get(mark), Some(4));
+}
+
+#[test]
+#[should_panic]
+fn marks_out_of_bounds() {
+    let mut r = Rope::from("foo");
+    r.add_mark(4, Gravity::Left);
+}
+
+/// Checks that marks placed at the start of random words keep pointing at the
+/// same words after random edits that don't touch them.
+#[cfg_attr(miri, ignore)]
+#[test]
+fn marks_random_edits() {
+    let mut rng = thread_rng();
+
+    let mut r = Rope::from(MEDIUM);
+
+    let marks = (0..20)
+        .map(|_| {
+            let offset = rng.gen_range(0..r.byte_len());
+            let offset =
+                (0..=offset).rev().find(|&o| r.is_char_boundary(o)).unwrap();
+            (
+                r.add_mark(offset, Gravity::Right),
+                r.byte_slice(offset..).chars().next(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for _ in 0..100 {
+        let start = rng.gen_range(0..=r.byte_len());
+        let end = rng.gen_range(start..=r.byte_len().min(start + 10));
+
+        if !r.is_char_boundary(start) || !r.is_char_boundary(end) {
+            continue;
+        }
+
+        // Skip the edits that would delete the character after a mark.
+        if marks.iter().any(|&(id, _)| {
+            let offset = r.marks().get(id).unwrap();
+            start <= offset && offset < end
+        }) {
+            continue;
+        }
+
+        r.replace(start..end, "🐸");
+    }
+
+    for (id, char) in marks {
+        let offset = r.marks().get(id).unwrap();
+        assert_eq!(r.byte_slice(offset..).chars().next(), char);
+    }
+}
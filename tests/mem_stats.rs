@@ -0,0 +1,91 @@
+mod common;
+
+use common::{LARGE, TINY};
+use crop::Rope;
+use rand::Rng;
+
+#[test]
+fn mem_stats_large() {
+    let mut r = Rope::from(LARGE);
+    r.insert(LARGE.len() / 2, "foo");
+
+    let stats = r.mem_stats();
+
+    assert_eq!(stats.text_bytes, r.byte_len());
+    assert_eq!(stats.nodes_per_depth[0], 1);
+    assert!(*stats.nodes_per_depth.last().unwrap() <= r.chunks().count());
+    assert_eq!(stats.leaf_bytes, stats.text_bytes + stats.slack_bytes);
+    assert!(stats.node_bytes > 0);
+    assert_eq!(stats.total_bytes(), stats.leaf_bytes + stats.node_bytes);
+
+    let fill_factor = stats.fill_factor();
+    assert!(fill_factor > 0.0 && fill_factor <= 1.0);
+
+    // Every level of the tree has more nodes than the one above it.
+    for depths in stats.nodes_per_depth.windows(2) {
+        assert!(depths[0] < depths[1]);
+    }
+}
+
+#[test]
+fn mem_stats_shrunk() {
+    let mut r = Rope::from(LARGE);
+    r.shrink_to_fit();
+
+    let stats = r.mem_stats();
+    assert_eq!(stats.slack_bytes, 0);
+    assert!(stats.leaf_bytes <= r.byte_len());
+}
+
+#[test]
+fn mem_stats_small() {
+    let stats = Rope::new().mem_stats();
+    assert_eq!(stats.text_bytes, 0);
+    assert_eq!(stats.leaf_bytes, stats.slack_bytes);
+    assert_eq!(stats.nodes_per_depth, [1]);
+    assert_eq!(stats.fill_factor(), 0.0);
+
+    let stats = Rope::from(TINY).mem_stats();
+    assert_eq!(stats.text_bytes, TINY.len());
+    assert_eq!(stats.leaf_bytes, stats.text_bytes + stats.slack_bytes);
+}
+
+#[test]
+fn mem_stats_repeated_nodes() {
+    let r = Rope::from(LARGE).repeat(4);
+
+    let stats = r.mem_stats();
+
+    // The nodes shared by the repetitions are only counted once.
+    assert!(stats.text_bytes < r.byte_len());
+    assert!(
+        stats.total_bytes() < 4 * Rope::from(LARGE).mem_stats().total_bytes()
+    );
+}
+
+/// Checks that deleting most of a `Rope` removes the levels of the tree its
+/// content doesn't need anymore.
+#[cfg_attr(miri, ignore)]
+#[test]
+fn mem_stats_depth_after_deletions() {
+    let mut rng = rand::thread_rng();
+
+    let mut r = Rope::from(LARGE);
+
+    while r.byte_len() > 0 {
+        let len = r.byte_len();
+        let start = rng.gen_range(0..=len / 4);
+        let end = len - rng.gen_range(0..=len / 4);
+
+        r.delete(start..end);
+        r.assert_invariants();
+
+        let depth = r.mem_stats().nodes_per_depth.len();
+        let fresh_depth =
+            Rope::from(r.to_string()).mem_stats().nodes_per_depth.len();
+
+        assert!(depth <= fresh_depth + 1);
+    }
+
+    assert_eq!(r.mem_stats().nodes_per_depth, [1]);
+}
@@ -0,0 +1,112 @@
+mod common;
+
+#[cfg(feature = "mmap")]
+mod tests {
+    use std::io::Write;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use crop::{Rope, StableBytes};
+    use rand::Rng;
+
+    use crate::common::{CURSED_LIPSUM, LARGE, SMALL, TINY};
+
+    #[test]
+    fn mmap_from_bytes() {
+        for s in ["", "a", TINY, SMALL, LARGE, CURSED_LIPSUM] {
+            let r = Rope::from_mmap(s.as_bytes().to_vec()).unwrap();
+            r.assert_invariants();
+            assert_eq!(r, s);
+            assert_eq!(r, Rope::from(s));
+        }
+    }
+
+    #[test]
+    fn mmap_edits() {
+        let mut rng = rand::thread_rng();
+
+        for s in [TINY, SMALL, LARGE, CURSED_LIPSUM] {
+            let mut r = Rope::from_mmap(s.as_bytes().to_vec()).unwrap();
+            let mut string = s.to_owned();
+            let snapshot = r.clone();
+
+            for _ in 0..20 {
+                let start = rng.gen_range(0..=r.byte_len());
+                let end = rng.gen_range(start..=r.byte_len());
+
+                if r.is_char_boundary(start) && r.is_char_boundary(end) {
+                    r.replace(start..end, "foo\r\n🦀");
+                    string.replace_range(start..end, "foo\r\n🦀");
+                    r.assert_invariants();
+                    assert_eq!(r, string);
+                }
+            }
+
+            assert_eq!(snapshot, s);
+        }
+    }
+
+    #[test]
+    fn mmap_invalid_utf8() {
+        let mut bytes = LARGE.as_bytes().to_vec();
+        bytes[1000] = 0xff;
+        assert!(Rope::from_mmap(bytes).is_err());
+    }
+
+    #[test]
+    fn mmap_keeps_source_alive() {
+        struct Source(Vec<u8>, Arc<AtomicBool>);
+
+        impl AsRef<[u8]> for Source {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        // SAFETY: the bytes are never changed.
+        unsafe impl StableBytes for Source {}
+
+        impl Drop for Source {
+            fn drop(&mut self) {
+                self.1.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicBool::new(false));
+
+        let source = Source(SMALL.as_bytes().to_vec(), Arc::clone(&dropped));
+        let r = Rope::from_mmap(source).unwrap();
+        let slice = r.byte_slice(10..100).to_string();
+        let clone = r.clone();
+
+        drop(r);
+        assert!(!dropped.load(Ordering::SeqCst));
+        assert_eq!(clone.byte_slice(10..100), slice);
+
+        drop(clone);
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn mmap_file() {
+        let path = std::env::temp_dir()
+            .join(format!("crop-mmap-test-{}.txt", std::process::id()));
+
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(LARGE.as_bytes()).unwrap();
+        drop(file);
+
+        let file = std::fs::File::open(&path).unwrap();
+        let mmap = unsafe { memmap2::Mmap::map(&file).unwrap() };
+
+        let mut r = Rope::from_mmap(mmap).unwrap();
+        assert_eq!(r, LARGE);
+
+        r.insert(0, "# ");
+        assert_eq!(r.byte_slice(..2), "# ");
+        assert_eq!(r.byte_slice(2..), LARGE);
+
+        drop(r);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+use crop::multi_rope::MultiRope;
+use crop::Rope;
+use rand::Rng;
+
+mod common;
+
+use common::{CURSED_LIPSUM, SMALL, TINY};
+
+/// Splits `text` at random char boundaries, returning the pieces as `Rope`s.
+fn random_pieces(text: &str) -> Vec<Rope> {
+    let mut rng = rand::thread_rng();
+
+    let mut pieces = Vec::new();
+    let mut start = 0;
+
+    while start < text.len() {
+        let mut end = rng.gen_range(start..=text.len().min(start + 200));
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        pieces.push(Rope::from(&text[start..end]));
+        start = end;
+    }
+
+    pieces
+}
+
+#[test]
+fn multi_rope_matches_rope() {
+    let mut rng = rand::thread_rng();
+
+    for text in [TINY, SMALL, CURSED_LIPSUM, "a\r\n\nb", "\n\n"] {
+        let pieces = random_pieces(text);
+        let multi = pieces.iter().collect::<MultiRope>();
+        let rope = Rope::from(text);
+
+        multi.assert_invariants();
+        assert_eq!(multi, text);
+        assert_eq!(multi.to_string(), text);
+        assert_eq!(multi.byte_len(), rope.byte_len());
+        assert_eq!(multi.line_len(), rope.line_len());
+        assert!(multi.chars().rev().eq(text.chars().rev()));
+
+        for line_offset in 0..=rope.line_len() {
+            assert_eq!(
+                multi.byte_of_line(line_offset),
+                rope.byte_of_line(line_offset)
+            );
+        }
+
+        for _ in 0..50 {
+            let mut start = rng.gen_range(0..=text.len());
+            while !text.is_char_boundary(start) {
+                start -= 1;
+            }
+
+            let mut end = rng.gen_range(start..=text.len());
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            assert_eq!(multi.line_of_byte(start), rope.line_of_byte(start));
+
+            if start < text.len() {
+                assert_eq!(multi.byte(start), text.as_bytes()[start]);
+            }
+
+            let slice = multi.byte_slice(start..end);
+            slice.assert_invariants();
+            assert_eq!(slice, &text[start..end]);
+            assert_eq!(
+                slice.line_len(),
+                rope.byte_slice(start..end).line_len()
+            );
+        }
+    }
+}
+
+#[test]
+fn multi_rope_empty() {
+    let multi = MultiRope::new();
+
+    assert!(multi.is_empty());
+    assert_eq!(multi, "");
+    assert_eq!(multi.line_len(), 0);
+    assert_eq!(multi.byte_of_line(0), 0);
+    assert_eq!(multi.line_of_byte(0), 0);
+}
+
+#[test]
+fn multi_rope_piece_of_byte() {
+    let foo = Rope::from("foo");
+    let bar = Rope::from("bar");
+    let empty = Rope::new();
+
+    let mut multi = MultiRope::new();
+    multi.push(&foo);
+    multi.push(empty.byte_slice(..));
+    multi.push(&bar);
+
+    assert_eq!(multi.pieces().len(), 2);
+    assert_eq!(multi.piece_of_byte(2), (0, 2));
+    assert_eq!(multi.piece_of_byte(3), (1, 0));
+}
+
+#[test]
+#[should_panic]
+fn multi_rope_slice_not_char_boundary() {
+    let foo = Rope::from("foo");
+    let bar = Rope::from("ƒoo");
+
+    let multi = [&foo, &bar].into_iter().collect::<MultiRope>();
+    let _ = multi.byte_slice(4..4);
+}
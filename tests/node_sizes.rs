@@ -0,0 +1,77 @@
+use crop::tree::AtomicCount;
+use crop::{Rope, RopeBuilder};
+use rand::Rng;
+
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, MEDIUM, SMALL, TINY};
+
+fn random_edits<const ARITY: usize, const MAX_BYTES: usize>() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", TINY, SMALL, MEDIUM, CURSED_LIPSUM] {
+        let mut builder =
+            RopeBuilder::<AtomicCount, ARITY, MAX_BYTES>::empty();
+        builder.append(s);
+
+        let mut r = builder.build();
+        assert_eq!(r, Rope::<AtomicCount, ARITY, MAX_BYTES>::from_text(s));
+        let mut s = s.to_owned();
+
+        r.assert_invariants();
+        assert_eq!(r, s);
+
+        for _ in 0..100 {
+            let mut start = rng.gen_range(0..=s.len());
+            while !s.is_char_boundary(start) {
+                start -= 1;
+            }
+
+            let mut end = rng.gen_range(start..=(start + 30).min(s.len()));
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let text = ["", "a", "\n", "ä\nb", "foo bar baz\r\n"]
+                [rng.gen_range(0..5)];
+
+            r.replace(start..end, text);
+            s.replace_range(start..end, text);
+
+            r.assert_invariants();
+            assert_eq!(r.byte_len(), s.len());
+            assert_eq!(r.line_len(), Rope::from(s.as_str()).line_len());
+        }
+
+        assert_eq!(r, s);
+        assert!(r.lines().eq(Rope::from(s.as_str()).lines()));
+    }
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn node_sizes_random_edits() {
+    random_edits::<4, 4>();
+    random_edits::<5, 64>();
+    random_edits::<32, 16384>();
+    random_edits::<255, 65535>();
+}
+
+#[test]
+fn node_sizes_large() {
+    let mut r = Rope::<AtomicCount, 4, 16>::empty();
+
+    r.insert(0, LARGE);
+    r.assert_invariants();
+
+    let half = r.byte_len() / 2;
+    let mut half = r.byte_slice(..half).line_len();
+    half = r.byte_of_line(half);
+
+    let right = Rope::<AtomicCount, 4, 16>::from(r.byte_slice(half..));
+    r.delete(half..);
+    r += right;
+    r.assert_invariants();
+
+    assert_eq!(r, LARGE);
+}
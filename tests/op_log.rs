@@ -0,0 +1,90 @@
+use crop::op_log::{ApplyError, Op, OpId, Replica, ReplicaId};
+use crop::Rope;
+
+mod common;
+
+use common::TEXT;
+
+#[test]
+fn op_log_records_local_edits() {
+    let mut r = Replica::new(ReplicaId(7));
+
+    let first = r.insert(0, "Hello World");
+    let second = r.replace(6..11, "Earth");
+    let third = r.delete(..6);
+
+    assert_eq!(*r, "Earth");
+
+    assert_eq!(first, OpId { replica: ReplicaId(7), seq: 0 });
+    assert_eq!(second, OpId { replica: ReplicaId(7), seq: 1 });
+    assert_eq!(third, OpId { replica: ReplicaId(7), seq: 2 });
+
+    let ops = r.ops();
+    assert_eq!(ops.len(), 3);
+    assert_eq!(ops[1].deleted(), 6..11);
+    assert_eq!(ops[1].inserted(), "Earth");
+    assert_eq!(ops[2].deleted(), 0..6);
+    assert_eq!(ops[2].inserted(), "");
+
+    assert_eq!(r.ops_since(2), &ops[2..]);
+    assert_eq!(r.version_of(ReplicaId(7)), 3);
+}
+
+#[test]
+fn op_log_replay_on_other_replica() {
+    let base = Rope::from(TEXT);
+
+    let mut alice = Replica::from_rope(ReplicaId(1), base.clone());
+    let mut bob = Replica::from_rope(ReplicaId(2), base);
+
+    alice.insert(0, "🐸 ");
+    alice.delete(20..40);
+
+    let exported = alice.ops_since(0).to_vec();
+
+    for op in &exported {
+        bob.apply(op).unwrap();
+    }
+
+    assert_eq!(*alice, *bob);
+    bob.assert_invariants();
+
+    bob.insert(bob.byte_len(), "!");
+
+    let mark = alice.ops().len();
+
+    for op in bob.ops_since(exported.len()) {
+        alice.apply(op).unwrap();
+    }
+
+    assert_eq!(*alice, *bob);
+    assert_eq!(alice.ops_since(mark).len(), 1);
+    assert_eq!(alice.version_of(ReplicaId(2)), 1);
+}
+
+#[test]
+fn op_log_apply_errors() {
+    let mut alice = Replica::new(ReplicaId(1));
+    let mut bob = Replica::new(ReplicaId(2));
+
+    alice.insert(0, "aaa");
+    alice.insert(0, "bbb");
+
+    let ops = alice.ops().to_vec();
+
+    assert_eq!(
+        bob.apply(&ops[1]),
+        Err(ApplyError::Missing { op: ops[1].id(), expected_seq: 0 })
+    );
+
+    bob.apply(&ops[0]).unwrap();
+
+    assert_eq!(bob.apply(&ops[0]), Err(ApplyError::Duplicate(ops[0].id())));
+
+    assert_eq!(alice.apply(&ops[0]), Err(ApplyError::OwnOp(ops[0].id())));
+
+    let bad = Op::new(OpId { replica: ReplicaId(1), seq: 1 }, 2..10, "");
+    assert_eq!(bob.apply(&bad), Err(ApplyError::InvalidRange(bad.id())));
+
+    assert_eq!(*bob, "aaa");
+}
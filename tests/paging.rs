@@ -0,0 +1,173 @@
+mod common;
+
+#[cfg(feature = "paging")]
+mod tests {
+    use std::fs::File;
+    use std::path::PathBuf;
+
+    use crop::paging::SpillFile;
+    use crop::tree::AtomicCount;
+    use crop::Rope;
+    use rand::Rng;
+
+    use crate::common::{CURSED_LIPSUM, LARGE, TINY};
+
+    fn spill_file(name: &str) -> (SpillFile, PathBuf) {
+        let path = std::env::temp_dir()
+            .join(format!("crop-paging-{name}-{}.spill", std::process::id()));
+
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+
+        (unsafe { SpillFile::new(file).unwrap() }, path)
+    }
+
+    /// The length of a slot of a `SpillFile`, i.e. the maximum length of a
+    /// chunk.
+    const SLOT_LEN: u64 = 2048;
+
+    #[test]
+    fn page_out_only_writes_edited_chunks() {
+        let (mut spill, path) = spill_file("edited");
+
+        let mut r = Rope::from(LARGE);
+        let snapshot = r.clone();
+
+        assert_eq!(r.page_out(&mut spill).unwrap(), LARGE.len() as u64);
+        r.assert_invariants();
+        assert_eq!(r, LARGE);
+        assert_eq!(snapshot, LARGE);
+
+        let len = spill.byte_len();
+        assert!(len >= LARGE.len() as u64);
+
+        assert_eq!(r.page_out(&mut spill).unwrap(), 0);
+
+        // The chunk containing the edit is hot, so it's kept in memory.
+        r.insert(LARGE.len() / 2, "foo");
+        assert_eq!(r.page_out(&mut spill).unwrap(), 0);
+        assert_eq!(spill.byte_len(), len);
+
+        // Once 16 more edits have been made it's cold, and it's written to
+        // a new slot because the snapshot is still using the old one.
+        for _ in 0..16 {
+            r.insert(0, "a");
+        }
+
+        let written = r.page_out(&mut spill).unwrap();
+        assert!(written > 0 && written < LARGE.len() as u64 / 10);
+        assert!(spill.byte_len() > len);
+
+        let mut expected = "a".repeat(16);
+        expected.push_str(LARGE);
+        expected.insert_str(16 + LARGE.len() / 2, "foo");
+        r.assert_invariants();
+        assert_eq!(r, expected);
+
+        drop(spill);
+        assert_eq!(r, expected);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn page_out_reuses_freed_slots() {
+        let (mut spill, path) = spill_file("reuse");
+        let mut rng = rand::thread_rng();
+
+        let mut r = Rope::from(LARGE);
+        let mut string = LARGE.to_owned();
+
+        r.page_out(&mut spill).unwrap();
+
+        let len = spill.byte_len();
+
+        for _ in 0..500 {
+            let start = rng.gen_range(0..=r.byte_len() - 3);
+
+            if r.is_char_boundary(start) && r.is_char_boundary(start + 3) {
+                r.replace(start..start + 3, "bar");
+                string.replace_range(start..start + 3, "bar");
+            }
+
+            r.page_out(&mut spill).unwrap();
+
+            // The slots of the edited chunks are reused, so the file doesn't
+            // grow by a slot for every edit.
+            assert!(spill.byte_len() <= len + 32 * SLOT_LEN);
+        }
+
+        r.assert_invariants();
+        assert_eq!(r, string);
+
+        // Once all the chunks have been dropped the file is truncated.
+        drop(r);
+
+        let mut r = Rope::from(TINY);
+        r.page_out(&mut spill).unwrap();
+        assert_eq!(spill.byte_len(), SLOT_LEN);
+        assert_eq!(r, TINY);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn page_out_edits() {
+        let (mut spill, path) = spill_file("edits");
+        let mut rng = rand::thread_rng();
+
+        for s in ["", TINY, LARGE, CURSED_LIPSUM] {
+            let mut r = Rope::from(s);
+            let mut string = s.to_owned();
+
+            for _ in 0..20 {
+                r.page_out(&mut spill).unwrap();
+
+                let start = rng.gen_range(0..=r.byte_len());
+                let end = rng.gen_range(start..=r.byte_len());
+
+                if r.is_char_boundary(start) && r.is_char_boundary(end) {
+                    r.replace(start..end, "bar\r\n🦀");
+                    string.replace_range(start..end, "bar\r\n🦀");
+                }
+
+                r.assert_invariants();
+                assert_eq!(r, string);
+                assert_eq!(r.line_len(), Rope::from(&*string).line_len());
+            }
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn page_out_other_chunk_sizes() {
+        let (mut spill, path) = spill_file("sizes");
+
+        let mut small = Rope::<AtomicCount, 4, 64>::from_text(LARGE);
+        assert_eq!(small.page_out(&mut spill).unwrap(), LARGE.len() as u64);
+        assert_eq!(spill.byte_len() % 64, 0);
+        small.assert_invariants();
+        assert_eq!(small, LARGE);
+
+        // The slots are too small for the chunks of a default `Rope`.
+        let mut r = Rope::from(LARGE);
+        assert!(r.page_out(&mut spill).is_err());
+        assert_eq!(r, LARGE);
+
+        // Once the chunks of the first `Rope` are dropped the slots can be
+        // resized.
+        drop(small);
+        r.page_out(&mut spill).unwrap();
+        assert_eq!(spill.byte_len() % SLOT_LEN, 0);
+        r.assert_invariants();
+        assert_eq!(r, LARGE);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,228 @@
+mod common;
+
+#[cfg(feature = "parallel")]
+mod tests {
+    use std::io::{ErrorKind, Read};
+
+    use crop::Rope;
+    use rand::Rng;
+    use rayon::prelude::*;
+
+    use crate::common::{CURSED_LIPSUM, LARGE, TINY};
+
+    /// A reader yielding at most `max` bytes at a time.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        max: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.max.min(buf.len()).min(self.bytes.len());
+            buf[..len].copy_from_slice(&self.bytes[..len]);
+            self.bytes = &self.bytes[len..];
+            Ok(len)
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn parallel_from_str() {
+        for s in [
+            String::new(),
+            TINY.to_owned(),
+            LARGE.repeat(4),
+            CURSED_LIPSUM.repeat(2000),
+        ] {
+            let r = Rope::from_str_parallel(&s);
+            r.assert_invariants();
+            assert_eq!(r, s);
+            assert_eq!(r.line_len(), Rope::from(&*s).line_len());
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn parallel_from_reader() {
+        for s in [
+            String::new(),
+            TINY.to_owned(),
+            LARGE.repeat(4),
+            CURSED_LIPSUM.repeat(2000),
+        ] {
+            let r = Rope::from_reader_parallel(s.as_bytes()).unwrap();
+            r.assert_invariants();
+            assert_eq!(r, s);
+            assert_eq!(r.line_len(), Rope::from(&*s).line_len());
+
+            let reader = Trickle { bytes: s.as_bytes(), max: 4093 };
+            let r = Rope::from_reader_parallel(reader).unwrap();
+            r.assert_invariants();
+            assert_eq!(r, s);
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn parallel_from_reader_invalid() {
+        let s = CURSED_LIPSUM.repeat(2000);
+
+        for offset in [0, 1, s.len() / 3, s.len() - 1] {
+            let mut bytes = s.clone().into_bytes();
+            bytes[offset] = 0xff;
+
+            let err = Rope::from_reader_parallel(&*bytes).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+            let valid_up_to =
+                std::str::from_utf8(&bytes).unwrap_err().valid_up_to();
+
+            assert!(err.to_string().ends_with(&format!(" {valid_up_to}")));
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn parallel_from_reader_incomplete_at_end() {
+        let mut bytes = LARGE.repeat(4).into_bytes();
+        bytes.extend_from_slice("🦀".as_bytes().split_last().unwrap().1);
+
+        let err = Rope::from_reader_parallel(&*bytes).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn par_chunks_random() {
+        let mut rng = rand::thread_rng();
+
+        for s in ["", TINY, LARGE, &CURSED_LIPSUM.repeat(200)] {
+            let mut r = Rope::from(s);
+
+            // Edit the rope so that its chunks aren't all full.
+            for _ in 0..100 {
+                let offset = rng.gen_range(0..=r.byte_len());
+                if r.is_char_boundary(offset) {
+                    r.insert(offset, "🦀");
+                }
+            }
+
+            for _ in 0..10 {
+                let start = rng.gen_range(0..=r.byte_len());
+                let end = rng.gen_range(start..=r.byte_len());
+
+                if !r.is_char_boundary(start) || !r.is_char_boundary(end) {
+                    continue;
+                }
+
+                let slice = r.byte_slice(start..end);
+
+                let chunks = slice.par_chunks().collect::<Vec<_>>();
+                assert_eq!(chunks, slice.chunks().collect::<Vec<_>>());
+
+                let byte_len = slice.par_chunks().map(str::len).sum::<usize>();
+                assert_eq!(byte_len, slice.byte_len());
+            }
+
+            assert_eq!(
+                r.par_chunks().collect::<String>(),
+                r.chunks().collect::<String>()
+            );
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn par_find_all_random() {
+        let mut rng = rand::thread_rng();
+
+        let aaa = "a".repeat(5000);
+
+        for s in [TINY, &aaa, &LARGE.repeat(2), &CURSED_LIPSUM.repeat(300)] {
+            let r = Rope::from(s);
+
+            let mut patterns = vec![
+                "".to_owned(),
+                "a".to_owned(),
+                "aa".to_owned(),
+                "aaa".to_owned(),
+            ];
+
+            for _ in 0..20 {
+                let len = [1, 2, 5, 40, 3000][rng.gen_range(0..5)];
+                let mut start = rng.gen_range(0..=s.len());
+                while !s.is_char_boundary(start) {
+                    start -= 1;
+                }
+                let mut end = (start + len).min(s.len());
+                while !s.is_char_boundary(end) {
+                    end += 1;
+                }
+                patterns.push(s[start..end].to_owned());
+            }
+
+            for pattern in &patterns {
+                let expected = if pattern.is_empty() {
+                    Vec::new()
+                } else {
+                    s.match_indices(&**pattern).map(|(idx, _)| idx).collect()
+                };
+
+                assert_eq!(r.par_find_all(pattern), expected);
+            }
+
+            let start = s.len() / 3;
+            let slice = r.byte_slice(start..);
+            let pattern = &patterns[patterns.len() - 1];
+
+            if !pattern.is_empty() {
+                let expected = s[start..]
+                    .match_indices(&**pattern)
+                    .map(|(idx, _)| idx)
+                    .collect::<Vec<_>>();
+
+                assert_eq!(slice.par_find_all(pattern), expected);
+            }
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn par_changes_since_random() {
+        let mut rng = rand::thread_rng();
+
+        let mut r = Rope::from(LARGE.repeat(2));
+        let snapshot = r.snapshot();
+
+        assert_eq!(r.par_changes_since(&snapshot).count(), 0);
+
+        for _ in 0..50 {
+            let mut start = rng.gen_range(0..=r.byte_len());
+            while !r.is_char_boundary(start) {
+                start -= 1;
+            }
+
+            let end = (start + rng.gen_range(0..10)).min(r.byte_len());
+
+            if r.is_char_boundary(end) {
+                r.replace(
+                    start..end,
+                    ["", "a", "foo\n", "🦀"][rng.gen_range(0..4)],
+                );
+            }
+
+            assert_eq!(
+                r.par_changes_since(&snapshot).collect::<Vec<_>>(),
+                r.changes_since(&snapshot).collect::<Vec<_>>()
+            );
+        }
+
+        // Unrelated trees storing the same text.
+        let other = Rope::from(r.to_string()).snapshot();
+
+        assert_eq!(
+            r.par_changes_since(&other).collect::<Vec<_>>(),
+            r.changes_since(&other).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crop::tree::LocalCount;
+use crop::Rope;
+use rand::Rng;
+
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, SMALL};
+
+/// Returns the records of `s` delimited by `separator`, counted like lines.
+fn records(s: &str, separator: char) -> Vec<&str> {
+    let mut records = s.split(separator).collect::<Vec<_>>();
+
+    if records.last() == Some(&"") {
+        records.pop();
+    }
+
+    records
+}
+
+#[test]
+fn record_index_random() {
+    let mut rng = rand::thread_rng();
+
+    let pieces = ["a", "🦀", "\0", "\n", ",", "\r\n"];
+
+    let mut strings = vec![
+        String::new(),
+        SMALL.to_owned(),
+        LARGE.to_owned(),
+        CURSED_LIPSUM.to_owned(),
+    ];
+
+    for _ in 0..50 {
+        strings.push(
+            (0..rng.gen_range(0..300))
+                .map(|_| pieces[rng.gen_range(0..pieces.len())])
+                .collect(),
+        );
+    }
+
+    for s in &strings {
+        let r = Rope::from(s.as_str());
+
+        for separator in ['\0', '\n', ','] {
+            let index = r.record_index(separator as u8);
+            let records = records(s, separator);
+
+            assert_eq!(index.record_len(), records.len());
+
+            assert_eq!(index.records().collect::<Vec<_>>(), records);
+
+            for (record_offset, record) in records.iter().enumerate() {
+                let byte_offset = index.byte_of_record(record_offset);
+                assert_eq!(
+                    byte_offset,
+                    record.as_ptr() as usize - s.as_ptr() as usize
+                );
+                assert_eq!(index.record_of_byte(byte_offset), record_offset);
+            }
+
+            assert_eq!(index.byte_of_record(records.len()), s.len());
+            assert_eq!(index.record_slice(..), s.as_str());
+        }
+    }
+}
+
+#[test]
+fn record_index_newline_matches_lines() {
+    let r = Rope::from(LARGE);
+    let index = r.record_index(b'\n');
+
+    assert_eq!(index.record_len(), r.line_len());
+
+    for (line_offset, line) in r.raw_lines().enumerate() {
+        assert_eq!(
+            index.byte_of_record(line_offset),
+            r.byte_of_line(line_offset)
+        );
+        assert_eq!(index.record_slice(line_offset..line_offset + 1), line);
+    }
+}
+
+#[test]
+fn record_index_local_rope() {
+    let r = Rope::<LocalCount>::from(&Rope::from(SMALL));
+    let index = r.record_index(b' ');
+
+    let records = records(SMALL, ' ');
+
+    assert_eq!(index.record_len(), records.len());
+
+    for (record, expected) in index.records().zip(records) {
+        assert_eq!(record, expected);
+    }
+}
+
+#[test]
+#[should_panic]
+fn record_index_non_ascii_separator() {
+    let r = Rope::from("foo");
+    r.record_index(0xF0);
+}
+
+#[test]
+#[should_panic]
+fn record_index_record_out_of_bounds() {
+    let r = Rope::from("foo\0bar\0");
+    r.record_index(b'\0').record(2);
+}
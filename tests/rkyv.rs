@@ -0,0 +1,160 @@
+mod common;
+
+#[cfg(feature = "rkyv")]
+mod tests {
+    use crop::{ArchivedRope, Rope};
+    use rand::Rng;
+    use rkyv::rancor::Error;
+
+    use crate::common::{CURSED_LIPSUM, LARGE, SMALL, TINY};
+
+    #[test]
+    fn rkyv_roundtrip() {
+        let mut rng = rand::thread_rng();
+
+        for s in ["", "a", TINY, SMALL, LARGE, CURSED_LIPSUM] {
+            let mut r = Rope::from(s);
+
+            for _ in 0..10 {
+                let bytes = rkyv::to_bytes::<Error>(&r).unwrap();
+
+                let archived =
+                    rkyv::access::<ArchivedRope, Error>(&bytes).unwrap();
+
+                assert_eq!(archived.byte_len(), r.byte_len());
+                assert_eq!(archived.is_empty(), r.byte_len() == 0);
+                assert_eq!(*archived, r);
+                assert_eq!(*archived, *r.to_string());
+                assert_eq!(archived.to_string(), r.to_string());
+
+                let de = rkyv::deserialize::<Rope, Error>(archived).unwrap();
+                de.assert_invariants();
+                assert_eq!(de, r);
+
+                let offset = rng.gen_range(0..=r.byte_len());
+                if r.is_char_boundary(offset) {
+                    r.insert(offset, "foo\r\n🦀");
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "line-metric")]
+    #[test]
+    fn rkyv_bytes_lines_and_slices() {
+        let mut rng = rand::thread_rng();
+
+        for s in
+            ["", "a", "\n", "a\r\nb\n\n", TINY, SMALL, LARGE, CURSED_LIPSUM]
+        {
+            let r = Rope::from(s);
+            let bytes = rkyv::to_bytes::<Error>(&r).unwrap();
+            let archived =
+                rkyv::access::<ArchivedRope, Error>(&bytes).unwrap();
+
+            assert_eq!(archived.line_len(), r.line_len());
+
+            for line_index in 0..r.line_len() {
+                assert_eq!(
+                    archived.line(line_index).to_string(),
+                    r.line(line_index).to_string()
+                );
+            }
+
+            for line_offset in 0..=r.line_len() {
+                assert_eq!(
+                    archived.byte_of_line(line_offset),
+                    r.byte_of_line(line_offset)
+                );
+            }
+
+            for _ in 0..10 {
+                let start = rng.gen_range(0..=r.byte_len());
+                let end = rng.gen_range(start..=r.byte_len());
+
+                if r.is_char_boundary(end) {
+                    assert_eq!(
+                        archived.line_of_byte(end),
+                        r.line_of_byte(end)
+                    );
+                }
+
+                if end < r.byte_len() {
+                    assert_eq!(archived.byte(end), r.byte(end));
+                }
+
+                if r.is_char_boundary(start) && r.is_char_boundary(end) {
+                    let slice = archived.byte_slice(start..end);
+                    assert_eq!(slice.byte_len(), end - start);
+                    assert_eq!(
+                        slice.to_string(),
+                        r.byte_slice(start..end).to_string()
+                    );
+                    assert_eq!(
+                        slice.chunks().rev().collect::<Vec<_>>(),
+                        slice
+                            .chunks()
+                            .collect::<Vec<_>>()
+                            .into_iter()
+                            .rev()
+                            .collect::<Vec<_>>()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn rkyv_byte_slice_not_char_boundary() {
+        let r = Rope::from("🦀");
+        let bytes = rkyv::to_bytes::<Error>(&r).unwrap();
+        let archived = rkyv::access::<ArchivedRope, Error>(&bytes).unwrap();
+        let _ = archived.byte_slice(1..);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rkyv_line_of_byte_not_char_boundary() {
+        let r = Rope::from("a🦀b");
+        let bytes = rkyv::to_bytes::<Error>(&r).unwrap();
+        let archived = rkyv::access::<ArchivedRope, Error>(&bytes).unwrap();
+        let _ = archived.line_of_byte(2);
+    }
+
+    #[test]
+    fn rkyv_not_equal() {
+        let r = Rope::from(LARGE);
+        let bytes = rkyv::to_bytes::<Error>(&r).unwrap();
+        let archived = rkyv::access::<ArchivedRope, Error>(&bytes).unwrap();
+
+        assert_ne!(*archived, Rope::from(&LARGE[1..]));
+        assert_ne!(*archived, Rope::from(&LARGE[..LARGE.len() - 1]));
+        assert_ne!(*archived, *CURSED_LIPSUM);
+    }
+
+    #[test]
+    fn rkyv_invalid_bytes() {
+        let r = Rope::from(SMALL);
+        let mut bytes = rkyv::to_bytes::<Error>(&r).unwrap();
+
+        let len = bytes.len();
+        bytes[len - 4..].copy_from_slice(&[0xff; 4]);
+
+        assert!(rkyv::access::<ArchivedRope, Error>(&bytes).is_err());
+    }
+
+    #[test]
+    fn rkyv_invalid_offsets() {
+        let r = Rope::from(SMALL);
+        let mut bytes = rkyv::to_bytes::<Error>(&r).unwrap();
+
+        // The number of line breaks is stored at the very end of the archive.
+        let len = bytes.len();
+        let line_breaks =
+            u64::from_le_bytes(bytes[len - 8..].try_into().unwrap());
+        bytes[len - 8..].copy_from_slice(&(line_breaks + 1).to_le_bytes());
+
+        assert!(rkyv::access::<ArchivedRope, Error>(&bytes).is_err());
+    }
+}
@@ -1,7 +1,10 @@
+use std::borrow::Cow;
+
 mod common;
 
-use common::LARGE;
-use crop::{Rope, RopeBuilder};
+use common::{CURSED_LIPSUM, LARGE, MEDIUM, SMALL, TINY};
+use crop::{LineEnding, Rope, RopeBuilder};
+use rand::Rng;
 
 #[test]
 fn builder_line_len() {
@@ -59,3 +62,356 @@ fn builder_crlf_1() {
     r.assert_invariants();
     assert_eq!(r, "aaa\r\nbbb");
 }
+
+#[test]
+fn map_chunks_and_filter_map_chars() {
+    for text in [TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = Rope::from(text);
+
+        let upper = r.map_chunks(|chunk| Cow::Owned(chunk.to_uppercase()));
+        upper.assert_invariants();
+        assert_eq!(upper, text.to_uppercase());
+
+        let same = r.map_chunks(Cow::Borrowed);
+        same.assert_invariants();
+        assert_eq!(same, r);
+
+        let filter = |ch: char| (!ch.is_whitespace()).then_some(ch);
+
+        let filtered = r.filter_map_chars(filter);
+        filtered.assert_invariants();
+        assert_eq!(
+            filtered,
+            text.chars().filter_map(filter).collect::<String>()
+        );
+    }
+}
+
+#[test]
+fn builder_normalize_eols_random() {
+    let mut rng = rand::thread_rng();
+
+    let pieces = ["a", "🦀", "\r", "\n", "\r\n"];
+
+    for _ in 0..100 {
+        let s = (0..rng.gen_range(0..200))
+            .map(|_| pieces[rng.gen_range(0..pieces.len())])
+            .collect::<String>();
+
+        let mut b = RopeBuilder::new();
+        b.normalize_eols();
+
+        // Append the text in random pieces, which may split CRLF sequences.
+        let mut rest = s.as_str();
+        while !rest.is_empty() {
+            let mut idx = rng.gen_range(0..=rest.len());
+            while !rest.is_char_boundary(idx) {
+                idx += 1;
+            }
+            let (append, next) = rest.split_at(idx);
+            b.append(append);
+            rest = next;
+        }
+
+        let expected_eol = Rope::from(s.as_str()).detect_line_ending();
+        assert_eq!(b.original_line_ending(), Some(expected_eol));
+
+        let r = b.build();
+        r.assert_invariants();
+        assert_eq!(r, s.replace("\r\n", "\n").replace('\r', "\n"));
+    }
+}
+
+#[test]
+fn builder_normalize_eols_off() {
+    let mut b = RopeBuilder::new();
+    b.append("a\r\n");
+    assert_eq!(b.original_line_ending(), None);
+
+    b.normalize_eols().append("b\r\nc\r");
+    assert_eq!(b.original_line_ending(), Some(LineEnding::CrLf));
+
+    let r = b.build();
+    r.assert_invariants();
+    assert_eq!(r, "a\r\nb\nc\n");
+}
+
+#[test]
+fn builder_push_bytes_random_splits() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let mut b = RopeBuilder::new();
+
+        let mut bytes = s.as_bytes();
+
+        while !bytes.is_empty() {
+            let len = rng.gen_range(0..=bytes.len().min(7));
+            let (push, rest) = bytes.split_at(len);
+            b.push_bytes(push).unwrap();
+            bytes = rest;
+        }
+
+        assert!(b.incomplete_bytes().is_empty());
+
+        let r = b.build();
+        r.assert_invariants();
+        assert_eq!(r, s);
+    }
+}
+
+#[test]
+fn builder_push_bytes_one_at_a_time() {
+    let mut b = RopeBuilder::new();
+
+    for byte in CURSED_LIPSUM.bytes() {
+        b.push_bytes(&[byte]).unwrap();
+    }
+
+    assert_eq!(b.build(), CURSED_LIPSUM);
+}
+
+#[test]
+fn builder_push_bytes_normalized() {
+    let mut b = RopeBuilder::new();
+
+    b.normalize_eols();
+
+    for bytes in ["foo\r".as_bytes(), b"\n\xc3", b"\xa9\r\nbar"] {
+        b.push_bytes(bytes).unwrap();
+    }
+
+    assert_eq!(b.original_line_ending(), Some(LineEnding::CrLf));
+    assert_eq!(b.build(), "foo\n\u{e9}\nbar");
+}
+
+#[test]
+fn builder_push_bytes_invalid() {
+    let mut b = RopeBuilder::new();
+    let err = b.push_bytes(b"abc\xffdef").err().unwrap();
+    assert_eq!(err.valid_up_to(), 3);
+    assert_eq!(err.error_len(), Some(1));
+    assert!(b.incomplete_bytes().is_empty());
+    assert_eq!(b.build(), "abc");
+
+    // The buffered code point isn't completed by the next bytes.
+    let mut b = RopeBuilder::new();
+    b.push_bytes(b"ab\xe2\x82").unwrap();
+    let err = b.push_bytes(b"cd").err().unwrap();
+    assert_eq!(err.valid_up_to(), 0);
+    assert_eq!(b.build(), "ab");
+
+    // The error comes after the buffered code point is completed, so its
+    // offset starts at the buffered bytes.
+    let mut b = RopeBuilder::new();
+    b.push_bytes(b"ab\xe2\x82").unwrap();
+    let err = b.push_bytes(b"\xacxy\xc0z").err().unwrap();
+    assert_eq!(err.valid_up_to(), 5);
+    assert_eq!(err.error_len(), Some(1));
+    assert_eq!(b.build(), "ab\u{20ac}xy");
+
+    // The input ends with bytes that can't start a valid code point.
+    let mut b = RopeBuilder::new();
+    let err = b.push_bytes(b"ab\xe0\x80").err().unwrap();
+    assert_eq!(err.valid_up_to(), 2);
+    assert_eq!(err.error_len(), Some(1));
+    assert!(b.incomplete_bytes().is_empty());
+    assert_eq!(b.build(), "ab");
+}
+
+#[test]
+fn builder_push_bytes_incomplete_at_end() {
+    let mut b = RopeBuilder::new();
+    b.push_bytes(b"ab\xf0").unwrap();
+    b.push_bytes(b"\x9f").unwrap();
+    b.push_bytes(b"").unwrap();
+    assert_eq!(b.incomplete_bytes(), b"\xf0\x9f");
+    assert_eq!(b.build(), "ab\u{fffd}");
+
+    // Appending a string replaces the incomplete code point.
+    let mut b = RopeBuilder::new();
+    b.push_bytes(b"ab\xf0").unwrap();
+    b.append("cd");
+    assert!(b.incomplete_bytes().is_empty());
+    assert_eq!(b.build(), "ab\u{fffd}cd");
+}
+
+#[test]
+fn builder_push_string_random_lengths() {
+    let mut rng = rand::thread_rng();
+
+    let s = LARGE.repeat(4);
+
+    for max_len in [8, 600, 2048, 3000] {
+        let mut strings = Vec::new();
+
+        let mut rest = &*s;
+
+        while !rest.is_empty() {
+            let mut len = rng.gen_range(0..=rest.len().min(max_len));
+            while !rest.is_char_boundary(len) {
+                len -= 1;
+            }
+            let (string, r) = rest.split_at(len);
+            strings.push(string.to_owned());
+            rest = r;
+        }
+
+        let r = Rope::from_strings(strings);
+        r.assert_invariants();
+        assert_eq!(r, s);
+    }
+}
+
+#[test]
+fn builder_push_string_mixed() {
+    let mut b = RopeBuilder::new();
+    let mut s = String::new();
+
+    for (idx, line) in MEDIUM.lines().enumerate() {
+        let chunk = line.repeat(idx % 40) + "\n";
+
+        s.push_str(&chunk);
+
+        if idx % 3 == 0 {
+            b.append(&chunk);
+        } else {
+            b.push_string(chunk);
+        }
+    }
+
+    let r = b.build();
+    r.assert_invariants();
+    assert_eq!(r, s);
+}
+
+#[test]
+fn builder_push_string_normalized() {
+    let mut b = RopeBuilder::new();
+
+    b.normalize_eols();
+
+    b.push_string("a\r\n".repeat(1000)).push_string("b\r".into());
+    b.push_string("\nc".into());
+
+    assert_eq!(b.original_line_ending(), Some(LineEnding::CrLf));
+
+    let r = b.build();
+    r.assert_invariants();
+    assert_eq!(r, "a\n".repeat(1000) + "b\nc");
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn builder_append_slices() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = Rope::from(s);
+
+        for _ in 0..20 {
+            let mut b = RopeBuilder::new();
+            let mut expected = String::new();
+
+            for _ in 0..rng.gen_range(1..10) {
+                let mut start = rng.gen_range(0..=s.len());
+                let mut end = rng.gen_range(start..=s.len());
+                while !s.is_char_boundary(start) {
+                    start -= 1;
+                }
+                while !s.is_char_boundary(end) {
+                    end += 1;
+                }
+
+                match rng.gen_range(0..3) {
+                    0 => {
+                        b.append_slice(r.byte_slice(start..end));
+                    },
+                    1 => {
+                        b.append_rope(&r);
+                        start = 0;
+                        end = s.len();
+                    },
+                    _ => {
+                        b.append(&s[start..end]);
+                    },
+                }
+
+                expected.push_str(&s[start..end]);
+            }
+
+            let rope = b.build();
+            rope.assert_invariants();
+            assert_eq!(rope, expected);
+            assert_eq!(rope.line_len(), Rope::from(&*expected).line_len());
+        }
+    }
+}
+
+#[test]
+fn builder_append_rope_trailing_newline() {
+    let r = Rope::from(LARGE);
+
+    let mut b = RopeBuilder::new();
+    b.append("foo").append_rope(&r).append("");
+    let rope = b.build();
+    rope.assert_invariants();
+    assert_eq!(rope.line_len(), Rope::from(format!("foo{LARGE}")).line_len());
+
+    let mut b = RopeBuilder::new();
+    b.append_rope(&r).append("bar");
+    let rope = b.build();
+    rope.assert_invariants();
+    assert_eq!(rope.line_len(), Rope::from(format!("{LARGE}bar")).line_len());
+}
+
+#[test]
+fn builder_append_slice_normalized() {
+    let r = Rope::from(LARGE.replace('\n', "\r\n"));
+
+    let mut b = RopeBuilder::new();
+    b.normalize_eols().append_slice(r.byte_slice(..));
+
+    assert_eq!(b.original_line_ending(), Some(LineEnding::CrLf));
+
+    let rope = b.build();
+    rope.assert_invariants();
+    assert_eq!(rope, LARGE);
+}
+
+#[test]
+fn builder_chunk_len() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        for chunk_len in [0, 7, 100, 512, 1024, 1 << 20] {
+            let mut b = RopeBuilder::new();
+            b.chunk_len(chunk_len);
+
+            let mut rest = s;
+
+            while !rest.is_empty() {
+                let mut at = rng.gen_range(0..=rest.len());
+                while !rest.is_char_boundary(at) {
+                    at += 1;
+                }
+                let (text, after) = rest.split_at(at);
+                if rng.gen() {
+                    b.append(text);
+                } else {
+                    b.push_string(text.to_owned());
+                }
+                rest = after;
+            }
+
+            let r = b.build();
+            r.assert_invariants();
+            assert_eq!(r, s);
+
+            // Shorter lengths are clamped to the minimum length of a chunk.
+            if chunk_len == 1024 {
+                assert!(r.chunks().all(|chunk| chunk.len() <= chunk_len));
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, MEDIUM, SMALL, TINY};
+use crop::Rope;
+use rand::Rng;
+
+#[test]
+fn cursor_empty() {
+    let r = Rope::new();
+
+    let mut cursor = r.cursor(0);
+    assert_eq!(cursor.offset(), 0);
+    assert_eq!(cursor.chunk(), "");
+    assert_eq!(cursor.peek_char(), None);
+    assert_eq!(cursor.next_char(), None);
+    assert_eq!(cursor.prev_char(), None);
+}
+
+#[test]
+fn cursor_next_char() {
+    for s in ["", TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = Rope::from(s);
+
+        let mut cursor = r.cursor(0);
+
+        for (offset, ch) in s.char_indices() {
+            assert_eq!(cursor.offset(), offset);
+            assert_eq!(cursor.peek_char(), Some(ch));
+            assert_eq!(cursor.next_char(), Some(ch));
+        }
+
+        assert_eq!(cursor.offset(), s.len());
+        assert_eq!(cursor.next_char(), None);
+    }
+}
+
+#[test]
+fn cursor_prev_char() {
+    for s in ["", TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = Rope::from(s);
+
+        let mut cursor = r.cursor(s.len());
+
+        for (offset, ch) in s.char_indices().rev() {
+            assert_eq!(cursor.prev_char(), Some(ch));
+            assert_eq!(cursor.offset(), offset);
+        }
+
+        assert_eq!(cursor.prev_char(), None);
+    }
+}
+
+#[test]
+fn cursor_chunks() {
+    for s in ["", TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = Rope::from(s);
+
+        let mut cursor = r.cursor(0);
+        let mut string = String::new();
+
+        loop {
+            let chunk = cursor.chunk();
+            if chunk.is_empty() {
+                break;
+            }
+            string.push_str(chunk);
+            cursor.seek(chunk.len() as isize);
+        }
+
+        assert_eq!(string, s);
+    }
+}
+
+#[test]
+fn cursor_random_seeks() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let mut r = Rope::from(s);
+        let mut string = s.to_owned();
+
+        // Edit the rope so that some of its leaves have a gap.
+        for _ in 0..10 {
+            let offset = rng.gen_range(0..=r.byte_len());
+            if r.is_char_boundary(offset) {
+                r.insert(offset, "bar\r\n🦀");
+                string.insert_str(offset, "bar\r\n🦀");
+            }
+        }
+
+        let mut cursor = r.cursor(0);
+
+        for _ in 0..500 {
+            let offset = if rng.gen_bool(0.5) {
+                rng.gen_range(0..=r.byte_len())
+            } else {
+                let delta = rng.gen_range(-64..=64);
+                (cursor.offset() as isize + delta).clamp(0, r.byte_len() as _)
+                    as usize
+            };
+
+            if !string.is_char_boundary(offset) {
+                continue;
+            }
+
+            cursor.seek_to(offset);
+            assert_eq!(cursor.offset(), offset);
+
+            let mut clone = cursor.clone();
+            assert_eq!(clone.next_char(), string[offset..].chars().next());
+            assert_eq!(
+                cursor.prev_char(),
+                string[..offset].chars().next_back()
+            );
+
+            let rest = cursor.chunk();
+            assert!(string[cursor.offset()..].starts_with(rest));
+            assert_eq!(rest.is_empty(), cursor.offset() == string.len());
+        }
+    }
+}
+
+#[test]
+fn cursor_slice() {
+    let mut rng = rand::thread_rng();
+
+    let r = Rope::from(LARGE);
+
+    for _ in 0..20 {
+        let start = rng.gen_range(0..=r.byte_len());
+        let end = rng.gen_range(start..=r.byte_len());
+
+        let s = r.byte_slice(start..end);
+        let string = &LARGE[start..end];
+
+        let mut cursor = s.cursor(0);
+        assert_eq!(cursor.prev_char(), None);
+
+        let mut chars = String::new();
+        while let Some(ch) = cursor.next_char() {
+            chars.push(ch);
+        }
+        assert_eq!(chars, string);
+
+        let offset = rng.gen_range(0..=string.len());
+        cursor.seek_to(offset);
+        assert!(string[offset..].starts_with(cursor.chunk()));
+    }
+}
+
+#[test]
+#[should_panic]
+fn cursor_out_of_bounds() {
+    let r = Rope::from("foo");
+    let _ = r.cursor(4);
+}
+
+#[test]
+#[should_panic]
+fn cursor_seek_not_char_boundary() {
+    let r = Rope::from("bär");
+    let mut cursor = r.cursor(0);
+    cursor.seek(2);
+}
+
+#[test]
+#[should_panic]
+fn cursor_seek_before_start() {
+    let r = Rope::from("foo");
+    let mut cursor = r.cursor(1);
+    cursor.seek(-2);
+}
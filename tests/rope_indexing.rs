@@ -1,4 +1,5 @@
-use crop::Rope;
+use crop::{LineCache, Rope};
+use rand::Rng;
 
 mod common;
 
@@ -35,6 +36,28 @@ fn rope_is_char_boundary() {
     }
 }
 
+#[cfg_attr(miri, ignore)]
+#[test]
+fn rope_is_ascii() {
+    for s in ["", "Hi", "🐕‍🦺", TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM]
+    {
+        let mut r = Rope::from(s);
+        assert_eq!(r.is_ascii(), s.is_ascii());
+        assert_eq!(r.byte_slice(..).is_ascii(), s.is_ascii());
+
+        let ascii_len = s.find(|ch: char| !ch.is_ascii()).unwrap_or(s.len());
+        assert!(r.byte_slice(..ascii_len).is_ascii());
+
+        let offset = ascii_len / 2;
+        r.insert(offset, "ö");
+        assert!(!r.is_ascii());
+        assert!(!r.byte_slice(offset..offset + 2).is_ascii());
+
+        r.delete(offset..offset + 2);
+        assert_eq!(r.is_ascii(), s.is_ascii());
+    }
+}
+
 /// ```
 /// Root
 /// ├───┐
@@ -99,3 +122,88 @@ fn rope_line_3() {
     let l = r.line(2);
     assert_eq!("", l);
 }
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn rope_line_conversions_around_cursor() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", "\n", "Hello", TINY, SMALL, LARGE, CURSED_LIPSUM] {
+        let mut r = Rope::from(s);
+        let mut s = s.to_owned();
+
+        let mut cache = LineCache::new();
+
+        for _ in 0..100 {
+            let line_starts = core::iter::once(0)
+                .chain(s.match_indices('\n').map(|(idx, _)| idx + 1))
+                .collect::<Vec<_>>();
+
+            let cursor = rng.gen_range(0..=s.len());
+
+            // A burst of conversions around the cursor, some of which are
+            // answered by the lines cached by the previous ones.
+            for _ in 0..20 {
+                let byte_offset = cursor
+                    .saturating_add_signed(rng.gen_range(-200..=200))
+                    .min(s.len());
+
+                let line = line_starts
+                    .partition_point(|&start| start <= byte_offset)
+                    - 1;
+
+                assert_eq!(cache.line_of_byte(&r, byte_offset), line);
+
+                let line = (line + rng.gen_range(0..3)).saturating_sub(1);
+
+                let expected =
+                    line_starts.get(line).copied().unwrap_or(s.len());
+
+                if line <= r.line_len() {
+                    assert_eq!(cache.byte_of_line(&r, line), expected);
+                }
+            }
+
+            let clone = r.clone();
+
+            let mut at = rng.gen_range(0..=s.len());
+
+            while !s.is_char_boundary(at) {
+                at -= 1;
+            }
+
+            let text = ["\n", "a\nb", "ä"][rng.gen_range(0..3)];
+
+            r.insert(at, text);
+            s.insert_str(at, text);
+
+            // The lines cached for the edited `Rope` aren't used for the
+            // clone, and vice versa.
+            assert_eq!(
+                cache.line_of_byte(&clone, clone.byte_len()),
+                line_starts.len() - 1
+            );
+        }
+    }
+}
+
+#[test]
+fn rope_line_cache_across_ropes() {
+    let foo = Rope::from("a\nbc\n");
+    let bar = Rope::from("ab\nc\n");
+
+    let mut cache = LineCache::new();
+
+    // The `Rope`s have never been edited and have the same length, but the
+    // lines cached for one aren't used for the other.
+    assert_eq!(cache.line_of_byte(&foo, 2), 1);
+    assert_eq!(cache.line_of_byte(&bar, 2), 0);
+    assert_eq!(cache.byte_of_line(&foo, 1), 2);
+    assert_eq!(cache.byte_of_line(&bar, 1), 3);
+}
+
+#[test]
+fn rope_is_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Rope>();
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, MEDIUM, SMALL, TINY};
+use crop::{Rope, RopeLoader};
+use rand::Rng;
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn rope_loader_random_steps() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", "🌎", TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let mut loader = RopeLoader::new(s.as_bytes());
+
+        let max_bytes = rng.gen_range(1..8192);
+
+        loop {
+            let is_done = loader.step(rng.gen_range(0..=max_bytes)).unwrap();
+
+            let rope = loader.rope();
+
+            assert!(loader.bytes_loaded() - rope.byte_len() < 4);
+            assert_eq!(*rope, s[..rope.byte_len()]);
+
+            #[cfg(feature = "line-metric")]
+            assert_eq!(loader.lines_loaded(), rope.line_len());
+
+            if is_done {
+                break;
+            }
+        }
+
+        assert!(loader.is_done());
+        assert_eq!(loader.bytes_loaded(), s.len());
+
+        let r = loader.into_rope();
+        assert_eq!(r, s);
+        r.assert_invariants();
+    }
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn rope_loader_finish() {
+    let mut loader = RopeLoader::new(MEDIUM.as_bytes());
+
+    loader.step(1000).unwrap();
+    assert!(!loader.is_done());
+
+    let r = loader.finish().unwrap();
+    assert_eq!(r, MEDIUM);
+    assert_eq!(r, Rope::from(MEDIUM));
+}
+
+#[test]
+fn rope_loader_invalid_utf8() {
+    let mut bytes = "Hello, 🌎!".as_bytes().to_vec();
+    bytes.insert(5, 0xff);
+
+    let mut loader = RopeLoader::new(&bytes[..]);
+
+    assert!(!loader.step(4).unwrap());
+
+    let err = loader.step(8).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().ends_with("at byte offset 5"));
+
+    assert!(loader.is_done());
+    assert_eq!(loader.rope(), "Hello");
+
+    // The incomplete globe at the end of the text is invalid too.
+    let bytes = &"Hello, 🌎".as_bytes()[..9];
+
+    let err = RopeLoader::new(bytes).finish().unwrap_err();
+    assert!(err.to_string().ends_with("at byte offset 7"));
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, MEDIUM, SMALL, TINY};
+use crop::{Rope, RopeRef};
+use rand::Rng;
+
+#[test]
+fn rope_ref_empty() {
+    let r = RopeRef::new("");
+
+    assert!(r.is_empty());
+    assert_eq!(r.byte_len(), 0);
+    assert_eq!(r.line_len(), 0);
+    assert_eq!(r.byte_of_line(0), 0);
+    assert_eq!(r.line_of_byte(0), 0);
+    assert_eq!(r.line_slice(..), "");
+}
+
+#[test]
+fn rope_ref_lines() {
+    for s in ["\n", "a\r\n", "\n\n\r\n", TINY, SMALL, MEDIUM, LARGE] {
+        let r = RopeRef::new(s);
+        let rope = Rope::from(s);
+
+        assert_eq!(r.line_len(), rope.line_len());
+
+        for line_offset in 0..=rope.line_len() {
+            assert_eq!(
+                r.byte_of_line(line_offset),
+                rope.byte_of_line(line_offset)
+            );
+        }
+
+        for (idx, line) in rope.lines().enumerate() {
+            assert_eq!(r.line(idx), line);
+        }
+
+        for (idx, line) in rope.raw_lines().enumerate() {
+            assert_eq!(r.line_slice(idx..idx + 1), line);
+        }
+    }
+}
+
+#[test]
+fn rope_ref_line_of_byte() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = RopeRef::new(s);
+        let rope = Rope::from(s);
+
+        for _ in 0..1000 {
+            let byte_offset = rng.gen_range(0..=s.len());
+
+            if s.is_char_boundary(byte_offset) {
+                assert_eq!(
+                    r.line_of_byte(byte_offset),
+                    rope.line_of_byte(byte_offset)
+                );
+            }
+        }
+
+        assert_eq!(r.line_of_byte(s.len()), rope.line_of_byte(s.len()));
+    }
+}
+
+#[test]
+fn rope_ref_random_slices() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = RopeRef::new(s);
+        let rope = Rope::from(s);
+
+        for _ in 0..100 {
+            let start = rng.gen_range(0..=r.line_len());
+            let end = rng.gen_range(start..=r.line_len());
+            assert_eq!(r.line_slice(start..end), rope.line_slice(start..end));
+
+            let start = rng.gen_range(0..=r.byte_len());
+            let end = rng.gen_range(start..=r.byte_len());
+
+            if s.is_char_boundary(start) && s.is_char_boundary(end) {
+                assert_eq!(r.byte_slice(start..end), &s[start..end]);
+            }
+        }
+    }
+}
+
+#[test]
+fn rope_ref_into_rope() {
+    for s in ["", TINY, LARGE, CURSED_LIPSUM] {
+        let rope = Rope::from(RopeRef::new(s));
+        rope.assert_invariants();
+        assert_eq!(rope, s);
+    }
+}
+
+#[test]
+#[should_panic]
+fn rope_ref_byte_slice_not_char_boundary() {
+    let r = RopeRef::new("🦀");
+    let _ = r.byte_slice(1..);
+}
+
+#[cfg(feature = "utf16-metric")]
+#[test]
+fn rope_ref_utf16() {
+    let mostly_ascii = format!("{}🦀\n{TINY}", "a".repeat(5000));
+
+    for s in ["", TINY, LARGE, CURSED_LIPSUM, "a𐐀b🦀\n", &mostly_ascii] {
+        let r = RopeRef::new(s);
+        let rope = Rope::from(s);
+
+        assert_eq!(r.utf16_len(), rope.utf16_len());
+
+        for (byte_offset, _) in s.char_indices() {
+            let utf16_offset = rope.utf16_code_unit_of_byte(byte_offset);
+            assert_eq!(r.utf16_code_unit_of_byte(byte_offset), utf16_offset);
+            assert_eq!(r.byte_of_utf16_code_unit(utf16_offset), byte_offset);
+        }
+
+        assert_eq!(r.byte_of_utf16_code_unit(r.utf16_len()), s.len());
+    }
+}
@@ -1,4 +1,4 @@
-use crop::Rope;
+use crop::{EditInfo, LineEnding, Rope};
 use rand::Rng;
 
 mod common;
@@ -182,6 +182,178 @@ fn insert_random() {
         }
     }
 }
+
+/// Replaces random ranges with, and appends, texts long enough to be spliced
+/// in as trees of their own.
+#[cfg_attr(miri, ignore)]
+#[test]
+fn replace_large_random() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", TINY, MEDIUM] {
+        let mut r = Rope::from(s);
+        let mut s = s.to_owned();
+
+        for _ in 0..10 {
+            let mut len = rng.gen_range(LARGE.len() / 2..=LARGE.len());
+
+            while !LARGE.is_char_boundary(len) {
+                len -= 1;
+            }
+
+            let text = &LARGE[..len];
+
+            if rng.gen_bool(0.25) {
+                r.push_str(text);
+                s.push_str(text);
+            } else {
+                let mut start = rng.gen_range(0..=r.byte_len());
+                let mut end = rng.gen_range(start..=r.byte_len());
+
+                while !s.is_char_boundary(start) {
+                    start -= 1;
+                }
+
+                while !s.is_char_boundary(end) {
+                    end -= 1;
+                }
+
+                r.replace(start..end, text);
+                s.replace_range(start..end, text);
+            }
+
+            r.assert_invariants();
+            assert_eq!(s, r);
+            assert_eq!(r.line_len(), Rope::from(&*s).line_len());
+        }
+    }
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn typing_random() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, SMALL, MEDIUM, LARGE] {
+        let mut r = Rope::from(s);
+        let mut s = s.to_owned();
+        let mut snapshots = Vec::new();
+
+        for _ in 0..50 {
+            let mut cursor = rng.gen_range(0..=r.byte_len());
+
+            while !s.is_char_boundary(cursor) {
+                cursor -= 1;
+            }
+
+            // Keep a clone around every once in a while so that some of the
+            // edits happen on shared nodes.
+            if rng.gen_bool(0.2) {
+                snapshots.push((r.clone(), s.clone()));
+            }
+
+            for _ in 0..rng.gen_range(1..200) {
+                if rng.gen_bool(0.2) && cursor > 0 {
+                    let len =
+                        s[..cursor].chars().next_back().unwrap().len_utf8();
+                    r.delete(cursor - len..cursor);
+                    s.replace_range(cursor - len..cursor, "");
+                    cursor -= len;
+                } else {
+                    let text =
+                        ["a", "\n", "\r\n", "ä", "🦀"][rng.gen_range(0..5)];
+                    r.insert(cursor, text);
+                    s.insert_str(cursor, text);
+                    cursor += text.len();
+                }
+            }
+
+            r.assert_invariants();
+            assert_eq!(s, r);
+        }
+
+        for (r, s) in snapshots {
+            r.assert_invariants();
+            assert_eq!(s, r);
+        }
+    }
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn overwrite_random() {
+    let mut rng = rand::thread_rng();
+
+    for s in [TINY, SMALL, MEDIUM, LARGE] {
+        let mut r = Rope::from(s);
+        let mut s = s.to_owned();
+
+        for _ in 0..100 {
+            let text = ["x", "\n", "ab\ncd", "ä", "🦀\r\n"]
+                [rng.gen_range(0..5)]
+            .repeat(rng.gen_range(0..500));
+
+            if text.len() > s.len() {
+                continue;
+            }
+
+            let mut at = rng.gen_range(0..=s.len() - text.len());
+
+            while !s.is_char_boundary(at)
+                || !s.is_char_boundary(at + text.len())
+            {
+                at = rng.gen_range(0..=s.len() - text.len());
+            }
+
+            r.overwrite(at, &text);
+            s.replace_range(at..at + text.len(), &text);
+
+            r.assert_invariants();
+            assert_eq!(s, r);
+        }
+
+        assert_eq!(r.line_len(), Rope::from(&*s).line_len());
+    }
+}
+
+#[test]
+#[should_panic]
+fn overwrite_out_of_bounds() {
+    let mut r = Rope::from("foo");
+    r.overwrite(1, "bar");
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn push_random() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", TINY, SMALL, MEDIUM] {
+        let mut r = Rope::from(s);
+        let mut s = s.to_owned();
+
+        for _ in 0..2000 {
+            let text = match rng.gen_range(0..4) {
+                0 => "\n",
+                1 => "log line ä\r\n",
+                2 => &MEDIUM[..rng.gen_range(0..MEDIUM.len() / 10)],
+                _ => "🦀",
+            };
+
+            if rng.gen_bool(0.5) {
+                r.push_str(text);
+            } else {
+                r.insert(r.byte_len(), text);
+            }
+
+            s.push_str(text);
+        }
+
+        r.assert_invariants();
+        assert_eq!(s, r);
+        assert_eq!(r.line_len(), Rope::from(&*s).line_len());
+    }
+}
 #[test]
 fn delete_1() {
     let mut r = Rope::from(TEXT);
@@ -294,6 +466,37 @@ fn delete_random() {
     }
 }
 
+/// Deletes ranges covering most of the rope, some of them from ropes sharing
+/// their nodes with a clone.
+#[cfg_attr(miri, ignore)]
+#[test]
+fn delete_large_random() {
+    let mut rng = rand::thread_rng();
+
+    let large = LARGE.repeat(4);
+
+    for _ in 0..20 {
+        let mut r = Rope::from(large.as_str());
+        let mut s = large.clone();
+
+        let clone = rng.gen_bool(0.5).then(|| r.clone());
+
+        let start = rng.gen_range(0..=1024);
+        let end = s.len() - rng.gen_range(0..=1024);
+
+        r.delete(start..end);
+        s.replace_range(start..end, "");
+
+        r.assert_invariants();
+        assert_eq!(s, r);
+
+        if let Some(clone) = clone {
+            clone.assert_invariants();
+            assert_eq!(large, clone);
+        }
+    }
+}
+
 #[test]
 fn replace_0() {
     let mut r = Rope::from("aaaa");
@@ -403,3 +606,644 @@ fn fix_crlf_4() {
 
     assert_eq!(r, "\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n");
 }
+
+#[test]
+fn revision_bumped_by_edits() {
+    let mut r = Rope::from(TEXT);
+    let mut last = r.revision();
+
+    r.insert(0, "a");
+    assert!(r.revision() > last);
+    last = r.revision();
+
+    r.delete(0..1);
+    assert!(r.revision() > last);
+    last = r.revision();
+
+    let clone = r.clone();
+    assert_eq!(clone.revision(), last);
+
+    r.replace(0..5, "b");
+    assert!(r.revision() > last);
+    assert_eq!(clone.revision(), last);
+}
+
+#[test]
+fn revision_unique_across_ropes() {
+    let r = Rope::from(TEXT);
+    let other = Rope::from(TEXT);
+    assert!(other.revision() > r.revision());
+
+    let mut edited = r.clone();
+    edited.insert(0, "a");
+    assert!(Rope::new().revision() > edited.revision());
+}
+
+#[test]
+fn revision_of_edited_clones() {
+    let mut r = Rope::from(TEXT);
+    let mut clone = r.clone();
+    let mut other_clone = r.clone();
+
+    r.insert(0, "a");
+    clone.insert(0, "a");
+    other_clone.insert(0, "a");
+
+    assert_ne!(r.revision(), clone.revision());
+    assert_ne!(r.revision(), other_clone.revision());
+    assert_ne!(clone.revision(), other_clone.revision());
+
+    // A clone of an edited clone gets its own id as well.
+    let mut clone_of_clone = clone.clone();
+    clone_of_clone.insert(0, "b");
+    clone.insert(0, "b");
+    assert_ne!(clone.revision(), clone_of_clone.revision());
+}
+
+#[test]
+fn apply_edits_matches_string() {
+    let mut rng = rand::thread_rng();
+
+    let mut r = Rope::from(MEDIUM);
+    let mut s = String::from(MEDIUM);
+
+    for _ in 0..20 {
+        let mut edits = Vec::new();
+        let mut offset = 0;
+
+        while offset < s.len() && edits.len() < 50 {
+            let start = offset + rng.gen_range(0..1000).min(s.len() - offset);
+            let end = start + rng.gen_range(0..10).min(s.len() - start);
+
+            if !(s.is_char_boundary(start) && s.is_char_boundary(end)) {
+                offset = end + 1;
+                continue;
+            }
+
+            edits.push((
+                start..end,
+                ["", "a", "bc\n", "ƒ"][rng.gen_range(0..4)],
+            ));
+            offset = end;
+        }
+
+        for (byte_range, text) in edits.iter().rev() {
+            s.replace_range(byte_range.clone(), text);
+        }
+
+        let delta = r.apply_edits(&edits);
+
+        r.assert_invariants();
+        assert_eq!(r, s);
+        assert_eq!(delta.len(), edits.len());
+    }
+}
+
+#[test]
+fn apply_edits_touching() {
+    let mut r = Rope::from("abc");
+
+    r.apply_edits(&[(0..1, "x"), (1..1, "y"), (1..1, "z"), (1..3, "")]);
+
+    assert_eq!(r, "xyz");
+}
+
+#[test]
+#[should_panic]
+fn apply_edits_overlapping() {
+    let mut r = Rope::from("Hello World");
+    r.apply_edits(&[(0..5, "a"), (4..6, "b")]);
+}
+
+#[test]
+fn edits_with_info() {
+    let mut r = Rope::from("foo\nbar\nbaz");
+
+    let region = r.insert_with_info(4, "\n\n");
+    assert_eq!(region.edit, EditInfo { byte_range: 4..4, inserted_len: 2 });
+    assert_eq!(region.old_lines, 1..2);
+    assert_eq!(region.new_lines, 1..4);
+
+    let region = r.delete_with_info(2..r.byte_len());
+    assert_eq!(r, "fo");
+    assert_eq!(region.old_lines, 0..5);
+    assert_eq!(region.new_lines, 0..1);
+
+    let region = r.replace_with_info(.., "a\nb\n");
+    assert_eq!(region.edit, EditInfo { byte_range: 0..2, inserted_len: 4 });
+    assert_eq!(region.old_lines, 0..1);
+    assert_eq!(region.new_lines, 0..3);
+}
+
+#[test]
+fn splice_returns_replaced() {
+    let mut rng = rand::thread_rng();
+
+    let mut r = Rope::from(LARGE);
+    let mut s = String::from(LARGE);
+
+    for _ in 0..50 {
+        let start = rng.gen_range(0..=s.len());
+        let end = start + rng.gen_range(0..10_000).min(s.len() - start);
+
+        if !(s.is_char_boundary(start) && s.is_char_boundary(end)) {
+            continue;
+        }
+
+        let expected = s[start..end].to_owned();
+
+        let replaced = if rng.gen_bool(0.5) {
+            s.replace_range(start..end, "");
+            r.remove(start..end)
+        } else {
+            s.replace_range(start..end, "ƒoo\n");
+            r.splice(start..end, "ƒoo\n")
+        };
+
+        replaced.assert_invariants();
+        r.assert_invariants();
+        assert_eq!(r, s);
+        assert_eq!(replaced, expected);
+    }
+}
+
+#[test]
+fn insert_slice_random() {
+    let mut rng = rand::thread_rng();
+
+    let sources = [TINY, SMALL, MEDIUM, LARGE].map(Rope::from);
+
+    let mut r = Rope::new();
+    let mut s = String::new();
+
+    for _ in 0..200 {
+        let source = &sources[rng.gen_range(0..sources.len())];
+
+        let start = rng.gen_range(0..=source.byte_len());
+        let end = rng.gen_range(start..=source.byte_len());
+
+        if !(source.is_char_boundary(start) && source.is_char_boundary(end)) {
+            continue;
+        }
+
+        let offset = rng.gen_range(0..=r.byte_len());
+
+        if !r.is_char_boundary(offset) {
+            continue;
+        }
+
+        let slice = source.byte_slice(start..end);
+
+        s.insert_str(offset, &slice.to_string());
+
+        if rng.gen_bool(0.5) {
+            r.insert_slice(offset, slice);
+        } else {
+            r.insert_rope(offset, &Rope::from(slice));
+        }
+
+        r.assert_invariants();
+        assert_eq!(r, s);
+
+        if r.byte_len() > 1 << 20 {
+            let len = r.byte_len() / 2;
+            let end = (len..).find(|&end| r.is_char_boundary(end)).unwrap();
+            r.delete(..end);
+            s.replace_range(..end, "");
+        }
+    }
+}
+
+#[test]
+fn insert_rope_notifies() {
+    let mut r = Rope::from("foo\nbar\n");
+    r.track_dirty_lines(true);
+
+    let revision = r.revision();
+    r.insert_rope(4, &Rope::from("baz\n"));
+
+    assert!(r.revision() > revision);
+    assert_eq!(r, "foo\nbaz\nbar\n");
+    assert_eq!(r.take_dirty_lines(), vec![1..3]);
+}
+
+#[test]
+#[should_panic]
+fn insert_slice_not_char_boundary() {
+    let mut r = Rope::from("ƒoo");
+    r.insert_rope(1, &Rope::from("bar"));
+}
+
+#[test]
+fn append_random() {
+    let mut rng = rand::thread_rng();
+
+    let mut r = Rope::new();
+    let mut s = String::new();
+
+    for _ in 0..100 {
+        let text = [TINY, SMALL, MEDIUM, LARGE, "", "\n"][rng.gen_range(0..6)];
+
+        let start = rng.gen_range(0..=text.len());
+        let end = rng.gen_range(start..=text.len());
+
+        if !(text.is_char_boundary(start) && text.is_char_boundary(end)) {
+            continue;
+        }
+
+        let other = Rope::from(&text[start..end]);
+        s.push_str(&text[start..end]);
+
+        match rng.gen_range(0..3) {
+            0 => r.append(other),
+            1 => r += other,
+            _ => r = r.clone() + other,
+        }
+
+        r.assert_invariants();
+        assert_eq!(r, s);
+
+        if r.byte_len() > 1 << 20 {
+            r = Rope::new();
+            s.clear();
+        }
+    }
+}
+
+#[test]
+fn append_prepends_deeper() {
+    let mut r = Rope::from("a");
+    r.append(Rope::from(LARGE));
+    r.assert_invariants();
+    assert_eq!(r, format!("a{LARGE}"));
+
+    let mut r = Rope::from(LARGE);
+    r.append(Rope::from(LARGE));
+    r.assert_invariants();
+    assert_eq!(r, LARGE.repeat(2));
+}
+
+#[test]
+fn remove_moves_text_between_ropes() {
+    let mut src = Rope::from("foo\nbar\nbaz\n");
+    let mut dst = Rope::from("qux\n");
+
+    let cut = src.remove(4..8);
+    dst.insert_rope(0, &cut);
+
+    assert_eq!(src, "foo\nbaz\n");
+    assert_eq!(dst, "bar\nqux\n");
+    src.assert_invariants();
+    dst.assert_invariants();
+}
+
+#[test]
+fn truncate_random() {
+    let mut rng = rand::thread_rng();
+
+    for text in [TINY, SMALL, MEDIUM, LARGE] {
+        let mut r = Rope::from(text);
+        let mut s = text.to_owned();
+
+        while !s.is_empty() {
+            let mut byte_offset = rng.gen_range(0..s.len());
+
+            while !s.is_char_boundary(byte_offset) {
+                byte_offset -= 1;
+            }
+
+            r.truncate(byte_offset);
+            s.truncate(byte_offset);
+
+            r.assert_invariants();
+            assert_eq!(r, s);
+        }
+    }
+}
+
+#[test]
+fn truncate_and_clear_notify() {
+    let mut r = Rope::from("foo\nbar\n");
+    let mark = r.add_mark(6, Default::default());
+
+    let revision = r.revision();
+    r.truncate(10);
+    assert_eq!(r.revision(), revision);
+
+    r.truncate(3);
+    assert!(r.revision() > revision);
+    assert_eq!(r.marks().get(mark), Some(3));
+
+    let revision = r.revision();
+    r.clear();
+    assert!(r.revision() > revision);
+    assert_eq!(r.marks().get(mark), Some(0));
+
+    let revision = r.revision();
+    r.clear();
+    assert_eq!(r.revision(), revision);
+
+    assert!(r.is_empty());
+    assert_eq!(r.line_len(), 0);
+    r.assert_invariants();
+}
+
+#[test]
+fn char_editing_random() {
+    let mut rng = rand::thread_rng();
+
+    let mut r = Rope::new();
+    let mut s = String::new();
+
+    for _ in 0..1000 {
+        let mut byte_offset = rng.gen_range(0..=s.len());
+
+        while !s.is_char_boundary(byte_offset) {
+            byte_offset -= 1;
+        }
+
+        match rng.gen_range(0..4) {
+            0 => {
+                let ch = ['a', '\n', 'ƒ', '🌎'][rng.gen_range(0..4)];
+                r.insert_char(byte_offset, ch);
+                s.insert(byte_offset, ch);
+            },
+            1 => {
+                r.push_char('\r');
+                s.push('\r');
+            },
+            2 => {
+                r.push_str("bär\n");
+                s.push_str("bär\n");
+            },
+            _ if byte_offset < s.len() => {
+                assert_eq!(r.remove_char(byte_offset), s.remove(byte_offset));
+            },
+            _ => {},
+        }
+
+        r.assert_invariants();
+        assert_eq!(r, s);
+    }
+}
+
+#[test]
+#[should_panic]
+fn remove_char_out_of_bounds() {
+    let mut r = Rope::from("foo");
+    r.remove_char(3);
+}
+
+#[test]
+#[should_panic]
+fn remove_char_not_char_boundary() {
+    let mut r = Rope::from("ƒoo");
+    r.remove_char(1);
+}
+
+#[test]
+fn repeat_random() {
+    let mut rng = rand::thread_rng();
+
+    for text in [TINY, SMALL, MEDIUM] {
+        let r = Rope::from(text);
+
+        for _ in 0..5 {
+            let n = rng.gen_range(0..100);
+
+            let repeated = r.repeat(n);
+            repeated.assert_invariants();
+            assert_eq!(repeated, text.repeat(n));
+        }
+    }
+}
+
+fn sorted_lines(text: &str) -> String {
+    let mut lines = text.lines().collect::<Vec<_>>();
+    lines.sort();
+    lines.join("\n") + if text.ends_with('\n') { "\n" } else { "" }
+}
+
+#[test]
+fn sort_lines_matches_str() {
+    for text in [TINY, SMALL, MEDIUM, "c\nb\na", "", "\n\n"] {
+        let mut r = Rope::from(text);
+        r.sort_lines(.., |a, b| a.chars().cmp(b.chars()));
+        r.assert_invariants();
+        assert_eq!(r, sorted_lines(text));
+    }
+}
+
+#[test]
+fn sort_lines_range() {
+    let mut r = Rope::from("d\nc\r\nb\na\n");
+    r.track_dirty_lines(true);
+
+    r.sort_lines(1..3, |a, b| a.chars().cmp(b.chars()));
+
+    assert_eq!(r, "d\nb\nc\r\na\n");
+    assert_eq!(r.take_dirty_lines(), vec![1..4]);
+
+    let mut r = Rope::from("b\na\r\nc");
+    r.sort_lines(.., |a, b| b.chars().cmp(a.chars()));
+    assert_eq!(r, "c\r\nb\na");
+}
+
+#[test]
+fn dedup_lines_matches_vec() {
+    let mut r = Rope::from("a\na\nb\nb\nb\na\nc\nc");
+    r.dedup_lines(..);
+    assert_eq!(r, "a\nb\na\nc");
+
+    let mut r = Rope::from("a\na\nb\nb\n");
+    r.dedup_lines(1..);
+    assert_eq!(r, "a\na\nb\n");
+
+    let revision = r.revision();
+    r.dedup_lines(..2);
+    assert_eq!(r, "a\nb\n");
+    assert!(r.revision() > revision);
+
+    let revision = r.revision();
+    r.dedup_lines(..);
+    assert_eq!(r.revision(), revision);
+}
+
+#[test]
+#[should_panic]
+fn sort_lines_out_of_bounds() {
+    let mut r = Rope::from("a\nb\n");
+    r.sort_lines(..3, |a, b| a.chars().cmp(b.chars()));
+}
+
+#[test]
+fn normalize_eols_random() {
+    let mut rng = rand::thread_rng();
+
+    let pieces = ["a", "🦀", "\r", "\n", "\r\n", "\n\r"];
+
+    for _ in 0..100 {
+        let s = (0..rng.gen_range(0..200))
+            .map(|_| pieces[rng.gen_range(0..pieces.len())])
+            .collect::<String>();
+
+        for line_ending in [LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr] {
+            let mut r = Rope::from(s.as_str());
+            r.normalize_eols(line_ending);
+            r.assert_invariants();
+
+            let expected = s
+                .replace("\r\n", "\n")
+                .replace('\r', "\n")
+                .replace('\n', line_ending.as_str());
+
+            assert_eq!(r, expected);
+        }
+    }
+}
+
+#[test]
+fn normalize_eols_random_large() {
+    let mut rng = rand::thread_rng();
+
+    let pieces = ["aaaaaaaa", "🦀", "\r", "\n", "\r\n", "\n\r"];
+
+    for _ in 0..20 {
+        let mut s = (0..rng.gen_range(0..5000))
+            .map(|_| pieces[rng.gen_range(0..pieces.len())])
+            .collect::<String>();
+
+        let mut r = Rope::from(s.as_str());
+
+        // Random insertions move the chunk boundaries around, splitting some
+        // CRLF sequences between two chunks.
+        for _ in 0..rng.gen_range(0..50) {
+            let mut offset = rng.gen_range(0..=s.len());
+            while !s.is_char_boundary(offset) {
+                offset -= 1;
+            }
+            let text = pieces[rng.gen_range(0..pieces.len())];
+            r.insert(offset, text);
+            s.insert_str(offset, text);
+        }
+
+        for line_ending in [LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr] {
+            let mut r = r.clone();
+            r.normalize_eols(line_ending);
+            r.assert_invariants();
+
+            let expected = s
+                .replace("\r\n", "\n")
+                .replace('\r', "\n")
+                .replace('\n', line_ending.as_str());
+
+            assert_eq!(r, expected);
+        }
+    }
+}
+
+#[test]
+fn normalize_eols_keeps_unchanged_chunks() {
+    let s = "a".repeat(10_000) + "\r\n" + &"b".repeat(10_000);
+    let mut r = Rope::from(s.as_str());
+    let chunks = r.chunks().map(str::as_ptr).collect::<Vec<_>>();
+
+    r.normalize_eols(LineEnding::Lf);
+    r.assert_invariants();
+    assert_eq!(r, s.replace("\r\n", "\n"));
+
+    let first = r.chunks().next().unwrap().as_ptr();
+    let last = r.chunks().next_back().unwrap().as_ptr();
+    assert_eq!(first, chunks[0]);
+    assert_eq!(last, *chunks.last().unwrap());
+}
+
+#[test]
+fn normalize_eols_already_normalized() {
+    let mut r = Rope::from("foo\r\nbar\r\n");
+    let revision = r.revision();
+    r.normalize_eols(LineEnding::CrLf);
+    assert_eq!(r.revision(), revision);
+
+    let mark = r.add_mark(8, Default::default());
+    r.normalize_eols(LineEnding::Lf);
+    assert_eq!(r, "foo\nbar\n");
+    assert_eq!(r.marks().get(mark), Some(7));
+}
+
+#[test]
+fn detect_line_ending() {
+    assert_eq!(Rope::new().detect_line_ending(), LineEnding::Lf);
+    assert_eq!(
+        Rope::from("a\r\nb\nc\r\n").detect_line_ending(),
+        LineEnding::CrLf
+    );
+    assert_eq!(
+        Rope::from("a\r\nb\nc\rd\r").detect_line_ending(),
+        LineEnding::Cr
+    );
+    assert_eq!(Rope::from("a\r\nb\n").detect_line_ending(), LineEnding::Lf);
+
+    let s = "foo\r\n".repeat(100) + &"bar\n".repeat(99);
+    let mut r = Rope::from(s.as_str());
+    assert_eq!(r.detect_line_ending(), LineEnding::CrLf);
+
+    r.normalize_eols(LineEnding::Cr);
+    assert_eq!(r.detect_line_ending(), LineEnding::Cr);
+}
+
+/// Regression test for a panic when converting a slice into a `Rope` if
+/// balancing the left side of the slice's tree left the root with a single
+/// leaf.
+#[test]
+fn splice_returns_replaced_collapsed_root() {
+    let edits = [
+        (505322..508883, None),
+        (120287..122980, Some("ƒoo\n")),
+        (275833..276950, None),
+        (730946..739652, None),
+        (341668..346025, Some("ƒoo\n")),
+        (239145..246739, None),
+        (237567..244141, None),
+        (319862..320357, None),
+        (465941..472794, None),
+        (293834..303786, Some("ƒoo\n")),
+        (495638..502317, Some("ƒoo\n")),
+        (690452..695625, Some("ƒoo\n")),
+        (475930..483108, None),
+        (522113..524574, Some("ƒoo\n")),
+        (673614..676330, Some("ƒoo\n")),
+        (447846..454067, Some("ƒoo\n")),
+        (257978..267235, None),
+        (648028..652913, Some("ƒoo\n")),
+        (402253..409480, Some("ƒoo\n")),
+        (533311..538137, Some("ƒoo\n")),
+        (532953..538607, Some("ƒoo\n")),
+        (705605..710182, None),
+        (644615..652257, None),
+        (697220..698044, None),
+    ];
+
+    let mut r = Rope::from(LARGE);
+    let mut s = String::from(LARGE);
+
+    for (range, text) in edits {
+        let expected = s[range.clone()].to_owned();
+
+        let replaced = match text {
+            Some(text) => {
+                s.replace_range(range.clone(), text);
+                r.splice(range, text)
+            },
+            None => {
+                s.replace_range(range.clone(), "");
+                r.remove(range)
+            },
+        };
+
+        replaced.assert_invariants();
+        r.assert_invariants();
+        assert_eq!(r, s);
+        assert_eq!(replaced, expected);
+    }
+}
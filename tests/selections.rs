@@ -0,0 +1,78 @@
+use crop::selections::Selections;
+use crop::{Delta, EditInfo, Rope};
+
+#[test]
+fn selections_normalize_merges_overlaps() {
+    let mut s = Selections::new(10..10);
+    s.push(0..4);
+    s.push(3..6);
+    s.push(6..8);
+    s.push(8..8);
+    s.push(10..10);
+    s.set_primary(2);
+
+    s.normalize();
+
+    assert_eq!(s.ranges(), [0..6, 6..8, 10..10]);
+    assert_eq!(s.primary(), 0..6);
+}
+
+#[test]
+fn selections_remove_keeps_primary() {
+    let mut s = Selections::new(0..1);
+    s.push(2..3);
+    s.push(4..5);
+    s.set_primary(2);
+
+    assert_eq!(s.remove(0), 0..1);
+    assert_eq!(s.primary(), 4..5);
+
+    assert_eq!(s.remove(1), 4..5);
+    assert_eq!(s.primary(), 2..3);
+}
+
+#[test]
+#[should_panic]
+fn selections_remove_last() {
+    let mut s = Selections::new(0..1);
+    s.remove(0);
+}
+
+#[test]
+fn selections_transform_through_subscription() {
+    use std::sync::{Arc, Mutex};
+
+    let mut r = Rope::from("foo bar baz");
+
+    let mut s = Selections::new(0..0);
+    s.push(4..7);
+    s.push(8..8);
+
+    let delta = Arc::new(Mutex::new(Delta::new()));
+
+    let recorded = Arc::clone(&delta);
+    r.subscribe(move |edit| recorded.lock().unwrap().push(edit.clone()));
+
+    // Type at every cursor, starting from the last one.
+    r.insert(8, "+");
+    r.insert(0, "+");
+    r.replace(5..8, "qux");
+
+    s.transform(&delta.lock().unwrap());
+
+    assert_eq!(r, "+foo qux +baz");
+    assert_eq!(s.ranges(), [1..1, 8..8, 10..10]);
+}
+
+#[test]
+fn selections_transform_merges_collapsed() {
+    let mut s = Selections::new(2..4);
+    s.push(6..8);
+    s.set_primary(1);
+
+    s.transform(&Delta::from(EditInfo { byte_range: 2..8, inserted_len: 1 }));
+
+    assert_eq!(s.len(), 1);
+    assert_eq!(s.primary(), 3..3);
+    assert_eq!(s.primary_index(), 0);
+}
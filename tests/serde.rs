@@ -0,0 +1,109 @@
+mod common;
+
+#[cfg(feature = "serde")]
+mod tests {
+    use crop::{FrozenRope, Rope};
+    use rand::Rng;
+
+    use crate::common::{CURSED_LIPSUM, LARGE, SMALL, TINY};
+
+    #[test]
+    fn serde_roundtrip() {
+        for s in
+            ["", "a", "\"foo\"\n\tbar\\", TINY, SMALL, LARGE, CURSED_LIPSUM]
+        {
+            let r = Rope::from(s);
+
+            let json = serde_json::to_string(&r).unwrap();
+            assert_eq!(json, serde_json::to_string(s).unwrap());
+
+            let de: Rope = serde_json::from_str(&json).unwrap();
+            de.assert_invariants();
+            assert_eq!(de, s);
+        }
+    }
+
+    #[test]
+    fn serde_frozen_rope_roundtrip() {
+        for s in ["", "\"foo\"\n\tbar\\", LARGE, CURSED_LIPSUM] {
+            let frozen = FrozenRope::from(s);
+
+            let json = serde_json::to_string(&frozen).unwrap();
+            assert_eq!(json, serde_json::to_string(s).unwrap());
+
+            let de: FrozenRope = serde_json::from_str(&json).unwrap();
+            assert_eq!(de, frozen);
+            assert_eq!(de.as_rope_ref().line_len(), Rope::from(s).line_len());
+        }
+    }
+
+    #[test]
+    fn serde_slice() {
+        let r = Rope::from(LARGE);
+        let s = r.byte_slice(100..LARGE.len() - 100);
+
+        assert_eq!(
+            serde_json::to_string(&s).unwrap(),
+            serde_json::to_string(&LARGE[100..LARGE.len() - 100]).unwrap()
+        );
+    }
+
+    fn to_chunked_json(r: &Rope) -> String {
+        let mut json = Vec::new();
+        crop::chunked::serialize(
+            r,
+            &mut serde_json::Serializer::new(&mut json),
+        )
+        .unwrap();
+        String::from_utf8(json).unwrap()
+    }
+
+    fn from_chunked_json(json: &str) -> serde_json::Result<Rope> {
+        crop::chunked::deserialize(&mut serde_json::Deserializer::from_str(
+            json,
+        ))
+    }
+
+    #[test]
+    fn serde_chunked_roundtrip() {
+        let mut rng = rand::thread_rng();
+
+        for s in ["", "a", TINY, SMALL, LARGE, CURSED_LIPSUM] {
+            let mut r = Rope::from(s);
+
+            for _ in 0..10 {
+                let json = to_chunked_json(&r);
+
+                let de = from_chunked_json(&json).unwrap();
+                de.assert_invariants();
+                assert_eq!(de, r);
+                assert_eq!(to_chunked_json(&de), json);
+
+                let offset = rng.gen_range(0..=r.byte_len());
+                if r.is_char_boundary(offset) {
+                    r.insert(offset, "foo\n\"\r\n");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn serde_chunked_invalid() {
+        assert!(from_chunked_json("\"foo\"").is_err());
+
+        let too_long = "a".repeat(1 << 16);
+        assert!(from_chunked_json(&format!("[\"{too_long}\"]")).is_err());
+
+        let r = Rope::from(LARGE);
+        let mut chunks = r.chunks().map(str::to_owned).collect::<Vec<_>>();
+        chunks.insert(1, String::new());
+        assert!(from_chunked_json(&serde_json::to_string(&chunks).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn serde_invalid() {
+        assert!(serde_json::from_str::<Rope>("[\"foo\"]").is_err());
+        assert!(serde_json::from_str::<Rope>("42").is_err());
+    }
+}
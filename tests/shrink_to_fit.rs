@@ -0,0 +1,63 @@
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, TINY};
+use crop::Rope;
+use rand::Rng;
+
+#[test]
+fn shrink_to_fit_large() {
+    let mut r = Rope::from(LARGE);
+    let snapshot = r.clone();
+
+    r.shrink_to_fit();
+    r.assert_invariants();
+    assert_eq!(r, LARGE);
+    assert_eq!(snapshot, LARGE);
+    assert_eq!(r.line_len(), snapshot.line_len());
+
+    // Shrinking again doesn't change anything.
+    r.shrink_to_fit();
+    r.assert_invariants();
+    assert_eq!(r, LARGE);
+}
+
+#[test]
+fn shrink_to_fit_edits() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", TINY, LARGE, CURSED_LIPSUM] {
+        let mut r = Rope::from(s);
+        let mut string = s.to_owned();
+
+        for _ in 0..20 {
+            r.shrink_to_fit();
+
+            let start = rng.gen_range(0..=r.byte_len());
+            let end = rng.gen_range(start..=r.byte_len());
+
+            if r.is_char_boundary(start) && r.is_char_boundary(end) {
+                r.replace(start..end, "bar\r\n🦀");
+                string.replace_range(start..end, "bar\r\n🦀");
+            }
+
+            r.assert_invariants();
+            assert_eq!(r, string);
+            assert_eq!(r.line_len(), Rope::from(&*string).line_len());
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn shrink_to_fit_then_compress() {
+    let mut r = Rope::from(LARGE);
+
+    r.shrink_to_fit();
+    r.compress();
+    r.shrink_to_fit();
+    r.assert_invariants();
+    assert_eq!(r, LARGE);
+
+    r.insert(0, "foo\n");
+    assert_eq!(r.line(0), "foo");
+}
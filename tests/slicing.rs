@@ -220,3 +220,187 @@ fn rope_from_slice() {
         }
     }
 }
+
+#[test]
+fn split_off_random() {
+    let mut rng = rand::thread_rng();
+
+    for text in [TINY, SMALL, MEDIUM, LARGE] {
+        for _ in 0..20 {
+            let offset = rng.gen_range(0..=text.len());
+
+            if !text.is_char_boundary(offset) {
+                continue;
+            }
+
+            let mut r = Rope::from(text);
+            let rest = r.split_off(offset);
+
+            r.assert_invariants();
+            rest.assert_invariants();
+            assert_eq!(r, text[..offset]);
+            assert_eq!(rest, text[offset..]);
+
+            r.append(rest);
+            r.assert_invariants();
+            assert_eq!(r, text);
+        }
+    }
+}
+
+#[test]
+fn split_off_moves_marks() {
+    let mut r = Rope::from("foo\nbar\n");
+    let mark = r.add_mark(6, Default::default());
+
+    let rest = r.split_off(4);
+
+    assert_eq!(r, "foo\n");
+    assert_eq!(rest, "bar\n");
+    assert_eq!(r.marks().get(mark), Some(4));
+    assert!(rest.marks().is_empty());
+}
+
+#[test]
+fn split_at_line_random() {
+    let mut rng = rand::thread_rng();
+
+    for text in [TINY, SMALL, MEDIUM, LARGE] {
+        let r = Rope::from(text);
+
+        for _ in 0..20 {
+            let line_offset = rng.gen_range(0..=r.line_len());
+
+            let (before, after) = r.split_at_line(line_offset);
+
+            before.assert_invariants();
+            after.assert_invariants();
+
+            let byte_offset = r.byte_of_line(line_offset);
+            assert_eq!(before, r.byte_slice(..byte_offset));
+            assert_eq!(after, r.byte_slice(byte_offset..));
+            assert_eq!(before.line_len(), line_offset);
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn split_at_line_out_of_bounds() {
+    let r = Rope::from("foo\nbar");
+    let _ = r.split_at_line(3);
+}
+
+#[test]
+fn split_into_random() {
+    let mut rng = rand::thread_rng();
+
+    for text in [TINY, SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+        let r = Rope::from(text);
+
+        for _ in 0..10 {
+            let n = rng.gen_range(1..=64);
+
+            let parts = r.split_into(n);
+            assert_eq!(parts.len(), n);
+
+            let mut joined = Rope::new();
+
+            for part in parts {
+                part.assert_invariants();
+                assert!(part.byte_len() <= text.len() / n + 4);
+                joined.append(part);
+            }
+
+            joined.assert_invariants();
+            assert_eq!(joined, text);
+        }
+    }
+}
+
+#[test]
+fn split_into_more_parts_than_bytes() {
+    let r = Rope::from("ab");
+
+    let parts = r.split_into(4);
+
+    assert_eq!(parts, ["", "a", "", "b"]);
+}
+
+#[test]
+fn trim_random() {
+    let mut rng = rand::thread_rng();
+
+    for text in [TINY, SMALL, MEDIUM, CURSED_LIPSUM, " \t\n\u{3000}", ""] {
+        let r = Rope::from(text);
+
+        for _ in 0..50 {
+            let mut start = rng.gen_range(0..=text.len());
+            while !text.is_char_boundary(start) {
+                start -= 1;
+            }
+
+            let mut end = rng.gen_range(start..=text.len());
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let s = r.byte_slice(start..end);
+            let str_slice = &text[start..end];
+
+            assert_eq!(s.trim(), str_slice.trim());
+            assert_eq!(s.trim_start(), str_slice.trim_start());
+            assert_eq!(s.trim_end(), str_slice.trim_end());
+
+            s.trim().assert_invariants();
+        }
+    }
+}
+
+#[test]
+fn strip_prefix_suffix_random() {
+    let mut rng = rand::thread_rng();
+
+    for text in [TINY, SMALL, MEDIUM, CURSED_LIPSUM] {
+        let r = Rope::from(text);
+
+        for _ in 0..50 {
+            let mut start = rng.gen_range(0..=text.len());
+            while !text.is_char_boundary(start) {
+                start -= 1;
+            }
+
+            let mut end = rng.gen_range(start..=text.len());
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let s = r.byte_slice(start..end);
+            let str_slice = &text[start..end];
+
+            let mut mid = rng.gen_range(0..=str_slice.len());
+            while !str_slice.is_char_boundary(mid) {
+                mid -= 1;
+            }
+
+            let (prefix, suffix) = str_slice.split_at(mid);
+
+            assert_eq!(s.strip_prefix(prefix).unwrap(), suffix);
+            assert_eq!(s.strip_suffix(suffix).unwrap(), prefix);
+
+            let wrong = format!("{prefix}\u{FFFF}");
+            assert_eq!(s.strip_prefix(&wrong), None);
+            assert_eq!(s.strip_suffix(&wrong), None);
+        }
+    }
+}
+
+#[test]
+fn trim_matches() {
+    let r = Rope::from("xxfooxbarxx");
+    let s = r.byte_slice(..);
+
+    assert_eq!(s.trim_matches('x'), "fooxbar");
+    assert_eq!(s.trim_matches('y'), "xxfooxbarxx");
+    assert_eq!(r.byte_slice(..2).trim_matches('x'), "");
+}
@@ -0,0 +1,128 @@
+use crop::Rope;
+use rand::{thread_rng, Rng};
+
+mod common;
+
+use common::{LARGE, MEDIUM, TEXT};
+
+/// Checks that the parts of `new` not covered by `changes` appear in `old` in
+/// the same order.
+fn assert_changes_cover_edits(
+    old: &Rope,
+    new: &Rope,
+    changes: &[std::ops::Range<usize>],
+) {
+    let old = old.to_string();
+    let new = new.to_string();
+
+    let mut unchanged = Vec::new();
+    let mut pos = 0;
+    for change in changes {
+        assert!(change.start >= pos, "changes must be sorted: {changes:?}");
+        assert!(change.end <= new.len());
+        unchanged.push(&new[pos..change.start]);
+        pos = change.end;
+    }
+    unchanged.push(&new[pos..]);
+
+    if changes.is_empty() {
+        assert_eq!(old, new);
+        return;
+    }
+
+    assert!(old.starts_with(unchanged[0]));
+    assert!(old.ends_with(unchanged[unchanged.len() - 1]));
+
+    let mut search_from = unchanged[0].len();
+    for segment in &unchanged[1..unchanged.len() - 1] {
+        let idx = old[search_from..].find(segment).unwrap();
+        search_from += idx + segment.len();
+    }
+}
+
+#[test]
+fn changes_since_no_edits() {
+    let r = Rope::from(MEDIUM);
+    let snapshot = r.snapshot();
+    assert_eq!(r.changes_since(&snapshot).count(), 0);
+}
+
+#[test]
+fn changes_since_single_edit() {
+    let mut r = Rope::from(LARGE);
+    let snapshot = r.snapshot();
+
+    r.replace(1000..1010, "hello");
+
+    let mut changes = r.changes_since(&snapshot);
+    assert_eq!(changes.next(), Some(1000..1005));
+    assert_eq!(changes.next(), None);
+
+    r.delete(5000..5100);
+
+    assert_eq!(
+        r.changes_since(&snapshot).collect::<Vec<_>>(),
+        [1000..1005, 5000..5000]
+    );
+}
+
+#[test]
+fn changes_since_reverted_edit() {
+    let mut r = Rope::from(MEDIUM);
+    let snapshot = r.snapshot();
+
+    r.insert(100, "a");
+    r.delete(100..101);
+
+    assert_eq!(r.changes_since(&snapshot).count(), 0);
+}
+
+#[test]
+fn changes_since_unrelated_rope() {
+    let r = Rope::from(TEXT);
+    let snapshot = Rope::from("Hello there!").snapshot();
+    let mut changes = r.changes_since(&snapshot);
+    assert_eq!(changes.next(), Some(12..r.byte_len()));
+    assert_eq!(changes.next(), None);
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn changes_since_random() {
+    let mut rng = thread_rng();
+
+    for _ in 0..20 {
+        let mut r = Rope::from(MEDIUM);
+        let snapshot = r.snapshot();
+
+        for _ in 0..rng.gen_range(0..10) {
+            let start = rng.gen_range(0..=r.byte_len());
+            let end = rng.gen_range(start..=r.byte_len().min(start + 50));
+            let text = ["", "a", "foo\n", "\r\n"][rng.gen_range(0..4)];
+            r.replace(start..end, text);
+        }
+
+        let changes = r.changes_since(&snapshot).collect::<Vec<_>>();
+        assert_changes_cover_edits(&snapshot, &r, &changes);
+    }
+}
+
+#[cfg(feature = "node-hashes")]
+#[test]
+fn changes_since_unshared_ropes_by_content() {
+    let old = Rope::from(LARGE);
+
+    // Same content, but none of the nodes are shared.
+    let r = Rope::from(LARGE);
+    assert_eq!(r.changes_since(&old.snapshot()).count(), 0);
+
+    // A single byte differs, so the subtrees containing it must not be
+    // reported as shared even though the rest of the tree is.
+    let mut string = LARGE.to_owned();
+    string.replace_range(5000..5001, "#");
+    let r = Rope::from(string);
+
+    let changes = r.changes_since(&old.snapshot()).collect::<Vec<_>>();
+    assert!(changes.iter().any(|change| change.contains(&5000)));
+    assert_changes_cover_edits(&old, &r, &changes);
+}
@@ -0,0 +1,124 @@
+use std::io::{Cursor, ErrorKind};
+
+use crop::store::Store;
+use crop::Rope;
+use rand::Rng;
+
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, SMALL, TINY};
+
+type MemStore = Store<Cursor<Vec<u8>>>;
+
+fn file_len(store: &MemStore) -> usize {
+    store.get_ref().get_ref().len()
+}
+
+#[test]
+fn store_roundtrip() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", "a", TINY, SMALL, LARGE, CURSED_LIPSUM] {
+        let mut store = Store::create(Cursor::new(Vec::new())).unwrap();
+        let mut r = Rope::from(s);
+        let mut versions = Vec::new();
+
+        for _ in 0..10 {
+            assert_eq!(store.save(&r).unwrap(), versions.len());
+            versions.push(r.clone());
+
+            let offset = rng.gen_range(0..=r.byte_len());
+            if r.is_char_boundary(offset) {
+                r.insert(offset, "foo\r\n🦀");
+            }
+        }
+
+        let mut store = Store::open(store.into_inner()).unwrap();
+        assert_eq!(store.len(), versions.len());
+
+        for (idx, version) in versions.iter().enumerate().rev() {
+            let loaded = store.load(idx).unwrap();
+            loaded.assert_invariants();
+            assert_eq!(loaded, *version);
+        }
+    }
+}
+
+#[test]
+fn store_shares_unchanged_subtrees() {
+    let mut store = Store::create(Cursor::new(Vec::new())).unwrap();
+
+    let mut r = Rope::from(LARGE);
+    store.save(&r).unwrap();
+    let first = file_len(&store);
+    assert!(first > LARGE.len());
+
+    r.insert(LARGE.len() / 2, "a");
+    store.save(&r).unwrap();
+    assert!(file_len(&store) - first < LARGE.len() / 10);
+
+    // Saving a loaded version after editing it also only writes the nodes
+    // that changed.
+    let mut store = Store::open(store.into_inner()).unwrap();
+    let mut r = store.load(0).unwrap();
+    let before = file_len(&store);
+
+    r.delete(..10);
+    store.save(&r).unwrap();
+    assert!(file_len(&store) - before < LARGE.len() / 10);
+
+    assert_eq!(store.load(2).unwrap(), LARGE[10..]);
+}
+
+#[test]
+fn store_ignores_incomplete_version() {
+    let mut store = Store::create(Cursor::new(Vec::new())).unwrap();
+
+    store.save(&Rope::from(SMALL)).unwrap();
+    let len = file_len(&store);
+    store.save(&Rope::from(LARGE)).unwrap();
+
+    let mut bytes = store.into_inner().into_inner();
+    bytes.truncate(bytes.len() - 3);
+
+    let mut store = Store::open(Cursor::new(bytes)).unwrap();
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.load(0).unwrap(), SMALL);
+
+    store.save(&Rope::from(TINY)).unwrap();
+
+    let mut bytes = store.into_inner().into_inner();
+    bytes.truncate(len + 1);
+
+    let mut store = Store::open(Cursor::new(bytes)).unwrap();
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.load(0).unwrap(), SMALL);
+}
+
+#[test]
+fn store_corrupted() {
+    assert_eq!(
+        Store::open(Cursor::new(b"not a crop store".to_vec()))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidData
+    );
+
+    let mut store = Store::create(Cursor::new(Vec::new())).unwrap();
+    store.save(&Rope::from(LARGE)).unwrap();
+
+    let mut bytes = store.into_inner().into_inner();
+    bytes[20] ^= 1;
+
+    let mut store = Store::open(Cursor::new(bytes)).unwrap();
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.load(0).unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+#[should_panic]
+fn store_load_out_of_bounds() {
+    let mut store = Store::create(Cursor::new(Vec::new())).unwrap();
+    store.save(&Rope::new()).unwrap();
+    let _ = store.load(1);
+}
@@ -0,0 +1,116 @@
+use std::sync::{Arc, Mutex};
+
+use crop::{EditInfo, Rope};
+use rand::{thread_rng, Rng};
+
+mod common;
+
+use common::MEDIUM;
+
+fn record(r: &mut Rope) -> Arc<Mutex<Vec<EditInfo>>> {
+    let edits = Arc::new(Mutex::new(Vec::new()));
+    let cloned = Arc::clone(&edits);
+    r.subscribe(move |edit| cloned.lock().unwrap().push(edit.clone()));
+    edits
+}
+
+#[test]
+fn subscribe_insert_delete_replace() {
+    let mut r = Rope::from("Hello World");
+    let edits = record(&mut r);
+
+    r.insert(5, ",");
+    r.delete(0..7);
+    r.replace(0..5, "Earth");
+
+    assert_eq!(
+        *edits.lock().unwrap(),
+        [
+            EditInfo { byte_range: 5..5, inserted_len: 1 },
+            EditInfo { byte_range: 0..7, inserted_len: 0 },
+            EditInfo { byte_range: 0..5, inserted_len: 5 },
+        ]
+    );
+}
+
+#[test]
+fn subscribe_apply_edits() {
+    let mut r = Rope::from("foo\nbar\nbaz\n");
+    let edits = record(&mut r);
+
+    let delta = r.apply_edits(&[(0..0, "// "), (4..7, "qux"), (8..8, "// ")]);
+
+    assert_eq!(r, "// foo\nqux\n// baz\n");
+    assert_eq!(
+        *edits.lock().unwrap(),
+        delta.iter().cloned().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn subscribe_multiple_and_unsubscribe() {
+    let mut r = Rope::from("foo");
+
+    let first = record(&mut r);
+    let second = record(&mut r);
+
+    let count = Arc::new(Mutex::new(0));
+    let id = r.subscribe({
+        let count = Arc::clone(&count);
+        move |_| *count.lock().unwrap() += 1
+    });
+
+    r.insert(3, "bar");
+    assert!(r.unsubscribe(id));
+    assert!(!r.unsubscribe(id));
+    r.insert(6, "baz");
+
+    assert_eq!(*count.lock().unwrap(), 1);
+    assert_eq!(first.lock().unwrap().len(), 2);
+    assert_eq!(*first.lock().unwrap(), *second.lock().unwrap());
+}
+
+#[test]
+fn subscribe_not_inherited_by_clones() {
+    let mut r = Rope::from("foo");
+    let edits = record(&mut r);
+
+    let mut clone = r.clone();
+    clone.insert(0, "bar");
+    assert!(edits.lock().unwrap().is_empty());
+
+    r.insert(0, "baz");
+    assert_eq!(edits.lock().unwrap().len(), 1);
+}
+
+/// Applies the edits reported to a subscriber to a `String` and checks that
+/// it ends up equal to the `Rope`.
+#[cfg_attr(miri, ignore)]
+#[test]
+fn subscribe_random_replay() {
+    let mut rng = thread_rng();
+
+    let mut r = Rope::from(MEDIUM);
+    let edits = record(&mut r);
+    let mut inserted = Vec::new();
+
+    for _ in 0..100 {
+        let start = rng.gen_range(0..=r.byte_len());
+        let end = rng.gen_range(start..=r.byte_len().min(start + 50));
+        let text = "🐸".repeat(rng.gen_range(0..4));
+
+        if r.is_char_boundary(start) && r.is_char_boundary(end) {
+            r.replace(start..end, &text);
+            inserted.push(text);
+        }
+    }
+
+    let mut s = MEDIUM.to_owned();
+
+    for (edit, text) in edits.lock().unwrap().iter().zip(&inserted) {
+        assert_eq!(edit.inserted_len, text.len());
+        s.replace_range(edit.byte_range.clone(), text);
+    }
+
+    assert_eq!(r, s);
+}
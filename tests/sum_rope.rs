@@ -0,0 +1,220 @@
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+use crop::sum_rope::SumRope;
+use crop::tree::{Metric, Summarize};
+use rand::{thread_rng, Rng};
+
+#[derive(Clone, Debug, PartialEq)]
+struct Span {
+    len: usize,
+    bold: bool,
+}
+
+impl Summarize for Span {
+    type Summary = usize;
+
+    fn summarize(&self) -> usize {
+        self.len
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Len(usize);
+
+impl Add for Len {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl Sub for Len {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl AddAssign for Len {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0
+    }
+}
+
+impl SubAssign for Len {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 -= other.0
+    }
+}
+
+impl Metric<usize> for Len {
+    fn zero() -> Self {
+        Self(0)
+    }
+
+    fn one() -> Self {
+        Self(1)
+    }
+
+    fn measure(len: &usize) -> Self {
+        Self(*len)
+    }
+}
+
+fn random_span(rng: &mut impl Rng) -> Span {
+    Span { len: rng.gen_range(0..5), bold: rng.gen() }
+}
+
+#[test]
+fn sum_rope_empty() {
+    let spans = SumRope::<Span>::new();
+    assert!(spans.is_empty());
+    assert_eq!(*spans.summary(), 0);
+    assert_eq!(spans.get(0), None);
+    assert_eq!(spans.iter().count(), 0);
+    assert_eq!(spans.slice(..).len(), 0);
+    assert_eq!(spans.index_of_measure(Len(0)), 0);
+    assert_eq!(spans.measure_of_index::<Len>(0), Len(0));
+    spans.assert_invariants();
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn sum_rope_random_edits() {
+    let mut rng = thread_rng();
+
+    let mut spans = SumRope::new();
+    let mut vec = Vec::new();
+
+    for _ in 0..2000 {
+        let start = rng.gen_range(0..=vec.len());
+
+        match rng.gen_range(0..4) {
+            0 => {
+                let span = random_span(&mut rng);
+                spans.insert(start, span.clone());
+                vec.insert(start, span);
+            },
+
+            1 if start < vec.len() => {
+                assert_eq!(spans.remove(start), vec.remove(start));
+            },
+
+            2 => {
+                let end = rng.gen_range(start..=vec.len().min(start + 100));
+
+                let inserted = (0..rng.gen_range(0..300))
+                    .map(|_| random_span(&mut rng))
+                    .collect::<Vec<_>>();
+
+                spans.splice(start..end, inserted.clone());
+                vec.splice(start..end, inserted);
+            },
+
+            _ => {
+                let span = random_span(&mut rng);
+                spans.push(span.clone());
+                vec.push(span);
+            },
+        }
+
+        spans.assert_invariants();
+
+        assert_eq!(spans.len(), vec.len());
+        assert_eq!(*spans.summary(), vec.iter().map(|s| s.len).sum());
+    }
+
+    assert!(spans.iter().eq(vec.iter()));
+    assert!(spans.iter().rev().eq(vec.iter().rev()));
+
+    for (idx, span) in vec.iter().enumerate() {
+        assert_eq!(spans.get(idx), Some(span));
+    }
+
+    assert_eq!(spans.get(vec.len()), None);
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn sum_rope_measures() {
+    let mut rng = thread_rng();
+
+    let vec = (0..5000).map(|_| random_span(&mut rng)).collect::<Vec<_>>();
+
+    let spans = vec.iter().cloned().collect::<SumRope<_>>();
+
+    spans.assert_invariants();
+
+    let total = vec.iter().map(|s| s.len).sum::<usize>();
+
+    assert_eq!(spans.measure::<Len>(), Len(total));
+
+    let mut offset = 0;
+
+    for (idx, span) in vec.iter().enumerate() {
+        assert_eq!(spans.measure_of_index::<Len>(idx), Len(offset));
+
+        for measure in offset..offset + span.len {
+            assert_eq!(spans.index_of_measure(Len(measure)), idx);
+        }
+
+        offset += span.len;
+    }
+
+    assert_eq!(spans.measure_of_index::<Len>(vec.len()), Len(total));
+    assert_eq!(spans.index_of_measure(Len(total)), vec.len());
+}
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn sum_rope_slices() {
+    let mut rng = thread_rng();
+
+    let vec = (0..5000).map(|_| random_span(&mut rng)).collect::<Vec<_>>();
+
+    let spans = vec.iter().cloned().collect::<SumRope<_>>();
+
+    for _ in 0..100 {
+        let start = rng.gen_range(0..=vec.len());
+        let end = rng.gen_range(start..=vec.len());
+
+        let slice = spans.slice(start..end);
+
+        assert_eq!(slice.len(), end - start);
+        assert_eq!(
+            *slice.summary(),
+            vec[start..end].iter().map(|s| s.len).sum()
+        );
+        assert!(slice.iter().eq(vec[start..end].iter()));
+
+        if start < end {
+            let idx = rng.gen_range(0..end - start);
+            assert_eq!(slice.get(idx), Some(&vec[start + idx]));
+
+            let sub = slice.slice(idx..);
+            assert!(sub.iter().eq(vec[start + idx..end].iter()));
+        }
+
+        let owned = SumRope::from(slice);
+        owned.assert_invariants();
+        assert!(owned.iter().eq(vec[start..end].iter()));
+    }
+}
+
+#[test]
+#[should_panic]
+fn sum_rope_insert_out_of_bounds() {
+    let mut spans = SumRope::new();
+    spans.push(Span { len: 1, bold: false });
+    spans.insert(2, Span { len: 1, bold: false });
+}
+
+#[test]
+#[should_panic]
+fn sum_rope_remove_out_of_bounds() {
+    let mut spans = SumRope::new();
+    spans.push(Span { len: 1, bold: false });
+    spans.remove(1);
+}
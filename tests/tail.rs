@@ -0,0 +1,162 @@
+use std::io::{Seek, SeekFrom, Write};
+
+mod common;
+
+use common::{CURSED_LIPSUM, LARGE, SMALL, TINY};
+use crop::Rope;
+use rand::Rng;
+
+#[cfg_attr(miri, ignore)]
+#[test]
+fn tail_random_appends() {
+    let mut rng = rand::thread_rng();
+
+    for s in ["", TINY, LARGE] {
+        let mut r = Rope::from(s);
+
+        let tail = r.follow_tail();
+
+        let mut appended = String::new();
+
+        for _ in 0..100 {
+            let text =
+                [TINY, SMALL, CURSED_LIPSUM, "", "\n"][rng.gen_range(0..5)];
+
+            r.push_str(text);
+            appended.push_str(text);
+
+            // Edits before the tail don't affect the text after it.
+            if rng.gen_bool(0.3) && r.marks().get(tail.mark()) > Some(0) {
+                r.insert(0, "foo");
+            }
+
+            if rng.gen_bool(0.5) {
+                assert_eq!(r.poll_tail(&tail), appended);
+                appended.clear();
+            }
+        }
+
+        assert_eq!(r.poll_tail(&tail), appended);
+        assert_eq!(r.poll_tail(&tail), "");
+        assert_eq!(r.marks().get(tail.mark()), Some(r.byte_len()));
+    }
+}
+
+// WASI doesn't necessarily give access to a temporary directory.
+#[cfg_attr(any(miri, target_family = "wasm"), ignore)]
+#[test]
+fn tail_follow_file() {
+    let (mut file, mut reader) = log_file();
+
+    let mut r = Rope::new();
+
+    let mut tail = r.follow_tail();
+
+    assert_eq!(r.extend_from_reader(&mut tail, &mut reader).unwrap(), 0);
+    assert_eq!(r.poll_tail(&tail), "");
+
+    let revision = r.revision();
+
+    assert_eq!(r.extend_from_reader(&mut tail, &mut reader).unwrap(), 0);
+    assert_eq!(r.revision(), revision);
+
+    for line in SMALL.lines() {
+        writeln!(file, "{line}").unwrap();
+        assert_eq!(
+            r.extend_from_reader(&mut tail, &mut reader).unwrap(),
+            line.len() + 1
+        );
+        assert_eq!(r.poll_tail(&tail), format!("{line}\n"));
+    }
+
+    // Truncating the file and the `Rope` moves the tail back to the start.
+    file.set_len(0).unwrap();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    reader.seek(SeekFrom::Start(0)).unwrap();
+    r.clear();
+
+    write!(file, "{TINY}").unwrap();
+    r.extend_from_reader(&mut tail, &mut reader).unwrap();
+    assert_eq!(r.poll_tail(&tail), TINY);
+}
+
+// WASI doesn't necessarily give access to a temporary directory.
+#[cfg_attr(any(miri, target_family = "wasm"), ignore)]
+#[test]
+fn tail_follow_file_split_chars() {
+    let (mut file, mut reader) = log_file();
+
+    let mut r = Rope::new();
+
+    let mut tail = r.follow_tail();
+
+    let text = "b\u{e4}r \u{1f980}\n";
+
+    // Write the text one byte at a time, so that every read but the last
+    // one of each character ends in the middle of it.
+    for (idx, byte) in text.bytes().enumerate() {
+        file.write_all(&[byte]).unwrap();
+
+        let revision = r.revision();
+
+        let appended = r.extend_from_reader(&mut tail, &mut reader).unwrap();
+
+        if text.is_char_boundary(idx + 1) {
+            let start =
+                (0..=idx).rev().find(|&i| text.is_char_boundary(i)).unwrap();
+            assert_eq!(r.poll_tail(&tail), &text[start..=idx]);
+            assert_eq!(appended, idx + 1 - start);
+        } else {
+            assert_eq!(appended, 0);
+            assert_eq!(r.revision(), revision);
+        }
+    }
+
+    assert_eq!(r, text);
+}
+
+#[test]
+fn extend_from_reader_invalid_utf8() {
+    let mut r = Rope::from("foo");
+
+    let mut tail = r.follow_tail();
+
+    let err =
+        r.extend_from_reader(&mut tail, &[b'b', b'a', 0xff][..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(r, "foo");
+
+    // A truncated character followed by an invalid byte is also invalid.
+    r.extend_from_reader(&mut tail, &[b'b', 0xc3][..]).unwrap();
+    let err = r.extend_from_reader(&mut tail, &[b'a'][..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(r, "foob");
+}
+
+#[test]
+#[should_panic]
+fn tail_removed_mark() {
+    let mut r = Rope::from("foo");
+    let tail = r.follow_tail();
+    r.remove_mark(tail.mark());
+    let _ = r.poll_tail(&tail);
+}
+
+/// Returns a writer and a reader of the same new, empty file.
+fn log_file() -> (std::fs::File, std::fs::File) {
+    let path = std::env::temp_dir()
+        .join(format!("crop-tail-{}.log", std::process::id()));
+
+    let writer = std::fs::File::options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+
+    let reader = std::fs::File::open(&path).unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+
+    (writer, reader)
+}
@@ -0,0 +1,118 @@
+use crop::{Rope, RopeBuilder};
+
+mod common;
+
+use common::{LARGE, TINY};
+
+#[test]
+fn try_edits_within_max_len() {
+    let mut r = Rope::from(TINY);
+
+    assert!(r.try_insert(0, LARGE).is_ok());
+    assert!(r.try_replace(..LARGE.len(), "foo").is_ok());
+    assert!(r.try_append(Rope::from(LARGE)).is_ok());
+
+    let mut builder = RopeBuilder::new();
+    builder
+        .try_append("foo")
+        .unwrap()
+        .try_append_slice(r.byte_slice(..))
+        .unwrap();
+
+    let s = format!("foo{TINY}{LARGE}");
+
+    assert_eq!(r, s);
+    assert_eq!(builder.build(), format!("foo{s}"));
+}
+
+#[cfg(feature = "compact-summaries")]
+mod compact_summaries {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use crop::{Rope, RopeBuilder, TooLongError};
+
+    const MIB: usize = 1 << 20;
+
+    /// Returns a `Rope` which is less than 1 MiB away from the maximum
+    /// length, whose chunks are all shared.
+    fn almost_full() -> Rope {
+        let r = Rope::from("a".repeat(MIB)).repeat(Rope::MAX_BYTE_LEN / MIB);
+        assert!(Rope::MAX_BYTE_LEN - r.byte_len() < MIB);
+        r
+    }
+
+    #[test]
+    fn try_insert_too_long() {
+        let mut r = almost_full();
+
+        let byte_len = r.byte_len();
+        let revision = r.revision();
+
+        let long = "b".repeat(MIB);
+
+        assert_eq!(r.try_insert(0, &long), Err(TooLongError));
+        assert_eq!(r.try_replace(1..2, format!("b{long}")), Err(TooLongError));
+        assert_eq!(r.try_append(Rope::from(&*long)), Err(TooLongError));
+
+        assert_eq!(r.byte_len(), byte_len);
+        assert_eq!(r.revision(), revision);
+
+        // Replacing text can still make the `Rope` longer as long as it fits.
+        assert!(r.try_replace(..MIB, "b".repeat(2 * MIB - 1)).is_ok());
+        assert_eq!(r.byte_len(), byte_len + MIB - 1);
+        assert_eq!(r.byte_slice(..3), "bbb");
+    }
+
+    #[test]
+    fn caught_panic_leaves_rope_untouched() {
+        let mut r = almost_full();
+
+        let byte_len = r.byte_len();
+
+        let other = Rope::from("b".repeat(MIB));
+
+        let res = catch_unwind(AssertUnwindSafe(|| r.insert_rope(1, &other)));
+        assert!(res.is_err());
+
+        let res = catch_unwind(AssertUnwindSafe(|| r.append(other.clone())));
+        assert!(res.is_err());
+
+        assert_eq!(r.byte_len(), byte_len);
+        assert_eq!(r.byte_slice(..3), "aaa");
+
+        r.insert(1, "b");
+        assert_eq!(r.byte_slice(..3), "aba");
+    }
+
+    #[test]
+    fn builder_try_append_too_long() {
+        let r = almost_full();
+
+        let mut builder = RopeBuilder::new();
+
+        builder.append_rope(&r);
+
+        assert_eq!(
+            builder.try_append("b".repeat(MIB)).err(),
+            Some(TooLongError)
+        );
+
+        assert_eq!(
+            builder.try_append_slice(r.byte_slice(..MIB)).err(),
+            Some(TooLongError)
+        );
+
+        builder.append("b");
+
+        let built = builder.build();
+        assert_eq!(built.byte_len(), r.byte_len() + 1);
+        assert_eq!(built.byte_slice(built.byte_len() - 2..), "ab");
+    }
+
+    #[test]
+    #[should_panic]
+    fn repeat_too_long() {
+        let r = Rope::from("a".repeat(MIB));
+        let _ = r.repeat(Rope::MAX_BYTE_LEN / MIB + 1);
+    }
+}
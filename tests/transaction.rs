@@ -0,0 +1,147 @@
+use std::sync::{Arc, Mutex};
+
+use crop::transaction::{Operation, Transaction, TransactionError};
+use crop::{EditInfo, Rope};
+use rand::{thread_rng, Rng};
+
+mod common;
+
+use common::SMALL;
+
+#[test]
+fn transaction_merges_operations() {
+    let r = Rope::from("Hello World");
+
+    let mut t = Transaction::new(&r);
+    t.retain(2).retain(0).retain(3).delete(1).delete(2).insert("").insert("a");
+    t.insert("b");
+
+    assert_eq!(
+        t.operations(),
+        [
+            Operation::Retain(5),
+            Operation::Delete(3),
+            Operation::Insert("ab".to_owned()),
+        ]
+    );
+
+    assert_eq!(t.len_before(), 11);
+    assert_eq!(t.len_after(), 10);
+}
+
+#[test]
+fn transaction_delta() {
+    let r = Rope::from("Hello World");
+
+    let mut t = Transaction::new(&r);
+    t.insert(">").retain(5).delete(1).insert("_").retain(2).delete(3);
+
+    assert_eq!(
+        t.delta().iter().cloned().collect::<Vec<_>>(),
+        [
+            EditInfo { byte_range: 0..0, inserted_len: 1 },
+            EditInfo { byte_range: 6..7, inserted_len: 1 },
+            EditInfo { byte_range: 9..12, inserted_len: 0 },
+        ]
+    );
+}
+
+#[test]
+fn transaction_is_a_single_edit() {
+    let mut r = Rope::from("Hello World");
+
+    let edits = Arc::new(Mutex::new(Vec::new()));
+
+    r.subscribe({
+        let edits = Arc::clone(&edits);
+        move |edit| edits.lock().unwrap().push(edit.clone())
+    });
+
+    let snapshot = r.clone();
+
+    let mut t = Transaction::new(&r);
+    t.insert(">").retain(5).delete(1).insert("_").retain(2).delete(3);
+    t.apply(&mut r).unwrap();
+
+    assert_eq!(r, ">Hello_Wo");
+    assert_ne!(r.revision(), snapshot.revision());
+
+    // The edits are applied as a batch, from last to first.
+    assert_eq!(
+        *edits.lock().unwrap(),
+        [
+            EditInfo { byte_range: 8..11, inserted_len: 0 },
+            EditInfo { byte_range: 5..6, inserted_len: 1 },
+            EditInfo { byte_range: 0..0, inserted_len: 1 },
+        ]
+    );
+}
+
+#[test]
+fn transaction_errors_leave_rope_untouched() {
+    let mut r = Rope::from("ƒoo");
+
+    let mut t = Transaction::new(&r);
+    t.retain(1).insert("x");
+    assert_eq!(t.apply(&mut r), Err(TransactionError::NotCharBoundary(1)));
+
+    let mut t = Transaction::new(&r);
+    t.delete(3);
+    r.insert(0, "a");
+    assert_eq!(
+        t.apply(&mut r),
+        Err(TransactionError::LengthMismatch { expected: 4, actual: 5 })
+    );
+
+    assert_eq!(r, "aƒoo");
+}
+
+#[test]
+#[should_panic]
+fn transaction_out_of_bounds() {
+    let r = Rope::from("foo");
+    Transaction::new(&r).retain(2).delete(2);
+}
+
+#[test]
+fn transaction_random_inverse() {
+    let mut rng = thread_rng();
+
+    let mut r = Rope::from(SMALL);
+
+    for _ in 0..50 {
+        let original = r.clone();
+
+        let mut t = Transaction::new(&r);
+
+        let mut offset = 0;
+
+        while offset < r.byte_len() {
+            let len = rng.gen_range(0..50).min(r.byte_len() - offset);
+            let end = (offset + len..=r.byte_len())
+                .find(|&end| r.is_char_boundary(end))
+                .unwrap();
+
+            match rng.gen_range(0..3) {
+                0 => t.retain(end - offset),
+                1 => t.delete(end - offset),
+                _ => t.insert("ab\ncd").retain(end - offset),
+            };
+
+            if rng.gen_bool(0.5) {
+                t.insert("ƒ");
+            }
+
+            offset = end;
+        }
+
+        let inverse = t.apply(&mut r).unwrap();
+        assert_eq!(r.byte_len(), t.len_after());
+
+        let mut undone = r.clone();
+        inverse.apply(&mut undone).unwrap();
+        assert_eq!(undone, original);
+
+        r.assert_invariants();
+    }
+}
@@ -0,0 +1,217 @@
+mod common;
+
+#[cfg(feature = "unicode-lines")]
+mod tests {
+    use crop::{Rope, RopeBuilder, RopeRef};
+    use rand::Rng;
+
+    use crate::common::{random_char_boundary, CURSED_LIPSUM};
+
+    const LINE_BREAKS: [&str; 7] =
+        ["\n", "\r\n", "\u{0B}", "\u{0C}", "\u{85}", "\u{2028}", "\u{2029}"];
+
+    /// Returns the lines of `s` including their line breaks, where a line
+    /// break is a CRLF, a line feed, a vertical tab, a form feed, a next line,
+    /// a line separator or a paragraph separator.
+    fn raw_lines(s: &str) -> Vec<&str> {
+        s.split_inclusive(|ch| {
+            matches!(
+                ch,
+                '\n' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+            )
+        })
+        .collect()
+    }
+
+    /// Returns the lines of `s` without their line breaks.
+    fn lines(s: &str) -> Vec<&str> {
+        raw_lines(s)
+            .into_iter()
+            .map(|line| match line.chars().next_back() {
+                Some('\n') => {
+                    let line = &line[..line.len() - 1];
+                    line.strip_suffix('\r').unwrap_or(line)
+                },
+                Some(ch @ ('\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}'
+                | '\u{2029}')) => &line[..line.len() - ch.len_utf8()],
+                _ => line,
+            })
+            .collect()
+    }
+
+    /// Returns a random text of about `len` bytes with every kind of line
+    /// break, and carriage returns not followed by a line feed.
+    fn random_text(len: usize) -> String {
+        let mut rng = rand::thread_rng();
+
+        let mut text = String::with_capacity(len);
+
+        while text.len() < len {
+            match rng.gen_range(0..4) {
+                0 => text.push_str(
+                    LINE_BREAKS[rng.gen_range(0..LINE_BREAKS.len())],
+                ),
+                1 => text.push('\r'),
+                _ => {
+                    let start = random_char_boundary(&mut rng, CURSED_LIPSUM);
+                    let end = start
+                        + CURSED_LIPSUM[start..]
+                            .char_indices()
+                            .nth(rng.gen_range(0..8))
+                            .map_or(CURSED_LIPSUM.len() - start, |(i, _)| i);
+                    text.push_str(&CURSED_LIPSUM[start..end]);
+                },
+            }
+        }
+
+        text
+    }
+
+    fn assert_lines(r: &Rope, s: &str) {
+        let raw_lines = raw_lines(s);
+        let lines = lines(s);
+
+        assert_eq!(r.line_len(), lines.len());
+        assert_eq!(r.raw_lines().count(), raw_lines.len());
+        assert_eq!(r.lines().count(), lines.len());
+
+        for (line, expected) in r.raw_lines().zip(&raw_lines) {
+            assert_eq!(line, *expected);
+        }
+
+        for (line, expected) in r.lines().zip(&lines) {
+            assert_eq!(line, *expected);
+        }
+
+        for (line, expected) in r.lines().rev().zip(lines.iter().rev()) {
+            assert_eq!(line, *expected);
+        }
+
+        let mut byte_offset = 0;
+
+        for (line_index, raw_line) in raw_lines.iter().enumerate() {
+            assert_eq!(r.byte_of_line(line_index), byte_offset);
+            assert_eq!(r.line_of_byte(byte_offset), line_index);
+            assert_eq!(r.line(line_index), lines[line_index]);
+            assert_eq!(r.line_slice(line_index..line_index + 1), *raw_line);
+            byte_offset += raw_line.len();
+        }
+    }
+
+    #[test]
+    fn unicode_lines_0() {
+        let s = "a\u{0B}b\u{0C}c\u{85}d\u{2028}e\u{2029}f\r\ng\rh\n";
+        let r = Rope::from(s);
+
+        assert_eq!(r.line_len(), 7);
+        assert_eq!(r.line(2), "c");
+        assert_eq!(r.line(5), "f");
+        assert_eq!(r.line(6), "g\rh");
+        assert_eq!(r.byte_of_line(4), "a\u{0B}b\u{0C}c\u{85}d\u{2028}".len());
+
+        assert_lines(&r, s);
+    }
+
+    #[test]
+    fn unicode_lines_trailing_line_break() {
+        for line_break in LINE_BREAKS {
+            let s = format!("foo{line_break}");
+
+            let r = Rope::from(s.as_str());
+            assert_eq!(r.line_len(), 1);
+            assert_eq!(r.line(0), "foo");
+
+            let mut r = Rope::from("foo");
+            r.insert(3, line_break);
+            assert_eq!(r.line_len(), 1);
+            assert_eq!(r.lines().collect::<Vec<_>>(), ["foo"]);
+
+            r.insert(r.byte_len(), "bar");
+            assert_eq!(r.line_len(), 2);
+        }
+    }
+
+    #[test]
+    fn unicode_lines_carriage_return_before_line_break() {
+        for line_break in &LINE_BREAKS[2..] {
+            let s = format!("foo\r{line_break}bar");
+            let r = Rope::from(s.as_str());
+            assert_eq!(r.line(0), "foo\r");
+            assert_eq!(r.lines().next().unwrap(), "foo\r");
+            assert_eq!(r.lines().rev().nth(1).unwrap(), "foo\r");
+        }
+    }
+
+    #[test]
+    fn unicode_lines_random() {
+        for _ in 0..10 {
+            let s = random_text(5_000);
+            let r = Rope::from(s.as_str());
+            assert_lines(&r, &s);
+        }
+    }
+
+    #[test]
+    fn unicode_lines_rope_ref() {
+        let s = random_text(5_000);
+        let r = RopeRef::new(&s);
+
+        let lines = lines(&s);
+
+        assert_eq!(r.line_len(), lines.len());
+
+        let mut byte_offset = 0;
+
+        for (line_index, raw_line) in raw_lines(&s).into_iter().enumerate() {
+            assert_eq!(r.byte_of_line(line_index), byte_offset);
+            assert_eq!(r.line_of_byte(byte_offset), line_index);
+            assert_eq!(r.line(line_index), lines[line_index]);
+            byte_offset += raw_line.len();
+        }
+    }
+
+    #[test]
+    fn unicode_lines_split_across_chunks() {
+        let s = random_text(5_000);
+
+        let mut rng = rand::thread_rng();
+
+        let mut builder = RopeBuilder::new();
+
+        let mut rest = s.as_str();
+
+        while !rest.is_empty() {
+            let mut len = rng.gen_range(1..=rest.len().min(8));
+            while !rest.is_char_boundary(len) {
+                len += 1;
+            }
+            builder.append(&rest[..len]);
+            rest = &rest[len..];
+        }
+
+        let r = builder.build();
+        r.assert_invariants();
+        assert_lines(&r, &s);
+    }
+
+    #[test]
+    fn unicode_lines_random_edits() {
+        let mut rng = rand::thread_rng();
+
+        let mut s = random_text(5_000);
+        let mut r = Rope::from(s.as_str());
+
+        for _ in 0..50 {
+            let start = random_char_boundary(&mut rng, &s);
+            let end = (start + rng.gen_range(0..100)).min(s.len());
+            let end = (end..=s.len()).find(|&i| s.is_char_boundary(i)).unwrap();
+            let text = random_text(rng.gen_range(0..20));
+
+            r.replace(start..end, &text);
+            s.replace_range(start..end, &text);
+
+            r.assert_invariants();
+            assert_lines(&r, &s);
+        }
+    }
+}
@@ -202,6 +202,29 @@ mod tests {
         let _ = r.byte_of_utf16_code_unit(13);
     }
 
+    #[test]
+    fn utf16_mostly_ascii() {
+        let text = format!("{}🦀\n{}ß\n", "a".repeat(2000), "b".repeat(2000));
+        let r = Rope::from(text.as_str());
+        assert!(!r.is_ascii());
+
+        let mut utf16_offset = 0;
+
+        for (byte_offset, ch) in text.char_indices() {
+            assert_eq!(r.utf16_code_unit_of_byte(byte_offset), utf16_offset);
+            assert_eq!(r.byte_of_utf16_code_unit(utf16_offset), byte_offset);
+            utf16_offset += ch.len_utf16();
+        }
+
+        assert_eq!(r.utf16_len(), utf16_offset);
+
+        let s = r.byte_slice(1000..);
+        assert_eq!(s.utf16_code_unit_of_byte(1005), 1003);
+        assert_eq!(s.byte_of_utf16_code_unit(1003), 1005);
+        assert_eq!(s.utf16_code_unit_of_byte(3005), 3003);
+        assert_eq!(s.byte_of_utf16_code_unit(3003), 3005);
+    }
+
     // TODO: see above.
     #[ignore]
     #[should_panic]
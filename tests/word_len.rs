@@ -0,0 +1,144 @@
+mod common;
+
+#[cfg(feature = "word-metric")]
+mod tests {
+    use crop::{Rope, RopeBuilder};
+    use rand::Rng;
+
+    use crate::common::{
+        random_char_boundary,
+        CURSED_LIPSUM,
+        LARGE,
+        MEDIUM,
+        SMALL,
+        TEXT,
+    };
+
+    fn word_len(s: &str) -> usize {
+        s.split_whitespace().count()
+    }
+
+    #[test]
+    fn word_len_0() {
+        for s in ["", " \n\t ", "foo", "  foo  bar\r\nbaz ", TEXT, LARGE] {
+            let r = Rope::from(s);
+            assert_eq!(r.word_len(), word_len(s));
+
+            let mut builder = RopeBuilder::new();
+            for chunk in s.split_inclusive(' ') {
+                builder.append(chunk);
+            }
+            assert_eq!(builder.build().word_len(), word_len(s));
+        }
+    }
+
+    #[test]
+    fn word_len_random_edits() {
+        let mut rng = rand::thread_rng();
+
+        let insertions =
+            ["", " ", "a", "foo bar", "\n", "\u{2003}", "🦀 🦀", "x\r\ny"];
+
+        for s in [SMALL, MEDIUM, CURSED_LIPSUM] {
+            let mut r = Rope::from(s);
+            let mut string = s.to_owned();
+
+            for _ in 0..100 {
+                let start = random_char_boundary(&mut rng, &string);
+                let end = random_char_boundary(&mut rng, &string);
+                let (start, end) = (start.min(end), start.max(end));
+                let text = insertions[rng.gen_range(0..insertions.len())];
+
+                r.replace(start..end, text);
+                string.replace_range(start..end, text);
+
+                assert_eq!(r.word_len(), word_len(&string));
+            }
+
+            let other = Rope::from(" foo bar");
+            r.append(other.clone());
+            string.push_str(" foo bar");
+            assert_eq!(r.word_len(), word_len(&string));
+
+            assert_eq!(r.clone().word_len(), word_len(&string));
+            assert_eq!(Rope::from(r.byte_slice(..)).word_len(), r.word_len());
+        }
+    }
+
+    #[test]
+    fn word_len_slices() {
+        let mut rng = rand::thread_rng();
+
+        for s in [SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+            let mut r = Rope::from(s);
+            let mut string = s.to_owned();
+
+            for _ in 0..50 {
+                let start = random_char_boundary(&mut rng, &string);
+                let end = random_char_boundary(&mut rng, &string);
+                let (start, end) = (start.min(end), start.max(end));
+
+                let slice = r.byte_slice(start..end);
+                assert_eq!(slice.word_len(), word_len(&string[start..end]));
+
+                let mid = random_char_boundary(&mut rng, &string[start..end]);
+                let slice = slice.byte_slice(mid..);
+                assert_eq!(
+                    slice.word_len(),
+                    word_len(&string[start + mid..end])
+                );
+
+                r.replace(start..end, "foo  bar");
+                string.replace_range(start..end, "foo  bar");
+                r.assert_invariants();
+            }
+        }
+    }
+
+    #[test]
+    fn word_len_lines() {
+        let mut rng = rand::thread_rng();
+
+        for s in [SMALL, MEDIUM, LARGE, CURSED_LIPSUM] {
+            let r = Rope::from(s);
+
+            for _ in 0..10 {
+                let start = random_char_boundary(&mut rng, s);
+                let end = random_char_boundary(&mut rng, s);
+                let (start, end) = (start.min(end), start.max(end));
+
+                let slice = r.byte_slice(start..end);
+                let string = &s[start..end];
+
+                for (line, str_line) in slice.raw_lines().zip(string.lines()) {
+                    assert_eq!(line.word_len(), word_len(str_line));
+                }
+
+                for (line, str_line) in
+                    slice.raw_lines().rev().zip(string.lines().rev())
+                {
+                    assert_eq!(line.word_len(), word_len(str_line));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn word_len_joining_words() {
+        let mut r = Rope::from("foo bar baz");
+        assert_eq!(r.word_len(), 3);
+
+        r.delete(3..4);
+        assert_eq!(r.word_len(), 2);
+
+        r.delete(..);
+        assert_eq!(r.word_len(), 0);
+
+        r.insert(0, "foo");
+        r.insert(3, "bar");
+        assert_eq!(r.word_len(), 1);
+
+        r.insert(3, "\n");
+        assert_eq!(r.word_len(), 2);
+    }
+}